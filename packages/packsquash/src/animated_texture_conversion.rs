@@ -0,0 +1,476 @@
+//! Contains a virtual file system adapter that converts animated textures stored in the GIF
+//! and APNG formats to the vertical frame strip PNG textures and `.mcmeta` animation metadata
+//! files Minecraft expects.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	num::NonZeroU16,
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::{Duration, SystemTime}
+};
+
+use ahash::{AHashMap, AHashSet};
+use bytes::Bytes;
+use itertools::Either as IterEither;
+use oxipng::{BitDepth, ColorType, Options, RawImage};
+use serde_json::{Map, Value};
+use tokio_util::either::Either;
+
+use crate::RelativePath;
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file
+};
+
+mod apng;
+mod gif;
+
+#[cfg(test)]
+mod tests;
+
+/// The duration of a Minecraft game tick, which is the time unit of animation frame times.
+const TICK_DURATION: Duration = Duration::from_millis(50);
+
+/// A file generated by [`AnimatedTextureConvertingVfs`] from an animated texture.
+struct ConvertedFile {
+	data: Bytes,
+	modification_time: Option<SystemTime>
+}
+
+/// The result of converting an animated texture: a vertical frame strip PNG and, if the
+/// texture has more than one distinct frame, its animation metadata file.
+struct ConvertedTexture {
+	texture: Vec<u8>,
+	animation_metadata: Option<Vec<u8>>
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently replacing animated textures
+/// in the GIF (`.gif`) and APNG (`.apng`) formats with a vertical frame strip PNG texture and
+/// the `.mcmeta` animation metadata file that describes how to play it, as if they were
+/// exported by hand.
+///
+/// The converted files are computed eagerly by [`Self::convert_animated_textures`] and kept in
+/// memory, because the [`VirtualFileSystem::open`] method is synchronous. Other files are read
+/// from the wrapped file system as-is.
+pub struct AnimatedTextureConvertingVfs<V: VirtualFileSystem> {
+	inner: V,
+	converted_files: AHashMap<PathBuf, ConvertedFile>,
+	/// The paths of the animated textures that were converted, which are replaced by the
+	/// converted files in file iterators.
+	converted_texture_paths: Arc<AHashSet<PathBuf>>
+}
+
+impl<V: VirtualFileSystem> AnimatedTextureConvertingVfs<V> {
+	/// Wraps the specified virtual file system. No animated textures will be converted until
+	/// [`Self::convert_animated_textures`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			converted_files: AHashMap::new(),
+			converted_texture_paths: Arc::new(AHashSet::new())
+		}
+	}
+
+	/// Scans the pack at the specified root path for GIF and APNG textures, converting them.
+	/// Animated textures that can't be read or decoded, that would be converted to files that
+	/// already exist in the pack, or whose frames or frame strip would be wider or taller than
+	/// the maximum texture dimension returned for the relative path of the converted texture,
+	/// are left as-is, so that they are skipped as usual.
+	pub async fn convert_animated_textures(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions,
+		maximum_width_and_height: impl Fn(&str) -> NonZeroU16
+	) {
+		let mut converted_texture_paths = AHashSet::new();
+
+		for VfsPackFileIterEntry {
+			relative_path,
+			file_path: animated_texture_path
+		} in self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+		{
+			let relative_path = relative_path.as_str();
+			let decode_animation = if relative_path.ends_with(".gif") {
+				gif::decode_animation
+			} else if relative_path.ends_with(".apng") {
+				apng::decode_animation
+			} else {
+				continue;
+			};
+
+			if !relative_path.starts_with("assets/") || !relative_path.contains("/textures/") {
+				continue;
+			}
+
+			let texture_path = animated_texture_path.with_extension("png");
+			let animation_metadata_path = animated_texture_path.with_extension("png.mcmeta");
+
+			// Never overwrite files that are in the pack or generated from another texture
+			let is_taken = |path: &Path| {
+				self.converted_files.contains_key(path) || self.inner.file_type(path).is_ok()
+			};
+			if is_taken(&texture_path) || is_taken(&animation_metadata_path) {
+				continue;
+			}
+
+			let Ok((animated_texture, modification_time)) =
+				read_file(&self.inner, &animated_texture_path).await
+			else {
+				continue;
+			};
+
+			let maximum_width_and_height = maximum_width_and_height(&format!(
+				"{}.png",
+				relative_path
+					.rsplit_once('.')
+					.map_or(relative_path, |(stem, _)| stem)
+			))
+			.get()
+			.into();
+			let Some(ConvertedTexture {
+				texture,
+				animation_metadata
+			}) = decode_animation(&animated_texture, maximum_width_and_height)
+				.and_then(|animation| convert_animation(animation, maximum_width_and_height))
+			else {
+				continue;
+			};
+
+			self.converted_files.insert(
+				texture_path,
+				ConvertedFile {
+					data: texture.into(),
+					modification_time
+				}
+			);
+			if let Some(animation_metadata) = animation_metadata {
+				self.converted_files.insert(
+					animation_metadata_path,
+					ConvertedFile {
+						data: animation_metadata.into(),
+						modification_time
+					}
+				);
+			}
+
+			converted_texture_paths.insert(animated_texture_path);
+		}
+
+		self.converted_texture_paths = Arc::new(converted_texture_paths);
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for AnimatedTextureConvertingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = impl Iterator<Item = Result<VfsPackFileIterEntry, io::Error>>;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		let converted_texture_paths = Arc::clone(&self.converted_texture_paths);
+		let converted_file_paths = self
+			.converted_files
+			.keys()
+			.cloned()
+			.collect::<AHashSet<_>>();
+
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flat_map(move |entry| {
+				let entry = match entry {
+					Ok(entry) if converted_texture_paths.contains(&entry.file_path) => entry,
+					entry => return IterEither::Left(Some(entry).into_iter())
+				};
+
+				// Replace the animated texture with the files it was converted to
+				let relative_path_stem = entry
+					.relative_path
+					.as_str()
+					.rsplit_once('.')
+					.map_or(entry.relative_path.as_str(), |(stem, _)| stem);
+				let texture_entry = VfsPackFileIterEntry {
+					relative_path: RelativePath::from_inner(format!("{relative_path_stem}.png")),
+					file_path: entry.file_path.with_extension("png")
+				};
+				let animation_metadata_entry = VfsPackFileIterEntry {
+					relative_path: RelativePath::from_inner(format!(
+						"{relative_path_stem}.png.mcmeta"
+					)),
+					file_path: entry.file_path.with_extension("png.mcmeta")
+				};
+
+				IterEither::Right(
+					[texture_entry]
+						.into_iter()
+						.chain(
+							converted_file_paths
+								.contains(&animation_metadata_entry.file_path)
+								.then_some(animation_metadata_entry)
+						)
+						.map(Ok)
+				)
+			})
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.converted_files
+				.get(path.as_ref())
+				.map(|converted_file| (&converted_file.data, converted_file.modification_time))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
+
+/// A rectangular region of an animation canvas, in pixels.
+#[derive(Clone, Copy)]
+struct FrameRegion {
+	x: usize,
+	y: usize,
+	width: usize,
+	height: usize
+}
+
+/// How the pixels of a frame are combined with the pixels already on the canvas.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FrameBlendOperation {
+	/// The frame pixels replace the canvas pixels.
+	Source,
+	/// The frame pixels are alpha composited over the canvas pixels.
+	Over
+}
+
+/// What happens to the frame region of the canvas after a frame is shown.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FrameDisposeOperation {
+	/// The canvas is left as-is.
+	None,
+	/// The frame region is cleared to fully transparent black.
+	Background,
+	/// The canvas is reverted to how it was before the frame was drawn.
+	Previous
+}
+
+/// A frame of an animation, as stored in an animated image file: a possibly partial update of
+/// the animation canvas, in 8-bit RGBA format.
+struct AnimationFrameUpdate {
+	region: FrameRegion,
+	pixels: Vec<u8>,
+	blend_operation: FrameBlendOperation,
+	dispose_operation: FrameDisposeOperation,
+	delay: Duration
+}
+
+/// A decoded animation, made up of the updates that draw each frame on a canvas of the
+/// specified dimensions, which starts fully transparent.
+struct DecodedAnimation {
+	width: usize,
+	height: usize,
+	frames: Vec<AnimationFrameUpdate>
+}
+
+/// Composes the frames of the specified animation and converts them to a vertical frame strip
+/// PNG texture and its animation metadata, as Minecraft expects. Identical frames are stored
+/// only once, and consecutive identical frames are merged into a single, longer one. `None` is
+/// returned if the animation has no frames or the resulting frame strip would be taller than
+/// the specified maximum texture dimension.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Resource_pack#Animation>
+/// - Minecraft class `net.minecraft.client.resources.metadata.animation.AnimationMetadataSection`
+fn convert_animation(
+	animation: DecodedAnimation,
+	maximum_width_and_height: usize
+) -> Option<ConvertedTexture> {
+	let DecodedAnimation {
+		width,
+		height,
+		frames
+	} = animation;
+	let mut canvas = vec![0; width.checked_mul(height)?.checked_mul(4)?];
+
+	let mut distinct_frame_indices = AHashMap::<Vec<u8>, usize>::new();
+	let mut shown_frames = Vec::<(usize, u64)>::new();
+	for frame in frames {
+		let canvas_before_frame =
+			(frame.dispose_operation == FrameDisposeOperation::Previous).then(|| canvas.clone());
+
+		draw_frame(&mut canvas, width, height, &frame);
+
+		let index = match distinct_frame_indices.get(&canvas) {
+			Some(index) => *index,
+			None => {
+				// Give up as soon as the frame strip gets too tall, instead of holding every
+				// distinct frame of the animation in memory first
+				let index = distinct_frame_indices.len();
+				if height.checked_mul(index + 1)? > maximum_width_and_height {
+					return None;
+				}
+
+				distinct_frame_indices.insert(canvas.clone(), index);
+				index
+			}
+		};
+		// Minecraft frame times are measured in ticks, and must be positive
+		let time = (frame.delay.as_secs_f64() / TICK_DURATION.as_secs_f64())
+			.round()
+			.max(1.0) as u64;
+
+		match shown_frames.last_mut() {
+			Some((last_index, last_time)) if *last_index == index => *last_time += time,
+			_ => shown_frames.push((index, time))
+		}
+
+		match frame.dispose_operation {
+			FrameDisposeOperation::None => {}
+			FrameDisposeOperation::Background => {
+				let blank_frame = AnimationFrameUpdate {
+					pixels: vec![0; frame.region.width * frame.region.height * 4],
+					blend_operation: FrameBlendOperation::Source,
+					..frame
+				};
+				draw_frame(&mut canvas, width, height, &blank_frame);
+			}
+			FrameDisposeOperation::Previous => {
+				canvas = canvas_before_frame.unwrap();
+			}
+		}
+	}
+
+	let mut distinct_frames = distinct_frame_indices.into_iter().collect::<Vec<_>>();
+	distinct_frames.sort_unstable_by_key(|(_, index)| *index);
+	let distinct_frame_count = distinct_frames.len();
+
+	// Encode the texture quickly: it will be optimized later, like any other
+	let texture = RawImage::new(
+		width.try_into().ok()?,
+		(height * distinct_frame_count).try_into().ok()?,
+		ColorType::RGBA,
+		BitDepth::Eight,
+		distinct_frames
+			.into_iter()
+			.flat_map(|(frame, _)| frame)
+			.collect()
+	)
+	.ok()?
+	.create_optimized_png(&Options::from_preset(0))
+	.ok()?;
+
+	if distinct_frame_count < 2 {
+		// Still images don't need animation metadata
+		return Some(ConvertedTexture {
+			texture,
+			animation_metadata: None
+		});
+	}
+
+	let mut animation = Map::new();
+	if width != height {
+		// Otherwise, Minecraft assumes frames are squares as wide as the texture
+		animation.insert("width".into(), width.into());
+		animation.insert("height".into(), height.into());
+	}
+
+	let common_time = shown_frames
+		.iter()
+		.map(|(_, time)| *time)
+		.reduce(|common_time, time| if common_time == time { time } else { 0 })
+		.filter(|common_time| *common_time > 0);
+	let frames_are_in_order = shown_frames
+		.iter()
+		.enumerate()
+		.all(|(position, (index, _))| position == *index);
+
+	if let Some(common_time) = common_time {
+		if common_time != 1 {
+			animation.insert("frametime".into(), common_time.into());
+		}
+		if !frames_are_in_order {
+			animation.insert(
+				"frames".into(),
+				shown_frames.iter().map(|(index, _)| *index).collect()
+			);
+		}
+	} else {
+		animation.insert(
+			"frames".into(),
+			shown_frames
+				.iter()
+				.map(|(index, time)| {
+					Value::Object(Map::from_iter([
+						("index".into(), (*index).into()),
+						("time".into(), (*time).into())
+					]))
+				})
+				.collect()
+		);
+	}
+
+	Some(ConvertedTexture {
+		texture,
+		animation_metadata: Some(
+			serde_json::to_vec(&Value::Object(Map::from_iter([(
+				"animation".into(),
+				Value::Object(animation)
+			)])))
+			.ok()?
+		)
+	})
+}
+
+/// Draws the pixels of the specified frame on a canvas with the specified dimensions. The
+/// parts of the frame region that are outside the canvas are ignored.
+fn draw_frame(canvas: &mut [u8], width: usize, height: usize, frame: &AnimationFrameUpdate) {
+	let region = frame.region;
+	let visible_width = region.width.min(width.saturating_sub(region.x));
+	let visible_height = region.height.min(height.saturating_sub(region.y));
+
+	// Regions that are fully off-canvas would start past the end of the canvas buffer
+	if visible_width == 0 || visible_height == 0 {
+		return;
+	}
+
+	for y in 0..visible_height {
+		let frame_row = &frame.pixels[y * region.width * 4..][..visible_width * 4];
+		let canvas_row = &mut canvas[((region.y + y) * width + region.x) * 4..][..visible_width * 4];
+
+		for (canvas_pixel, frame_pixel) in canvas_row
+			.chunks_exact_mut(4)
+			.zip(frame_row.chunks_exact(4))
+		{
+			match (frame.blend_operation, frame_pixel[3]) {
+				(FrameBlendOperation::Source, _) | (FrameBlendOperation::Over, u8::MAX) => {
+					canvas_pixel.copy_from_slice(frame_pixel)
+				}
+				(FrameBlendOperation::Over, 0) => {}
+				(FrameBlendOperation::Over, frame_alpha) => {
+					// Straight alpha "over" compositing, scaled by 255 to use integer math
+					let frame_alpha = frame_alpha as u32;
+					let canvas_alpha = canvas_pixel[3] as u32 * (255 - frame_alpha);
+					let alpha = frame_alpha * 255 + canvas_alpha;
+
+					for (canvas_sample, frame_sample) in
+						canvas_pixel[..3].iter_mut().zip(&frame_pixel[..3])
+					{
+						*canvas_sample = ((*frame_sample as u32 * frame_alpha * 255
+							+ *canvas_sample as u32 * canvas_alpha
+							+ alpha / 2) / alpha) as u8;
+					}
+					canvas_pixel[3] = ((alpha + 127) / 255) as u8;
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,230 @@
+//! Decodes animations stored in the APNG format.
+//!
+//! APNG files are PNG files with additional chunks that describe the animation frames. Each
+//! frame is decoded by building a standalone PNG file with its image data, which keeps the
+//! actual decoding in the same PNG library used for other textures.
+//!
+//! References:
+//! - <https://www.w3.org/TR/png-3/#apng-frame-control-chunk>
+//! - <https://wiki.mozilla.org/APNG_Specification>
+
+use std::time::Duration;
+
+use spng::{ContextFlags, DecodeFlags, Format};
+
+use super::{
+	AnimationFrameUpdate, DecodedAnimation, FrameBlendOperation, FrameDisposeOperation, FrameRegion
+};
+
+/// The signature every PNG file starts with.
+pub(super) const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// The control data of an APNG frame, as stored in its `fcTL` chunk.
+struct FrameControl {
+	region: FrameRegion,
+	delay: Duration,
+	dispose_operation: FrameDisposeOperation,
+	blend_operation: FrameBlendOperation
+}
+
+impl FrameControl {
+	/// Parses the data of a `fcTL` chunk, returning `None` if it is not valid.
+	fn parse(data: &[u8]) -> Option<Self> {
+		let data: &[u8; 26] = data.try_into().ok()?;
+		let u32_at =
+			|offset: usize| u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+		let delay_numerator = u16::from_be_bytes([data[20], data[21]]);
+		// A zero denominator means hundredths of a second
+		let delay_denominator = match u16::from_be_bytes([data[22], data[23]]) {
+			0 => 100,
+			delay_denominator => delay_denominator
+		};
+
+		Some(Self {
+			region: FrameRegion {
+				width: u32_at(4),
+				height: u32_at(8),
+				x: u32_at(12),
+				y: u32_at(16)
+			},
+			delay: Duration::from_secs_f64(delay_numerator as f64 / delay_denominator as f64),
+			dispose_operation: match data[24] {
+				0 => FrameDisposeOperation::None,
+				1 => FrameDisposeOperation::Background,
+				2 => FrameDisposeOperation::Previous,
+				_ => return None
+			},
+			blend_operation: match data[25] {
+				0 => FrameBlendOperation::Source,
+				1 => FrameBlendOperation::Over,
+				_ => return None
+			}
+		})
+	}
+}
+
+/// Decodes the animation stored in the specified APNG file, returning `None` if it is not
+/// valid or its canvas or frames are wider or taller than the specified maximum dimension.
+/// PNG files without animation chunks are decoded as single frame animations.
+pub(super) fn decode_animation(
+	apng: &[u8],
+	maximum_width_and_height: usize
+) -> Option<DecodedAnimation> {
+	let mut remaining_data = apng.strip_prefix(&PNG_SIGNATURE)?;
+
+	let mut header = None;
+	let mut is_animated = false;
+	// Chunks other than the image data that appear before it, such as palettes, which are
+	// shared by every frame
+	let mut shared_chunks = Vec::new();
+	let mut frames = Vec::<(FrameControl, Vec<u8>)>::new();
+	let mut seen_image_data = false;
+	while let Some((chunk_type, chunk_data, next_chunk_data)) = next_chunk(remaining_data) {
+		remaining_data = next_chunk_data;
+
+		match &chunk_type {
+			b"IHDR" => header = Some(chunk_data),
+			b"acTL" => is_animated = true,
+			b"fcTL" => frames.push((FrameControl::parse(chunk_data)?, Vec::new())),
+			b"IDAT" => {
+				seen_image_data = true;
+
+				// The default image is the first frame only if a frame control chunk
+				// precedes it. Otherwise, it is not part of the animation
+				if let [(_, frame_data)] = frames.as_mut_slice() {
+					frame_data.extend_from_slice(chunk_data);
+				}
+			}
+			b"fdAT" => {
+				// Frame data chunks start with a sequence number
+				frames.last_mut()?.1.extend_from_slice(chunk_data.get(4..)?);
+			}
+			b"IEND" => break,
+			_ if !seen_image_data => shared_chunks.push((chunk_type, chunk_data)),
+			_ => {}
+		}
+	}
+
+	let header = header?;
+	let width = u32::from_be_bytes(header.get(0..4)?.try_into().unwrap()) as usize;
+	let height = u32::from_be_bytes(header.get(4..8)?.try_into().unwrap()) as usize;
+	if width > maximum_width_and_height || height > maximum_width_and_height {
+		return None;
+	}
+
+	if !is_animated {
+		return Some(DecodedAnimation {
+			width,
+			height,
+			frames: vec![AnimationFrameUpdate {
+				region: FrameRegion {
+					x: 0,
+					y: 0,
+					width,
+					height
+				},
+				pixels: decode_png(apng, width, height)?,
+				blend_operation: FrameBlendOperation::Source,
+				dispose_operation: FrameDisposeOperation::None,
+				delay: Duration::ZERO
+			}]
+		});
+	}
+
+	let frames = frames
+		.into_iter()
+		.map(|(control, data)| {
+			if control.region.width > maximum_width_and_height
+				|| control.region.height > maximum_width_and_height
+			{
+				return None;
+			}
+
+			let frame_png = frame_png(header, &shared_chunks, control.region, &data)?;
+
+			Some(AnimationFrameUpdate {
+				region: control.region,
+				pixels: decode_png(&frame_png, control.region.width, control.region.height)?,
+				blend_operation: control.blend_operation,
+				dispose_operation: control.dispose_operation,
+				delay: control.delay
+			})
+		})
+		.collect::<Option<Vec<_>>>()?;
+
+	Some(DecodedAnimation {
+		width,
+		height,
+		frames
+	})
+}
+
+/// Splits the next chunk from the specified PNG data, returning its type, its data and the
+/// data that follows it. `None` is returned if there are no more chunks or they are truncated.
+/// Chunk CRCs are not checked: if the chunk data is corrupt, decoding it will fail later.
+pub(super) fn next_chunk(png_data: &[u8]) -> Option<([u8; 4], &[u8], &[u8])> {
+	let (length, png_data) = png_data.split_first_chunk::<4>()?;
+	let (chunk_type, png_data) = png_data.split_first_chunk::<4>()?;
+	let (chunk_data, png_data) = png_data.split_at_checked(u32::from_be_bytes(*length) as usize)?;
+	let (_crc, png_data) = png_data.split_first_chunk::<4>()?;
+
+	Some((*chunk_type, chunk_data, png_data))
+}
+
+/// Builds a standalone PNG file with the specified image data of an APNG frame.
+fn frame_png(
+	header: &[u8],
+	shared_chunks: &[([u8; 4], &[u8])],
+	region: FrameRegion,
+	image_data: &[u8]
+) -> Option<Vec<u8>> {
+	let mut frame_header = header.to_vec();
+	frame_header
+		.get_mut(0..4)?
+		.copy_from_slice(&(region.width as u32).to_be_bytes());
+	frame_header
+		.get_mut(4..8)?
+		.copy_from_slice(&(region.height as u32).to_be_bytes());
+
+	let mut png = PNG_SIGNATURE.to_vec();
+	write_chunk(&mut png, b"IHDR", &frame_header);
+	for (chunk_type, chunk_data) in shared_chunks {
+		write_chunk(&mut png, chunk_type, chunk_data);
+	}
+	write_chunk(&mut png, b"IDAT", image_data);
+	write_chunk(&mut png, b"IEND", &[]);
+
+	Some(png)
+}
+
+/// Appends a PNG chunk with the specified type and data to a PNG file.
+pub(super) fn write_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], chunk_data: &[u8]) {
+	let mut crc_hasher = crc32fast::Hasher::new();
+	crc_hasher.update(chunk_type);
+	crc_hasher.update(chunk_data);
+
+	png.extend_from_slice(&(chunk_data.len() as u32).to_be_bytes());
+	png.extend_from_slice(chunk_type);
+	png.extend_from_slice(chunk_data);
+	png.extend_from_slice(&crc_hasher.finalize().to_be_bytes());
+}
+
+/// Decodes the specified PNG file to 8-bit RGBA pixels, returning `None` if it is not valid or
+/// its dimensions are not the expected ones.
+fn decode_png(png: &[u8], width: usize, height: usize) -> Option<Vec<u8>> {
+	let mut png_reader = spng::Decoder::new(png)
+		.with_decode_flags(DecodeFlags::GAMMA | DecodeFlags::TRANSPARENCY)
+		.with_context_flags(ContextFlags::IGNORE_ADLER32)
+		.with_output_format(Format::Rgba8)
+		.read_info()
+		.ok()?;
+
+	if png_reader.info().width as usize != width || png_reader.info().height as usize != height {
+		return None;
+	}
+
+	let mut pixels = vec![0; png_reader.output_buffer_size()];
+	png_reader.next_frame(&mut pixels).ok()?;
+
+	Some(pixels)
+}
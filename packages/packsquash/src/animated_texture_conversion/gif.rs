@@ -0,0 +1,69 @@
+//! Decodes animations stored in the GIF format.
+
+use std::{num::NonZeroU64, time::Duration};
+
+use gif::{ColorOutput, DecodeOptions, DisposalMethod, MemoryLimit};
+
+use super::{
+	AnimationFrameUpdate, DecodedAnimation, FrameBlendOperation, FrameDisposeOperation, FrameRegion
+};
+
+/// The frame delay that most GIF viewers use for frames that declare a delay of 10 ms or
+/// less, which are shown too fast otherwise, in hundredths of a second.
+const MINIMUM_FRAME_DELAY: u16 = 10;
+
+/// Decodes the animation stored in the specified GIF file, returning `None` if it is not valid
+/// or its logical screen or frames are wider or taller than the specified maximum dimension.
+///
+/// Transparent pixels leave the canvas pixels below them as-is, and the background color of
+/// the GIF is ignored in favor of full transparency, as web browsers do.
+pub(super) fn decode_animation(
+	gif: &[u8],
+	maximum_width_and_height: usize
+) -> Option<DecodedAnimation> {
+	let mut decode_options = DecodeOptions::new();
+	decode_options.set_color_output(ColorOutput::RGBA);
+	// Frames bigger than the canvas can be, which take 4 bytes per pixel, are not decoded
+	decode_options.set_memory_limit(MemoryLimit::Bytes(NonZeroU64::new(
+		maximum_width_and_height.checked_pow(2)?.checked_mul(4)? as u64
+	)?));
+	let mut decoder = decode_options.read_info(gif).ok()?;
+
+	let width = decoder.width() as usize;
+	let height = decoder.height() as usize;
+	if width > maximum_width_and_height || height > maximum_width_and_height {
+		return None;
+	}
+
+	let mut frames = Vec::new();
+	while let Some(frame) = decoder.read_next_frame().ok()? {
+		frames.push(AnimationFrameUpdate {
+			region: FrameRegion {
+				x: frame.left as usize,
+				y: frame.top as usize,
+				width: frame.width as usize,
+				height: frame.height as usize
+			},
+			pixels: frame.buffer.to_vec(),
+			blend_operation: FrameBlendOperation::Over,
+			dispose_operation: match frame.dispose {
+				DisposalMethod::Any | DisposalMethod::Keep => FrameDisposeOperation::None,
+				DisposalMethod::Background => FrameDisposeOperation::Background,
+				DisposalMethod::Previous => FrameDisposeOperation::Previous
+			},
+			delay: Duration::from_millis(
+				if frame.delay <= 1 {
+					MINIMUM_FRAME_DELAY
+				} else {
+					frame.delay
+				} as u64 * 10
+			)
+		});
+	}
+
+	Some(DecodedAnimation {
+		width,
+		height,
+		frames
+	})
+}
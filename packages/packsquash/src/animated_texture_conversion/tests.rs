@@ -0,0 +1,345 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use spng::Format;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::apng::{PNG_SIGNATURE, next_chunk, write_chunk};
+
+use super::*;
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+/// The default maximum texture width and height.
+const MAXIMUM_WIDTH_AND_HEIGHT: usize = 8192;
+
+/// Encodes a GIF animation with frames of the specified dimensions, made up of pixels of the
+/// specified colors and shown during the specified time, in hundredths of a second.
+fn gif(width: u16, height: u16, frames: &[(&[[u8; 4]], u16)]) -> Vec<u8> {
+	const PALETTE: [[u8; 4]; 3] = [RED, GREEN, BLUE];
+
+	let mut encoder = ::gif::Encoder::new(
+		Vec::new(),
+		width,
+		height,
+		&PALETTE.map(|color| [color[0], color[1], color[2]]).concat()
+	)
+	.expect("The test GIF is assumed to be valid");
+
+	for (pixels, delay) in frames {
+		let mut frame = ::gif::Frame::from_indexed_pixels(
+			width,
+			height,
+			pixels
+				.iter()
+				.map(|pixel| PALETTE.iter().position(|color| color == pixel).unwrap() as u8)
+				.collect::<Vec<_>>(),
+			None
+		);
+		frame.delay = *delay;
+
+		encoder
+			.write_frame(&frame)
+			.expect("Encoding the test GIF is assumed not to fail");
+	}
+
+	encoder
+		.into_inner()
+		.expect("Encoding the test GIF is assumed not to fail")
+}
+
+/// Encodes an APNG animation with 1x1 frames of the specified colors, shown during the
+/// specified time, in ticks, and alpha composited over the previous frames.
+fn apng(frames: &[([u8; 4], u16)]) -> Vec<u8> {
+	let mut apng = PNG_SIGNATURE.to_vec();
+	let mut sequence_number = 0u32;
+
+	for (frame_number, (color, ticks)) in frames.iter().enumerate() {
+		let frame_png = RawImage::new(1, 1, ColorType::RGBA, BitDepth::Eight, color.to_vec())
+			.expect("The test image is assumed to be valid")
+			.create_optimized_png(&Options {
+				// Every frame must keep the color type of the first one
+				optimize_alpha: false,
+				bit_depth_reduction: false,
+				color_type_reduction: false,
+				palette_reduction: false,
+				grayscale_reduction: false,
+				..Options::from_preset(0)
+			})
+			.expect("Encoding the test image is assumed not to fail");
+
+		let mut remaining_data = &frame_png[PNG_SIGNATURE.len()..];
+		while let Some((chunk_type, chunk_data, next_chunk_data)) = next_chunk(remaining_data) {
+			remaining_data = next_chunk_data;
+
+			match (&chunk_type, frame_number) {
+				(b"IHDR", 0) => {
+					write_chunk(&mut apng, &chunk_type, chunk_data);
+					write_chunk(
+						&mut apng,
+						b"acTL",
+						&[(frames.len() as u32).to_be_bytes(), 0u32.to_be_bytes()].concat()
+					);
+				}
+				(b"IDAT", _) => {
+					write_chunk(
+						&mut apng,
+						b"fcTL",
+						&[
+							&sequence_number.to_be_bytes()[..],
+							&1u32.to_be_bytes(),
+							&1u32.to_be_bytes(),
+							&0u32.to_be_bytes(),
+							&0u32.to_be_bytes(),
+							&ticks.to_be_bytes(),
+							&20u16.to_be_bytes(),
+							&[0, 1]
+						]
+						.concat()
+					);
+					sequence_number += 1;
+
+					if frame_number == 0 {
+						write_chunk(&mut apng, b"IDAT", chunk_data);
+					} else {
+						write_chunk(
+							&mut apng,
+							b"fdAT",
+							&[&sequence_number.to_be_bytes()[..], chunk_data].concat()
+						);
+						sequence_number += 1;
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+
+	write_chunk(&mut apng, b"IEND", &[]);
+
+	apng
+}
+
+/// Decodes the pixels of the specified PNG texture.
+fn texture_pixels(png: &[u8]) -> Vec<[u8; 4]> {
+	let mut png_reader = spng::Decoder::new(png)
+		.with_output_format(Format::Rgba8)
+		.read_info()
+		.expect("The converted texture should be a valid PNG");
+	let mut pixels = vec![0; png_reader.output_buffer_size()];
+	png_reader
+		.next_frame(&mut pixels)
+		.expect("The converted texture should be a valid PNG");
+
+	pixels
+		.chunks_exact(4)
+		.map(|pixel| pixel.try_into().unwrap())
+		.collect()
+}
+
+/// Parses the specified animation metadata file.
+fn animation_metadata(animation_metadata: Option<Vec<u8>>) -> Value {
+	serde_json::from_slice(&animation_metadata.expect("Animation metadata should be generated"))
+		.expect("The generated animation metadata should be valid JSON")
+}
+
+#[test]
+fn gif_is_converted_to_frame_strip() {
+	let ConvertedTexture {
+		texture,
+		animation_metadata: metadata
+	} = gif::decode_animation(
+		&gif(
+			1,
+			1,
+			&[(&[RED], 10), (&[GREEN], 10), (&[RED], 10), (&[BLUE], 10)]
+		),
+		MAXIMUM_WIDTH_AND_HEIGHT
+	)
+	.and_then(|animation| convert_animation(animation, MAXIMUM_WIDTH_AND_HEIGHT))
+	.expect("The GIF should be converted");
+
+	assert_eq!(texture_pixels(&texture), [RED, GREEN, BLUE]);
+	assert_eq!(
+		animation_metadata(metadata),
+		json!({ "animation": { "frametime": 2, "frames": [0, 1, 0, 2] } })
+	);
+}
+
+#[test]
+fn non_square_gif_frame_dimensions_are_kept() {
+	let ConvertedTexture {
+		texture,
+		animation_metadata: metadata
+	} = gif::decode_animation(
+		&gif(2, 1, &[(&[RED, GREEN], 5), (&[GREEN, RED], 15)]),
+		MAXIMUM_WIDTH_AND_HEIGHT
+	)
+	.and_then(|animation| convert_animation(animation, MAXIMUM_WIDTH_AND_HEIGHT))
+	.expect("The GIF should be converted");
+
+	assert_eq!(texture_pixels(&texture), [RED, GREEN, GREEN, RED]);
+	assert_eq!(
+		animation_metadata(metadata),
+		json!({
+			"animation": {
+				"width": 2,
+				"height": 1,
+				"frames": [{ "index": 0, "time": 1 }, { "index": 1, "time": 3 }]
+			}
+		})
+	);
+}
+
+#[test]
+fn still_gif_is_converted_without_animation_metadata() {
+	let ConvertedTexture {
+		texture,
+		animation_metadata
+	} = gif::decode_animation(
+		&gif(1, 1, &[(&[BLUE], 10), (&[BLUE], 10)]),
+		MAXIMUM_WIDTH_AND_HEIGHT
+	)
+	.and_then(|animation| convert_animation(animation, MAXIMUM_WIDTH_AND_HEIGHT))
+	.expect("The GIF should be converted");
+
+	assert_eq!(texture_pixels(&texture), [BLUE]);
+	assert!(animation_metadata.is_none());
+}
+
+#[test]
+fn apng_frames_are_composited() {
+	let ConvertedTexture {
+		texture,
+		animation_metadata: metadata
+	} = apng::decode_animation(
+		&apng(&[(RED, 1), (TRANSPARENT, 1), (BLUE, 4)]),
+		MAXIMUM_WIDTH_AND_HEIGHT
+	)
+	.and_then(|animation| convert_animation(animation, MAXIMUM_WIDTH_AND_HEIGHT))
+	.expect("The APNG should be converted");
+
+	// Transparent frames composited over the previous frame leave it unchanged, so the
+	// first two frames should be merged
+	assert_eq!(texture_pixels(&texture), [RED, BLUE]);
+	assert_eq!(
+		animation_metadata(metadata),
+		json!({
+			"animation": {
+				"frames": [{ "index": 0, "time": 2 }, { "index": 1, "time": 4 }]
+			}
+		})
+	);
+}
+
+#[test]
+fn off_canvas_frames_are_ignored() {
+	let frame = |x, pixel: [u8; 4]| AnimationFrameUpdate {
+		region: FrameRegion {
+			x,
+			y: 0,
+			width: 1,
+			height: 1
+		},
+		pixels: pixel.to_vec(),
+		blend_operation: FrameBlendOperation::Source,
+		dispose_operation: FrameDisposeOperation::Background,
+		delay: TICK_DURATION
+	};
+
+	let ConvertedTexture { texture, .. } = convert_animation(
+		DecodedAnimation {
+			width: 1,
+			height: 1,
+			frames: vec![frame(0, RED), frame(3, GREEN)]
+		},
+		MAXIMUM_WIDTH_AND_HEIGHT
+	)
+	.expect("The animation should be converted");
+
+	// The second frame leaves the canvas as the first one left it when disposed
+	assert_eq!(texture_pixels(&texture), [RED, TRANSPARENT]);
+}
+
+#[test]
+fn oversized_animations_are_not_converted() {
+	// Allocating a canvas of the declared dimensions would take 16 GiB of memory
+	let mut gif = gif(1, 1, &[(&[RED], 10)]);
+	gif[6..10].copy_from_slice(&[0xFF; 4]);
+	assert!(gif::decode_animation(&gif, MAXIMUM_WIDTH_AND_HEIGHT).is_none());
+
+	let mut apng = apng(&[(RED, 1), (BLUE, 1)]);
+	let header_offset = PNG_SIGNATURE.len() + 8;
+	apng[header_offset..header_offset + 8].copy_from_slice(&[0, 0, 0xFF, 0xFF, 0, 0, 0xFF, 0xFF]);
+	assert!(apng::decode_animation(&apng, MAXIMUM_WIDTH_AND_HEIGHT).is_none());
+}
+
+#[test]
+fn animations_with_too_tall_frame_strips_are_not_converted() {
+	let animation = || {
+		gif::decode_animation(
+			&gif(1, 1, &[(&[RED], 10), (&[GREEN], 10), (&[BLUE], 10)]),
+			MAXIMUM_WIDTH_AND_HEIGHT
+		)
+		.expect("The GIF should be decoded")
+	};
+
+	assert!(convert_animation(animation(), 2).is_none());
+	assert!(convert_animation(animation(), 3).is_some());
+}
+
+#[test]
+fn converted_files_replace_animated_textures() {
+	let root_dir = Builder::new()
+		.prefix("ps-animated-texture-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	let textures_dir = root_dir.path().join("assets/minecraft/textures/block");
+	fs::create_dir_all(&textures_dir).expect("I/O operations are assumed not to fail during tests");
+	fs::write(
+		textures_dir.join("fire.gif"),
+		gif(1, 1, &[(&[RED], 10), (&[GREEN], 10)])
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+	// This GIF must not be converted, because its conversion would overwrite a pack file
+	fs::write(textures_dir.join("water.gif"), gif(1, 1, &[(&[BLUE], 10)]))
+		.expect("I/O operations are assumed not to fail during tests");
+	fs::write(textures_dir.join("water.png"), [])
+		.expect("I/O operations are assumed not to fail during tests");
+
+	let mut vfs = AnimatedTextureConvertingVfs::new(OsFilesystem);
+	tokio_test::block_on(vfs.convert_animated_textures(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		|_| NonZeroU16::new(MAXIMUM_WIDTH_AND_HEIGHT as u16).unwrap()
+	));
+
+	let mut relative_paths = vfs
+		.file_iterator(root_dir.path(), IteratorTraversalOptions::default())
+		.map(|entry| {
+			entry
+				.expect("I/O operations are assumed not to fail during tests")
+				.relative_path
+				.as_str()
+				.to_string()
+		})
+		.collect::<Vec<_>>();
+	relative_paths.sort_unstable();
+
+	assert_eq!(
+		relative_paths,
+		[
+			"assets/minecraft/textures/block/fire.png",
+			"assets/minecraft/textures/block/fire.png.mcmeta",
+			"assets/minecraft/textures/block/water.gif",
+			"assets/minecraft/textures/block/water.png"
+		]
+	);
+	assert!(vfs.open(textures_dir.join("fire.png.mcmeta")).is_ok());
+}
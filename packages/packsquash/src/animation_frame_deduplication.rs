@@ -0,0 +1,267 @@
+//! Contains a virtual file system adapter that deduplicates identical frames of animated
+//! textures, rewriting both their vertical frame strip and their animation metadata file.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf},
+	time::SystemTime
+};
+
+use ahash::AHashMap;
+use bytes::Bytes;
+use json_comments::StripComments;
+use oxipng::{BitDepth, ColorType, Options, RawImage};
+use serde_json::Value;
+use spng::{ContextFlags, DecodeFlags, Format};
+use tokio_util::either::Either;
+
+use crate::pack_file::strip_utf8_bom;
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A pack file whose contents were rewritten by [`AnimationFrameDeduplicatingVfs`].
+struct RewrittenFile {
+	data: Bytes,
+	modification_time: Option<SystemTime>
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently replacing animated textures
+/// that contain duplicate or unused frames in their vertical frame strip with a version that
+/// contains every used frame just once, and rewriting the `frames` list of their animation
+/// metadata file to reference the deduplicated frames.
+///
+/// The rewritten files are computed eagerly by [`Self::deduplicate_frames`] and kept in memory,
+/// because the [`VirtualFileSystem::open`] method is synchronous. Other files are read from
+/// the wrapped file system as-is.
+pub struct AnimationFrameDeduplicatingVfs<V: VirtualFileSystem> {
+	inner: V,
+	rewritten_files: AHashMap<PathBuf, RewrittenFile>
+}
+
+impl<V: VirtualFileSystem> AnimationFrameDeduplicatingVfs<V> {
+	/// Wraps the specified virtual file system. No files will be rewritten until
+	/// [`Self::deduplicate_frames`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			rewritten_files: AHashMap::new()
+		}
+	}
+
+	/// Scans the pack at the specified root path for animated textures with duplicate or unused
+	/// frames, computing their rewritten versions. Animated textures that can't be read or parsed
+	/// are left as-is, so that the usual pack file processing reports any relevant error.
+	pub async fn deduplicate_frames(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) {
+		for VfsPackFileIterEntry {
+			relative_path,
+			file_path: animation_metadata_path
+		} in self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+		{
+			let relative_path = relative_path.as_str();
+			if !relative_path.starts_with("assets/")
+				|| !relative_path.contains("/textures/")
+				|| !relative_path.ends_with(".png.mcmeta")
+			{
+				continue;
+			}
+
+			let texture_path = animation_metadata_path.with_extension("");
+
+			let (
+				Ok((texture, texture_modification_time)),
+				Ok((animation_metadata, animation_metadata_modification_time))
+			) = (
+				read_file(&self.inner, &texture_path).await,
+				read_file(&self.inner, &animation_metadata_path).await
+			)
+			else {
+				continue;
+			};
+
+			if let Some((deduplicated_texture, deduplicated_animation_metadata)) =
+				deduplicate_animation_frames(&texture, &animation_metadata)
+			{
+				// Both files depend on each other now, so consider both modified when any of
+				// them is, so that stale versions of them are not reused from previous runs
+				let modification_time = texture_modification_time
+					.zip(animation_metadata_modification_time)
+					.map(|(texture_time, animation_metadata_time)| {
+						texture_time.max(animation_metadata_time)
+					});
+
+				self.rewritten_files.insert(
+					texture_path,
+					RewrittenFile {
+						data: deduplicated_texture.into(),
+						modification_time
+					}
+				);
+				self.rewritten_files.insert(
+					animation_metadata_path,
+					RewrittenFile {
+						data: deduplicated_animation_metadata.into(),
+						modification_time
+					}
+				);
+			}
+		}
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for AnimationFrameDeduplicatingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = V::FileIter;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.rewritten_files
+				.get(path.as_ref())
+				.map(|rewritten_file| (&rewritten_file.data, rewritten_file.modification_time))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
+
+/// Deduplicates the frames of the animated texture contained in the specified PNG file, whose
+/// animation metadata is in the specified `.mcmeta` file. If the texture has duplicate or unused
+/// frames, the rewritten PNG and animation metadata files are returned. Otherwise, or if the
+/// texture is not a vertical frame strip or any of the files is invalid, `None` is returned.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Resource_pack#Animation>
+/// - Minecraft class `net.minecraft.client.resources.metadata.animation.AnimationMetadataSection`
+fn deduplicate_animation_frames(
+	texture: &[u8],
+	animation_metadata: &[u8]
+) -> Option<(Vec<u8>, Vec<u8>)> {
+	let mut animation_metadata: Value =
+		serde_json::from_reader(StripComments::new(strip_utf8_bom(animation_metadata))).ok()?;
+	let animation = animation_metadata.get_mut("animation")?.as_object_mut()?;
+
+	let mut png_reader = spng::Decoder::new(texture)
+		.with_decode_flags(DecodeFlags::GAMMA | DecodeFlags::TRANSPARENCY)
+		.with_context_flags(ContextFlags::IGNORE_ADLER32)
+		.with_output_format(Format::Rgba8)
+		.read_info()
+		.ok()?;
+	let width = png_reader.info().width as usize;
+	let height = png_reader.info().height as usize;
+	let mut pixels = vec![0; png_reader.output_buffer_size()];
+	png_reader.next_frame(&mut pixels).ok()?;
+
+	let frame_dimension = |key: &str| {
+		animation
+			.get(key)
+			.map(|dimension: &Value| dimension.as_u64().filter(|dimension| *dimension > 0))
+	};
+	let (frame_width, frame_height) = match (frame_dimension("width"), frame_dimension("height")) {
+		(Some(None), _) | (_, Some(None)) => return None,
+		(Some(Some(frame_width)), frame_height) => (
+			frame_width as usize,
+			frame_height.flatten().unwrap_or(height as u64) as usize
+		),
+		(None, Some(Some(frame_height))) => (width, frame_height as usize),
+		(None, None) => (width.min(height), width.min(height))
+	};
+
+	// Only vertical frame strips are handled. Frames laid out in a grid are rare
+	if frame_width != width || frame_height == 0 || height % frame_height != 0 {
+		return None;
+	}
+
+	let frame_size = frame_height * width * 4;
+	let frame_count = height / frame_height;
+	let frame = |index: usize| &pixels[index * frame_size..(index + 1) * frame_size];
+
+	// Get the frames that are shown, in order, from the frames list. Each element may be a
+	// frame index or an object with the frame index and its display time
+	let mut frames = match animation.get("frames") {
+		Some(Value::Array(frames)) => frames.clone(),
+		Some(_) => return None,
+		None => (0..frame_count).map(Value::from).collect()
+	};
+
+	// Assign a new index to every distinct frame that is shown, in order of appearance
+	let mut deduplicated_frame_indices = AHashMap::<&[u8], usize>::new();
+	let mut deduplicated_frames = Vec::new();
+	for frame_entry in &mut frames {
+		let index = match frame_entry {
+			Value::Object(frame_entry) => frame_entry.get_mut("index")?,
+			frame_entry => frame_entry
+		};
+		let source_index = index
+			.as_u64()
+			.map(|index| index as usize)
+			.filter(|index| *index < frame_count)?;
+
+		let new_index = *deduplicated_frame_indices
+			.entry(frame(source_index))
+			.or_insert_with(|| {
+				deduplicated_frames.push(source_index);
+				deduplicated_frames.len() - 1
+			});
+
+		*index = new_index.into();
+	}
+
+	if deduplicated_frames.len() == frame_count {
+		// Every frame is distinct and shown, so there is nothing to deduplicate
+		return None;
+	}
+
+	let deduplicated_pixels = deduplicated_frames
+		.iter()
+		.flat_map(|index| frame(*index))
+		.copied()
+		.collect::<Vec<_>>();
+
+	// Encode the deduplicated texture quickly: it will be optimized later, like any other
+	let deduplicated_texture = RawImage::new(
+		width as u32,
+		(deduplicated_frames.len() * frame_height) as u32,
+		ColorType::RGBA,
+		BitDepth::Eight,
+		deduplicated_pixels
+	)
+	.ok()?
+	.create_optimized_png(&Options::from_preset(0))
+	.ok()?;
+
+	animation.insert("frames".into(), Value::Array(frames));
+	// A missing frame height makes Minecraft compute it from the texture height, which changed
+	if !animation.contains_key("height") && animation.contains_key("width") {
+		animation.insert("height".into(), frame_height.into());
+	}
+
+	Some((
+		deduplicated_texture,
+		serde_json::to_vec(&animation_metadata).ok()?
+	))
+}
@@ -0,0 +1,94 @@
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+use super::*;
+
+/// Encodes a vertical frame strip of 1x1 frames with the specified RGBA colors as a PNG.
+fn frame_strip(frame_colors: &[[u8; 4]]) -> Vec<u8> {
+	RawImage::new(
+		1,
+		frame_colors.len() as u32,
+		ColorType::RGBA,
+		BitDepth::Eight,
+		frame_colors.concat()
+	)
+	.expect("The test image is assumed to be valid")
+	.create_optimized_png(&Options::from_preset(0))
+	.expect("Encoding the test image is assumed not to fail")
+}
+
+/// Decodes the colors of the 1x1 frames of the specified PNG vertical frame strip.
+fn frame_strip_colors(png: &[u8]) -> Vec<[u8; 4]> {
+	let mut png_reader = spng::Decoder::new(png)
+		.with_output_format(Format::Rgba8)
+		.read_info()
+		.expect("The deduplicated texture should be a valid PNG");
+	let mut pixels = vec![0; png_reader.output_buffer_size()];
+	png_reader
+		.next_frame(&mut pixels)
+		.expect("The deduplicated texture should be a valid PNG");
+
+	pixels
+		.chunks_exact(4)
+		.map(|pixel| pixel.try_into().unwrap())
+		.collect()
+}
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+#[test]
+fn duplicate_frames_are_removed() {
+	let (texture, animation_metadata) = deduplicate_animation_frames(
+		&frame_strip(&[RED, GREEN, RED, GREEN]),
+		br#"{ "animation": { "frametime": 2 } }"#
+	)
+	.expect("The duplicate frames should be deduplicated");
+
+	assert_eq!(frame_strip_colors(&texture), [RED, GREEN]);
+	assert_eq!(
+		serde_json::from_slice::<Value>(&animation_metadata).unwrap(),
+		json!({ "animation": { "frametime": 2, "frames": [0, 1, 0, 1] } })
+	);
+}
+
+#[test]
+fn unused_frames_are_removed_and_frame_times_are_kept() {
+	let (texture, animation_metadata) = deduplicate_animation_frames(
+		&frame_strip(&[RED, GREEN, BLUE]),
+		br#"{
+			"animation": { "frames": [2, { "index": 0, "time": 5 }, 2] },
+			"texture": { "blur": true }
+		}"#
+	)
+	.expect("The unused frames should be removed");
+
+	assert_eq!(frame_strip_colors(&texture), [BLUE, RED]);
+	assert_eq!(
+		serde_json::from_slice::<Value>(&animation_metadata).unwrap(),
+		json!({
+			"animation": { "frames": [0, { "index": 1, "time": 5 }, 0] },
+			"texture": { "blur": true }
+		})
+	);
+}
+
+#[test]
+fn textures_without_duplicate_frames_are_left_as_is() {
+	assert!(
+		deduplicate_animation_frames(&frame_strip(&[RED, GREEN, BLUE]), br#"{ "animation": {} }"#)
+			.is_none()
+	);
+}
+
+#[test]
+fn invalid_frame_indices_are_left_as_is() {
+	assert!(
+		deduplicate_animation_frames(
+			&frame_strip(&[RED, RED]),
+			br#"{ "animation": { "frames": [0, 2] } }"#
+		)
+		.is_none()
+	);
+}
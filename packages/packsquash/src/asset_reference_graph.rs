@@ -0,0 +1,449 @@
+//! Contains routines to build a graph of the references between the assets of a pack, from
+//! block states and item model definitions to models, from models to other models and textures,
+//! from atlas configuration and OptiFine properties files to textures and models, and from
+//! `sounds.json` files to sound files, in order to find the assets no other pack file references.
+
+use std::path::{Path, PathBuf};
+
+use ahash::{AHashMap, AHashSet};
+use globset::GlobSet;
+use serde_json::{Map, Value};
+
+use crate::RelativePath;
+use crate::pack_file::{blank_out_relaxed_syntax, strip_utf8_bom};
+use crate::sound_reference_validation::validate_sound_references;
+use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem, read_file};
+
+#[cfg(test)]
+mod tests;
+
+/// The namespace of vanilla assets, which is also the namespace of resource locations that do
+/// not specify any.
+const VANILLA_NAMESPACE: &str = "minecraft";
+
+/// The texture folders that the block atlas includes by default, and thus the only folders
+/// that models can use textures from without custom atlas configuration files.
+const MODEL_TEXTURE_FOLDERS: &[&str] = &["block/", "item/"];
+
+/// A kind of asset file that may reference or be referenced by other asset files.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AssetKind {
+	BlockState,
+	ItemModelDefinition,
+	Model,
+	Texture,
+	TextureMetadata,
+	Font,
+	Atlas,
+	OptifineProperties
+}
+
+/// An asset file of a pack, identified by its namespace and its path relative to the asset
+/// kind folder of that namespace, without extension.
+struct Asset {
+	kind: AssetKind,
+	namespace: String,
+	path: String,
+	relative_path: RelativePath<'static>,
+	file_path: PathBuf
+}
+
+/// Builds a reference graph of the assets of the pack at the specified root path and returns
+/// the models, textures and sound files that nothing references, which are dead weight.
+///
+/// Only the assets of namespaces other than the vanilla one that contain block states or item
+/// model definitions are considered to be possibly unreferenced, as the game, mods and other
+/// packs may reference the assets of other namespaces. Within those namespaces, item models
+/// are always considered to be referenced, because the game loads them by the identifier of
+/// their item, and only textures in the folders models can use by default are considered, as
+/// textures in other folders are usually used by the game directly. Textures included by atlas
+/// configuration files and assets named by OptiFine properties files are considered to be
+/// referenced too. Sound files follow the same rules as [`validate_sound_references`]. Assets
+/// matched by the `exemption_globs`, and the assets they reference, are never considered to be
+/// unreferenced.
+///
+/// If some file that may reference assets can't be read or parsed, no asset is returned, so
+/// that used assets are never reported as unreferenced. The usual pack file processing reports
+/// any relevant error.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Tutorial:Models>
+/// - <https://minecraft.wiki/w/Items_model_definition>
+/// - <https://minecraft.wiki/w/Resource_pack#Fonts>
+/// - <https://minecraft.wiki/w/Resource_pack#Atlases>
+/// - <https://github.com/sp614x/optifine/blob/master/OptiFineDoc/doc/cit.properties>
+/// - <https://github.com/sp614x/optifine/blob/master/OptiFineDoc/doc/ctm.properties>
+pub async fn find_unreferenced_assets<V: VirtualFileSystem>(
+	vfs: &V,
+	root_path: &Path,
+	iterator_traversal_options: IteratorTraversalOptions,
+	exemption_globs: &GlobSet
+) -> AHashSet<RelativePath<'static>> {
+	let mut assets = vec![];
+	let mut candidate_namespaces = AHashSet::new();
+	for VfsPackFileIterEntry {
+		relative_path,
+		file_path
+	} in vfs
+		.file_iterator(root_path, iterator_traversal_options)
+		.flatten()
+	{
+		let Some(asset) = asset_for_path(relative_path, file_path) else {
+			continue;
+		};
+
+		if matches!(
+			asset.kind,
+			AssetKind::BlockState | AssetKind::ItemModelDefinition
+		) && asset.namespace != VANILLA_NAMESPACE
+		{
+			candidate_namespaces.insert(asset.namespace.clone());
+		}
+
+		assets.push(asset);
+	}
+
+	let is_candidate = |asset: &Asset| {
+		candidate_namespaces.contains(&asset.namespace)
+			&& !exemption_globs.is_match(asset.relative_path.as_str())
+			&& match asset.kind {
+				AssetKind::Model => !asset.path.starts_with("item/"),
+				AssetKind::Texture | AssetKind::TextureMetadata => MODEL_TEXTURE_FOLDERS
+					.iter()
+					.any(|folder| asset.path.starts_with(folder)),
+				_ => false
+			}
+	};
+
+	// Every model that is not a candidate for being unreferenced is a root of the reference
+	// graph, so follow the references of block states, item model definitions and such models
+	let mut model_references = AHashMap::new();
+	let mut pending_models = vec![];
+	let mut referenced_textures = AHashSet::new();
+	let mut referenced_texture_folders = vec![];
+	let mut optifine_referenced_file_names = AHashSet::new();
+	for asset in &assets {
+		match asset.kind {
+			AssetKind::Texture | AssetKind::TextureMetadata => continue,
+			AssetKind::OptifineProperties => {
+				let Ok((properties, _)) = read_file(vfs, &asset.file_path).await else {
+					return AHashSet::new();
+				};
+
+				optifine_referenced_file_names.extend(optifine_asset_references(
+					&String::from_utf8_lossy(strip_utf8_bom(&properties))
+				));
+				continue;
+			}
+			_ => {}
+		}
+
+		let Some(asset_json) = read_json(vfs, &asset.file_path).await else {
+			return AHashSet::new();
+		};
+
+		match asset.kind {
+			AssetKind::Font => referenced_textures.extend(font_texture_references(&asset_json)),
+			AssetKind::Atlas => {
+				let references = atlas_references(&asset_json);
+				referenced_textures.extend(references.textures);
+				referenced_texture_folders.extend(references.folders);
+			}
+			AssetKind::Model => {
+				if !is_candidate(asset) {
+					pending_models.push((asset.namespace.clone(), asset.path.clone()));
+				}
+
+				model_references.insert(
+					(asset.namespace.as_str(), asset.path.as_str()),
+					model_file_references(&asset_json)
+				);
+			}
+			_ => {
+				let references = model_file_references(&asset_json);
+				pending_models.extend(references.models);
+				referenced_textures.extend(references.textures);
+			}
+		}
+	}
+
+	// OptiFine properties files may reference assets by paths relative to them, which are not
+	// resolved. Instead, every asset with a file name they reference is considered to be used
+	pending_models.extend(
+		assets
+			.iter()
+			.filter(|asset| {
+				asset.kind == AssetKind::Model
+					&& optifine_referenced_file_names.contains(asset_file_name(&asset.path))
+			})
+			.map(|asset| (asset.namespace.clone(), asset.path.clone()))
+	);
+
+	let mut referenced_models = AHashSet::new();
+	while let Some(model) = pending_models.pop() {
+		if let Some(references) = model_references.get(&(model.0.as_str(), model.1.as_str()))
+			&& !referenced_models.contains(&model)
+		{
+			pending_models.extend(references.models.iter().cloned());
+			referenced_textures.extend(references.textures.iter().cloned());
+		}
+
+		referenced_models.insert(model);
+	}
+
+	let mut unreferenced_assets = assets
+		.into_iter()
+		.filter(|asset| is_candidate(asset))
+		.filter(|asset| {
+			let asset_id = (asset.namespace.clone(), asset.path.clone());
+
+			match asset.kind {
+				AssetKind::Model => !referenced_models.contains(&asset_id),
+				_ => {
+					!referenced_textures.contains(&asset_id)
+						&& !referenced_texture_folders
+							.iter()
+							.any(|folder| asset.path.starts_with(folder.as_str()))
+						&& !optifine_referenced_file_names.contains(asset_file_name(&asset.path))
+				}
+			}
+		})
+		.map(|asset| asset.relative_path)
+		.collect::<AHashSet<_>>();
+
+	let sound_references =
+		validate_sound_references(vfs, root_path, iterator_traversal_options).await;
+	unreferenced_assets.extend(
+		sound_references
+			.unreferenced_sounds
+			.into_iter()
+			.filter(|relative_path| !exemption_globs.is_match(relative_path.as_str()))
+	);
+
+	unreferenced_assets
+}
+
+/// The models and textures referenced by a block state, item model definition or model file,
+/// as namespace and path pairs.
+#[derive(Default)]
+struct AssetReferences {
+	models: Vec<(String, String)>,
+	textures: Vec<(String, String)>
+}
+
+/// Returns the asset at the specified relative path, or `None` if it is not an asset file
+/// that may reference or be referenced by other asset files.
+fn asset_for_path(relative_path: RelativePath<'static>, file_path: PathBuf) -> Option<Asset> {
+	let (namespace, path) = relative_path
+		.as_str()
+		.strip_prefix("assets/")?
+		.split_once('/')?;
+	let (kind_folder, path) = path.split_once('/')?;
+
+	let (kind, path) = match kind_folder {
+		"blockstates" => (AssetKind::BlockState, path.strip_suffix(".json")?),
+		"items" => (AssetKind::ItemModelDefinition, path.strip_suffix(".json")?),
+		"models" => (AssetKind::Model, path.strip_suffix(".json")?),
+		"font" => (AssetKind::Font, path.strip_suffix(".json")?),
+		"atlases" => (AssetKind::Atlas, path.strip_suffix(".json")?),
+		"optifine" | "mcpatcher" => (
+			AssetKind::OptifineProperties,
+			path.strip_suffix(".properties")?
+		),
+		"textures" => match path.strip_suffix(".png.mcmeta") {
+			Some(path) => (AssetKind::TextureMetadata, path),
+			None => (AssetKind::Texture, path.strip_suffix(".png")?)
+		},
+		_ => return None
+	};
+
+	Some(Asset {
+		kind,
+		namespace: namespace.to_string(),
+		path: path.to_string(),
+		relative_path,
+		file_path
+	})
+}
+
+/// Returns the models and textures referenced by the specified block state, item model
+/// definition or model. Models are referenced by `model` and `base` keys of block states and
+/// item model definitions, and the `parent` key and the `model` key of overrides of models.
+/// Textures are referenced by the values of the `textures` object of models that are not
+/// references to texture variables.
+fn model_file_references(asset_json: &Value) -> AssetReferences {
+	let mut references = AssetReferences::default();
+
+	let mut pending_values = vec![asset_json];
+	while let Some(value) = pending_values.pop() {
+		match value {
+			Value::Object(object) => {
+				for (key, value) in object {
+					match (key.as_str(), value) {
+						("model" | "base" | "parent", Value::String(model)) => {
+							references.models.push(resource_location(model));
+						}
+						("textures", Value::Object(textures)) => {
+							references.textures.extend(
+								textures
+									.values()
+									.filter_map(Value::as_str)
+									.filter(|texture| !texture.starts_with('#'))
+									.map(resource_location)
+							);
+						}
+						_ => pending_values.push(value)
+					}
+				}
+			}
+			Value::Array(array) => pending_values.extend(array),
+			_ => {}
+		}
+	}
+
+	references
+}
+
+/// Returns the textures referenced by the bitmap providers of a font file, whose `file` key
+/// contains the path of the texture relative to the textures folder, with extension.
+fn font_texture_references(font: &Value) -> impl Iterator<Item = (String, String)> + '_ {
+	font.get("providers")
+		.and_then(Value::as_array)
+		.into_iter()
+		.flatten()
+		.filter_map(|provider| provider.get("file")?.as_str()?.strip_suffix(".png"))
+		.map(resource_location)
+}
+
+/// The textures referenced by an atlas configuration file.
+#[derive(Default)]
+struct AtlasReferences {
+	/// Individual textures, as namespace and path pairs.
+	textures: Vec<(String, String)>,
+	/// Folders whose textures are all included, relative to the textures folder of any
+	/// namespace and with a trailing slash.
+	folders: Vec<String>
+}
+
+/// Returns the textures referenced by the sources of the specified atlas configuration file.
+/// `single` and `unstitch` sources reference their `resource`, `paletted_permutations` sources
+/// reference their `textures`, their `palette_key` and the palettes of their `permutations`,
+/// and `directory` sources reference every texture in their `source` folder. The folders models
+/// can use by default are not considered to be referenced, because the block atlas always
+/// includes them, so that does not mean that their textures are used.
+fn atlas_references(atlas: &Value) -> AtlasReferences {
+	let mut references = AtlasReferences::default();
+
+	for source in atlas
+		.get("sources")
+		.and_then(Value::as_array)
+		.into_iter()
+		.flatten()
+	{
+		let source_type = source
+			.get("type")
+			.and_then(Value::as_str)
+			.unwrap_or_default();
+
+		match source_type
+			.strip_prefix("minecraft:")
+			.unwrap_or(source_type)
+		{
+			"single" | "unstitch" => references.textures.extend(
+				source
+					.get("resource")
+					.and_then(Value::as_str)
+					.map(resource_location)
+			),
+			"paletted_permutations" => references.textures.extend(
+				source
+					.get("textures")
+					.and_then(Value::as_array)
+					.into_iter()
+					.flatten()
+					.chain(source.get("palette_key"))
+					.chain(
+						source
+							.get("permutations")
+							.and_then(Value::as_object)
+							.into_iter()
+							.flat_map(Map::values)
+					)
+					.filter_map(Value::as_str)
+					.map(resource_location)
+			),
+			"directory" => references.folders.extend(
+				source
+					.get("source")
+					.and_then(Value::as_str)
+					.map(|folder| format!("{}/", folder.trim_end_matches('/')))
+					.filter(|folder| !MODEL_TEXTURE_FOLDERS.contains(&folder.as_str()))
+			),
+			_ => {}
+		}
+	}
+
+	references
+}
+
+/// Returns the file names, without extension, of the textures and models referenced by the
+/// `texture`, `tiles` and `model` properties of the specified OptiFine properties file, and by
+/// their variants for specific models and textures, such as `texture.bow_pulling_0`. Those
+/// properties may contain paths relative to the properties file, so only file names are
+/// returned.
+fn optifine_asset_references(properties: &str) -> AHashSet<String> {
+	properties
+		.lines()
+		.map(str::trim_start)
+		.filter(|line| !line.starts_with(['#', '!']))
+		.filter_map(|line| line.split_once('=').or_else(|| line.split_once(':')))
+		.filter(|(key, _)| {
+			let key = key.trim_end();
+			let key = key.split_once('.').map_or(key, |(key, _)| key);
+
+			matches!(key, "texture" | "tiles" | "model")
+		})
+		.flat_map(|(_, value)| value.split_whitespace())
+		.map(|reference| {
+			let file_name = reference
+				.rsplit_once(['/', ':'])
+				.map_or(reference, |(_, file_name)| file_name);
+
+			file_name
+				.strip_suffix(".png")
+				.or_else(|| file_name.strip_suffix(".json"))
+				.unwrap_or(file_name)
+				.to_string()
+		})
+		.collect()
+}
+
+/// Returns the file name of the asset at the specified path, relative to its asset kind folder
+/// and without extension.
+fn asset_file_name(path: &str) -> &str {
+	path.rsplit_once('/')
+		.map_or(path, |(_, file_name)| file_name)
+}
+
+/// Splits a resource location into its namespace and path, using the vanilla namespace if it
+/// does not specify any.
+fn resource_location(resource_location: &str) -> (String, String) {
+	let (namespace, path) = resource_location
+		.split_once(':')
+		.unwrap_or((VANILLA_NAMESPACE, resource_location));
+
+	(namespace.to_string(), path.to_string())
+}
+
+/// Reads and parses the JSON file at the specified path of a virtual file system, allowing
+/// comments and trailing commas like the usual JSON file processing does, and returning `None`
+/// if some I/O or parsing error occurs.
+async fn read_json<V: VirtualFileSystem>(vfs: &V, path: &Path) -> Option<Value> {
+	let (mut data, _) = read_file(vfs, path).await.ok()?;
+
+	let bom_length = data.len() - strip_utf8_bom(&data).len();
+	let json = &mut data[bom_length..];
+	blank_out_relaxed_syntax(json).ok()?;
+
+	serde_json::from_slice(json).ok()
+}
@@ -0,0 +1,182 @@
+use std::fs;
+
+use globset::GlobSetBuilder;
+use itertools::Itertools;
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use crate::config::compile_pack_file_glob_pattern;
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[test]
+fn unreferenced_assets_are_found() {
+	let root_dir = Builder::new()
+		.prefix("ps-asset-reference-graph-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"assets/mypack/blockstates/ruby_block.json",
+			r#"{
+				// Comments and trailing commas are allowed, like in the usual JSON processing
+				"variants": { "": { "model": "mypack:block/ruby_block" }, },
+			}"#
+		),
+		(
+			"assets/mypack/models/block/ruby_block.json",
+			r#"{ "parent": "mypack:block/base", "textures": { "all": "mypack:block/ruby" } }"#
+		),
+		(
+			"assets/mypack/models/block/base.json",
+			r##"{ "parent": "block/cube_all", "textures": { "particle": "#all" } }"##
+		),
+		(
+			"assets/mypack/models/block/unused.json",
+			r#"{ "textures": { "all": "mypack:block/unused" } }"#
+		),
+		(
+			"assets/mypack/models/block/exempt.json",
+			r#"{ "textures": { "all": "mypack:block/exempt" } }"#
+		),
+		(
+			"assets/mypack/models/item/ruby.json",
+			r#"{ "parent": "item/generated", "textures": { "layer0": "mypack:item/ruby" } }"#
+		),
+		(
+			"assets/mypack/font/default.json",
+			r#"{ "providers": [{ "type": "bitmap", "file": "mypack:item/icon.png", "chars": ["a"] }] }"#
+		),
+		(
+			"assets/minecraft/models/block/stone.json",
+			r#"{ "parent": "block/cube_all", "textures": { "all": "mypack:block/stone" } }"#
+		),
+		(
+			"assets/mypack/sounds.json",
+			r#"{ "block.ruby": { "sounds": ["mypack:block/ruby"] } }"#
+		),
+		("assets/mypack/textures/block/ruby.png", ""),
+		("assets/mypack/textures/block/ruby.png.mcmeta", ""),
+		("assets/mypack/textures/block/unused.png", ""),
+		("assets/mypack/textures/block/unused.png.mcmeta", ""),
+		("assets/mypack/textures/block/exempt.png", ""),
+		("assets/mypack/textures/block/stone.png", ""),
+		("assets/mypack/textures/item/ruby.png", ""),
+		("assets/mypack/textures/item/icon.png", ""),
+		("assets/mypack/textures/entity/unused.png", ""),
+		("assets/mypack/sounds/block/ruby.ogg", ""),
+		("assets/mypack/sounds/block/unused.ogg", ""),
+		("assets/othermod/models/block/unused.json", "{}"),
+		("assets/othermod/textures/block/unused.png", "")
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mut exemption_globs = GlobSetBuilder::new();
+	exemption_globs
+		.add(compile_pack_file_glob_pattern("assets/mypack/models/block/exempt.json").unwrap());
+
+	let unreferenced_assets = tokio_test::block_on(find_unreferenced_assets(
+		&OsFilesystem,
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		&exemption_globs.build().unwrap()
+	));
+
+	assert_eq!(
+		unreferenced_assets
+			.iter()
+			.map(RelativePath::as_str)
+			.sorted_unstable()
+			.collect::<Vec<_>>(),
+		[
+			"assets/mypack/models/block/unused.json",
+			"assets/mypack/sounds/block/unused.ogg",
+			"assets/mypack/textures/block/unused.png",
+			"assets/mypack/textures/block/unused.png.mcmeta"
+		]
+	);
+}
+
+#[test]
+fn atlas_and_optifine_references_are_followed() {
+	let root_dir = Builder::new()
+		.prefix("ps-asset-reference-graph-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"assets/mypack/items/ruby.json",
+			r#"{ "model": { "type": "model", "model": "mypack:item/ruby" } }"#
+		),
+		(
+			"assets/mypack/atlases/gems.json",
+			r#"{
+				"sources": [
+					{ "type": "single", "resource": "mypack:block/gem_single" },
+					{ "type": "minecraft:directory", "source": "block/gems", "prefix": "gems/" },
+					{ "type": "directory", "source": "block", "prefix": "" },
+					{
+						"type": "paletted_permutations",
+						"textures": ["mypack:item/gem_trim"],
+						"palette_key": "mypack:item/gem_palette",
+						"permutations": { "ruby": "mypack:item/ruby_palette" }
+					}
+				]
+			}"#
+		),
+		(
+			"assets/minecraft/optifine/cit/ruby.properties",
+			"# Comments are ignored: texture=unused\n\
+			type=item\n\
+			texture=../textures/item/cit_ruby.png\n\
+			texture.bow_pulling_0=mypack:item/cit_bow\n\
+			model=cit_model"
+		),
+		("assets/mypack/models/item/ruby.json", "{}"),
+		(
+			"assets/mypack/models/block/cit_model.json",
+			r#"{ "textures": { "all": "mypack:block/cit_model_texture" } }"#
+		),
+		("assets/mypack/models/block/unused.json", "{}"),
+		("assets/mypack/textures/block/gem_single.png", ""),
+		("assets/mypack/textures/block/gems/emerald.png", ""),
+		("assets/mypack/textures/block/cit_model_texture.png", ""),
+		("assets/mypack/textures/block/unused.png", ""),
+		("assets/mypack/textures/item/gem_trim.png", ""),
+		("assets/mypack/textures/item/gem_palette.png", ""),
+		("assets/mypack/textures/item/ruby_palette.png", ""),
+		("assets/mypack/textures/item/cit_ruby.png", ""),
+		("assets/mypack/textures/item/cit_bow.png", ""),
+		("assets/mypack/textures/item/unused.png", "")
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let unreferenced_assets = tokio_test::block_on(find_unreferenced_assets(
+		&OsFilesystem,
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		&GlobSetBuilder::new().build().unwrap()
+	));
+
+	assert_eq!(
+		unreferenced_assets
+			.iter()
+			.map(RelativePath::as_str)
+			.sorted_unstable()
+			.collect::<Vec<_>>(),
+		[
+			"assets/mypack/models/block/unused.json",
+			"assets/mypack/textures/block/unused.png",
+			"assets/mypack/textures/item/unused.png"
+		]
+	);
+}
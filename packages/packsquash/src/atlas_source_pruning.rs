@@ -0,0 +1,329 @@
+//! Contains a virtual file system adapter that validates the sources of the texture atlas
+//! configuration files of a pack, and may prune the sources that match no texture.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf},
+	time::SystemTime
+};
+
+use ahash::{AHashMap, AHashSet};
+use bytes::Bytes;
+use serde_json::Value;
+use tokio_util::either::Either;
+
+use crate::RelativePath;
+use crate::config::UnmatchedAtlasSourceHandling;
+use crate::pack_file::{blank_out_relaxed_syntax, strip_utf8_bom};
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The namespace of vanilla assets, which is also the namespace of resource locations that do
+/// not specify any.
+const VANILLA_NAMESPACE: &str = "minecraft";
+
+/// The top-level folders of the textures of the vanilla namespace in the Minecraft versions
+/// that have atlas configuration files. As `directory` sources match textures in any namespace,
+/// the textures of the game may match the sources of these folders and their subfolders.
+const VANILLA_TEXTURE_FOLDERS: [&str; 14] = [
+	"block",
+	"colormap",
+	"effect",
+	"entity",
+	"environment",
+	"font",
+	"gui",
+	"item",
+	"map",
+	"misc",
+	"mob_effect",
+	"painting",
+	"particle",
+	"trims"
+];
+
+/// An atlas configuration file whose unmatched sources were pruned by [`AtlasSourcePruningVfs`].
+struct PrunedAtlas {
+	data: Bytes,
+	modification_time: Option<SystemTime>
+}
+
+/// The result of checking a source of an atlas configuration file against the textures of a
+/// pack.
+#[derive(Default)]
+struct SourceValidation {
+	/// Descriptions of the problems found, which are meant to follow a description of the
+	/// source.
+	mismatches: Vec<String>,
+	/// Whether the source matches no texture, neither of the pack nor of the game, and thus
+	/// can be removed without changing the atlas.
+	prunable: bool
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, checking that the sources of the texture
+/// atlas configuration files of the pack match textures that exist, and transparently removing
+/// the sources that match no texture if requested. Minecraft stitches atlases when resources
+/// are loaded, and textures that a model or other asset expects in an atlas, but that no source
+/// added to it, are rendered as the missing texture, while only reporting it in its log.
+///
+/// The pruned atlas configuration files are computed eagerly by [`Self::process_atlases`] and
+/// kept in memory, because the [`VirtualFileSystem::open`] method is synchronous. Other files
+/// are read from the wrapped file system as-is.
+pub struct AtlasSourcePruningVfs<V: VirtualFileSystem> {
+	inner: V,
+	pruned_atlases: AHashMap<PathBuf, PrunedAtlas>
+}
+
+impl<V: VirtualFileSystem> AtlasSourcePruningVfs<V> {
+	/// Wraps the specified virtual file system. No atlas configuration files will be changed
+	/// until [`Self::process_atlases`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			pruned_atlases: AHashMap::new()
+		}
+	}
+
+	/// Scans the pack at the specified root path for atlas configuration files, checking their
+	/// sources against the textures of the pack according to the specified handling:
+	///
+	/// - `single` and `unstitch` sources must reference a texture in the pack.
+	/// - `directory` sources must match some texture in the pack, in any namespace, unless
+	///   they refer to a folder of the vanilla textures.
+	/// - `paletted_permutations` sources must reference base textures, a palette key and
+	///   permutation palettes in the pack.
+	///
+	/// Textures of the vanilla namespace are not checked, because they may be provided by the
+	/// game. `directory` sources are never pruned, as the textures of the game and other packs
+	/// may match them. The returned vector contains the paths of the atlas configuration files
+	/// with mismatches and their descriptions, sorted by path, except for the mismatches of
+	/// the sources that were pruned. Files that can't be read or parsed are left as-is, so that
+	/// the usual pack file processing reports any relevant error.
+	///
+	/// References:
+	/// - <https://minecraft.wiki/w/Resource_pack#Atlases>
+	/// - Minecraft class `net.minecraft.client.renderer.texture.atlas.SpriteSources`
+	pub async fn process_atlases(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions,
+		unmatched_atlas_source_handling: UnmatchedAtlasSourceHandling
+	) -> Vec<(RelativePath<'static>, String)> {
+		let mut textures = AHashSet::new();
+		let mut atlas_files = vec![];
+		for VfsPackFileIterEntry {
+			relative_path,
+			file_path
+		} in self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+		{
+			let Some((namespace, asset_path)) = relative_path
+				.as_str()
+				.strip_prefix("assets/")
+				.and_then(|path| path.split_once('/'))
+			else {
+				continue;
+			};
+
+			if let Some(texture_path) = asset_path
+				.strip_prefix("textures/")
+				.and_then(|path| path.strip_suffix(".png"))
+			{
+				textures.insert((namespace.to_string(), texture_path.to_string()));
+			} else if asset_path.starts_with("atlases/")
+				&& (asset_path.ends_with(".json") || asset_path.ends_with(".jsonc"))
+			{
+				atlas_files.push((relative_path, file_path));
+			}
+		}
+		atlas_files.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+		let mut mismatches = vec![];
+		for (relative_path, file_path) in atlas_files {
+			let Some((mut atlas, modification_time)) = self.read_json(&file_path).await else {
+				continue;
+			};
+			let Some(sources) = atlas.get_mut("sources").and_then(Value::as_array_mut) else {
+				continue;
+			};
+
+			let mut source_index = 0;
+			let source_count = sources.len();
+			sources.retain(|source| {
+				let source_type = source.get("type").and_then(Value::as_str).unwrap_or("?");
+				let source_type = source_type
+					.strip_prefix("minecraft:")
+					.unwrap_or(source_type);
+				let source_description = format!("source {} ({source_type})", source_index + 1);
+				source_index += 1;
+
+				let source_validation = validate_source(source, source_type, &textures);
+				let prune = source_validation.prunable
+					&& unmatched_atlas_source_handling == UnmatchedAtlasSourceHandling::Prune;
+
+				if !prune {
+					mismatches.extend(source_validation.mismatches.into_iter().map(|mismatch| {
+						(
+							relative_path.clone(),
+							format!("{source_description} {mismatch}")
+						)
+					}));
+				}
+
+				!prune
+			});
+
+			if sources.len() != source_count {
+				self.pruned_atlases.insert(
+					file_path,
+					PrunedAtlas {
+						data: serde_json::to_vec(&atlas)
+							.expect("JSON values are assumed to be serializable")
+							.into(),
+						modification_time
+					}
+				);
+			}
+		}
+
+		mismatches
+	}
+
+	/// Reads and parses the JSON file at the specified path of the wrapped file system,
+	/// allowing comments and trailing commas like the usual JSON file processing does, and
+	/// returning `None` if some I/O or parsing error occurs.
+	async fn read_json(&self, path: &Path) -> Option<(Value, Option<SystemTime>)> {
+		let (mut data, modification_time) = read_file(&self.inner, path).await.ok()?;
+
+		let bom_length = data.len() - strip_utf8_bom(&data).len();
+		let json = &mut data[bom_length..];
+		blank_out_relaxed_syntax(json).ok()?;
+
+		Some((serde_json::from_slice(json).ok()?, modification_time))
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for AtlasSourcePruningVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = V::FileIter;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.pruned_atlases
+				.get(path.as_ref())
+				.map(|pruned_atlas| (&pruned_atlas.data, pruned_atlas.modification_time))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
+
+/// Checks the specified atlas source of the specified type against a set of the namespaces
+/// and paths of the textures of a pack.
+fn validate_source(
+	source: &Value,
+	source_type: &str,
+	textures: &AHashSet<(String, String)>
+) -> SourceValidation {
+	// Returns the relative path of the texture with the specified resource location if it is
+	// not available, neither in the pack nor in the game
+	let missing_texture = |resource_location: &str| {
+		let (namespace, path) = resource_location
+			.split_once(':')
+			.unwrap_or((VANILLA_NAMESPACE, resource_location));
+
+		(namespace != VANILLA_NAMESPACE
+			&& !textures.contains(&(namespace.to_string(), path.to_string())))
+		.then(|| format!("assets/{namespace}/textures/{path}.png"))
+	};
+	let string_values = |key: &str| -> Vec<&str> {
+		match source.get(key) {
+			Some(Value::String(value)) => vec![value],
+			Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+			Some(Value::Object(values)) => values.values().filter_map(Value::as_str).collect(),
+			_ => vec![]
+		}
+	};
+
+	let mut source_validation = SourceValidation::default();
+	match source_type {
+		"single" | "unstitch" => {
+			if let Some(texture_path) = string_values("resource")
+				.into_iter()
+				.find_map(missing_texture)
+			{
+				source_validation.mismatches.push(format!(
+					"uses the texture {texture_path}, which is not in the pack"
+				));
+				source_validation.prunable = true;
+			}
+		}
+		"directory" => {
+			if let Some(directory) = string_values("source").first() {
+				let directory = directory.trim_end_matches('/');
+				let directory_prefix = format!("{directory}/");
+				let top_level_folder = directory.split('/').next().unwrap_or(directory);
+
+				// The game may provide textures in the vanilla namespace that match the source
+				if !VANILLA_TEXTURE_FOLDERS.contains(&top_level_folder)
+					&& !textures
+						.iter()
+						.any(|(_, path)| path.starts_with(&directory_prefix))
+				{
+					source_validation.mismatches.push(format!(
+						"matches no texture of the pack in the {directory} folder"
+					));
+				}
+			}
+		}
+		"paletted_permutations" => {
+			let base_textures = string_values("textures");
+			let missing_base_textures = base_textures
+				.iter()
+				.filter_map(|texture| missing_texture(texture))
+				.collect::<Vec<_>>();
+			let missing_palettes = string_values("palette_key")
+				.into_iter()
+				.chain(string_values("permutations"))
+				.filter_map(missing_texture);
+
+			for texture_path in missing_base_textures
+				.iter()
+				.cloned()
+				.chain(missing_palettes)
+			{
+				source_validation.mismatches.push(format!(
+					"uses the texture {texture_path}, which is not in the pack"
+				));
+			}
+
+			// The palettes are only used to generate variants of the base textures
+			source_validation.prunable =
+				!base_textures.is_empty() && missing_base_textures.len() == base_textures.len();
+		}
+		_ => {}
+	}
+
+	source_validation
+}
@@ -0,0 +1,140 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+use tokio::io::AsyncReadExt;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[test]
+fn atlas_sources_are_validated_and_pruned() {
+	let root_dir = Builder::new()
+		.prefix("ps-atlas-source-pruning-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"assets/minecraft/atlases/blocks.json",
+			r#"{
+				// Comments are allowed, like in the usual JSON processing
+				"sources": [
+					{ "type": "minecraft:directory", "source": "mypack_block", "prefix": "mypack_block/" },
+					{ "type": "minecraft:directory", "source": "missing", "prefix": "missing/" },
+					{ "type": "minecraft:single", "resource": "mypack:misc/glint" },
+					{ "type": "minecraft:single", "resource": "mypack:misc/missing" },
+					{ "type": "minecraft:single", "resource": "misc/enchanted_glint_item" },
+					{
+						"type": "minecraft:paletted_permutations",
+						"textures": ["mypack:trims/missing"],
+						"palette_key": "trims/color_palettes/trim_palette",
+						"permutations": { "gold": "trims/color_palettes/gold" }
+					},
+					{
+						"type": "minecraft:paletted_permutations",
+						"textures": ["mypack:misc/glint"],
+						"palette_key": "mypack:palettes/missing",
+						"permutations": {}
+					},
+					{ "type": "minecraft:directory", "source": "entity/signs", "prefix": "entity/signs/" }
+				]
+			}"#
+		),
+		("assets/mypack/textures/mypack_block/ore.png", ""),
+		("assets/mypack/textures/misc/glint.png", "")
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	// Only report mismatches at first
+	let mut vfs = AtlasSourcePruningVfs::new(OsFilesystem);
+	let mismatches = tokio_test::block_on(vfs.process_atlases(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		UnmatchedAtlasSourceHandling::Warn
+	));
+
+	assert_eq!(
+		mismatches
+			.iter()
+			.map(|(relative_path, mismatch)| (relative_path.as_str(), mismatch.as_str()))
+			.collect::<Vec<_>>(),
+		[
+			(
+				"assets/minecraft/atlases/blocks.json",
+				"source 2 (directory) matches no texture of the pack in the missing folder"
+			),
+			(
+				"assets/minecraft/atlases/blocks.json",
+				"source 4 (single) uses the texture assets/mypack/textures/misc/missing.png, which \
+				is not in the pack"
+			),
+			(
+				"assets/minecraft/atlases/blocks.json",
+				"source 6 (paletted_permutations) uses the texture \
+				assets/mypack/textures/trims/missing.png, which is not in the pack"
+			),
+			(
+				"assets/minecraft/atlases/blocks.json",
+				"source 7 (paletted_permutations) uses the texture \
+				assets/mypack/textures/palettes/missing.png, which is not in the pack"
+			)
+		]
+	);
+	assert!(
+		vfs.pruned_atlases.is_empty(),
+		"No atlas should be pruned when only reporting mismatches"
+	);
+
+	// Now prune the sources that match nothing
+	let mut vfs = AtlasSourcePruningVfs::new(OsFilesystem);
+	let mismatches = tokio_test::block_on(vfs.process_atlases(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		UnmatchedAtlasSourceHandling::Prune
+	));
+
+	assert_eq!(
+		mismatches
+			.iter()
+			.map(|(_, mismatch)| mismatch.as_str())
+			.collect::<Vec<_>>(),
+		[
+			"source 2 (directory) matches no texture of the pack in the missing folder",
+			"source 7 (paletted_permutations) uses the texture \
+			assets/mypack/textures/palettes/missing.png, which is not in the pack"
+		]
+	);
+
+	let mut pruned_atlas = vec![];
+	tokio_test::block_on(
+		vfs.open(root_dir.path().join("assets/minecraft/atlases/blocks.json"))
+			.expect("The pruned atlas should be readable")
+			.file_read
+			.read_to_end(&mut pruned_atlas)
+	)
+	.expect("The pruned atlas should be readable");
+
+	let pruned_atlas = serde_json::from_slice::<Value>(&pruned_atlas)
+		.expect("The pruned atlas should be valid JSON");
+	assert_eq!(
+		pruned_atlas["sources"]
+			.as_array()
+			.expect("The pruned atlas should have sources")
+			.iter()
+			.map(|source| source["type"].as_str().unwrap())
+			.collect::<Vec<_>>(),
+		[
+			"minecraft:directory",
+			"minecraft:directory",
+			"minecraft:single",
+			"minecraft:single",
+			"minecraft:paletted_permutations",
+			"minecraft:directory"
+		]
+	);
+}
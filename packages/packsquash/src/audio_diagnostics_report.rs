@@ -0,0 +1,144 @@
+//! Contains the data structures and routines used to generate reports that flag level issues
+//! of the sounds of a pack, such as clipping, DC offset and excessive peak levels, so that
+//! they can be fixed in their source material.
+
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::RelativePath;
+use crate::pack_file::AudioDiagnostics;
+
+#[cfg(test)]
+mod tests;
+
+/// The true peak level above which sounds are flagged for having an excessive peak level, in
+/// dBTP. Lossy encoding usually raises peaks, so sounds above this level are likely to clip
+/// once transcoded.
+const EXCESSIVE_TRUE_PEAK_LEVEL: f64 = -1.0;
+/// The DC offset above which sounds are flagged for having it, relative to full scale. This
+/// corresponds to -40 dBFS.
+const EXCESSIVE_DC_OFFSET: f64 = 0.01;
+
+/// A level issue of a sound that is flagged in an [`AudioDiagnosticsReport`].
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioIssue {
+	/// The sound could not be decoded, so it could not be analyzed.
+	Undecodable,
+	/// Some samples of the sound are likely clipped.
+	Clipping,
+	/// The mean sample value of some channel of the sound deviates noticeably from zero,
+	/// which wastes headroom and may cause clicks when the sound starts or stops playing.
+	DcOffset,
+	/// The true peak level of the sound is so high that it is likely to clip once transcoded.
+	ExcessivePeakLevel
+}
+
+/// The level diagnostics of a single sound.
+#[derive(Serialize, Clone, Debug)]
+#[non_exhaustive]
+pub struct AudioReportEntry {
+	/// The relative path of the sound in the pack.
+	pub path: String,
+	/// The gated integrated loudness of the sound, in LUFS. It is `None` if the sound could
+	/// not be decoded or is silent.
+	pub integrated_loudness: Option<f64>,
+	/// The estimated true peak level of the sound, in dBTP. It is `None` if the sound could
+	/// not be decoded or is silent.
+	pub true_peak: Option<f64>,
+	/// The number of likely clipped samples of the sound. It is `None` if the sound could
+	/// not be decoded.
+	pub clipped_sample_count: Option<u64>,
+	/// The largest absolute mean sample value of any channel of the sound, relative to full
+	/// scale. It is `None` if the sound could not be decoded.
+	pub dc_offset: Option<f64>,
+	/// The level issues flagged for the sound, if any.
+	pub issues: Vec<AudioIssue>
+}
+
+/// A report with level diagnostics for every sound of a pack, which is collected while
+/// pack files are processed.
+#[derive(Default)]
+pub struct AudioDiagnosticsReport {
+	entries: Mutex<Vec<AudioReportEntry>>
+}
+
+/// The JSON representation of a finished [`AudioDiagnosticsReport`].
+#[derive(Serialize)]
+struct AudioDiagnosticsReportSummary<'entries> {
+	/// The number of sounds in the report.
+	sound_count: usize,
+	/// The number of sounds with some flagged issue.
+	sounds_with_issues_count: usize,
+	/// The report entry of every sound, sorted by path.
+	sounds: &'entries [AudioReportEntry]
+}
+
+impl AudioDiagnosticsReport {
+	/// Adds an entry to this report for the sound at the specified path, with the specified
+	/// diagnostics of its input data, which are `None` if it could not be decoded.
+	pub fn add_sound(&self, path: &RelativePath<'_>, diagnostics: Option<AudioDiagnostics>) {
+		let entry = match diagnostics {
+			Some(diagnostics) => {
+				let true_peak = diagnostics
+					.true_peak
+					.is_finite()
+					.then_some(diagnostics.true_peak);
+
+				let mut issues = vec![];
+				if diagnostics.clipped_sample_count > 0 {
+					issues.push(AudioIssue::Clipping);
+				}
+				if diagnostics.dc_offset > EXCESSIVE_DC_OFFSET {
+					issues.push(AudioIssue::DcOffset);
+				}
+				if true_peak.is_some_and(|true_peak| true_peak > EXCESSIVE_TRUE_PEAK_LEVEL) {
+					issues.push(AudioIssue::ExcessivePeakLevel);
+				}
+
+				AudioReportEntry {
+					path: path.as_str().to_string(),
+					integrated_loudness: diagnostics.integrated_loudness,
+					true_peak,
+					clipped_sample_count: Some(diagnostics.clipped_sample_count),
+					dc_offset: Some(diagnostics.dc_offset),
+					issues
+				}
+			}
+			None => AudioReportEntry {
+				path: path.as_str().to_string(),
+				integrated_loudness: None,
+				true_peak: None,
+				clipped_sample_count: None,
+				dc_offset: None,
+				issues: vec![AudioIssue::Undecodable]
+			}
+		};
+
+		self.entries.lock().unwrap().push(entry);
+	}
+
+	/// Writes this report to a file at the specified path in JSON format, consuming it.
+	pub async fn write(self, file_path: &Path) -> Result<(), io::Error> {
+		let mut entries = self.entries.into_inner().unwrap();
+		entries.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+		let summary = AudioDiagnosticsReportSummary {
+			sound_count: entries.len(),
+			sounds_with_issues_count: entries
+				.iter()
+				.filter(|entry| !entry.issues.is_empty())
+				.count(),
+			sounds: &entries
+		};
+
+		tokio::fs::write(
+			file_path,
+			serde_json::to_vec_pretty(&summary).map_err(io::Error::from)?
+		)
+		.await
+	}
+}
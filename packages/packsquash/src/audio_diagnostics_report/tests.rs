@@ -0,0 +1,70 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+#[test]
+fn sound_issues_are_flagged() {
+	let report = AudioDiagnosticsReport::default();
+	report.add_sound(
+		&RelativePath::from_inner("assets/example/sounds/clean.ogg"),
+		Some(AudioDiagnostics {
+			integrated_loudness: Some(-18.0),
+			true_peak: -3.0,
+			clipped_sample_count: 0,
+			dc_offset: 0.0001
+		})
+	);
+	report.add_sound(
+		&RelativePath::from_inner("assets/example/sounds/loud.wav"),
+		Some(AudioDiagnostics {
+			integrated_loudness: Some(-6.0),
+			true_peak: 0.5,
+			clipped_sample_count: 42,
+			dc_offset: 0.05
+		})
+	);
+	report.add_sound(
+		&RelativePath::from_inner("assets/example/sounds/silent.ogg"),
+		Some(AudioDiagnostics {
+			integrated_loudness: None,
+			true_peak: f64::NEG_INFINITY,
+			clipped_sample_count: 0,
+			dc_offset: 0.0
+		})
+	);
+	report.add_sound(
+		&RelativePath::from_inner("assets/example/sounds/corrupt.ogg"),
+		None
+	);
+
+	let mut entries = report.entries.into_inner().unwrap();
+	entries.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+	assert_eq!(
+		entries
+			.iter()
+			.map(|entry| (entry.path.as_str(), entry.issues.as_slice()))
+			.collect::<Vec<_>>(),
+		[
+			("assets/example/sounds/clean.ogg", [].as_slice()),
+			(
+				"assets/example/sounds/corrupt.ogg",
+				[AudioIssue::Undecodable].as_slice()
+			),
+			(
+				"assets/example/sounds/loud.wav",
+				[
+					AudioIssue::Clipping,
+					AudioIssue::DcOffset,
+					AudioIssue::ExcessivePeakLevel
+				]
+				.as_slice()
+			),
+			("assets/example/sounds/silent.ogg", [].as_slice())
+		]
+	);
+	assert_eq!(
+		entries[3].true_peak, None,
+		"Silent sounds should not have a true peak level"
+	);
+}
@@ -0,0 +1,325 @@
+//! Contains a virtual file system adapter and ZIP entry name transformations that convert a
+//! Java Edition resource pack to a Bedrock Edition resource pack, which can be distributed as
+//! a `.mcpack` file.
+
+use std::{
+	borrow::Cow,
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf},
+	time::SystemTime
+};
+
+use ahash::AHashMap;
+use bytes::Bytes;
+use serde_json::{Map, Value, json};
+use sha2::{Digest, Sha256};
+use tokio_util::either::Either;
+use uuid::Builder;
+
+use crate::pack_file::{blank_out_relaxed_syntax, strip_utf8_bom};
+use crate::squash_zip::{EntryNameRegex, ZipEntryNameTransformation};
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VirtualFileSystem, open_in_memory_or_inner, read_file
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The namespace of vanilla assets, which is also the namespace of sound names that do not
+/// specify any.
+const VANILLA_NAMESPACE: &str = "minecraft";
+
+/// The regular expressions and replacements that move the files of a Java Edition resource
+/// pack to the paths Bedrock Edition reads equivalent files from, in application order. Only
+/// the files whose paths can be mechanically converted are moved; the rest are left as-is,
+/// and Bedrock Edition ignores them.
+///
+/// References:
+/// - <https://learn.microsoft.com/en-us/minecraft/creator/documents/resourcepack>
+/// - <https://wiki.bedrock.dev/concepts/sounds>
+const ENTRY_NAME_REPLACEMENTS: &[(&str, &str)] = &[
+	(r"^pack\.mcmeta$", "manifest.json"),
+	(r"^pack\.png$", "pack_icon.png"),
+	(
+		r"^assets/minecraft/sounds\.json$",
+		"sounds/sound_definitions.json"
+	),
+	(r"^assets/minecraft/sounds/", "sounds/"),
+	(r"^assets/minecraft/textures/block/", "textures/blocks/"),
+	(r"^assets/minecraft/textures/item/", "textures/items/"),
+	(r"^assets/minecraft/textures/", "textures/")
+];
+
+/// Returns the ZIP entry name transformations that move the files of a Java Edition resource
+/// pack to the paths Bedrock Edition reads equivalent files from. These transformations are
+/// meant to be applied after the ones configured by the user.
+pub fn bedrock_entry_name_transformations() -> impl Iterator<Item = ZipEntryNameTransformation> {
+	ENTRY_NAME_REPLACEMENTS
+		.iter()
+		.map(
+			|(pattern, replacement)| ZipEntryNameTransformation::RegexReplace {
+				pattern: EntryNameRegex::try_from(Cow::Borrowed(*pattern))
+					.expect("Hardcoded regular expressions are valid"),
+				replacement: replacement.to_string()
+			}
+		)
+}
+
+/// A file generated by [`BedrockConvertingVfs`].
+struct GeneratedFile {
+	data: Bytes,
+	modification_time: Option<SystemTime>
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently replacing the pack metadata
+/// file with a Bedrock Edition `manifest.json` file, and the vanilla `sounds.json` file with a
+/// Bedrock Edition `sound_definitions.json` file. The replaced files keep their path, so that
+/// they are processed as usual, and [`bedrock_entry_name_transformations`] moves them to their
+/// Bedrock Edition path in the output ZIP file.
+///
+/// The generated files are computed eagerly by [`Self::convert_pack`] and kept in memory,
+/// because the [`VirtualFileSystem::open`] method is synchronous. Other files are read from
+/// the wrapped file system as-is.
+pub struct BedrockConvertingVfs<V: VirtualFileSystem> {
+	inner: V,
+	generated_files: AHashMap<PathBuf, GeneratedFile>
+}
+
+impl<V: VirtualFileSystem> BedrockConvertingVfs<V> {
+	/// Wraps the specified virtual file system. No files will be converted until
+	/// [`Self::convert_pack`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			generated_files: AHashMap::new()
+		}
+	}
+
+	/// Converts the pack metadata file and the vanilla `sounds.json` file of the pack at the
+	/// specified root path to their Bedrock Edition equivalents. The manifest is named after
+	/// the pack folder, and its UUIDs are derived from that name, so that Bedrock Edition
+	/// treats the packs generated from the same folder as versions of the same pack. Files
+	/// that can't be read or parsed are left as-is, so that the usual pack file processing
+	/// reports any relevant error.
+	///
+	/// References:
+	/// - <https://learn.microsoft.com/en-us/minecraft/creator/reference/content/addonsreference/packmanifest>
+	/// - <https://minecraft.wiki/w/Sounds.json>
+	pub async fn convert_pack(&mut self, root_path: &Path) {
+		// Paths such as "." have no file name until they are resolved
+		let pack_name = tokio::fs::canonicalize(root_path)
+			.await
+			.as_deref()
+			.unwrap_or(root_path)
+			.file_name()
+			.map_or(Cow::Borrowed("Resource pack"), |pack_name| {
+				pack_name.to_string_lossy()
+			})
+			.into_owned();
+
+		// Prefer the same pack metadata file as the usual pack metadata validation
+		let mut pack_meta_path = root_path.join("pack.mcmetac");
+		let mut pack_meta = self.read_json(&pack_meta_path).await;
+		if pack_meta.is_none() {
+			pack_meta_path = root_path.join("pack.mcmeta");
+			pack_meta = self.read_json(&pack_meta_path).await;
+		}
+		if let Some((pack_meta, modification_time)) = pack_meta {
+			self.generated_files.insert(
+				pack_meta_path,
+				GeneratedFile {
+					data: serde_json::to_vec(&manifest(&pack_meta, &pack_name))
+						.unwrap()
+						.into(),
+					modification_time
+				}
+			);
+		}
+
+		let sounds_json_path = root_path
+			.join("assets")
+			.join(VANILLA_NAMESPACE)
+			.join("sounds.json");
+		if let Some((sounds_json, modification_time)) = self.read_json(&sounds_json_path).await {
+			self.generated_files.insert(
+				sounds_json_path,
+				GeneratedFile {
+					data: serde_json::to_vec(&sound_definitions(&sounds_json))
+						.unwrap()
+						.into(),
+					modification_time
+				}
+			);
+		}
+	}
+
+	/// Reads and parses the JSON object in the file at the specified path of the wrapped file
+	/// system, returning `None` if some I/O error occurs or it is not a valid JSON object.
+	/// Comments and trailing commas are allowed, like in the usual JSON processing.
+	async fn read_json(&self, path: &Path) -> Option<(Map<String, Value>, Option<SystemTime>)> {
+		let (mut data, modification_time) = read_file(&self.inner, path).await.ok()?;
+
+		let bom_length = data.len() - strip_utf8_bom(&data).len();
+		let json = &mut data[bom_length..];
+		blank_out_relaxed_syntax(json).ok()?;
+
+		Some((serde_json::from_slice(json).ok()?, modification_time))
+	}
+}
+
+/// Generates a Bedrock Edition `manifest.json` file for a pack with the specified parsed pack
+/// metadata file and name. The description of the pack is converted to plain text.
+fn manifest(pack_meta: &Map<String, Value>, pack_name: &str) -> Value {
+	let description = pack_meta
+		.get("pack")
+		.and_then(|pack| pack.get("description"))
+		.map_or_else(String::new, plain_text);
+
+	json!({
+		"format_version": 2,
+		"header": {
+			"name": pack_name,
+			"description": description,
+			"uuid": name_based_uuid(pack_name, "header"),
+			"version": [1, 0, 0],
+			"min_engine_version": [1, 16, 0]
+		},
+		"modules": [{
+			"type": "resources",
+			"uuid": name_based_uuid(pack_name, "resources"),
+			"version": [1, 0, 0]
+		}]
+	})
+}
+
+/// Derives a UUID from the specified pack name and manifest UUID purpose, which is always the
+/// same for the same inputs.
+fn name_based_uuid(pack_name: &str, purpose: &str) -> String {
+	let digest = Sha256::new()
+		.chain_update(pack_name)
+		.chain_update([0])
+		.chain_update(purpose)
+		.finalize();
+
+	Builder::from_custom_bytes(digest[..16].try_into().unwrap())
+		.into_uuid()
+		.to_string()
+}
+
+/// Converts the specified Java Edition text component to plain text, discarding its
+/// formatting and any content that can't be represented as plain text.
+fn plain_text(text_component: &Value) -> String {
+	match text_component {
+		Value::String(text) => text.clone(),
+		Value::Array(text_components) => text_components.iter().map(plain_text).collect(),
+		Value::Object(text_component) => {
+			let mut text = text_component
+				.get("text")
+				.map_or_else(String::new, plain_text);
+			if let Some(extra) = text_component.get("extra") {
+				text.push_str(&plain_text(extra));
+			}
+			text
+		}
+		Value::Number(number) => number.to_string(),
+		Value::Bool(boolean) => boolean.to_string(),
+		Value::Null => String::new()
+	}
+}
+
+/// Converts the specified parsed Java Edition `sounds.json` file to a Bedrock Edition
+/// `sound_definitions.json` file. The volume, pitch, weight and streaming properties of each
+/// sound are kept, while sounds that refer to other sound events, which have no Bedrock
+/// Edition equivalent, are discarded. Sound names are converted to paths relative to the pack
+/// root, taking into account that vanilla sounds are moved to the `sounds` folder.
+fn sound_definitions(sounds_json: &Map<String, Value>) -> Value {
+	let mut sound_definitions = Map::new();
+
+	for (sound_event, sound_event_definition) in sounds_json {
+		let Some(sounds) = sound_event_definition
+			.get("sounds")
+			.and_then(Value::as_array)
+		else {
+			continue;
+		};
+
+		let sounds = sounds.iter().filter_map(bedrock_sound).collect::<Vec<_>>();
+		if !sounds.is_empty() {
+			sound_definitions.insert(sound_event.clone(), json!({ "sounds": sounds }));
+		}
+	}
+
+	json!({
+		"format_version": "1.14.0",
+		"sound_definitions": sound_definitions
+	})
+}
+
+/// Converts a sound of a Java Edition sound event to its Bedrock Edition equivalent, returning
+/// `None` if it refers to another sound event or is malformed.
+fn bedrock_sound(sound: &Value) -> Option<Value> {
+	let (sound_name, sound_properties) = match sound {
+		Value::String(sound_name) => (sound_name.as_str(), None),
+		Value::Object(sound) => {
+			if sound.get("type").and_then(Value::as_str) == Some("event") {
+				return None;
+			}
+
+			(sound.get("name")?.as_str()?, Some(sound))
+		}
+		_ => return None
+	};
+
+	let (namespace, path) = sound_name
+		.split_once(':')
+		.unwrap_or((VANILLA_NAMESPACE, sound_name));
+	let sound_path = if namespace == VANILLA_NAMESPACE {
+		format!("sounds/{path}")
+	} else {
+		format!("assets/{namespace}/sounds/{path}")
+	};
+
+	let mut bedrock_sound = Map::new();
+	bedrock_sound.insert("name".into(), sound_path.into());
+	for property in ["volume", "pitch", "weight", "stream"] {
+		if let Some(value) = sound_properties.and_then(|properties| properties.get(property)) {
+			bedrock_sound.insert(property.into(), value.clone());
+		}
+	}
+
+	// Sounds without properties can be written in the shorter string form
+	Some(if bedrock_sound.len() == 1 {
+		bedrock_sound.remove("name").unwrap()
+	} else {
+		Value::Object(bedrock_sound)
+	})
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for BedrockConvertingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = V::FileIter;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.generated_files
+				.get(path.as_ref())
+				.map(|generated_file| (&generated_file.data, generated_file.modification_time))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
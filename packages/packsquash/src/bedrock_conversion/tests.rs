@@ -0,0 +1,145 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+use tokio::io::AsyncReadExt;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+/// Converts a pack with the specified files, returning the parsed contents of the files at
+/// the specified relative paths after the conversion.
+fn convert_pack(files: &[(&str, &str)], relative_paths: &[&str]) -> Vec<Value> {
+	let root_dir = Builder::new()
+		.prefix("ps-bedrock-conversion-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in files {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mut vfs = BedrockConvertingVfs::new(OsFilesystem);
+	tokio_test::block_on(vfs.convert_pack(root_dir.path()));
+
+	relative_paths
+		.iter()
+		.map(|relative_path| {
+			let mut file = vfs
+				.open(root_dir.path().join(relative_path))
+				.expect("I/O operations are assumed not to fail during tests");
+			let mut data = Vec::new();
+			tokio_test::block_on(file.file_read.read_to_end(&mut data))
+				.expect("I/O operations are assumed not to fail during tests");
+
+			serde_json::from_slice(&data).expect("The converted file should be valid JSON")
+		})
+		.collect()
+}
+
+#[test]
+fn pack_is_converted() {
+	let converted_files = convert_pack(
+		&[
+			(
+				"pack.mcmeta",
+				r#"{
+					// Comments are allowed, like in the usual pack metadata processing
+					"pack": {
+						"pack_format": 46,
+						"description": ["Shiny ", { "text": "blocks", "color": "gold" }]
+					}
+				}"#
+			),
+			(
+				"assets/minecraft/sounds.json",
+				r#"{
+					"block.chest.open": {
+						"subtitle": "subtitles.block.chest.open",
+						"sounds": [
+							"block/chest/open",
+							{ "name": "mypack:chest/creak", "volume": 0.5, "attenuation_distance": 8 },
+							{ "name": "block.barrel.open", "type": "event" }
+						]
+					},
+					"ambient.cave": {
+						"sounds": [{ "name": "block.barrel.open", "type": "event" }]
+					}
+				}"#
+			)
+		],
+		&["pack.mcmeta", "assets/minecraft/sounds.json"]
+	);
+
+	let manifest = &converted_files[0];
+	assert_eq!(manifest["format_version"], json!(2));
+	assert_eq!(manifest["header"]["description"], json!("Shiny blocks"));
+	assert_eq!(manifest["modules"][0]["type"], json!("resources"));
+
+	let header_uuid = manifest["header"]["uuid"].as_str().unwrap();
+	let module_uuid = manifest["modules"][0]["uuid"].as_str().unwrap();
+	assert!(header_uuid.parse::<uuid::Uuid>().is_ok());
+	assert_ne!(header_uuid, module_uuid, "Every UUID should be different");
+
+	assert_eq!(
+		converted_files[1],
+		json!({
+			"format_version": "1.14.0",
+			"sound_definitions": {
+				"block.chest.open": {
+					"sounds": [
+						"sounds/block/chest/open",
+						{ "name": "assets/mypack/sounds/chest/creak", "volume": 0.5 }
+					]
+				}
+			}
+		})
+	);
+}
+
+#[test]
+fn manifest_is_named_after_resolved_pack_folder() {
+	let root_dir = Builder::new()
+		.prefix("ps-bedrock-conversion-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	fs::create_dir(root_dir.path().join("assets"))
+		.expect("I/O operations are assumed not to fail during tests");
+	fs::write(root_dir.path().join("pack.mcmeta"), "{}")
+		.expect("I/O operations are assumed not to fail during tests");
+
+	// This path has no file name, but it refers to the pack folder
+	let root_path = root_dir.path().join("assets/..");
+	let mut vfs = BedrockConvertingVfs::new(OsFilesystem);
+	tokio_test::block_on(vfs.convert_pack(&root_path));
+
+	let mut data = Vec::new();
+	tokio_test::block_on(
+		vfs.open(root_path.join("pack.mcmeta"))
+			.expect("I/O operations are assumed not to fail during tests")
+			.file_read
+			.read_to_end(&mut data)
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+	let manifest = serde_json::from_slice::<Value>(&data).expect("The manifest should be valid JSON");
+
+	assert_eq!(
+		manifest["header"]["name"],
+		root_dir.path().file_name().unwrap().to_str().unwrap()
+	);
+}
+
+#[test]
+fn manifest_uuids_are_stable() {
+	assert_eq!(
+		name_based_uuid("My pack", "header"),
+		name_based_uuid("My pack", "header")
+	);
+	assert_ne!(
+		name_based_uuid("My pack", "header"),
+		name_based_uuid("My other pack", "header")
+	);
+}
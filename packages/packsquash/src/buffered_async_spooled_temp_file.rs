@@ -6,6 +6,10 @@ use std::{
 	fs::File,
 	io::{self, BufRead, BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write},
 	pin::Pin,
+	sync::{
+		Arc,
+		atomic::{AtomicU64, Ordering}
+	},
 	task::{Context, Poll}
 };
 use tempfile::tempfile;
@@ -41,8 +45,26 @@ mod tests;
 // can't accept async I/O. For now, this struct is an acceptable compromise which allows
 // as much async-ready code as is reasonable, so at least it'll make migration easier
 pub(super) enum BufferedAsyncSpooledTempFile {
-	InMemory(usize, Cursor<Vec<u8>>),
-	OnDisk(BufReader<File>, BufWriter<File>)
+	InMemory(usize, Cursor<Vec<u8>>, Option<Arc<TemporaryStorageQuota>>),
+	OnDisk(BufReader<File>, BufWriter<File>, DiskUsage)
+}
+
+/// A limit on the bytes that the [`BufferedAsyncSpooledTempFile`]s that share it may store on
+/// disk altogether. Writes that would exceed the limit fail with a
+/// [`io::ErrorKind::StorageFull`] error.
+pub(super) struct TemporaryStorageQuota {
+	used_bytes: AtomicU64,
+	limit: u64
+}
+
+/// Keeps track of the bytes written to disk by a [`BufferedAsyncSpooledTempFile`] rolled over
+/// to disk, charging them to its temporary storage quota, if any, before writing them. Bytes
+/// that overwrite previous ones are charged again, which keeps the accounting simple at the
+/// cost of overestimating the disk usage of files that are modified in place. The charged
+/// bytes are released when it is dropped.
+pub(super) struct DiskUsage {
+	charged_bytes: u64,
+	quota: Option<Arc<TemporaryStorageQuota>>
 }
 
 impl BufferedAsyncSpooledTempFile {
@@ -57,16 +79,91 @@ impl BufferedAsyncSpooledTempFile {
 	pub fn with_capacity(capacity: usize, size_threshold: usize) -> Self {
 		Self::InMemory(
 			size_threshold,
-			Cursor::new(Vec::with_capacity(cmp::min(capacity, size_threshold)))
+			Cursor::new(Vec::with_capacity(cmp::min(capacity, size_threshold))),
+			None
 		)
 	}
+
+	/// Makes the data this [`BufferedAsyncSpooledTempFile`] stores on disk count towards the
+	/// specified temporary storage quota, if any. This has no effect on files that were
+	/// already rolled over to disk.
+	pub fn with_temporary_storage_quota(
+		mut self,
+		temporary_storage_quota: Option<Arc<TemporaryStorageQuota>>
+	) -> Self {
+		if let Self::InMemory(_, _, quota) = &mut self {
+			*quota = temporary_storage_quota;
+		}
+
+		self
+	}
+}
+
+impl TemporaryStorageQuota {
+	/// Creates a new temporary storage quota that allows storing up to the specified number
+	/// of bytes on disk.
+	pub fn new(limit: u64) -> Self {
+		Self {
+			used_bytes: AtomicU64::new(0),
+			limit
+		}
+	}
+
+	/// Charges the specified number of bytes to this quota, returning an error if that would
+	/// exceed its limit.
+	fn charge(&self, bytes: u64) -> io::Result<()> {
+		self.used_bytes
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used_bytes| {
+				used_bytes
+					.checked_add(bytes)
+					.filter(|&used_bytes| used_bytes <= self.limit)
+			})
+			.map(|_| ())
+			.map_err(|_| {
+				io::Error::new(
+					io::ErrorKind::StorageFull,
+					format!(
+						"The temporary storage limit of {} bytes was exceeded",
+						self.limit
+					)
+				)
+			})
+	}
+}
+
+impl DiskUsage {
+	/// Charges the specified number of bytes to the quota, if any.
+	fn charge(&mut self, bytes: usize) -> io::Result<()> {
+		if let Some(quota) = &self.quota {
+			quota.charge(bytes as u64)?;
+			self.charged_bytes += bytes as u64;
+		}
+
+		Ok(())
+	}
+
+	/// Writes the specified buffer to the specified writer, charging it to the quota first.
+	fn write(&mut self, file_writer: &mut impl Write, buf: &[u8]) -> io::Result<usize> {
+		self.charge(buf.len())?;
+		file_writer.write(buf)
+	}
+}
+
+impl Drop for DiskUsage {
+	fn drop(&mut self) {
+		if let Some(quota) = &self.quota {
+			quota
+				.used_bytes
+				.fetch_sub(self.charged_bytes, Ordering::Relaxed);
+		}
+	}
 }
 
 impl Read for BufferedAsyncSpooledTempFile {
 	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
 		match self {
-			Self::InMemory(_, cursor) => cursor.read(buf),
-			Self::OnDisk(file_reader, _) => file_reader.read(buf)
+			Self::InMemory(_, cursor, _) => cursor.read(buf),
+			Self::OnDisk(file_reader, _, _) => file_reader.read(buf)
 		}
 	}
 }
@@ -74,13 +171,19 @@ impl Read for BufferedAsyncSpooledTempFile {
 impl Write for BufferedAsyncSpooledTempFile {
 	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
 		match self {
-			Self::InMemory(size_threshold, cursor) => {
+			Self::InMemory(size_threshold, cursor, quota) => {
 				let bytes_written = cursor.write(
 					&buf[..cmp::min(buf.len(), *size_threshold - cursor.position() as usize)]
 				)?;
 				let new_cursor_position = cursor.position();
 
 				if new_cursor_position >= *size_threshold as u64 {
+					let mut disk_usage = DiskUsage {
+						charged_bytes: 0,
+						quota: quota.clone()
+					};
+					disk_usage.charge(cursor.get_ref().len())?;
+
 					let mut rolled_file = tempfile()?;
 
 					rolled_file.write_all(cursor.get_ref())?;
@@ -88,7 +191,8 @@ impl Write for BufferedAsyncSpooledTempFile {
 
 					*self = Self::OnDisk(
 						BufReader::new(rolled_file.try_clone()?),
-						BufWriter::new(rolled_file)
+						BufWriter::new(rolled_file),
+						disk_usage
 					);
 				}
 
@@ -103,12 +207,12 @@ impl Write for BufferedAsyncSpooledTempFile {
 					Ok(bytes_written)
 				}
 			}
-			Self::OnDisk(_, file_writer) => file_writer.write(buf)
+			Self::OnDisk(_, file_writer, disk_usage) => disk_usage.write(file_writer, buf)
 		}
 	}
 
 	fn flush(&mut self) -> io::Result<()> {
-		if let Self::OnDisk(_, file_writer) = self {
+		if let Self::OnDisk(_, file_writer, _) = self {
 			file_writer.flush()?;
 		}
 
@@ -119,8 +223,8 @@ impl Write for BufferedAsyncSpooledTempFile {
 impl Seek for BufferedAsyncSpooledTempFile {
 	fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
 		match self {
-			Self::InMemory(_, cursor) => cursor.seek(pos),
-			Self::OnDisk(file_reader, file_writer) => {
+			Self::InMemory(_, cursor, _) => cursor.seek(pos),
+			Self::OnDisk(file_reader, file_writer, _) => {
 				// Flush the write buffer (i.e. empty it)
 				file_writer.flush()?;
 
@@ -137,15 +241,15 @@ impl Seek for BufferedAsyncSpooledTempFile {
 impl BufRead for BufferedAsyncSpooledTempFile {
 	fn fill_buf(&mut self) -> io::Result<&[u8]> {
 		match self {
-			Self::InMemory(_, cursor) => cursor.fill_buf(),
-			Self::OnDisk(file_reader, _) => file_reader.fill_buf()
+			Self::InMemory(_, cursor, _) => cursor.fill_buf(),
+			Self::OnDisk(file_reader, _, _) => file_reader.fill_buf()
 		}
 	}
 
 	fn consume(&mut self, amt: usize) {
 		match self {
-			Self::InMemory(_, cursor) => cursor.consume(amt),
-			Self::OnDisk(file_reader, _) => file_reader.consume(amt)
+			Self::InMemory(_, cursor, _) => cursor.consume(amt),
+			Self::OnDisk(file_reader, _, _) => file_reader.consume(amt)
 		}
 	}
 }
@@ -157,8 +261,8 @@ impl AsyncRead for BufferedAsyncSpooledTempFile {
 		buf: &mut ReadBuf<'_>
 	) -> Poll<io::Result<()>> {
 		match Pin::into_inner(self) {
-			Self::InMemory(_, cursor) => Pin::new(cursor).poll_read(cx, buf),
-			Self::OnDisk(file_reader, _) => {
+			Self::InMemory(_, cursor, _) => Pin::new(cursor).poll_read(cx, buf),
+			Self::OnDisk(file_reader, _, _) => {
 				let read_buf = buf.initialize_unfilled();
 				let remaining_bytes = file_reader.capacity() - file_reader.buffer().len();
 
@@ -185,7 +289,7 @@ impl AsyncWrite for BufferedAsyncSpooledTempFile {
 	) -> Poll<Result<usize, io::Error>> {
 		let this = Pin::into_inner(self);
 		match this {
-			Self::InMemory(size_threshold, cursor) => {
+			Self::InMemory(size_threshold, cursor, _) => {
 				if cursor.position() as usize + buf.len() >= *size_threshold {
 					// Write is very likely to roll to disk (and, in fact, it will,
 					// because Write::write implementation for Cursor<Vec<u8>> always
@@ -195,14 +299,14 @@ impl AsyncWrite for BufferedAsyncSpooledTempFile {
 					Poll::Ready(this.write(buf))
 				}
 			}
-			Self::OnDisk(_, file_writer) => {
+			Self::OnDisk(_, file_writer, disk_usage) => {
 				let remaining_bytes = file_writer.capacity() - file_writer.buffer().len();
 
 				// Avoid blocking if the bytes will go to a memory buffer
 				if buf.len() < remaining_bytes {
-					Poll::Ready(file_writer.write(buf))
+					Poll::Ready(disk_usage.write(file_writer, buf))
 				} else {
-					Poll::Ready(task::block_in_place(|| file_writer.write(buf)))
+					Poll::Ready(task::block_in_place(|| disk_usage.write(file_writer, buf)))
 				}
 			}
 		}
@@ -210,8 +314,10 @@ impl AsyncWrite for BufferedAsyncSpooledTempFile {
 
 	fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
 		match Pin::into_inner(self) {
-			Self::InMemory(_, _) => Poll::Ready(Ok(())),
-			Self::OnDisk(_, file_writer) => Poll::Ready(task::block_in_place(|| file_writer.flush()))
+			Self::InMemory(_, _, _) => Poll::Ready(Ok(())),
+			Self::OnDisk(_, file_writer, _) => {
+				Poll::Ready(task::block_in_place(|| file_writer.flush()))
+			}
 		}
 	}
 
@@ -224,16 +330,16 @@ impl AsyncSeek for BufferedAsyncSpooledTempFile {
 	fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
 		let this = Pin::into_inner(self);
 		match this {
-			Self::InMemory(_, cursor) => cursor.seek(position).map(|_| ()),
-			Self::OnDisk(_, _) => task::block_in_place(|| this.seek(position)).map(|_| ())
+			Self::InMemory(_, cursor, _) => cursor.seek(position).map(|_| ()),
+			Self::OnDisk(_, _, _) => task::block_in_place(|| this.seek(position)).map(|_| ())
 		}
 	}
 
 	fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<u64>> {
 		let this = Pin::into_inner(self);
 		match this {
-			Self::InMemory(_, cursor) => Poll::Ready(Ok(cursor.position())),
-			Self::OnDisk(_, _) => Poll::Ready(task::block_in_place(|| this.stream_position()))
+			Self::InMemory(_, cursor, _) => Poll::Ready(Ok(cursor.position())),
+			Self::OnDisk(_, _, _) => Poll::Ready(task::block_in_place(|| this.stream_position()))
 		}
 	}
 }
@@ -142,10 +142,40 @@ async fn async_io_works() {
 	);
 }
 
+#[test]
+fn temporary_storage_quota_is_enforced() {
+	let quota = Arc::new(TemporaryStorageQuota::new(12));
+	let mut spooled_file =
+		BufferedAsyncSpooledTempFile::new(4).with_temporary_storage_quota(Some(Arc::clone(&quota)));
+
+	Write::write_all(&mut spooled_file, &[0; 8]).expect("The write should fit in the quota");
+
+	assert!(
+		is_rolled(&spooled_file),
+		"Expected file to roll to disk after reaching threshold"
+	);
+
+	assert_eq!(
+		Write::write_all(&mut spooled_file, &[0; 8])
+			.expect_err("The write should exceed the quota")
+			.kind(),
+		io::ErrorKind::StorageFull,
+		"Unexpected error kind for a write that exceeds the quota"
+	);
+
+	// Other files can use the quota once the file that used it is dropped
+	drop(spooled_file);
+
+	let mut spooled_file =
+		BufferedAsyncSpooledTempFile::new(0).with_temporary_storage_quota(Some(quota));
+	Write::write_all(&mut spooled_file, &[0; 12])
+		.expect("The quota should be released when files are dropped");
+}
+
 /// Returns whether a spooled file was written out to disk, which means that any I/O
 /// operation would potentially interact with a disk. Due to the use of buffering,
 /// however, not every I/O operation when rolled over will necessarily imply actual
 /// I/O.
 fn is_rolled(spooled_file: &BufferedAsyncSpooledTempFile) -> bool {
-	matches!(spooled_file, BufferedAsyncSpooledTempFile::OnDisk(_, _))
+	matches!(spooled_file, BufferedAsyncSpooledTempFile::OnDisk(..))
 }
@@ -1,5 +1,6 @@
 //! Contains the configuration options needed to create a `PackSquasher` run.
 
+use std::cmp;
 use std::num::{NonZeroU8, NonZeroU16, NonZeroU32};
 use std::thread::available_parallelism;
 use std::{num::NonZeroUsize, path::PathBuf};
@@ -7,10 +8,28 @@ use std::{num::NonZeroUsize, path::PathBuf};
 use enumset::{EnumSet, EnumSetType};
 use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
 use indexmap::IndexMap;
-use serde::{Deserialize, Serialize};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
 use sysinfo::{MemoryRefreshKind, RefreshKind, System};
 
+use crate::bedrock_conversion::bedrock_entry_name_transformations;
+use crate::file_options_matching::FileOptionsGlobSet;
+use crate::pack_file::AnimationFrameSize;
+pub use crate::pack_file_filtering::{FilterTimestamp, PackFileFilter};
+pub use crate::pack_format_stamping::{
+	MinecraftVersion, MinecraftVersionCondition, MinecraftVersionRange
+};
+pub use crate::squash_zip::{EntryNameRegex, ZipEntryNameTransformation};
 use crate::squash_zip::{SquashZipSettings, ZipArchiveCommentString};
+pub use crate::text_replacement::{TextReplacementRegex, TextReplacementRule};
+pub use options_linting::{OptionsLint, lint_options};
+pub use options_migration::{OptionMigration, migrate_options};
+pub use options_schema::{UnknownOption, find_unknown_options, options_json_schema};
+
+mod options_linting;
+mod options_migration;
+mod options_schema;
 
 /// Contains all the options that configure a `PackSquasher` operation.
 ///
@@ -26,7 +45,9 @@ pub struct SquashOptions {
 	pub global_options: GlobalOptions,
 	/// A map that relates glob patterns that match relative file paths within the
 	/// pack to file options, to further customize how the files that match the
-	/// pattern are processed.
+	/// pattern are processed. Glob patterns prefixed with `!` match the paths that
+	/// the rest of the pattern does not match, and when several patterns match a
+	/// path, the ones that come later take precedence.
 	#[serde(flatten)]
 	pub file_options: IndexMap<String, FileOptions>
 }
@@ -36,22 +57,132 @@ pub struct SquashOptions {
 #[derive(Clone)]
 pub struct ProcessedSquashOptions {
 	pub(super) options: SquashOptions,
-	pub(super) file_options_globs: GlobSet
+	pub(super) file_options_globs: FileOptionsGlobSet,
+	pub(super) pack_file_filter_globs: FileOptionsGlobSet,
+	pub(super) file_type_route_globs: FileOptionsGlobSet,
+	pub(super) stereo_positional_sound_exemption_globs: GlobSet,
+	pub(super) unreferenced_asset_exemption_globs: GlobSet,
+	pub(super) text_replacement_globs: GlobSet,
+	pub(super) split_output_group_globs: Vec<(PathBuf, GlobSet)>
 }
 
 impl TryFrom<SquashOptions> for ProcessedSquashOptions {
 	type Error = globset::Error;
 
-	fn try_from(squash_options: SquashOptions) -> Result<Self, Self::Error> {
+	fn try_from(mut squash_options: SquashOptions) -> Result<Self, Self::Error> {
+		// Add the precompiled shader artifacts to the output ZIP file as-is, unless the user
+		// configured other options for them, which take precedence because they come later
+		let artifact_extensions = &squash_options
+			.global_options
+			.shader_cache
+			.artifact_extensions;
+		if !artifact_extensions.is_empty() {
+			add_default_file_options(
+				&mut squash_options.file_options,
+				format!(
+					"assets/*/shaders/**/?*.{{{}}}",
+					artifact_extensions.join(",")
+				),
+				FileOptions::CustomFileOptions(CustomFileOptions {
+					force_include: true,
+					custom_file_format: CustomFileFormat::Opaque
+				})
+			);
+		}
+
+		// Optimize the resource pack and data pack archives of worlds as nested archives,
+		// unless the user configured other options for them
+		if squash_options.global_options.world_template_packaging {
+			add_default_file_options(
+				&mut squash_options.file_options,
+				"{resources.zip,datapacks/?*.zip}".into(),
+				FileOptions::CustomFileOptions(CustomFileOptions {
+					force_include: true,
+					custom_file_format: CustomFileFormat::ZipArchive
+				})
+			);
+		}
+
+		// Add the metadata files of mods to the output ZIP file, unless the user configured
+		// other options for them
+		if let Some(mod_resources_profile) = squash_options.global_options.mod_resources_profile {
+			for (glob_pattern, custom_file_format) in mod_resources_profile.metadata_files() {
+				add_default_file_options(
+					&mut squash_options.file_options,
+					glob_pattern.to_string(),
+					FileOptions::CustomFileOptions(CustomFileOptions {
+						force_include: true,
+						custom_file_format: *custom_file_format
+					})
+				);
+			}
+		}
+
 		// Build glob patterns to match file paths with their options
-		let mut globset_builder = GlobSetBuilder::new();
-		for glob_pattern in squash_options.file_options.keys() {
-			globset_builder.add(compile_pack_file_glob_pattern(glob_pattern)?);
+		let file_options_globs =
+			FileOptionsGlobSet::new(squash_options.file_options.keys().map(String::as_str))?;
+
+		let pack_file_filter_globs = FileOptionsGlobSet::new(
+			squash_options
+				.global_options
+				.pack_file_filters
+				.keys()
+				.map(String::as_str)
+		)?;
+
+		let file_type_route_globs = FileOptionsGlobSet::new(
+			squash_options
+				.global_options
+				.file_type_routes
+				.keys()
+				.map(String::as_str)
+		)?;
+
+		let mut exemption_globset_builder = GlobSetBuilder::new();
+		for glob_pattern in &squash_options
+			.global_options
+			.stereo_positional_sound_exemptions
+		{
+			exemption_globset_builder.add(compile_pack_file_glob_pattern(glob_pattern)?);
+		}
+
+		let mut unreferenced_asset_exemption_globset_builder = GlobSetBuilder::new();
+		for glob_pattern in &squash_options.global_options.unreferenced_asset_exemptions {
+			unreferenced_asset_exemption_globset_builder
+				.add(compile_pack_file_glob_pattern(glob_pattern)?);
+		}
+
+		let mut text_replacement_globset_builder = GlobSetBuilder::new();
+		for glob_pattern in squash_options.global_options.text_replacement_rules.keys() {
+			text_replacement_globset_builder.add(compile_pack_file_glob_pattern(glob_pattern)?);
+		}
+
+		let mut split_output_group_globs =
+			Vec::with_capacity(squash_options.global_options.split_output_groups.len());
+		for (split_output_file_path, glob_patterns) in
+			&squash_options.global_options.split_output_groups
+		{
+			let mut split_output_group_globset_builder = GlobSetBuilder::new();
+			for glob_pattern in glob_patterns {
+				split_output_group_globset_builder.add(compile_pack_file_glob_pattern(glob_pattern)?);
+			}
+
+			split_output_group_globs.push((
+				split_output_file_path.clone(),
+				split_output_group_globset_builder.build()?
+			));
 		}
 
 		Ok(ProcessedSquashOptions {
 			options: squash_options,
-			file_options_globs: globset_builder.build()?
+			file_options_globs,
+			pack_file_filter_globs,
+			file_type_route_globs,
+			stereo_positional_sound_exemption_globs: exemption_globset_builder.build()?,
+			unreferenced_asset_exemption_globs: unreferenced_asset_exemption_globset_builder
+				.build()?,
+			text_replacement_globs: text_replacement_globset_builder.build()?,
+			split_output_group_globs
 		})
 	}
 }
@@ -85,6 +216,46 @@ pub struct GlobalOptions {
 	///
 	/// **Default value**: `true`
 	pub validate_pack_metadata_file: bool,
+	/// The `[min, max]` range of Minecraft release versions the pack targets, such as
+	/// `["1.20", "1.21.4"]`. When set, the `pack_format` key of `pack.mcmeta` will be rewritten
+	/// to the pack format version of the oldest targeted Minecraft version, and its
+	/// `supported_formats` key to the range of pack format versions of the targeted Minecraft
+	/// versions, so that they do not need to be updated by hand for every game update. Data
+	/// pack format versions are used for packs with a `data` folder and no `assets` folder.
+	/// Other PackSquash features that depend on `pack.mcmeta`, such as automatic Minecraft quirk
+	/// detection, see the rewritten file.
+	///
+	/// **Default value**: none (`pack.mcmeta` is not rewritten)
+	pub target_minecraft_version_range: Option<MinecraftVersionRange>,
+	/// A map of overlay directory names to the `[min, max]` range of Minecraft release versions
+	/// each overlay targets. When not empty and `target_minecraft_version_range` is set, the
+	/// overlay entries of `pack.mcmeta` will be replaced with entries for these directories,
+	/// in the same order, with the range of pack format versions of the Minecraft versions
+	/// they target.
+	///
+	/// **Default value**: empty (overlay entries are not rewritten)
+	pub overlay_minecraft_version_ranges: IndexMap<String, MinecraftVersionRange>,
+	/// A map of variable names to values that will replace `${name}` placeholders in JSON,
+	/// legacy language (`.lang`) and text (`.txt`) pack files, which is useful to inject
+	/// release metadata, such as a version number or a build date, without a separate
+	/// preprocessing step. Values are escaped as JSON string contents in JSON files, where
+	/// placeholders are expected to be in strings. Placeholders of variables not in this map
+	/// are left as-is. Files that contain placeholders are processed again on every squash
+	/// operation, as the values of the variables may change without the files changing.
+	///
+	/// **Default value**: empty (no placeholders are expanded)
+	pub template_variables: IndexMap<String, String>,
+	/// A map of glob patterns that match the relative paths of pack files to lists of
+	/// regular expression replacement rules that will be applied, in order, to the text of
+	/// those files. If a file matches several patterns, the rules of every matching pattern
+	/// are applied, in the order the patterns are listed. This is useful to redact content
+	/// that should not be distributed, such as internal URLs or invite links left in
+	/// development copies of a pack. Files that are not valid UTF-8 are left as-is, and files
+	/// changed by some rule are processed again on every squash operation, as the rules may
+	/// change without the files changing. Rules are applied after placeholders are expanded.
+	///
+	/// **Default value**: empty (no text is replaced)
+	pub text_replacement_rules: IndexMap<String, Vec<TextReplacementRule>>,
 	/// PackSquash uses a custom ZIP compressor that is able to balance ZIP file
 	/// interoperability and specification intent conformance with increased space savings,
 	/// compressibility and protection against external programs being able to extract files
@@ -218,6 +389,29 @@ pub struct GlobalOptions {
 	///
 	/// **Default value**: `true`
 	pub ignore_system_and_hidden_files: bool,
+	/// A map of glob patterns that match the relative paths of pack files to filters that
+	/// decide whether those files are included in the generated ZIP file depending on their
+	/// size and modification time. This is useful to leave out files that are too big to be
+	/// distributed, or to only include the files changed since some date. Like for file
+	/// options, patterns prefixed with `!` match the paths that the rest of the pattern does
+	/// not match, and when several patterns match a path, only the filter of the last one is
+	/// applied, so a filter without conditions can include files that earlier filters would
+	/// exclude. Files that match no pattern are always included.
+	///
+	/// **Default value**: empty (no files are excluded due to their size or modification time)
+	pub pack_file_filters: IndexMap<String, PackFileFilter>,
+	/// A map of glob patterns that match the relative paths of pack files to the built-in file
+	/// type they should be processed as, regardless of their extension or location. This is
+	/// useful for packs that use unconventional naming, such as `.fsh2` fragment shaders, whose
+	/// files would otherwise not be recognized and need to be included as custom files without
+	/// any optimizations. Like for file options, patterns prefixed with `!` match the paths that
+	/// the rest of the pattern does not match, and when several patterns match a path, the last
+	/// one wins. The file options for the file type apply to routed files as usual, and routed
+	/// files get the extension of their file type if it changes their format, such as when
+	/// converting audio files to Ogg Vorbis.
+	///
+	/// **Default value**: empty (pack files are recognized by their extension and location)
+	pub file_type_routes: IndexMap<String, FileTypeRoute>,
 	/// PackSquash supports pack files added by mods, but, in the interest of keeping its output as
 	/// lean as possible by default, you should indicate what mods do you want to support and include
 	/// in the result ZIP file.
@@ -226,14 +420,80 @@ pub struct GlobalOptions {
 	#[cfg(any(feature = "optifine", feature = "mtr3"))]
 	#[doc(cfg(any(feature = "optifine", feature = "mtr3")))]
 	pub allow_mods: EnumSet<MinecraftMod>,
+	/// If set, the pack directory is treated as the resources folder of a mod project for the
+	/// specified mod loader, such as `src/main/resources`, so that mod developers can optimize
+	/// it directly. The mod metadata files of the mod loader are added to the generated ZIP
+	/// file, a missing pack metadata file is tolerated, the pack metadata quirks of the mod
+	/// loader are taken into account, and the keys of the mod loader, such as resource
+	/// conditions, are not reported by JSON schema validation.
+	///
+	/// **Default value**: none (the pack directory is a resource or data pack)
+	pub mod_resources_profile: Option<ModResourcesProfile>,
+	/// The directories of other packs whose files will be merged with the files of the pack in
+	/// the pack directory, as if they were a single pack. This is useful to generate a single
+	/// ZIP file from packs that are maintained separately. When several packs contain a file
+	/// at the same relative path, `pack_merge_conflict_handling` decides which one is kept.
+	///
+	/// **Default value**: empty (only the pack in the pack directory is processed)
+	pub merged_pack_directories: Vec<PathBuf>,
+	/// How to resolve conflicts between files at the same relative path of the packs merged
+	/// according to `merged_pack_directories`. Conflicts are always reported.
+	///
+	/// **Default value**: [PackMergeConflictHandling::FirstWins]
+	pub pack_merge_conflict_handling: PackMergeConflictHandling,
 	/// The output file path where the result ZIP will be written to. This path must not point to a
 	/// folder.
 	///
 	/// Depending on how other options are configured, PackSquash may use this ZIP file, if it exists,
 	/// to reuse its processed data and speed up squash operations.
 	///
+	/// This path is used as-is by the library. Client code can expand `{name}` placeholders in it
+	/// with build metadata before squashing by using [`crate::expand_output_file_path`], like the
+	/// PackSquash CLI does.
+	///
 	/// **Default value**: `pack.zip` (file `pack.zip` in the current working directory)
 	pub output_file_path: PathBuf,
+	/// If `true`, in addition to the output ZIP file, a split output ZIP file will be written for
+	/// every namespace of the pack, next to the output file. Each split output file contains the
+	/// files within the `assets` and `data` folders of its namespace, and is named after the
+	/// output file and the namespace: for example, a `pack.zip` output file with a `lobby`
+	/// namespace gets a `pack-lobby.zip` split output file.
+	///
+	/// Files outside of namespace folders, such as `pack.mcmeta` and `pack.png`, are added to
+	/// every split output file. Split output files reuse the processing work done for the
+	/// output ZIP file, so generating them is cheap. This is useful for server networks that
+	/// deliver modular packs to different game modes.
+	///
+	/// **Default value**: `false`
+	pub split_output_by_namespace: bool,
+	/// A map of file paths to lists of glob patterns. For each entry, a split output ZIP file
+	/// that contains the files of the output ZIP file whose entry names match any of the glob
+	/// patterns will be written to that path. Like with
+	/// [`split_output_by_namespace`](GlobalOptions::split_output_by_namespace), files outside of
+	/// namespace folders are added to every split output file, and the processing work done for
+	/// the output ZIP file is reused.
+	///
+	/// **Default value**: empty (no split output files are written for glob pattern groups)
+	pub split_output_groups: IndexMap<PathBuf, Vec<String>>,
+	/// If `true`, the pack directory is treated as a Minecraft world folder, such as that of a
+	/// map or world template, instead of a pack, and the output ZIP file is a distributable
+	/// version of it. The data packs of its `datapacks` folder and its `resources.zip` resource
+	/// pack are optimized like any other pack, and the chunk data of its region files, its
+	/// `level.dat` file and its `icon.png` icon are recompressed with more effort than
+	/// Minecraft does.
+	///
+	/// **Default value**: `false`
+	pub world_template_packaging: bool,
+	/// If `true`, the pack is converted to a Bedrock Edition resource pack, which can be
+	/// imported by Bedrock Edition when the output ZIP file has a `.mcpack` extension. The pack
+	/// metadata file is converted to a `manifest.json` file with UUIDs derived from the name of
+	/// the pack folder, the vanilla `sounds.json` file is converted to a
+	/// `sound_definitions.json` file, and vanilla textures and sounds are moved to the folders
+	/// Bedrock Edition reads them from. Files that have no mechanical Bedrock Edition
+	/// equivalent are left as-is.
+	///
+	/// **Default value**: `false`
+	pub bedrock_edition_target: bool,
 	/// The number of concurrent threads that PackSquash will use to process the resource pack files.
 	/// Several threads allow processing several files at once, improving speed substantially. PackSquash
 	/// may end up spawning slightly more threads than this for internal reasons.
@@ -251,6 +511,48 @@ pub struct GlobalOptions {
 	///
 	/// **Default value**: `half of the available memory reported by the OS / (number of CPU hardware threads + 1)`
 	pub spooling_buffers_size: usize,
+	/// The maximum amount of memory, **in MiB**, that the PNG files being optimized at the same time
+	/// are estimated to use. Optimizing big PNG files takes a lot of memory, so optimizing many of them
+	/// concurrently in every thread may exhaust the available memory. Before decoding a PNG file, its
+	/// memory usage is estimated from its dimensions, and its optimization waits for others to finish if
+	/// it would make the total estimate exceed this limit. A PNG file is always optimized when no other
+	/// is, even if its estimate exceeds this limit on its own.
+	///
+	/// **Default value**: `half of the available memory reported by the OS`
+	pub png_optimization_memory_limit: usize,
+	/// The maximum number of audio files that will be transcoded at the same time. Transcoding
+	/// audio files takes a lot of memory for long sounds, as they are decoded completely, so
+	/// transcoding many of them concurrently in every thread may exhaust the available memory.
+	/// When this limit is reached, transcoding further audio files waits for others to finish,
+	/// while other pack files keep being processed. If not set, as many audio files as threads
+	/// may be transcoded at the same time.
+	///
+	/// **Default value**: none (as many as [threads](GlobalOptions::threads))
+	pub maximum_concurrent_audio_transcodes: Option<NonZeroUsize>,
+	/// The maximum amount of memory, **in MiB**, that the spooling buffers and the pack files
+	/// being optimized at the same time should use altogether, which is useful to run PackSquash
+	/// on shared build servers without starving other jobs. Half of this budget is split among
+	/// the spooling buffers, capping `spooling_buffers_size`, and the other half bounds the
+	/// estimated memory usage of the PNG and audio files being optimized at the same time,
+	/// capping `png_optimization_memory_limit`. Optimizations wait for others to finish when
+	/// they would exceed the budget, but a pack file is always optimized when no other is, even
+	/// if its estimate exceeds the budget on its own, so this budget is not a hard limit.
+	///
+	/// This budget only covers the memory listed above, not the memory used by the whole squash
+	/// operation. Other memory, such as the files that some preprocessing steps keep in memory
+	/// before pack files are processed, like the files whose placeholders were expanded or whose
+	/// text was replaced, and the memory used by other kinds of pack files, is not accounted for.
+	///
+	/// **Default value**: none (memory usage is only bounded by the options above)
+	pub optimization_memory_budget: Option<usize>,
+	/// The maximum amount of disk space, **in MiB**, that the temporary files spooling buffers
+	/// are rolled over to may take altogether. If processing the pack needs more temporary
+	/// storage, the squash operation fails instead of filling up the disk. Data that overwrites
+	/// previously written temporary data is counted again, so the actual disk usage may be
+	/// somewhat lower than accounted.
+	///
+	/// **Default value**: none (temporary files may take as much disk space as needed)
+	pub temporary_storage_limit: Option<u64>,
 	/// The comment string that will be attached to the output ZIP file, which is displayed by some
 	/// ZIP file manipulation programs when examining the archive. This string is limited to 65535
 	/// US-ASCII characters in size, must not contain some special character sequences that are
@@ -264,7 +566,378 @@ pub struct GlobalOptions {
 	/// important user-facing notices and file tracking metadata.
 	///
 	/// **Default value**: empty string (no comment)
-	pub zip_comment: ZipArchiveCommentString
+	pub zip_comment: ZipArchiveCommentString,
+	/// A list of transformations that will be applied, in order, to the relative path of every
+	/// pack file to get the name it will be stored with in the output ZIP file. Entry names can
+	/// be converted to lowercase, have a prefix stripped, or be rewritten with regular expressions.
+	///
+	/// This is useful to fix packs with inconsistent path casing, which work on case-insensitive
+	/// file systems but break when the game reads them from a ZIP file, or to store files whose
+	/// directory layout differs from the one Minecraft expects. If the transformations map two
+	/// different pack files to the same name, or generate a name that is not a valid relative
+	/// path, the squash operation will fail.
+	///
+	/// **Default value**: empty list (files are stored with their relative path as-is)
+	pub zip_entry_name_transformations: Vec<ZipEntryNameTransformation>,
+	/// If set, statistics about every file added to the output ZIP file, such as the time
+	/// spent compressing it, the compression method that was chosen, its compression ratio
+	/// and the time spent waiting for I/O, will be collected and written in JSON format to a
+	/// file at this path when the squash operation finishes. These statistics are useful to
+	/// identify which files dominate the squash time and the output ZIP file size.
+	///
+	/// Collecting these statistics has a small performance cost, so they are not collected
+	/// by default.
+	///
+	/// **Default value**: none (statistics are not collected)
+	pub zip_statistics_file_path: Option<PathBuf>,
+	/// If set, a report that compares every PNG texture before and after being optimized
+	/// will be written to a file at this path when the squash operation finishes. For each
+	/// texture, the report includes its input and output sizes and dimensions, whether its
+	/// visible pixels were changed, and, if so, their PSNR and SSIM quality metrics. This
+	/// report is meant to help reviewing the changes done by lossy optimizations before
+	/// distributing a pack.
+	///
+	/// The report is written as a self-contained HTML page with before and after thumbnails
+	/// if the path has a `.html` or `.htm` extension, and in JSON format otherwise. Textures
+	/// copied from a previous run are listed without output data, as they are not optimized
+	/// again. Generating the report requires reading and decoding every texture again, so it
+	/// is not generated by default.
+	///
+	/// **Default value**: none (no report is generated)
+	pub texture_optimization_report_file_path: Option<PathBuf>,
+	/// If set, a low-bitrate, mono Ogg Vorbis preview clip of the beginning of every sound of
+	/// the pack will be written to this directory, at the same relative path as the sound but
+	/// with an `.ogg` extension, so that web-based pack browsers and review tools can embed
+	/// previews without shipping the full sound files. Previews are generated from the input
+	/// sound files, and are not included in the generated ZIP file.
+	///
+	/// Every sound is decoded to generate its preview, even if it was copied from a previous
+	/// run, so previews are not generated by default.
+	///
+	/// **Default value**: none (no previews are generated)
+	pub audio_preview_directory: Option<PathBuf>,
+	/// The maximum duration of the audio previews written to the `audio_preview_directory`, in
+	/// seconds. Sounds shorter than this are previewed in full.
+	///
+	/// **Default value**: `10`
+	pub audio_preview_duration: NonZeroU16,
+	/// If set, a report that flags level issues of the sounds of the pack, such as clipping, DC
+	/// offset and excessive peak levels, will be written to this file in JSON format. These
+	/// issues are better fixed in the source material of the sounds than by PackSquash, and
+	/// lossy encoding tends to make them worse. The report also lists the integrated loudness
+	/// and true peak level of every sound. Sounds are analyzed from their input data, and
+	/// sounds that can't be decoded are flagged as such.
+	///
+	/// The report is not generated by default, as analyzing sounds means decoding them. When
+	/// previews are generated too, each sound is decoded once for both.
+	///
+	/// **Default value**: none (no report is generated)
+	pub audio_diagnostics_report_file_path: Option<PathBuf>,
+	/// If set, a manifest that describes the outcome of the squash operation will be written to
+	/// a file at this path in JSON format when it finishes successfully, so that downstream
+	/// tooling, such as launchers or CDN uploaders, can consume it. The manifest includes the
+	/// size and SHA-256 hash of the output ZIP file, and lists every pack file with its applied
+	/// optimization strategy, input and output sizes and hashes, and any processing error, in
+	/// addition to every warning emitted during the squash operation.
+	///
+	/// Hashing the input pack files requires reading them again, so no manifest is written by
+	/// default. Files copied from a previous run are listed without output data, as they are
+	/// not processed again.
+	///
+	/// **Default value**: none (no manifest is written)
+	pub run_manifest_file_path: Option<PathBuf>,
+	/// If `true`, animated textures whose vertical frame strip contains identical or unused
+	/// frames will be rewritten to contain every shown frame just once, and the `frames` list of
+	/// their `.mcmeta` animation metadata file will be rewritten to reference the deduplicated
+	/// frames. This can drastically shrink long animations that show the same frames several
+	/// times, without changing how they look in game.
+	///
+	/// This is disabled by default because mods and shaders that read animated textures directly
+	/// may expect their frames to be laid out as in the original texture.
+	///
+	/// **Default value**: `false`
+	pub deduplicate_animation_frames: bool,
+	/// If `true`, animated textures stored as GIF (`.gif`) or APNG (`.apng`) files will be
+	/// converted to the vertical frame strip PNG texture and `.mcmeta` animation metadata file
+	/// Minecraft expects, so that they don't have to be exported by hand. Textures whose
+	/// conversion would overwrite a file in the pack, or whose frames or frame strip would be
+	/// bigger than the `maximum_width_and_height` of the converted texture, are not converted.
+	///
+	/// **Default value**: `true`
+	pub convert_animated_textures: bool,
+	/// If `true`, the textures of compatible bitmap providers of the same font will be packed into
+	/// fewer, bigger atlas textures, and the font definition files will be rewritten to reference
+	/// the atlases. This reduces the number of files in the pack, and thus the ZIP file overhead,
+	/// without changing how text looks in game. Providers are compatible when their properties
+	/// other than the texture and characters are the same, and their textures have the same width
+	/// and glyph cell size.
+	///
+	/// This is disabled by default because textures referenced by fonts may also be used by mods,
+	/// shaders or other pack files, and only references from fonts are rewritten.
+	///
+	/// **Default value**: `false`
+	pub pack_font_atlases: bool,
+	/// If `true`, the rows of glyphs of bitmap font providers that only contain null characters,
+	/// which mark empty glyph cells, will be removed from their textures and font definition
+	/// files. Font textures are often laid out as fixed-size grids with many unused cells, so
+	/// this can make them significantly smaller, without changing how text looks in game. The
+	/// trimmed textures are then optimized like any other texture.
+	///
+	/// This is disabled by default for the same reason as `pack_font_atlases`.
+	///
+	/// **Default value**: `false`
+	pub trim_font_glyph_sheets: bool,
+	/// If `true`, the TrueType and OpenType fonts of the pack will be subset to the glyphs of
+	/// the characters used in the translations of its language files, and the characters in
+	/// `font_subset_extra_characters`. This can make fonts with thousands of glyphs, such as
+	/// CJK fonts, dramatically smaller. Only fonts with TrueType outlines are subset: fonts
+	/// with CFF outlines, variable fonts and font collections are left as-is.
+	///
+	/// This is disabled by default because fonts may show text that is not in language files,
+	/// such as chat messages, sign text or the contents of text components, whose characters
+	/// must then be added to `font_subset_extra_characters`.
+	///
+	/// **Default value**: `false`
+	pub subset_fonts: bool,
+	/// The characters whose glyphs will be kept in subset fonts in addition to the characters
+	/// used by language files. This has no effect unless `subset_fonts` is `true`.
+	///
+	/// **Default value**: the printable ASCII characters, from the space to `~`
+	pub font_subset_extra_characters: String,
+	/// If `true`, sounds split into several part files will be concatenated into a single sound
+	/// file when the pack contains a `.parts.json` manifest that lists the parts in order, so that
+	/// long tracks can be shipped as one optimized Ogg Vorbis file, without seams between parts
+	/// nor the overhead of several files. The part files and manifests are not added to the
+	/// generated ZIP file. Manifests whose parts are missing, can't be decoded or have different
+	/// sampling frequencies or channel counts are left as-is.
+	///
+	/// **Default value**: `false`
+	pub concatenate_sound_parts: bool,
+	/// What to do with sounds of the pack that decode to the same audio data as other sounds at
+	/// different paths, which are common in packs merged from several sources. Detecting them
+	/// requires decoding every sound of the pack before processing it, which takes some time.
+	///
+	/// **Default value**: `ignore` (duplicate sounds are not detected)
+	pub duplicate_sound_handling: DuplicateSoundHandling,
+	/// If `true`, the sounds referenced by the `sounds.json` files of the pack will be checked to
+	/// exist in the pack, and the sound files of the namespaces with a `sounds.json` file that no
+	/// `sounds.json` file references will be reported. Sounds of the `minecraft` namespace are not
+	/// checked, because they may be provided or referenced by the game itself.
+	///
+	/// **Default value**: `false`
+	pub sound_reference_validation: bool,
+	/// If `true`, and `sound_reference_validation` is enabled, sound files that no `sounds.json`
+	/// file references will be excluded from the generated ZIP file, instead of just being reported.
+	///
+	/// This is disabled by default because mods may play sounds of a pack without any sound event
+	/// referencing them.
+	///
+	/// **Default value**: `false`
+	pub exclude_unreferenced_sounds: bool,
+	/// What to do with the models, textures and sound files of the pack that no other pack file
+	/// references, which are common in packs merged from several sources. References are
+	/// followed from block states and item model definitions to models, from models to their
+	/// parent models and textures, from fonts and atlas configuration files to textures, from
+	/// OptiFine properties files to the textures and models they name, and from `sounds.json`
+	/// files to sound files. Only the assets of namespaces other than `minecraft` that contain block states or
+	/// item model definitions are considered, as the game, mods and other packs may reference
+	/// the assets of any other namespace. Item models and textures outside the `block` and
+	/// `item` folders are always considered to be referenced, because the game may use them
+	/// directly.
+	///
+	/// **Default value**: `ignore` (unreferenced assets are not detected)
+	pub unreferenced_asset_handling: UnreferencedAssetHandling,
+	/// Glob patterns that match the relative paths of assets that should never be considered
+	/// unreferenced by the `unreferenced_asset_handling` option, such as assets used by mods or
+	/// commands. The assets that these assets reference are not considered unreferenced either.
+	///
+	/// **Default value**: `[]` (no asset is exempt)
+	pub unreferenced_asset_exemptions: Vec<String>,
+	/// What to do with stereo sounds that Minecraft plays as positional sounds, which are every
+	/// sound outside the `music` and `records` folders of the `sounds` directory of a namespace.
+	/// Minecraft only attenuates and pans mono sounds depending on the position of their source,
+	/// so stereo sounds are heard at the same volume no matter how far away they are played.
+	///
+	/// **Default value**: `ignore` (stereo positional sounds are not detected)
+	pub stereo_positional_sound_handling: StereoPositionalSoundHandling,
+	/// Glob patterns that match the relative paths of positional sounds that should be left
+	/// alone by the `stereo_positional_sound_handling` option, such as sounds that are always
+	/// played at the position of the listener and are meant to be heard in stereo.
+	///
+	/// **Default value**: `[]` (no sound is exempt)
+	pub stereo_positional_sound_exemptions: Vec<String>,
+	/// If `true`, the JSON language file fragments in a folder named after a locale, such as
+	/// `lang/en_us/blocks.json` and `lang/en_us/items.json`, which some build setups generate,
+	/// will be merged into the language file of that locale, such as `lang/en_us.json`, which
+	/// is generated if it does not exist. Fragments are merged in path order after the language
+	/// file, so their translations take precedence. Minecraft ignores such fragments otherwise.
+	///
+	/// **Default value**: `false`
+	pub merge_language_file_fragments: bool,
+	/// What to do with the translations of JSON language files that are the same as the
+	/// translation of the same key in the American English (`en_us`) language file of the same
+	/// namespace, which Minecraft falls back to for keys the selected language does not
+	/// translate. These are common in packs that ship language files for several regions of a
+	/// language, such as `en_gb` and `en_us`.
+	///
+	/// **Default value**: `ignore` (duplicate translations are not detected)
+	pub duplicate_translation_handling: DuplicateTranslationHandling,
+	/// If `true`, language files will be converted between the legacy `.lang` format, read by
+	/// Minecraft 1.12.2 and older versions, and the JSON format, read by Minecraft 1.13 and newer
+	/// versions, when the pack contains a locale only in the format the Minecraft versions
+	/// targeted by the `pack_format` version in `pack.mcmeta` do not read. The source language
+	/// files are kept. Enabling this option requires reading `pack.mcmeta`, even if
+	/// `validate_pack_metadata_file` is set to `false`.
+	///
+	/// **Default value**: `false`
+	pub convert_language_files: bool,
+	/// What to do with `pack.mcmeta`, model, block state, item model definition, atlas,
+	/// particle, advancement, loot table, predicate, item modifier, recipe and tag files that
+	/// do not match the structure
+	/// that the Minecraft versions targeted by the `pack_format` version in `pack.mcmeta`
+	/// expect, such as files with misspelled keys or values of the wrong type, which Minecraft
+	/// usually ignores or silently replaces with defaults. Setting this option to something
+	/// other than `ignore` requires reading `pack.mcmeta`, even if `validate_pack_metadata_file`
+	/// is set to `false`.
+	///
+	/// **Default value**: `ignore` (JSON files are not checked against schemas)
+	pub json_schema_violation_handling: JsonSchemaViolationHandling,
+	/// A map of warning categories to how the warnings of that category are handled, which is
+	/// useful to fail squash operations in continuous integration pipelines when certain classes
+	/// of issues are found. Warnings can be allowed, which silences them, reported as usual, or
+	/// denied, which reports them and makes the squash operation fail after it finishes, so
+	/// that the output ZIP file is generated anyway. Categories that are not in this map are
+	/// reported as usual.
+	///
+	/// **Default value**: empty (every warning is reported as usual)
+	pub warnings: IndexMap<WarningCategory, WarningHandling>,
+	/// The path to the JAR file of the vanilla Minecraft client the pack targets, which the
+	/// launcher usually stores at `.minecraft/versions/<version>/<version>.jar`. When set, the
+	/// textures of the pack that are byte-identical to the vanilla texture at the same path, or
+	/// that have the same dimensions and visible pixels, will be detected and reported, as they
+	/// are dead weight: Minecraft would show the same texture if the pack did not contain them.
+	///
+	/// **Default value**: none (textures are not compared against vanilla ones)
+	pub vanilla_client_jar_path: Option<PathBuf>,
+	/// If `true`, and `vanilla_client_jar_path` is set, textures that are identical to vanilla
+	/// ones will be excluded from the generated ZIP file, instead of just being reported.
+	///
+	/// This is disabled by default because packs may rely on vanilla-identical textures to
+	/// override textures of other packs applied below them.
+	///
+	/// **Default value**: `false`
+	pub exclude_vanilla_identical_textures: bool,
+	/// If `true`, the numbered tiles of OptiFine connected textures (CTM) will be renamed to
+	/// remove gaps and duplicates in their numbering, and the `tiles` list of their properties
+	/// files will be rewritten to reference them with as few ranges as possible. Tiles are only
+	/// renumbered when doing so is known to be safe, and the tiles referenced by every CTM
+	/// properties file are validated to exist regardless of this option, as long as OptiFine
+	/// support is enabled in `allow_mods`.
+	///
+	/// This is disabled by default because other mods or pack files may reference tiles by
+	/// their path, and those references are not rewritten.
+	///
+	/// **Default value**: `false`
+	#[cfg(feature = "optifine")]
+	#[doc(cfg(feature = "optifine"))]
+	pub renumber_connected_texture_tiles: bool,
+	/// What to do with OptiFine emissive textures whose dimensions differ from those of the
+	/// base texture they are overlaid on, which are rendered misaligned or stretched. Emissive
+	/// textures are only detected when the `optifine/emissive.properties` file defines their
+	/// suffix, such as `_e`.
+	///
+	/// **Default value**: `ignore` (emissive textures are not checked)
+	#[cfg(feature = "optifine")]
+	#[doc(cfg(feature = "optifine"))]
+	pub mismatched_emissive_texture_handling: MismatchedEmissiveTextureHandling,
+	/// If `true`, the `#moj_import` directives of vanilla namespace vertex and fragment shaders
+	/// will be replaced with the source code of the shaders they import, like Minecraft does
+	/// before compiling them. Without the imported source code, PackSquash can't tell how the
+	/// preprocessor directives of a shader expand, and thus leaves shaders with imports as-is.
+	/// Shaders that import shaders that are not in the pack, which may be vanilla ones, or that
+	/// have a `#version` directive, are not changed. The imported shaders are still added to the
+	/// output ZIP file, as vanilla shaders may import them too.
+	///
+	/// **Default value**: `false`
+	pub inline_shader_imports: bool,
+	/// If `true`, families of vanilla namespace core shaders that only differ in a few numeric
+	/// constants will be factored into a single shared shader in the `include` folder, which
+	/// each shader of the family imports with a `#moj_import` directive after defining its
+	/// constants as macros. Every Minecraft version that reads core shaders supports such
+	/// imports. Families are only factored when that makes them smaller, and a notice lists
+	/// the shaders of every factored family.
+	///
+	/// **Default value**: `false`
+	pub factor_shader_variants: bool,
+	/// If `true`, the shader program JSON definitions of the pack will be cross-validated against
+	/// the vertex and fragment shaders they reference, reporting referenced shaders that are not
+	/// in the pack, and samplers and uniforms that are defined in a JSON file but not declared by
+	/// its shaders, are declared with a different type, or are declared but not defined. Minecraft
+	/// only reports these mismatches with cryptic log messages, if at all. Shaders of the
+	/// `minecraft` namespace that are not in the pack are not reported missing, because they may
+	/// be provided by the game itself, and declarations are only checked when the source code of
+	/// both shaders and every shader they import is in the pack.
+	///
+	/// **Default value**: `false`
+	pub shader_program_validation: bool,
+	/// If `true`, the post-processing effect pipelines of the pack will be validated, reporting
+	/// passes that use programs or textures that are not in the pack, read from or write to
+	/// undeclared render targets, or bind samplers their programs do not define, and render
+	/// targets that are not used or never written to. Both the `post_effect` folder Minecraft
+	/// reads pipelines from since 1.21.2 and the `shaders/post` folder used before are
+	/// considered. Programs and textures of the `minecraft` namespace that are not in the pack
+	/// are not reported missing, because they may be provided by the game itself.
+	///
+	/// **Default value**: `false`
+	pub post_effect_validation: bool,
+	/// If `true`, the providers of the font definition files of the pack will be validated,
+	/// reporting bitmap provider textures, `unihex` provider ZIP files, `ttf` provider font files
+	/// and `reference` provider fonts that are not in the pack, bitmap providers whose rows of
+	/// glyphs have different lengths, whose texture can't be evenly divided in glyph cells or
+	/// whose ascent is greater than their height, and `space` provider advances defined for more
+	/// than one character. Minecraft skips such providers, or fails to load their font, while
+	/// only reporting it in its log. Assets of the `minecraft` namespace that are not in the
+	/// pack are not reported missing, because they may be provided by the game itself.
+	///
+	/// **Default value**: `false`
+	pub font_provider_validation: bool,
+	/// If `true`, the item model definitions of the pack will be validated, reporting the
+	/// models they render that are not in the pack, and the parents and textures of those
+	/// models that are not in the pack either. Minecraft renders such items with the missing
+	/// model or texture, while only reporting it in its log. Assets of the `minecraft`
+	/// namespace that are not in the pack are not reported missing, because they may be
+	/// provided by the game itself.
+	///
+	/// **Default value**: `false`
+	pub item_model_definition_validation: bool,
+	/// What to do with the sources of texture atlas configuration files that reference
+	/// textures that are not in the pack, or match no texture of the pack. Minecraft renders
+	/// the textures that no source added to an atlas as the missing texture, while only
+	/// reporting it in its log. Textures of the `minecraft` namespace that are not in the pack
+	/// are not reported missing, because they may be provided by the game itself.
+	///
+	/// **Default value**: `ignore` (atlas sources are not checked)
+	pub unmatched_atlas_source_handling: UnmatchedAtlasSourceHandling,
+	/// If `true`, the tags of the data pack will be validated, reporting required entries that
+	/// reference tags, functions or elements of data-driven registries, such as biomes or
+	/// enchantments, that are not in the pack. Minecraft fails to load such tags, and every tag
+	/// that includes them, while only reporting it in its log. The folders these files are
+	/// expected in depend on the pack format version, so the pack metadata file is read to
+	/// know it. References to the `minecraft` namespace, and to namespaces the pack does not
+	/// define data for, are not checked, because they may be provided by the game itself, mods
+	/// or other data packs.
+	///
+	/// Only the references of tags are checked: the references of recipes, advancements, loot
+	/// tables, worldgen files and other data pack files are not followed.
+	///
+	/// **Default value**: `false`
+	pub data_pack_reference_validation: bool,
+	/// Options that define the precompiled shader artifacts, such as the shader caches some
+	/// modded clients accept for specific GPUs, that will be bundled next to the shaders of
+	/// the pack.
+	///
+	/// **Default value**: see [`ShaderCacheOptions`] (no artifacts are bundled)
+	pub shader_cache: ShaderCacheOptions
 }
 
 impl Default for GlobalOptions {
@@ -279,6 +952,10 @@ impl Default for GlobalOptions {
 		Self {
 			skip_pack_icon: false,
 			validate_pack_metadata_file: true,
+			target_minecraft_version_range: None,
+			overlay_minecraft_version_ranges: IndexMap::new(),
+			template_variables: IndexMap::new(),
+			text_replacement_rules: IndexMap::new(),
 			zip_spec_conformance_level: Default::default(),
 			size_increasing_zip_obfuscation: false,
 			percentage_of_zip_structures_tuned_for_obfuscation_discretion: PercentageInteger(0),
@@ -289,19 +966,101 @@ impl Default for GlobalOptions {
 			work_around_minecraft_quirks: EnumSet::empty(),
 			automatic_asset_types_mask_detection: true,
 			ignore_system_and_hidden_files: true,
+			pack_file_filters: IndexMap::new(),
+			file_type_routes: IndexMap::new(),
 			#[cfg(any(feature = "optifine", feature = "mtr3"))]
 			allow_mods: EnumSet::empty(),
+			mod_resources_profile: None,
+			merged_pack_directories: Vec::new(),
+			pack_merge_conflict_handling: PackMergeConflictHandling::default(),
 			threads: hardware_threads,
 			output_file_path: PathBuf::from("pack.zip"),
+			split_output_by_namespace: false,
+			split_output_groups: IndexMap::new(),
+			world_template_packaging: false,
+			bedrock_edition_target: false,
 			// In MiB. By default, half of available memory / (hardware threads + 1 for the output ZIP)
 			spooling_buffers_size: (available_memory / 2097152 / (hardware_threads.get() as u64 + 1))
 				.try_into()
 				.unwrap_or(usize::MAX),
-			zip_comment: ZipArchiveCommentString::default()
+			png_optimization_memory_limit: (available_memory / 2097152)
+				.try_into()
+				.unwrap_or(usize::MAX),
+			maximum_concurrent_audio_transcodes: None,
+			optimization_memory_budget: None,
+			temporary_storage_limit: None,
+			zip_comment: ZipArchiveCommentString::default(),
+			zip_entry_name_transformations: Vec::new(),
+			zip_statistics_file_path: None,
+			texture_optimization_report_file_path: None,
+			audio_preview_directory: None,
+			audio_preview_duration: NonZeroU16::new(10).unwrap(),
+			audio_diagnostics_report_file_path: None,
+			run_manifest_file_path: None,
+			deduplicate_animation_frames: false,
+			convert_animated_textures: true,
+			pack_font_atlases: false,
+			trim_font_glyph_sheets: false,
+			subset_fonts: false,
+			font_subset_extra_characters: (' '..='~').collect(),
+			concatenate_sound_parts: false,
+			duplicate_sound_handling: DuplicateSoundHandling::default(),
+			sound_reference_validation: false,
+			exclude_unreferenced_sounds: false,
+			unreferenced_asset_handling: UnreferencedAssetHandling::default(),
+			unreferenced_asset_exemptions: Vec::new(),
+			stereo_positional_sound_handling: StereoPositionalSoundHandling::default(),
+			stereo_positional_sound_exemptions: Vec::new(),
+			merge_language_file_fragments: false,
+			duplicate_translation_handling: DuplicateTranslationHandling::default(),
+			convert_language_files: false,
+			json_schema_violation_handling: JsonSchemaViolationHandling::default(),
+			warnings: IndexMap::new(),
+			vanilla_client_jar_path: None,
+			exclude_vanilla_identical_textures: false,
+			#[cfg(feature = "optifine")]
+			renumber_connected_texture_tiles: false,
+			#[cfg(feature = "optifine")]
+			mismatched_emissive_texture_handling: MismatchedEmissiveTextureHandling::default(),
+			inline_shader_imports: false,
+			factor_shader_variants: false,
+			shader_program_validation: false,
+			post_effect_validation: false,
+			font_provider_validation: false,
+			item_model_definition_validation: false,
+			unmatched_atlas_source_handling: UnmatchedAtlasSourceHandling::default(),
+			data_pack_reference_validation: false,
+			shader_cache: ShaderCacheOptions::default()
 		}
 	}
 }
 
+/// Options that define the precompiled shader artifacts that will be bundled next to the
+/// shaders of a pack. PackSquash does not generate these artifacts, but adds the ones it finds
+/// in a directory that mirrors the structure of the pack to the output ZIP file, as if they
+/// were pack files, which allows bundling artifacts generated by external tools without
+/// copying them to the pack directory.
+#[derive(Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ShaderCacheOptions {
+	/// The directory that contains the precompiled shader artifacts to bundle. The artifacts
+	/// of a shader must be at the same relative path as the shader in the pack, with one of
+	/// the extensions listed in `artifact_extensions` appended. For example, the artifact of
+	/// `assets/minecraft/shaders/core/position.vsh` with the `spv` extension must be at
+	/// `assets/minecraft/shaders/core/position.vsh.spv` within this directory.
+	///
+	/// **Default value**: `None` (no artifacts are bundled)
+	pub artifacts_directory: Option<PathBuf>,
+	/// The extensions of the precompiled shader artifacts to bundle, without a leading dot.
+	/// Several extensions can be used to bundle artifacts for different GPU targets. Pack
+	/// files with these extensions next to shaders are also added to the output ZIP file
+	/// as-is, unless other file options match them.
+	///
+	/// **Default value**: `[]` (no artifacts are bundled)
+	pub artifact_extensions: Vec<String>
+}
+
 impl GlobalOptions {
 	/// Returns the [`SquashZipSettings`] contained within these options, which are used to configure
 	/// the SquashZip compressor.
@@ -327,10 +1086,315 @@ impl GlobalOptions {
 			workaround_old_java_obfuscation_quirks: self
 				.work_around_minecraft_quirks
 				.contains(MinecraftQuirk::Java8ZipParsing),
-			spool_buffer_size: self.spooling_buffers_size.saturating_mul(1024 * 1024),
-			zip_comment: self.zip_comment.clone()
+			spool_buffer_size: self.spool_buffer_size(),
+			temporary_storage_limit: self
+				.temporary_storage_limit
+				.map(|temporary_storage_limit| temporary_storage_limit.saturating_mul(1024 * 1024)),
+			zip_comment: self.zip_comment.clone(),
+			entry_name_transformations: self
+				.zip_entry_name_transformations
+				.iter()
+				.cloned()
+				.chain(
+					self.bedrock_edition_target
+						.then(bedrock_entry_name_transformations)
+						.into_iter()
+						.flatten()
+				)
+				.collect(),
+			collect_statistics: self.zip_statistics_file_path.is_some()
 		}
 	}
+
+	/// Returns the size, in bytes, that each spooling buffer may grow up to. It is capped so that
+	/// the spooling buffers of every thread and the output ZIP file fit in half of the memory
+	/// budget, if any.
+	fn spool_buffer_size(&self) -> usize {
+		let spool_buffer_size = self.spooling_buffers_size.saturating_mul(1024 * 1024);
+
+		self.optimization_memory_budget
+			.map_or(spool_buffer_size, |memory_budget| {
+				cmp::min(
+					spool_buffer_size,
+					memory_budget.saturating_mul(1024 * 1024) / 2 / (self.threads.get() + 1)
+				)
+			})
+	}
+
+	/// Returns the maximum memory, in bytes, that the pack files being optimized at the same
+	/// time are estimated to use altogether according to the memory budget, which is half of it.
+	fn optimization_memory_limit(&self) -> usize {
+		self.optimization_memory_budget
+			.map_or(usize::MAX, |memory_budget| {
+				memory_budget.saturating_mul(1024 * 1024) / 2
+			})
+	}
+}
+
+/// The mod loaders whose mod project resources layout PackSquash knows about, contained in
+/// [`GlobalOptions`].
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModResourcesProfile {
+	/// Forge, which reads the mod metadata from `META-INF/mods.toml`, and the resource pack
+	/// format version of mod resources from the `forge:resource_pack_format` key of their
+	/// pack metadata file.
+	Forge,
+	/// NeoForge, which reads the mod metadata from `META-INF/neoforge.mods.toml`, or
+	/// `META-INF/mods.toml` in versions before 20.5.
+	NeoForge,
+	/// Fabric, which reads the mod metadata from `fabric.mod.json`.
+	Fabric,
+	/// Quilt, which reads the mod metadata from `quilt.mod.json`, and also loads Fabric mods.
+	Quilt
+}
+
+impl ModResourcesProfile {
+	/// Returns glob patterns that match the files of a mod project that are read by the mod
+	/// loader instead of Minecraft, with the format they should be processed as. Every mod
+	/// loader supports Mixin configuration files.
+	///
+	/// References:
+	/// - <https://docs.neoforged.net/docs/gettingstarted/modfiles>
+	/// - <https://wiki.fabricmc.net/documentation:fabric_mod_json>
+	const fn metadata_files(self) -> &'static [(&'static str, CustomFileFormat)] {
+		match self {
+			Self::Forge => &[
+				("META-INF/mods.toml", CustomFileFormat::Toml),
+				("META-INF/accesstransformer.cfg", CustomFileFormat::Opaque),
+				("{?*.mixins.json,logo.png}", CustomFileFormat::Opaque)
+			],
+			Self::NeoForge => &[
+				("META-INF/{neoforge.mods,mods}.toml", CustomFileFormat::Toml),
+				("META-INF/accesstransformer.cfg", CustomFileFormat::Opaque),
+				("{?*.mixins.json,logo.png}", CustomFileFormat::Opaque)
+			],
+			Self::Fabric => &[(
+				"{fabric.mod.json,?*.mixins.json,?*.accesswidener,assets/*/icon.png}",
+				CustomFileFormat::Opaque
+			)],
+			Self::Quilt => &[(
+				"{quilt.mod.json,fabric.mod.json,?*.mixins.json,?*.accesswidener,assets/*/icon.png}",
+				CustomFileFormat::Opaque
+			)]
+		}
+	}
+
+	/// Returns the prefixes of the JSON object keys that the mod loader reads from vanilla
+	/// JSON files, such as resource conditions.
+	///
+	/// References:
+	/// - <https://docs.neoforged.net/docs/resources/server/conditions>
+	/// - <https://fabricmc.net/wiki/tutorial:resource_conditions>
+	pub(crate) const fn json_key_prefixes(self) -> &'static [&'static str] {
+		match self {
+			Self::Forge => &["forge:"],
+			Self::NeoForge => &["neoforge:", "forge:"],
+			Self::Fabric => &["fabric:"],
+			Self::Quilt => &["quilt:", "fabric:"]
+		}
+	}
+}
+
+/// The ways in which sounds that decode to the same audio data as other sounds at different
+/// paths can be handled, contained in [`GlobalOptions`].
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateSoundHandling {
+	/// Duplicate sounds are not detected.
+	#[default]
+	Ignore,
+	/// Duplicate sounds are detected and reported, so that they can be removed from the pack
+	/// and the sound events that use them pointed to a single copy.
+	Warn,
+	/// Duplicate sounds in the same file format are processed as if they were byte-for-byte
+	/// copies of the first sound of their group, so that the generated ZIP file can store their
+	/// data just once when ZIP file deduplication is enabled by the ZIP specification conformance
+	/// level. Duplicate sounds in different file formats are reported instead.
+	Deduplicate
+}
+
+/// The ways in which the translations of language files that are the same as the American
+/// English translations Minecraft falls back to can be handled, contained in [`GlobalOptions`].
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateTranslationHandling {
+	/// Duplicate translations are not detected.
+	#[default]
+	Ignore,
+	/// Duplicate translations are detected and reported, so that they can be reviewed.
+	Warn,
+	/// Duplicate translations are removed from the language files that are not American
+	/// English, so that Minecraft falls back to the American English translation. This is
+	/// only safe when no language file of a pack applied below this one, including the vanilla
+	/// assets, translates the same keys for the same language, because those translations would
+	/// take precedence over the fallback ones.
+	Deduplicate
+}
+
+/// The ways in which the sources of texture atlas configuration files that match no texture can
+/// be handled, contained in [`GlobalOptions`].
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnmatchedAtlasSourceHandling {
+	/// Atlas sources are not checked.
+	#[default]
+	Ignore,
+	/// Atlas sources that reference textures that are not in the pack, or match no texture
+	/// of the pack, are reported, so that they can be reviewed.
+	Warn,
+	/// Atlas sources that only reference textures that are neither in the pack nor in the
+	/// `minecraft` namespace are removed from their atlas configuration file, as they add
+	/// nothing to the atlas. This is only safe when no pack applied below this one provides
+	/// those textures. Other mismatches are reported like with `Warn`.
+	Prune
+}
+
+/// The ways in which OptiFine emissive textures whose dimensions differ from those of their
+/// base texture can be handled, contained in [`GlobalOptions`].
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg(feature = "optifine")]
+#[doc(cfg(feature = "optifine"))]
+pub enum MismatchedEmissiveTextureHandling {
+	/// Emissive textures are not checked.
+	#[default]
+	Ignore,
+	/// Emissive textures with different dimensions than their base texture are reported, so
+	/// that they can be reviewed.
+	Warn,
+	/// Emissive textures that are smaller than their base texture, with a different aspect
+	/// ratio, are padded with transparent pixels to the dimensions of their base texture.
+	/// Other mismatches are reported like with `Warn`.
+	Pad
+}
+
+/// The ways in which the assets of a pack that no other pack file references can be handled,
+/// contained in [`GlobalOptions`].
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnreferencedAssetHandling {
+	/// Unreferenced assets are not detected.
+	#[default]
+	Ignore,
+	/// Unreferenced assets are detected and reported, so that they can be reviewed.
+	Warn,
+	/// Unreferenced assets are excluded from the generated ZIP file.
+	Exclude
+}
+
+/// The ways in which stereo sounds that Minecraft plays as positional sounds can be handled,
+/// contained in [`GlobalOptions`].
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StereoPositionalSoundHandling {
+	/// Stereo positional sounds are not detected.
+	#[default]
+	Ignore,
+	/// Stereo positional sounds are detected and reported, so that they can be reviewed.
+	Warn,
+	/// Positional sounds are downmixed to mono, unless the [`AudioFileOptions::channels`]
+	/// option is explicitly set for them, even if it is set to keep their channels.
+	Downmix
+}
+
+/// The ways in which conflicts between files at the same relative path of several merged
+/// packs can be resolved, contained in [`GlobalOptions`].
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackMergeConflictHandling {
+	/// The file of the pack that comes first is kept, where the pack in the pack directory
+	/// comes before every merged pack.
+	#[default]
+	FirstWins,
+	/// The file of the pack that comes last is kept.
+	LastWins,
+	/// The squash operation fails if there is any conflict.
+	Error
+}
+
+/// The ways in which JSON files of well-known types that do not match the schema Minecraft
+/// expects for them can be handled, contained in [`GlobalOptions`].
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonSchemaViolationHandling {
+	/// JSON files are not checked against schemas.
+	#[default]
+	Ignore,
+	/// Schema violations are reported, but the squash operation goes on.
+	Warn,
+	/// Schema violations are reported, and the squash operation fails if there is any.
+	Error
+}
+
+/// The categories of the warnings a squash operation may emit, whose handling can be configured
+/// in [`GlobalOptions`]. Each category corresponds to a kind of
+/// [`PackSquasherWarning`](crate::PackSquasherWarning).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum WarningCategory {
+	/// The previously generated ZIP file can't be used to speed up pack processing.
+	UnusablePreviousZip,
+	/// A predictable key was used to encrypt system time data.
+	PredictableSystemTimeSanitizationKey,
+	/// The key used to encrypt system time data may change in the future.
+	VolatileSystemTimeSanitizationKey,
+	/// The number of parallel tasks was limited due to open file descriptor limits.
+	ConcurrencyLimitedDueToOpenFdLimits,
+	/// A texture is identical to the vanilla texture at the same path.
+	VanillaIdenticalTexture,
+	/// An OptiFine connected textures properties file references missing tiles.
+	MissingConnectedTextureTiles,
+	/// An OptiFine custom entity model references missing textures or part models.
+	CustomEntityModelMismatch,
+	/// An OptiFine emissive texture has different dimensions than its base texture.
+	EmissiveTextureMismatch,
+	/// Some sounds decode to the same audio data.
+	DuplicateSounds,
+	/// A `sounds.json` file references a missing sound.
+	MissingSound,
+	/// A sound is not referenced by any `sounds.json` file.
+	UnreferencedSound,
+	/// A preview of a sound could not be generated.
+	AudioPreviewNotGenerated,
+	/// A sound played as a positional sound is stereo.
+	StereoPositionalSound,
+	/// Several merged packs contain a file at the same relative path.
+	PackMergeConflict,
+	/// A JSON file does not match the schema Minecraft expects for it.
+	JsonSchemaViolation,
+	/// An asset is not referenced by any other pack file.
+	UnreferencedAsset,
+	/// A language file contains translations that are the same as the fallback ones.
+	DuplicateTranslations,
+	/// A shader program definition does not match its shaders.
+	ShaderProgramMismatch,
+	/// A post-processing effect pipeline is not wired correctly.
+	PostEffectMismatch,
+	/// A font provider references missing assets or defines its glyphs inconsistently.
+	FontProviderMismatch,
+	/// An item model definition renders missing models or textures.
+	ItemModelDefinitionMismatch,
+	/// A texture atlas source references missing textures or matches none.
+	AtlasSourceMismatch,
+	/// A data pack tag has required entries that reference missing elements.
+	DataPackReferenceMismatch
+}
+
+/// The ways in which the warnings of a [`WarningCategory`] can be handled, contained in
+/// [`GlobalOptions`].
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningHandling {
+	/// The warnings are not reported.
+	Allow,
+	/// The warnings are reported, but the squash operation goes on.
+	#[default]
+	Warn,
+	/// The warnings are reported, and the squash operation fails after it finishes if there
+	/// is any.
+	Deny
 }
 
 /// A ZIP specification intent conformance level that a squash operation can adhere to.
@@ -541,7 +1605,7 @@ pub enum MinecraftMod {
 /// Options that customize how some file, of a certain file type, is processed.
 // When adding new variants to this enum, please update the lib.rs file too, so
 // the default options are used for new file types too
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Clone)]
 #[serde(
 	untagged,
 	expecting = "some options did not match the expected global or file-specific options.\n\
@@ -556,7 +1620,7 @@ pub enum FileOptions {
 	JsonFileOptions(JsonFileOptions),
 	/// The options that customize how PNG files are optimized to a more space-efficient
 	/// representation.
-	PngFileOptions(PngFileOptions),
+	PngFileOptions(#[serde(deserialize_with = "deserialize_png_file_options")] PngFileOptions),
 	/// Options that influence how shader files are converted to a more distribution-friendly
 	/// representation.
 	ShaderFileOptions(ShaderFileOptions),
@@ -574,12 +1638,40 @@ pub enum FileOptions {
 	/// Options that influence how compressed compound NBT tag files are converted to a more
 	/// distribution-friendly representation.
 	CompressedCompoundNbtTagFileOptions(CompressedCompoundNbtTagFileOptions),
+	/// Options that influence how ZIP files of Unifont `.hex` glyphs are converted to a more
+	/// distribution-friendly representation.
+	ZippedUnifontHexFileOptions(ZippedUnifontHexFileOptions),
 	/// Options that influence how custom files that the user explicitly wants to include in the
 	/// pack are processed.
 	// For better style, keep this variant last
 	CustomFileOptions(CustomFileOptions)
 }
 
+/// The directories of texture assets, relative to the `textures` directory of their namespace,
+/// whose textures are stitched to vanilla texture atlases. Textures in the `blocks` and `items`
+/// directories are stitched by versions before 1.13.
+///
+/// Reference: the `atlases` directory of the vanilla resource pack
+const ATLAS_TEXTURE_DIRECTORIES: &[&str] = &[
+	"block",
+	"blocks",
+	"item",
+	"items",
+	"particle",
+	"painting",
+	"mob_effect",
+	"gui/sprites",
+	"trims",
+	"map/decorations",
+	"entity/banner",
+	"entity/bed",
+	"entity/chest",
+	"entity/shield",
+	"entity/shulker",
+	"entity/signs",
+	"entity/decorated_pot"
+];
+
 impl FileOptions {
 	/// Tweaks the value of the crate-private fields that are used to enforce global options
 	/// contained in the [`GlobalOptions`] struct.
@@ -600,12 +1692,95 @@ impl FileOptions {
 			file_options.minecraft_version_supports_png_obfuscation = !global_options
 				.work_around_minecraft_quirks
 				.contains(MinecraftQuirk::PngObfuscationIncompatibility);
+			file_options.optimization_memory_limit = cmp::min(
+				global_options
+					.png_optimization_memory_limit
+					.saturating_mul(1024 * 1024),
+				global_options.optimization_memory_limit()
+			);
+		}
+
+		if let FileOptions::CommandFunctionFileOptions(file_options) = &mut self {
+			file_options.target_minecraft_version_range =
+				global_options.target_minecraft_version_range;
+		}
+
+		if let FileOptions::ShaderFileOptions(file_options) = &mut self {
+			file_options.target_minecraft_version_range =
+				global_options.target_minecraft_version_range;
+		}
+
+		if let FileOptions::ZippedUnifontHexFileOptions(file_options) = &mut self {
+			file_options.compression_iterations = global_options.zip_compression_iterations;
 		}
 
 		if let FileOptions::AudioFileOptions(file_options) = &mut self {
 			file_options.minecraft_version_supports_ogg_obfuscation = !global_options
 				.work_around_minecraft_quirks
 				.contains(MinecraftQuirk::OggObfuscationIncompatibility);
+			file_options.concurrent_transcodes_limit = global_options
+				.maximum_concurrent_audio_transcodes
+				.map_or(usize::MAX, NonZeroUsize::get);
+			file_options.streaming_transcodes_limit = global_options.threads.get();
+			file_options.optimization_memory_limit = global_options.optimization_memory_limit();
+		}
+
+		self
+	}
+
+	/// Tweaks the value of the crate-private fields that depend on the relative path of the
+	/// pack file these options apply to. Like [`Self::tweak_from_global_options`], this method
+	/// should be executed before actually using the file options.
+	pub(crate) fn tweak_from_relative_path(
+		mut self,
+		relative_path: &str,
+		squash_options: &ProcessedSquashOptions
+	) -> Self {
+		#[cfg(feature = "optifine")]
+		if let FileOptions::PropertiesFileOptions(file_options) = &mut self {
+			file_options.is_custom_item_texture_properties =
+				["optifine", "mcpatcher"].iter().any(|directory| {
+					relative_path.starts_with(&format!("assets/minecraft/{directory}/cit/"))
+				});
+		}
+
+		#[cfg(feature = "shader-compilation-check")]
+		if let FileOptions::ShaderFileOptions(file_options) = &mut self {
+			file_options.is_fragment_shader = relative_path.ends_with(".fsh");
+		}
+
+		if let FileOptions::PngFileOptions(file_options) = &mut self {
+			file_options.is_atlas_texture = relative_path
+				.strip_prefix("assets/")
+				.and_then(|asset_path| asset_path.split_once("/textures/"))
+				.is_some_and(|(namespace, texture_path)| {
+					!namespace.contains('/')
+						&& ATLAS_TEXTURE_DIRECTORIES.iter().any(|directory| {
+							texture_path
+								.strip_prefix(directory)
+								.is_some_and(|path| path.starts_with('/'))
+						})
+				});
+		}
+
+		if let FileOptions::AudioFileOptions(file_options) = &mut self {
+			let sound_category = SoundCategory::from_relative_path(relative_path);
+
+			if file_options.sound_category_profiles {
+				file_options.sound_category = sound_category;
+			}
+
+			// Music is the only kind of sound that Minecraft does not play as positional sounds
+			if sound_category != Some(SoundCategory::Music)
+				&& !squash_options
+					.stereo_positional_sound_exemption_globs
+					.is_match(relative_path)
+			{
+				file_options.stereo_positional_sound_handling = squash_options
+					.options
+					.global_options
+					.stereo_positional_sound_handling;
+			}
 		}
 
 		self
@@ -613,7 +1788,7 @@ impl FileOptions {
 }
 
 /// Parameters that influence how an audio file is optimized.
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Clone)]
 #[serde(default, deny_unknown_fields)]
 #[non_exhaustive]
 pub struct AudioFileOptions {
@@ -622,6 +1797,17 @@ pub struct AudioFileOptions {
 	///
 	/// **Default value**: `true`
 	pub transcode_ogg: bool,
+	/// If `true`, input Ogg Vorbis files will not be transcoded again when they were already
+	/// encoded by PackSquash, or at or below the target bitrate when a bitrate control mode other
+	/// than CQF is used, and transcoding them would not change their channels, sampling frequency,
+	/// pitch, loudness or duration. This avoids the generational quality loss of re-encoding lossy
+	/// audio over and over, such as when squashing packs that contain already squashed sounds.
+	///
+	/// Files encoded by PackSquash are recognized by their empty Vorbis vendor string. This is
+	/// disabled by default because other tools may also empty that string.
+	///
+	/// **Default value**: `false`
+	pub skip_redundant_ogg_transcoding: bool,
 	/// If `true`, an additional fast two-pass optimization and validation step will be performed
 	/// on the generated Ogg Vorbis file before it is added to the pack, regardless of whether it
 	/// has been transcoded. This enables PackSquash to ensure that the generated file will work
@@ -650,8 +1836,13 @@ pub struct AudioFileOptions {
 	/// get the different positional effects. If `is_positional_audio` is set to `None`, this
 	/// channel mixing also influences whether the sound is considered positional or not.
 	///
-	/// **Default value**: do not downmix or upmix (keep the channels of the input file)
-	pub channels: ChannelMixingOption,
+	/// When this option is not set, the channels may be changed according to
+	/// [`Self::sound_category_profiles`] and [GlobalOptions::stereo_positional_sound_handling],
+	/// which never happens when it is explicitly set.
+	///
+	/// **Default value**: `None` (do not downmix or upmix, keeping the channels of the input
+	/// file)
+	pub channels: Option<ChannelMixingOption>,
 	/// The bitrate control mode that will be used for transcoding the audio file. Different bitrate
 	/// control modes have different trade-offs between audio quality, file size, bandwidth
 	/// predictability and encoding speed.
@@ -673,6 +1864,17 @@ pub struct AudioFileOptions {
 	///
 	/// **Default value**: `40050` (40.05 kHz) for stereo audio, `32000` for mono audio
 	pub sampling_frequency: Option<NonZeroU32>,
+	/// If `true`, the bandwidth of the audio will be analyzed in an additional decoding pass, and
+	/// the audio will be resampled to the lowest common sampling frequency whose Nyquist frequency
+	/// (half of it) has no audible content above it, if that is lower than the sampling frequency
+	/// it would be resampled to otherwise. This allows using aggressive sampling frequencies for
+	/// sounds that do not need higher ones, such as low rumbles and muffled ambience, without
+	/// degrading sounds that do. Resampling does not change the pitch or speed of the audio. This
+	/// option is only honored if the audio file is being transcoded, which is always the case when
+	/// the `transcode_ogg` option is set to `true`.
+	///
+	/// **Default value**: `false`
+	pub bandwidth_adaptive_sampling_frequency: bool,
 	/// Sets the pitch shift coefficient that will have to be used to play back the sound
 	/// at the original pitch. This pitch shift coefficient can be used directly in Minecraft
 	/// commands like `/playsound`.
@@ -684,6 +1886,70 @@ pub struct AudioFileOptions {
 	///
 	/// **Default value**: `1.0` (the audio pitch is not shifted)
 	pub target_pitch: f32,
+	/// If set, the audio will be normalized to this integrated loudness, in LUFS (loudness units
+	/// relative to full scale), as measured according to the EBU R128 recommendation. This lets
+	/// sounds recorded at very different levels play at a consistent volume. The loudness is
+	/// measured in an additional decoding pass over the audio, after any channel mixing,
+	/// resampling and pitch shifting, so normalization roughly doubles the decoding time.
+	///
+	/// The gain applied to normalize the loudness is reduced when needed to keep the true peak
+	/// level of the audio below the `maximum_true_peak` option value, so loud sounds with high
+	/// peaks may end up quieter than the target loudness. Silent sounds are never normalized.
+	///
+	/// This option is only honored if the audio file is being transcoded, which is always the
+	/// case when the `transcode_ogg` option is set to `true`.
+	///
+	/// **Default value**: none (the loudness is not normalized)
+	pub target_loudness: Option<f32>,
+	/// The maximum true peak level that loudness normalization may raise the audio to, in dBTP
+	/// (decibels relative to full scale, true peak). True peaks account for the peaks between
+	/// samples that appear when reconstructing the audio signal, which may cause clipping even
+	/// if no sample exceeds full scale. This option only has an effect if `target_loudness` is
+	/// set.
+	///
+	/// **Default value**: `-1.0`
+	pub maximum_true_peak: f32,
+	/// If set, the leading and trailing silence of the audio will be trimmed, considering silent
+	/// the sample frames whose samples are all below this threshold, in dBFS (decibels relative
+	/// to full scale). This saves space for sounds exported with silent padding, and makes them
+	/// start playing without a delay. Silence between non-silent samples is never trimmed.
+	///
+	/// Trimming would break the timing of seamless loops, so audio files with loop point
+	/// metadata, such as `LOOPSTART` Vorbis comments or WAV sampler chunk loops, are never
+	/// trimmed. This option is only honored if the audio file is being transcoded, which is
+	/// always the case when the `transcode_ogg` option is set to `true`.
+	///
+	/// **Default value**: none (silence is not trimmed)
+	pub silence_trimming_threshold: Option<f32>,
+	/// The names of the Vorbis comment fields of the input audio file, such as `ARTIST` or
+	/// `LICENSE`, that will be kept in the generated Ogg Vorbis file. Field names are compared
+	/// without regard to ASCII case, as Vorbis comment field names are case-insensitive. Every
+	/// other comment is removed to save space. Retaining comments can be useful to keep the
+	/// attribution required by the license of some music.
+	///
+	/// Comments are only read from Ogg Vorbis and FLAC input files. When this list is not empty,
+	/// Ogg Vorbis files that are not transcoded keep all of their comments, as they can't be
+	/// filtered without transcoding.
+	///
+	/// **Default value**: `[]` (every comment is removed)
+	pub retained_vorbis_comment_fields: Vec<String>,
+	/// If `true`, the defaults of the `channels`, `target_bitrate_control_metric` and
+	/// `sampling_frequency` options will be tailored to the category of the sound, inferred from
+	/// the folder of the `sounds` directory of its namespace that contains it:
+	///
+	/// - Music (`sounds/music/**` and `sounds/records/**`) is transcoded at a higher quality and
+	///   sampling frequency, as listeners pay more attention to it and it is often stereo.
+	/// - Ambient sounds (`sounds/ambient/**`) are transcoded at the quality and sampling frequency
+	///   used for positional sounds, even if they are stereo, as they are usually played quietly
+	///   in the background.
+	/// - User interface sounds (`sounds/ui/**`) are downmixed to mono, as they are short and
+	///   barely benefit from stereo.
+	///
+	/// Options that are explicitly set always take precedence over these category defaults.
+	/// Sounds in other folders use the usual defaults.
+	///
+	/// **Default value**: `false`
+	pub sound_category_profiles: bool,
 	/// If `true`, the generated Ogg Vorbis files will be mangled in a way so that they will be
 	/// harder to play outside of Minecraft. The obfuscation technique used is not robust against
 	/// some scenarios or expert knowledge, but it does not increase file size.
@@ -699,23 +1965,106 @@ pub struct AudioFileOptions {
 	///
 	/// **Default value**: `true`
 	#[serde(skip)]
-	pub(crate) minecraft_version_supports_ogg_obfuscation: bool
+	pub(crate) minecraft_version_supports_ogg_obfuscation: bool,
+	/// Crate-private option set from [GlobalOptions::maximum_concurrent_audio_transcodes]
+	/// with the maximum number of audio files that may be transcoded at the same time.
+	///
+	/// **Default value**: `usize::MAX`
+	#[serde(skip)]
+	pub(crate) concurrent_transcodes_limit: usize,
+	/// Crate-private option set from [GlobalOptions::threads] with the maximum number of audio
+	/// files that may be transcoded in dedicated threads while they are read. Files read when
+	/// that many are already being transcoded like this are transcoded once fully read instead.
+	///
+	/// **Default value**: `usize::MAX`
+	#[serde(skip)]
+	pub(crate) streaming_transcodes_limit: usize,
+	/// Crate-private option set from [GlobalOptions::optimization_memory_budget] with the maximum memory,
+	/// in bytes, that the pack files being transcoded or optimized at the same time are
+	/// estimated to use.
+	///
+	/// **Default value**: `usize::MAX`
+	#[serde(skip)]
+	pub(crate) optimization_memory_limit: usize,
+	/// Crate-private option set from the relative path of the audio file when
+	/// `sound_category_profiles` is enabled, with the category of the sound.
+	///
+	/// **Default value**: `None`
+	#[serde(skip)]
+	pub(crate) sound_category: Option<SoundCategory>,
+	/// Crate-private option set from [GlobalOptions::stereo_positional_sound_handling] when the
+	/// audio file is a positional sound that is not exempt from it.
+	///
+	/// **Default value**: `ignore`
+	#[serde(skip)]
+	pub(crate) stereo_positional_sound_handling: StereoPositionalSoundHandling
 }
 
 impl Default for AudioFileOptions {
 	fn default() -> Self {
 		Self {
 			transcode_ogg: true,
+			skip_redundant_ogg_transcoding: false,
 			two_pass_vorbis_optimization_and_validation: true,
 			empty_audio_optimization: true,
-			channels: Default::default(),
+			channels: None,
 			bitrate_control_mode: Default::default(),
 			target_bitrate_control_metric: None,
 			sampling_frequency: None,
+			bandwidth_adaptive_sampling_frequency: false,
 			target_pitch: 1.0,
+			target_loudness: None,
+			maximum_true_peak: -1.0,
+			silence_trimming_threshold: None,
+			retained_vorbis_comment_fields: Vec::new(),
+			sound_category_profiles: false,
 			ogg_obfuscation: false,
-			minecraft_version_supports_ogg_obfuscation: true
+			minecraft_version_supports_ogg_obfuscation: true,
+			concurrent_transcodes_limit: usize::MAX,
+			streaming_transcodes_limit: usize::MAX,
+			optimization_memory_limit: usize::MAX,
+			sound_category: None,
+			stereo_positional_sound_handling: StereoPositionalSoundHandling::Ignore
+		}
+	}
+}
+
+/// A category of sounds, inferred from the folder of the `sounds` directory of a namespace
+/// that contains them, that tailors the defaults used to transcode them when the
+/// [`AudioFileOptions::sound_category_profiles`] option is enabled.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum SoundCategory {
+	/// Music and music disc tracks, in the `music` and `records` folders.
+	Music,
+	/// Ambient sounds and loops, in the `ambient` folder.
+	Ambient,
+	/// User interface sounds, such as button clicks, in the `ui` folder.
+	Ui
+}
+
+impl SoundCategory {
+	/// Infers the category of the sound at the specified relative path, returning `None` if
+	/// the path is not in a folder associated to a category.
+	pub(crate) fn from_relative_path(relative_path: &str) -> Option<Self> {
+		let mut path_components = relative_path.split('/');
+
+		if path_components.next()? != "assets" {
+			return None;
+		}
+		path_components.next()?; // Namespace
+		if path_components.next()? != "sounds" {
+			return None;
 		}
+
+		let category = match path_components.next()? {
+			"music" | "records" => Self::Music,
+			"ambient" => Self::Ambient,
+			"ui" => Self::Ui,
+			_ => return None
+		};
+
+		// The category folder must contain the sound, not be its file name
+		path_components.next().map(|_| category)
 	}
 }
 
@@ -864,19 +2213,21 @@ pub struct JsonFileOptions {
 	/// **Default value**: `true` (delete superfluous keys)
 	#[serde(rename = "delete_bloat_keys")]
 	pub delete_bloat: bool,
-	/// If `true`, PackSquash will allow comments in JSON files whose usual extension does not end
-	/// with an extra `c` letter, which explicitly marks the file as following an extended JSON
-	/// format that can contain comments. If `false`, comments will only be allowed in JSON files
-	/// with those specific extensions.
-	///
-	/// **Default value**: `true` (allow comments in the JSON file, no matter its extension)
+	/// If `true`, PackSquash will allow comments and trailing commas in JSON files whose usual
+	/// extension does not end with an extra `c` letter, which explicitly marks the file as
+	/// following an extended JSON format that can contain them. If `false`, comments and trailing
+	/// commas will only be allowed in JSON files with those specific extensions. Either way, the
+	/// generated files are strict JSON.
+	///
+	/// **Default value**: `true` (allow comments and trailing commas in the JSON file, no matter
+	/// its extension)
 	#[serde(rename = "always_allow_json_comments")]
 	pub always_allow_comments: bool,
-	/// If `true`, PackSquash will recursively sort JSON object keys by their lexicographic order.
-	/// This improves style consistency by ensuring equivalent key sets always appear in the same
-	/// order, and thus also compressibility. If `false`, the original key order is preserved,
-	/// which may be preferable when working with mods that improperly rely on key ordering, or
-	/// in cases where key sorting turns out to be a wrong heuristic for compressibility.
+	/// How PackSquash will recursively sort JSON object keys. Sorting keys improves style
+	/// consistency by ensuring equivalent key sets always appear in the same order, and thus
+	/// also compressibility and reproducibility. Not sorting them preserves the original key
+	/// order, which may be preferable when working with mods that improperly rely on key ordering,
+	/// or in cases where key sorting turns out to be a wrong heuristic for compressibility.
 	///
 	/// Note that PackSquash may still not sort the keys of JSON objects no matter the value of this
 	/// option if it detects that doing so would be inappropriate, due to excessive resource usage
@@ -884,7 +2235,114 @@ pub struct JsonFileOptions {
 	///
 	/// **Default value**: `true` (sort keys of JSON objects by their lexicographic order)
 	#[serde(rename = "sort_json_object_keys")]
-	pub sort_object_keys: bool
+	pub sort_object_keys: JsonObjectKeySorting,
+	/// If `true`, Minecraft block and item models will be structurally optimized: keys whose
+	/// values are the same as the defaults Minecraft uses for missing keys, like `"shade": true`
+	/// or zero-angle element rotations, will be removed, and elements that are identical to a
+	/// previous element of the model will be removed too, as they render the same faces again.
+	/// These optimizations do not change how models look in game.
+	///
+	/// **Default value**: `true`
+	#[serde(rename = "optimize_model_structure")]
+	pub optimize_model_structure: bool,
+	/// If `true`, and `optimize_model_structure` is enabled, the texture variables defined and
+	/// used within Minecraft models without a parent will be renamed to shorter names. This breaks
+	/// models of this or other packs that use such a model as their parent and set its texture
+	/// variables, so it is disabled by default.
+	///
+	/// **Default value**: `false`
+	#[serde(rename = "shorten_model_texture_variables")]
+	pub shorten_texture_variables: bool,
+	/// If `true`, Minecraft blockstate definitions will be structurally optimized: keys of their
+	/// models whose values are the same as the defaults, like `"weight": 1` or `"uvlock": false`,
+	/// will be removed, consecutive identical models of weighted lists will be merged, and the
+	/// conditions of multipart cases will be simplified. Multipart cases that apply the same
+	/// model under the same condition will be removed too. These optimizations do not change
+	/// which models blocks are rendered with.
+	///
+	/// **Default value**: `true`
+	pub optimize_blockstate_structure: bool,
+	/// If `true`, Minecraft item model definitions will be structurally optimized: keys whose
+	/// values are the same as the defaults, like `"hand_animation_on_swap": true`, will be
+	/// removed, the redundant `minecraft:` namespace of the types and models they reference
+	/// will be removed, and item models that choose between identical models will be replaced
+	/// with that model. These optimizations do not change which models items are rendered with.
+	///
+	/// **Default value**: `true`
+	pub optimize_item_model_definition_structure: bool,
+	/// If `true`, Minecraft data pack tags will be structurally optimized: `"replace": false`
+	/// will be removed, as it is the default, required entries will be written in their
+	/// shorter string form, and duplicate entries will be removed. These optimizations do not
+	/// change which elements tags contain.
+	///
+	/// **Default value**: `true`
+	pub optimize_tag_structure: bool,
+	/// If `true`, OptiFine custom entity models and part models will be structurally
+	/// optimized: keys of model parts, boxes and sprites whose values are the same as the
+	/// defaults, like `"translate": [0, 0, 0]` or `"sizeAdd": 0`, and empty lists of boxes,
+	/// sprites, submodels and animations will be removed, and numbers will be rounded to four
+	/// decimal places, which is far more precise than what can be seen in game. These
+	/// optimizations do not noticeably change how entities are rendered.
+	///
+	/// **Default value**: `true`
+	#[cfg(feature = "optifine")]
+	#[doc(cfg(feature = "optifine"))]
+	pub optimize_custom_entity_model_structure: bool,
+	/// If set, floating-point numbers will be rounded to this maximum number of decimal places,
+	/// getting rid of the floating-point noise that pack authoring tools like Blockbench write,
+	/// such as `22.499999999999996` instead of `22.5`. This is meant for models and animations,
+	/// where a few decimal places are more precise than what can be seen in game, but it may
+	/// change the meaning of other files, so it is disabled by default.
+	///
+	/// **Default value**: none (numbers are not rounded)
+	#[serde(rename = "maximum_json_number_decimal_places")]
+	pub maximum_decimal_places: Option<u8>
+}
+
+/// The ways in which the keys of JSON objects can be sorted, contained in [`JsonFileOptions`].
+/// In options files, `true` and `false` stand for lexicographic sorting and no sorting.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(from = "JsonObjectKeySortingOption")]
+pub enum JsonObjectKeySorting {
+	/// The original key order is preserved.
+	Disabled,
+	/// Keys are sorted by their lexicographic order.
+	#[default]
+	Lexicographic,
+	/// Well-known keys of Minecraft JSON files are sorted in the order they are conventionally
+	/// written in vanilla assets, followed by the rest of keys in lexicographic order. In
+	/// addition, numbers with an integral value are written as integers, so that semantically
+	/// identical files always produce byte-identical outputs.
+	Canonical
+}
+
+/// The representation of [`JsonObjectKeySorting`] in options files.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonObjectKeySortingOption {
+	Enabled(bool),
+	Named(NamedJsonObjectKeySorting)
+}
+
+/// The [`JsonObjectKeySorting`] values that can be specified by name in options files.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum NamedJsonObjectKeySorting {
+	Lexicographic,
+	Canonical
+}
+
+impl From<JsonObjectKeySortingOption> for JsonObjectKeySorting {
+	fn from(option: JsonObjectKeySortingOption) -> Self {
+		match option {
+			JsonObjectKeySortingOption::Enabled(false) => Self::Disabled,
+			JsonObjectKeySortingOption::Enabled(true)
+			| JsonObjectKeySortingOption::Named(NamedJsonObjectKeySorting::Lexicographic) => {
+				Self::Lexicographic
+			}
+			JsonObjectKeySortingOption::Named(NamedJsonObjectKeySorting::Canonical) => Self::Canonical
+		}
+	}
 }
 
 impl Default for JsonFileOptions {
@@ -893,7 +2351,15 @@ impl Default for JsonFileOptions {
 			minify: true,
 			delete_bloat: true,
 			always_allow_comments: true,
-			sort_object_keys: true
+			sort_object_keys: JsonObjectKeySorting::default(),
+			optimize_model_structure: true,
+			shorten_texture_variables: false,
+			optimize_blockstate_structure: true,
+			optimize_item_model_definition_structure: true,
+			optimize_tag_structure: true,
+			#[cfg(feature = "optifine")]
+			optimize_custom_entity_model_structure: true,
+			maximum_decimal_places: None
 		}
 	}
 }
@@ -920,6 +2386,12 @@ pub struct PngFileOptions {
 	///
 	/// **Default value**: `5`
 	pub image_data_compression_iterations: u8,
+	/// The set of filters that will be tried on the image data before compressing it, which
+	/// make it more compressible. The filters that yield the best results depend on the kind
+	/// of image.
+	///
+	/// **Default value**: [ImageDataFilterStrategy::Auto]
+	pub image_data_filter_strategy: ImageDataFilterStrategy,
 	/// Controls how the colors of the image will be quantized.
 	///
 	/// Color quantization is a lossy process if and only if the image contains more colors than
@@ -948,12 +2420,86 @@ pub struct PngFileOptions {
 	///
 	/// **Default value**: `0.85`
 	pub color_quantization_dithering_level: UnitIntervalFloat,
+	/// The range of acceptable visual qualities for color quantized images, expressed as a
+	/// `[min, max]` array of integers between 0 and 100. This option has no effect if
+	/// `color_quantization_target` is set to not perform color quantization.
+	///
+	/// The quantizer will use as few colors as possible to reach the `max` quality, up to the
+	/// maximum number of colors allowed by `color_quantization_target`, and skip quantizing
+	/// images that can't be quantized with at least the `min` quality, optimizing them
+	/// losslessly instead. Lowering `max` lets texture artists trade imperceptible color loss
+	/// for significantly smaller textures, while raising `min` protects images that would
+	/// look noticeably worse after quantization, such as those with smooth gradients.
+	///
+	/// **Default value**: `[0, 100]` (always quantize, trying to achieve the best quality)
+	pub image_quantization_quality: QuantizationQualityRange,
 	/// The maximum width and height of the images that will be accepted. This parameter
 	/// sets a high bound of memory usage by PackSquash and helps to author packs with
 	/// reasonable texture sizes.
 	///
 	/// **Default value**: 8192
 	pub maximum_width_and_height: NonZeroU16,
+	/// If `true`, PNG files with chunks whose CRC does not match their data, truncated PNG files
+	/// and PNG files in the CgBI format generated by Apple tools will be repaired before being
+	/// optimized, instead of causing an error. Missing pixel data of truncated files is filled
+	/// with transparent or black pixels. The optimization result of repaired files mentions the
+	/// problems that were repaired, so that their source can be fixed too.
+	///
+	/// **Default value**: `true`
+	pub corrupt_png_recovery: bool,
+	/// If set, images whose width or height exceed this size will be downscaled by the smallest
+	/// power of two factor that makes both dimensions fit within it. This is useful for packs
+	/// that ship high resolution textures for things that Minecraft renders at much smaller
+	/// sizes, such as items. Downscaling by powers of two keeps the boundaries between
+	/// animation frames and mipmap levels intact for textures with power of two dimensions.
+	///
+	/// The size of animated textures is checked per frame. Animated textures whose animation
+	/// metadata file declares a frame size are never downscaled, because that file would no
+	/// longer match the texture. Textures that may be read by shaders are never downscaled
+	/// either, because shaders may depend on their exact size.
+	///
+	/// **Default value**: none (do not downscale images)
+	pub maximum_texture_size: Option<NonZeroU16>,
+	/// The resampling filter that will be used to downscale images that exceed the
+	/// `maximum_texture_size` or their `maximum_file_size`, and to resize textures when
+	/// `mipmap_level_reduction_fix` is set to resize them. This option has no effect if none
+	/// of these options is set.
+	///
+	/// **Default value**: [TextureDownscalingFilter::Lanczos3]
+	pub texture_downscaling_filter: TextureDownscalingFilter,
+	/// If set, the size budget, in bytes, that optimized images should fit in. Images that exceed
+	/// it after being optimized as usual will be color quantized with progressively lower
+	/// qualities, and then downscaled by progressively greater powers of two factors, until they
+	/// fit. This is useful to bound the size of images that are known to be big, such as
+	/// paintings. Images whose budget can't be met losslessly are reported, and images whose
+	/// budget can't be met at all are left as if no budget was set.
+	///
+	/// Textures that may be read by shaders are never downscaled, and color quantization is
+	/// skipped when the color type or the colors of transparent pixels must not be changed.
+	///
+	/// **Default value**: none (do not limit the size of images)
+	pub maximum_file_size: Option<NonZeroU32>,
+	/// The number of mipmap levels that textures must let Minecraft generate when they are
+	/// stitched to a texture atlas, between 0 and 4. Minecraft reduces the mipmap levels of a
+	/// whole atlas when the width or height of any of its textures is not a multiple of 2 raised
+	/// to the configured mipmap levels, which makes distant surfaces look noisier. When set to a
+	/// value other than zero, textures whose dimensions would cause such a reduction are reported,
+	/// and fixed as specified by `mipmap_level_reduction_fix`. Values greater than 4 are treated
+	/// as 4, the maximum that Minecraft supports.
+	///
+	/// Only textures in the directories that vanilla texture atlases stitch textures from, such
+	/// as `textures/block` and `textures/item`, are checked. The dimensions of each frame are
+	/// checked for animated textures, which are only reported, as padding or resizing their
+	/// strip of frames would misalign them.
+	///
+	/// **Default value**: `0` (do not check mipmap levels)
+	pub required_mipmap_levels: u8,
+	/// How textures whose dimensions reduce the mipmap levels of their atlas below
+	/// `required_mipmap_levels` will be fixed. This option has no effect if
+	/// `required_mipmap_levels` is zero.
+	///
+	/// **Default value**: [MipmapLevelReductionFix::None]
+	pub mipmap_level_reduction_fix: MipmapLevelReductionFix,
 	/// If `true`, this option prevents the color values of completely transparent pixels from being
 	/// changed in order to achieve better compression. This optimization is visually lossless,
 	/// because completely transparent pixels are invisible no matter their color, and does not
@@ -964,6 +2510,11 @@ pub struct PngFileOptions {
 	///
 	/// **Default value**: `false`
 	pub skip_alpha_optimizations: bool,
+	/// How the colors of completely transparent pixels will be normalized. Any normalization is
+	/// visually lossless, and it is skipped if `skip_alpha_optimizations` is `true`.
+	///
+	/// **Default value**: [TransparentPixelColorNormalization::Optimize]
+	pub transparent_pixel_color_normalization: TransparentPixelColorNormalization,
 	/// If `true`, single-color textures that are estimated to be safe to resize will be downsized
 	/// to the minimum resolution that maintains the current maximum mipmap level. This can provide
 	/// significant space savings for this kind of textures, but in some edge cases (using
@@ -978,6 +2529,49 @@ pub struct PngFileOptions {
 	///
 	/// **Default value**: `false`
 	pub png_obfuscation: bool,
+	/// If `true`, images will be rewritten in the smallest PNG color type and bit depth that
+	/// can represent them exactly, before their pixel data is compressed. For example, images
+	/// whose pixels are all gray will be stored as grayscale or grayscale with alpha, and images
+	/// with 16 or fewer colors will be stored as palette images with less than 8 bits per pixel.
+	/// This usually saves a significant amount of space, but shaders that rely on textures having
+	/// an exact color type may break. Setting this to `false` keeps the color type and bit depth
+	/// of every image as-is, which also disables color quantization and single-color texture
+	/// downsizing, as both of them change the color type.
+	///
+	/// **Default value**: `true`
+	pub color_type_and_bit_depth_reduction: bool,
+	/// The dithering method that will be used to reduce the pixels of images with 16 bits per
+	/// channel to 8 bits per channel, as Minecraft discards the extra precision anyway. Dithering
+	/// avoids the banding that this reduction may introduce in smooth gradients, at the cost of
+	/// adding noise patterns that are harder to compress. The alpha channel is never dithered,
+	/// so that transparency edges stay clean.
+	///
+	/// **Default value**: [BitDepthReductionDithering::None]
+	pub bit_depth_reduction_dithering: BitDepthReductionDithering,
+	/// The set of ancillary PNG chunks that will be copied as-is to the generated PNG files,
+	/// instead of being stripped. This is useful to keep license or attribution text that must
+	/// accompany the images, or color space information for external image viewers. Minecraft
+	/// ignores these chunks, so keeping them only increases file size.
+	///
+	/// Keeping the image gamma chunk disables gamma correction, so that it still matches the
+	/// pixels. Keeping the ICC profile chunk disables the conversion of color images to
+	/// grayscale, because it may not be valid for grayscale images.
+	///
+	/// **Default value**: empty set (strip every ancillary chunk)
+	pub preserved_ancillary_chunks: EnumSet<PngAncillaryChunk>,
+	/// If `true`, the pixels of images with an embedded ICC color profile will be converted from
+	/// that profile to the sRGB color space before the profile is stripped. Minecraft ignores
+	/// color profiles, so images authored on wide-gamut displays or exported with a non-sRGB
+	/// profile may show different colors in game than in image editors. Converting them makes
+	/// the colors look as intended, but changes how they look in game compared to the original
+	/// image. Images with an sRGB chunk are already in the sRGB color space and not converted.
+	///
+	/// Images whose ICC profile is preserved by `preserved_ancillary_chunks` are not converted,
+	/// so that the pixels still match the profile. Non-sRGB image gamma is always corrected
+	/// unless the image gamma chunk is preserved, regardless of this option.
+	///
+	/// **Default value**: `false`
+	pub color_profile_conversion: bool,
 	/// Crate-private option set by the [MinecraftQuirk::GrayscaleImagesGammaMiscorrection]
 	/// workaround to not reduce color images to grayscale.
 	///
@@ -1001,25 +2595,149 @@ pub struct PngFileOptions {
 	///
 	/// **Default value**: `true`
 	#[serde(skip)]
-	pub(crate) minecraft_version_supports_png_obfuscation: bool
+	pub(crate) minecraft_version_supports_png_obfuscation: bool,
+	/// Crate-private option set from [GlobalOptions::png_optimization_memory_limit] and
+	/// [GlobalOptions::optimization_memory_budget] with the maximum memory, in bytes, that PNG files being
+	/// optimized at the same time may use.
+	///
+	/// **Default value**: `usize::MAX`
+	#[serde(skip)]
+	pub(crate) optimization_memory_limit: usize,
+	/// Crate-private option set from the animation metadata file of animated textures with the
+	/// size of their frames.
+	///
+	/// **Default value**: `None`
+	#[serde(skip)]
+	pub(crate) animation_frame_size: Option<AnimationFrameSize>,
+	/// Crate-private option set from the relative path of PNG files to whether they are textures
+	/// that Minecraft stitches to a texture atlas.
+	///
+	/// **Default value**: `false`
+	#[serde(skip)]
+	pub(crate) is_atlas_texture: bool
 }
 
 impl Default for PngFileOptions {
 	fn default() -> Self {
 		Self {
 			image_data_compression_iterations: 5,
+			image_data_filter_strategy: ImageDataFilterStrategy::default(),
 			color_quantization_target: Default::default(),
 			color_quantization_dithering_level: UnitIntervalFloat(0.85),
+			image_quantization_quality: QuantizationQualityRange::default(),
 			maximum_width_and_height: NonZeroU16::new(8192).unwrap(),
+			corrupt_png_recovery: true,
+			maximum_texture_size: None,
+			texture_downscaling_filter: TextureDownscalingFilter::default(),
+			maximum_file_size: None,
+			required_mipmap_levels: 0,
+			mipmap_level_reduction_fix: MipmapLevelReductionFix::default(),
 			skip_alpha_optimizations: false,
+			transparent_pixel_color_normalization: TransparentPixelColorNormalization::default(),
 			downsize_if_single_color: false,
 			png_obfuscation: false,
+			color_type_and_bit_depth_reduction: true,
+			bit_depth_reduction_dithering: BitDepthReductionDithering::default(),
+			preserved_ancillary_chunks: EnumSet::empty(),
+			color_profile_conversion: false,
 			working_around_grayscale_reduction_quirk: false,
 			working_around_color_type_change_quirk: false,
 			working_around_transparent_pixel_colors_change_quirk: false,
-			minecraft_version_supports_png_obfuscation: true
+			minecraft_version_supports_png_obfuscation: true,
+			optimization_memory_limit: usize::MAX,
+			animation_frame_size: None,
+			is_atlas_texture: false
+		}
+	}
+}
+
+/// Deserializes [`PngFileOptions`] from a table that may contain a `preset` key with the name of
+/// a [`PngOptimizationPreset`]. The options bundled in that preset are used as defaults for the
+/// options that are not explicitly set in the table.
+fn deserialize_png_file_options<'de, D: Deserializer<'de>>(
+	deserializer: D
+) -> Result<PngFileOptions, D::Error> {
+	let mut options_table = Map::<String, Value>::deserialize(deserializer)?;
+
+	if let Some(preset) = options_table.remove("preset") {
+		let preset = PngOptimizationPreset::deserialize(preset).map_err(D::Error::custom)?;
+
+		for (option_name, option_value) in preset.option_values() {
+			options_table.entry(option_name).or_insert(option_value);
 		}
 	}
+
+	PngFileOptions::deserialize(Value::Object(options_table)).map_err(D::Error::custom)
+}
+
+/// A named bundle of PNG optimization options tuned for a kind of image, which may be selected
+/// for some files with the `preset` key of their options table.
+#[derive(Deserialize, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum PngOptimizationPreset {
+	/// Tuned for pixel art: images with hard edges and few flat colors. Color quantization is
+	/// only done if it does not visibly change the image, without dithering, which would add
+	/// noise to flat areas. The image data is not filtered, which usually compresses this kind
+	/// of image best, and textures are downscaled with the nearest neighbor filter.
+	PixelArt,
+	/// Tuned for photographs, paintings and other images with smooth gradients and many colors.
+	/// Color quantization is not done to avoid banding, the image data filters that work best
+	/// for continuous tones are tried, and textures are downscaled with the Lanczos3 filter.
+	Photo
+}
+
+impl PngOptimizationPreset {
+	/// Returns the names and values of the options bundled in this preset, as they would be
+	/// written in an options file.
+	fn option_values(self) -> [(String, Value); 4] {
+		let (
+			color_quantization_target,
+			color_quantization_dithering_level,
+			image_data_filter_strategy,
+			texture_downscaling_filter
+		) = match self {
+			Self::PixelArt => ("auto", 0.0, "no_filtering", "nearest"),
+			Self::Photo => ("none", 0.85, "adaptive", "lanczos3")
+		};
+
+		[
+			(
+				"color_quantization_target".into(),
+				color_quantization_target.into()
+			),
+			(
+				"color_quantization_dithering_level".into(),
+				color_quantization_dithering_level.into()
+			),
+			(
+				"image_data_filter_strategy".into(),
+				image_data_filter_strategy.into()
+			),
+			(
+				"texture_downscaling_filter".into(),
+				texture_downscaling_filter.into()
+			)
+		]
+	}
+}
+
+/// The sets of filters that may be tried on the image data of a PNG file before compressing it.
+/// PNG images are never interlaced, because Minecraft does not benefit from progressive loading
+/// and interlacing makes image data less compressible.
+#[derive(Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageDataFilterStrategy {
+	/// A variety of filters and filter selection heuristics that usually work well for any
+	/// kind of image are tried, and the best one is used.
+	#[default]
+	Auto,
+	/// The image data is not filtered. This usually works best for pixel art and images with
+	/// few colors, and is faster than trying several filters.
+	NoFiltering,
+	/// Several adaptive filter selection heuristics that choose the best filter for each row
+	/// of pixels are tried, and the best one is used. This usually works best for photographs
+	/// and images with smooth gradients.
+	Adaptive
 }
 
 /// Possible targets the colors of a PNG file will be quantized to.
@@ -1074,6 +2792,131 @@ impl ColorQuantizationTarget {
 	}
 }
 
+/// The resampling filters that can be used to downscale images.
+#[derive(Default, Deserialize, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum TextureDownscalingFilter {
+	/// Each pixel of the downscaled image takes the color of the closest pixel of the original
+	/// image. This filter preserves hard edges and does not introduce new colors, making it the
+	/// most suitable for pixel art.
+	Nearest,
+	/// Each pixel of the downscaled image is a linear interpolation of the surrounding pixels
+	/// of the original image. This filter yields smooth results, but tends to blur fine details.
+	Bilinear,
+	/// Each pixel of the downscaled image is computed with a windowed sinc function over the
+	/// surrounding pixels of the original image. This filter preserves fine details better
+	/// than bilinear filtering, making it the most suitable for paintings and photographs.
+	#[default]
+	Lanczos3
+}
+
+/// The ways in which textures whose dimensions reduce the mipmap levels of their atlas can be
+/// fixed.
+#[derive(Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MipmapLevelReductionFix {
+	/// Textures are only reported, not changed.
+	#[default]
+	None,
+	/// Transparent pixels are added to the right and bottom edges of textures, up to the
+	/// closest greater dimensions that allow the required mipmap levels. This does not
+	/// resample any pixel, but models will show the added transparent area.
+	Pad,
+	/// Textures are resized to the closest dimensions that allow the required mipmap levels,
+	/// using the `texture_downscaling_filter`. This keeps the texture covering the same model
+	/// area, but resampling may blur its pixels.
+	Resize
+}
+
+/// The ways in which the colors of completely transparent pixels can be normalized.
+#[derive(Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransparentPixelColorNormalization {
+	/// The colors of completely transparent pixels are chosen by the PNG optimizer to achieve
+	/// the best compression.
+	#[default]
+	Optimize,
+	/// The colors of completely transparent pixels are set to the specified RGB color.
+	Constant([u8; 3]),
+	/// The colors of completely transparent pixels are set to the average color of their
+	/// closest visible neighbors, which avoids dark fringes around the edges of visible areas
+	/// when Minecraft blends pixels together to generate mipmaps.
+	Bleed
+}
+
+/// The dithering methods that can be used to reduce the bit depth of images with 16 bits per
+/// channel to 8 bits per channel.
+#[derive(Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BitDepthReductionDithering {
+	/// No dithering is done: the 8 least significant bits of every channel value are discarded.
+	#[default]
+	None,
+	/// A fixed 8x8 Bayer threshold pattern is added to the channel values before reducing them.
+	/// The resulting patterns are regular and compress relatively well.
+	Ordered,
+	/// The error of reducing each channel value is diffused to the neighboring pixels, following
+	/// the Floyd-Steinberg algorithm. This usually looks best for photographic images, but
+	/// generates noisier patterns.
+	FloydSteinberg
+}
+
+/// An ancillary PNG chunk that can be preserved in the generated PNG files. Every chunk is
+/// named after its PNG chunk type.
+#[derive(Deserialize, Serialize, EnumSetType)]
+#[enumset(serialize_deny_unknown, serialize_repr = "list")]
+#[non_exhaustive]
+pub enum PngAncillaryChunk {
+	/// Textual data (`tEXt`), such as the author, copyright or license of the image.
+	#[serde(rename = "tEXt")]
+	Text,
+	/// Compressed textual data (`zTXt`).
+	#[serde(rename = "zTXt")]
+	CompressedText,
+	/// International textual data (`iTXt`), which may be compressed.
+	#[serde(rename = "iTXt")]
+	InternationalText,
+	/// The time of the last image modification (`tIME`).
+	#[serde(rename = "tIME")]
+	LastModificationTime,
+	/// The intended pixel size or aspect ratio of the image (`pHYs`).
+	#[serde(rename = "pHYs")]
+	PhysicalPixelDimensions,
+	/// Exif metadata (`eXIf`).
+	#[serde(rename = "eXIf")]
+	ExifMetadata,
+	/// An embedded ICC color profile (`iCCP`).
+	#[serde(rename = "iCCP")]
+	IccProfile,
+	/// The standard RGB color space rendering intent (`sRGB`).
+	#[serde(rename = "sRGB")]
+	StandardRgbColorSpace,
+	/// The primary chromaticities and white point of the image (`cHRM`).
+	#[serde(rename = "cHRM")]
+	PrimaryChromaticities,
+	/// The image gamma (`gAMA`).
+	#[serde(rename = "gAMA")]
+	ImageGamma
+}
+
+impl PngAncillaryChunk {
+	/// Returns the PNG chunk type of this ancillary chunk.
+	pub(crate) const fn chunk_type(&self) -> &'static [u8; 4] {
+		match self {
+			Self::Text => b"tEXt",
+			Self::CompressedText => b"zTXt",
+			Self::InternationalText => b"iTXt",
+			Self::LastModificationTime => b"tIME",
+			Self::PhysicalPixelDimensions => b"pHYs",
+			Self::ExifMetadata => b"eXIf",
+			Self::IccProfile => b"iCCP",
+			Self::StandardRgbColorSpace => b"sRGB",
+			Self::PrimaryChromaticities => b"cHRM",
+			Self::ImageGamma => b"gAMA"
+		}
+	}
+}
+
 /// A helper struct that contains an 32-bit floating point number guaranteed to be
 /// in the `[0, 1]` interval.
 #[derive(Deserialize, Clone, Copy)]
@@ -1098,6 +2941,48 @@ impl From<UnitIntervalFloat> for f32 {
 	}
 }
 
+/// A helper struct that contains a range of image quality values, in the `[0, 100]`
+/// interval, whose minimum is guaranteed to not be greater than its maximum.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(try_from = "[u8; 2]")]
+pub struct QuantizationQualityRange {
+	minimum: u8,
+	maximum: u8
+}
+
+impl QuantizationQualityRange {
+	/// Returns the minimum quality of this range.
+	pub const fn minimum(&self) -> u8 {
+		self.minimum
+	}
+
+	/// Returns the maximum quality of this range.
+	pub const fn maximum(&self) -> u8 {
+		self.maximum
+	}
+}
+
+impl Default for QuantizationQualityRange {
+	fn default() -> Self {
+		Self {
+			minimum: 0,
+			maximum: 100
+		}
+	}
+}
+
+impl TryFrom<[u8; 2]> for QuantizationQualityRange {
+	type Error = &'static str;
+
+	fn try_from([minimum, maximum]: [u8; 2]) -> Result<Self, Self::Error> {
+		(minimum <= maximum && maximum <= 100)
+			.then_some(Self { minimum, maximum })
+			.ok_or(
+				"The specified value is not a [min, max] quality range, where 0 <= min <= max <= 100"
+			)
+	}
+}
+
 /// Parameters that influence how a shader file is optimized.
 #[derive(Deserialize, Clone, Copy, Default)]
 #[serde(default, deny_unknown_fields)]
@@ -1131,7 +3016,51 @@ pub struct ShaderFileOptions {
 	/// it is the most appropriate course of action.
 	///
 	/// **Default value**: `None` (`true` for every vertex and fragment shader)
-	pub is_top_level_shader: Option<bool>
+	pub is_top_level_shader: Option<bool>,
+	/// If `true`, the function definitions, function prototypes and uniform variable declarations
+	/// that a top-level shader never references will be removed from it when its source code is
+	/// minified or prettified. GLSL compilers ignore such code, and Minecraft ignores the values
+	/// of uniforms that are not used, so removing it does not change how the shader works. This
+	/// is most useful when shader imports are inlined with [`GlobalOptions::inline_shader_imports`],
+	/// as imported shaders usually define many functions that a single shader does not need.
+	///
+	/// **Default value**: `false`
+	#[serde(rename = "strip_unused_shader_code")]
+	pub strip_unused_code: bool,
+	/// The newest GLSL version that vertex and fragment shaders may declare with their
+	/// `#version` directive, such as `150` for GLSL 1.50. Shaders that declare a newer version
+	/// will be rejected, as they may not work on the hardware some players use. The `#version`
+	/// directive is always checked to declare a desktop GLSL version with a valid profile.
+	///
+	/// **Default value**: `None` (the newest GLSL version every Minecraft version in
+	/// [GlobalOptions::target_minecraft_version_range] is guaranteed to support, or no maximum
+	/// if that option is not set)
+	pub maximum_glsl_version: Option<u16>,
+	/// Crate-private option set from [GlobalOptions::target_minecraft_version_range] with the
+	/// range of Minecraft versions whose supported GLSL versions shaders are validated against.
+	///
+	/// **Default value**: `None`
+	#[serde(skip)]
+	pub(crate) target_minecraft_version_range: Option<MinecraftVersionRange>,
+	/// If `true`, the source code of top-level vertex and fragment shaders will be compiled
+	/// to SPIR-V with the glslang reference compiler after processing, without adding the
+	/// compiled code to the output ZIP file, to check that it is valid for compilers stricter
+	/// than the OpenGL driver of the computer it was tested on. Shaders that fail to compile
+	/// are reported as errors. Shaders with imports that were not inlined, and shaders for GLSL
+	/// versions older than 1.50, are not compiled.
+	///
+	/// **Default value**: `false`
+	#[cfg(feature = "shader-compilation-check")]
+	#[doc(cfg(feature = "shader-compilation-check"))]
+	#[serde(rename = "shader_compilation_check")]
+	pub compilation_check: bool,
+	/// Crate-private option set from the relative path of the shader, which is `true` for
+	/// fragment shaders, to compile them as such when checking their compilation.
+	///
+	/// **Default value**: `false`
+	#[cfg(feature = "shader-compilation-check")]
+	#[serde(skip)]
+	pub(crate) is_fragment_shader: bool
 }
 
 /// A strategy that may be used to transform GLSL shader source code.
@@ -1149,6 +3078,11 @@ pub enum ShaderSourceTransformationStrategy {
 	/// comments and preprocessor directives) to save space and improve parsing performance.
 	#[default]
 	Minify,
+	/// Like [`Self::Minify`], but additionally rename the local variables, function parameters
+	/// and functions of top-level shaders to short identifiers, which saves more space and makes
+	/// the source code harder to understand. The names of uniforms, inputs, outputs, structures
+	/// and the `main` function are preserved, as the game and other shaders may refer to them.
+	MinifyAndRenameIdentifiers,
 	/// Attempt to prettify the shader (i.e., print it in an indented, human-readable form),
 	/// while expanding and removing preprocessor directives and comments.
 	Prettify,
@@ -1203,18 +3137,49 @@ impl Default for LegacyLanguageFileOptions {
 #[non_exhaustive]
 pub struct CommandFunctionFileOptions {
 	/// If `true`, the command function file will be minified: empty lines and comments will be
-	/// removed. This saves space and improves parsing performance. If `false`, the file will
-	/// still be validated for errors, but left as-is. Line endings are normalized to Unix style
-	/// (using a single LF character) no matter what.
+	/// removed, and the JSON text components of `tellraw` and `title` commands will have their
+	/// insignificant whitespace removed. This saves space and improves parsing performance. If
+	/// `false`, the file will still be validated for errors, but left as-is. Line endings are
+	/// normalized to Unix style (using a single LF character) no matter what.
 	///
 	/// **Default value**: `true` (minify)
 	#[serde(rename = "minify_command_function")]
-	pub minify: bool
+	pub minify: bool,
+	/// If `true`, the name of every command in the command function file will be checked to be
+	/// a vanilla command available in every Minecraft version targeted by the
+	/// `target_minecraft_version_range` option, or in any Minecraft version that supports data
+	/// packs if that option is not set. This catches misspelled or unavailable commands before
+	/// the data pack ships. Macro lines are not checked.
+	///
+	/// This is disabled by default because mods and plugins may add commands.
+	///
+	/// **Default value**: `false`
+	pub validate_command_names: bool,
+	/// If `true`, the text components that `tellraw` and `title` commands take, including those
+	/// run by `execute`, will be parsed and checked against the structure Minecraft expects for
+	/// them, both when written in JSON and in the SNBT syntax introduced in Minecraft 1.21.5.
+	/// This catches malformed components, such as misspelled keys of click and hover events,
+	/// before the data pack ships. Macro lines and commands that span several lines are not
+	/// checked.
+	///
+	/// **Default value**: `false`
+	pub validate_text_components: bool,
+	/// Crate-private option set from [GlobalOptions::target_minecraft_version_range] with the
+	/// range of Minecraft versions that command names are validated against.
+	///
+	/// **Default value**: `None`
+	#[serde(skip)]
+	pub(crate) target_minecraft_version_range: Option<MinecraftVersionRange>
 }
 
 impl Default for CommandFunctionFileOptions {
 	fn default() -> Self {
-		Self { minify: true }
+		Self {
+			minify: true,
+			validate_command_names: false,
+			validate_text_components: false,
+			target_minecraft_version_range: None
+		}
 	}
 }
 
@@ -1236,13 +3201,52 @@ pub struct CompressedCompoundNbtTagFileOptions {
 	/// performance levels.
 	///
 	/// **Default value**: `15`
-	pub nbt_compression_iterations: u8
+	pub nbt_compression_iterations: u8,
+	/// If `true`, tags of structure files that Minecraft ignores or treats the same as if they
+	/// were missing will be removed: the `author` tag, which only old Minecraft versions show in
+	/// structure blocks, empty entity lists, and empty block state property compounds.
+	///
+	/// **Default value**: `true`
+	pub strip_redundant_structure_tags: bool
 }
 
 impl Default for CompressedCompoundNbtTagFileOptions {
 	fn default() -> Self {
 		Self {
-			nbt_compression_iterations: 15
+			nbt_compression_iterations: 15,
+			strip_redundant_structure_tags: true
+		}
+	}
+}
+
+/// Parameters that influence how a ZIP file of Unifont `.hex` glyphs, read by `unihex` font
+/// providers, is optimized.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ZippedUnifontHexFileOptions {
+	/// If `true`, the `.hex` files inside the ZIP file will be merged into a single file, sorted
+	/// by codepoint and without glyphs defined several times, which usually compresses better.
+	/// Files are never merged if they define some glyph differently, as Minecraft uses the
+	/// definition of the last file it reads, which depends on their order in the ZIP file.
+	/// Regardless of this option, files without a `.hex` extension are always removed, because
+	/// Minecraft ignores them, and the remaining files are recompressed.
+	///
+	/// **Default value**: `true`
+	pub merge_unifont_hex_files: bool,
+	/// Crate-private option set from [GlobalOptions::zip_compression_iterations] with the
+	/// number of Zopfli compression iterations to recompress the `.hex` files with.
+	///
+	/// **Default value**: `20`
+	#[serde(skip)]
+	pub(crate) compression_iterations: u8
+}
+
+impl Default for ZippedUnifontHexFileOptions {
+	fn default() -> Self {
+		Self {
+			merge_unifont_hex_files: true,
+			compression_iterations: 20
 		}
 	}
 }
@@ -1258,18 +3262,30 @@ impl Default for CompressedCompoundNbtTagFileOptions {
 #[doc(cfg(feature = "optifine"))]
 pub struct PropertiesFileOptions {
 	/// If `true`, the properties file will be minified (i.e. unnecessary white space, line breaks
-	/// and comments will be removed) to save space and improve parsing performance. If `false`,
-	/// the properties file will still be validated for errors, but left as-is.
+	/// and comments will be removed) to save space and improve parsing performance. Keys will also
+	/// be sorted, only the last value of keys defined several times will be kept, and texture paths
+	/// of OptiFine custom item texture properties files will be shortened to their simplest
+	/// equivalent form. If `false`, the properties file will still be validated for errors, but
+	/// left as-is.
 	///
 	/// **Default value**: `true` (minify)
 	#[serde(rename = "minify_properties")]
-	pub minify: bool
+	pub minify: bool,
+	/// Crate-private option set from the relative path of the properties file when it is an
+	/// OptiFine custom item texture (CIT) properties file, whose texture paths may be shortened.
+	///
+	/// **Default value**: `false`
+	#[serde(skip)]
+	pub(crate) is_custom_item_texture_properties: bool
 }
 
 #[cfg(feature = "optifine")]
 impl Default for PropertiesFileOptions {
 	fn default() -> Self {
-		Self { minify: true }
+		Self {
+			minify: true,
+			is_custom_item_texture_properties: false
+		}
 	}
 }
 
@@ -1280,10 +3296,93 @@ impl Default for PropertiesFileOptions {
 #[serde(deny_unknown_fields)]
 #[non_exhaustive]
 pub struct CustomFileOptions {
-	/// If `true`, the custom file will be copied to the generated ZIP file as-is,
-	/// without any specific optimizations. A `false` value explicitly asks for
-	/// the default behavior of skipping the file.
-	pub force_include: bool
+	/// If `true`, the custom file will be added to the generated ZIP file, processed
+	/// according to `custom_file_format`. A `false` value explicitly asks for the
+	/// default behavior of skipping the file.
+	pub force_include: bool,
+	/// The format of the custom file, which determines how it is processed when
+	/// `force_include` is `true`.
+	///
+	/// **Default value**: `opaque` (the file is copied as-is)
+	#[serde(default)]
+	pub custom_file_format: CustomFileFormat
+}
+
+/// The built-in file types that pack files can be routed to, regardless of their extension or
+/// location, contained in [`GlobalOptions`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum FileTypeRoute {
+	/// A JSON file, processed with the JSON file options.
+	Json,
+	/// A JSON file that may contain comments, processed with the JSON file options. As the
+	/// comments are removed, routed files get the `.json` extension.
+	JsonWithComments,
+	/// A texture in PNG format, processed with the PNG file options.
+	Png,
+	/// A texture in WebP format, processed with the PNG file options. As it is converted to
+	/// PNG, routed files get the `.png` extension.
+	Webp,
+	/// An audio file in Ogg Vorbis format, processed with the audio file options. Routed files
+	/// get the `.ogg` extension.
+	OggVorbisAudio,
+	/// An audio file in a supported format other than Ogg Vorbis, processed with the audio file
+	/// options. As it is converted to Ogg Vorbis, routed files get the `.ogg` extension.
+	Audio,
+	/// A GLSL vertex shader, processed with the shader file options.
+	VertexShader,
+	/// A GLSL fragment shader, processed with the shader file options.
+	FragmentShader,
+	/// A segment of GLSL code that is included in other shaders, processed with the shader file
+	/// options.
+	ShaderInclude,
+	/// A legacy language strings file, processed with the legacy language file options.
+	LegacyLanguage,
+	/// A UTF-8 plain text file, which is copied as-is, without any specific optimizations.
+	Text,
+	/// A properties file added by OptiFine, processed with the properties file options.
+	#[cfg(feature = "optifine")]
+	#[doc(cfg(feature = "optifine"))]
+	Properties,
+	/// A gzip-compressed NBT structure file, processed with the compressed compound NBT tag
+	/// file options.
+	NbtStructure,
+	/// A data pack command function, processed with the command function file options.
+	CommandFunction
+}
+
+/// The formats of custom files that PackSquash knows how to process.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum CustomFileFormat {
+	/// The file contents are opaque to PackSquash, so the file is copied as-is, without any
+	/// specific optimizations.
+	#[default]
+	Opaque,
+	/// The file is a TOML document, which is validated and minified by removing comments,
+	/// whitespace and table headers, writing nested tables as inline tables instead.
+	Toml,
+	/// The file is a ZIP archive, such as a JAR file. Its entries are processed as if they
+	/// were pack files at the same relative path within the archive, using the file options
+	/// that match that path, and the archive is then recompressed with a deterministic layout.
+	/// Entries that are not recognized as pack files are kept as-is, and entries whose file
+	/// options make them ZIP archives too are processed the same way.
+	ZipArchive
+}
+
+/// Adds the specified file options for a glob pattern before any other file options, so
+/// that the file options configured by the user for the files it matches take precedence,
+/// unless the user already configured file options for that exact glob pattern.
+fn add_default_file_options(
+	file_options: &mut IndexMap<String, FileOptions>,
+	glob_pattern: String,
+	default_file_options: FileOptions
+) {
+	if !file_options.contains_key(&glob_pattern) {
+		file_options.shift_insert(0, glob_pattern, default_file_options);
+	}
 }
 
 /// Compiles the specified glob pattern to a matcher that is ready to consume
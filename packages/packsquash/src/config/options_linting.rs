@@ -0,0 +1,191 @@
+//! Contains routines to find combinations of options that contradict each other or have no
+//! effect, which are valid but are likely to not do what the user intended.
+
+use std::fmt::{self, Display, Formatter};
+
+use super::{FileOptions, FileTypeRoute, SquashOptions, ZipSpecConformanceLevel};
+
+#[cfg(test)]
+mod tests;
+
+/// An option, or combination of options, that is valid but contradictory or ineffective.
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptionsLint {
+	/// A per-file options table contains options for a file type, but its glob pattern only
+	/// matches extensions of files of other types, so the options never apply.
+	InapplicableFileOptions {
+		/// The glob pattern of the per-file options table.
+		glob_pattern: String,
+		/// The file type the options are for.
+		file_type: &'static str
+	},
+	/// A ZIP obfuscation option is set, but the ZIP specification conformance level does not
+	/// allow obfuscating the ZIP file.
+	IneffectiveZipObfuscationOption(&'static str),
+	/// Storing squash times is disabled, but the ZIP specification conformance level already
+	/// does not allow storing them.
+	IneffectiveSquashTimesOption
+}
+
+impl Display for OptionsLint {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::InapplicableFileOptions {
+				glob_pattern,
+				file_type
+			} => write!(
+				f,
+				"The file options table for '{glob_pattern}' contains {file_type} file options, \
+				but its pattern only matches files of other types, so they will never apply"
+			),
+			Self::IneffectiveZipObfuscationOption(key) => write!(
+				f,
+				"The `{key}` option has no effect because `zip_spec_conformance_level` is not \
+				`disregard`, which is needed to obfuscate the ZIP file"
+			),
+			Self::IneffectiveSquashTimesOption => write!(
+				f,
+				"The `never_store_squash_times` option has no effect because squash times are \
+				never stored when `zip_spec_conformance_level` is `pedantic`"
+			)
+		}
+	}
+}
+
+/// Finds the options of the specified squash options that are contradictory or have no
+/// effect, which is meant to be done before squashing to warn the user about them.
+pub fn lint_options(options: &SquashOptions) -> Vec<OptionsLint> {
+	let global_options = &options.global_options;
+	let mut options_lints = vec![];
+
+	// Routed pack files may have any extension, so file options for the file types pack files
+	// are routed to may apply to any pattern
+	let routed_file_types = global_options
+		.file_type_routes
+		.values()
+		.filter_map(|route| routed_file_type(*route))
+		.collect::<Vec<_>>();
+
+	for (glob_pattern, file_options) in &options.file_options {
+		if let Some((file_type, file_type_extensions)) = file_options_file_type(file_options)
+			&& !routed_file_types.contains(&file_type)
+			&& let Some(matched_extensions) = matched_extensions(glob_pattern)
+			&& !matched_extensions
+				.iter()
+				.any(|extension| file_type_extensions.contains(extension))
+		{
+			options_lints.push(OptionsLint::InapplicableFileOptions {
+				glob_pattern: glob_pattern.clone(),
+				file_type
+			});
+		}
+	}
+
+	if !matches!(
+		global_options.zip_spec_conformance_level,
+		ZipSpecConformanceLevel::Disregard
+	) {
+		if global_options.size_increasing_zip_obfuscation {
+			options_lints.push(OptionsLint::IneffectiveZipObfuscationOption(
+				"size_increasing_zip_obfuscation"
+			));
+		}
+
+		if u8::from(global_options.percentage_of_zip_structures_tuned_for_obfuscation_discretion) > 0
+		{
+			options_lints.push(OptionsLint::IneffectiveZipObfuscationOption(
+				"percentage_of_zip_structures_tuned_for_obfuscation_discretion"
+			));
+		}
+	}
+
+	if global_options.never_store_squash_times
+		&& matches!(
+			global_options.zip_spec_conformance_level,
+			ZipSpecConformanceLevel::Pedantic
+		) {
+		options_lints.push(OptionsLint::IneffectiveSquashTimesOption);
+	}
+
+	options_lints
+}
+
+/// Returns the file type the specified file options are for, and the extensions of the pack
+/// files of that type, or `None` if they may be for files with any extension.
+fn file_options_file_type(
+	file_options: &FileOptions
+) -> Option<(&'static str, &'static [&'static str])> {
+	match file_options {
+		FileOptions::AudioFileOptions(_) => {
+			Some(("audio", &["ogg", "oga", "mp3", "flac", "wav", "m4a"]))
+		}
+		FileOptions::JsonFileOptions(_) => Some((
+			"JSON",
+			&[
+				"json", "jsonc", "mcmeta", "mcmetac", "jem", "jemc", "jpm", "jpmc", "bbmodel",
+				"bbmodelc"
+			]
+		)),
+		FileOptions::PngFileOptions(_) => Some(("PNG", &["png", "webp"])),
+		FileOptions::ShaderFileOptions(_) => Some(("shader", &["vsh", "fsh", "glsl"])),
+		#[cfg(feature = "optifine")]
+		FileOptions::PropertiesFileOptions(_) => Some(("properties", &["properties"])),
+		FileOptions::LegacyLanguageFileOptions(_) => Some(("legacy language", &["lang"])),
+		FileOptions::CommandFunctionFileOptions(_) => Some(("command function", &["mcfunction"])),
+		FileOptions::CompressedCompoundNbtTagFileOptions(_) => {
+			Some(("compressed compound NBT tag", &["nbt", "dat", "mca"]))
+		}
+		FileOptions::ZippedUnifontHexFileOptions(_) => Some(("zipped Unifont", &["zip"])),
+		FileOptions::CustomFileOptions(_) => None
+	}
+}
+
+/// Returns the file type whose file options apply to the pack files routed to the specified
+/// built-in file type, as returned by [`file_options_file_type`], if any.
+fn routed_file_type(route: FileTypeRoute) -> Option<&'static str> {
+	match route {
+		FileTypeRoute::Json | FileTypeRoute::JsonWithComments => Some("JSON"),
+		FileTypeRoute::Png | FileTypeRoute::Webp => Some("PNG"),
+		FileTypeRoute::OggVorbisAudio | FileTypeRoute::Audio => Some("audio"),
+		FileTypeRoute::VertexShader
+		| FileTypeRoute::FragmentShader
+		| FileTypeRoute::ShaderInclude => Some("shader"),
+		FileTypeRoute::LegacyLanguage => Some("legacy language"),
+		FileTypeRoute::Text => None,
+		#[cfg(feature = "optifine")]
+		FileTypeRoute::Properties => Some("properties"),
+		FileTypeRoute::NbtStructure => Some("compressed compound NBT tag"),
+		FileTypeRoute::CommandFunction => Some("command function")
+	}
+}
+
+/// Returns the file extensions that the paths matched by the specified glob pattern may have,
+/// when the pattern ends with a literal extension or a set of alternative literal extensions,
+/// such as `*.ogg` or `*.{ogg,oga}`. `None` is returned if the pattern may match paths with
+/// other extensions.
+fn matched_extensions(glob_pattern: &str) -> Option<Vec<&str>> {
+	if glob_pattern.starts_with('!') {
+		return None;
+	}
+
+	let file_name_pattern = glob_pattern.rsplit('/').next()?;
+	let (_, extension_pattern) = file_name_pattern.rsplit_once('.')?;
+	let extensions = match extension_pattern
+		.strip_prefix('{')
+		.and_then(|extension_pattern| extension_pattern.strip_suffix('}'))
+	{
+		Some(alternative_extensions) => alternative_extensions.split(',').collect::<Vec<_>>(),
+		None => vec![extension_pattern]
+	};
+
+	extensions
+		.iter()
+		.all(|extension| {
+			!extension.is_empty()
+				&& extension
+					.chars()
+					.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+		})
+		.then_some(extensions)
+}
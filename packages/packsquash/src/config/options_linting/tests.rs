@@ -0,0 +1,123 @@
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+
+use super::*;
+use crate::config::{
+	AudioFileOptions, CustomFileFormat, CustomFileOptions, GlobalOptions, JsonFileOptions,
+	PngFileOptions
+};
+
+fn squash_options(
+	global_options: GlobalOptions,
+	file_options: impl IntoIterator<Item = (&'static str, FileOptions)>
+) -> SquashOptions {
+	SquashOptions {
+		pack_directory: "pack".into(),
+		global_options,
+		file_options: file_options
+			.into_iter()
+			.map(|(glob_pattern, file_options)| (glob_pattern.to_string(), file_options))
+			.collect()
+	}
+}
+
+#[test]
+fn default_options_have_no_lints() {
+	assert_eq!(
+		lint_options(&squash_options(GlobalOptions::default(), [])),
+		vec![]
+	);
+}
+
+#[test]
+fn file_options_for_other_file_types_are_linted() {
+	let options = squash_options(
+		GlobalOptions::default(),
+		[
+			(
+				"**/*?.ogg",
+				FileOptions::PngFileOptions(PngFileOptions::default())
+			),
+			(
+				"**/*?.{ogg,oga}",
+				FileOptions::AudioFileOptions(AudioFileOptions::default())
+			),
+			(
+				"assets/*/models/**/*?.{json,txt}",
+				FileOptions::JsonFileOptions(JsonFileOptions::default())
+			),
+			(
+				"**/*?.txt",
+				FileOptions::CustomFileOptions(CustomFileOptions {
+					force_include: true,
+					custom_file_format: CustomFileFormat::default()
+				})
+			),
+			(
+				"**/*?.og?",
+				FileOptions::PngFileOptions(PngFileOptions::default())
+			),
+			(
+				"!**/*?.png",
+				FileOptions::PngFileOptions(PngFileOptions::default())
+			)
+		]
+	);
+
+	assert_eq!(
+		lint_options(&options),
+		vec![OptionsLint::InapplicableFileOptions {
+			glob_pattern: "**/*?.ogg".into(),
+			file_type: "PNG"
+		}]
+	);
+}
+
+#[test]
+fn file_options_for_routed_file_types_are_not_linted() {
+	let options = squash_options(
+		GlobalOptions {
+			file_type_routes: IndexMap::from([("**/*?.tex".into(), FileTypeRoute::Png)]),
+			..Default::default()
+		},
+		[(
+			"**/*?.tex",
+			FileOptions::PngFileOptions(PngFileOptions::default())
+		)]
+	);
+
+	assert_eq!(lint_options(&options), vec![]);
+}
+
+#[test]
+fn ineffective_zip_options_are_linted() {
+	let options = squash_options(
+		GlobalOptions {
+			zip_spec_conformance_level: ZipSpecConformanceLevel::Pedantic,
+			size_increasing_zip_obfuscation: true,
+			never_store_squash_times: true,
+			..Default::default()
+		},
+		[]
+	);
+
+	assert_eq!(
+		lint_options(&options),
+		vec![
+			OptionsLint::IneffectiveZipObfuscationOption("size_increasing_zip_obfuscation"),
+			OptionsLint::IneffectiveSquashTimesOption
+		]
+	);
+
+	let options = squash_options(
+		GlobalOptions {
+			zip_spec_conformance_level: ZipSpecConformanceLevel::Disregard,
+			size_increasing_zip_obfuscation: true,
+			never_store_squash_times: true,
+			..Default::default()
+		},
+		[]
+	);
+
+	assert_eq!(lint_options(&options), vec![]);
+}
@@ -0,0 +1,158 @@
+//! Contains routines to migrate the options that previous PackSquash versions accepted, but
+//! were renamed or removed since then, to the current options.
+
+use std::fmt::{self, Display, Formatter};
+
+use serde_json::{Map, Value, json};
+
+use super::options_schema::global_option_keys;
+
+#[cfg(test)]
+mod tests;
+
+/// An option that PackSquash no longer accepts, and how to migrate it.
+struct DeprecatedOption {
+	/// Whether this option was a per-file option, rather than a global option.
+	per_file: bool,
+	/// The key of the option.
+	key: &'static str,
+	/// Computes the key and value of the option that replaces this option from its value, or
+	/// `None` if this option has no replacement.
+	replacement: fn(&Value) -> Option<(&'static str, Value)>,
+	/// Why this option is no longer accepted.
+	reason: &'static str
+}
+
+/// The options that PackSquash no longer accepts.
+const DEPRECATED_OPTIONS: &[DeprecatedOption] = &[
+	DeprecatedOption {
+		per_file: false,
+		key: "open_files_limit",
+		replacement: |_| None,
+		reason: "the open files limit is now raised automatically when needed"
+	},
+	DeprecatedOption {
+		per_file: true,
+		key: "minimum_bitrate",
+		replacement: |_| None,
+		reason: "it was superseded by the `bitrate_control_mode` and \
+			`target_bitrate_control_metric` options"
+	},
+	DeprecatedOption {
+		per_file: true,
+		key: "maximum_bitrate",
+		replacement: |_| None,
+		reason: "it was superseded by the `bitrate_control_mode` and \
+			`target_bitrate_control_metric` options"
+	},
+	DeprecatedOption {
+		per_file: true,
+		key: "minify_shader",
+		replacement: |minify_shader| {
+			Some((
+				"shader_source_transformation_strategy",
+				json!(if minify_shader.as_bool() == Some(false) {
+					"keep_as_is"
+				} else {
+					"minify"
+				})
+			))
+		},
+		reason: "it was superseded by the `shader_source_transformation_strategy` option"
+	}
+];
+
+/// A deprecated option of an options file that was migrated to the current options.
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub struct OptionMigration {
+	/// The glob pattern of the per-file options table that contains the option, or `None`
+	/// if it is a global option.
+	pub file_options_glob_pattern: Option<String>,
+	/// The key of the deprecated option, which was removed.
+	pub deprecated_key: &'static str,
+	/// The key and value of the option that replaces the deprecated option, if any. The
+	/// replacement option is not added if its key was already present.
+	pub replacement: Option<(&'static str, Value)>,
+	/// Why the deprecated option is no longer accepted.
+	pub reason: &'static str
+}
+
+impl Display for OptionMigration {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "The deprecated option `{}`", self.deprecated_key)?;
+
+		if let Some(glob_pattern) = &self.file_options_glob_pattern {
+			write!(f, " in the file options table for '{glob_pattern}'")?;
+		}
+
+		match &self.replacement {
+			Some((key, value)) => write!(f, " was replaced by `{key} = {value}`")?,
+			None => write!(f, " was ignored")?
+		}
+
+		write!(
+			f,
+			", as {}. Please update the options file accordingly",
+			self.reason
+		)
+	}
+}
+
+/// Migrates the deprecated options of the specified options file table to the current options,
+/// returning the migrations that were done. Tables whose key is not a global option are
+/// considered per-file options tables, and other values global options.
+pub fn migrate_options(options: &mut Map<String, Value>) -> Vec<OptionMigration> {
+	let global_option_keys = global_option_keys().collect::<Vec<_>>();
+	let mut option_migrations = vec![];
+
+	let per_file_options_tables = options
+		.iter_mut()
+		.filter(|(key, _)| !global_option_keys.contains(&key.as_str()))
+		.filter_map(|(key, value)| Some((Some(key.clone()), value.as_object_mut()?)))
+		.collect::<Vec<_>>();
+
+	for (file_options_glob_pattern, options_table) in per_file_options_tables {
+		migrate_options_table(
+			options_table,
+			file_options_glob_pattern,
+			true,
+			&mut option_migrations
+		);
+	}
+
+	migrate_options_table(options, None, false, &mut option_migrations);
+
+	option_migrations
+}
+
+/// Migrates the deprecated per-file or global options of the specified table, as specified.
+fn migrate_options_table(
+	options_table: &mut Map<String, Value>,
+	file_options_glob_pattern: Option<String>,
+	per_file: bool,
+	option_migrations: &mut Vec<OptionMigration>
+) {
+	for deprecated_option in DEPRECATED_OPTIONS
+		.iter()
+		.filter(|deprecated_option| deprecated_option.per_file == per_file)
+	{
+		let Some(value) = options_table.shift_remove(deprecated_option.key) else {
+			continue;
+		};
+
+		let replacement = (deprecated_option.replacement)(&value);
+		if let Some((key, value)) = &replacement
+			&& !options_table.contains_key(*key)
+		{
+			options_table.insert(key.to_string(), value.clone());
+		}
+
+		option_migrations.push(OptionMigration {
+			file_options_glob_pattern: file_options_glob_pattern.clone(),
+			deprecated_key: deprecated_option.key,
+			replacement,
+			reason: deprecated_option.reason
+		});
+	}
+}
@@ -0,0 +1,81 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+fn migrate(options: Value) -> (Value, Vec<OptionMigration>) {
+	let Value::Object(mut options) = options else {
+		unreachable!("The options should be a table");
+	};
+
+	let option_migrations = migrate_options(&mut options);
+
+	(Value::Object(options), option_migrations)
+}
+
+#[test]
+fn current_options_are_left_as_is() {
+	let options = json!({
+		"pack_directory": "pack",
+		"template_variables": { "minify_shader": "not an option" },
+		"**/*?.fsh": { "shader_source_transformation_strategy": "prettify" }
+	});
+
+	assert_eq!(migrate(options.clone()), (options, vec![]));
+}
+
+#[test]
+fn deprecated_options_are_migrated() {
+	let (migrated_options, option_migrations) = migrate(json!({
+		"pack_directory": "pack",
+		"open_files_limit": 512,
+		"**/*?.fsh": { "minify_shader": false },
+		"**/*?.ogg": { "minimum_bitrate": 40000, "transcode_ogg": true }
+	}));
+
+	assert_eq!(
+		migrated_options,
+		json!({
+			"pack_directory": "pack",
+			"**/*?.fsh": { "shader_source_transformation_strategy": "keep_as_is" },
+			"**/*?.ogg": { "transcode_ogg": true }
+		})
+	);
+	assert_eq!(
+		option_migrations
+			.iter()
+			.map(|option_migration| (
+				option_migration.file_options_glob_pattern.as_deref(),
+				option_migration.deprecated_key,
+				option_migration.replacement.clone()
+			))
+			.collect::<Vec<_>>(),
+		[
+			(
+				Some("**/*?.fsh"),
+				"minify_shader",
+				Some(("shader_source_transformation_strategy", json!("keep_as_is")))
+			),
+			(Some("**/*?.ogg"), "minimum_bitrate", None),
+			(None, "open_files_limit", None)
+		]
+	);
+}
+
+#[test]
+fn replacements_do_not_override_current_options() {
+	let (migrated_options, _) = migrate(json!({
+		"pack_directory": "pack",
+		"**/*?.fsh": {
+			"shader_source_transformation_strategy": "prettify",
+			"minify_shader": true
+		}
+	}));
+
+	assert_eq!(
+		migrated_options,
+		json!({
+			"pack_directory": "pack",
+			"**/*?.fsh": { "shader_source_transformation_strategy": "prettify" }
+		})
+	);
+}
@@ -0,0 +1,234 @@
+//! Contains routines to describe the keys accepted by options files as a JSON Schema, and to
+//! find unknown keys in them with suggestions of known keys that were probably meant instead.
+
+use std::fmt::{self, Display, Formatter};
+
+use serde::de::{Deserialize, Deserializer, Error as _, Visitor, value};
+use serde::forward_to_deserialize_any;
+use serde_json::{Map, Value, json};
+
+#[cfg(feature = "optifine")]
+use super::PropertiesFileOptions;
+use super::{
+	AudioFileOptions, CommandFunctionFileOptions, CompressedCompoundNbtTagFileOptions,
+	CustomFileOptions, GlobalOptions, JsonFileOptions, LegacyLanguageFileOptions, PngFileOptions,
+	ShaderFileOptions, ZippedUnifontHexFileOptions
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The key of the only option of an options file that is not a global or per-file option.
+const PACK_DIRECTORY_KEY: &str = "pack_directory";
+
+/// The minimum Jaro-Winkler similarity between an unknown key and a known key for the
+/// known key to be suggested as the key that was probably meant.
+const SUGGESTION_SIMILARITY_THRESHOLD: f64 = 0.8;
+
+/// An option of an options file that PackSquash does not know about, usually due to a typo
+/// or to using a per-file option as a global option or vice versa.
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct UnknownOption {
+	/// The glob pattern of the per-file options table that contains the option, or `None`
+	/// if it is a global option.
+	pub file_options_glob_pattern: Option<String>,
+	/// The key of the unknown option.
+	pub key: String,
+	/// The most similar known option key, which was probably meant instead, if any.
+	pub suggestion: Option<&'static str>
+}
+
+impl Display for UnknownOption {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match &self.file_options_glob_pattern {
+			Some(glob_pattern) => write!(
+				f,
+				"Unknown option `{}` in the file options table for '{glob_pattern}'",
+				self.key
+			)?,
+			None => write!(f, "Unknown global option `{}`", self.key)?
+		}
+
+		match self.suggestion {
+			Some(suggestion) => write!(f, ". Did you mean `{suggestion}`?"),
+			None => Ok(())
+		}
+	}
+}
+
+/// Returns a JSON Schema that describes the keys accepted by options files, meant to be used
+/// by editors to offer completions and flag unknown keys. The values of the options are not
+/// described, as PackSquash validates them when reading the options.
+pub fn options_json_schema() -> Value {
+	let global_option_properties = global_option_keys()
+		.map(|key| (key.to_string(), json!({})))
+		.collect::<Map<_, _>>();
+
+	let file_options_schemas = file_options_kinds()
+		.into_iter()
+		.map(|(title, keys)| {
+			json!({
+				"title": title,
+				"type": "object",
+				"properties": keys
+					.iter()
+					.map(|key| (key.to_string(), json!({})))
+					.collect::<Map<_, _>>(),
+				"additionalProperties": false
+			})
+		})
+		.collect::<Vec<_>>();
+
+	json!({
+		"$schema": "https://json-schema.org/draft/2020-12/schema",
+		"title": "PackSquash options file",
+		"type": "object",
+		"properties": global_option_properties,
+		"required": [PACK_DIRECTORY_KEY],
+		"additionalProperties": {
+			"anyOf": file_options_schemas
+		}
+	})
+}
+
+/// Finds the options of the specified options file table that PackSquash does not know about,
+/// suggesting similar known options for them. Tables whose key is not a global option are
+/// considered per-file options tables, and other values global options.
+pub fn find_unknown_options(options: &Map<String, Value>) -> Vec<UnknownOption> {
+	let global_option_keys = global_option_keys().collect::<Vec<_>>();
+	let file_option_keys = file_options_kinds()
+		.into_iter()
+		.flat_map(|(_, keys)| keys)
+		.collect::<Vec<_>>();
+
+	let mut unknown_options = vec![];
+	for (key, value) in options {
+		if global_option_keys.contains(&key.as_str()) {
+			continue;
+		}
+
+		match value {
+			Value::Object(file_options) => unknown_options.extend(
+				file_options
+					.keys()
+					.filter(|key| !file_option_keys.contains(&key.as_str()))
+					.map(|option_key| UnknownOption {
+						file_options_glob_pattern: Some(key.clone()),
+						key: option_key.clone(),
+						suggestion: most_similar_key(option_key, &file_option_keys)
+					})
+			),
+			_ => unknown_options.push(UnknownOption {
+				file_options_glob_pattern: None,
+				key: key.clone(),
+				suggestion: most_similar_key(key, &global_option_keys)
+			})
+		}
+	}
+
+	unknown_options
+}
+
+/// Returns the keys of the options that may be set at the top level of an options file.
+pub(super) fn global_option_keys() -> impl Iterator<Item = &'static str> {
+	[PACK_DIRECTORY_KEY]
+		.into_iter()
+		.chain(struct_field_names::<GlobalOptions>().iter().copied())
+}
+
+/// Returns a human-readable title and the accepted keys of every kind of per-file options.
+fn file_options_kinds() -> Vec<(&'static str, Vec<&'static str>)> {
+	vec![
+		(
+			"Audio file options",
+			struct_field_names::<AudioFileOptions>().to_vec()
+		),
+		(
+			"JSON file options",
+			struct_field_names::<JsonFileOptions>().to_vec()
+		),
+		(
+			"PNG file options",
+			// Presets are handled before deserializing the rest of the options
+			[&["preset"], struct_field_names::<PngFileOptions>()].concat()
+		),
+		(
+			"Shader file options",
+			struct_field_names::<ShaderFileOptions>().to_vec()
+		),
+		#[cfg(feature = "optifine")]
+		(
+			"Properties file options",
+			struct_field_names::<PropertiesFileOptions>().to_vec()
+		),
+		(
+			"Legacy language file options",
+			struct_field_names::<LegacyLanguageFileOptions>().to_vec()
+		),
+		(
+			"Command function file options",
+			struct_field_names::<CommandFunctionFileOptions>().to_vec()
+		),
+		(
+			"Compressed compound NBT tag file options",
+			struct_field_names::<CompressedCompoundNbtTagFileOptions>().to_vec()
+		),
+		(
+			"Zipped Unifont HEX file options",
+			struct_field_names::<ZippedUnifontHexFileOptions>().to_vec()
+		),
+		(
+			"Custom file options",
+			struct_field_names::<CustomFileOptions>().to_vec()
+		),
+	]
+}
+
+/// Returns the most similar known key to the specified unknown key, if it is similar enough.
+fn most_similar_key(unknown_key: &str, known_keys: &[&'static str]) -> Option<&'static str> {
+	known_keys
+		.iter()
+		.map(|known_key| (*known_key, strsim::jaro_winkler(unknown_key, known_key)))
+		.filter(|(_, similarity)| *similarity >= SUGGESTION_SIMILARITY_THRESHOLD)
+		.max_by(|(_, a), (_, b)| a.total_cmp(b))
+		.map(|(known_key, _)| known_key)
+}
+
+/// Returns the names of the fields that the derived [`Deserialize`] implementation of a struct
+/// accepts, which serde passes to the deserializer when deserializing the struct.
+fn struct_field_names<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+	/// A deserializer that only records the field names of the struct it is asked to
+	/// deserialize, failing afterward.
+	struct FieldNamesDeserializer<'names>(&'names mut &'static [&'static str]);
+
+	impl<'de> Deserializer<'de> for FieldNamesDeserializer<'_> {
+		type Error = value::Error;
+
+		fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+			Err(Self::Error::custom("only structs are supported"))
+		}
+
+		fn deserialize_struct<V: Visitor<'de>>(
+			self,
+			_name: &'static str,
+			fields: &'static [&'static str],
+			_visitor: V
+		) -> Result<V::Value, Self::Error> {
+			*self.0 = fields;
+
+			Err(Self::Error::custom("field names recorded"))
+		}
+
+		forward_to_deserialize_any! {
+			bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+			option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+			ignored_any
+		}
+	}
+
+	let mut field_names: &'static [&'static str] = &[];
+	let _ = T::deserialize(FieldNamesDeserializer(&mut field_names));
+
+	field_names
+}
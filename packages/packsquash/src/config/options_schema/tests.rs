@@ -0,0 +1,78 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+fn unknown_options_of(options: Value) -> Vec<UnknownOption> {
+	let Value::Object(options) = options else {
+		unreachable!("The options should be a table");
+	};
+
+	find_unknown_options(&options)
+}
+
+#[test]
+fn known_options_are_accepted() {
+	assert_eq!(
+		unknown_options_of(json!({
+			"pack_directory": "pack",
+			"zip_spec_conformance_level": "pedantic",
+			"**/*?.json": { "minify_json": false },
+			"**/*?.png": { "preset": "pixel_art", "color_quantization_target": "none" },
+			"**/*?.ogg": { "transcode_ogg": false }
+		})),
+		[]
+	);
+}
+
+#[test]
+fn unknown_global_options_are_reported_with_suggestions() {
+	assert_eq!(
+		unknown_options_of(json!({
+			"pack_directory": "pack",
+			"zip_spec_conformance_levle": "pedantic",
+			"frobnicate": true
+		})),
+		[
+			UnknownOption {
+				file_options_glob_pattern: None,
+				key: "zip_spec_conformance_levle".into(),
+				suggestion: Some("zip_spec_conformance_level")
+			},
+			UnknownOption {
+				file_options_glob_pattern: None,
+				key: "frobnicate".into(),
+				suggestion: None
+			}
+		]
+	);
+}
+
+#[test]
+fn unknown_file_options_are_reported_with_suggestions() {
+	assert_eq!(
+		unknown_options_of(json!({
+			"pack_directory": "pack",
+			"**/*?.png": { "colour_quantization_target": "none" }
+		})),
+		[UnknownOption {
+			file_options_glob_pattern: Some("**/*?.png".into()),
+			key: "colour_quantization_target".into(),
+			suggestion: Some("color_quantization_target")
+		}]
+	);
+}
+
+#[test]
+fn schema_describes_global_and_file_options() {
+	let schema = options_json_schema();
+
+	assert!(schema["properties"]["pack_directory"].is_object());
+	assert!(schema["properties"]["zip_spec_conformance_level"].is_object());
+	assert!(
+		schema["additionalProperties"]["anyOf"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.any(|file_options_schema| file_options_schema["properties"]["minify_json"].is_object())
+	);
+}
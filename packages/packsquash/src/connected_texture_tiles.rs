@@ -0,0 +1,482 @@
+//! Contains a virtual file system adapter that validates the tiles referenced by OptiFine
+//! connected textures (CTM) properties files, and renumbers numbered tiles to remove gaps
+//! in their numbering.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf},
+	sync::Arc
+};
+
+use ahash::{AHashMap, AHashSet};
+use bytes::Bytes;
+use java_properties::{LineEnding, PropertiesIter, PropertiesWriter};
+use tokio_util::either::Either;
+
+use crate::RelativePath;
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum number of tiles that a single range of the `tiles` list may expand to. Wider
+/// ranges are almost certainly mistakes, and are not validated nor renumbered.
+const MAXIMUM_TILE_RANGE_LENGTH: u32 = 4096;
+
+/// An entry of the `tiles` list of a CTM properties file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TileEntry {
+	/// A tile named after a number, stored next to the properties file. Ranges of numbered
+	/// tiles are expanded to an entry for each tile.
+	Numbered(u32),
+	/// A tile with another name, stored next to the properties file. The original entry text
+	/// is kept along with the name of the tile file.
+	Named { entry: String, file_name: String },
+	/// An entry that does not reference a tile next to the properties file, such as the special
+	/// `<skip>` and `<default>` entries, or paths to textures elsewhere. The original entry text
+	/// is kept.
+	Other(String)
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently renaming the numbered tiles
+/// of OptiFine connected textures to remove gaps and duplicates in their numbering, and
+/// rewriting the `tiles` list of their properties files accordingly. Every tile referenced by
+/// these files is also validated to exist.
+///
+/// Renamed tiles and rewritten properties files are reported to have no modification time,
+/// because their contents depend on the numbering of other files, so that stale versions of
+/// them are never reused from previous runs.
+pub struct ConnectedTextureTilesVfs<V: VirtualFileSystem> {
+	inner: V,
+	/// The rewritten properties files, which are kept in memory.
+	rewritten_properties_files: AHashMap<PathBuf, Bytes>,
+	/// The original file paths of the renamed tiles, keyed by their new file path.
+	renamed_tile_sources: AHashMap<PathBuf, PathBuf>,
+	/// The original file paths of the renamed tiles, which are removed from file iterators.
+	renamed_tile_paths: Arc<AHashSet<PathBuf>>,
+	/// The new relative and file paths of the renamed tiles, which are added to file iterators.
+	renamed_tile_entries: Arc<Vec<(String, PathBuf)>>
+}
+
+impl<V: VirtualFileSystem> ConnectedTextureTilesVfs<V> {
+	/// Wraps the specified virtual file system. No tiles will be validated or renumbered until
+	/// [`Self::process_connected_texture_tiles`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			rewritten_properties_files: AHashMap::new(),
+			renamed_tile_sources: AHashMap::new(),
+			renamed_tile_paths: Arc::new(AHashSet::new()),
+			renamed_tile_entries: Arc::new(Vec::new())
+		}
+	}
+
+	/// Scans the pack at the specified root path for CTM properties files, returning the
+	/// relative path of the properties files that reference tiles which do not exist, along with
+	/// the missing tiles. Only tiles stored next to the properties files are validated, because
+	/// other paths may refer to vanilla textures.
+	///
+	/// If `renumber_tiles` is `true`, the numbered tiles of the properties files whose tiles
+	/// exist will also be renumbered, as long as doing so is known to be safe: the properties
+	/// file must be the only one in its directory, its tiles must all be numbered, and no other
+	/// numbered files or references from other properties files may be in that directory.
+	/// Files with the same number as a tile, such as `.png.mcmeta` animation metadata files or
+	/// `_e.png` emissive textures, are renamed along with it.
+	pub async fn process_connected_texture_tiles(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions,
+		renumber_tiles: bool
+	) -> Vec<(RelativePath<'static>, Vec<String>)> {
+		let pack_files = self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+			.map(|entry| {
+				(
+					entry.relative_path.into_inner().into_owned(),
+					entry.file_path
+				)
+			})
+			.collect::<AHashMap<_, _>>();
+
+		let mut properties_files = Vec::new();
+		let mut properties_file_counts = AHashMap::<&str, usize>::new();
+		for (relative_path, file_path) in &pack_files {
+			if !is_ctm_properties_file(relative_path) {
+				continue;
+			}
+
+			let directory = parent_directory(relative_path);
+			*properties_file_counts.entry(directory).or_default() += 1;
+
+			let Some(properties) = self.read_properties(file_path).await else {
+				continue;
+			};
+			let Some(tiles) = properties
+				.iter()
+				.find_map(|(key, value)| (key == "tiles").then(|| parse_tiles(value)))
+			else {
+				continue;
+			};
+
+			properties_files.push((relative_path, file_path, directory, properties, tiles));
+		}
+
+		// Directories whose files are referenced by path from any properties file can't have
+		// their tiles renamed, because those references would break
+		let referenced_directories = properties_files
+			.iter()
+			.flat_map(|(_, _, directory, _, tiles)| {
+				tiles.iter().filter_map(|tile| match tile {
+					TileEntry::Other(entry) => resolve_tile_path(entry, directory),
+					_ => None
+				})
+			})
+			.map(|tile_path| parent_directory(&tile_path).to_string())
+			.collect::<AHashSet<_>>();
+
+		let mut missing_tiles = Vec::new();
+		let mut renamed_tile_paths = AHashSet::new();
+		let mut renamed_tile_entries = Vec::new();
+		for (relative_path, file_path, directory, properties, tiles) in &properties_files {
+			let mut missing_properties_file_tiles = Vec::new();
+			for tile in tiles {
+				let (tile_name, file_name) = match tile {
+					TileEntry::Numbered(number) => (number.to_string(), format!("{number}.png")),
+					TileEntry::Named { entry, file_name } => (entry.clone(), file_name.clone()),
+					TileEntry::Other(_) => continue
+				};
+
+				if !pack_files.contains_key(&format!("{directory}/{file_name}"))
+					&& !missing_properties_file_tiles.contains(&tile_name)
+				{
+					missing_properties_file_tiles.push(tile_name);
+				}
+			}
+
+			if !missing_properties_file_tiles.is_empty() {
+				missing_tiles.push((
+					RelativePath::from_inner(relative_path.to_string()),
+					missing_properties_file_tiles
+				));
+				continue;
+			}
+
+			if !renumber_tiles
+				|| properties_file_counts[directory] > 1
+				|| referenced_directories.contains(*directory)
+				|| !tiles.iter().all(|tile| match tile {
+					TileEntry::Numbered(_) => true,
+					TileEntry::Named { .. } => false,
+					TileEntry::Other(entry) => entry.starts_with('<')
+				}) {
+				continue;
+			}
+
+			// Number the tiles in the order they first appear in the list, which keeps the
+			// position of each entry, as some methods pair them with other lists
+			let mut tile_numbers = AHashMap::new();
+			for tile in tiles {
+				if let TileEntry::Numbered(number) = tile {
+					let new_number = tile_numbers.len() as u32;
+					tile_numbers.entry(*number).or_insert(new_number);
+				}
+			}
+
+			let renumbered_tiles = tiles
+				.iter()
+				.map(|tile| match tile {
+					TileEntry::Numbered(number) => TileEntry::Numbered(tile_numbers[number]),
+					other => other.clone()
+				})
+				.collect::<Vec<_>>();
+			let original_tiles_value = properties
+				.iter()
+				.find_map(|(key, value)| (key == "tiles").then_some(value.trim()))
+				.unwrap();
+			let renumbered_tiles_value = format_tiles(&renumbered_tiles);
+			if renumbered_tiles_value == original_tiles_value {
+				continue;
+			}
+
+			// Every numbered file in the directory must be a renumbered tile or belong to one,
+			// so that renamed files never overwrite other files
+			let Some(directory_renames) = pack_files
+				.keys()
+				.filter(|pack_file_path| parent_directory(pack_file_path) == *directory)
+				.filter_map(|pack_file_path| {
+					let file_name = &pack_file_path[directory.len() + 1..];
+					let number_length = file_name
+						.find(|c: char| !c.is_ascii_digit())
+						.filter(|number_length| *number_length > 0)?;
+					let suffix = &file_name[number_length..];
+					(suffix.starts_with('.') || suffix.starts_with('_')).then(|| {
+						// Numbers with leading zeros are not tile numbers
+						let number = file_name[..number_length]
+							.parse::<u32>()
+							.ok()
+							.filter(|number| number.to_string() == file_name[..number_length]);
+						(pack_file_path, number, suffix)
+					})
+				})
+				.filter(|(pack_file_path, _, _)| pack_file_path != relative_path)
+				.map(|(pack_file_path, number, suffix)| {
+					let new_number = tile_numbers.get(&number?)?;
+					Some((pack_file_path, format!("{directory}/{new_number}{suffix}")))
+				})
+				.collect::<Option<Vec<_>>>()
+			else {
+				continue;
+			};
+
+			let mut rewritten_properties = Vec::new();
+			let mut properties_writer = PropertiesWriter::new(&mut rewritten_properties);
+			properties_writer.set_line_ending(LineEnding::LF);
+			properties_writer.set_kv_separator("=").unwrap();
+			if properties
+				.iter()
+				.map(|(key, value)| {
+					properties_writer.write(
+						key,
+						if key == "tiles" {
+							&renumbered_tiles_value
+						} else {
+							value
+						}
+					)
+				})
+				.any(|result| result.is_err())
+			{
+				continue;
+			}
+			drop(properties_writer);
+
+			self.rewritten_properties_files
+				.insert((*file_path).clone(), rewritten_properties.into());
+
+			for (original_relative_path, new_relative_path) in directory_renames {
+				if *original_relative_path == new_relative_path {
+					continue;
+				}
+
+				let new_file_path = root_path.join(&new_relative_path);
+				self.renamed_tile_sources.insert(
+					new_file_path.clone(),
+					pack_files[original_relative_path].clone()
+				);
+				renamed_tile_paths.insert(pack_files[original_relative_path].clone());
+				renamed_tile_entries.push((new_relative_path, new_file_path));
+			}
+		}
+
+		self.renamed_tile_paths = Arc::new(renamed_tile_paths);
+		self.renamed_tile_entries = Arc::new(renamed_tile_entries);
+
+		missing_tiles
+	}
+
+	/// Reads and parses the properties file at the specified path of the wrapped file system,
+	/// returning its key-value pairs in order. `None` is returned if some I/O or parsing error
+	/// occurs.
+	async fn read_properties(&self, path: &Path) -> Option<Vec<(String, String)>> {
+		let (data, _) = read_file(&self.inner, path).await.ok()?;
+
+		let mut properties = Vec::new();
+		PropertiesIter::new(&*data)
+			.read_into(|key, value| properties.push((key, value)))
+			.ok()?;
+
+		Some(properties)
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for ConnectedTextureTilesVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = impl Iterator<Item = Result<VfsPackFileIterEntry, io::Error>>;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		let renamed_tile_paths = Arc::clone(&self.renamed_tile_paths);
+		let renamed_tile_entries = self
+			.renamed_tile_entries
+			.iter()
+			.filter(|(_, file_path)| file_path.starts_with(root_path))
+			.map(|(relative_path, file_path)| {
+				Ok(VfsPackFileIterEntry {
+					relative_path: RelativePath::from_inner(relative_path.clone()),
+					file_path: file_path.clone()
+				})
+			})
+			.collect::<Vec<_>>();
+
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.filter(move |entry| {
+				!entry
+					.as_ref()
+					.is_ok_and(|entry| renamed_tile_paths.contains(&entry.file_path))
+			})
+			.chain(renamed_tile_entries)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		let (inner_path, modification_time_known) = match self.renamed_tile_sources.get(path.as_ref())
+		{
+			Some(source_path) => (source_path.as_path(), false),
+			None => (path.as_ref(), true)
+		};
+
+		open_in_memory_or_inner(
+			&self.inner,
+			inner_path,
+			self.rewritten_properties_files
+				.get(path.as_ref())
+				.map(|rewritten_properties_file| (rewritten_properties_file, None))
+		)
+		.map(|mut file| {
+			if !modification_time_known {
+				file.metadata.modification_time = None;
+			}
+
+			file
+		})
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		match self.renamed_tile_sources.get(path.as_ref()) {
+			Some(source_path) => self.inner.file_type(source_path),
+			None => self.inner.file_type(path)
+		}
+	}
+}
+
+/// Checks whether the specified relative path is of a CTM properties file, which are stored
+/// in the `mcpatcher/ctm` or `optifine/ctm` directories of any namespace.
+fn is_ctm_properties_file(relative_path: &str) -> bool {
+	let mut path_components = relative_path.split('/');
+
+	path_components.next() == Some("assets")
+		&& path_components.next().is_some()
+		&& matches!(path_components.next(), Some("mcpatcher" | "optifine"))
+		&& path_components.next() == Some("ctm")
+		&& relative_path.ends_with(".properties")
+}
+
+/// Returns the relative path of the directory that contains the file at the specified
+/// relative path, without a trailing slash.
+fn parent_directory(relative_path: &str) -> &str {
+	relative_path
+		.rsplit_once('/')
+		.map_or("", |(directory, _)| directory)
+}
+
+/// Parses the value of the `tiles` property of a CTM properties file, which is a list of
+/// whitespace-separated tile numbers, ranges of tile numbers, tile names and paths, in the
+/// format described by the OptiFine documentation.
+///
+/// References:
+/// - <https://github.com/sp614x/optifine/blob/master/OptiFineDoc/doc/ctm.properties>
+fn parse_tiles(value: &str) -> Vec<TileEntry> {
+	let mut tiles = Vec::new();
+
+	for entry in value.split_whitespace() {
+		if let Some((first, last)) = entry.split_once('-')
+			&& let (Ok(first), Ok(last)) = (first.parse::<u32>(), last.parse::<u32>())
+			&& first <= last
+			&& last - first < MAXIMUM_TILE_RANGE_LENGTH
+		{
+			tiles.extend((first..=last).map(TileEntry::Numbered));
+			continue;
+		}
+
+		let name = entry.strip_prefix("./").unwrap_or(entry);
+		let name = name.strip_suffix(".png").unwrap_or(name);
+		let is_invalid_range = name
+			.split_once('-')
+			.is_some_and(|(first, last)| first.parse::<u32>().is_ok() && last.parse::<u32>().is_ok());
+		tiles.push(
+			if entry.starts_with('<')
+				|| name.is_empty()
+				|| is_invalid_range
+				|| name.contains(['/', ':', '~'])
+			{
+				TileEntry::Other(entry.to_string())
+			} else if let Ok(number) = name.parse() {
+				TileEntry::Numbered(number)
+			} else {
+				TileEntry::Named {
+					entry: entry.to_string(),
+					file_name: format!("{name}.png")
+				}
+			}
+		);
+	}
+
+	tiles
+}
+
+/// Formats the specified tiles as the value of the `tiles` property of a CTM properties
+/// file, using ranges for runs of three or more consecutive tile numbers.
+fn format_tiles(tiles: &[TileEntry]) -> String {
+	let mut entries = Vec::new();
+
+	let mut i = 0;
+	while i < tiles.len() {
+		match &tiles[i] {
+			TileEntry::Numbered(first) => {
+				let mut last = *first;
+				while let Some(TileEntry::Numbered(next)) = tiles.get(i + 1)
+					&& last.checked_add(1) == Some(*next)
+				{
+					last = *next;
+					i += 1;
+				}
+
+				match last - first {
+					0 => entries.push(first.to_string()),
+					1 => entries.extend([first.to_string(), last.to_string()]),
+					_ => entries.push(format!("{first}-{last}"))
+				}
+			}
+			TileEntry::Named { entry, .. } | TileEntry::Other(entry) => entries.push(entry.clone())
+		}
+
+		i += 1;
+	}
+
+	entries.join(" ")
+}
+
+/// Resolves a tile path entry of the `tiles` list of a CTM properties file in the specified
+/// directory to the relative path of the texture it references, if it may be stored in a pack.
+/// Paths that do not start with `./`, `~/` or a namespace are resolved from the `minecraft`
+/// namespace.
+fn resolve_tile_path(entry: &str, directory: &str) -> Option<String> {
+	if entry.starts_with('<') {
+		return None;
+	}
+
+	let tile_path = if let Some(relative_path) = entry.strip_prefix("./") {
+		format!("{directory}/{relative_path}")
+	} else if let Some(optifine_path) = entry.strip_prefix("~/") {
+		format!("assets/minecraft/optifine/{optifine_path}")
+	} else if let Some((namespace, path)) = entry.split_once(':') {
+		format!("assets/{namespace}/{path}")
+	} else {
+		format!("assets/minecraft/{entry}")
+	};
+
+	Some(if tile_path.ends_with(".png") {
+		tile_path
+	} else {
+		format!("{tile_path}.png")
+	})
+}
@@ -0,0 +1,193 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::{Builder, TempDir};
+use tokio::io::AsyncReadExt;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+/// Creates a pack with the specified files in its `assets/minecraft/optifine/ctm/glass`
+/// directory.
+fn ctm_pack(files: &[(&str, &[u8])]) -> TempDir {
+	let root_dir = Builder::new()
+		.prefix("ps-ctm-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	let ctm_dir = root_dir.path().join("assets/minecraft/optifine/ctm/glass");
+	fs::create_dir_all(&ctm_dir).expect("I/O operations are assumed not to fail during tests");
+
+	for (file_name, data) in files {
+		fs::write(ctm_dir.join(file_name), data)
+			.expect("I/O operations are assumed not to fail during tests");
+	}
+
+	root_dir
+}
+
+/// Reads the file at the specified relative path of a virtual file system.
+fn read_file<V: VirtualFileSystem>(vfs: &V, root_path: &Path, relative_path: &str) -> Vec<u8> {
+	let mut data = Vec::new();
+	tokio_test::block_on(
+		vfs.open(root_path.join(relative_path))
+			.expect("The file should exist")
+			.file_read
+			.read_to_end(&mut data)
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+
+	data
+}
+
+#[test]
+fn tiles_are_renumbered() {
+	let root_dir = ctm_pack(&[
+		(
+			"glass.properties",
+			b"method=random\ntiles=0-1 5 <skip> 7\nweights=1 2 3 4 5"
+		),
+		("0.png", b"zero"),
+		("1.png", b"one"),
+		("5.png", b"five"),
+		("5.png.mcmeta", b"{}"),
+		("7.png", b"seven"),
+		("7_e.png", b"seven emissive")
+	]);
+
+	let mut vfs = ConnectedTextureTilesVfs::new(OsFilesystem);
+	let missing_tiles = tokio_test::block_on(vfs.process_connected_texture_tiles(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		true
+	));
+	assert!(missing_tiles.is_empty(), "No tiles should be missing");
+
+	let mut relative_paths = vfs
+		.file_iterator(root_dir.path(), IteratorTraversalOptions::default())
+		.map(|entry| {
+			entry
+				.expect("I/O operations are assumed not to fail during tests")
+				.relative_path
+				.as_str()
+				.to_string()
+		})
+		.collect::<Vec<_>>();
+	relative_paths.sort_unstable();
+
+	assert_eq!(
+		relative_paths,
+		[
+			"assets/minecraft/optifine/ctm/glass/0.png",
+			"assets/minecraft/optifine/ctm/glass/1.png",
+			"assets/minecraft/optifine/ctm/glass/2.png",
+			"assets/minecraft/optifine/ctm/glass/2.png.mcmeta",
+			"assets/minecraft/optifine/ctm/glass/3.png",
+			"assets/minecraft/optifine/ctm/glass/3_e.png",
+			"assets/minecraft/optifine/ctm/glass/glass.properties"
+		]
+	);
+
+	let ctm_dir = "assets/minecraft/optifine/ctm/glass";
+	assert_eq!(
+		read_file(&vfs, root_dir.path(), &format!("{ctm_dir}/2.png")),
+		b"five"
+	);
+	assert_eq!(
+		read_file(&vfs, root_dir.path(), &format!("{ctm_dir}/3_e.png")),
+		b"seven emissive"
+	);
+	assert_eq!(
+		String::from_utf8(read_file(
+			&vfs,
+			root_dir.path(),
+			&format!("{ctm_dir}/glass.properties")
+		))
+		.unwrap(),
+		"method=random\ntiles=0-2 <skip> 3\nweights=1 2 3 4 5\n"
+	);
+}
+
+#[test]
+fn missing_tiles_are_reported_and_not_renumbered() {
+	let root_dir = ctm_pack(&[
+		(
+			"glass.properties",
+			b"method=horizontal\ntiles=0 2 4 missing"
+		),
+		("0.png", b"zero"),
+		("2.png", b"two")
+	]);
+
+	let mut vfs = ConnectedTextureTilesVfs::new(OsFilesystem);
+	let missing_tiles = tokio_test::block_on(vfs.process_connected_texture_tiles(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		true
+	))
+	.into_iter()
+	.map(|(relative_path, tiles)| (relative_path.as_str().to_string(), tiles))
+	.collect::<Vec<_>>();
+
+	assert_eq!(
+		missing_tiles,
+		[(
+			"assets/minecraft/optifine/ctm/glass/glass.properties".to_string(),
+			vec!["4".to_string(), "missing".to_string()]
+		)]
+	);
+	assert!(
+		vfs.renamed_tile_sources.is_empty() && vfs.rewritten_properties_files.is_empty(),
+		"Tiles of properties files with missing tiles should not be renumbered"
+	);
+}
+
+#[test]
+fn unreferenced_numbered_files_prevent_renumbering() {
+	let root_dir = ctm_pack(&[
+		("glass.properties", b"method=fixed\ntiles=1"),
+		("0.png", b"zero"),
+		("1.png", b"one")
+	]);
+
+	let mut vfs = ConnectedTextureTilesVfs::new(OsFilesystem);
+	tokio_test::block_on(vfs.process_connected_texture_tiles(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		true
+	));
+
+	assert!(
+		vfs.renamed_tile_sources.is_empty() && vfs.rewritten_properties_files.is_empty(),
+		"Tiles should not be renumbered over unreferenced numbered files"
+	);
+}
+
+#[test]
+fn tiles_are_parsed_and_formatted() {
+	let tiles = parse_tiles("0-2 ./3.png 5 6 <default> name ~/ctm/other/0 minecraft:block/stone 9-8");
+
+	assert_eq!(
+		tiles,
+		[
+			TileEntry::Numbered(0),
+			TileEntry::Numbered(1),
+			TileEntry::Numbered(2),
+			TileEntry::Numbered(3),
+			TileEntry::Numbered(5),
+			TileEntry::Numbered(6),
+			TileEntry::Other("<default>".to_string()),
+			TileEntry::Named {
+				entry: "name".to_string(),
+				file_name: "name.png".to_string()
+			},
+			TileEntry::Other("~/ctm/other/0".to_string()),
+			TileEntry::Other("minecraft:block/stone".to_string()),
+			TileEntry::Other("9-8".to_string())
+		]
+	);
+	assert_eq!(
+		format_tiles(&tiles),
+		"0-3 5 6 <default> name ~/ctm/other/0 minecraft:block/stone 9-8"
+	);
+}
@@ -0,0 +1,170 @@
+//! Contains routines to validate the OptiFine custom entity models of a pack, checking that the
+//! textures and part models they reference exist.
+
+use std::path::Path;
+
+use ahash::AHashSet;
+use serde_json::Value;
+
+use crate::RelativePath;
+use crate::pack_file::{blank_out_relaxed_syntax, strip_utf8_bom};
+use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem, read_file};
+
+#[cfg(test)]
+mod tests;
+
+/// The folders OptiFine reads its files from, relative to the root of the pack.
+const OPTIFINE_FOLDERS: [&str; 2] = ["assets/minecraft/optifine/", "assets/minecraft/mcpatcher/"];
+
+/// Parses the OptiFine custom entity models (`.jem` files) and part models (`.jpm` files) of
+/// the pack at the specified root path, finding the textures and part models they reference
+/// that are not in the pack. OptiFine renders entities whose texture is missing with the
+/// missing texture, and ignores models with missing parts, while only reporting it in its
+/// log.
+///
+/// References are resolved like OptiFine does: paths starting with `./` are relative to the
+/// folder of the referencing file, as are paths without any folder, paths starting with `~/`
+/// are relative to the OptiFine folder, paths with a namespace are relative to the folder of
+/// that namespace, and other paths are relative to the `minecraft` namespace folder. Assets of
+/// the `minecraft` namespace outside the OptiFine folders are not checked, because they may be
+/// provided by the game itself.
+///
+/// Files that can't be read or parsed are ignored, so that the usual pack file processing
+/// reports any relevant error. Mismatches are sorted by path.
+///
+/// References:
+/// - <https://optifine.readthedocs.io/cem_model.html>
+/// - <https://optifine.readthedocs.io/cem_part.html>
+pub async fn validate_custom_entity_models<V: VirtualFileSystem>(
+	vfs: &V,
+	root_path: &Path,
+	iterator_traversal_options: IteratorTraversalOptions
+) -> Vec<(RelativePath<'static>, String)> {
+	let mut pack_files = AHashSet::new();
+	let mut model_files = vec![];
+	for VfsPackFileIterEntry {
+		relative_path,
+		file_path
+	} in vfs
+		.file_iterator(root_path, iterator_traversal_options)
+		.flatten()
+	{
+		let relative_path_str = relative_path.as_str();
+		if OPTIFINE_FOLDERS
+			.iter()
+			.any(|folder| relative_path_str.starts_with(&format!("{folder}cem/")))
+			&& [".jem", ".jemc", ".jpm", ".jpmc"]
+				.iter()
+				.any(|extension| relative_path_str.ends_with(extension))
+		{
+			model_files.push((relative_path.clone(), file_path));
+		}
+
+		// Files with comments are referenced by their canonical extension
+		pack_files.insert(
+			match relative_path_str
+				.strip_suffix(".jemc")
+				.map(|path| (path, ".jem"))
+				.or_else(|| {
+					relative_path_str
+						.strip_suffix(".jpmc")
+						.map(|path| (path, ".jpm"))
+				}) {
+				Some((relative_path, extension)) => format!("{relative_path}{extension}"),
+				None => relative_path_str.to_owned()
+			}
+		);
+	}
+	model_files.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+	let mut mismatches = vec![];
+	for (relative_path, file_path) in model_files {
+		let Some(model) = read_json(vfs, &file_path).await else {
+			continue;
+		};
+		let folder = relative_path
+			.as_str()
+			.rsplit_once('/')
+			.map_or("", |(folder, _)| folder);
+
+		let mut reported_paths = AHashSet::new();
+		let mut pending_values = vec![&model];
+		while let Some(value) = pending_values.pop() {
+			match value {
+				Value::Object(object) => {
+					for (key, value) in object.iter().rev() {
+						let (description, reference, extension) = match (key.as_str(), value) {
+							("texture", Value::String(reference)) => ("texture", reference, ".png"),
+							("model", Value::String(reference)) => ("part model", reference, ".jpm"),
+							_ => {
+								pending_values.push(value);
+								continue;
+							}
+						};
+
+						let referenced_path = resolve_reference(reference, folder, extension);
+						if !pack_files.contains(&referenced_path)
+							&& !is_vanilla_asset(&referenced_path)
+							&& reported_paths.insert(referenced_path.clone())
+						{
+							mismatches.push((
+								relative_path.clone(),
+								format!(
+									"uses the {description} {referenced_path}, which is not in the pack"
+								)
+							));
+						}
+					}
+				}
+				Value::Array(array) => pending_values.extend(array.iter().rev()),
+				_ => {}
+			}
+		}
+	}
+
+	mismatches
+}
+
+/// Returns the relative path of the file referenced by the specified OptiFine path, which is
+/// referenced by a file in the specified folder, adding the specified extension if missing.
+fn resolve_reference(reference: &str, referencing_folder: &str, extension: &str) -> String {
+	let extension = if reference.ends_with(extension) {
+		""
+	} else {
+		extension
+	};
+
+	if let Some(path) = reference.strip_prefix("./") {
+		format!("{referencing_folder}/{path}{extension}")
+	} else if let Some(path) = reference.strip_prefix("~/") {
+		format!("assets/minecraft/optifine/{path}{extension}")
+	} else if let Some((namespace, path)) = reference.split_once(':') {
+		format!("assets/{namespace}/{path}{extension}")
+	} else if !reference.contains('/') {
+		format!("{referencing_folder}/{reference}{extension}")
+	} else {
+		format!("assets/minecraft/{reference}{extension}")
+	}
+}
+
+/// Checks whether the specified relative path is of an asset of the `minecraft` namespace
+/// that the game may provide, which excludes assets in the OptiFine folders.
+fn is_vanilla_asset(relative_path: &str) -> bool {
+	relative_path.starts_with("assets/minecraft/")
+		&& !OPTIFINE_FOLDERS
+			.iter()
+			.any(|folder| relative_path.starts_with(folder))
+}
+
+/// Reads and parses the JSON file at the specified path of a virtual file system, allowing
+/// comments and trailing commas like the usual JSON file processing does, and returning `None`
+/// if some I/O or parsing error occurs.
+async fn read_json<V: VirtualFileSystem>(vfs: &V, path: &Path) -> Option<Value> {
+	let (mut data, _) = read_file(vfs, path).await.ok()?;
+
+	let bom_length = data.len() - strip_utf8_bom(&data).len();
+	let json = &mut data[bom_length..];
+	blank_out_relaxed_syntax(json).ok()?;
+
+	serde_json::from_slice(json).ok()
+}
@@ -0,0 +1,79 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[test]
+fn custom_entity_models_are_validated() {
+	let root_dir = Builder::new()
+		.prefix("ps-custom-entity-model-validation-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"assets/minecraft/optifine/cem/creeper.jem",
+			r#"{
+				"texture": "creeper_custom",
+				"textureSize": [64, 32],
+				"models": [
+					{ "part": "head", "id": "head", "model": "creeper_head.jpm" },
+					{ "part": "body", "id": "body", "model": "./creeper_body" },
+					{
+						"part": "leg1",
+						"id": "leg1",
+						"texture": "textures/entity/creeper/creeper.png",
+						"submodels": [{ "id": "toe", "texture": "~/cem/missing_toe.png" }]
+					}
+				]
+			}"#
+		),
+		(
+			"assets/minecraft/optifine/cem/creeper_head.jpmc",
+			r#"{
+				// Comments are allowed, like in the usual JSON processing
+				"texture": "mymod:textures/entity/missing_head.png",
+				"boxes": [{ "coordinates": [-4, 0, -4, 8, 8, 8] }]
+			}"#
+		),
+		("assets/minecraft/optifine/cem/creeper_custom.png", "")
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mismatches = tokio_test::block_on(validate_custom_entity_models(
+		&OsFilesystem,
+		root_dir.path(),
+		IteratorTraversalOptions::default()
+	));
+
+	assert_eq!(
+		mismatches
+			.iter()
+			.map(|(relative_path, mismatch)| (relative_path.as_str(), mismatch.as_str()))
+			.collect::<Vec<_>>(),
+		[
+			(
+				"assets/minecraft/optifine/cem/creeper.jem",
+				"uses the part model assets/minecraft/optifine/cem/creeper_body.jpm, which is not \
+				in the pack"
+			),
+			(
+				"assets/minecraft/optifine/cem/creeper.jem",
+				"uses the texture assets/minecraft/optifine/cem/missing_toe.png, which is not in \
+				the pack"
+			),
+			(
+				"assets/minecraft/optifine/cem/creeper_head.jpmc",
+				"uses the texture assets/mymod/textures/entity/missing_head.png, which is not in \
+				the pack"
+			)
+		]
+	);
+}
@@ -0,0 +1,230 @@
+//! Contains routines to validate the references between the files of a data pack, checking
+//! that the tags of the pack reference tags, functions and registry elements that exist.
+
+use std::path::Path;
+
+use ahash::AHashSet;
+use serde_json::Value;
+
+use crate::RelativePath;
+use crate::pack_file::{blank_out_relaxed_syntax, strip_utf8_bom};
+use crate::pack_meta::PACK_FORMAT_DATA_PACK_VERSION_24W_21A;
+use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem, read_file};
+
+#[cfg(test)]
+mod tests;
+
+/// The namespace of vanilla data, which is also the namespace of resource locations that do
+/// not specify any.
+const VANILLA_NAMESPACE: &str = "minecraft";
+
+/// The registries whose elements are defined by JSON files of data packs, in a folder of each
+/// namespace named after the registry, and thus can be checked to exist.
+const DATA_DRIVEN_REGISTRIES: &[&str] = &[
+	"banner_pattern",
+	"cat_variant",
+	"chat_type",
+	"chicken_variant",
+	"cow_variant",
+	"damage_type",
+	"dimension",
+	"dimension_type",
+	"enchantment",
+	"enchantment_provider",
+	"frog_variant",
+	"instrument",
+	"jukebox_song",
+	"painting_variant",
+	"pig_variant",
+	"trim_material",
+	"trim_pattern",
+	"wolf_sound_variant",
+	"wolf_variant",
+	"worldgen/biome",
+	"worldgen/configured_carver",
+	"worldgen/configured_feature",
+	"worldgen/density_function",
+	"worldgen/flat_level_generator_preset",
+	"worldgen/multi_noise_biome_source_parameter_list",
+	"worldgen/noise",
+	"worldgen/noise_settings",
+	"worldgen/placed_feature",
+	"worldgen/processor_list",
+	"worldgen/structure",
+	"worldgen/structure_set",
+	"worldgen/template_pool",
+	"worldgen/world_preset"
+];
+
+/// Parses the tags of the data pack at the specified root path, finding the entries that
+/// reference tags, functions or elements of data-driven registries that are not in the pack.
+/// Minecraft fails to load every tag with a missing required entry, and the tags that
+/// reference them, while only reporting it in its log, which usually breaks every function
+/// and recipe that uses them.
+///
+/// The folders that tags, functions and registry elements are expected in are those of the
+/// Minecraft versions of the specified pack format version, as they were renamed to their
+/// singular form in [`PACK_FORMAT_DATA_PACK_VERSION_24W_21A`]. Only the references to the
+/// namespaces the pack defines data for, other than the vanilla namespace, are checked,
+/// because the game, mods and other data packs may provide the rest. Entries that are not
+/// required are not checked either.
+///
+/// Files that can't be read or parsed are ignored, so that the usual pack file processing
+/// reports any relevant error. Mismatches are sorted by path.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Tag_(Java_Edition)>
+/// - <https://minecraft.wiki/w/Data_pack#Folder_structure>
+pub async fn validate_data_pack_references<V: VirtualFileSystem>(
+	vfs: &V,
+	root_path: &Path,
+	iterator_traversal_options: IteratorTraversalOptions,
+	pack_format_version: i32
+) -> Vec<(RelativePath<'static>, String)> {
+	// Functions, and the tags of functions, are in the functions folder in older versions
+	let function_folder_name = if pack_format_version < PACK_FORMAT_DATA_PACK_VERSION_24W_21A {
+		"functions"
+	} else {
+		"function"
+	};
+
+	let mut pack_files = AHashSet::new();
+	let mut namespaces = AHashSet::new();
+	let mut tag_files = vec![];
+	for VfsPackFileIterEntry {
+		relative_path,
+		file_path
+	} in vfs
+		.file_iterator(root_path, iterator_traversal_options)
+		.flatten()
+	{
+		let Some((namespace, path)) = relative_path
+			.as_str()
+			.strip_prefix("data/")
+			.and_then(|path| path.split_once('/'))
+		else {
+			continue;
+		};
+
+		if namespace != VANILLA_NAMESPACE {
+			namespaces.insert(namespace.to_owned());
+		}
+
+		if path.starts_with("tags/") && (path.ends_with(".json") || path.ends_with(".jsonc")) {
+			tag_files.push((relative_path.clone(), file_path));
+		}
+
+		// Files with comments are referenced by their canonical extension
+		pack_files.insert(match relative_path.as_str().strip_suffix(".jsonc") {
+			Some(relative_path) => format!("{relative_path}.json"),
+			None => relative_path.as_str().to_owned()
+		});
+	}
+	tag_files.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+	let mut mismatches = vec![];
+	for (relative_path, file_path) in tag_files {
+		let Some(tag_path) = relative_path
+			.as_str()
+			.strip_prefix("data/")
+			.and_then(|path| path.split_once("/tags/"))
+			.map(|(_, tag_path)| tag_path)
+		else {
+			continue;
+		};
+
+		// Tags are in a folder named after their registry. World generation registries are
+		// grouped in a worldgen folder
+		let registry_folder_length = match tag_path.split_once('/') {
+			Some(("worldgen", path)) => match path.split_once('/') {
+				Some((registry, _)) => "worldgen/".len() + registry.len(),
+				None => continue
+			},
+			Some((registry, _)) => registry.len(),
+			None => continue
+		};
+		let registry_tag_folder = &tag_path[..registry_folder_length];
+
+		let Some(tag) = read_json(vfs, &file_path).await else {
+			continue;
+		};
+
+		for entry in tag
+			.get("values")
+			.and_then(Value::as_array)
+			.into_iter()
+			.flatten()
+		{
+			let (id, required) = match entry {
+				Value::String(id) => (id.as_str(), true),
+				Value::Object(entry) => match entry.get("id").and_then(Value::as_str) {
+					Some(id) => (
+						id,
+						entry
+							.get("required")
+							.and_then(Value::as_bool)
+							.unwrap_or(true)
+					),
+					None => continue
+				},
+				_ => continue
+			};
+
+			let (is_tag, resource_location) = match id.strip_prefix('#') {
+				Some(tag_id) => (true, tag_id),
+				None => (false, id)
+			};
+			let (namespace, path) = resource_location
+				.split_once(':')
+				.unwrap_or((VANILLA_NAMESPACE, resource_location));
+
+			if !required || !namespaces.contains(namespace) {
+				continue;
+			}
+
+			let (description, referenced_path) = if is_tag {
+				(
+					"tag",
+					format!("data/{namespace}/tags/{registry_tag_folder}/{path}.json")
+				)
+			} else if registry_tag_folder == function_folder_name {
+				(
+					"function",
+					format!("data/{namespace}/{function_folder_name}/{path}.mcfunction")
+				)
+			} else if DATA_DRIVEN_REGISTRIES.contains(&registry_tag_folder) {
+				(
+					registry_tag_folder,
+					format!("data/{namespace}/{registry_tag_folder}/{path}.json")
+				)
+			} else {
+				continue;
+			};
+
+			if !pack_files.contains(&referenced_path) {
+				mismatches.push((
+					relative_path.clone(),
+					format!(
+						"references the {description} {id}, but {referenced_path} is not in the \
+						pack"
+					)
+				));
+			}
+		}
+	}
+
+	mismatches
+}
+
+/// Reads and parses the JSON file at the specified path of a virtual file system, allowing
+/// comments and trailing commas like the usual JSON file processing does, and returning `None`
+/// if some I/O or parsing error occurs.
+async fn read_json<V: VirtualFileSystem>(vfs: &V, path: &Path) -> Option<Value> {
+	let (mut data, _) = read_file(vfs, path).await.ok()?;
+
+	let bom_length = data.len() - strip_utf8_bom(&data).len();
+	let json = &mut data[bom_length..];
+	blank_out_relaxed_syntax(json).ok()?;
+
+	serde_json::from_slice(json).ok()
+}
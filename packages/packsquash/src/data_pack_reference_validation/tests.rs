@@ -0,0 +1,102 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[test]
+fn tag_references_are_validated() {
+	let root_dir = Builder::new()
+		.prefix("ps-data-pack-reference-validation-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"data/minecraft/tags/function/load.json",
+			r##"{
+				// Comments are allowed, like in the usual JSON processing
+				"values": [
+					"mypack:init",
+					"mypack:missing",
+					"#mypack:missing_tag",
+					{ "id": "mypack:optional", "required": false },
+					"othermod:setup"
+				]
+			}"##
+		),
+		(
+			"data/mypack/tags/worldgen/biome/warm.jsonc",
+			r##"{ "values": ["mypack:desert", { "id": "mypack:jungle" }, "#mypack:hot"] }"##
+		),
+		(
+			"data/mypack/tags/worldgen/biome/hot.json",
+			r#"{ "values": ["minecraft:desert"] }"#
+		),
+		(
+			"data/mypack/tags/item/gems.json",
+			r#"{ "values": ["mypack:ruby"] }"#
+		),
+		("data/mypack/function/init.mcfunction", "say Hello"),
+		("data/mypack/worldgen/biome/desert.json", "{}")
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mismatches = tokio_test::block_on(validate_data_pack_references(
+		&OsFilesystem,
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		48
+	));
+
+	assert_eq!(
+		mismatches
+			.iter()
+			.map(|(relative_path, mismatch)| (relative_path.as_str(), mismatch.as_str()))
+			.collect::<Vec<_>>(),
+		[
+			(
+				"data/minecraft/tags/function/load.json",
+				"references the function mypack:missing, but \
+				data/mypack/function/missing.mcfunction is not in the pack"
+			),
+			(
+				"data/minecraft/tags/function/load.json",
+				"references the tag #mypack:missing_tag, but \
+				data/mypack/tags/function/missing_tag.json is not in the pack"
+			),
+			(
+				"data/mypack/tags/worldgen/biome/warm.jsonc",
+				"references the worldgen/biome mypack:jungle, but \
+				data/mypack/worldgen/biome/jungle.json is not in the pack"
+			)
+		]
+	);
+
+	// Older versions expect functions in the functions folder
+	let mismatches = tokio_test::block_on(validate_data_pack_references(
+		&OsFilesystem,
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		41
+	));
+
+	assert_eq!(
+		mismatches
+			.iter()
+			.map(|(_, mismatch)| mismatch.as_str())
+			.collect::<Vec<_>>(),
+		[
+			"references the tag #mypack:missing_tag, but \
+			data/mypack/tags/function/missing_tag.json is not in the pack",
+			"references the worldgen/biome mypack:jungle, but \
+			data/mypack/worldgen/biome/jungle.json is not in the pack"
+		]
+	);
+}
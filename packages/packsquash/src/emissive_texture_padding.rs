@@ -0,0 +1,272 @@
+//! Contains a virtual file system adapter that validates that OptiFine emissive textures have
+//! the same dimensions as the textures they are overlaid on, and may pad them to match.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf}
+};
+
+use ahash::{AHashMap, AHashSet};
+use bytes::Bytes;
+use java_properties::PropertiesIter;
+use oxipng::{BitDepth, ColorType, Options, RawImage};
+use spng::{ContextFlags, DecodeFlags, Format};
+use tokio_util::either::Either;
+
+use crate::RelativePath;
+use crate::config::MismatchedEmissiveTextureHandling;
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VirtualFileSystem, open_in_memory_or_inner, read_file
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The path of the properties file that enables emissive textures and defines their suffix,
+/// relative to the root of the pack.
+const EMISSIVE_PROPERTIES_FILE_PATH: &str = "assets/minecraft/optifine/emissive.properties";
+
+/// A [`VirtualFileSystem`] that wraps another one, checking that the emissive textures of the
+/// pack have the same dimensions as the base textures OptiFine and compatible mods, such as
+/// Continuity, overlay them on, and transparently padding the emissive textures that are
+/// smaller than their base texture if requested. Emissive textures with other dimensions are
+/// rendered misaligned or stretched, which usually shows as broken glowing parts in game.
+///
+/// Emissive textures are the textures named like another texture of the same folder plus the
+/// suffix defined by the `suffix.emissive` property of the `optifine/emissive.properties`
+/// file, which is usually `_e`. No texture is considered emissive if that file or property
+/// are missing, as OptiFine does not render emissive textures then.
+///
+/// The padded emissive textures are computed eagerly by [`Self::process_emissive_textures`]
+/// and kept in memory, because the [`VirtualFileSystem::open`] method is synchronous. They are
+/// reported to have no modification time, because their contents depend on the dimensions of
+/// another file, so that stale versions of them are never reused from previous runs. Other
+/// files are read from the wrapped file system as-is.
+pub struct EmissiveTexturePaddingVfs<V: VirtualFileSystem> {
+	inner: V,
+	padded_textures: AHashMap<PathBuf, Bytes>
+}
+
+impl<V: VirtualFileSystem> EmissiveTexturePaddingVfs<V> {
+	/// Wraps the specified virtual file system. No emissive textures will be changed until
+	/// [`Self::process_emissive_textures`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			padded_textures: AHashMap::new()
+		}
+	}
+
+	/// Scans the pack at the specified root path for emissive textures, comparing their
+	/// dimensions with those of their base texture according to the specified handling.
+	///
+	/// Emissive textures are only padded when they are smaller than their base texture in some
+	/// dimension, not bigger in any, and have a different aspect ratio, which is how they look
+	/// when their base texture was extended with more space, such as entity textures that
+	/// were made taller. They are padded with transparent pixels to the right and bottom,
+	/// which do not glow. Emissive textures with the same aspect ratio as a base texture of
+	/// another size are for a different resolution, and are never padded. Neither are emissive
+	/// textures of animated textures, as their frames would be misaligned. The returned vector
+	/// contains the paths of the emissive textures with mismatched dimensions and their
+	/// descriptions, sorted by path, except for the textures that were padded. Textures that
+	/// can't be read or decoded are left as-is, so that the usual pack file processing reports
+	/// any relevant error.
+	///
+	/// References:
+	/// - <https://optifine.readthedocs.io/emissive_textures.html>
+	pub async fn process_emissive_textures(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions,
+		mismatched_emissive_texture_handling: MismatchedEmissiveTextureHandling
+	) -> Vec<(RelativePath<'static>, String)> {
+		let pack_files = self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+			.map(|entry| (entry.relative_path, entry.file_path))
+			.collect::<Vec<_>>();
+
+		let Some(emissive_suffix) = self.emissive_suffix(&pack_files).await else {
+			return vec![];
+		};
+
+		let animated_textures = pack_files
+			.iter()
+			.filter_map(|(relative_path, _)| relative_path.as_str().strip_suffix(".png.mcmeta"))
+			.collect::<AHashSet<_>>();
+
+		let textures = pack_files
+			.iter()
+			.filter_map(|(relative_path, file_path)| {
+				Some((
+					relative_path.as_str().strip_suffix(".png")?,
+					(relative_path, file_path)
+				))
+			})
+			.collect::<AHashMap<_, _>>();
+
+		let mut emissive_textures = textures
+			.iter()
+			.filter_map(|(texture_path, (relative_path, file_path))| {
+				let base_texture = textures.get(texture_path.strip_suffix(&*emissive_suffix)?)?;
+				Some((
+					(*relative_path).clone(),
+					*file_path,
+					base_texture.0,
+					base_texture.1
+				))
+			})
+			.collect::<Vec<_>>();
+		emissive_textures.sort_unstable_by(|(a, ..), (b, ..)| a.as_str().cmp(b.as_str()));
+
+		let mut mismatches = vec![];
+		for (relative_path, file_path, base_relative_path, base_file_path) in emissive_textures {
+			let (Ok((emissive_texture, _)), Ok((base_texture, _))) = (
+				read_file(&self.inner, file_path).await,
+				read_file(&self.inner, base_file_path).await
+			) else {
+				continue;
+			};
+			let (Some((width, height)), Some((base_width, base_height))) = (
+				texture_dimensions(&emissive_texture),
+				texture_dimensions(&base_texture)
+			) else {
+				continue;
+			};
+
+			if (width, height) == (base_width, base_height) {
+				continue;
+			}
+
+			// Padding the frame strip of animated textures would not pad their frames
+			let paddable = width <= base_width
+				&& height <= base_height
+				&& width as u64 * base_height as u64 != height as u64 * base_width as u64
+				&& ![&relative_path, base_relative_path]
+					.iter()
+					.any(|texture_relative_path| {
+						texture_relative_path
+							.as_str()
+							.strip_suffix(".png")
+							.is_some_and(|texture_path| animated_textures.contains(texture_path))
+					});
+			if paddable
+				&& mismatched_emissive_texture_handling == MismatchedEmissiveTextureHandling::Pad
+				&& let Some(padded_texture) = pad_texture(&emissive_texture, base_width, base_height)
+			{
+				self.padded_textures
+					.insert(file_path.clone(), padded_texture.into());
+				continue;
+			}
+
+			mismatches.push((
+				relative_path,
+				format!(
+					"is {width}x{height} pixels, but its base texture {} is \
+					{base_width}x{base_height} pixels",
+					base_relative_path.as_str()
+				)
+			));
+		}
+
+		mismatches
+	}
+
+	/// Reads the suffix of emissive textures from the emissive textures properties file of the
+	/// specified pack files, returning `None` if it is not defined.
+	async fn emissive_suffix(&self, pack_files: &[(RelativePath<'_>, PathBuf)]) -> Option<String> {
+		let (_, file_path) = pack_files
+			.iter()
+			.find(|(relative_path, _)| relative_path.as_str() == EMISSIVE_PROPERTIES_FILE_PATH)?;
+		let (data, _) = read_file(&self.inner, file_path).await.ok()?;
+
+		let mut emissive_suffix = None;
+		PropertiesIter::new(&*data)
+			.read_into(|key, value| {
+				if key == "suffix.emissive" {
+					emissive_suffix = Some(value);
+				}
+			})
+			.ok()?;
+
+		emissive_suffix.filter(|suffix| !suffix.is_empty())
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for EmissiveTexturePaddingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = V::FileIter;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.padded_textures
+				.get(path.as_ref())
+				.map(|padded_texture| (padded_texture, None))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
+
+/// Returns the width and height of the specified PNG texture, or `None` if its header can't
+/// be decoded.
+fn texture_dimensions(texture: &[u8]) -> Option<(u32, u32)> {
+	let png_reader = spng::Decoder::new(texture)
+		.with_context_flags(ContextFlags::IGNORE_ADLER32)
+		.read_info()
+		.ok()?;
+
+	Some((png_reader.info().width, png_reader.info().height))
+}
+
+/// Pads the specified PNG texture with transparent pixels to the right and bottom, up to the
+/// specified dimensions, returning the padded texture encoded as a PNG. `None` is returned if
+/// the texture can't be decoded or encoded.
+fn pad_texture(texture: &[u8], padded_width: u32, padded_height: u32) -> Option<Vec<u8>> {
+	// Minecraft does not apply gamma correction to textures, so neither do we
+	let mut png_reader = spng::Decoder::new(texture)
+		.with_decode_flags(DecodeFlags::TRANSPARENCY)
+		.with_context_flags(ContextFlags::IGNORE_ADLER32)
+		.with_output_format(Format::Rgba8)
+		.read_info()
+		.ok()?;
+	let row_size = png_reader.info().width as usize * 4;
+	let mut pixels = vec![0; png_reader.output_buffer_size()];
+	png_reader.next_frame(&mut pixels).ok()?;
+
+	let padded_row_size = padded_width as usize * 4;
+	let mut padded_pixels = vec![0; padded_row_size * padded_height as usize];
+	for (row, padded_row) in pixels
+		.chunks_exact(row_size)
+		.zip(padded_pixels.chunks_exact_mut(padded_row_size))
+	{
+		padded_row[..row_size].copy_from_slice(row);
+	}
+
+	// Encode the padded texture quickly: it will be optimized later, like any other texture
+	RawImage::new(
+		padded_width,
+		padded_height,
+		ColorType::RGBA,
+		BitDepth::Eight,
+		padded_pixels
+	)
+	.ok()?
+	.create_optimized_png(&Options::from_preset(0))
+	.ok()
+}
@@ -0,0 +1,141 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+use tokio::io::AsyncReadExt;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+/// Encodes an opaque white texture with the specified dimensions as a PNG.
+fn texture(width: u32, height: u32) -> Vec<u8> {
+	RawImage::new(
+		width,
+		height,
+		ColorType::RGBA,
+		BitDepth::Eight,
+		vec![255; width as usize * height as usize * 4]
+	)
+	.expect("The test image is assumed to be valid")
+	.create_optimized_png(&Options::from_preset(0))
+	.expect("Encoding the test image is assumed not to fail")
+}
+
+#[test]
+fn emissive_textures_are_validated_and_padded() {
+	let root_dir = Builder::new()
+		.prefix("ps-emissive-texture-padding-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"assets/minecraft/optifine/emissive.properties",
+			b"suffix.emissive=_e\n".to_vec()
+		),
+		("assets/minecraft/textures/block/lamp.png", texture(16, 16)),
+		(
+			"assets/minecraft/textures/block/furnace.png",
+			texture(16, 64)
+		),
+		(
+			"assets/minecraft/textures/block/furnace.png.mcmeta",
+			b"{ \"animation\": {} }".to_vec()
+		),
+		(
+			"assets/minecraft/textures/block/furnace_e.png",
+			texture(16, 32)
+		),
+		(
+			"assets/minecraft/textures/block/lamp_e.png",
+			texture(16, 16)
+		),
+		(
+			"assets/minecraft/textures/entity/golem.png",
+			texture(64, 64)
+		),
+		(
+			"assets/minecraft/textures/entity/golem_e.png",
+			texture(64, 32)
+		),
+		("assets/minecraft/textures/item/gem.png", texture(32, 32)),
+		("assets/minecraft/textures/item/gem_e.png", texture(16, 16)),
+		("assets/minecraft/textures/item/orphan_e.png", texture(8, 8))
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	// Only report mismatches at first
+	let mut vfs = EmissiveTexturePaddingVfs::new(OsFilesystem);
+	let mismatches = tokio_test::block_on(vfs.process_emissive_textures(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		MismatchedEmissiveTextureHandling::Warn
+	));
+
+	assert_eq!(
+		mismatches
+			.iter()
+			.map(|(relative_path, mismatch)| (relative_path.as_str(), mismatch.as_str()))
+			.collect::<Vec<_>>(),
+		[
+			(
+				"assets/minecraft/textures/block/furnace_e.png",
+				"is 16x32 pixels, but its base texture assets/minecraft/textures/block/furnace.png \
+				is 16x64 pixels"
+			),
+			(
+				"assets/minecraft/textures/entity/golem_e.png",
+				"is 64x32 pixels, but its base texture assets/minecraft/textures/entity/golem.png \
+				is 64x64 pixels"
+			),
+			(
+				"assets/minecraft/textures/item/gem_e.png",
+				"is 16x16 pixels, but its base texture assets/minecraft/textures/item/gem.png is \
+				32x32 pixels"
+			)
+		]
+	);
+	assert!(
+		vfs.padded_textures.is_empty(),
+		"No texture should be padded when only reporting mismatches"
+	);
+
+	// Now pad the emissive textures that can be padded
+	let mut vfs = EmissiveTexturePaddingVfs::new(OsFilesystem);
+	let mismatches = tokio_test::block_on(vfs.process_emissive_textures(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		MismatchedEmissiveTextureHandling::Pad
+	));
+
+	assert_eq!(
+		mismatches
+			.iter()
+			.map(|(relative_path, _)| relative_path.as_str())
+			.collect::<Vec<_>>(),
+		[
+			"assets/minecraft/textures/block/furnace_e.png",
+			"assets/minecraft/textures/item/gem_e.png"
+		],
+		"Emissive textures of animated textures should not be padded"
+	);
+
+	let mut padded_texture = vec![];
+	tokio_test::block_on(
+		vfs.open(
+			root_dir
+				.path()
+				.join("assets/minecraft/textures/entity/golem_e.png")
+		)
+		.expect("The padded texture should be readable")
+		.file_read
+		.read_to_end(&mut padded_texture)
+	)
+	.expect("The padded texture should be readable");
+
+	assert_eq!(texture_dimensions(&padded_texture), Some((64, 64)));
+}
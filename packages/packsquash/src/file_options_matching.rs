@@ -0,0 +1,66 @@
+//! Contains the matcher that relates relative pack file paths to the per-file options that
+//! apply to them.
+
+use globset::{GlobSet, GlobSetBuilder};
+
+use crate::config::compile_pack_file_glob_pattern;
+
+#[cfg(test)]
+mod tests;
+
+/// The prefix of a per-file options glob pattern that negates it, making it match the paths
+/// that the rest of the pattern does not match. A literal leading `!` can be matched by
+/// escaping it with a backslash.
+const NEGATION_PREFIX: char = '!';
+
+/// A set of per-file options glob patterns, which may be negated, with well-defined
+/// precedence: when several patterns match a path, the patterns that come later take
+/// precedence over the earlier ones.
+#[derive(Clone)]
+pub struct FileOptionsGlobSet {
+	glob_set: GlobSet,
+	negated_globs: Vec<bool>
+}
+
+impl FileOptionsGlobSet {
+	/// Compiles the specified per-file options glob patterns to a set ready to match
+	/// relative pack file paths. An error will be returned if any glob pattern is invalid.
+	pub fn new<'pattern>(
+		glob_patterns: impl IntoIterator<Item = &'pattern str>
+	) -> Result<Self, globset::Error> {
+		let mut globset_builder = GlobSetBuilder::new();
+		let mut negated_globs = vec![];
+
+		for glob_pattern in glob_patterns {
+			let negated_glob_pattern = glob_pattern.strip_prefix(NEGATION_PREFIX);
+
+			globset_builder.add(compile_pack_file_glob_pattern(
+				negated_glob_pattern.unwrap_or(glob_pattern)
+			)?);
+			negated_globs.push(negated_glob_pattern.is_some());
+		}
+
+		Ok(Self {
+			glob_set: globset_builder.build()?,
+			negated_globs
+		})
+	}
+
+	/// Returns the indices of the glob patterns that match the specified relative path, in
+	/// decreasing precedence order. Negated glob patterns match a path when the pattern
+	/// without the negation prefix does not.
+	pub fn matches(&self, relative_path: &str) -> Vec<usize> {
+		let mut glob_matches = vec![false; self.negated_globs.len()];
+		for i in self.glob_set.matches(relative_path) {
+			glob_matches[i] = true;
+		}
+
+		glob_matches
+			.into_iter()
+			.zip(&self.negated_globs)
+			.enumerate()
+			.rev()
+			.filter_map(|(i, (glob_matches, negated))| (glob_matches != *negated).then_some(i))
+			.collect()
+	}
+}
@@ -0,0 +1,47 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+#[test]
+fn later_patterns_take_precedence() {
+	let glob_set =
+		FileOptionsGlobSet::new(["**/*?.png", "assets/*/textures/gui/**", "**/*?.ogg"]).unwrap();
+
+	assert_eq!(
+		glob_set.matches("assets/minecraft/textures/gui/widgets.png"),
+		[1, 0]
+	);
+	assert_eq!(
+		glob_set.matches("assets/minecraft/textures/block/stone.png"),
+		[0]
+	);
+	assert_eq!(glob_set.matches("pack.mcmeta"), [] as [usize; 0]);
+}
+
+#[test]
+fn negated_patterns_match_the_rest_of_paths() {
+	let glob_set = FileOptionsGlobSet::new(["**/*?.png", "!assets/**/debug/**"]).unwrap();
+
+	assert_eq!(
+		glob_set.matches("assets/minecraft/textures/block/stone.png"),
+		[1, 0]
+	);
+	assert_eq!(
+		glob_set.matches("assets/minecraft/textures/debug/grid.png"),
+		[0]
+	);
+	assert_eq!(glob_set.matches("pack.mcmeta"), [1]);
+}
+
+#[test]
+fn escaped_negation_prefixes_are_literal() {
+	let glob_set = FileOptionsGlobSet::new([r"\!important.txt"]).unwrap();
+
+	assert_eq!(glob_set.matches("!important.txt"), [0]);
+	assert_eq!(glob_set.matches("important.txt"), [] as [usize; 0]);
+}
+
+#[test]
+fn invalid_patterns_are_rejected() {
+	assert!(FileOptionsGlobSet::new(["!assets/{textures"]).is_err());
+}
@@ -0,0 +1,675 @@
+//! Contains a virtual file system adapter that packs the textures of bitmap font providers
+//! into fewer, bigger atlas textures, rewriting the font definition files that reference them.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::SystemTime
+};
+
+use ahash::{AHashMap, AHashSet};
+use bytes::Bytes;
+use json_comments::StripComments;
+use oxipng::{BitDepth, ColorType, Options, RawImage};
+use serde_json::Value;
+use spng::{ContextFlags, DecodeFlags, Format};
+use tokio_util::either::Either;
+
+use crate::RelativePath;
+use crate::pack_file::strip_utf8_bom;
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum height of the generated atlas textures, in pixels, which keeps them well within
+/// the texture size limits of most GPUs.
+const MAXIMUM_ATLAS_HEIGHT: usize = 4096;
+
+/// A file generated or rewritten by [`FontAtlasPackingVfs`].
+struct GeneratedFile {
+	data: Bytes,
+	modification_time: Option<SystemTime>
+}
+
+/// A font definition file read by [`FontAtlasPackingVfs`]: its file path, namespace, name,
+/// parsed contents and modification time.
+type FontFile<'a> = (&'a PathBuf, &'a str, &'a str, Value, Option<SystemTime>);
+
+/// The decoded texture of a bitmap font provider, whose glyphs are laid out in a grid of cells
+/// with as many rows as strings in the `chars` list of the provider, and as many columns as
+/// codepoints in each of these strings.
+struct FontTexture {
+	pixels: Vec<u8>,
+	width: usize,
+	height: usize,
+	columns: usize
+}
+
+/// An atlas texture generated by [`pack_font_atlases`], made up of the textures of several
+/// bitmap font providers stacked vertically.
+struct FontAtlas {
+	pixels: Vec<u8>,
+	width: usize,
+	height: usize,
+	/// The indices of the packed providers in the providers list, in ascending order.
+	provider_indices: Vec<usize>,
+	/// The `chars` list of the provider that replaces the packed providers.
+	chars: Vec<Value>
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently replacing the textures of
+/// compatible bitmap font providers of the same font with atlas textures that contain several
+/// of them, and rewriting the font definition files to reference the atlases. This reduces the
+/// number of files in the pack, and thus the ZIP file overhead.
+///
+/// GUI sprites are not packed, because they are referenced by the path of their texture, and
+/// Minecraft already stitches every GUI sprite into a single atlas when loading the pack.
+///
+/// The generated files are computed eagerly by [`Self::pack_font_atlases`] and kept in memory,
+/// because the [`VirtualFileSystem::open`] method is synchronous. Other files are read from
+/// the wrapped file system as-is.
+pub struct FontAtlasPackingVfs<V: VirtualFileSystem> {
+	inner: V,
+	generated_files: AHashMap<PathBuf, GeneratedFile>,
+	/// The paths of the font textures that were packed into atlases, which are removed from
+	/// file iterators.
+	packed_texture_paths: Arc<AHashSet<PathBuf>>,
+	/// The relative and file paths of the generated atlas textures, which are added to file
+	/// iterators.
+	atlas_texture_paths: Arc<Vec<(String, PathBuf)>>
+}
+
+impl<V: VirtualFileSystem> FontAtlasPackingVfs<V> {
+	/// Wraps the specified virtual file system. No font textures will be packed until
+	/// [`Self::pack_font_atlases`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			generated_files: AHashMap::new(),
+			packed_texture_paths: Arc::new(AHashSet::new()),
+			atlas_texture_paths: Arc::new(Vec::new())
+		}
+	}
+
+	/// Scans the pack at the specified root path for font definition files with bitmap
+	/// providers whose textures can be packed into atlases, computing the atlases and the
+	/// rewritten font definition files. Textures referenced by more than one provider are never
+	/// packed, and font files or textures that can't be read or parsed are left as-is, so that
+	/// the usual pack file processing reports any relevant error.
+	pub async fn pack_font_atlases(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) {
+		let pack_files = self.pack_files(root_path, iterator_traversal_options);
+		let (fonts, texture_reference_counts) = self.read_fonts(&pack_files).await;
+
+		let mut packed_texture_paths = AHashSet::new();
+		let mut atlas_texture_paths = Vec::new();
+		for (font_file_path, namespace, font_name, mut font, font_modification_time) in fonts {
+			let mut textures = Vec::new();
+			let mut texture_sources = Vec::new();
+			for provider in providers(&font) {
+				let texture_file_path = match bitmap_provider_texture_path(provider) {
+					Some(texture_path) if texture_reference_counts[&texture_path] == 1 => {
+						pack_files.get(&texture_path)
+					}
+					_ => None
+				};
+
+				let (texture, texture_modification_time) = match texture_file_path {
+					Some(texture_file_path) => self.read_file(texture_file_path).await.and_then(
+						|(texture, modification_time)| {
+							Some((FontTexture::decode(&texture, provider)?, modification_time))
+						}
+					),
+					None => None
+				}
+				.unzip();
+
+				textures.push(texture);
+				texture_sources.push((texture_file_path, texture_modification_time.flatten()));
+			}
+
+			let Some(providers) = font.get_mut("providers").and_then(Value::as_array_mut) else {
+				continue;
+			};
+			let atlases = pack_font_atlases(providers, textures);
+			if atlases.is_empty() {
+				continue;
+			}
+
+			// The font file depends on every packed texture now, so consider it modified when any
+			// of them is, so that stale versions of it are not reused from previous runs
+			let mut rewritten_font_modification_time = font_modification_time;
+			let mut removed_provider_indices = Vec::new();
+			let mut atlas_number = 0;
+			for atlas in atlases {
+				// Never overwrite files that are in the pack or were generated before
+				let (atlas_resource_path, atlas_relative_path, atlas_file_path) = loop {
+					let atlas_resource_path = format!("font/{font_name}_atlas_{atlas_number}.png");
+					let atlas_relative_path =
+						format!("assets/{namespace}/textures/{atlas_resource_path}");
+					let atlas_file_path = root_path.join(&atlas_relative_path);
+					atlas_number += 1;
+
+					if !pack_files.contains_key(&atlas_relative_path)
+						&& !self.generated_files.contains_key(&atlas_file_path)
+					{
+						break (atlas_resource_path, atlas_relative_path, atlas_file_path);
+					}
+				};
+
+				// Encode the atlas quickly: it will be optimized later, like any other texture
+				let Some(atlas_texture) = RawImage::new(
+					atlas.width as u32,
+					atlas.height as u32,
+					ColorType::RGBA,
+					BitDepth::Eight,
+					atlas.pixels
+				)
+				.ok()
+				.and_then(|atlas_texture| {
+					atlas_texture
+						.create_optimized_png(&Options::from_preset(0))
+						.ok()
+				}) else {
+					continue;
+				};
+
+				let mut atlas_modification_time = font_modification_time;
+				for provider_index in &atlas.provider_indices {
+					let (texture_file_path, texture_modification_time) =
+						texture_sources[*provider_index];
+
+					atlas_modification_time = atlas_modification_time
+						.zip(texture_modification_time)
+						.map(|(atlas_time, texture_time)| atlas_time.max(texture_time));
+					packed_texture_paths.insert(texture_file_path.unwrap().clone());
+				}
+				rewritten_font_modification_time = rewritten_font_modification_time
+					.zip(atlas_modification_time)
+					.map(|(font_time, atlas_time)| font_time.max(atlas_time));
+
+				// The first packed provider is replaced with a provider that references the atlas,
+				// so that glyphs keep the same precedence
+				let atlas_provider = &mut providers[atlas.provider_indices[0]];
+				atlas_provider["file"] = format!("{namespace}:{atlas_resource_path}").into();
+				atlas_provider["chars"] = Value::Array(atlas.chars);
+				removed_provider_indices.extend_from_slice(&atlas.provider_indices[1..]);
+
+				self.generated_files.insert(
+					atlas_file_path.clone(),
+					GeneratedFile {
+						data: atlas_texture.into(),
+						modification_time: atlas_modification_time
+					}
+				);
+				atlas_texture_paths.push((atlas_relative_path, atlas_file_path));
+			}
+
+			removed_provider_indices.sort_unstable();
+			for provider_index in removed_provider_indices.into_iter().rev() {
+				providers.remove(provider_index);
+			}
+
+			if let Ok(rewritten_font) = serde_json::to_vec(&font) {
+				self.generated_files.insert(
+					font_file_path.clone(),
+					GeneratedFile {
+						data: rewritten_font.into(),
+						modification_time: rewritten_font_modification_time
+					}
+				);
+			}
+		}
+
+		self.packed_texture_paths = Arc::new(packed_texture_paths);
+		self.atlas_texture_paths = Arc::new(atlas_texture_paths);
+	}
+
+	/// Scans the pack at the specified root path for bitmap font providers whose glyph grid has
+	/// rows without glyphs, which are made up of null characters, removing these rows from both
+	/// the `chars` list of the provider and its texture. Trimmed textures are only quickly
+	/// encoded here, because they will be optimized later like any other texture, which also
+	/// reduces their palette to the colors of the remaining glyphs. Textures referenced by more
+	/// than one provider are never trimmed, and font files or textures that can't be read or
+	/// parsed are left as-is, so that the usual pack file processing reports any relevant error.
+	///
+	/// Font textures should be trimmed before they are packed by [`Self::pack_font_atlases`],
+	/// which then packs the trimmed textures.
+	pub async fn trim_glyph_sheets(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) {
+		let pack_files = self.pack_files(root_path, iterator_traversal_options);
+		let (fonts, texture_reference_counts) = self.read_fonts(&pack_files).await;
+
+		for (font_file_path, _, _, mut font, font_modification_time) in fonts {
+			let Some(providers) = font.get_mut("providers").and_then(Value::as_array_mut) else {
+				continue;
+			};
+
+			let mut font_was_trimmed = false;
+			for provider in providers {
+				let Some(texture_file_path) = bitmap_provider_texture_path(provider)
+					.filter(|texture_path| texture_reference_counts[texture_path] == 1)
+					.and_then(|texture_path| pack_files.get(&texture_path))
+				else {
+					continue;
+				};
+
+				let Some((texture, texture_modification_time)) =
+					self.read_file(texture_file_path).await
+				else {
+					continue;
+				};
+				let Some((trimmed_texture, trimmed_chars)) = FontTexture::decode(&texture, provider)
+					.and_then(|texture| trim_glyph_sheet(provider, texture))
+				else {
+					continue;
+				};
+
+				let Some(trimmed_texture) = RawImage::new(
+					trimmed_texture.width as u32,
+					trimmed_texture.height as u32,
+					ColorType::RGBA,
+					BitDepth::Eight,
+					trimmed_texture.pixels
+				)
+				.ok()
+				.and_then(|trimmed_texture| {
+					trimmed_texture
+						.create_optimized_png(&Options::from_preset(0))
+						.ok()
+				}) else {
+					continue;
+				};
+
+				// Only change the glyphs once the trimmed texture is ready, so that they
+				// stay in sync with the texture if it can't be encoded
+				provider["chars"] = trimmed_chars;
+
+				// The trimmed texture depends on the glyphs of the font file too
+				self.generated_files.insert(
+					texture_file_path.clone(),
+					GeneratedFile {
+						data: trimmed_texture.into(),
+						modification_time: font_modification_time
+							.zip(texture_modification_time)
+							.map(|(font_time, texture_time)| font_time.max(texture_time))
+					}
+				);
+				font_was_trimmed = true;
+			}
+
+			if font_was_trimmed && let Ok(trimmed_font) = serde_json::to_vec(&font) {
+				self.generated_files.insert(
+					font_file_path.clone(),
+					GeneratedFile {
+						data: trimmed_font.into(),
+						modification_time: font_modification_time
+					}
+				);
+			}
+		}
+	}
+
+	/// Returns the relative and file paths of the files of the wrapped file system, keyed by
+	/// their relative path.
+	fn pack_files(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> AHashMap<String, PathBuf> {
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+			.map(|entry| {
+				(
+					entry.relative_path.into_inner().into_owned(),
+					entry.file_path
+				)
+			})
+			.collect()
+	}
+
+	/// Reads and parses the font definition files among the specified pack files, returning
+	/// their file path, namespace, name, contents and modification time, and how many bitmap
+	/// providers reference each texture. Font files that can't be read or parsed are skipped.
+	async fn read_fonts<'a>(
+		&self,
+		pack_files: &'a AHashMap<String, PathBuf>
+	) -> (Vec<FontFile<'a>>, AHashMap<String, usize>) {
+		let mut fonts = Vec::new();
+		let mut texture_reference_counts = AHashMap::<String, usize>::new();
+		for (relative_path, file_path) in pack_files {
+			let Some((namespace, font_name)) = relative_path
+				.strip_prefix("assets/")
+				.and_then(|path| path.split_once("/font/"))
+				.and_then(|(namespace, font_path)| {
+					Some((namespace, font_path.strip_suffix(".json")?))
+				})
+			else {
+				continue;
+			};
+
+			let Some((font, modification_time)) = self.read_file(file_path).await else {
+				continue;
+			};
+			let Ok(font) =
+				serde_json::from_reader::<_, Value>(StripComments::new(strip_utf8_bom(&font)))
+			else {
+				continue;
+			};
+
+			for provider in providers(&font) {
+				if let Some(texture_path) = bitmap_provider_texture_path(provider) {
+					*texture_reference_counts.entry(texture_path).or_default() += 1;
+				}
+			}
+
+			fonts.push((file_path, namespace, font_name, font, modification_time));
+		}
+
+		(fonts, texture_reference_counts)
+	}
+
+	/// Reads the contents and modification time of the file at the specified path of the
+	/// wrapped file system, returning `None` if some I/O error occurs. Files generated before
+	/// take precedence over the files of the wrapped file system.
+	async fn read_file(&self, path: &Path) -> Option<(Vec<u8>, Option<SystemTime>)> {
+		if let Some(generated_file) = self.generated_files.get(path) {
+			return Some((
+				generated_file.data.to_vec(),
+				generated_file.modification_time
+			));
+		}
+
+		read_file(&self.inner, path).await.ok()
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for FontAtlasPackingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = impl Iterator<Item = Result<VfsPackFileIterEntry, io::Error>>;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		let packed_texture_paths = Arc::clone(&self.packed_texture_paths);
+		let atlas_texture_entries = self
+			.atlas_texture_paths
+			.iter()
+			.filter(|(_, file_path)| file_path.starts_with(root_path))
+			.map(|(relative_path, file_path)| {
+				Ok(VfsPackFileIterEntry {
+					relative_path: RelativePath::from_inner(relative_path.clone()),
+					file_path: file_path.clone()
+				})
+			})
+			.collect::<Vec<_>>();
+
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.filter(move |entry| {
+				!entry
+					.as_ref()
+					.is_ok_and(|entry| packed_texture_paths.contains(&entry.file_path))
+			})
+			.chain(atlas_texture_entries)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.generated_files
+				.get(path.as_ref())
+				.map(|generated_file| (&generated_file.data, generated_file.modification_time))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
+
+impl FontTexture {
+	/// Decodes the texture of the specified bitmap font provider, validating that its
+	/// dimensions are evenly divided by the glyph grid defined by its `chars` list. `None` is
+	/// returned if the texture or the provider are invalid.
+	fn decode(texture: &[u8], provider: &Value) -> Option<Self> {
+		let rows = provider_chars(provider)?;
+		let columns = rows.first()?.chars().count();
+		if columns == 0 || rows.iter().any(|row| row.chars().count() != columns) {
+			return None;
+		}
+
+		// Minecraft does not apply gamma correction to textures, so neither do we
+		let mut png_reader = spng::Decoder::new(texture)
+			.with_decode_flags(DecodeFlags::TRANSPARENCY)
+			.with_context_flags(ContextFlags::IGNORE_ADLER32)
+			.with_output_format(Format::Rgba8)
+			.read_info()
+			.ok()?;
+		let width = png_reader.info().width as usize;
+		let height = png_reader.info().height as usize;
+		if width % columns != 0 || height % rows.len() != 0 {
+			return None;
+		}
+
+		let mut pixels = vec![0; png_reader.output_buffer_size()];
+		png_reader.next_frame(&mut pixels).ok()?;
+
+		Some(Self {
+			pixels,
+			width,
+			height,
+			columns
+		})
+	}
+}
+
+/// Returns the providers of the specified font definition, which is empty if it has no valid
+/// providers list.
+fn providers(font: &Value) -> &[Value] {
+	font.get("providers")
+		.and_then(Value::as_array)
+		.map(Vec::as_slice)
+		.unwrap_or_default()
+}
+
+/// Returns whether the specified font provider is a bitmap provider.
+fn is_bitmap_provider(provider: &Value) -> bool {
+	provider.get("type").and_then(Value::as_str) == Some("bitmap")
+}
+
+/// Returns the rows of glyphs of the specified bitmap font provider, or `None` if they are
+/// missing or invalid.
+fn provider_chars(provider: &Value) -> Option<Vec<&str>> {
+	provider
+		.get("chars")?
+		.as_array()?
+		.iter()
+		.map(Value::as_str)
+		.collect()
+}
+
+/// Returns the pack relative path of the texture of the specified font provider, if it is a
+/// bitmap provider with a valid texture resource location.
+fn bitmap_provider_texture_path(provider: &Value) -> Option<String> {
+	if !is_bitmap_provider(provider) {
+		return None;
+	}
+
+	let (namespace, path) = provider
+		.get("file")?
+		.as_str()?
+		.split_once(':')
+		.unwrap_or(("minecraft", provider.get("file")?.as_str()?));
+
+	Some(format!("assets/{namespace}/textures/{path}"))
+}
+
+/// Removes the rows of glyphs of the specified bitmap font provider that are only made up of
+/// null characters, which mark empty glyph cells, from its decoded texture, returning the
+/// trimmed texture and the `chars` list that matches it. The provider itself is not modified,
+/// so that callers can commit the new `chars` list only after the texture is successfully
+/// encoded. `None` is returned if no row can be removed, or if every row would be removed,
+/// because Minecraft rejects bitmap providers without glyphs.
+fn trim_glyph_sheet(provider: &Value, texture: FontTexture) -> Option<(FontTexture, Value)> {
+	let rows = provider_chars(provider)?;
+	let row_count = rows.len();
+	let used_rows = rows
+		.iter()
+		.enumerate()
+		.filter(|(_, row)| row.chars().any(|glyph| glyph != '\0'))
+		.map(|(index, _)| index)
+		.collect::<Vec<_>>();
+	if used_rows.is_empty() || used_rows.len() == row_count {
+		return None;
+	}
+
+	let row_size = texture.pixels.len() / row_count;
+	let mut pixels = Vec::with_capacity(row_size * used_rows.len());
+	for index in &used_rows {
+		pixels.extend_from_slice(&texture.pixels[index * row_size..(index + 1) * row_size]);
+	}
+
+	let chars = used_rows
+		.iter()
+		.map(|index| Value::from(rows[*index]))
+		.collect();
+
+	Some((
+		FontTexture {
+			height: texture.height / row_count * used_rows.len(),
+			pixels,
+			width: texture.width,
+			columns: texture.columns
+		},
+		Value::Array(chars)
+	))
+}
+
+/// Packs the specified decoded textures of the providers of a font into atlases. Each element
+/// of `textures` is the texture of the provider at the same index, if it can be packed. Textures
+/// are only packed together when their providers are compatible, which means that they have the
+/// same properties except the texture and glyphs, and their textures have the same width, glyph
+/// grid columns and glyph cell height, so that they can be stacked vertically.
+///
+/// As Minecraft uses the first provider that defines a glyph, packing is restricted to runs of
+/// consecutive bitmap providers whose glyphs are all different, so that the precedence of glyphs
+/// does not change. The providers themselves are not modified: the returned atlases describe how
+/// they should be replaced.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Resource_pack#Fonts>
+/// - Minecraft class `net.minecraft.client.gui.font.providers.BitmapProvider`
+fn pack_font_atlases(providers: &[Value], mut textures: Vec<Option<FontTexture>>) -> Vec<FontAtlas> {
+	let mut atlases = Vec::new();
+
+	let mut run_start = 0;
+	while run_start < providers.len() {
+		let run_length = providers[run_start..]
+			.iter()
+			.take_while(|provider| is_bitmap_provider(provider))
+			.count();
+		let run = run_start..run_start + run_length;
+		run_start += run_length.max(1);
+
+		// Empty glyph cells are marked with the null character, which is not a glyph
+		let mut glyphs = AHashSet::new();
+		let glyphs_are_unique = providers[run.clone()].iter().all(|provider| {
+			provider_chars(provider).is_some_and(|rows| {
+				rows.iter()
+					.flat_map(|row| row.chars())
+					.filter(|glyph| *glyph != '\0')
+					.all(|glyph| glyphs.insert(glyph))
+			})
+		});
+		if !glyphs_are_unique {
+			continue;
+		}
+
+		// Group the packable providers of this run by their compatibility key, in order
+		let mut groups = Vec::<((Value, usize, usize, usize), Vec<usize>)>::new();
+		for index in run {
+			let Some(texture) = &textures[index] else {
+				continue;
+			};
+
+			let mut properties = providers[index].clone();
+			if let Some(properties) = properties.as_object_mut() {
+				properties.remove("file");
+				properties.remove("chars");
+			}
+			let rows = provider_chars(&providers[index]).unwrap().len();
+			let key = (
+				properties,
+				texture.width,
+				texture.columns,
+				texture.height / rows
+			);
+
+			match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+				Some((_, provider_indices)) => provider_indices.push(index),
+				None => groups.push((key, vec![index]))
+			}
+		}
+
+		for ((_, width, _, _), provider_indices) in groups {
+			// Split the group in atlases that do not get too big
+			let mut atlases_provider_indices = vec![Vec::new()];
+			let mut atlas_height = 0;
+			for index in provider_indices {
+				let texture_height = textures[index].as_ref().unwrap().height;
+				if atlas_height + texture_height > MAXIMUM_ATLAS_HEIGHT {
+					atlases_provider_indices.push(Vec::new());
+					atlas_height = 0;
+				}
+
+				atlases_provider_indices.last_mut().unwrap().push(index);
+				atlas_height += texture_height;
+			}
+
+			// Atlases of a single texture would not reduce the number of files
+			for provider_indices in atlases_provider_indices
+				.into_iter()
+				.filter(|provider_indices| provider_indices.len() > 1)
+			{
+				let mut pixels = Vec::new();
+				let mut chars = Vec::new();
+				for index in &provider_indices {
+					pixels.extend(textures[*index].take().unwrap().pixels);
+					chars.extend(
+						provider_chars(&providers[*index])
+							.unwrap()
+							.into_iter()
+							.map(Value::from)
+					);
+				}
+
+				atlases.push(FontAtlas {
+					height: pixels.len() / (width * 4),
+					pixels,
+					width,
+					provider_indices,
+					chars
+				});
+			}
+		}
+	}
+
+	atlases
+}
@@ -0,0 +1,170 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+/// Encodes a single row of pixels with the specified RGBA colors as a PNG.
+fn pixel_row(colors: &[[u8; 4]]) -> Vec<u8> {
+	RawImage::new(
+		colors.len() as u32,
+		1,
+		ColorType::RGBA,
+		BitDepth::Eight,
+		colors.concat()
+	)
+	.expect("The test image is assumed to be valid")
+	.create_optimized_png(&Options::from_preset(0))
+	.expect("Encoding the test image is assumed not to fail")
+}
+
+/// Returns a decoded font texture with a single row of 1x1 glyphs of the specified colors.
+fn font_texture(colors: &[[u8; 4]]) -> Option<FontTexture> {
+	Some(FontTexture {
+		pixels: colors.concat(),
+		width: colors.len(),
+		height: 1,
+		columns: colors.len()
+	})
+}
+
+#[test]
+fn compatible_font_textures_are_packed() {
+	let root_dir = Builder::new()
+		.prefix("ps-font-atlas-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	let font_dir = root_dir.path().join("assets/minecraft/font");
+	let textures_dir = root_dir.path().join("assets/minecraft/textures/font");
+	fs::create_dir_all(&font_dir).expect("I/O operations are assumed not to fail during tests");
+	fs::create_dir_all(&textures_dir).expect("I/O operations are assumed not to fail during tests");
+	fs::write(
+		font_dir.join("default.json"),
+		br#"{
+			"providers": [
+				{ "type": "bitmap", "file": "font/first.png", "ascent": 7, "chars": ["ab"] },
+				{ "type": "bitmap", "file": "minecraft:font/second.png", "ascent": 7, "chars": ["cd"] },
+				{ "type": "space", "advances": { " ": 4 } }
+			]
+		}"#
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+	fs::write(textures_dir.join("first.png"), pixel_row(&[RED, GREEN]))
+		.expect("I/O operations are assumed not to fail during tests");
+	fs::write(textures_dir.join("second.png"), pixel_row(&[BLUE, WHITE]))
+		.expect("I/O operations are assumed not to fail during tests");
+
+	let mut vfs = FontAtlasPackingVfs::new(OsFilesystem);
+	tokio_test::block_on(vfs.pack_font_atlases(root_dir.path(), IteratorTraversalOptions::default()));
+
+	let mut relative_paths = vfs
+		.file_iterator(root_dir.path(), IteratorTraversalOptions::default())
+		.map(|entry| {
+			entry
+				.expect("I/O operations are assumed not to fail during tests")
+				.relative_path
+				.as_str()
+				.to_string()
+		})
+		.collect::<Vec<_>>();
+	relative_paths.sort_unstable();
+
+	assert_eq!(
+		relative_paths,
+		[
+			"assets/minecraft/font/default.json",
+			"assets/minecraft/textures/font/default_atlas_0.png"
+		]
+	);
+
+	let font = &vfs.generated_files[&font_dir.join("default.json")].data;
+	assert_eq!(
+		serde_json::from_slice::<Value>(font).unwrap(),
+		json!({
+			"providers": [
+				{
+					"type": "bitmap",
+					"file": "minecraft:font/default_atlas_0.png",
+					"ascent": 7,
+					"chars": ["ab", "cd"]
+				},
+				{ "type": "space", "advances": { " ": 4 } }
+			]
+		})
+	);
+
+	let atlas = &vfs.generated_files[&textures_dir.join("default_atlas_0.png")].data;
+	let mut png_reader = spng::Decoder::new(&**atlas)
+		.with_output_format(Format::Rgba8)
+		.read_info()
+		.expect("The atlas should be a valid PNG");
+	let mut pixels = vec![0; png_reader.output_buffer_size()];
+	png_reader
+		.next_frame(&mut pixels)
+		.expect("The atlas should be a valid PNG");
+	assert_eq!(pixels, [RED, GREEN, BLUE, WHITE].concat());
+}
+
+#[test]
+fn providers_with_different_properties_are_not_packed() {
+	let providers = [
+		json!({ "type": "bitmap", "file": "font/a.png", "ascent": 7, "chars": ["ab"] }),
+		json!({ "type": "bitmap", "file": "font/b.png", "ascent": 8, "chars": ["cd"] })
+	];
+
+	assert!(
+		pack_font_atlases(
+			&providers,
+			vec![font_texture(&[RED, GREEN]), font_texture(&[BLUE, WHITE])]
+		)
+		.is_empty()
+	);
+}
+
+#[test]
+fn providers_with_overlapping_glyphs_are_not_packed() {
+	let providers = [
+		json!({ "type": "bitmap", "file": "font/a.png", "ascent": 7, "chars": ["ab"] }),
+		json!({ "type": "bitmap", "file": "font/b.png", "ascent": 7, "chars": ["bc"] })
+	];
+
+	assert!(
+		pack_font_atlases(
+			&providers,
+			vec![font_texture(&[RED, GREEN]), font_texture(&[BLUE, WHITE])]
+		)
+		.is_empty()
+	);
+}
+
+#[test]
+fn rows_without_glyphs_are_trimmed() {
+	let provider =
+		json!({ "type": "bitmap", "file": "font/a.png", "ascent": 7, "chars": ["ab", "\0\0", "cd"] });
+	let texture = FontTexture {
+		pixels: [RED, GREEN, WHITE, WHITE, BLUE, WHITE].concat(),
+		width: 2,
+		height: 3,
+		columns: 2
+	};
+
+	let (trimmed_texture, trimmed_chars) =
+		trim_glyph_sheet(&provider, texture).expect("The empty row should be trimmed");
+
+	assert_eq!(trimmed_chars, json!(["ab", "cd"]));
+	assert_eq!(
+		provider,
+		json!({ "type": "bitmap", "file": "font/a.png", "ascent": 7, "chars": ["ab", "\0\0", "cd"] })
+	);
+	assert_eq!(trimmed_texture.height, 2);
+	assert_eq!(trimmed_texture.pixels, [RED, GREEN, BLUE, WHITE].concat());
+}
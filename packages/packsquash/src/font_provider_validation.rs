@@ -0,0 +1,229 @@
+//! Contains routines to validate the providers of the font definition files of a pack,
+//! checking that the assets they reference exist and that their glyph grids are consistent.
+
+use std::path::{Path, PathBuf};
+
+use ahash::AHashMap;
+use json_comments::StripComments;
+use serde_json::Value;
+use spng::ContextFlags;
+
+use crate::RelativePath;
+use crate::pack_file::strip_utf8_bom;
+use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem, read_file};
+
+#[cfg(test)]
+mod tests;
+
+/// The namespace of vanilla assets, which is also the namespace of resource locations that do
+/// not specify any.
+const VANILLA_NAMESPACE: &str = "minecraft";
+
+/// The height of the glyphs of bitmap providers that do not specify any.
+const DEFAULT_BITMAP_HEIGHT: i64 = 8;
+
+/// Parses the font definition files of the pack at the specified root path, finding provider
+/// mistakes that make Minecraft skip the whole provider, or fail to load the font, while only
+/// reporting them in its log, if at all. The following is checked:
+///
+/// - That the textures of bitmap providers, the ZIP files of `unihex` providers, the font files
+///   of `ttf` providers and the fonts of `reference` providers are in the pack. Assets of the
+///   vanilla namespace are not checked, because they may be provided by the game.
+/// - That every row of glyphs of a bitmap provider has the same number of glyphs, that its
+///   texture can be evenly divided in a grid of glyph cells of the same height and width, and
+///   that its ascent is not greater than its height.
+/// - That every advance of a `space` provider is defined for a single character.
+///
+/// Files that can't be read or parsed are ignored, so that the usual pack file processing
+/// reports any relevant error. Mismatches are sorted by path.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Resource_pack#Fonts>
+/// - Minecraft class `net.minecraft.client.gui.font.providers.BitmapProvider`
+pub async fn validate_font_providers<V: VirtualFileSystem>(
+	vfs: &V,
+	root_path: &Path,
+	iterator_traversal_options: IteratorTraversalOptions
+) -> Vec<(RelativePath<'static>, String)> {
+	let mut pack_files = AHashMap::new();
+	let mut font_files = vec![];
+	for VfsPackFileIterEntry {
+		relative_path,
+		file_path
+	} in vfs
+		.file_iterator(root_path, iterator_traversal_options)
+		.flatten()
+	{
+		if is_font(relative_path.as_str()) {
+			font_files.push((relative_path.clone(), file_path.clone()));
+		}
+
+		pack_files.insert(relative_path.as_str().to_owned(), file_path);
+	}
+	font_files.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+	let mut mismatches = vec![];
+	for (relative_path, file_path) in font_files {
+		let Some(font) = read_file(vfs, &file_path).await.ok().and_then(|(font, _)| {
+			serde_json::from_reader::<_, Value>(StripComments::new(strip_utf8_bom(&font))).ok()
+		}) else {
+			continue;
+		};
+
+		for (index, provider) in font
+			.get("providers")
+			.and_then(Value::as_array)
+			.into_iter()
+			.flatten()
+			.enumerate()
+		{
+			let provider_type = provider.get("type").and_then(Value::as_str).unwrap_or("?");
+			let provider_description = format!("provider {} ({provider_type})", index + 1);
+
+			for mismatch in validate_provider(vfs, provider, provider_type, &pack_files).await {
+				mismatches.push((
+					relative_path.clone(),
+					format!("{provider_description} {mismatch}")
+				));
+			}
+		}
+	}
+
+	mismatches
+}
+
+/// Checks whether the specified relative path is of a font definition file.
+fn is_font(relative_path: &str) -> bool {
+	relative_path.ends_with(".json")
+		&& relative_path
+			.strip_prefix("assets/")
+			.and_then(|path| path.split_once('/'))
+			.is_some_and(|(_, path)| path.starts_with("font/"))
+}
+
+/// Returns the relative path of the asset with the specified resource location, which is
+/// located within the specified folder prefix of its namespace.
+fn asset_path(resource_location: &str, folder_prefix: &str) -> String {
+	let (namespace, path) = resource_location
+		.split_once(':')
+		.unwrap_or((VANILLA_NAMESPACE, resource_location));
+
+	format!("assets/{namespace}/{folder_prefix}{path}")
+}
+
+/// Validates the specified font provider, returning a description of every mismatch found,
+/// which is meant to follow a description of the provider.
+async fn validate_provider<V: VirtualFileSystem>(
+	vfs: &V,
+	provider: &Value,
+	provider_type: &str,
+	pack_files: &AHashMap<String, PathBuf>
+) -> Vec<String> {
+	let is_in_pack = |relative_path: &str| {
+		pack_files.contains_key(relative_path)
+			|| relative_path.starts_with(&format!("assets/{VANILLA_NAMESPACE}/"))
+	};
+	let referenced_asset_path = |key: &str, folder_prefix: &str, extension: &str| {
+		provider
+			.get(key)
+			.and_then(Value::as_str)
+			.map(|resource_location| asset_path(resource_location, folder_prefix) + extension)
+	};
+
+	let mut mismatches = vec![];
+	match provider_type {
+		"bitmap" => {
+			let rows = provider
+				.get("chars")
+				.and_then(Value::as_array)
+				.into_iter()
+				.flatten()
+				.filter_map(Value::as_str)
+				.map(|row| row.chars().count())
+				.collect::<Vec<_>>();
+			let columns = rows.first().copied().unwrap_or_default();
+			if rows.iter().any(|row_columns| *row_columns != columns) {
+				mismatches.push("has rows of glyphs of different lengths".to_owned());
+			}
+
+			let height = provider
+				.get("height")
+				.and_then(Value::as_i64)
+				.unwrap_or(DEFAULT_BITMAP_HEIGHT);
+			if let Some(ascent) = provider.get("ascent").and_then(Value::as_i64)
+				&& ascent > height
+			{
+				mismatches.push(format!(
+					"has an ascent of {ascent}, which is greater than its height of {height}"
+				));
+			}
+
+			let Some(texture_path) = referenced_asset_path("file", "textures/", "") else {
+				return mismatches;
+			};
+			match pack_files.get(&texture_path) {
+				Some(file_path) => {
+					if let Some((width, texture_height)) = read_png_dimensions(vfs, file_path).await
+						&& columns > 0 && (width % columns != 0 || texture_height % rows.len() != 0)
+					{
+						mismatches.push(format!(
+							"uses the {width}x{texture_height} texture {texture_path}, which can't be \
+							evenly divided in {} rows and {columns} columns of glyphs",
+							rows.len()
+						));
+					}
+				}
+				None if !is_in_pack(&texture_path) => mismatches.push(format!(
+					"uses the texture {texture_path}, which is not in the pack"
+				)),
+				None => {}
+			}
+		}
+		"space" => {
+			for character in provider
+				.get("advances")
+				.and_then(Value::as_object)
+				.into_iter()
+				.flatten()
+				.map(|(character, _)| character)
+				.filter(|character| character.chars().count() != 1)
+			{
+				mismatches.push(format!(
+					"defines an advance for {character:?}, which is not a single character"
+				));
+			}
+		}
+		_ => {
+			// Resource locations of unihex ZIP files are relative to the namespace folder
+			let referenced_asset_path = match provider_type {
+				"unihex" => referenced_asset_path("hex_file", "", ""),
+				"ttf" => referenced_asset_path("file", "font/", ""),
+				"reference" => referenced_asset_path("id", "font/", ".json"),
+				_ => None
+			};
+
+			if let Some(asset_path) =
+				referenced_asset_path.filter(|asset_path| !is_in_pack(asset_path))
+			{
+				mismatches.push(format!("uses {asset_path}, which is not in the pack"));
+			}
+		}
+	}
+
+	mismatches
+}
+
+/// Reads the dimensions of the PNG image at the specified path of a virtual file system,
+/// returning `None` if some I/O or decoding error occurs.
+async fn read_png_dimensions<V: VirtualFileSystem>(vfs: &V, path: &Path) -> Option<(usize, usize)> {
+	let (png, _) = read_file(vfs, path).await.ok()?;
+	let png_reader = spng::Decoder::new(&*png)
+		.with_context_flags(ContextFlags::IGNORE_ADLER32)
+		.read_info()
+		.ok()?;
+
+	Some((
+		png_reader.info().width as usize,
+		png_reader.info().height as usize
+	))
+}
@@ -0,0 +1,100 @@
+use std::fs;
+
+use oxipng::{BitDepth, ColorType, Options, RawImage};
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[test]
+fn font_providers_are_validated() {
+	let root_dir = Builder::new()
+		.prefix("ps-font-provider-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	let glyph_sheet = RawImage::new(3, 2, ColorType::RGBA, BitDepth::Eight, vec![255; 3 * 2 * 4])
+		.expect("The test image is assumed to be valid")
+		.create_optimized_png(&Options::from_preset(0))
+		.expect("Encoding the test image is assumed not to fail");
+	for (relative_path, data) in [
+		(
+			"assets/mypack/font/icons.json",
+			r#"{
+				"providers": [
+					{ "type": "bitmap", "file": "mypack:font/icons.png", "ascent": 9, "chars": ["ab", "c"] },
+					{ "type": "bitmap", "file": "mypack:font/missing.png", "ascent": 7, "chars": ["d"] },
+					{ "type": "bitmap", "file": "font/ascii.png", "ascent": 7, "chars": ["e"] },
+					{ "type": "space", "advances": { " ": 4, "fg": 2 } },
+					{ "type": "unihex", "hex_file": "mypack:font/unifont.zip", "size_overrides": [] },
+					{ "type": "reference", "id": "mypack:missing" },
+					{ "type": "reference", "id": "mypack:symbols" }
+				]
+			}"#
+			.as_bytes()
+		),
+		(
+			"assets/mypack/textures/font/icons.png",
+			glyph_sheet.as_slice()
+		),
+		(
+			"assets/mypack/font/symbols.json",
+			br#"{ "providers": [{ "type": "ttf", "file": "mypack:symbols.ttf" }] }"#.as_slice()
+		)
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mismatches = tokio_test::block_on(validate_font_providers(
+		&OsFilesystem,
+		root_dir.path(),
+		IteratorTraversalOptions::default()
+	));
+
+	assert_eq!(
+		mismatches
+			.iter()
+			.map(|(relative_path, mismatch)| (relative_path.as_str(), mismatch.as_str()))
+			.collect::<Vec<_>>(),
+		[
+			(
+				"assets/mypack/font/icons.json",
+				"provider 1 (bitmap) has rows of glyphs of different lengths"
+			),
+			(
+				"assets/mypack/font/icons.json",
+				"provider 1 (bitmap) has an ascent of 9, which is greater than its height of 8"
+			),
+			(
+				"assets/mypack/font/icons.json",
+				"provider 1 (bitmap) uses the 3x2 texture assets/mypack/textures/font/icons.png, \
+				which can't be evenly divided in 2 rows and 2 columns of glyphs"
+			),
+			(
+				"assets/mypack/font/icons.json",
+				"provider 2 (bitmap) uses the texture assets/mypack/textures/font/missing.png, which \
+				is not in the pack"
+			),
+			(
+				"assets/mypack/font/icons.json",
+				"provider 4 (space) defines an advance for \"fg\", which is not a single character"
+			),
+			(
+				"assets/mypack/font/icons.json",
+				"provider 5 (unihex) uses assets/mypack/font/unifont.zip, which is not in the pack"
+			),
+			(
+				"assets/mypack/font/icons.json",
+				"provider 6 (reference) uses assets/mypack/font/missing.json, which is not in the pack"
+			),
+			(
+				"assets/mypack/font/symbols.json",
+				"provider 1 (ttf) uses assets/mypack/font/symbols.ttf, which is not in the pack"
+			)
+		]
+	);
+}
@@ -0,0 +1,584 @@
+//! Contains a virtual file system adapter that subsets the TrueType and OpenType fonts of a
+//! pack to the characters its language files use.
+
+use std::{
+	collections::BTreeMap,
+	fs::FileType,
+	io::{self, Cursor},
+	iter,
+	path::{Path, PathBuf},
+	time::SystemTime
+};
+
+use ahash::{AHashMap, AHashSet};
+use bytes::Bytes;
+use json_comments::StripComments;
+use serde_json::Value;
+use tokio_util::either::Either;
+
+use crate::pack_file::strip_utf8_bom;
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The flags of a composite glyph component that define the size of its arguments and
+/// transformation, and whether more components follow.
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+/// The value the checksum of a whole font file must have, according to the OpenType
+/// specification.
+const FONT_CHECKSUM: u32 = 0xB1B0AFBA;
+
+/// A font subset by [`FontSubsettingVfs`].
+struct SubsetFont {
+	data: Bytes,
+	modification_time: Option<SystemTime>
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently replacing its TrueType and
+/// OpenType fonts with subsets that only contain the glyphs of the characters used by the
+/// language files of the pack, and a configurable set of extra characters. Fonts with
+/// thousands of glyphs, such as CJK fonts, are often much bigger than the few glyphs a pack
+/// actually shows.
+///
+/// The glyph identifiers of the subset fonts are the same as those of the original fonts, so
+/// every table that references glyphs is still valid: the outlines of the glyphs that are not
+/// kept are just emptied. Only fonts with TrueType outlines are subset, because fonts with
+/// CFF outlines and variable fonts store glyph data in ways this subsetter does not rewrite,
+/// and font collections are left as-is too.
+///
+/// The subset fonts are computed eagerly by [`Self::subset_fonts`] and kept in memory,
+/// because the [`VirtualFileSystem::open`] method is synchronous. Other files are read from
+/// the wrapped file system as-is.
+pub struct FontSubsettingVfs<V: VirtualFileSystem> {
+	inner: V,
+	subset_fonts: AHashMap<PathBuf, SubsetFont>
+}
+
+impl<V: VirtualFileSystem> FontSubsettingVfs<V> {
+	/// Wraps the specified virtual file system. No fonts will be subset until
+	/// [`Self::subset_fonts`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			subset_fonts: AHashMap::new()
+		}
+	}
+
+	/// Scans the pack at the specified root path for language files and fonts, subsetting the
+	/// fonts to the characters used in the translations of the language files, and the
+	/// specified extra characters. Fonts are only replaced when their subset is smaller, and
+	/// fonts that can't be read or parsed are left as-is, so that the usual pack file
+	/// processing reports any relevant error. Returns the number of subset fonts.
+	///
+	/// References:
+	/// - <https://learn.microsoft.com/en-us/typography/opentype/spec/otff>
+	pub async fn subset_fonts(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions,
+		extra_characters: &str
+	) -> usize {
+		let mut characters = extra_characters.chars().collect::<AHashSet<_>>();
+		let mut language_files_modification_time = Some(SystemTime::UNIX_EPOCH);
+		let mut font_file_paths = vec![];
+		for VfsPackFileIterEntry {
+			relative_path,
+			file_path
+		} in self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+		{
+			let Some(asset_path) = relative_path
+				.as_str()
+				.strip_prefix("assets/")
+				.and_then(|path| path.split_once('/'))
+				.map(|(_, asset_path)| asset_path)
+			else {
+				continue;
+			};
+
+			if asset_path.starts_with("font/")
+				&& (asset_path.ends_with(".ttf") || asset_path.ends_with(".otf"))
+			{
+				font_file_paths.push(file_path);
+				continue;
+			}
+
+			let is_legacy_language_file = match asset_path.rsplit_once('.') {
+				Some((language_file_path, "json")) if language_file_path.starts_with("lang/") => {
+					false
+				}
+				Some((language_file_path, "lang")) if language_file_path.starts_with("lang/") => true,
+				_ => continue
+			};
+
+			let Ok((data, modification_time)) = read_file(&self.inner, &file_path).await else {
+				continue;
+			};
+			if is_legacy_language_file {
+				add_legacy_language_file_characters(&data, &mut characters);
+			} else {
+				add_language_file_characters(&data, &mut characters);
+			}
+
+			language_files_modification_time = language_files_modification_time
+				.zip(modification_time)
+				.map(|(latest_time, time)| latest_time.max(time));
+		}
+
+		let mut subset_font_count = 0;
+		for font_file_path in font_file_paths {
+			let Ok((font, modification_time)) = read_file(&self.inner, &font_file_path).await else {
+				continue;
+			};
+			let Some(subset_font) =
+				subset_font(&font, &characters).filter(|subset_font| subset_font.len() < font.len())
+			else {
+				continue;
+			};
+
+			// The subset font depends on the language files too, so consider it modified when
+			// any of them is, so that stale versions of it are not reused from previous runs
+			self.subset_fonts.insert(
+				font_file_path,
+				SubsetFont {
+					data: subset_font.into(),
+					modification_time: modification_time
+						.zip(language_files_modification_time)
+						.map(|(font_time, language_files_time)| font_time.max(language_files_time))
+				}
+			);
+			subset_font_count += 1;
+		}
+
+		subset_font_count
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for FontSubsettingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = V::FileIter;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.subset_fonts
+				.get(path.as_ref())
+				.map(|subset_font| (&subset_font.data, subset_font.modification_time))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
+
+/// Adds the characters of the translations of the specified JSON language file to a set.
+/// Language files that can't be parsed are ignored.
+fn add_language_file_characters(data: &[u8], characters: &mut AHashSet<char>) {
+	let Ok(Value::Object(translations)) =
+		serde_json::from_reader(StripComments::new(strip_utf8_bom(data)))
+	else {
+		return;
+	};
+
+	for translation in translations.values().filter_map(Value::as_str) {
+		characters.extend(translation.chars());
+	}
+}
+
+/// Adds the characters of the translations of the specified legacy `.lang` language file to
+/// a set. Language files that are not valid UTF-8 are ignored.
+fn add_legacy_language_file_characters(data: &[u8], characters: &mut AHashSet<char>) {
+	let Ok(data) = std::str::from_utf8(strip_utf8_bom(data)) else {
+		return;
+	};
+
+	for (_, translation) in data
+		.lines()
+		.filter(|line| !line.starts_with('#'))
+		.filter_map(|line| line.split_once('='))
+	{
+		characters.extend(translation.chars());
+	}
+}
+
+/// Subsets the specified font file to the glyphs of the specified characters, the `.notdef`
+/// glyph, and the glyphs the composite glyphs among them are made of. The outlines of the
+/// other glyphs are emptied, and the character to glyph mapping is rewritten to only map the
+/// kept characters, so that Minecraft falls back to other font providers for the rest.
+/// `None` is returned if the font does not have TrueType outlines, is a variable font, or is
+/// not valid.
+fn subset_font(font: &[u8], characters: &AHashSet<char>) -> Option<Vec<u8>> {
+	let mut tables = read_font_tables(font)?;
+	if tables.contains_key(b"gvar") {
+		return None;
+	}
+
+	let head = *tables.get(b"head")?;
+	let glyph_count = read_u16(tables.get(b"maxp")?, 4)? as usize;
+	let long_glyph_offsets = read_u16(head, 50)? != 0;
+	let loca = *tables.get(b"loca")?;
+	let glyf = *tables.get(b"glyf")?;
+
+	let glyph_offsets = (0..=glyph_count)
+		.map(|glyph| {
+			if long_glyph_offsets {
+				read_u32(loca, glyph * 4).map(|offset| offset as usize)
+			} else {
+				read_u16(loca, glyph * 2).map(|offset| offset as usize * 2)
+			}
+		})
+		.collect::<Option<Vec<_>>>()?;
+	let glyph_data = |glyph: usize| glyf.get(glyph_offsets[glyph]..glyph_offsets[glyph + 1]);
+
+	let mut character_glyphs = read_character_glyphs(tables.get(b"cmap")?, glyph_count)?
+		.into_iter()
+		.filter(|(codepoint, _)| {
+			char::from_u32(*codepoint).is_some_and(|character| characters.contains(&character))
+		})
+		.collect::<Vec<_>>();
+	character_glyphs.sort_unstable();
+	character_glyphs.dedup_by_key(|(codepoint, _)| *codepoint);
+
+	let mut kept_glyphs = vec![false; glyph_count];
+	let mut pending_glyphs = iter::once(0)
+		.chain(character_glyphs.iter().map(|(_, glyph)| *glyph))
+		.collect::<Vec<_>>();
+	while let Some(glyph) = pending_glyphs.pop() {
+		let glyph = glyph as usize;
+		if glyph >= glyph_count || kept_glyphs[glyph] {
+			continue;
+		}
+
+		kept_glyphs[glyph] = true;
+		pending_glyphs.extend(composite_glyph_components(glyph_data(glyph)?)?);
+	}
+
+	let mut subset_glyf = vec![];
+	let mut subset_glyph_offsets = Vec::with_capacity(glyph_count + 1);
+	for (glyph, kept) in kept_glyphs.into_iter().enumerate() {
+		subset_glyph_offsets.push(subset_glyf.len());
+		if kept {
+			subset_glyf.extend_from_slice(glyph_data(glyph)?);
+
+			// Short glyph offsets are stored divided by two, so glyphs must have an even length
+			if subset_glyf.len() % 2 != 0 {
+				subset_glyf.push(0);
+			}
+		}
+	}
+	subset_glyph_offsets.push(subset_glyf.len());
+
+	let subset_long_glyph_offsets = subset_glyf.len() / 2 > u16::MAX as usize;
+	let subset_loca = subset_glyph_offsets
+		.into_iter()
+		.flat_map(|offset| {
+			if subset_long_glyph_offsets {
+				(offset as u32).to_be_bytes().to_vec()
+			} else {
+				((offset / 2) as u16).to_be_bytes().to_vec()
+			}
+		})
+		.collect::<Vec<_>>();
+
+	let mut subset_head = head.to_vec();
+	subset_head[50..52].copy_from_slice(&(subset_long_glyph_offsets as u16).to_be_bytes());
+
+	let subset_cmap = write_cmap(&character_glyphs);
+
+	// Digital signatures are no longer valid once a font is modified
+	tables.remove(b"DSIG");
+	tables.insert(*b"head", &subset_head);
+	tables.insert(*b"loca", &subset_loca);
+	tables.insert(*b"glyf", &subset_glyf);
+	tables.insert(*b"cmap", &subset_cmap);
+
+	Some(write_font(&tables))
+}
+
+/// Reads the tables of the specified font file with TrueType outlines, keyed by their tag.
+/// `None` is returned if the font does not have TrueType outlines, or is not valid.
+fn read_font_tables(font: &[u8]) -> Option<BTreeMap<[u8; 4], &[u8]>> {
+	let sfnt_version = read_u32(font, 0)?;
+	if sfnt_version != 0x00010000 && sfnt_version != u32::from_be_bytes(*b"true") {
+		return None;
+	}
+
+	let table_count = read_u16(font, 4)? as usize;
+	(0..table_count)
+		.map(|table| {
+			let table_record = font.get(12 + table * 16..12 + (table + 1) * 16)?;
+			let offset = read_u32(table_record, 8)? as usize;
+			let length = read_u32(table_record, 12)? as usize;
+
+			Some((
+				table_record[..4].try_into().unwrap(),
+				font.get(offset..offset.checked_add(length)?)?
+			))
+		})
+		.collect()
+}
+
+/// Writes a font file with the specified tables, keyed by their tag, computing the checksums
+/// of the tables and the font.
+fn write_font(tables: &BTreeMap<[u8; 4], &[u8]>) -> Vec<u8> {
+	let table_count = tables.len() as u16;
+	let entry_selector = table_count.max(1).ilog2() as u16;
+	let search_range: u16 = (1 << entry_selector) * 16;
+
+	let mut font = Vec::new();
+	font.extend_from_slice(&0x00010000_u32.to_be_bytes());
+	font.extend_from_slice(&table_count.to_be_bytes());
+	font.extend_from_slice(&search_range.to_be_bytes());
+	font.extend_from_slice(&entry_selector.to_be_bytes());
+	font.extend_from_slice(&(table_count * 16 - search_range).to_be_bytes());
+
+	let mut table_offset = font.len() + tables.len() * 16;
+	let mut head_offset = None;
+	for (tag, table) in tables {
+		if tag == b"head" {
+			head_offset = Some(table_offset);
+		}
+
+		font.extend_from_slice(tag);
+		font.extend_from_slice(&checksum(table).to_be_bytes());
+		font.extend_from_slice(&(table_offset as u32).to_be_bytes());
+		font.extend_from_slice(&(table.len() as u32).to_be_bytes());
+		table_offset += table.len().next_multiple_of(4);
+	}
+
+	for table in tables.values() {
+		font.extend_from_slice(table);
+		font.resize(font.len().next_multiple_of(4), 0);
+	}
+
+	// The checksum adjustment of the head table makes the checksum of the whole font constant.
+	// It must be zero while the checksums are computed
+	if let Some(head_offset) = head_offset.filter(|offset| font.len() >= offset + 12) {
+		font[head_offset + 8..head_offset + 12].fill(0);
+		let checksum_adjustment = FONT_CHECKSUM.wrapping_sub(checksum(&font));
+		font[head_offset + 8..head_offset + 12].copy_from_slice(&checksum_adjustment.to_be_bytes());
+	}
+
+	font
+}
+
+/// Reads the character to glyph mapping of the specified `cmap` table, as pairs of Unicode
+/// codepoints and glyph identifiers lower than the specified glyph count. Only the Unicode
+/// subtables in the formats every Unicode font has are read, preferring those that cover
+/// every Unicode plane. `None` is returned if there is no such subtable, or the table is not
+/// valid.
+///
+/// The codepoint ranges of the subtable must be sorted and not overlap, as the specification
+/// requires, and glyphs out of range are skipped without enumerating them, so that the size of
+/// the returned mapping is bounded by the number of codepoints and glyphs no matter the ranges
+/// a malformed table declares.
+fn read_character_glyphs(cmap: &[u8], glyph_count: usize) -> Option<Vec<(u32, u16)>> {
+	let mut best_subtable = None;
+	for subtable in 0..read_u16(cmap, 2)? as usize {
+		let platform = read_u16(cmap, 4 + subtable * 8)?;
+		let encoding = read_u16(cmap, 6 + subtable * 8)?;
+		let offset = read_u32(cmap, 8 + subtable * 8)? as usize;
+
+		let priority = match (platform, encoding, read_u16(cmap, offset)?) {
+			(0, _, 12) | (3, 10, 12) => 2,
+			(0, _, 4) | (3, 1, 4) => 1,
+			_ => continue
+		};
+		if best_subtable.is_none_or(|(best_priority, _)| priority > best_priority) {
+			best_subtable = Some((priority, offset));
+		}
+	}
+
+	let (_, offset) = best_subtable?;
+	let subtable = cmap.get(offset..)?;
+	let mut character_glyphs = vec![];
+	if read_u16(subtable, 0)? == 4 {
+		let segment_count = read_u16(subtable, 6)? as usize / 2;
+		let start_codes = 16 + segment_count * 2;
+		let id_deltas = start_codes + segment_count * 2;
+		let id_range_offsets = id_deltas + segment_count * 2;
+
+		let mut next_code = 0;
+		for segment in 0..segment_count {
+			let end_code = read_u16(subtable, 14 + segment * 2)?;
+			let start_code = read_u16(subtable, start_codes + segment * 2)?;
+			if start_code < next_code || end_code < start_code {
+				return None;
+			}
+			next_code = end_code.saturating_add(1);
+			let id_delta = read_u16(subtable, id_deltas + segment * 2)?;
+			let id_range_offset_position = id_range_offsets + segment * 2;
+			let id_range_offset = read_u16(subtable, id_range_offset_position)? as usize;
+
+			for codepoint in start_code..=end_code {
+				let glyph = if id_range_offset == 0 {
+					codepoint.wrapping_add(id_delta)
+				} else {
+					match read_u16(
+						subtable,
+						id_range_offset_position
+							+ id_range_offset + (codepoint - start_code) as usize * 2
+					)? {
+						0 => 0,
+						glyph => glyph.wrapping_add(id_delta)
+					}
+				};
+
+				if glyph != 0 && (glyph as usize) < glyph_count {
+					character_glyphs.push((codepoint as u32, glyph));
+				}
+			}
+		}
+	} else {
+		let mut next_codepoint = 0;
+		for group in 0..read_u32(subtable, 12)? as usize {
+			let start_codepoint = read_u32(subtable, 16 + group * 12)?;
+			let end_codepoint = read_u32(subtable, 20 + group * 12)?;
+			let start_glyph = read_u32(subtable, 24 + group * 12)? as usize;
+			if start_codepoint < next_codepoint || end_codepoint < start_codepoint {
+				return None;
+			}
+			next_codepoint = end_codepoint.saturating_add(1);
+
+			// Stop at the last codepoint and glyph, as groups may extend past them
+			let glyph_range_length = glyph_count.saturating_sub(start_glyph);
+			let end_codepoint = end_codepoint.min(char::MAX as u32).min(
+				start_codepoint
+					.saturating_add(glyph_range_length as u32)
+					.saturating_sub(1)
+			);
+			if glyph_range_length == 0 || start_codepoint > end_codepoint {
+				continue;
+			}
+
+			for codepoint in start_codepoint..=end_codepoint {
+				character_glyphs.push((
+					codepoint,
+					(start_glyph + (codepoint - start_codepoint) as usize) as u16
+				));
+			}
+		}
+	}
+
+	Some(character_glyphs)
+}
+
+/// Writes a `cmap` table with a single Unicode subtable in format 12, which every Minecraft
+/// version that reads TrueType fonts supports, that maps the specified pairs of Unicode
+/// codepoints and glyph identifiers, sorted by codepoint.
+fn write_cmap(character_glyphs: &[(u32, u16)]) -> Vec<u8> {
+	let mut groups = Vec::<(u32, u32, u32)>::new();
+	for (codepoint, glyph) in character_glyphs.iter().map(|(c, g)| (*c, *g as u32)) {
+		match groups.last_mut() {
+			Some((start_codepoint, end_codepoint, start_glyph))
+				if codepoint == *end_codepoint + 1
+					&& glyph == *start_glyph + (codepoint - *start_codepoint) =>
+			{
+				*end_codepoint = codepoint;
+			}
+			_ => groups.push((codepoint, codepoint, glyph))
+		}
+	}
+
+	let mut cmap = Vec::with_capacity(28 + groups.len() * 12);
+	cmap.extend_from_slice(&0_u16.to_be_bytes());
+	cmap.extend_from_slice(&1_u16.to_be_bytes());
+	cmap.extend_from_slice(&3_u16.to_be_bytes());
+	cmap.extend_from_slice(&10_u16.to_be_bytes());
+	cmap.extend_from_slice(&12_u32.to_be_bytes());
+	cmap.extend_from_slice(&12_u16.to_be_bytes());
+	cmap.extend_from_slice(&0_u16.to_be_bytes());
+	cmap.extend_from_slice(&(16 + groups.len() as u32 * 12).to_be_bytes());
+	cmap.extend_from_slice(&0_u32.to_be_bytes());
+	cmap.extend_from_slice(&(groups.len() as u32).to_be_bytes());
+	for (start_codepoint, end_codepoint, start_glyph) in groups {
+		cmap.extend_from_slice(&start_codepoint.to_be_bytes());
+		cmap.extend_from_slice(&end_codepoint.to_be_bytes());
+		cmap.extend_from_slice(&start_glyph.to_be_bytes());
+	}
+
+	cmap
+}
+
+/// Returns the identifiers of the glyphs the specified glyph is made of, which is empty if
+/// it is not a composite glyph. `None` is returned if the glyph is not valid.
+fn composite_glyph_components(glyph: &[u8]) -> Option<Vec<u16>> {
+	if glyph.is_empty() || (read_u16(glyph, 0)? as i16) >= 0 {
+		return Some(vec![]);
+	}
+
+	let mut components = vec![];
+	let mut offset = 10;
+	loop {
+		let flags = read_u16(glyph, offset)?;
+		components.push(read_u16(glyph, offset + 2)?);
+
+		offset += if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+			8
+		} else {
+			6
+		};
+		offset += if flags & WE_HAVE_A_SCALE != 0 {
+			2
+		} else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+			4
+		} else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+			8
+		} else {
+			0
+		};
+
+		if flags & MORE_COMPONENTS == 0 {
+			return Some(components);
+		}
+	}
+}
+
+/// Computes the checksum of a font table, which is the sum of its big-endian 32-bit words.
+fn checksum(data: &[u8]) -> u32 {
+	data.chunks(4).fold(0, |checksum, word| {
+		let mut padded_word = [0; 4];
+		padded_word[..word.len()].copy_from_slice(word);
+		checksum.wrapping_add(u32::from_be_bytes(padded_word))
+	})
+}
+
+/// Reads a big-endian 16-bit unsigned integer at the specified offset of some data.
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+	Some(u16::from_be_bytes(
+		data.get(offset..offset.checked_add(2)?)?
+			.try_into()
+			.unwrap()
+	))
+}
+
+/// Reads a big-endian 32-bit unsigned integer at the specified offset of some data.
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+	Some(u32::from_be_bytes(
+		data.get(offset..offset.checked_add(4)?)?
+			.try_into()
+			.unwrap()
+	))
+}
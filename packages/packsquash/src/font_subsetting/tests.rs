@@ -0,0 +1,143 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+use tokio::io::AsyncReadExt;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+/// A simple glyph with a single contour, whose outline data is not meaningful.
+const SIMPLE_GLYPH: &[u8] = &[0, 1, 0, 0, 0, 0, 0, 8, 0, 8, 1, 2, 3, 4];
+
+/// A composite glyph made of glyph 3, with byte arguments and no transformation.
+const COMPOSITE_GLYPH: &[u8] = &[0xFF, 0xFF, 0, 0, 0, 0, 0, 8, 0, 8, 0, 0, 0, 3, 0, 0];
+
+/// Builds a font with TrueType outlines that maps `a` to glyph 1, `b` to the composite glyph
+/// 2, and `c` to glyph 4.
+fn test_font() -> Vec<u8> {
+	let glyphs = [
+		&[][..],
+		SIMPLE_GLYPH,
+		COMPOSITE_GLYPH,
+		SIMPLE_GLYPH,
+		SIMPLE_GLYPH
+	];
+
+	let mut glyf = vec![];
+	let mut loca = vec![];
+	for glyph in glyphs {
+		loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+		glyf.extend_from_slice(glyph);
+	}
+	loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+
+	let mut head = vec![0; 54];
+	head[50..52].copy_from_slice(&1_u16.to_be_bytes());
+	let mut maxp = vec![0, 0, 0x50, 0];
+	maxp.extend_from_slice(&(glyphs.len() as u16).to_be_bytes());
+	let cmap = write_cmap(&[('a' as u32, 1), ('b' as u32, 2), ('c' as u32, 4)]);
+
+	write_font(&BTreeMap::from([
+		(*b"cmap", &cmap[..]),
+		(*b"glyf", &glyf[..]),
+		(*b"head", &head[..]),
+		(*b"loca", &loca[..]),
+		(*b"maxp", &maxp[..]),
+		(*b"DSIG", &[0, 0, 0, 1][..])
+	]))
+}
+
+#[test]
+fn fonts_are_subset_to_used_characters() {
+	let root_dir = Builder::new()
+		.prefix("ps-font-subset-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		("assets/mypack/font/symbols.ttf", test_font()),
+		(
+			"assets/mypack/lang/en_us.json",
+			br#"{ "item.mypack.symbol": "b" }"#.to_vec()
+		)
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mut vfs = FontSubsettingVfs::new(OsFilesystem);
+	let subset_font_count = tokio_test::block_on(vfs.subset_fonts(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		""
+	));
+	assert_eq!(subset_font_count, 1);
+
+	let mut subset_font = vec![];
+	tokio_test::block_on(
+		vfs.open(root_dir.path().join("assets/mypack/font/symbols.ttf"))
+			.expect("The subset font should be readable")
+			.file_read
+			.read_to_end(&mut subset_font)
+	)
+	.expect("The subset font should be readable");
+
+	assert_eq!(checksum(&subset_font), FONT_CHECKSUM);
+
+	let tables = read_font_tables(&subset_font).expect("The subset font should be valid");
+	assert_eq!(
+		tables.keys().collect::<Vec<_>>(),
+		[b"cmap", b"glyf", b"head", b"loca", b"maxp"]
+	);
+	assert_eq!(
+		read_character_glyphs(tables[b"cmap"], 4),
+		Some(vec![('b' as u32, 2)])
+	);
+
+	// Glyphs 0, 2 and 3 are kept, and the glyph offsets are short now
+	let glyph_offsets = tables[b"loca"]
+		.chunks(2)
+		.map(|offset| u16::from_be_bytes(offset.try_into().unwrap()) as usize * 2)
+		.collect::<Vec<_>>();
+	assert_eq!(read_u16(tables[b"head"], 50), Some(0));
+	assert_eq!(
+		glyph_offsets,
+		[
+			0,
+			0,
+			0,
+			COMPOSITE_GLYPH.len(),
+			COMPOSITE_GLYPH.len() + SIMPLE_GLYPH.len(),
+			COMPOSITE_GLYPH.len() + SIMPLE_GLYPH.len()
+		]
+	);
+}
+
+#[test]
+fn character_glyph_ranges_are_bounded_by_the_glyph_count() {
+	let mut cmap = write_cmap(&[('a' as u32, 1)]);
+	// Make the only group span every codepoint from "a"
+	cmap[32..36].copy_from_slice(&(char::MAX as u32).to_be_bytes());
+
+	assert_eq!(
+		read_character_glyphs(&cmap, 4),
+		Some(vec![('a' as u32, 1), ('b' as u32, 2), ('c' as u32, 3)])
+	);
+}
+
+#[test]
+fn overlapping_character_glyph_ranges_are_rejected() {
+	let cmap = write_cmap(&[('a' as u32, 1), ('c' as u32, 3)]);
+	let mut overlapping_cmap = cmap.clone();
+	// Make the second group start at "a" too
+	overlapping_cmap[40..44].copy_from_slice(&('a' as u32).to_be_bytes());
+
+	assert_eq!(
+		read_character_glyphs(&cmap, 4),
+		Some(vec![('a' as u32, 1), ('c' as u32, 3)])
+	);
+	assert_eq!(read_character_glyphs(&overlapping_cmap, 4), None);
+}
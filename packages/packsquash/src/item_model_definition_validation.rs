@@ -0,0 +1,202 @@
+//! Contains routines to validate the item model definitions of a pack, checking that the
+//! models they render, and the parents and textures of those models, exist.
+
+use std::path::Path;
+
+use ahash::{AHashMap, AHashSet};
+use serde_json::Value;
+
+use crate::RelativePath;
+use crate::pack_file::{blank_out_relaxed_syntax, strip_utf8_bom};
+use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem, read_file};
+
+#[cfg(test)]
+mod tests;
+
+/// The namespace of vanilla assets, which is also the namespace of resource locations that do
+/// not specify any.
+const VANILLA_NAMESPACE: &str = "minecraft";
+
+/// Parses the item model definitions of the pack at the specified root path, finding models
+/// they render that are not in the pack, and parents and textures of those models that are
+/// not in the pack either. Minecraft renders such items with the missing model or texture,
+/// while only reporting it in its log. Models are referenced by `model` and `base` keys of
+/// item model definitions, and every model of the pack they reference is followed through its
+/// parents. Assets of the vanilla namespace are not checked, because they may be provided by
+/// the game.
+///
+/// Files that can't be read or parsed are ignored, so that the usual pack file processing
+/// reports any relevant error. Mismatches are sorted by path.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Items_model_definition>
+/// - <https://minecraft.wiki/w/Model#Item_models>
+pub async fn validate_item_model_definitions<V: VirtualFileSystem>(
+	vfs: &V,
+	root_path: &Path,
+	iterator_traversal_options: IteratorTraversalOptions
+) -> Vec<(RelativePath<'static>, String)> {
+	let mut pack_files = AHashMap::new();
+	let mut item_model_definition_files = vec![];
+	for VfsPackFileIterEntry {
+		relative_path,
+		file_path
+	} in vfs
+		.file_iterator(root_path, iterator_traversal_options)
+		.flatten()
+	{
+		if is_item_model_definition(relative_path.as_str()) {
+			item_model_definition_files.push((relative_path.clone(), file_path.clone()));
+		}
+
+		// Files with comments are referenced by their canonical extension
+		let relative_path = match relative_path.as_str().strip_suffix(".jsonc") {
+			Some(relative_path) => format!("{relative_path}.json"),
+			None => relative_path.as_str().to_owned()
+		};
+		pack_files.insert(relative_path, file_path);
+	}
+	item_model_definition_files.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+	let mut models = AHashMap::new();
+	let mut mismatches = vec![];
+	for (relative_path, file_path) in item_model_definition_files {
+		let Some(item_model_definition) = read_json(vfs, &file_path).await else {
+			continue;
+		};
+
+		let mut pending_models = model_references(&item_model_definition)
+			.into_iter()
+			.rev()
+			.map(|model| (model, None))
+			.collect::<Vec<_>>();
+		let mut visited_models = AHashSet::new();
+		while let Some((model, referencing_model)) = pending_models.pop() {
+			if !visited_models.insert(model.clone()) {
+				continue;
+			}
+
+			let model_path = asset_path(&model, "models/", ".json");
+			let Some(model_file_path) = pack_files.get(&model_path) else {
+				if !is_vanilla_asset(&model_path) {
+					mismatches.push((
+						relative_path.clone(),
+						match referencing_model {
+							Some(referencing_model) => format!(
+								"uses the model {referencing_model}, whose parent {model_path} is \
+								not in the pack"
+							),
+							None => format!("uses the model {model_path}, which is not in the pack")
+						}
+					));
+				}
+				continue;
+			};
+
+			if !models.contains_key(&model_path) {
+				let model = read_json(vfs, model_file_path).await;
+				models.insert(model_path.clone(), model);
+			}
+			let Some(model) = &models[&model_path] else {
+				continue;
+			};
+
+			for texture_path in texture_references(model)
+				.map(|texture| asset_path(texture, "textures/", ".png"))
+				.filter(|texture_path| {
+					!pack_files.contains_key(texture_path) && !is_vanilla_asset(texture_path)
+				}) {
+				mismatches.push((
+					relative_path.clone(),
+					format!(
+						"uses the model {model_path}, whose texture {texture_path} is not in the pack"
+					)
+				));
+			}
+
+			if let Some(parent) = model.get("parent").and_then(Value::as_str) {
+				pending_models.push((parent.to_owned(), Some(model_path)));
+			}
+		}
+	}
+
+	mismatches
+}
+
+/// Checks whether the specified relative path is of an item model definition file.
+fn is_item_model_definition(relative_path: &str) -> bool {
+	(relative_path.ends_with(".json") || relative_path.ends_with(".jsonc"))
+		&& relative_path
+			.strip_prefix("assets/")
+			.and_then(|path| path.split_once('/'))
+			.is_some_and(|(_, path)| path.starts_with("items/"))
+}
+
+/// Checks whether the specified relative path is of an asset of the vanilla namespace.
+fn is_vanilla_asset(relative_path: &str) -> bool {
+	relative_path.starts_with(&format!("assets/{VANILLA_NAMESPACE}/"))
+}
+
+/// Returns the relative path of the asset with the specified resource location, which is
+/// located within the specified folder prefix of its namespace and has the specified
+/// extension.
+fn asset_path(resource_location: &str, folder_prefix: &str, extension: &str) -> String {
+	let (namespace, path) = resource_location
+		.split_once(':')
+		.unwrap_or((VANILLA_NAMESPACE, resource_location));
+
+	format!("assets/{namespace}/{folder_prefix}{path}{extension}")
+}
+
+/// Returns the resource locations of the models referenced by the `model` and `base` keys of
+/// the specified item model definition, in order and without duplicates.
+fn model_references(item_model_definition: &Value) -> Vec<String> {
+	let mut models = vec![];
+
+	let mut pending_values = vec![item_model_definition];
+	while let Some(value) = pending_values.pop() {
+		match value {
+			Value::Object(object) => {
+				for (key, value) in object.iter().rev() {
+					match (key.as_str(), value) {
+						("model" | "base", Value::String(model)) => {
+							if !models.contains(model) {
+								models.push(model.clone());
+							}
+						}
+						_ => pending_values.push(value)
+					}
+				}
+			}
+			Value::Array(array) => pending_values.extend(array.iter().rev()),
+			_ => {}
+		}
+	}
+
+	models
+}
+
+/// Returns the resource locations of the textures referenced by the `textures` object of the
+/// specified model, ignoring references to texture variables.
+fn texture_references(model: &Value) -> impl Iterator<Item = &str> {
+	model
+		.get("textures")
+		.and_then(Value::as_object)
+		.into_iter()
+		.flatten()
+		.filter_map(|(_, texture)| texture.as_str())
+		.filter(|texture| !texture.starts_with('#'))
+}
+
+/// Reads and parses the JSON file at the specified path of a virtual file system, allowing
+/// comments and trailing commas like the usual JSON file processing does, and returning `None`
+/// if some I/O or parsing error occurs.
+async fn read_json<V: VirtualFileSystem>(vfs: &V, path: &Path) -> Option<Value> {
+	let (mut data, _) = read_file(vfs, path).await.ok()?;
+
+	let bom_length = data.len() - strip_utf8_bom(&data).len();
+	let json = &mut data[bom_length..];
+	blank_out_relaxed_syntax(json).ok()?;
+
+	serde_json::from_slice(json).ok()
+}
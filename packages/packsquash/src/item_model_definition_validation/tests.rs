@@ -0,0 +1,94 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[test]
+fn item_model_definitions_are_validated() {
+	let root_dir = Builder::new()
+		.prefix("ps-item-model-definition-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"assets/mypack/items/gem.json",
+			r#"{
+				"model": {
+					"type": "minecraft:condition",
+					"property": "minecraft:using_item",
+					"on_true": { "type": "minecraft:model", "model": "mypack:item/gem_glowing" },
+					"on_false": { "type": "minecraft:model", "model": "mypack:item/gem" }
+				}
+			}"#
+		),
+		(
+			"assets/mypack/items/chest.json",
+			r#"{
+				"model": {
+					"type": "minecraft:special",
+					"base": "mypack:item/chest",
+					"model": { "type": "minecraft:chest", "texture": "minecraft:normal" }
+				}
+			}"#
+		),
+		(
+			"assets/mypack/items/stick.json",
+			r#"{ "model": { "type": "minecraft:model", "model": "minecraft:item/stick" } }"#
+		),
+		(
+			"assets/mypack/models/item/gem.jsonc",
+			r#"{
+				// Comments are allowed
+				"parent": "mypack:item/base_gem",
+				"textures": { "layer0": "mypack:item/gem", "layer1": "item/diamond" }
+			}"#
+		),
+		(
+			"assets/mypack/models/item/chest.json",
+			r##"{ "parent": "mypack:item/template_chest", "textures": { "particle": "#side" } }"##
+		),
+		(
+			"assets/mypack/models/item/template_chest.json",
+			r#"{ "parent": "builtin/entity", "textures": { "side": "mypack:block/chest_side" } }"#
+		),
+		("assets/mypack/textures/block/chest_side.png", "")
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mismatches = tokio_test::block_on(validate_item_model_definitions(
+		&OsFilesystem,
+		root_dir.path(),
+		IteratorTraversalOptions::default()
+	));
+
+	assert_eq!(
+		mismatches
+			.iter()
+			.map(|(relative_path, mismatch)| (relative_path.as_str(), mismatch.as_str()))
+			.collect::<Vec<_>>(),
+		[
+			(
+				"assets/mypack/items/gem.json",
+				"uses the model assets/mypack/models/item/gem_glowing.json, which is not in the pack"
+			),
+			(
+				"assets/mypack/items/gem.json",
+				"uses the model assets/mypack/models/item/gem.json, whose texture \
+				assets/mypack/textures/item/gem.png is not in the pack"
+			),
+			(
+				"assets/mypack/items/gem.json",
+				"uses the model assets/mypack/models/item/gem.json, whose parent \
+				assets/mypack/models/item/base_gem.json is not in the pack"
+			)
+		]
+	);
+}
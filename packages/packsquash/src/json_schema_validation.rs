@@ -0,0 +1,983 @@
+//! Contains routines to validate the JSON files of well-known types of a pack against the
+//! schema Minecraft expects for them, so that typos that Minecraft silently ignores or falls
+//! back from can be noticed.
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::RelativePath;
+use crate::pack_file::{blank_out_relaxed_syntax, strip_utf8_bom};
+use crate::pack_meta::{
+	PACK_FORMAT_DATA_PACK_VERSION_1_20_5, PACK_FORMAT_DATA_PACK_VERSION_24W_21A,
+	PACK_FORMAT_RESOURCE_PACK_VERSION_1_21, PACK_FORMAT_RESOURCE_PACK_VERSION_1_21_6,
+	PACK_FORMAT_VERSION_1_15
+};
+use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem, read_file};
+
+#[cfg(test)]
+mod tests;
+
+/// Keys that are commonly used to add comments to JSON objects, which Minecraft ignores.
+const COMMENT_KEYS: [&str; 3] = ["__comment", "__comment__", "_comment"];
+
+/// The maximum edit distance between an unknown key and a known key for the latter to be
+/// suggested as the intended key.
+const MAXIMUM_SUGGESTION_EDIT_DISTANCE: usize = 2;
+
+/// The data pack folders that were renamed to their singular form in data pack format
+/// [`PACK_FORMAT_DATA_PACK_VERSION_24W_21A`], with the schema of the files they contain.
+/// Tag folders are relative to the namespace folder too, as they are named after a registry.
+static RENAMED_DATA_PACK_FOLDERS: [(&str, &str, &Schema); 11] = [
+	("advancements", "advancement", &ADVANCEMENT),
+	("item_modifiers", "item_modifier", &ITEM_MODIFIER_FILE),
+	("loot_tables", "loot_table", &LOOT_TABLE),
+	("predicates", "predicate", &PREDICATE),
+	("recipes", "recipe", &RECIPE),
+	("tags/blocks", "tags/block", &TAG),
+	("tags/entity_types", "tags/entity_type", &TAG),
+	("tags/fluids", "tags/fluid", &TAG),
+	("tags/functions", "tags/function", &TAG),
+	("tags/game_events", "tags/game_event", &TAG),
+	("tags/items", "tags/item", &TAG)
+];
+
+/// The structure a JSON value is expected to have.
+enum Schema {
+	/// Any JSON value.
+	Any,
+	/// A boolean.
+	Boolean,
+	/// A number.
+	Number,
+	/// An integer number.
+	Integer,
+	/// A string.
+	String,
+	/// An array whose elements have the specified structure.
+	Array(&'static Schema),
+	/// An array of the specified number of numbers, such as coordinates.
+	NumberVector(usize),
+	/// An object with arbitrary keys, whose values have the specified structure.
+	Map(&'static Schema),
+	/// An object with the specified keys. Keys not listed are reported as unknown.
+	Object(&'static [Field]),
+	/// An object with at least the specified keys. Keys not listed are not checked, because
+	/// their meaning depends on the value of some listed key.
+	OpenObject(&'static [Field]),
+	/// A value with one of the specified structures, which must have different JSON types.
+	OneOf(&'static [Schema])
+}
+
+/// A key of a JSON object with a known structure.
+struct Field {
+	name: &'static str,
+	schema: Schema,
+	required: bool,
+	/// The first pack format version whose Minecraft versions read this key.
+	since_pack_format_version: Option<i32>
+}
+
+/// Creates a schema field for an optional key.
+const fn optional(name: &'static str, schema: Schema) -> Field {
+	Field {
+		name,
+		schema,
+		required: false,
+		since_pack_format_version: None
+	}
+}
+
+/// Creates a schema field for a key that Minecraft requires.
+const fn required(name: &'static str, schema: Schema) -> Field {
+	Field {
+		required: true,
+		..optional(name, schema)
+	}
+}
+
+impl Field {
+	/// Returns this field, restricted to the Minecraft versions of the specified pack format
+	/// version and later.
+	const fn since(self, pack_format_version: i32) -> Self {
+		Self {
+			since_pack_format_version: Some(pack_format_version),
+			..self
+		}
+	}
+}
+
+/// A Minecraft text component: a plain string, a list of components whose first element is
+/// the parent of the rest, or an object. Since 1.21.5, the click and hover events of objects
+/// are named in snake case and have a flatter structure, but both forms are accepted for every
+/// version.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Text_component_format>
+const TEXT_COMPONENT: Schema = Schema::OneOf(&TEXT_COMPONENT_FORMS);
+
+/// The structures a text component may have.
+static TEXT_COMPONENT_FORMS: [Schema; 3] = [
+	Schema::String,
+	Schema::Array(&TEXT_COMPONENT_ELEMENT),
+	Schema::Object(&TEXT_COMPONENT_FIELDS)
+];
+
+/// A text component nested within another, which allows the text component schema to be
+/// recursive. Schemas nested within text components must refer to statics like this instead
+/// of using [`TEXT_COMPONENT`], because evaluating that constant requires evaluating them.
+static TEXT_COMPONENT_ELEMENT: Schema = Schema::OneOf(&TEXT_COMPONENT_FORMS);
+
+/// The keys of a text component object.
+static TEXT_COMPONENT_FIELDS: [Field; 34] = [
+	// Content keys, which may define the type of the component
+	optional("type", Schema::String),
+	optional("text", Schema::String),
+	optional("translate", Schema::String),
+	optional("fallback", Schema::String),
+	optional("with", Schema::Array(&TEXT_COMPONENT_ELEMENT)),
+	optional(
+		"score",
+		Schema::Object(&[
+			required("name", Schema::String),
+			required("objective", Schema::String),
+			optional("value", Schema::String)
+		])
+	),
+	optional("selector", Schema::String),
+	optional("separator", Schema::OneOf(&TEXT_COMPONENT_FORMS)),
+	optional("keybind", Schema::String),
+	optional("nbt", Schema::String),
+	optional("interpret", Schema::Boolean),
+	optional("source", Schema::String),
+	optional("block", Schema::String),
+	optional("entity", Schema::String),
+	optional("storage", Schema::String),
+	optional("object", Schema::String),
+	optional("atlas", Schema::String),
+	optional("sprite", Schema::String),
+	optional("player", Schema::Any),
+	optional("hat", Schema::Boolean),
+	// Children and formatting keys
+	optional("extra", Schema::Array(&TEXT_COMPONENT_ELEMENT)),
+	optional("color", Schema::String),
+	optional("shadow_color", Schema::Any),
+	optional("font", Schema::String),
+	optional("bold", Schema::Boolean),
+	optional("italic", Schema::Boolean),
+	optional("underlined", Schema::Boolean),
+	optional("strikethrough", Schema::Boolean),
+	optional("obfuscated", Schema::Boolean),
+	optional("insertion", Schema::String),
+	// Interaction keys, in their 1.21.5 and later form
+	optional(
+		"click_event",
+		Schema::Object(&[
+			required("action", Schema::String),
+			optional("url", Schema::String),
+			optional("path", Schema::String),
+			optional("command", Schema::String),
+			optional("page", Schema::Integer),
+			optional("value", Schema::String),
+			optional("dialog", Schema::Any),
+			optional("id", Schema::String),
+			optional("payload", Schema::Any)
+		])
+	),
+	optional(
+		"hover_event",
+		Schema::Object(&[
+			required("action", Schema::String),
+			optional("value", Schema::OneOf(&TEXT_COMPONENT_FORMS)),
+			optional("id", Schema::String),
+			optional("count", Schema::Integer),
+			optional("components", Schema::Map(&Schema::Any)),
+			optional("uuid", Schema::Any),
+			optional("name", Schema::OneOf(&TEXT_COMPONENT_FORMS))
+		])
+	),
+	// Interaction keys, in their form before 1.21.5
+	optional(
+		"clickEvent",
+		Schema::Object(&[
+			required("action", Schema::String),
+			required("value", Schema::String)
+		])
+	),
+	optional(
+		"hoverEvent",
+		Schema::Object(&[
+			required("action", Schema::String),
+			optional("contents", Schema::Any),
+			optional("value", Schema::Any)
+		])
+	)
+];
+
+/// References:
+/// - <https://minecraft.wiki/w/Pack_format>
+/// - <https://minecraft.wiki/w/Resource_pack#Contents>
+/// - <https://minecraft.wiki/w/Data_pack#pack.mcmeta>
+static PACK_METADATA: Schema = Schema::Object(&[
+	required(
+		"pack",
+		Schema::Object(&[
+			required("pack_format", Schema::Integer),
+			required("description", TEXT_COMPONENT),
+			optional("supported_formats", Schema::Any),
+			optional("min_format", Schema::Any),
+			optional("max_format", Schema::Any)
+		])
+	),
+	optional(
+		"language",
+		Schema::Map(&Schema::Object(&[
+			optional("name", Schema::String),
+			optional("region", Schema::String),
+			optional("bidirectional", Schema::Boolean)
+		]))
+	),
+	optional(
+		"filter",
+		Schema::Object(&[required(
+			"block",
+			Schema::Array(&Schema::Object(&[
+				optional("namespace", Schema::String),
+				optional("path", Schema::String)
+			]))
+		)])
+	),
+	optional(
+		"overlays",
+		Schema::Object(&[required(
+			"entries",
+			Schema::Array(&Schema::Object(&[
+				required("directory", Schema::String),
+				optional("formats", Schema::Any),
+				optional("min_format", Schema::Any),
+				optional("max_format", Schema::Any)
+			]))
+		)])
+	),
+	optional(
+		"features",
+		Schema::Object(&[required("enabled", Schema::Array(&Schema::String))])
+	)
+]);
+
+/// References:
+/// - <https://minecraft.wiki/w/Model#Block_models>
+/// - <https://minecraft.wiki/w/Model#Item_models>
+static MODEL: Schema = Schema::Object(&[
+	optional("parent", Schema::String),
+	optional("ambientocclusion", Schema::Boolean),
+	optional("gui_light", Schema::String).since(PACK_FORMAT_VERSION_1_15),
+	optional(
+		"display",
+		Schema::Object(&[
+			optional(
+				"thirdperson_righthand",
+				Schema::Object(&MODEL_DISPLAY_TRANSFORM)
+			),
+			optional(
+				"thirdperson_lefthand",
+				Schema::Object(&MODEL_DISPLAY_TRANSFORM)
+			),
+			optional(
+				"firstperson_righthand",
+				Schema::Object(&MODEL_DISPLAY_TRANSFORM)
+			),
+			optional(
+				"firstperson_lefthand",
+				Schema::Object(&MODEL_DISPLAY_TRANSFORM)
+			),
+			optional("gui", Schema::Object(&MODEL_DISPLAY_TRANSFORM)),
+			optional("head", Schema::Object(&MODEL_DISPLAY_TRANSFORM)),
+			optional("ground", Schema::Object(&MODEL_DISPLAY_TRANSFORM)),
+			optional("fixed", Schema::Object(&MODEL_DISPLAY_TRANSFORM))
+		])
+	),
+	optional("textures", Schema::Map(&Schema::String)),
+	optional(
+		"elements",
+		Schema::Array(&Schema::Object(&[
+			required("from", Schema::NumberVector(3)),
+			required("to", Schema::NumberVector(3)),
+			optional(
+				"rotation",
+				Schema::Object(&[
+					required("origin", Schema::NumberVector(3)),
+					required("axis", Schema::String),
+					required("angle", Schema::Number),
+					optional("rescale", Schema::Boolean)
+				])
+			),
+			optional("shade", Schema::Boolean),
+			optional("light_emission", Schema::Integer).since(PACK_FORMAT_RESOURCE_PACK_VERSION_1_21),
+			required(
+				"faces",
+				Schema::Object(&[
+					optional("down", Schema::Object(&MODEL_FACE)),
+					optional("up", Schema::Object(&MODEL_FACE)),
+					optional("north", Schema::Object(&MODEL_FACE)),
+					optional("south", Schema::Object(&MODEL_FACE)),
+					optional("west", Schema::Object(&MODEL_FACE)),
+					optional("east", Schema::Object(&MODEL_FACE))
+				])
+			),
+			// Blockbench element names, which Minecraft ignores
+			optional("name", Schema::String)
+		]))
+	),
+	optional(
+		"overrides",
+		Schema::Array(&Schema::Object(&[
+			required("predicate", Schema::Map(&Schema::Number)),
+			required("model", Schema::String)
+		]))
+	),
+	// Blockbench metadata, which Minecraft ignores and PackSquash may debloat
+	optional("credit", Schema::String),
+	optional("groups", Schema::Any),
+	optional("texture_size", Schema::NumberVector(2))
+]);
+
+/// The transformation of a model in some display position.
+const MODEL_DISPLAY_TRANSFORM: [Field; 3] = [
+	optional("rotation", Schema::NumberVector(3)),
+	optional("translation", Schema::NumberVector(3)),
+	optional("scale", Schema::NumberVector(3))
+];
+
+/// A face of a model element.
+const MODEL_FACE: [Field; 5] = [
+	required("texture", Schema::String),
+	optional("uv", Schema::NumberVector(4)),
+	optional("cullface", Schema::String),
+	optional("rotation", Schema::Integer),
+	optional("tintindex", Schema::Integer)
+];
+
+/// References:
+/// - <https://minecraft.wiki/w/Tutorials/Models#Block_states>
+static BLOCKSTATE: Schema = Schema::Object(&[
+	optional("variants", Schema::Map(&BLOCKSTATE_MODELS)),
+	optional(
+		"multipart",
+		Schema::Array(&Schema::Object(&[
+			optional("when", Schema::Map(&Schema::Any)),
+			required("apply", BLOCKSTATE_MODELS)
+		]))
+	)
+]);
+
+/// The models a block state variant or multipart case may render, which are either a single
+/// model or a list of weighted models to pick from randomly.
+const BLOCKSTATE_MODELS: Schema = Schema::OneOf(&[
+	Schema::Object(&BLOCKSTATE_MODEL),
+	Schema::Array(&Schema::Object(&BLOCKSTATE_MODEL))
+]);
+
+/// A model rendered by a block state.
+const BLOCKSTATE_MODEL: [Field; 5] = [
+	required("model", Schema::String),
+	optional("x", Schema::Integer),
+	optional("y", Schema::Integer),
+	optional("uvlock", Schema::Boolean),
+	optional("weight", Schema::Integer)
+];
+
+/// References:
+/// - <https://minecraft.wiki/w/Items_model_definition>
+static ITEM_MODEL_DEFINITION: Schema = Schema::Object(&[
+	required("model", Schema::OpenObject(&ITEM_MODEL)),
+	optional("hand_animation_on_swap", Schema::Boolean),
+	optional("oversized_in_gui", Schema::Boolean).since(PACK_FORMAT_RESOURCE_PACK_VERSION_1_21_6),
+	optional("swap_animation_scale", Schema::Number)
+]);
+
+/// An item model of an item model definition, whose keys depend on its type. Item models that
+/// select, combine or condition other item models nest them, so those keys are checked.
+static ITEM_MODEL: [Field; 9] = [
+	required("type", Schema::String),
+	optional(
+		"model",
+		Schema::OneOf(&[Schema::String, Schema::OpenObject(&ITEM_MODEL)])
+	),
+	optional("models", Schema::Array(&Schema::OpenObject(&ITEM_MODEL))),
+	optional("on_true", Schema::OpenObject(&ITEM_MODEL)),
+	optional("on_false", Schema::OpenObject(&ITEM_MODEL)),
+	optional("fallback", Schema::OpenObject(&ITEM_MODEL)),
+	optional(
+		"cases",
+		Schema::Array(&Schema::Object(&[
+			required("when", Schema::Any),
+			required("model", Schema::OpenObject(&ITEM_MODEL))
+		]))
+	),
+	optional(
+		"entries",
+		Schema::Array(&Schema::Object(&[
+			required("threshold", Schema::Number),
+			required("model", Schema::OpenObject(&ITEM_MODEL))
+		]))
+	),
+	optional(
+		"tints",
+		Schema::Array(&Schema::OpenObject(&[required("type", Schema::String)]))
+	)
+];
+
+/// References:
+/// - <https://minecraft.wiki/w/Resource_pack#Atlases>
+static ATLAS: Schema = Schema::Object(&[required(
+	"sources",
+	Schema::Array(&Schema::OpenObject(&[
+		required("type", Schema::String),
+		optional("source", Schema::String),
+		optional("prefix", Schema::String),
+		optional("resource", Schema::String),
+		optional("sprite", Schema::String),
+		optional("textures", Schema::Array(&Schema::String)),
+		optional("palette_key", Schema::String),
+		optional("permutations", Schema::Map(&Schema::String))
+	]))
+)]);
+
+/// References:
+/// - <https://minecraft.wiki/w/Particles_(Java_Edition)#Particle_definitions>
+static PARTICLE: Schema = Schema::Object(&[optional("textures", Schema::Array(&Schema::String))]);
+
+/// References:
+/// - <https://minecraft.wiki/w/Advancement_definition>
+static ADVANCEMENT: Schema = Schema::Object(&[
+	optional("parent", Schema::String),
+	optional(
+		"display",
+		Schema::Object(&[
+			required(
+				"icon",
+				Schema::Object(&[
+					// Item stacks were identified by an "item" key before 1.20.5
+					optional("item", Schema::String),
+					optional("id", Schema::String).since(PACK_FORMAT_DATA_PACK_VERSION_1_20_5),
+					optional("count", Schema::Integer),
+					optional("nbt", Schema::String),
+					optional("components", Schema::Map(&Schema::Any))
+						.since(PACK_FORMAT_DATA_PACK_VERSION_1_20_5)
+				])
+			),
+			required("title", TEXT_COMPONENT),
+			required("description", TEXT_COMPONENT),
+			optional("frame", Schema::String),
+			optional("background", Schema::String),
+			optional("show_toast", Schema::Boolean),
+			optional("announce_to_chat", Schema::Boolean),
+			optional("hidden", Schema::Boolean)
+		])
+	),
+	optional(
+		"criteria",
+		Schema::Map(&Schema::Object(&[
+			required("trigger", Schema::String),
+			optional("conditions", Schema::Map(&Schema::Any))
+		]))
+	),
+	optional(
+		"requirements",
+		Schema::Array(&Schema::Array(&Schema::String))
+	),
+	optional(
+		"rewards",
+		Schema::Object(&[
+			optional("recipes", Schema::Array(&Schema::String)),
+			optional("loot", Schema::Array(&Schema::String)),
+			optional("experience", Schema::Integer),
+			optional("function", Schema::String)
+		])
+	),
+	optional("sends_telemetry_event", Schema::Boolean)
+]);
+
+/// References:
+/// - <https://minecraft.wiki/w/Loot_table>
+static LOOT_TABLE: Schema = Schema::Object(&[
+	optional("type", Schema::String),
+	optional(
+		"pools",
+		Schema::Array(&Schema::Object(&[
+			required("rolls", Schema::Any),
+			optional("bonus_rolls", Schema::Any),
+			required("entries", Schema::Array(&LOOT_TABLE_ENTRY)),
+			optional("conditions", Schema::Array(&PREDICATE)),
+			optional("functions", Schema::Array(&ITEM_MODIFIER))
+		]))
+	),
+	optional("functions", Schema::Array(&ITEM_MODIFIER)),
+	optional("random_sequence", Schema::String)
+]);
+
+/// An entry of a loot table pool, whose keys depend on its type.
+static LOOT_TABLE_ENTRY: Schema = Schema::OpenObject(&[
+	required("type", Schema::String),
+	optional("conditions", Schema::Array(&PREDICATE)),
+	optional("functions", Schema::Array(&ITEM_MODIFIER)),
+	optional("children", Schema::Array(&LOOT_TABLE_ENTRY))
+]);
+
+/// References:
+/// - <https://minecraft.wiki/w/Item_modifier>
+static ITEM_MODIFIER: Schema = Schema::OpenObject(&ITEM_MODIFIER_FIELDS);
+
+/// The keys of an item modifier that are common to every item modifier function.
+static ITEM_MODIFIER_FIELDS: [Field; 2] = [
+	required("function", Schema::String),
+	optional("conditions", Schema::Array(&PREDICATE))
+];
+
+/// An item modifier file, which may contain a single item modifier or a list of item modifiers
+/// to apply in order.
+static ITEM_MODIFIER_FILE: Schema = Schema::OneOf(&[
+	Schema::OpenObject(&ITEM_MODIFIER_FIELDS),
+	Schema::Array(&ITEM_MODIFIER)
+]);
+
+/// References:
+/// - <https://minecraft.wiki/w/Recipe#JSON_format>
+static RECIPE: Schema = Schema::OpenObject(&[
+	required("type", Schema::String),
+	optional("group", Schema::String),
+	optional("category", Schema::String),
+	optional("show_notification", Schema::Boolean)
+]);
+
+/// References:
+/// - <https://minecraft.wiki/w/Tag_(Java_Edition)#JSON_format>
+static TAG: Schema = Schema::Object(&[
+	optional("replace", Schema::Boolean),
+	required("values", Schema::Array(&TAG_ENTRY)),
+	// Not read by vanilla Minecraft, but read by popular mod loaders such as NeoForge
+	optional("remove", Schema::Array(&TAG_ENTRY))
+]);
+
+/// An entry of a tag, which references a registry element or another tag, maybe optionally.
+const TAG_ENTRY: Schema = Schema::OneOf(&[
+	Schema::String,
+	Schema::Object(&[
+		required("id", Schema::String),
+		optional("required", Schema::Boolean)
+	])
+]);
+
+/// A predicate, or a list of predicates that must all pass, whose keys depend on the type of
+/// its condition.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Predicate>
+static PREDICATE: Schema = Schema::OneOf(&[
+	Schema::OpenObject(&[required("condition", Schema::String)]),
+	Schema::Array(&PREDICATE)
+]);
+
+/// Parses the JSON files of well-known types of the pack at the specified root path, checking
+/// that they match the schema that the Minecraft versions of the specified pack format version
+/// expect for them. The relative path of every file that does not match its schema is
+/// returned with a description of the mismatch, sorted by path. Files that can't be read or
+/// parsed are ignored, so that the usual pack file processing reports any relevant error.
+/// Object keys that start with any of the specified prefixes, which mod loaders read, are
+/// ignored too.
+pub async fn validate_json_schemas<V: VirtualFileSystem>(
+	vfs: &V,
+	root_path: &Path,
+	iterator_traversal_options: IteratorTraversalOptions,
+	pack_format_version: i32,
+	ignored_key_prefixes: &[&str]
+) -> Vec<(RelativePath<'static>, String)> {
+	let mut schema_violations = vec![];
+
+	for VfsPackFileIterEntry {
+		relative_path,
+		file_path
+	} in vfs
+		.file_iterator(root_path, iterator_traversal_options)
+		.flatten()
+	{
+		let schema = match file_schema(relative_path.as_str(), pack_format_version) {
+			Ok(Some(schema)) => schema,
+			Ok(None) => continue,
+			Err(violation) => {
+				schema_violations.push((relative_path, violation));
+				continue;
+			}
+		};
+
+		let Some(mut value) = read_json_value(vfs, &file_path).await else {
+			continue;
+		};
+		if !ignored_key_prefixes.is_empty() {
+			remove_prefixed_keys(&mut value, ignored_key_prefixes);
+		}
+
+		// Models loaded by custom model loaders of mods have a format defined by those mods
+		if std::ptr::eq(schema, &MODEL) && value.get("loader").is_some() {
+			continue;
+		}
+
+		let mut violations = vec![];
+		validate_value(
+			&value,
+			schema,
+			pack_format_version,
+			&mut String::new(),
+			&mut violations
+		);
+
+		schema_violations.extend(
+			violations
+				.into_iter()
+				.map(|violation| (relative_path.clone(), violation))
+		);
+	}
+
+	schema_violations.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+	schema_violations
+}
+
+/// Removes the object keys that start with any of the specified prefixes from the specified
+/// JSON value, recursively.
+fn remove_prefixed_keys(value: &mut Value, key_prefixes: &[&str]) {
+	match value {
+		Value::Object(object) => {
+			object.retain(|key, _| !key_prefixes.iter().any(|prefix| key.starts_with(prefix)));
+			for value in object.values_mut() {
+				remove_prefixed_keys(value, key_prefixes);
+			}
+		}
+		Value::Array(elements) => {
+			for element in elements {
+				remove_prefixed_keys(element, key_prefixes);
+			}
+		}
+		_ => {}
+	}
+}
+
+/// Validates a text component, such as one embedded in a command, against the schema that
+/// Minecraft expects for text components, returning a description of each mismatch. Keys that
+/// only some Minecraft versions read are accepted.
+pub fn validate_text_component(text_component: &Value) -> Vec<String> {
+	let mut violations = vec![];
+	validate_value(
+		text_component,
+		&TEXT_COMPONENT,
+		i32::MAX,
+		&mut String::new(),
+		&mut violations
+	);
+
+	violations
+}
+
+/// Returns the schema of the JSON file at the specified relative path for the Minecraft
+/// versions of the specified pack format version, or `None` if it is not a file of a
+/// well-known type. If the file is in a folder those Minecraft versions do not read, a
+/// description of the problem is returned as an error.
+fn file_schema(
+	relative_path: &str,
+	pack_format_version: i32
+) -> Result<Option<&'static Schema>, String> {
+	if relative_path == "pack.mcmeta" || relative_path == "pack.mcmetac" {
+		return Ok(Some(&PACK_METADATA));
+	}
+
+	let Some(path) = relative_path
+		.strip_suffix(".json")
+		.or_else(|| relative_path.strip_suffix(".jsonc"))
+	else {
+		return Ok(None);
+	};
+
+	if let Some((_, path)) = path
+		.strip_prefix("assets/")
+		.and_then(|path| path.split_once('/'))
+	{
+		return Ok(match path.split_once('/') {
+			Some(("models", _)) => Some(&MODEL),
+			Some(("blockstates", name)) if !name.contains('/') => Some(&BLOCKSTATE),
+			Some(("items", _)) => Some(&ITEM_MODEL_DEFINITION),
+			Some(("atlases", _)) => Some(&ATLAS),
+			Some(("particles", name)) if !name.contains('/') => Some(&PARTICLE),
+			_ => None
+		});
+	}
+
+	let Some(path) = path
+		.strip_prefix("data/")
+		.and_then(|path| path.split_once('/'))
+		.map(|(_, path)| path)
+	else {
+		return Ok(None);
+	};
+
+	// Tags are grouped in folders named after their registry
+	let folder = match path.split_once('/') {
+		Some(("tags", tag_path)) => match tag_path.split_once('/') {
+			Some((registry, _)) => &path[.."tags/".len() + registry.len()],
+			None => return Ok(None)
+		},
+		Some((folder, _)) => folder,
+		None => return Ok(None)
+	};
+
+	let uses_legacy_folders = pack_format_version < PACK_FORMAT_DATA_PACK_VERSION_24W_21A;
+	for &(legacy_folder, folder_name, schema) in &RENAMED_DATA_PACK_FOLDERS {
+		if folder == legacy_folder || folder == folder_name {
+			let expected_folder = if uses_legacy_folders {
+				legacy_folder
+			} else {
+				folder_name
+			};
+
+			return if folder == expected_folder {
+				Ok(Some(schema))
+			} else {
+				Err(format!(
+					"Minecraft versions of pack format version {pack_format_version} ignore files in \
+					 the \"{folder}\" folder, expecting them in the \"{expected_folder}\" folder"
+				))
+			};
+		}
+	}
+
+	Ok(folder.starts_with("tags/").then_some(&TAG))
+}
+
+/// Validates a JSON value against the specified schema, appending a description of each
+/// mismatch to `violations`. `pointer` is the JSON pointer to the value, which is used to
+/// locate mismatches, and is restored to its original contents before returning.
+fn validate_value(
+	value: &Value,
+	schema: &Schema,
+	pack_format_version: i32,
+	pointer: &mut String,
+	violations: &mut Vec<String>
+) {
+	if !schema.matches_type(value) {
+		violations.push(format!(
+			"Expected {} at {}",
+			schema.description(),
+			location(pointer)
+		));
+		return;
+	}
+
+	match (schema, value) {
+		(Schema::Array(element_schema), Value::Array(elements)) => {
+			for (index, element) in elements.iter().enumerate() {
+				let pointer_length = pointer.len();
+				pointer.push('/');
+				pointer.push_str(&index.to_string());
+
+				validate_value(
+					element,
+					element_schema,
+					pack_format_version,
+					pointer,
+					violations
+				);
+
+				pointer.truncate(pointer_length);
+			}
+		}
+		(Schema::Map(value_schema), Value::Object(object)) => {
+			for (key, value) in object {
+				if COMMENT_KEYS.contains(&key.as_str()) {
+					continue;
+				}
+
+				let pointer_length = pointer.len();
+				push_pointer_key(pointer, key);
+				validate_value(
+					value,
+					value_schema,
+					pack_format_version,
+					pointer,
+					violations
+				);
+				pointer.truncate(pointer_length);
+			}
+		}
+		(Schema::Object(fields) | Schema::OpenObject(fields), Value::Object(object)) => {
+			for field in *fields {
+				if field.required && !object.contains_key(field.name) {
+					violations.push(format!(
+						"Missing required key \"{}\" at {}",
+						field.name,
+						location(pointer)
+					));
+				}
+			}
+
+			for (key, value) in object {
+				if COMMENT_KEYS.contains(&key.as_str()) {
+					continue;
+				}
+
+				let Some(field) = fields.iter().find(|field| field.name == key) else {
+					if matches!(schema, Schema::Object(_)) {
+						violations.push(
+							match suggested_key(key, fields.iter().map(|field| field.name)) {
+								Some(suggested_key) => format!(
+									"Unknown key \"{key}\" at {}, did you mean \"{suggested_key}\"?",
+									location(pointer)
+								),
+								None => format!("Unknown key \"{key}\" at {}", location(pointer))
+							}
+						);
+					}
+					continue;
+				};
+
+				if let Some(since_pack_format_version) = field.since_pack_format_version
+					&& pack_format_version < since_pack_format_version
+				{
+					violations.push(format!(
+						"Key \"{key}\" at {} is ignored by Minecraft versions of pack format \
+						 version {pack_format_version}, requiring version {since_pack_format_version} \
+						 or later",
+						location(pointer)
+					));
+				}
+
+				let pointer_length = pointer.len();
+				push_pointer_key(pointer, key);
+				validate_value(
+					value,
+					&field.schema,
+					pack_format_version,
+					pointer,
+					violations
+				);
+				pointer.truncate(pointer_length);
+			}
+		}
+		(Schema::OneOf(schemas), value) => {
+			// The alternative schemas have different JSON types, so at most one matches
+			if let Some(schema) = schemas.iter().find(|schema| schema.matches_type(value)) {
+				validate_value(value, schema, pack_format_version, pointer, violations);
+			}
+		}
+		_ => {}
+	}
+}
+
+impl Schema {
+	/// Checks whether the specified JSON value has the type this schema expects, and the
+	/// expected number of elements, if applicable. The contents of arrays and objects are
+	/// not checked.
+	fn matches_type(&self, value: &Value) -> bool {
+		match self {
+			Self::Any => true,
+			Self::Boolean => value.is_boolean(),
+			Self::Number => value.is_number(),
+			Self::Integer => value.is_i64() || value.is_u64(),
+			Self::String => value.is_string(),
+			Self::Array(_) => value.is_array(),
+			Self::NumberVector(length) => value.as_array().is_some_and(|elements| {
+				elements.len() == *length && elements.iter().all(Value::is_number)
+			}),
+			Self::Map(_) | Self::Object(_) | Self::OpenObject(_) => value.is_object(),
+			Self::OneOf(schemas) => schemas.iter().any(|schema| schema.matches_type(value))
+		}
+	}
+
+	/// Returns a user-friendly description of the values this schema expects.
+	fn description(&self) -> String {
+		match self {
+			Self::Any => "any value".into(),
+			Self::Boolean => "a boolean".into(),
+			Self::Number => "a number".into(),
+			Self::Integer => "an integer".into(),
+			Self::String => "a string".into(),
+			Self::Array(_) => "an array".into(),
+			Self::NumberVector(length) => format!("an array of {length} numbers"),
+			Self::Map(_) | Self::Object(_) | Self::OpenObject(_) => "an object".into(),
+			Self::OneOf(schemas) => schemas
+				.iter()
+				.map(Self::description)
+				.collect::<Vec<_>>()
+				.join(" or ")
+		}
+	}
+}
+
+/// Appends an object key to a JSON pointer, escaping it as needed.
+fn push_pointer_key(pointer: &mut String, key: &str) {
+	pointer.push('/');
+	pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+}
+
+/// Returns a user-friendly description of the location of the value at a JSON pointer.
+fn location(pointer: &str) -> &str {
+	if pointer.is_empty() { "/" } else { pointer }
+}
+
+/// Returns the known key that an unknown key most likely is a typo of, if any.
+fn suggested_key<'key>(
+	unknown_key: &str,
+	known_keys: impl Iterator<Item = &'key str>
+) -> Option<&'key str> {
+	known_keys
+		.map(|known_key| (known_key, edit_distance(unknown_key, known_key)))
+		.filter(|&(_, distance)| distance <= MAXIMUM_SUGGESTION_EDIT_DISTANCE)
+		.min_by_key(|&(_, distance)| distance)
+		.map(|(known_key, _)| known_key)
+}
+
+/// Computes the Damerau-Levenshtein edit distance between two strings, where swapping two
+/// adjacent characters counts as a single edit, like most typos.
+fn edit_distance(a: &str, b: &str) -> usize {
+	let a = a.chars().collect::<Vec<_>>();
+	let b = b.chars().collect::<Vec<_>>();
+
+	// distances[i][j] is the distance between the first i characters of a and the first j
+	// characters of b
+	let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+	for (i, row) in distances.iter_mut().enumerate() {
+		row[0] = i;
+	}
+	for (j, distance) in distances[0].iter_mut().enumerate() {
+		*distance = j;
+	}
+
+	for i in 1..=a.len() {
+		for j in 1..=b.len() {
+			let substitution_cost = usize::from(a[i - 1] != b[j - 1]);
+
+			let mut distance = (distances[i - 1][j] + 1)
+				.min(distances[i][j - 1] + 1)
+				.min(distances[i - 1][j - 1] + substitution_cost);
+			if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+				distance = distance.min(distances[i - 2][j - 2] + 1);
+			}
+
+			distances[i][j] = distance;
+		}
+	}
+
+	distances[a.len()][b.len()]
+}
+
+/// Reads and parses the JSON file at the specified path of a virtual file system, allowing
+/// comments and trailing commas like the usual JSON file processing does, and returning `None`
+/// if some I/O or parsing error occurs.
+async fn read_json_value<V: VirtualFileSystem>(vfs: &V, path: &Path) -> Option<Value> {
+	let (mut data, _) = read_file(vfs, path).await.ok()?;
+
+	let bom_length = data.len() - strip_utf8_bom(&data).len();
+	let json = &mut data[bom_length..];
+	blank_out_relaxed_syntax(json).ok()?;
+
+	serde_json::from_slice(json).ok()
+}
@@ -0,0 +1,385 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+/// Validates the specified pack files, returning the relative path of every file with
+/// schema violations and the violation descriptions, in order.
+fn validate_pack_files(files: &[(&str, &str)], pack_format_version: i32) -> Vec<(String, String)> {
+	validate_pack_files_ignoring_key_prefixes(files, pack_format_version, &[])
+}
+
+/// Like [`validate_pack_files`], but ignoring the object keys with the specified prefixes.
+fn validate_pack_files_ignoring_key_prefixes(
+	files: &[(&str, &str)],
+	pack_format_version: i32,
+	ignored_key_prefixes: &[&str]
+) -> Vec<(String, String)> {
+	let root_dir = Builder::new()
+		.prefix("ps-json-schema-validation-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in files {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	tokio_test::block_on(validate_json_schemas(
+		&OsFilesystem,
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		pack_format_version,
+		ignored_key_prefixes
+	))
+	.into_iter()
+	.map(|(relative_path, violation)| (relative_path.as_str().to_string(), violation))
+	.collect()
+}
+
+#[test]
+fn valid_files_pass() {
+	assert_eq!(
+		validate_pack_files(
+			&[
+				(
+					"pack.mcmeta",
+					r#"{ "pack": { "pack_format": 34, "description": "Valid pack" } }"#
+				),
+				(
+					"assets/mypack/models/block/cube.json",
+					r##"{
+						// Comments are allowed, like in Minecraft
+						"parent": "block/block",
+						"gui_light": "side",
+						"textures": { "all": "mypack:block/cube", "particle": "#all" },
+						"elements": [{
+							"from": [0, 0, 0],
+							"to": [16, 16, 16],
+							"faces": { "north": { "texture": "#all", "uv": [0, 0, 16, 16] } }
+						}],
+						"__comment": "Made with Blockbench"
+					}"##
+				),
+				(
+					"assets/mypack/models/block/custom.json",
+					r#"{ "loader": "somemod:obj", "model": "mypack:models/block/custom.obj" }"#
+				),
+				(
+					"assets/mypack/blockstates/cube.json",
+					r#"{
+						"variants": {
+							"": [{ "model": "mypack:block/cube" }, { "model": "mypack:block/cube", "y": 90 }]
+						}
+					}"#
+				),
+				(
+					"assets/mypack/items/gem.json",
+					r#"{
+						"model": {
+							"type": "minecraft:condition",
+							"property": "minecraft:using_item",
+							"on_true": { "type": "minecraft:model", "model": "mypack:item/gem_glowing" },
+							"on_false": {
+								"type": "minecraft:select",
+								"property": "minecraft:display_context",
+								"cases": [{ "when": "gui", "model": { "type": "minecraft:empty" } }],
+								"fallback": {
+									"type": "minecraft:model",
+									"model": "mypack:item/gem",
+									"tints": [{ "type": "minecraft:constant", "value": -1 }]
+								}
+							}
+						},
+						"hand_animation_on_swap": false
+					}"#
+				),
+				(
+					"assets/minecraft/atlases/blocks.json",
+					r#"{
+						"sources": [
+							{ "type": "minecraft:directory", "source": "mypack_blocks", "prefix": "mypack_blocks/" },
+							{ "type": "minecraft:single", "resource": "mypack:misc/glint" }
+						]
+					}"#
+				),
+				(
+					"assets/mypack/particles/spark.json",
+					r#"{ "textures": ["mypack:spark"] }"#
+				),
+				(
+					"data/mypack/loot_table/blocks/cube.json",
+					r#"{
+						"type": "minecraft:block",
+						"pools": [{
+							"rolls": 1,
+							"entries": [{ "type": "minecraft:item", "name": "mypack:cube" }],
+							"conditions": [{ "condition": "minecraft:survives_explosion" }]
+						}]
+					}"#
+				),
+				(
+					"data/mypack/tags/item/gems.json",
+					r##"{ "values": ["mypack:ruby", { "id": "#othermod:gems", "required": false }] }"##
+				),
+				(
+					"data/mypack/recipe/ruby_block.json",
+					r#"{
+						"type": "minecraft:crafting_shapeless",
+						"category": "building",
+						"ingredients": ["mypack:ruby"],
+						"result": { "id": "mypack:ruby_block" }
+					}"#
+				),
+				("assets/mypack/sounds.json", r#"{ "unrelated": true }"#),
+				("assets/mypack/models/block/broken.json", "{")
+			],
+			48
+		),
+		[]
+	);
+}
+
+#[test]
+fn typos_are_reported() {
+	assert_eq!(
+		validate_pack_files(
+			&[
+				(
+					"pack.mcmeta",
+					r#"{ "pack": { "pack_format": "15", "descripton": "Typo pack" } }"#
+				),
+				(
+					"assets/mypack/models/item/gem.json",
+					r#"{
+						"parent": "item/generated",
+						"textrues": { "layer0": "mypack:item/gem" },
+						"display": { "gui": { "scale": [1, 1] } }
+					}"#
+				),
+				(
+					"assets/mypack/blockstates/gem_block.json",
+					r#"{ "variants": { "": { "modle": "mypack:block/gem_block" } } }"#
+				),
+				(
+					"assets/mypack/items/gem.json",
+					r#"{
+						"model": {
+							"type": "minecraft:range_dispatch",
+							"property": "minecraft:count",
+							"entries": [{ "treshold": 0.5, "model": { "type": "minecraft:empty" } }]
+						}
+					}"#
+				),
+				(
+					"data/mypack/advancements/story/gem.json",
+					r#"{ "criteria": { "has_gem": { "trigger": "minecraft:inventory_changed" } } }"#
+				)
+			],
+			15
+		),
+		[
+			(
+				"assets/mypack/blockstates/gem_block.json".into(),
+				"Missing required key \"model\" at /variants/".into()
+			),
+			(
+				"assets/mypack/blockstates/gem_block.json".into(),
+				"Unknown key \"modle\" at /variants/, did you mean \"model\"?".into()
+			),
+			(
+				"assets/mypack/items/gem.json".into(),
+				"Missing required key \"threshold\" at /model/entries/0".into()
+			),
+			(
+				"assets/mypack/items/gem.json".into(),
+				"Unknown key \"treshold\" at /model/entries/0, did you mean \"threshold\"?".into()
+			),
+			(
+				"assets/mypack/models/item/gem.json".into(),
+				"Unknown key \"textrues\" at /, did you mean \"textures\"?".into()
+			),
+			(
+				"assets/mypack/models/item/gem.json".into(),
+				"Expected an array of 3 numbers at /display/gui/scale".into()
+			),
+			(
+				"pack.mcmeta".into(),
+				"Missing required key \"description\" at /pack".into()
+			),
+			(
+				"pack.mcmeta".into(),
+				"Expected an integer at /pack/pack_format".into()
+			),
+			(
+				"pack.mcmeta".into(),
+				"Unknown key \"descripton\" at /pack, did you mean \"description\"?".into()
+			)
+		]
+	);
+}
+
+#[test]
+fn version_specific_schemas_are_used() {
+	let files = [
+		(
+			"assets/mypack/models/item/flat.json",
+			r#"{ "parent": "item/generated", "gui_light": "front" }"#
+		),
+		(
+			"data/mypack/predicates/is_sneaking.json",
+			r#"{ "condition": "minecraft:entity_properties", "entity": "this" }"#
+		),
+		(
+			"data/mypack/tags/functions/load.json",
+			r#"{ "values": ["mypack:load"] }"#
+		)
+	];
+
+	assert_eq!(
+		validate_pack_files(&files, 4),
+		[(
+			"assets/mypack/models/item/flat.json".into(),
+			"Key \"gui_light\" at / is ignored by Minecraft versions of pack format version 4, \
+			 requiring version 5 or later"
+				.into()
+		)]
+	);
+	assert_eq!(
+		validate_pack_files(&files, 48),
+		[
+			(
+				"data/mypack/predicates/is_sneaking.json".into(),
+				"Minecraft versions of pack format version 48 ignore files in the \"predicates\" \
+				 folder, expecting them in the \"predicate\" folder"
+					.into()
+			),
+			(
+				"data/mypack/tags/functions/load.json".into(),
+				"Minecraft versions of pack format version 48 ignore files in the \
+				 \"tags/functions\" folder, expecting them in the \"tags/function\" folder"
+					.into()
+			)
+		]
+	);
+}
+
+#[test]
+fn files_with_trailing_commas_are_validated() {
+	assert_eq!(
+		validate_pack_files(
+			&[
+				(
+					"assets/mypack/models/block/glowing.json",
+					r##"{
+						"parent": "block/block",
+						"elements": [{
+							"from": [0, 0, 0],
+							"to": [16, 16, 16],
+							"light_emission": 15,
+							"faces": { "up": { "texture": "#all", }, },
+						},],
+					}"##
+				),
+				(
+					"data/mypack/advancements/story/gem.json",
+					r#"{
+						"display": {
+							"icon": { "id": "mypack:gem", },
+							"title": "Gem",
+							"description": "Get a gem",
+						},
+						"criteria": { "has_gem": { "triger": "minecraft:inventory_changed" } },
+					}"#
+				)
+			],
+			26
+		),
+		[
+			(
+				"assets/mypack/models/block/glowing.json".into(),
+				"Key \"light_emission\" at /elements/0 is ignored by Minecraft versions of pack \
+				 format version 26, requiring version 34 or later"
+					.into()
+			),
+			(
+				"data/mypack/advancements/story/gem.json".into(),
+				"Key \"id\" at /display/icon is ignored by Minecraft versions of pack format \
+				 version 26, requiring version 41 or later"
+					.into()
+			),
+			(
+				"data/mypack/advancements/story/gem.json".into(),
+				"Missing required key \"trigger\" at /criteria/has_gem".into()
+			),
+			(
+				"data/mypack/advancements/story/gem.json".into(),
+				"Unknown key \"triger\" at /criteria/has_gem, did you mean \"trigger\"?".into()
+			)
+		]
+	);
+}
+
+#[test]
+fn text_components_are_validated() {
+	let text_component = serde_json::from_str(
+		r#"[
+			"",
+			{ "text": "Click me", "bold": true, "click_event": { "action": "run_command", "comand": "/help" } },
+			{ "translate": "chat.type.text", "with": [{ "selector": "@s" }, 3] },
+			{ "text": "Hover me", "hoverEvent": { "action": "show_text" } }
+		]"#
+	)
+	.expect("The test text component is assumed to be valid JSON");
+
+	assert_eq!(
+		validate_text_component(&text_component),
+		[
+			"Unknown key \"comand\" at /1/click_event, did you mean \"command\"?",
+			"Expected a string or an array or an object at /2/with/1"
+		]
+	);
+}
+
+#[test]
+fn mod_loader_keys_are_ignored() {
+	let files = [
+		(
+			"pack.mcmeta",
+			r#"{
+				"pack": {
+					"pack_format": 15,
+					"forge:resource_pack_format": 15,
+					"description": "Mod resources"
+				}
+			}"#
+		),
+		(
+			"data/mymod/tags/item/gems.json",
+			r#"{
+				"fabric:load_conditions": [{ "condition": "fabric:all_mods_loaded", "values": ["othermod"] }],
+				"values": ["mymod:ruby"]
+			}"#
+		)
+	];
+
+	assert_eq!(
+		validate_pack_files_ignoring_key_prefixes(&files, 48, &["forge:", "fabric:"]),
+		[]
+	);
+	assert_eq!(validate_pack_files(&files, 48).len(), 2);
+}
+
+#[test]
+fn edit_distance_counts_transpositions_as_single_edits() {
+	assert_eq!(edit_distance("textures", "textures"), 0);
+	assert_eq!(edit_distance("textrues", "textures"), 1);
+	assert_eq!(edit_distance("modle", "model"), 1);
+	assert_eq!(edit_distance("parnt", "parent"), 1);
+	assert_eq!(edit_distance("elements", "display"), 8);
+}
@@ -0,0 +1,276 @@
+//! Contains a virtual file system adapter that converts language files between the legacy
+//! `.lang` format and the modern JSON format, depending on the format the Minecraft versions
+//! targeted by a pack read.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::SystemTime
+};
+
+use ahash::{AHashMap, AHashSet};
+use bytes::Bytes;
+use serde_json::{Map, Value};
+use tokio_util::either::Either;
+
+use crate::RelativePath;
+use crate::pack_file::{blank_out_relaxed_syntax, strip_utf8_bom};
+use crate::pack_meta::{PACK_FORMAT_VERSION_1_11, PACK_FORMAT_VERSION_1_13};
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A language file generated by [`LanguageFileConvertingVfs`].
+struct GeneratedFile {
+	data: Bytes,
+	modification_time: Option<SystemTime>
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently adding language files in the
+/// format the Minecraft versions targeted by the pack read for the locales whose language
+/// files are only in the other format. Minecraft 1.13 and newer only read JSON language files,
+/// such as `lang/en_us.json`, while older versions only read legacy `.lang` language files,
+/// such as `lang/en_us.lang`, or `lang/en_US.lang` before Minecraft 1.11. This allows keeping
+/// language files in a single format for packs that target several Minecraft versions.
+///
+/// The source language files are left as-is, so that automatic asset type mask detection can
+/// decide whether they should be added to the generated ZIP file.
+///
+/// The generated files are computed eagerly by [`Self::convert_language_files`] and kept in
+/// memory, because the [`VirtualFileSystem::open`] method is synchronous. Other files are
+/// read from the wrapped file system as-is.
+pub struct LanguageFileConvertingVfs<V: VirtualFileSystem> {
+	inner: V,
+	generated_files: AHashMap<PathBuf, GeneratedFile>,
+	/// The relative and file paths of the generated language files, which are added to file
+	/// iterators.
+	generated_language_file_paths: Arc<Vec<(String, PathBuf)>>
+}
+
+impl<V: VirtualFileSystem> LanguageFileConvertingVfs<V> {
+	/// Wraps the specified virtual file system. No language files will be converted until
+	/// [`Self::convert_language_files`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			generated_files: AHashMap::new(),
+			generated_language_file_paths: Arc::new(Vec::new())
+		}
+	}
+
+	/// Scans the pack at the specified root path for language files in the format the
+	/// Minecraft versions targeted by the specified `pack_format` version do not read,
+	/// converting those whose locale has no language file in the format they read. Language
+	/// files that can't be read, parsed or represented in the other format are left as-is,
+	/// so that the usual pack file processing reports any relevant error.
+	///
+	/// References:
+	/// - <https://minecraft.wiki/w/Resource_pack#Language>
+	/// - Minecraft class `net.minecraft.client.resources.Locale` (MCP 1.12.2 name)
+	pub async fn convert_language_files(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions,
+		pack_format_version: i32
+	) {
+		let target_legacy_format = pack_format_version < PACK_FORMAT_VERSION_1_13;
+
+		// Index the language files of each namespace by their lowercase locale, which is the
+		// only way locales of legacy language files can be matched with modern ones
+		let mut language_files = vec![];
+		let mut target_format_locales = AHashSet::new();
+		for VfsPackFileIterEntry {
+			relative_path,
+			file_path
+		} in self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+		{
+			let Some((namespace, file_name)) = relative_path
+				.as_str()
+				.strip_prefix("assets/")
+				.and_then(|path| path.split_once("/lang/"))
+				.filter(|(namespace, file_name)| {
+					!namespace.contains('/') && !file_name.contains('/')
+				})
+			else {
+				continue;
+			};
+			let Some((locale, extension)) = file_name.rsplit_once('.') else {
+				continue;
+			};
+			let is_legacy_format = match extension {
+				"lang" => true,
+				"json" => false,
+				_ => continue
+			};
+
+			let locale_key = (namespace.to_string(), locale.to_ascii_lowercase());
+			if is_legacy_format == target_legacy_format {
+				target_format_locales.insert(locale_key);
+			} else {
+				language_files.push((locale_key, relative_path, file_path));
+			}
+		}
+
+		// Sort the language files to generate the same files consistently, no matter the order
+		// the file system lists them in
+		language_files.sort_unstable_by(|(_, a, _), (_, b, _)| a.as_str().cmp(b.as_str()));
+
+		let mut generated_language_file_paths = vec![];
+		for (locale_key, _, file_path) in language_files {
+			if target_format_locales.contains(&locale_key) {
+				continue;
+			}
+			let (namespace, locale) = &locale_key;
+
+			let Ok((data, modification_time)) = read_file(&self.inner, &file_path).await else {
+				continue;
+			};
+
+			let (converted_data, relative_path) = if target_legacy_format {
+				let Some(converted_data) = json_to_legacy_language_file(data) else {
+					continue;
+				};
+
+				(
+					converted_data,
+					format!(
+						"assets/{namespace}/lang/{}.lang",
+						legacy_locale_name(locale, pack_format_version)
+					)
+				)
+			} else {
+				let Some(converted_data) = legacy_language_file_to_json(&data) else {
+					continue;
+				};
+
+				(
+					converted_data,
+					format!("assets/{namespace}/lang/{locale}.json")
+				)
+			};
+
+			let generated_file_path = root_path.join(&relative_path);
+			self.generated_files.insert(
+				generated_file_path.clone(),
+				GeneratedFile {
+					data: converted_data.into(),
+					modification_time
+				}
+			);
+			generated_language_file_paths.push((relative_path, generated_file_path));
+			target_format_locales.insert(locale_key);
+		}
+
+		self.generated_language_file_paths = Arc::new(generated_language_file_paths);
+	}
+}
+
+/// Converts the contents of a legacy `.lang` language file to a JSON language file, returning
+/// `None` if it is not valid UTF-8 or some line that is not empty nor a comment does not
+/// contain a `=` separator. Like Minecraft does, keys defined several times keep their last
+/// value.
+fn legacy_language_file_to_json(data: &[u8]) -> Option<Vec<u8>> {
+	let mut translations = Map::new();
+
+	for line in std::str::from_utf8(strip_utf8_bom(data)).ok()?.lines() {
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let (key, value) = line.split_once('=')?;
+		translations.insert(key.to_string(), Value::String(value.to_string()));
+	}
+
+	serde_json::to_vec(&translations).ok()
+}
+
+/// Converts the contents of a JSON language file to a legacy `.lang` language file, returning
+/// `None` if it can't be parsed or contains translations that can't be represented in the
+/// legacy format: keys that contain a `=` separator, and keys or values that are not strings
+/// or contain line breaks. Comments and trailing commas are allowed, like in the usual JSON
+/// processing.
+fn json_to_legacy_language_file(mut data: Vec<u8>) -> Option<Vec<u8>> {
+	let bom_length = data.len() - strip_utf8_bom(&data).len();
+	let json = &mut data[bom_length..];
+	blank_out_relaxed_syntax(json).ok()?;
+
+	let translations: Map<String, Value> = serde_json::from_slice(json).ok()?;
+
+	let mut converted_data = Vec::with_capacity(data.len());
+	for (key, value) in translations {
+		let value = value.as_str()?;
+
+		if key.contains(['=', '\n', '\r']) || value.contains(['\n', '\r']) {
+			return None;
+		}
+
+		converted_data.extend_from_slice(key.as_bytes());
+		converted_data.push(b'=');
+		converted_data.extend_from_slice(value.as_bytes());
+		converted_data.push(b'\n');
+	}
+
+	Some(converted_data)
+}
+
+/// Returns the name of the legacy language file of the specified lowercase locale for the
+/// Minecraft versions targeted by the specified `pack_format` version. Versions before 1.11
+/// expect the region of the locale to be uppercase.
+fn legacy_locale_name(locale: &str, pack_format_version: i32) -> String {
+	match locale.split_once('_') {
+		Some((language, region)) if pack_format_version < PACK_FORMAT_VERSION_1_11 => {
+			format!("{language}_{}", region.to_ascii_uppercase())
+		}
+		_ => locale.to_string()
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for LanguageFileConvertingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = impl Iterator<Item = Result<VfsPackFileIterEntry, io::Error>>;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		let generated_language_file_entries = self
+			.generated_language_file_paths
+			.iter()
+			.filter(|(_, file_path)| file_path.starts_with(root_path))
+			.map(|(relative_path, file_path)| {
+				Ok(VfsPackFileIterEntry {
+					relative_path: RelativePath::from_inner(relative_path.clone()),
+					file_path: file_path.clone()
+				})
+			})
+			.collect::<Vec<_>>();
+
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.chain(generated_language_file_entries)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.generated_files
+				.get(path.as_ref())
+				.map(|generated_file| (&generated_file.data, generated_file.modification_time))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
@@ -0,0 +1,109 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+use tokio::io::AsyncReadExt;
+
+use crate::pack_meta::PACK_FORMAT_VERSION_1_15;
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+/// Converts the language files of a pack with the specified files for the specified
+/// `pack_format` version, returning the relative paths and contents of the generated files,
+/// sorted by path.
+fn convert_language_files(files: &[(&str, &str)], pack_format_version: i32) -> Vec<(String, String)> {
+	let root_dir = Builder::new()
+		.prefix("ps-language-file-conversion-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in files {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mut vfs = LanguageFileConvertingVfs::new(OsFilesystem);
+	tokio_test::block_on(vfs.convert_language_files(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		pack_format_version
+	));
+
+	let mut generated_files = vfs
+		.generated_language_file_paths
+		.iter()
+		.map(|(relative_path, file_path)| {
+			let mut file = vfs
+				.open(file_path)
+				.expect("I/O operations are assumed not to fail during tests");
+			let mut data = String::new();
+			tokio_test::block_on(file.file_read.read_to_string(&mut data))
+				.expect("I/O operations are assumed not to fail during tests");
+
+			(relative_path.clone(), data)
+		})
+		.collect::<Vec<_>>();
+	generated_files.sort_unstable();
+
+	generated_files
+}
+
+#[test]
+fn legacy_language_files_are_converted_for_modern_versions() {
+	assert_eq!(
+		convert_language_files(
+			&[
+				(
+					"assets/mypack/lang/en_US.lang",
+					"\u{feff}# A comment\nitem.ruby.name=Ruby\n\nitem.ruby.desc=Shiny = red\r\n"
+				),
+				("assets/mypack/lang/de_de.lang", "item.ruby.name=Rubin\n"),
+				(
+					"assets/mypack/lang/de_de.json",
+					r#"{ "item.ruby.name": "Rubin" }"#
+				),
+				("assets/mypack/lang/es_es.lang", "missing separator\n")
+			],
+			PACK_FORMAT_VERSION_1_15
+		),
+		[(
+			"assets/mypack/lang/en_us.json".to_string(),
+			r#"{"item.ruby.name":"Ruby","item.ruby.desc":"Shiny = red"}"#.to_string()
+		)]
+	);
+}
+
+#[test]
+fn json_language_files_are_converted_for_legacy_versions() {
+	let files = [
+		(
+			"assets/mypack/lang/en_us.json",
+			r#"{
+				// Comments and trailing commas are allowed, like in the usual JSON processing
+				"item.ruby.name": "Ruby",
+				"item.ruby.desc": "Shiny",
+			}"#
+		),
+		(
+			"assets/mypack/lang/fr_fr.json",
+			r#"{ "item.ruby.desc": "Brillant\net rouge" }"#
+		)
+	];
+
+	assert_eq!(
+		convert_language_files(&files, PACK_FORMAT_VERSION_1_11),
+		[(
+			"assets/mypack/lang/en_us.lang".to_string(),
+			"item.ruby.name=Ruby\nitem.ruby.desc=Shiny\n".to_string()
+		)]
+	);
+	assert_eq!(
+		convert_language_files(&files, PACK_FORMAT_VERSION_1_11 - 1),
+		[(
+			"assets/mypack/lang/en_US.lang".to_string(),
+			"item.ruby.name=Ruby\nitem.ruby.desc=Shiny\n".to_string()
+		)]
+	);
+}
@@ -0,0 +1,373 @@
+//! Contains a virtual file system adapter that merges language file fragments into a single
+//! language file per locale, and finds the translations of a language file that are the same
+//! as the American English translations Minecraft falls back to.
+
+use std::{
+	collections::BTreeMap,
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::SystemTime
+};
+
+use ahash::{AHashMap, AHashSet};
+use bytes::Bytes;
+use serde_json::{Map, Value};
+use tokio_util::either::Either;
+
+use crate::RelativePath;
+use crate::config::DuplicateTranslationHandling;
+use crate::pack_file::{blank_out_relaxed_syntax, strip_utf8_bom};
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The locale that Minecraft loads before the selected one, so that its translations are used
+/// for keys the selected locale does not translate.
+const FALLBACK_LOCALE: &str = "en_us";
+
+/// A language file generated by [`LanguageFileMergingVfs`].
+struct GeneratedFile {
+	data: Bytes,
+	modification_time: Option<SystemTime>
+}
+
+/// The language file and language file fragments of a locale of a namespace.
+#[derive(Default)]
+struct LocaleFiles {
+	language_file: Option<PathBuf>,
+	fragments: Vec<(String, PathBuf)>
+}
+
+/// The translations of a locale of a namespace, read from its language file and fragments.
+struct LocaleTranslations {
+	relative_path: String,
+	/// The path of the language file of the locale, if the pack contains it.
+	file_path: Option<PathBuf>,
+	translations: Map<String, Value>,
+	modification_time: Option<SystemTime>,
+	modified: bool
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently replacing the JSON language
+/// file fragments in a folder named after a locale, which some build setups generate, with a
+/// single language file for that locale. For example, the `lang/en_us/blocks.json` and
+/// `lang/en_us/items.json` fragments of a namespace are merged into its `lang/en_us.json`
+/// language file, which is generated if it does not exist. It may also remove the translations
+/// of language files that are the same as the American English translations of the same
+/// namespace, which Minecraft falls back to.
+///
+/// The generated files are computed eagerly by [`Self::process_language_files`] and kept in
+/// memory, because the [`VirtualFileSystem::open`] method is synchronous. Other files are
+/// read from the wrapped file system as-is.
+pub struct LanguageFileMergingVfs<V: VirtualFileSystem> {
+	inner: V,
+	generated_files: AHashMap<PathBuf, GeneratedFile>,
+	/// The paths of the fragments that were merged, which are removed from file iterators.
+	merged_fragment_paths: Arc<AHashSet<PathBuf>>,
+	/// The relative and file paths of the generated language files that did not exist in the
+	/// pack, which are added to file iterators.
+	generated_language_file_paths: Arc<Vec<(String, PathBuf)>>
+}
+
+impl<V: VirtualFileSystem> LanguageFileMergingVfs<V> {
+	/// Wraps the specified virtual file system. No language files will be changed until
+	/// [`Self::process_language_files`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			generated_files: AHashMap::new(),
+			merged_fragment_paths: Arc::new(AHashSet::new()),
+			generated_language_file_paths: Arc::new(Vec::new())
+		}
+	}
+
+	/// Scans the pack at the specified root path for JSON language files, merging their
+	/// fragments if `merge_fragments` is `true`, and finding their translations that are the
+	/// same as the American English translation of the same namespace according to the
+	/// specified handling. The returned vector contains the paths of the language files with
+	/// such translations and their count, sorted by path, and is only populated when they are
+	/// not removed. Fragments are merged in path order after the language file of their
+	/// locale, so their translations take precedence. Locales whose language file or fragments
+	/// can't be read or parsed are left as-is, so that the usual pack file processing reports
+	/// any relevant error.
+	pub async fn process_language_files(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions,
+		merge_fragments: bool,
+		duplicate_translation_handling: DuplicateTranslationHandling
+	) -> Vec<(RelativePath<'static>, usize)> {
+		// Sort the locales and fragments to generate the same files consistently, no matter the
+		// order the file system lists them in
+		let mut locale_files = BTreeMap::<(String, String), LocaleFiles>::new();
+		for VfsPackFileIterEntry {
+			relative_path,
+			file_path
+		} in self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+		{
+			let Some((namespace, path)) = relative_path
+				.as_str()
+				.strip_prefix("assets/")
+				.and_then(|path| path.split_once("/lang/"))
+				.filter(|(namespace, _)| !namespace.contains('/'))
+			else {
+				continue;
+			};
+			let Some(path) = path.strip_suffix(".json") else {
+				continue;
+			};
+
+			match path.split_once('/') {
+				None => {
+					locale_files
+						.entry((namespace.to_string(), path.to_string()))
+						.or_default()
+						.language_file = Some(file_path);
+				}
+				Some((locale, _)) if merge_fragments => {
+					locale_files
+						.entry((namespace.to_string(), locale.to_string()))
+						.or_default()
+						.fragments
+						.push((relative_path.into_inner().into_owned(), file_path));
+				}
+				Some(_) => {}
+			}
+		}
+
+		let find_duplicate_translations =
+			duplicate_translation_handling != DuplicateTranslationHandling::Ignore;
+
+		let mut locale_translations = BTreeMap::new();
+		let mut merged_fragment_paths = AHashSet::new();
+		for ((namespace, locale), mut files) in locale_files {
+			if files.fragments.is_empty() && !find_duplicate_translations {
+				continue;
+			}
+			files.fragments.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+			let Some(translations) = self
+				.read_locale_translations(&namespace, &locale, &files)
+				.await
+			else {
+				continue;
+			};
+
+			merged_fragment_paths.extend(files.fragments.into_iter().map(|(_, file_path)| file_path));
+			locale_translations.insert((namespace, locale), translations);
+		}
+
+		let mut duplicate_translation_counts = vec![];
+		if find_duplicate_translations {
+			let namespaces = locale_translations
+				.keys()
+				.map(|(namespace, _)| namespace.clone())
+				.collect::<AHashSet<_>>();
+
+			for namespace in namespaces {
+				let fallback_key = (namespace, FALLBACK_LOCALE.to_string());
+				let Some(fallback_translations) = locale_translations.remove(&fallback_key) else {
+					continue;
+				};
+
+				for (_, translations) in locale_translations
+					.iter_mut()
+					.filter(|((namespace, _), _)| *namespace == fallback_key.0)
+				{
+					let duplicate_translation_count = remove_duplicate_translations(
+						translations,
+						&fallback_translations,
+						duplicate_translation_handling == DuplicateTranslationHandling::Deduplicate
+					);
+
+					if duplicate_translation_count > 0
+						&& duplicate_translation_handling == DuplicateTranslationHandling::Warn
+					{
+						duplicate_translation_counts.push((
+							RelativePath::from_inner(translations.relative_path.clone()),
+							duplicate_translation_count
+						));
+					}
+				}
+
+				locale_translations.insert(fallback_key, fallback_translations);
+			}
+		}
+		duplicate_translation_counts.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+		let mut generated_language_file_paths = vec![];
+		for (_, translations) in locale_translations {
+			if !translations.modified {
+				continue;
+			}
+
+			let file_path = match translations.file_path {
+				Some(file_path) => file_path,
+				None => {
+					let file_path = root_path.join(&translations.relative_path);
+					generated_language_file_paths
+						.push((translations.relative_path, file_path.clone()));
+					file_path
+				}
+			};
+
+			self.generated_files.insert(
+				file_path,
+				GeneratedFile {
+					data: serde_json::to_vec(&translations.translations)
+						.unwrap()
+						.into(),
+					modification_time: translations.modification_time
+				}
+			);
+		}
+
+		self.merged_fragment_paths = Arc::new(merged_fragment_paths);
+		self.generated_language_file_paths = Arc::new(generated_language_file_paths);
+
+		duplicate_translation_counts
+	}
+
+	/// Reads and merges the translations of the language file and fragments of a locale of a
+	/// namespace, returning `None` if some I/O or parsing error occurs.
+	async fn read_locale_translations(
+		&self,
+		namespace: &str,
+		locale: &str,
+		files: &LocaleFiles
+	) -> Option<LocaleTranslations> {
+		let mut locale_translations = LocaleTranslations {
+			relative_path: format!("assets/{namespace}/lang/{locale}.json"),
+			file_path: files.language_file.clone(),
+			translations: Map::new(),
+			modification_time: None,
+			modified: !files.fragments.is_empty()
+		};
+
+		// The merged language file depends on every fragment, so consider it modified when any
+		// of them is, so that stale versions of it are not reused from previous runs
+		let mut first_file = true;
+		for file_path in files
+			.language_file
+			.iter()
+			.chain(files.fragments.iter().map(|(_, file_path)| file_path))
+		{
+			let (translations, modification_time) = self.read_translations(file_path).await?;
+
+			locale_translations.translations.extend(translations);
+			locale_translations.modification_time = if first_file {
+				modification_time
+			} else {
+				locale_translations
+					.modification_time
+					.zip(modification_time)
+					.map(|(merged_time, time)| merged_time.max(time))
+			};
+			first_file = false;
+		}
+
+		Some(locale_translations)
+	}
+
+	/// Reads the translations of the JSON language file at the specified path of the wrapped
+	/// file system and its modification time, returning `None` if some I/O or parsing error
+	/// occurs. Comments and trailing commas are allowed, like in the usual JSON processing.
+	async fn read_translations(
+		&self,
+		path: &Path
+	) -> Option<(Map<String, Value>, Option<SystemTime>)> {
+		let (mut data, modification_time) = read_file(&self.inner, path).await.ok()?;
+
+		let bom_length = data.len() - strip_utf8_bom(&data).len();
+		let json = &mut data[bom_length..];
+		blank_out_relaxed_syntax(json).ok()?;
+
+		Some((serde_json::from_slice(json).ok()?, modification_time))
+	}
+}
+
+/// Counts the translations of a locale that are the same as the fallback translations of its
+/// namespace, removing them if `remove` is `true`.
+fn remove_duplicate_translations(
+	translations: &mut LocaleTranslations,
+	fallback_translations: &LocaleTranslations,
+	remove: bool
+) -> usize {
+	let translation_count = translations.translations.len();
+
+	let mut duplicate_translation_count = 0;
+	translations.translations.retain(|key, translation| {
+		let is_duplicate = fallback_translations.translations.get(key) == Some(translation);
+		duplicate_translation_count += usize::from(is_duplicate);
+
+		!(remove && is_duplicate)
+	});
+
+	if translations.translations.len() != translation_count {
+		// The deduplicated language file depends on the fallback one now, so consider it
+		// modified when any of them is
+		translations.modified = true;
+		translations.modification_time = translations
+			.modification_time
+			.zip(fallback_translations.modification_time)
+			.map(|(time, fallback_time)| time.max(fallback_time));
+	}
+
+	duplicate_translation_count
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for LanguageFileMergingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = impl Iterator<Item = Result<VfsPackFileIterEntry, io::Error>>;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		let merged_fragment_paths = Arc::clone(&self.merged_fragment_paths);
+		let generated_language_file_entries = self
+			.generated_language_file_paths
+			.iter()
+			.filter(|(_, file_path)| file_path.starts_with(root_path))
+			.map(|(relative_path, file_path)| {
+				Ok(VfsPackFileIterEntry {
+					relative_path: RelativePath::from_inner(relative_path.clone()),
+					file_path: file_path.clone()
+				})
+			})
+			.collect::<Vec<_>>();
+
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.filter(move |entry| {
+				!entry
+					.as_ref()
+					.is_ok_and(|entry| merged_fragment_paths.contains(&entry.file_path))
+			})
+			.chain(generated_language_file_entries)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.generated_files
+				.get(path.as_ref())
+				.map(|generated_file| (&generated_file.data, generated_file.modification_time))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
@@ -0,0 +1,124 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+use tokio::io::AsyncReadExt;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[test]
+fn language_files_are_merged_and_deduplicated() {
+	let root_dir = Builder::new()
+		.prefix("ps-language-file-merging-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		("assets/mypack/lang/en_us.json", r#"{ "a": "A", "b": "B" }"#),
+		(
+			"assets/mypack/lang/en_us/items.json",
+			r#"{
+				// Comments and trailing commas are allowed, like in the usual JSON processing
+				"c": "C",
+			}"#
+		),
+		("assets/mypack/lang/en_us/blocks.json", r#"{ "b": "B2" }"#),
+		(
+			"assets/mypack/lang/en_gb.json",
+			r#"{ "a": "A", "b": "B", "c": "Colour" }"#
+		),
+		("assets/mypack/lang/de_de/items.json", r#"{ "c": "Ce" }"#),
+		("assets/mypack/lang/broken/items.json", "{"),
+		("assets/othermod/lang/en_gb.json", r#"{ "a": "A" }"#)
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	// Only report duplicate translations at first
+	let mut vfs = LanguageFileMergingVfs::new(OsFilesystem);
+	let duplicate_translations = tokio_test::block_on(vfs.process_language_files(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		false,
+		DuplicateTranslationHandling::Warn
+	));
+
+	assert_eq!(
+		duplicate_translations
+			.iter()
+			.map(|(relative_path, count)| (relative_path.as_str(), *count))
+			.collect::<Vec<_>>(),
+		[("assets/mypack/lang/en_gb.json", 2)]
+	);
+	assert!(
+		vfs.generated_files.is_empty(),
+		"No language file should be generated when only reporting duplicate translations"
+	);
+
+	// Now merge fragments and remove duplicate translations
+	let mut vfs = LanguageFileMergingVfs::new(OsFilesystem);
+	let duplicate_translations = tokio_test::block_on(vfs.process_language_files(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		true,
+		DuplicateTranslationHandling::Deduplicate
+	));
+	assert!(
+		duplicate_translations.is_empty(),
+		"Removed duplicate translations should not be reported"
+	);
+
+	let mut relative_paths = vfs
+		.file_iterator(root_dir.path(), IteratorTraversalOptions::default())
+		.map(|entry| {
+			entry
+				.expect("I/O operations are assumed not to fail during tests")
+				.relative_path
+				.as_str()
+				.to_string()
+		})
+		.collect::<Vec<_>>();
+	relative_paths.sort_unstable();
+
+	assert_eq!(
+		relative_paths,
+		[
+			"assets/mypack/lang/broken/items.json",
+			"assets/mypack/lang/de_de.json",
+			"assets/mypack/lang/en_gb.json",
+			"assets/mypack/lang/en_us.json",
+			"assets/othermod/lang/en_gb.json"
+		]
+	);
+
+	let read_language_file = |relative_path: &str| {
+		let mut file = vfs
+			.open(root_dir.path().join(relative_path))
+			.expect("I/O operations are assumed not to fail during tests");
+		let mut data = String::new();
+		tokio_test::block_on(file.file_read.read_to_string(&mut data))
+			.expect("I/O operations are assumed not to fail during tests");
+		data
+	};
+
+	assert_eq!(
+		read_language_file("assets/mypack/lang/en_us.json"),
+		r#"{"a":"A","b":"B2","c":"C"}"#
+	);
+	assert_eq!(
+		read_language_file("assets/mypack/lang/en_gb.json"),
+		r#"{"b":"B","c":"Colour"}"#
+	);
+	assert_eq!(
+		read_language_file("assets/mypack/lang/de_de.json"),
+		r#"{"c":"Ce"}"#
+	);
+	assert_eq!(
+		read_language_file("assets/othermod/lang/en_gb.json"),
+		r#"{ "a": "A" }"#
+	);
+}
@@ -7,51 +7,149 @@
 #![feature(generic_const_exprs)]
 #![cfg_attr(windows, feature(windows_by_handle))]
 
+use ahash::{AHashMap, AHashSet};
+use bytes::Bytes;
 use itertools::Itertools;
 use std::borrow::Cow;
 use std::convert::Infallible;
+use std::io;
 use std::io::ErrorKind;
+use std::iter;
 use std::panic;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, LazyLock};
-use std::{io, time::SystemTime};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 use enumset::EnumSet;
 use futures::StreamExt;
-use futures::future;
+use futures::{future, stream};
 use thiserror::Error;
+use tokio::io::AsyncReadExt;
 use tokio::io::AsyncSeek;
 use tokio::io::BufReader;
 use tokio::sync::Semaphore;
-use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::{Sender, channel};
+use tokio::task;
 use tokio::{fs::File, io::AsyncRead, runtime::Builder};
 
+use animated_texture_conversion::AnimatedTextureConvertingVfs;
+use animation_frame_deduplication::AnimationFrameDeduplicatingVfs;
+use asset_reference_graph::find_unreferenced_assets;
+use atlas_source_pruning::AtlasSourcePruningVfs;
+use audio_diagnostics_report::AudioDiagnosticsReport;
+use bedrock_conversion::BedrockConvertingVfs;
 use config::ProcessedSquashOptions;
+#[cfg(feature = "optifine")]
+use connected_texture_tiles::ConnectedTextureTilesVfs;
+#[cfg(feature = "optifine")]
+use custom_entity_model_validation::validate_custom_entity_models;
+use data_pack_reference_validation::validate_data_pack_references;
+#[cfg(feature = "optifine")]
+use emissive_texture_padding::EmissiveTexturePaddingVfs;
+use font_atlas_packing::FontAtlasPackingVfs;
+use font_provider_validation::validate_font_providers;
+use font_subsetting::FontSubsettingVfs;
+use item_model_definition_validation::validate_item_model_definitions;
+use json_schema_validation::validate_json_schemas;
+use language_file_conversion::LanguageFileConvertingVfs;
+use language_file_merging::LanguageFileMergingVfs;
+use pack_format_stamping::PackFormatStampingVfs;
+use pack_merging::{PackMergeConflict, PackMergingVfs};
 use pack_meta::{PackMeta, PackMetaError};
+use placeholder_expansion::PlaceholderExpandingVfs;
+use post_effect_validation::validate_post_effects;
+use run_manifest::{FileDigest, FileDigester, RunManifest};
+use shader_cache_bundling::ShaderCacheBundlingVfs;
+use shader_import_inlining::ShaderImportInliningVfs;
+use shader_program_validation::validate_shader_programs;
+use shader_variant_factoring::ShaderVariantFactoringVfs;
+use sound_concatenation::SoundConcatenatingVfs;
+use sound_deduplication::{SoundDeduplicatingVfs, sound_file_extension};
+use sound_reference_validation::validate_sound_references;
+use split_output::split_outputs;
 use squash_zip::{SquashZip, SquashZipError};
+use text_replacement::TextReplacingVfs;
+use texture_optimization_report::{TextureOptimizationReport, TextureOptimizationReportFormat};
+use vanilla_texture_matching::find_vanilla_identical_textures;
 
-#[cfg(feature = "optifine")]
-use crate::config::PropertiesFileOptions;
 use crate::config::{
-	AudioFileOptions, CommandFunctionFileOptions, CompressedCompoundNbtTagFileOptions, FileOptions,
-	JsonFileOptions, LegacyLanguageFileOptions, PngFileOptions, ShaderFileOptions, SquashOptions
+	AudioFileOptions, CommandFunctionFileOptions, CompressedCompoundNbtTagFileOptions,
+	CustomFileFormat, CustomFileOptions, DuplicateSoundHandling, DuplicateTranslationHandling,
+	FileOptions, JsonFileOptions, JsonSchemaViolationHandling, LegacyLanguageFileOptions,
+	ModResourcesProfile, PackMergeConflictHandling, PngFileOptions, ShaderFileOptions, SquashOptions,
+	StereoPositionalSoundHandling, UnmatchedAtlasSourceHandling, UnreferencedAssetHandling,
+	WarningCategory, WarningHandling, ZippedUnifontHexFileOptions
+};
+#[cfg(feature = "optifine")]
+use crate::config::{MinecraftMod, MismatchedEmissiveTextureHandling, PropertiesFileOptions};
+pub use crate::output_path_templating::{
+	OutputFilePathTemplateError, expand_output_file_path, output_file_path_variables
 };
-use crate::pack_file::PackFileProcessData;
-use crate::pack_file::asset_type::{
-	PackFileAssetTypeMatcher, PackFileAssetTypeMatches, tweak_asset_types_mask_from_global_options
+pub use crate::pack_diff::{PackDiff, PackDiffError, PackFileChange, PackFileDiff, diff_packs};
+use crate::pack_file::asset_type::{PackFileAssetTypeMatcher, PackFileAssetTypeMatches};
+use crate::pack_file::{
+	AnimationFrameSize, OptimizationError, PackFileProcessData, analyze_audio, is_ogg_extension,
+	output_channel_count
 };
-use crate::squash_zip::PreviousZipParseError;
+use crate::pack_file_filtering::pack_file_exclusion_reason;
+pub use crate::pack_file_planning::PlannedPackFile;
 pub use crate::squash_zip::relative_path::RelativePath;
-use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem};
+use crate::squash_zip::{FileListingCircumstances, PreviousZipParseError};
+use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem, read_file};
 
 pub mod config;
 pub mod vfs;
 
+mod animated_texture_conversion;
+mod animation_frame_deduplication;
+mod asset_reference_graph;
+mod atlas_source_pruning;
+mod audio_diagnostics_report;
+mod bedrock_conversion;
 mod buffered_async_spooled_temp_file;
+#[cfg(feature = "optifine")]
+mod connected_texture_tiles;
+#[cfg(feature = "optifine")]
+mod custom_entity_model_validation;
+mod data_pack_reference_validation;
+#[cfg(feature = "optifine")]
+mod emissive_texture_padding;
+mod file_options_matching;
+mod font_atlas_packing;
+mod font_provider_validation;
+mod font_subsetting;
+mod item_model_definition_validation;
+mod json_schema_validation;
+mod language_file_conversion;
+mod language_file_merging;
+mod nested_archive;
+mod output_path_templating;
+mod pack_diff;
 mod pack_file;
+mod pack_file_filtering;
+mod pack_file_planning;
+mod pack_format_stamping;
+mod pack_merging;
 mod pack_meta;
+mod placeholder_expansion;
+mod post_effect_validation;
+mod run_manifest;
+mod shader_cache_bundling;
+mod shader_import_inlining;
+mod shader_program_validation;
+mod shader_variant_factoring;
+mod sound_concatenation;
+mod sound_deduplication;
+mod sound_reference_validation;
+mod split_output;
 mod squash_zip;
+mod text_replacement;
+mod texture_optimization_report;
+mod vanilla_texture_matching;
+mod world_template;
 mod zopfli_iterations_time_model;
 
 /// A struct that represents a resource or data pack optimization operation with configuration
@@ -98,8 +196,101 @@ impl PackSquasher {
 	where
 		PackSquasherError: From<<O as TryInto<ProcessedSquashOptions>>::Error>
 	{
-		let mut options_holder = squash_options.try_into()?;
+		let options_holder = squash_options.try_into()?;
+
+		let run_manifest_file_path = options_holder
+			.options
+			.global_options
+			.run_manifest_file_path
+			.clone();
+		let warning_handling = options_holder.options.global_options.warnings.clone();
+		if run_manifest_file_path.is_none()
+			&& warning_handling
+				.values()
+				.all(|handling| *handling == WarningHandling::Warn)
+		{
+			return self.squash(vfs, options_holder, pack_file_status_sender, None);
+		}
+		let output_file_path = options_holder
+			.options
+			.global_options
+			.output_file_path
+			.clone();
+
+		// Run manifests are partly collected from status updates, and warnings are handled
+		// according to their category, so intercept status updates, forwarding them to the
+		// client code as appropriate. Do that in a thread, because any status updates pending
+		// in the squash operation runtime are dropped with it
+		let run_manifest = run_manifest_file_path
+			.is_some()
+			.then(|| Arc::new(RunManifest::default()));
+		let (status_sender, mut status_receiver) = channel(64);
+		let status_recorder = thread::spawn({
+			let run_manifest = run_manifest.clone();
+
+			move || {
+				let mut denied_warning_count = 0;
+
+				while let Some(status) = status_receiver.blocking_recv() {
+					if let PackSquasherStatus::Warning(warning) = &status {
+						match warning_handling
+							.get(&warning.category())
+							.copied()
+							.unwrap_or_default()
+						{
+							WarningHandling::Allow => continue,
+							WarningHandling::Warn => {}
+							WarningHandling::Deny => denied_warning_count += 1
+						}
+					}
+
+					if let Some(run_manifest) = &run_manifest {
+						run_manifest.record_status(&status);
+					}
+
+					if let Some(pack_file_status_sender) = &pack_file_status_sender {
+						pack_file_status_sender.blocking_send(status).ok();
+					}
+				}
+
+				denied_warning_count
+			}
+		});
+
+		let squash_result = self.squash(
+			vfs,
+			options_holder,
+			Some(status_sender),
+			run_manifest.clone()
+		);
+		let denied_warning_count = status_recorder.join().unwrap_or_default();
+		squash_result?;
+
+		if let (Some(run_manifest), Some(run_manifest_file_path)) =
+			(run_manifest, run_manifest_file_path)
+		{
+			match Arc::try_unwrap(run_manifest) {
+				Ok(run_manifest) => run_manifest.write(&run_manifest_file_path, &output_file_path)?,
+				Err(_) => panic!("Unexpected number of strong references to the run manifest")
+			}
+		}
+
+		if denied_warning_count > 0 {
+			return Err(PackSquasherError::DeniedWarnings(denied_warning_count));
+		}
+
+		Ok(())
+	}
 
+	/// Executes a squash operation like [`Self::run`] does, with already processed options,
+	/// recording the digests of the processed pack files in a run manifest, if provided.
+	fn squash(
+		&self,
+		vfs: impl VirtualFileSystem + 'static,
+		mut options_holder: ProcessedSquashOptions,
+		pack_file_status_sender: Option<Sender<PackSquasherStatus>>,
+		run_manifest: Option<Arc<RunManifest>>
+	) -> Result<(), PackSquasherError> {
 		// When reading from a pack directory that is not a directory, no files will be
 		// processed. Avoid useless computation and help the user out by bailing out early
 		// with a descriptive error message in that case.
@@ -107,12 +298,19 @@ impl PackSquasher {
 		// Note that program correctness cannot depend on these conditions staying true
 		// during its execution. These checks are just meant to handle usage mistakes
 		// promptly
-		if !vfs
-			.file_type(&options_holder.options.pack_directory)
-			.map_or_else(|_| true, |file_type| file_type.is_dir())
-		{
+		if !iter::once(&options_holder.options.pack_directory)
+			.chain(
+				&options_holder
+					.options
+					.global_options
+					.merged_pack_directories
+			)
+			.all(|pack_directory| {
+				vfs.file_type(pack_directory)
+					.map_or_else(|_| true, |file_type| file_type.is_dir())
+			}) {
 			return Err(PackSquasherError::InvalidFileType(
-				"The pack directory path must refer to a directory, not a file"
+				"The pack directory and merged pack directory paths must refer to directories, not files"
 			));
 		}
 
@@ -147,6 +345,115 @@ impl PackSquasher {
 			.build()
 			.unwrap();
 
+		// Merge the other packs before anything else reads pack files, so that every other step
+		// sees the files of every pack
+		let mut vfs = PackMergingVfs::new(vfs);
+		let pack_merge_conflicts = vfs.merge_packs(
+			&options_holder.options.pack_directory,
+			&options_holder
+				.options
+				.global_options
+				.merged_pack_directories,
+			IteratorTraversalOptions {
+				ignore_system_and_hidden_files: options_holder
+					.options
+					.global_options
+					.ignore_system_and_hidden_files
+			},
+			options_holder
+				.options
+				.global_options
+				.pack_merge_conflict_handling
+		);
+		let pack_merge_conflict_count = pack_merge_conflicts.len();
+
+		if let Some(pack_file_status_sender) = &pack_file_status_sender {
+			runtime.block_on(async {
+				for PackMergeConflict {
+					relative_path,
+					kept_file_path,
+					discarded_file_paths
+				} in pack_merge_conflicts
+				{
+					pack_file_status_sender
+						.send(PackSquasherStatus::Warning(
+							PackSquasherWarning::PackMergeConflict(
+								relative_path,
+								kept_file_path,
+								discarded_file_paths
+							)
+						))
+						.await
+						.ok();
+				}
+			});
+		}
+
+		if options_holder
+			.options
+			.global_options
+			.pack_merge_conflict_handling
+			== PackMergeConflictHandling::Error
+			&& pack_merge_conflict_count > 0
+		{
+			return Err(PackSquasherError::PackMergeConflicts(
+				pack_merge_conflict_count
+			));
+		}
+
+		// Expand placeholders before anything else reads pack files, as any of them, including
+		// the pack metadata file, may contain placeholders
+		let mut vfs = PlaceholderExpandingVfs::new(vfs);
+		runtime.block_on(
+			vfs.expand_placeholders(
+				&options_holder.options.pack_directory,
+				IteratorTraversalOptions {
+					ignore_system_and_hidden_files: options_holder
+						.options
+						.global_options
+						.ignore_system_and_hidden_files
+				},
+				&options_holder.options.global_options.template_variables
+			)
+		);
+
+		// Replace text right after expanding placeholders, so that the rules can also act
+		// on expanded values, and everything else sees the replaced text
+		let mut vfs = TextReplacingVfs::new(vfs);
+		runtime.block_on(
+			vfs.replace_text(
+				&options_holder.options.pack_directory,
+				IteratorTraversalOptions {
+					ignore_system_and_hidden_files: options_holder
+						.options
+						.global_options
+						.ignore_system_and_hidden_files
+				},
+				&options_holder.text_replacement_globs,
+				&options_holder.options.global_options.text_replacement_rules
+			)
+		);
+
+		// Stamp the pack metadata file before anything else reads it, so that the rest of the
+		// squash operation sees the pack format versions of the targeted Minecraft versions
+		let mut vfs = PackFormatStampingVfs::new(vfs);
+		if let Some(target_minecraft_version_range) = options_holder
+			.options
+			.global_options
+			.target_minecraft_version_range
+		{
+			runtime.block_on(
+				vfs.stamp_pack_format(
+					&options_holder.options.pack_directory,
+					target_minecraft_version_range,
+					&options_holder
+						.options
+						.global_options
+						.overlay_minecraft_version_ranges
+				)
+			);
+		}
+
 		let automatic_quirk_detection = options_holder
 			.options
 			.global_options
@@ -157,73 +464,842 @@ impl PackSquasher {
 			.global_options
 			.automatic_asset_types_mask_detection;
 
-		let read_pack_meta = automatic_quirk_detection
-			|| automatic_asset_type_mask_detection
-			|| options_holder
-				.options
-				.global_options
-				.validate_pack_metadata_file;
+		let json_schema_violation_handling = options_holder
+			.options
+			.global_options
+			.json_schema_violation_handling;
+
+		let convert_language_files = options_holder.options.global_options.convert_language_files;
+
+		// World folders have no pack metadata file in their root folder
+		let read_pack_meta = !options_holder
+			.options
+			.global_options
+			.world_template_packaging
+			&& (automatic_quirk_detection
+				|| automatic_asset_type_mask_detection
+				|| json_schema_violation_handling != JsonSchemaViolationHandling::Ignore
+				|| convert_language_files
+				|| options_holder
+					.options
+					.global_options
+					.validate_pack_metadata_file
+				|| options_holder
+					.options
+					.global_options
+					.data_pack_reference_validation);
 
 		// By default, allow every known asset type to match pack files. This will be adjusted later
 		// depending on the options and automatic asset type mask detection, if enabled
 		let mut asset_types_mask = EnumSet::all();
 
+		// The pack format version is only known if the pack metadata is read
+		let mut pack_format_version = None;
+
 		// Transparently modify the options before doing the actual processing we want to read the
 		// pack metadata, either to validate it, use automatic quirk detection or detect an asset
 		// type mask
 		if read_pack_meta {
 			runtime.block_on(async {
-				let pack_meta = PackMeta::new(&vfs, &options_holder.options.pack_directory).await?;
+				let mod_resources_profile =
+					options_holder.options.global_options.mod_resources_profile;
+
+				let mut pack_meta = match PackMeta::new(&vfs, &options_holder.options.pack_directory)
+					.await
+				{
+					Ok(pack_meta) => pack_meta,
+					// The resources of mods do not need a pack metadata file for every
+					// mod loader
+					Err(PackMetaError::Io(err))
+						if mod_resources_profile.is_some() && err.kind() == ErrorKind::NotFound =>
+					{
+						return Ok(());
+					}
+					Err(err) => return Err(err.into())
+				};
+				if mod_resources_profile == Some(ModResourcesProfile::Forge) {
+					pack_meta.use_forge_resource_pack_format_version();
+				}
+				pack_format_version = Some(pack_meta.pack_format_version());
+
+				if automatic_quirk_detection {
+					let quirks = pack_meta.target_minecraft_versions_quirks();
+
+					options_holder
+						.options
+						.global_options
+						.work_around_minecraft_quirks = quirks;
+
+					if let Some(pack_file_status_sender) = &pack_file_status_sender
+						&& !quirks.is_empty()
+					{
+						let notice_message = format!(
+							"Working around automatically detected Minecraft quirks: {}",
+							quirks.iter().map(|quirk| quirk.as_str()).join(", ")
+						);
+
+						pack_file_status_sender
+							.send(PackSquasherStatus::Notice(Cow::Owned(notice_message)))
+							.await
+							.ok();
+					}
+				}
+
+				if automatic_asset_type_mask_detection {
+					asset_types_mask = pack_meta.target_minecraft_version_asset_type_mask();
+				}
+
+				Ok::<_, PackSquasherError>(())
+			})?;
+		}
+
+		let traversal_options = || IteratorTraversalOptions {
+			ignore_system_and_hidden_files: options_holder
+				.options
+				.global_options
+				.ignore_system_and_hidden_files
+		};
+
+		// Convert GIF and APNG textures before processing any pack file, because each of
+		// them generates both a texture and an animation metadata file
+		let mut vfs = AnimatedTextureConvertingVfs::new(vfs);
+		if options_holder
+			.options
+			.global_options
+			.convert_animated_textures
+		{
+			// Converted textures are PNG files, so the PNG file options that apply to them
+			// bound how big they can be
+			let maximum_width_and_height = |relative_path: &str| {
+				options_holder
+					.file_options_globs
+					.matches(relative_path)
+					.into_iter()
+					.find_map(|i| match &options_holder.options.file_options[i] {
+						FileOptions::PngFileOptions(png_file_options) => {
+							Some(png_file_options.maximum_width_and_height)
+						}
+						_ => None
+					})
+					.unwrap_or(PngFileOptions::default().maximum_width_and_height)
+			};
+
+			runtime.block_on(vfs.convert_animated_textures(
+				&options_holder.options.pack_directory,
+				traversal_options(),
+				maximum_width_and_height
+			));
+		}
+
+		// Deduplicate animated texture frames before processing any pack file, because this
+		// requires reading and rewriting texture and animation metadata files together
+		let mut vfs = AnimationFrameDeduplicatingVfs::new(vfs);
+		if options_holder
+			.options
+			.global_options
+			.deduplicate_animation_frames
+		{
+			runtime.block_on(
+				vfs.deduplicate_frames(&options_holder.options.pack_directory, traversal_options())
+			);
+		}
+
+		// Validate and renumber connected texture tiles before processing any pack file, because
+		// this requires reading CTM properties files and listing the tiles next to them together
+		#[cfg(feature = "optifine")]
+		let vfs = {
+			let mut vfs = ConnectedTextureTilesVfs::new(vfs);
+			if options_holder
+				.options
+				.global_options
+				.allow_mods
+				.contains(MinecraftMod::Optifine)
+			{
+				let missing_tiles = runtime.block_on(
+					vfs.process_connected_texture_tiles(
+						&options_holder.options.pack_directory,
+						traversal_options(),
+						options_holder
+							.options
+							.global_options
+							.renumber_connected_texture_tiles
+					)
+				);
+
+				if let Some(pack_file_status_sender) = &pack_file_status_sender {
+					runtime.block_on(async {
+						for (relative_path, tiles) in missing_tiles {
+							pack_file_status_sender
+								.send(PackSquasherStatus::Warning(
+									PackSquasherWarning::MissingConnectedTextureTiles(
+										relative_path,
+										tiles
+									)
+								))
+								.await
+								.ok();
+						}
+					});
+				}
+			}
+			vfs
+		};
+
+		// Inline shader imports before processing any pack file, because this requires reading
+		// every shader a shader imports together
+		let mut vfs = ShaderImportInliningVfs::new(vfs);
+		if options_holder.options.global_options.inline_shader_imports {
+			runtime.block_on(
+				vfs.inline_imports(&options_holder.options.pack_directory, traversal_options())
+			);
+		}
+
+		// Factor near-identical shaders after inlining their imports, so that the shaders
+		// of a family are compared with their complete source code
+		let mut vfs = ShaderVariantFactoringVfs::new(vfs);
+		if options_holder.options.global_options.factor_shader_variants {
+			let factored_families = runtime.block_on(
+				vfs.factor_variants(&options_holder.options.pack_directory, traversal_options())
+			);
+
+			if let Some(pack_file_status_sender) = &pack_file_status_sender {
+				runtime.block_on(async {
+					for (variant_paths, shared_shader_path) in factored_families {
+						let notice_message = format!(
+							"Factored the near-identical shaders {} into {shared_shader_path}",
+							variant_paths.iter().map(RelativePath::as_str).join(", ")
+						);
+
+						pack_file_status_sender
+							.send(PackSquasherStatus::Notice(Cow::Owned(notice_message)))
+							.await
+							.ok();
+					}
+				});
+			}
+		}
+
+		// Bundle precompiled shader artifacts before processing any pack file, so that they are
+		// processed like any other pack file
+		let mut vfs = ShaderCacheBundlingVfs::new(vfs);
+		let bundled_artifact_count = runtime.block_on(vfs.bundle_artifacts(
+			&options_holder.options.pack_directory,
+			traversal_options(),
+			&options_holder.options.global_options.shader_cache
+		));
+		if let Some(pack_file_status_sender) = &pack_file_status_sender
+			&& bundled_artifact_count > 0
+		{
+			runtime
+				.block_on(
+					pack_file_status_sender.send(PackSquasherStatus::Notice(Cow::Owned(format!(
+						"Bundling {bundled_artifact_count} precompiled shader artifacts"
+					))))
+				)
+				.ok();
+		}
+
+		// Trim font textures and pack them into atlases before processing any pack file, because
+		// this requires reading every font definition file and the textures they reference
+		// together. Trimmed textures are packed, so trimming goes first
+		let mut vfs = FontAtlasPackingVfs::new(vfs);
+		if options_holder.options.global_options.trim_font_glyph_sheets {
+			runtime.block_on(
+				vfs.trim_glyph_sheets(&options_holder.options.pack_directory, traversal_options())
+			);
+		}
+		if options_holder.options.global_options.pack_font_atlases {
+			runtime.block_on(
+				vfs.pack_font_atlases(&options_holder.options.pack_directory, traversal_options())
+			);
+		}
+
+		// Merge language file fragments and find duplicate translations before processing any
+		// pack file, because this requires reading every language file of a namespace together
+		let mut vfs = LanguageFileMergingVfs::new(vfs);
+		let merge_language_file_fragments = options_holder
+			.options
+			.global_options
+			.merge_language_file_fragments;
+		let duplicate_translation_handling = options_holder
+			.options
+			.global_options
+			.duplicate_translation_handling;
+		if merge_language_file_fragments
+			|| duplicate_translation_handling != DuplicateTranslationHandling::Ignore
+		{
+			let duplicate_translations = runtime.block_on(vfs.process_language_files(
+				&options_holder.options.pack_directory,
+				traversal_options(),
+				merge_language_file_fragments,
+				duplicate_translation_handling
+			));
+
+			if let Some(pack_file_status_sender) = &pack_file_status_sender {
+				runtime.block_on(async {
+					for (relative_path, duplicate_translation_count) in duplicate_translations {
+						pack_file_status_sender
+							.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::DuplicateTranslations(
+									relative_path,
+									duplicate_translation_count
+								)
+							))
+							.await
+							.ok();
+					}
+				});
+			}
+		}
+
+		// Convert language files to the format the targeted Minecraft versions read after merging
+		// language file fragments, so that the merged language files are converted too
+		let mut vfs = LanguageFileConvertingVfs::new(vfs);
+		if convert_language_files && let Some(pack_format_version) = pack_format_version {
+			runtime.block_on(vfs.convert_language_files(
+				&options_holder.options.pack_directory,
+				traversal_options(),
+				pack_format_version
+			));
+		}
+
+		// Subset fonts to the characters used by language files after they are merged and
+		// converted, so that the final language files are considered
+		let mut vfs = FontSubsettingVfs::new(vfs);
+		if options_holder.options.global_options.subset_fonts {
+			let subset_font_count = runtime.block_on(
+				vfs.subset_fonts(
+					&options_holder.options.pack_directory,
+					traversal_options(),
+					&options_holder
+						.options
+						.global_options
+						.font_subset_extra_characters
+				)
+			);
+
+			if let Some(pack_file_status_sender) = &pack_file_status_sender
+				&& subset_font_count > 0
+			{
+				runtime
+					.block_on(
+						pack_file_status_sender.send(PackSquasherStatus::Notice(Cow::Owned(
+							format!(
+								"Subset {subset_font_count} fonts to the characters used by the pack"
+							)
+						)))
+					)
+					.ok();
+			}
+		}
+
+		// Check the sources of atlas configuration files before processing any pack file, because
+		// this requires knowing every texture of the pack
+		let mut vfs = AtlasSourcePruningVfs::new(vfs);
+		let unmatched_atlas_source_handling = options_holder
+			.options
+			.global_options
+			.unmatched_atlas_source_handling;
+		if unmatched_atlas_source_handling != UnmatchedAtlasSourceHandling::Ignore {
+			let atlas_source_mismatches = runtime.block_on(vfs.process_atlases(
+				&options_holder.options.pack_directory,
+				traversal_options(),
+				unmatched_atlas_source_handling
+			));
+
+			if let Some(pack_file_status_sender) = &pack_file_status_sender {
+				runtime.block_on(async {
+					for (relative_path, mismatch) in atlas_source_mismatches {
+						pack_file_status_sender
+							.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::AtlasSourceMismatch(relative_path, mismatch)
+							))
+							.await
+							.ok();
+					}
+				});
+			}
+		}
+
+		// Check the dimensions of emissive textures before processing any pack file too, because
+		// this requires decoding them together with their base texture
+		#[cfg(feature = "optifine")]
+		let vfs = {
+			let mut vfs = EmissiveTexturePaddingVfs::new(vfs);
+			let mismatched_emissive_texture_handling = options_holder
+				.options
+				.global_options
+				.mismatched_emissive_texture_handling;
+			if mismatched_emissive_texture_handling != MismatchedEmissiveTextureHandling::Ignore {
+				let emissive_texture_mismatches = runtime.block_on(vfs.process_emissive_textures(
+					&options_holder.options.pack_directory,
+					traversal_options(),
+					mismatched_emissive_texture_handling
+				));
+
+				if let Some(pack_file_status_sender) = &pack_file_status_sender {
+					runtime.block_on(async {
+						for (relative_path, mismatch) in emissive_texture_mismatches {
+							pack_file_status_sender
+								.send(PackSquasherStatus::Warning(
+									PackSquasherWarning::EmissiveTextureMismatch(
+										relative_path,
+										mismatch
+									)
+								))
+								.await
+								.ok();
+						}
+					});
+				}
+			}
+			vfs
+		};
+
+		// Concatenate multi-part sounds before processing any pack file, because this requires
+		// decoding every part together. Do it before finding duplicate sounds, so that the
+		// concatenated sounds are considered too
+		let mut vfs = SoundConcatenatingVfs::new(vfs);
+		if options_holder
+			.options
+			.global_options
+			.concatenate_sound_parts
+		{
+			runtime.block_on(
+				vfs.concatenate_sounds(&options_holder.options.pack_directory, traversal_options())
+			);
+		}
+
+		// Find duplicate sounds before processing any pack file, because this requires decoding
+		// every sound of the pack and comparing them together
+		let mut vfs = SoundDeduplicatingVfs::new(vfs);
+		let duplicate_sound_handling = options_holder
+			.options
+			.global_options
+			.duplicate_sound_handling;
+		if duplicate_sound_handling != DuplicateSoundHandling::Ignore {
+			let duplicate_sounds = runtime.block_on(vfs.find_duplicate_sounds(
+				&options_holder.options.pack_directory,
+				traversal_options(),
+				duplicate_sound_handling == DuplicateSoundHandling::Deduplicate
+			));
+
+			if let Some(pack_file_status_sender) = &pack_file_status_sender {
+				runtime.block_on(async {
+					for relative_paths in duplicate_sounds {
+						pack_file_status_sender
+							.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::DuplicateSounds(relative_paths)
+							))
+							.await
+							.ok();
+					}
+				});
+			}
+		}
+
+		// Cross-validate sounds.json files against the sound files of the pack after duplicate
+		// sounds are found, as both are about sounds. Unreferenced sounds are just reported unless
+		// the user asked to exclude them
+		let unreferenced_sounds = Arc::new(
+			if options_holder
+				.options
+				.global_options
+				.sound_reference_validation
+			{
+				let sound_references = runtime.block_on(validate_sound_references(
+					&vfs,
+					&options_holder.options.pack_directory,
+					traversal_options()
+				));
+				let exclude_unreferenced_sounds = options_holder
+					.options
+					.global_options
+					.exclude_unreferenced_sounds;
+
+				if let Some(pack_file_status_sender) = &pack_file_status_sender {
+					runtime.block_on(async {
+						for (relative_path, sound_name) in sound_references.missing_sounds {
+							pack_file_status_sender
+								.send(PackSquasherStatus::Warning(
+									PackSquasherWarning::MissingSound(relative_path, sound_name)
+								))
+								.await
+								.ok();
+						}
+
+						if !exclude_unreferenced_sounds {
+							for relative_path in &sound_references.unreferenced_sounds {
+								pack_file_status_sender
+									.send(PackSquasherStatus::Warning(
+										PackSquasherWarning::UnreferencedSound(relative_path.clone())
+									))
+									.await
+									.ok();
+							}
+						}
+					});
+				}
+
+				if exclude_unreferenced_sounds {
+					sound_references.unreferenced_sounds
+				} else {
+					AHashSet::new()
+				}
+			} else {
+				AHashSet::new()
+			}
+		);
+
+		// Cross-validate shader program JSON definitions against the shaders they reference
+		// before processing any pack file, because this requires reading them together
+		if options_holder
+			.options
+			.global_options
+			.shader_program_validation
+		{
+			let shader_program_mismatches = runtime.block_on(validate_shader_programs(
+				&vfs,
+				&options_holder.options.pack_directory,
+				traversal_options()
+			));
+
+			if let Some(pack_file_status_sender) = &pack_file_status_sender {
+				runtime.block_on(async {
+					for (relative_path, mismatch) in shader_program_mismatches {
+						pack_file_status_sender
+							.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::ShaderProgramMismatch(relative_path, mismatch)
+							))
+							.await
+							.ok();
+					}
+				});
+			}
+		}
+
+		// Validate post-processing effect pipelines before processing any pack file, because
+		// this requires reading them together with the programs they run
+		if options_holder.options.global_options.post_effect_validation {
+			let post_effect_mismatches = runtime.block_on(validate_post_effects(
+				&vfs,
+				&options_holder.options.pack_directory,
+				traversal_options()
+			));
+
+			if let Some(pack_file_status_sender) = &pack_file_status_sender {
+				runtime.block_on(async {
+					for (relative_path, mismatch) in post_effect_mismatches {
+						pack_file_status_sender
+							.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::PostEffectMismatch(relative_path, mismatch)
+							))
+							.await
+							.ok();
+					}
+				});
+			}
+		}
+
+		// Validate font providers before processing any pack file, because this requires reading
+		// font definition files together with the assets they reference
+		if options_holder
+			.options
+			.global_options
+			.font_provider_validation
+		{
+			let font_provider_mismatches = runtime.block_on(validate_font_providers(
+				&vfs,
+				&options_holder.options.pack_directory,
+				traversal_options()
+			));
+
+			if let Some(pack_file_status_sender) = &pack_file_status_sender {
+				runtime.block_on(async {
+					for (relative_path, mismatch) in font_provider_mismatches {
+						pack_file_status_sender
+							.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::FontProviderMismatch(relative_path, mismatch)
+							))
+							.await
+							.ok();
+					}
+				});
+			}
+		}
+
+		// Validate item model definitions likewise, as they reference models and textures
+		if options_holder
+			.options
+			.global_options
+			.item_model_definition_validation
+		{
+			let item_model_definition_mismatches = runtime.block_on(validate_item_model_definitions(
+				&vfs,
+				&options_holder.options.pack_directory,
+				traversal_options()
+			));
+
+			if let Some(pack_file_status_sender) = &pack_file_status_sender {
+				runtime.block_on(async {
+					for (relative_path, mismatch) in item_model_definition_mismatches {
+						pack_file_status_sender
+							.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::ItemModelDefinitionMismatch(
+									relative_path,
+									mismatch
+								)
+							))
+							.await
+							.ok();
+					}
+				});
+			}
+		}
+
+		// Validate the references of OptiFine custom entity models too, which OptiFine support
+		// being enabled is enough for, like for connected texture tiles
+		#[cfg(feature = "optifine")]
+		if options_holder
+			.options
+			.global_options
+			.allow_mods
+			.contains(MinecraftMod::Optifine)
+		{
+			let custom_entity_model_mismatches = runtime.block_on(validate_custom_entity_models(
+				&vfs,
+				&options_holder.options.pack_directory,
+				traversal_options()
+			));
+
+			if let Some(pack_file_status_sender) = &pack_file_status_sender {
+				runtime.block_on(async {
+					for (relative_path, mismatch) in custom_entity_model_mismatches {
+						pack_file_status_sender
+							.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::CustomEntityModelMismatch(
+									relative_path,
+									mismatch
+								)
+							))
+							.await
+							.ok();
+					}
+				});
+			}
+		}
+
+		// Validate the references of data pack tags, whose expected folders depend on the pack
+		// format version
+		if options_holder
+			.options
+			.global_options
+			.data_pack_reference_validation
+			&& let Some(pack_format_version) = pack_format_version
+		{
+			let data_pack_reference_mismatches = runtime.block_on(validate_data_pack_references(
+				&vfs,
+				&options_holder.options.pack_directory,
+				traversal_options(),
+				pack_format_version
+			));
+
+			if let Some(pack_file_status_sender) = &pack_file_status_sender {
+				runtime.block_on(async {
+					for (relative_path, mismatch) in data_pack_reference_mismatches {
+						pack_file_status_sender
+							.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::DataPackReferenceMismatch(
+									relative_path,
+									mismatch
+								)
+							))
+							.await
+							.ok();
+					}
+				});
+			}
+		}
 
-				if automatic_quirk_detection {
-					let quirks = pack_meta.target_minecraft_versions_quirks();
+		// Follow the references between assets after sounds.json files are validated, so that
+		// unreferenced sounds already reported by that validation are not reported again
+		let unreferenced_asset_handling = options_holder
+			.options
+			.global_options
+			.unreferenced_asset_handling;
+		let unreferenced_assets = Arc::new(
+			if unreferenced_asset_handling != UnreferencedAssetHandling::Ignore {
+				let unreferenced_assets = runtime.block_on(find_unreferenced_assets(
+					&vfs,
+					&options_holder.options.pack_directory,
+					traversal_options(),
+					&options_holder.unreferenced_asset_exemption_globs
+				));
+
+				if unreferenced_asset_handling == UnreferencedAssetHandling::Exclude {
+					unreferenced_assets
+				} else {
+					if let Some(pack_file_status_sender) = &pack_file_status_sender {
+						let sound_references_validated = options_holder
+							.options
+							.global_options
+							.sound_reference_validation;
+
+						runtime.block_on(async {
+							for relative_path in unreferenced_assets
+								.into_iter()
+								.sorted_unstable_by(|a, b| a.as_str().cmp(b.as_str()))
+							{
+								if sound_references_validated
+									&& sound_file_extension(relative_path.as_str()).is_some()
+								{
+									continue;
+								}
+
+								pack_file_status_sender
+									.send(PackSquasherStatus::Warning(
+										PackSquasherWarning::UnreferencedAsset(relative_path)
+									))
+									.await
+									.ok();
+							}
+						});
+					}
 
-					options_holder
-						.options
-						.global_options
-						.work_around_minecraft_quirks = quirks;
+					AHashSet::new()
+				}
+			} else {
+				AHashSet::new()
+			}
+		);
 
-					if let Some(pack_file_status_sender) = &pack_file_status_sender
-						&& !quirks.is_empty()
-					{
-						let notice_message = format!(
-							"Working around automatically detected Minecraft quirks: {}",
-							quirks.iter().map(|quirk| quirk.as_str()).join(", ")
-						);
+		// Validate JSON files against the schemas of the targeted Minecraft versions before
+		// processing any pack file, so that the squash operation can fail early if requested
+		if json_schema_violation_handling != JsonSchemaViolationHandling::Ignore
+			&& let Some(pack_format_version) = pack_format_version
+		{
+			let schema_violations = runtime.block_on(validate_json_schemas(
+				&vfs,
+				&options_holder.options.pack_directory,
+				traversal_options(),
+				pack_format_version,
+				options_holder
+					.options
+					.global_options
+					.mod_resources_profile
+					.map_or(&[], ModResourcesProfile::json_key_prefixes)
+			));
+			let schema_violation_count = schema_violations.len();
 
+			if let Some(pack_file_status_sender) = &pack_file_status_sender {
+				runtime.block_on(async {
+					for (relative_path, violation) in schema_violations {
 						pack_file_status_sender
-							.send(PackSquasherStatus::Notice(Cow::Owned(notice_message)))
+							.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::JsonSchemaViolation(relative_path, violation)
+							))
 							.await
 							.ok();
 					}
-				}
+				});
+			}
 
-				if automatic_asset_type_mask_detection {
-					asset_types_mask = pack_meta.target_minecraft_version_asset_type_mask();
+			if json_schema_violation_handling == JsonSchemaViolationHandling::Error
+				&& schema_violation_count > 0
+			{
+				return Err(PackSquasherError::JsonSchemaViolations(
+					schema_violation_count
+				));
+			}
+		}
+
+		// Compare textures against vanilla ones after every other preprocessing step, so that the
+		// textures that will actually be processed are compared. Identical textures are just
+		// reported unless the user asked to exclude them
+		let vanilla_identical_textures = Arc::new(
+			match &options_holder
+				.options
+				.global_options
+				.vanilla_client_jar_path
+			{
+				Some(client_jar_path) => {
+					let vanilla_identical_textures =
+						runtime.block_on(find_vanilla_identical_textures(
+							&vfs,
+							&options_holder.options.pack_directory,
+							traversal_options(),
+							client_jar_path
+						))?;
+
+					if options_holder
+						.options
+						.global_options
+						.exclude_vanilla_identical_textures
+					{
+						vanilla_identical_textures
+					} else {
+						if let Some(pack_file_status_sender) = &pack_file_status_sender {
+							runtime.block_on(async {
+								for relative_path in vanilla_identical_textures.into_keys() {
+									pack_file_status_sender
+										.send(PackSquasherStatus::Warning(
+											PackSquasherWarning::VanillaIdenticalTexture(
+												relative_path
+											)
+										))
+										.await
+										.ok();
+								}
+							});
+						}
+
+						AHashMap::new()
+					}
 				}
+				None => AHashMap::new()
+			}
+		);
 
-				Ok::<_, PackSquasherError>(())
-			})?;
+		// Convert the pack metadata and sounds.json files to their Bedrock Edition equivalents
+		// last, because every other preprocessing step expects Java Edition files
+		let mut vfs = BedrockConvertingVfs::new(vfs);
+		if options_holder.options.global_options.bedrock_edition_target {
+			runtime.block_on(vfs.convert_pack(&options_holder.options.pack_directory));
 		}
 
 		let vfs = Arc::new(vfs);
-		let asset_type_matcher = Arc::new(PackFileAssetTypeMatcher::new(
-			tweak_asset_types_mask_from_global_options(
-				asset_types_mask,
-				&options_holder.options.global_options
-			)
+		let asset_type_matcher = Arc::new(PackFileAssetTypeMatcher::for_options(
+			asset_types_mask,
+			&options_holder
 		));
 		let options_holder = Arc::new(options_holder);
 
 		runtime.block_on(async {
+			let pack_file_traversal_options = IteratorTraversalOptions {
+				ignore_system_and_hidden_files: options_holder
+					.options
+					.global_options
+					.ignore_system_and_hidden_files
+			};
+
+			// Count the pack files beforehand, so that the progress of the operation can be
+			// shown. Walking the pack directory is cheap compared to processing its files
+			if let Some(tx) = &pack_file_status_sender {
+				let pack_file_count = vfs
+					.file_iterator(
+						&options_holder.options.pack_directory,
+						pack_file_traversal_options
+					)
+					.count();
+
+				tx.send(PackSquasherStatus::PackFilesFound(pack_file_count as u64))
+					.await
+					.ok();
+			}
+
 			let pack_file_iter = vfs.file_iterator(
 				&options_holder.options.pack_directory,
-				IteratorTraversalOptions {
-					ignore_system_and_hidden_files: options_holder
-						.options
-						.global_options
-						.ignore_system_and_hidden_files
-				}
+				pack_file_traversal_options
 			);
 
 			let squashzip_settings = options_holder
@@ -267,6 +1343,27 @@ impl PackSquasher {
 				}
 			);
 
+			let texture_optimization_report = Arc::new(
+				options_holder
+					.options
+					.global_options
+					.texture_optimization_report_file_path
+					.as_deref()
+					.map(|report_file_path| {
+						TextureOptimizationReport::new(
+							TextureOptimizationReportFormat::for_file_path(report_file_path)
+						)
+					})
+			);
+			let audio_diagnostics_report = Arc::new(
+				options_holder
+					.options
+					.global_options
+					.audio_diagnostics_report_file_path
+					.as_ref()
+					.map(|_| AudioDiagnosticsReport::default())
+			);
+
 			let mut pack_file_tasks = Vec::with_capacity(squash_zip.previous_file_count());
 
 			// Instantiate a semaphore that will help us limit the number of in-flight tasks.
@@ -354,6 +1451,12 @@ impl PackSquasher {
 				let asset_type_matcher = Arc::clone(&asset_type_matcher);
 				let squash_zip = Arc::clone(&squash_zip);
 				let vfs = Arc::clone(&vfs);
+				let vanilla_identical_textures = Arc::clone(&vanilla_identical_textures);
+				let unreferenced_sounds = Arc::clone(&unreferenced_sounds);
+				let unreferenced_assets = Arc::clone(&unreferenced_assets);
+				let texture_optimization_report = Arc::clone(&texture_optimization_report);
+				let audio_diagnostics_report = Arc::clone(&audio_diagnostics_report);
+				let run_manifest = run_manifest.clone();
 
 				let in_flight_tasks_semaphore = Arc::clone(&in_flight_tasks_semaphore);
 				let pack_file_optimization_failed = Arc::clone(&pack_file_optimization_failed);
@@ -373,7 +1476,10 @@ impl PackSquasher {
 									path: RelativePath::from_inner(Cow::Borrowed("-")),
 									optimization_strategy: Cow::Borrowed("Pack directory scan error"),
 									optimization_error: Some(err.to_string()),
-									skipped: false
+									skipped: false,
+									processing_time: None,
+									input_size: None,
+									output_size: None
 								}))
 								.await
 								.ok();
@@ -384,10 +1490,130 @@ impl PackSquasher {
 						}
 					};
 
+					// Textures identical to vanilla ones are only in this map if they should be
+					// excluded from the output ZIP file
+					if let Some(texture_match) =
+						vanilla_identical_textures.get(&pack_file_data.relative_path)
+					{
+						if let Some(tx) = pack_file_status_sender {
+							tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
+								path: pack_file_data.relative_path,
+								optimization_strategy: Cow::Owned(format!(
+									"Skipped: {}",
+									texture_match.description()
+								)),
+								optimization_error: None,
+								skipped: true,
+								processing_time: None,
+								input_size: None,
+								output_size: None
+							}))
+							.await
+							.ok();
+						}
+
+						return;
+					}
+
+					// Likewise, unreferenced sounds are only in this set if they should be excluded
+					if unreferenced_sounds.contains(&pack_file_data.relative_path) {
+						if let Some(tx) = pack_file_status_sender {
+							tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
+								path: pack_file_data.relative_path,
+								optimization_strategy: Cow::Borrowed(
+									"Skipped: not referenced by any sounds.json file"
+								),
+								optimization_error: None,
+								skipped: true,
+								processing_time: None,
+								input_size: None,
+								output_size: None
+							}))
+							.await
+							.ok();
+						}
+
+						return;
+					}
+
+					// Unreferenced assets are only in this set if they should be excluded, too
+					if unreferenced_assets.contains(&pack_file_data.relative_path) {
+						if let Some(tx) = pack_file_status_sender {
+							tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
+								path: pack_file_data.relative_path,
+								optimization_strategy: Cow::Borrowed(
+									"Skipped: not referenced by any other pack file"
+								),
+								optimization_error: None,
+								skipped: true,
+								processing_time: None,
+								input_size: None,
+								output_size: None
+							}))
+							.await
+							.ok();
+						}
+
+						return;
+					}
+
+					// Files excluded by pack file filters are skipped before anything else is done with them
+					match pack_file_exclusion_reason(&pack_file_data, &options_holder, &*vfs) {
+						Ok(None) => {}
+						Ok(Some(exclusion_reason)) => {
+							if let Some(tx) = pack_file_status_sender {
+								tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
+									path: pack_file_data.relative_path,
+									optimization_strategy: Cow::Owned(format!(
+										"Skipped: {exclusion_reason}"
+									)),
+									optimization_error: None,
+									skipped: true,
+									processing_time: None,
+									input_size: None,
+									output_size: None
+								}))
+								.await
+								.ok();
+							}
+
+							return;
+						}
+						Err(err) => {
+							if let Some(tx) = pack_file_status_sender {
+								tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
+									path: pack_file_data.relative_path,
+									optimization_strategy: Cow::Borrowed("Error opening pack file"),
+									optimization_error: Some(err.to_string()),
+									skipped: false,
+									processing_time: None,
+									input_size: None,
+									output_size: None
+								}))
+								.await
+								.ok();
+							}
+
+							pack_file_optimization_failed.store(true, Ordering::Release);
+							return;
+						}
+					}
+
 					let have_default_options;
 					let asset_type_matches = {
-						let asset_type_matches =
-							asset_type_matcher.matches_for(&pack_file_data.relative_path);
+						// The files of data packs in world folders are identified by their path
+						// within the data pack
+						let asset_type_matches = if options_holder
+							.options
+							.global_options
+							.world_template_packaging
+						{
+							asset_type_matcher.matches_for(&world_template::pack_relative_path(
+								&pack_file_data.relative_path
+							))
+						} else {
+							asset_type_matcher.matches_for(&pack_file_data.relative_path)
+						};
 
 						if !asset_type_matches.is_empty() {
 							// Use the found matches. Every matched asset type has default options
@@ -408,14 +1634,22 @@ impl PackSquasher {
 							match_and_process_pack_file(
 								&options_holder.options,
 								$file_options.map(|file_options| {
-									file_options.tweak_from_global_options(
-										&options_holder.options.global_options
-									)
+									file_options
+										.tweak_from_global_options(
+											&options_holder.options.global_options
+										)
+										.tweak_from_relative_path(
+											pack_file_data.relative_path.as_str(),
+											&options_holder
+										)
 								}),
 								&*squash_zip,
 								&*vfs,
 								&asset_type_matches,
 								&pack_file_data,
+								(*texture_optimization_report).as_ref(),
+								(*audio_diagnostics_report).as_ref(),
+								run_manifest.as_deref(),
 								&*pack_file_optimization_failed,
 								pack_file_status_sender.as_ref()
 							)
@@ -424,13 +1658,38 @@ impl PackSquasher {
 					}
 
 					// Try to match configuration-provided file settings and process the pack file
-					// with those. The first match that contains settings for this pack file type
+					// with those. The last match that contains settings for this pack file type
 					// "wins"
 					for i in options_holder
 						.file_options_globs
-						.matches(&*pack_file_data.relative_path)
+						.matches(pack_file_data.relative_path.as_str())
 					{
-						let file_options = options_holder.options.file_options[i];
+						let file_options = options_holder.options.file_options[i].clone();
+
+						// Nested archives are custom files whose entries are processed like pack
+						// files, which needs more context than pack file processors have
+						if !have_default_options
+							&& let FileOptions::CustomFileOptions(CustomFileOptions {
+								force_include: true,
+								custom_file_format: CustomFileFormat::ZipArchive,
+								..
+							}) = file_options
+						{
+							if !process_nested_archive(
+								Arc::clone(&options_holder),
+								Arc::clone(&asset_type_matcher),
+								&*squash_zip,
+								&*vfs,
+								&pack_file_data,
+								pack_file_status_sender.as_ref()
+							)
+							.await
+							{
+								pack_file_optimization_failed.store(true, Ordering::Release);
+							}
+
+							return;
+						}
 
 						if try_process_with_file_options!(Some(file_options)) {
 							return;
@@ -442,26 +1701,7 @@ impl PackSquasher {
 					// were not appropriate for its type (i.e. all matches were for JSON files,
 					// but this is an audio file), in which case we should try defaults too
 					if have_default_options {
-						for default_file_options in [
-							Some(FileOptions::JsonFileOptions(JsonFileOptions::default())),
-							Some(FileOptions::AudioFileOptions(AudioFileOptions::default())),
-							Some(FileOptions::PngFileOptions(PngFileOptions::default())),
-							#[cfg(feature = "optifine")]
-							Some(FileOptions::PropertiesFileOptions(
-								PropertiesFileOptions::default()
-							)),
-							Some(FileOptions::ShaderFileOptions(ShaderFileOptions::default())),
-							Some(FileOptions::LegacyLanguageFileOptions(
-								LegacyLanguageFileOptions::default()
-							)),
-							Some(FileOptions::CommandFunctionFileOptions(
-								CommandFunctionFileOptions::default()
-							)),
-							Some(FileOptions::CompressedCompoundNbtTagFileOptions(
-								CompressedCompoundNbtTagFileOptions::default()
-							)),
-							None
-						] {
+						for default_file_options in default_file_options() {
 							if try_process_with_file_options!(default_file_options) {
 								return;
 							}
@@ -476,7 +1716,10 @@ impl PackSquasher {
 							path: pack_file_data.relative_path,
 							optimization_strategy: Cow::Borrowed("Skipped"),
 							optimization_error: None,
-							skipped: true
+							skipped: true,
+							processing_time: None,
+							input_size: None,
+							output_size: None
 						}))
 						.await
 						.ok();
@@ -519,15 +1762,82 @@ impl PackSquasher {
 			// At this point we have only our strong reference to squash_zip, because
 			// we have just waited for the pack file tasks to conclude, and each task
 			// held one strong reference
-			match Arc::try_unwrap(squash_zip) {
-				Ok(squash_zip) => {
+			let zip_statistics = match Arc::try_unwrap(squash_zip) {
+				Ok(mut squash_zip) => {
+					let global_options = &options_holder.options.global_options;
+
+					let entry_names = if global_options.split_output_by_namespace {
+						squash_zip
+							.entry_names()
+							.map(|entry_name| entry_name.as_str().to_owned())
+							.collect()
+					} else {
+						vec![]
+					};
+
 					squash_zip
-						.finish(&options_holder.options.global_options.output_file_path)
+						.finish_with_split_outputs(
+							&global_options.output_file_path,
+							&split_outputs(
+								&global_options.output_file_path,
+								&options_holder.split_output_group_globs,
+								global_options.split_output_by_namespace,
+								entry_names.iter().map(String::as_str)
+							)
+						)
 						.await?
 				}
 				Err(_) => panic!("Unexpected number of strong references to SquashZip")
 			};
 
+			// Write the collected ZIP statistics, if requested
+			if let (Some(zip_statistics), Some(zip_statistics_file_path)) = (
+				zip_statistics,
+				&options_holder
+					.options
+					.global_options
+					.zip_statistics_file_path
+			) {
+				tokio::fs::write(
+					zip_statistics_file_path,
+					serde_json::to_vec_pretty(&zip_statistics).map_err(io::Error::from)?
+				)
+				.await?;
+			}
+
+			// Write the texture optimization report, if requested. Like SquashZip, the pack file
+			// tasks held the other strong references to it
+			if let (Some(texture_optimization_report), Some(texture_optimization_report_file_path)) = (
+				match Arc::try_unwrap(texture_optimization_report) {
+					Ok(texture_optimization_report) => texture_optimization_report,
+					Err(_) => panic!("Unexpected number of strong references to the texture report")
+				},
+				&options_holder
+					.options
+					.global_options
+					.texture_optimization_report_file_path
+			) {
+				texture_optimization_report
+					.write(texture_optimization_report_file_path)
+					.await?;
+			}
+
+			// Likewise, write the audio diagnostics report, if requested
+			if let (Some(audio_diagnostics_report), Some(audio_diagnostics_report_file_path)) = (
+				match Arc::try_unwrap(audio_diagnostics_report) {
+					Ok(audio_diagnostics_report) => audio_diagnostics_report,
+					Err(_) => panic!("Unexpected number of strong references to the audio report")
+				},
+				&options_holder
+					.options
+					.global_options
+					.audio_diagnostics_report_file_path
+			) {
+				audio_diagnostics_report
+					.write(audio_diagnostics_report_file_path)
+					.await?;
+			}
+
 			// Finally, send warnings about relevant conditions
 			if let Some(tx) = pack_file_status_sender
 				&& let Some(system_time_sanitizer) = LazyLock::get(&squash_zip::SYSTEM_TIME_SANITIZER)
@@ -552,6 +1862,84 @@ impl PackSquasher {
 			Ok(())
 		})
 	}
+
+	/// Plans what the squash operation configured by the specified options would do with each
+	/// file of the pack in the provided virtual file system, without reading, processing or
+	/// writing any file. This is useful to check which file options apply to each pack file.
+	///
+	/// The plan does not take into account the asset types mask detected from the pack metadata
+	/// file, nor the pack files that would be skipped due to analyses of the pack contents, such
+	/// as those identical to vanilla textures or unreferenced by other pack files.
+	pub fn plan<O: TryInto<ProcessedSquashOptions>>(
+		&self,
+		vfs: impl VirtualFileSystem,
+		squash_options: O
+	) -> Result<Vec<PlannedPackFile>, PackSquasherError>
+	where
+		PackSquasherError: From<<O as TryInto<ProcessedSquashOptions>>::Error>
+	{
+		let options_holder = squash_options.try_into()?;
+
+		if !iter::once(&options_holder.options.pack_directory)
+			.chain(
+				&options_holder
+					.options
+					.global_options
+					.merged_pack_directories
+			)
+			.all(|pack_directory| {
+				vfs.file_type(pack_directory)
+					.map_or_else(|_| true, |file_type| file_type.is_dir())
+			}) {
+			return Err(PackSquasherError::InvalidFileType(
+				"The pack directory and merged pack directory paths must refer to directories, not files"
+			));
+		}
+
+		let traversal_options = IteratorTraversalOptions {
+			ignore_system_and_hidden_files: options_holder
+				.options
+				.global_options
+				.ignore_system_and_hidden_files
+		};
+
+		// Conflicts between merged packs are not reported, but they are resolved as usual
+		let mut vfs = PackMergingVfs::new(vfs);
+		vfs.merge_packs(
+			&options_holder.options.pack_directory,
+			&options_holder
+				.options
+				.global_options
+				.merged_pack_directories,
+			traversal_options,
+			options_holder
+				.options
+				.global_options
+				.pack_merge_conflict_handling
+		);
+
+		let asset_type_matcher =
+			PackFileAssetTypeMatcher::for_options(EnumSet::all(), &options_holder);
+
+		vfs.file_iterator(&options_holder.options.pack_directory, traversal_options)
+			.map(|pack_file_data| {
+				let pack_file_data = pack_file_data?;
+
+				Ok(
+					match pack_file_exclusion_reason(&pack_file_data, &options_holder, &vfs)? {
+						Some(exclusion_reason) => {
+							PlannedPackFile::skipped(pack_file_data.relative_path, exclusion_reason)
+						}
+						None => pack_file_planning::plan_pack_file(
+							pack_file_data.relative_path,
+							&options_holder,
+							&asset_type_matcher
+						)
+					}
+				)
+			})
+			.collect()
+	}
 }
 
 /// An error that may occur during a pack squashing operation.
@@ -579,7 +1967,22 @@ pub enum PackSquasherError {
 	/// Thrown when an error happened while parsing the pack metadata file,
 	/// which defines some basic characteristics of a pack.
 	#[error("Pack metadata file error: {0}")]
-	PackMetaError(#[from] PackMetaError)
+	PackMetaError(#[from] PackMetaError),
+	/// Thrown when some JSON files of well-known types do not match the schema that the
+	/// targeted Minecraft versions expect for them, and schema violations were configured
+	/// to be errors. The violations are sent as warnings before this error is returned.
+	#[error("{0} JSON schema violations were found")]
+	JsonSchemaViolations(usize),
+	/// Thrown when several merged packs contain files at the same relative path, and these
+	/// conflicts were configured to be errors. The conflicts are sent as warnings before this
+	/// error is returned.
+	#[error("{0} conflicts between the files of the merged packs were found")]
+	PackMergeConflicts(usize),
+	/// Thrown when some warnings of categories that were configured to be denied were emitted.
+	/// The squash operation finishes before this error is returned, so the output ZIP file
+	/// is generated anyway.
+	#[error("{0} warnings of denied categories were emitted")]
+	DeniedWarnings(usize)
 }
 
 impl From<Infallible> for PackSquasherError {
@@ -611,12 +2014,145 @@ pub enum PackSquasherWarning {
 	/// The number of parallel tasks used to process pack files was limited
 	/// due to limits on the number of concurrent open file descriptors.
 	#[cfg(unix)]
-	ConcurrencyLimitedDueToOpenFdLimits
+	ConcurrencyLimitedDueToOpenFdLimits,
+	/// A texture of the pack is identical to the vanilla texture at the same
+	/// path, so it could be removed from the pack without changing how it
+	/// looks in game. These warnings are only emitted when textures are
+	/// compared against a vanilla client JAR and not excluded.
+	VanillaIdenticalTexture(RelativePath<'static>),
+	/// An OptiFine connected textures properties file references tiles that
+	/// do not exist in the pack, so the connected texture may not look as
+	/// intended in game.
+	#[cfg(feature = "optifine")]
+	#[doc(cfg(feature = "optifine"))]
+	MissingConnectedTextureTiles(RelativePath<'static>, Vec<String>),
+	/// An OptiFine custom entity model or part model references textures or
+	/// part models that are not in the pack, so the entity may not look as
+	/// intended in game.
+	#[cfg(feature = "optifine")]
+	#[doc(cfg(feature = "optifine"))]
+	CustomEntityModelMismatch(RelativePath<'static>, String),
+	/// An OptiFine emissive texture has different dimensions than the base
+	/// texture it is overlaid on, so it may be rendered misaligned or
+	/// stretched. These warnings are only emitted when emissive textures are
+	/// checked.
+	#[cfg(feature = "optifine")]
+	#[doc(cfg(feature = "optifine"))]
+	EmissiveTextureMismatch(RelativePath<'static>, String),
+	/// Some sounds of the pack decode to the same audio data, so all but one of
+	/// them could be removed from the pack. These warnings are only emitted when
+	/// duplicate sounds are detected and can't be deduplicated.
+	DuplicateSounds(Vec<RelativePath<'static>>),
+	/// A `sounds.json` file references a sound that is not in the pack, so the
+	/// sound event that uses it may not play as intended in game. These warnings
+	/// are only emitted when sound references are validated.
+	MissingSound(RelativePath<'static>, String),
+	/// A sound file of the pack is not referenced by any `sounds.json` file, so
+	/// it may be dead weight. These warnings are only emitted when sound
+	/// references are validated and unreferenced sounds are not excluded.
+	UnreferencedSound(RelativePath<'static>),
+	/// A preview of a sound could not be written to the audio preview directory,
+	/// because the sound could not be decoded or some I/O error occurred. These
+	/// warnings are only emitted when audio previews are generated.
+	AudioPreviewNotGenerated(RelativePath<'static>),
+	/// A sound that Minecraft plays as a positional sound is stereo, so it will
+	/// be heard at the same volume no matter how far away it is played. These
+	/// warnings are only emitted when stereo positional sounds are reported.
+	StereoPositionalSound(RelativePath<'static>),
+	/// Several merged packs contain a file at the same relative path, so only the file at
+	/// the first path is kept, and the files at the rest of paths are discarded. These
+	/// warnings are only emitted when several packs are merged.
+	PackMergeConflict(RelativePath<'static>, PathBuf, Vec<PathBuf>),
+	/// A JSON file of a well-known type does not match the schema that the
+	/// targeted Minecraft versions expect for it, so some of its contents may
+	/// be ignored or replaced with defaults in game. These warnings are only
+	/// emitted when JSON files are validated against schemas.
+	JsonSchemaViolation(RelativePath<'static>, String),
+	/// A model, texture or sound file of the pack is not referenced by any other
+	/// pack file, so it may be dead weight. These warnings are only emitted when
+	/// unreferenced assets are reported instead of excluded.
+	UnreferencedAsset(RelativePath<'static>),
+	/// A language file of the pack contains some translations that are the same
+	/// as the American English translations Minecraft falls back to, so they may
+	/// be dead weight. These warnings are only emitted when duplicate
+	/// translations are reported instead of removed.
+	DuplicateTranslations(RelativePath<'static>, usize),
+	/// A shader program JSON definition does not match the vertex and fragment shaders it
+	/// references, so Minecraft may fail to load it, or not bind some of its samplers and
+	/// uniforms. These warnings are only emitted when shader programs are validated.
+	ShaderProgramMismatch(RelativePath<'static>, String),
+	/// A post-processing effect pipeline is not wired correctly to its render targets,
+	/// programs or textures, so it may not look as intended in game. These warnings are only
+	/// emitted when post-processing effects are validated.
+	PostEffectMismatch(RelativePath<'static>, String),
+	/// A provider of a font definition file references assets that are not in the pack, or
+	/// defines its glyphs inconsistently, so Minecraft may skip it or fail to load its font.
+	/// These warnings are only emitted when font providers are validated.
+	FontProviderMismatch(RelativePath<'static>, String),
+	/// An item model definition renders models that are not in the pack, or whose parents or
+	/// textures are not in the pack, so Minecraft may render its item with the missing model
+	/// or texture. These warnings are only emitted when item model definitions are validated.
+	ItemModelDefinitionMismatch(RelativePath<'static>, String),
+	/// A source of a texture atlas configuration file references textures that are not in the
+	/// pack, or matches no texture of the pack, so Minecraft may render the textures expected
+	/// in that atlas as the missing texture. These warnings are only emitted when atlas sources
+	/// are checked.
+	AtlasSourceMismatch(RelativePath<'static>, String),
+	/// A data pack tag has required entries that reference tags, functions or registry
+	/// elements that are not in the pack, so Minecraft may fail to load it and the tags that
+	/// include it. These warnings are only emitted when data pack references are validated.
+	DataPackReferenceMismatch(RelativePath<'static>, String)
+}
+
+impl PackSquasherWarning {
+	/// Gets the category of this warning, which determines how it is handled.
+	pub const fn category(&self) -> WarningCategory {
+		match self {
+			Self::UnusablePreviousZip(_) => WarningCategory::UnusablePreviousZip,
+			Self::PredictableSystemTimeSanitizationKey => {
+				WarningCategory::PredictableSystemTimeSanitizationKey
+			}
+			Self::VolatileSystemTimeSanitizationKey => {
+				WarningCategory::VolatileSystemTimeSanitizationKey
+			}
+			#[cfg(unix)]
+			Self::ConcurrencyLimitedDueToOpenFdLimits => WarningCategory::ConcurrencyLimitedDueToOpenFdLimits,
+			Self::VanillaIdenticalTexture(_) => WarningCategory::VanillaIdenticalTexture,
+			#[cfg(feature = "optifine")]
+			Self::MissingConnectedTextureTiles(..) => WarningCategory::MissingConnectedTextureTiles,
+			#[cfg(feature = "optifine")]
+			Self::CustomEntityModelMismatch(..) => WarningCategory::CustomEntityModelMismatch,
+			#[cfg(feature = "optifine")]
+			Self::EmissiveTextureMismatch(..) => WarningCategory::EmissiveTextureMismatch,
+			Self::DuplicateSounds(_) => WarningCategory::DuplicateSounds,
+			Self::MissingSound(..) => WarningCategory::MissingSound,
+			Self::UnreferencedSound(_) => WarningCategory::UnreferencedSound,
+			Self::AudioPreviewNotGenerated(_) => WarningCategory::AudioPreviewNotGenerated,
+			Self::StereoPositionalSound(_) => WarningCategory::StereoPositionalSound,
+			Self::PackMergeConflict(..) => WarningCategory::PackMergeConflict,
+			Self::JsonSchemaViolation(..) => WarningCategory::JsonSchemaViolation,
+			Self::UnreferencedAsset(_) => WarningCategory::UnreferencedAsset,
+			Self::DuplicateTranslations(..) => WarningCategory::DuplicateTranslations,
+			Self::ShaderProgramMismatch(..) => WarningCategory::ShaderProgramMismatch,
+			Self::PostEffectMismatch(..) => WarningCategory::PostEffectMismatch,
+			Self::FontProviderMismatch(..) => WarningCategory::FontProviderMismatch,
+			Self::ItemModelDefinitionMismatch(..) => WarningCategory::ItemModelDefinitionMismatch,
+			Self::AtlasSourceMismatch(..) => WarningCategory::AtlasSourceMismatch,
+			Self::DataPackReferenceMismatch(..) => WarningCategory::DataPackReferenceMismatch
+		}
+	}
 }
 
 /// A status message concerning an in-progress squash operation.
 #[non_exhaustive]
 pub enum PackSquasherStatus {
+	/// The pack files to process were found, and this many of them will be processed.
+	/// This is sent once, before any pack file is processed. Then, a `PackFileProcessed`
+	/// status is sent for each pack file, unless the operation stops due to an error.
+	PackFilesFound(u64),
+	/// The pack file at the specified path started being processed. A `PackFileProcessed`
+	/// status is sent for it when it is done.
+	PackFileProcessingStarted(RelativePath<'static>),
 	/// A pack file was processed in some way, either successfully or not.
 	PackFileProcessed(PackFileStatus),
 	/// Every pack file was processed, and the output ZIP file is being
@@ -635,7 +2171,10 @@ pub struct PackFileStatus {
 	path: RelativePath<'static>,
 	optimization_strategy: Cow<'static, str>,
 	optimization_error: Option<String>,
-	skipped: bool
+	skipped: bool,
+	processing_time: Option<Duration>,
+	input_size: Option<u64>,
+	output_size: Option<u64>
 }
 
 impl PackFileStatus {
@@ -666,6 +2205,28 @@ impl PackFileStatus {
 	pub const fn skipped(&self) -> bool {
 		self.skipped
 	}
+
+	/// Gets how long it took to process this file, including the time taken to
+	/// optimize it and add it to the generated ZIP file. This is `None` if the
+	/// file was not processed, such as when it was skipped.
+	pub const fn processing_time(&self) -> Option<Duration> {
+		self.processing_time
+	}
+
+	/// Gets the size of this file before processing it, in bytes, as reported by
+	/// the filesystem it was read from. This is `None` if the file was not
+	/// processed, such as when it was skipped.
+	pub const fn input_size(&self) -> Option<u64> {
+		self.input_size
+	}
+
+	/// Gets the size of the data generated for this file, in bytes, before it was
+	/// compressed in the generated ZIP file. This is `None` if such data was not
+	/// generated, such as when the file was skipped, copied from a previous run,
+	/// or an error occurred.
+	pub const fn output_size(&self) -> Option<u64> {
+		self.output_size
+	}
 }
 
 /// Processes the given pack file according to the provided file options and the asset types that
@@ -679,17 +2240,43 @@ impl PackFileStatus {
 #[allow(clippy::too_many_arguments)] // Alternatives are not really more readable
 async fn match_and_process_pack_file(
 	squash_options: &SquashOptions,
-	file_options: Option<FileOptions>,
+	mut file_options: Option<FileOptions>,
 	squash_zip: &SquashZip<impl AsyncRead + AsyncSeek + Unpin>,
 	vfs: &impl VirtualFileSystem,
 	asset_type_matches: &PackFileAssetTypeMatches,
 	pack_file_data: &VfsPackFileIterEntry,
+	texture_optimization_report: Option<&TextureOptimizationReport>,
+	audio_diagnostics_report: Option<&AudioDiagnosticsReport>,
+	run_manifest: Option<&RunManifest>,
 	pack_file_optimization_failed: &AtomicBool,
 	pack_file_status_sender: Option<&Sender<PackSquasherStatus>>
 ) -> bool {
 	let mut pack_file_open_error = None;
 	let mut vfs_file_meta = None;
 
+	// The size limits of animated textures apply to each of their frames, so tell PNG files
+	// the frame size declared by their animation metadata file, if they have one
+	if let Some(FileOptions::PngFileOptions(png_file_options)) = &mut file_options {
+		png_file_options.animation_frame_size =
+			read_animation_metadata(vfs, &pack_file_data.file_path)
+				.await
+				.and_then(|animation_metadata| {
+					AnimationFrameSize::from_animation_metadata(&animation_metadata)
+				});
+	}
+
+	// The file options are consumed below, so keep the ones needed to report stereo
+	// positional sounds
+	let stereo_positional_sound_report_options = match &file_options {
+		Some(FileOptions::AudioFileOptions(audio_file_options))
+			if audio_file_options.stereo_positional_sound_handling
+				== StereoPositionalSoundHandling::Warn =>
+		{
+			Some(audio_file_options.clone())
+		}
+		_ => None
+	};
+
 	let process_data =
 		asset_type_matches.process_data(file_options, || match vfs.open(&pack_file_data.file_path) {
 			Ok(vfs_file) => {
@@ -707,17 +2294,138 @@ async fn match_and_process_pack_file(
 	if let Some(process_data) = process_data {
 		let (vfs_file_meta, pack_file_size_hint) = vfs_file_meta.unwrap();
 
+		// Texture optimization reports compare the input and output data of PNG files, so
+		// read the input data again for them. Textures that can't be read are not reported
+		let is_png_file = process_data.canonical_extension.map_or_else(
+			|| pack_file_data.relative_path.as_str().ends_with(".png"),
+			|canonical_extension| canonical_extension == "png"
+		);
+		let texture_report = match texture_optimization_report {
+			Some(texture_optimization_report) if is_png_file => read_pack_file(vfs, pack_file_data)
+				.await
+				.map(|input_data| (texture_optimization_report, input_data)),
+			_ => None
+		};
+		let is_audio_file = process_data.canonical_extension == Some("ogg");
+
+		// Run manifests hash the input data of every pack file, so read it again for them too
+		let run_manifest = match run_manifest {
+			Some(run_manifest) => Some((
+				run_manifest,
+				read_pack_file(vfs, pack_file_data)
+					.await
+					.map(|input_data| FileDigest::of(&input_data))
+			)),
+			None => None
+		};
+
 		pack_file_process_failed = !process_pack_file(
 			process_data,
 			pack_file_data.relative_path.as_owned(),
 			vfs_file_meta.modification_time,
 			pack_file_size_hint,
 			squash_zip,
+			texture_report,
+			run_manifest,
 			pack_file_status_sender,
 			squash_options.global_options.recompress_compressed_files
 		)
 		.await;
 		have_process_data = true;
+
+		// Audio previews, stereo positional sound warnings and audio diagnostics are generated
+		// from the input data of sound files, like texture optimization reports. Read it once
+		// for all of them, and decode it at most once. Failing to generate them does not fail
+		// the squash operation
+		let audio_preview_directory = squash_options
+			.global_options
+			.audio_preview_directory
+			.as_deref();
+		if is_audio_file
+			&& (audio_preview_directory.is_some()
+				|| stereo_positional_sound_report_options.is_some()
+				|| audio_diagnostics_report.is_some())
+		{
+			let input_data = read_pack_file(vfs, pack_file_data).await.map(Bytes::from);
+			let is_ogg = pack_file_data
+				.relative_path
+				.as_str()
+				.rsplit_once('.')
+				.is_some_and(|(_, extension)| is_ogg_extension(extension));
+
+			let analysis = match &input_data {
+				Some(input_data)
+					if audio_preview_directory.is_some() || audio_diagnostics_report.is_some() =>
+				{
+					task::block_in_place(|| {
+						analyze_audio(
+							input_data.clone(),
+							is_ogg,
+							audio_preview_directory.map(|_| {
+								Duration::from_secs(
+									squash_options
+										.global_options
+										.audio_preview_duration
+										.get()
+										.into()
+								)
+							}),
+							audio_diagnostics_report.is_some()
+						)
+					})
+				}
+				_ => None
+			};
+			let (preview, diagnostics) = analysis.map_or((None, None), |analysis| {
+				(analysis.preview, analysis.diagnostics)
+			});
+
+			if let Some(audio_preview_directory) = audio_preview_directory {
+				let preview_written = match preview {
+					Some(preview) => {
+						write_audio_preview(
+							&pack_file_data.relative_path,
+							audio_preview_directory,
+							&preview
+						)
+						.await
+					}
+					None => false
+				};
+
+				if !preview_written && let Some(tx) = pack_file_status_sender {
+					tx.send(PackSquasherStatus::Warning(
+						PackSquasherWarning::AudioPreviewNotGenerated(
+							pack_file_data.relative_path.as_owned()
+						)
+					))
+					.await
+					.ok();
+				}
+			}
+
+			// Sounds that can't be read or decoded are not considered stereo
+			if let Some(audio_file_options) = &stereo_positional_sound_report_options
+				&& let Some(input_data) = &input_data
+				&& task::block_in_place(|| {
+					output_channel_count(input_data.clone(), is_ogg, audio_file_options)
+				})
+				.is_some_and(|channels| channels.get() == 2)
+				&& let Some(tx) = pack_file_status_sender
+			{
+				tx.send(PackSquasherStatus::Warning(
+					PackSquasherWarning::StereoPositionalSound(
+						pack_file_data.relative_path.as_owned()
+					)
+				))
+				.await
+				.ok();
+			}
+
+			if let Some(audio_diagnostics_report) = audio_diagnostics_report {
+				audio_diagnostics_report.add_sound(&pack_file_data.relative_path, diagnostics);
+			}
+		}
 	} else {
 		pack_file_process_failed = false;
 		have_process_data = false;
@@ -729,7 +2437,10 @@ async fn match_and_process_pack_file(
 				path: pack_file_data.relative_path.as_owned(),
 				optimization_strategy: Cow::Borrowed("Error opening pack file"),
 				optimization_error: Some(err.to_string()),
-				skipped: false
+				skipped: false,
+				processing_time: None,
+				input_size: None,
+				output_size: None
 			}))
 			.await
 			.ok();
@@ -743,21 +2454,189 @@ async fn match_and_process_pack_file(
 	have_process_data || pack_file_open_error.is_some() || pack_file_process_failed
 }
 
+/// Returns the file options that are tried, in order, to process pack files that do not match
+/// any appropriate configured file options. The last one, `None`, stands for no options, which
+/// some asset types are processed with.
+fn default_file_options() -> impl Iterator<Item = Option<FileOptions>> {
+	[
+		Some(FileOptions::JsonFileOptions(JsonFileOptions::default())),
+		Some(FileOptions::AudioFileOptions(AudioFileOptions::default())),
+		Some(FileOptions::PngFileOptions(PngFileOptions::default())),
+		#[cfg(feature = "optifine")]
+		Some(FileOptions::PropertiesFileOptions(
+			PropertiesFileOptions::default()
+		)),
+		Some(FileOptions::ShaderFileOptions(ShaderFileOptions::default())),
+		Some(FileOptions::LegacyLanguageFileOptions(
+			LegacyLanguageFileOptions::default()
+		)),
+		Some(FileOptions::CommandFunctionFileOptions(
+			CommandFunctionFileOptions::default()
+		)),
+		Some(FileOptions::CompressedCompoundNbtTagFileOptions(
+			CompressedCompoundNbtTagFileOptions::default()
+		)),
+		Some(FileOptions::ZippedUnifontHexFileOptions(
+			ZippedUnifontHexFileOptions::default()
+		)),
+		None
+	]
+	.into_iter()
+}
+
+/// Processes the specified nested archive pack file, optimizing its entries as pack files and
+/// adding the result to the output ZIP file. The archive is read and optimized lazily, so that
+/// this work is not done if the archive is copied from a previous run. Returns whether the
+/// archive was processed successfully.
+async fn process_nested_archive(
+	options_holder: Arc<ProcessedSquashOptions>,
+	asset_type_matcher: Arc<PackFileAssetTypeMatcher>,
+	squash_zip: &SquashZip<impl AsyncRead + AsyncSeek + Unpin>,
+	vfs: &impl VirtualFileSystem,
+	pack_file_data: &VfsPackFileIterEntry,
+	pack_file_status_sender: Option<&Sender<PackSquasherStatus>>
+) -> bool {
+	let mut vfs_file = match vfs.open(&pack_file_data.file_path) {
+		Ok(vfs_file) => vfs_file,
+		Err(err) => {
+			if let Some(tx) = pack_file_status_sender {
+				tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
+					path: pack_file_data.relative_path.as_owned(),
+					optimization_strategy: Cow::Borrowed("Error opening pack file"),
+					optimization_error: Some(err.to_string()),
+					skipped: false,
+					processing_time: None,
+					input_size: None,
+					output_size: None
+				}))
+				.await
+				.ok();
+			}
+
+			return false;
+		}
+	};
+
+	let archive_size_hint = vfs_file.file_size_hint;
+	let edit_time = vfs_file.metadata.modification_time;
+	let compress_already_compressed = options_holder
+		.options
+		.global_options
+		.recompress_compressed_files;
+	let optimized_archive = Box::pin(async move {
+		let mut archive = Vec::with_capacity(archive_size_hint.try_into().unwrap_or(0));
+		vfs_file
+			.file_read
+			.read_to_end(&mut archive)
+			.await
+			.map_err(OptimizationError::from)?;
+
+		nested_archive::optimize_nested_archive(&archive, &options_holder, &asset_type_matcher)
+			.await
+			.map(|(optimization_strategy, optimized_archive)| {
+				(
+					optimization_strategy,
+					Box::new(optimized_archive) as Box<dyn AsRef<[u8]> + Send>
+				)
+			})
+	});
+
+	process_pack_file(
+		PackFileProcessData {
+			optimized_byte_chunks_stream: Box::new(stream::once(optimized_archive)),
+			is_compressed: true,
+			canonical_extension: None,
+			listing_circumstances: FileListingCircumstances {
+				may_be_read_and_provided_by_mods: false,
+				is_force_included: true
+			}
+		},
+		pack_file_data.relative_path.as_owned(),
+		edit_time,
+		archive_size_hint,
+		squash_zip,
+		None,
+		pack_file_status_sender,
+		compress_already_compressed
+	)
+	.await
+}
+
+/// Reads the contents of the specified pack file, returning `None` if some I/O error occurs.
+async fn read_pack_file(
+	vfs: &impl VirtualFileSystem,
+	pack_file_data: &VfsPackFileIterEntry
+) -> Option<Vec<u8>> {
+	read_file(vfs, &pack_file_data.file_path)
+		.await
+		.ok()
+		.map(|(data, _)| data)
+}
+
+/// Reads the animation metadata file of the texture at the specified path, which has the same
+/// path as the texture followed by a `.mcmeta` or `.mcmetac` extension. Returns `None` if the
+/// texture has no such file, or it can't be read.
+async fn read_animation_metadata(
+	vfs: &impl VirtualFileSystem,
+	texture_path: &Path
+) -> Option<Vec<u8>> {
+	for extension in [".mcmeta", ".mcmetac"] {
+		let mut animation_metadata_path = texture_path.as_os_str().to_owned();
+		animation_metadata_path.push(extension);
+
+		if let Ok((data, _)) = read_file(vfs, &animation_metadata_path).await {
+			return Some(data);
+		}
+	}
+
+	None
+}
+
+/// Writes the specified preview of a sound pack file to the specified directory, at the same
+/// relative path as the sound but with an `.ogg` extension. Returns `false` if the preview
+/// could not be written.
+async fn write_audio_preview(
+	relative_path: &RelativePath<'_>,
+	audio_preview_directory: &Path,
+	preview: &[u8]
+) -> bool {
+	let preview_path = audio_preview_directory
+		.join(relative_path.as_str())
+		.with_extension("ogg");
+	async {
+		if let Some(preview_directory) = preview_path.parent() {
+			tokio::fs::create_dir_all(preview_directory).await?;
+		}
+		tokio::fs::write(&preview_path, preview).await
+	}
+	.await
+	.is_ok()
+}
+
 /// Processes the provided pack file, adding it to the output ZIP file as appropriate and
 /// notifying client code via a channel about the result of the operation. If some error
 /// occurs, the state of the output ZIP file may become invalid, and no further pack files
 /// should be processed and added to it.
 ///
+/// If a texture optimization report and the input data of the pack file are provided, the
+/// pack file will be added to that report. Likewise, if a run manifest is provided, the
+/// digests of the input and output data of the pack file will be recorded in it.
+///
 /// The return value is `true` if no error occurred, and `false` if some error happened.
+#[allow(clippy::too_many_arguments)] // Alternatives are not really more readable
 async fn process_pack_file(
 	pack_file_process_data: PackFileProcessData,
 	relative_path: RelativePath<'static>,
 	edit_time: Option<SystemTime>,
 	file_size_hint: u64,
 	squash_zip: &SquashZip<impl AsyncRead + AsyncSeek + Unpin>,
+	texture_report: Option<(&TextureOptimizationReport, Vec<u8>)>,
+	run_manifest: Option<(&RunManifest, Option<FileDigest>)>,
 	pack_file_status_sender: Option<&Sender<PackSquasherStatus>>,
 	compress_already_compressed: bool
 ) -> bool {
+	let start_instant = Instant::now();
+
 	// We may have to change the file extension to a canonical one that's accepted by Minecraft.
 	// Do that early, because we store the file with the canonical extension in the ZIP
 	let pack_file_path = match pack_file_process_data.canonical_extension {
@@ -771,6 +2650,14 @@ async fn process_pack_file(
 		None => relative_path
 	};
 
+	if let Some(tx) = pack_file_status_sender {
+		tx.send(PackSquasherStatus::PackFileProcessingStarted(
+			pack_file_path.clone()
+		))
+		.await
+		.ok();
+	}
+
 	let copy_previous_file = squash_zip.file_process_time(&pack_file_path).map_or_else(
 		|| false,
 		|squash_time| edit_time.is_some() && Some(squash_time) >= edit_time
@@ -778,6 +2665,8 @@ async fn process_pack_file(
 
 	let mut optimization_error = None;
 	let optimization_strategy;
+	let mut output_digest = None;
+	let mut output_size = None;
 
 	if copy_previous_file {
 		optimization_error = squash_zip
@@ -790,6 +2679,15 @@ async fn process_pack_file(
 			.map(|err| err.to_string());
 
 		optimization_strategy = Cow::Borrowed("Copied from previous run");
+
+		if let Some((texture_optimization_report, input_data)) = &texture_report {
+			texture_optimization_report.add_texture(
+				&pack_file_path,
+				&optimization_strategy,
+				input_data,
+				None
+			);
+		}
 	} else {
 		let mut processed_pack_file_chunks = pack_file_process_data
 			.optimized_byte_chunks_stream
@@ -826,6 +2724,21 @@ async fn process_pack_file(
 			})
 			.map(|chunk| BoxedDynAsByteSliceRef(chunk.unwrap().1));
 
+		// Keep a copy of the processed data if it will be compared in a texture report, and
+		// digest it if it will be recorded in a run manifest
+		let mut output_data = texture_report.is_some().then(Vec::new);
+		let mut output_digester = run_manifest.is_some().then(FileDigester::default);
+		let mut processed_size = 0;
+		let processed_pack_file_chunks = processed_pack_file_chunks.inspect(|chunk| {
+			processed_size += chunk.as_ref().len() as u64;
+			if let Some(output_data) = &mut output_data {
+				output_data.extend_from_slice(chunk.as_ref());
+			}
+			if let Some(output_digester) = &mut output_digester {
+				output_digester.update(chunk.as_ref());
+			}
+		});
+
 		let squash_zip_error = squash_zip
 			.add_file(
 				&pack_file_path,
@@ -839,6 +2752,26 @@ async fn process_pack_file(
 			.map(|err| err.to_string());
 
 		optimization_error = optimization_error.or(squash_zip_error);
+
+		if let (Some((texture_optimization_report, input_data)), Some(output_data), None) =
+			(&texture_report, &output_data, &optimization_error)
+		{
+			texture_optimization_report.add_texture(
+				&pack_file_path,
+				&optimization_strategy,
+				input_data,
+				Some(output_data.as_slice())
+			);
+		}
+
+		output_digest = output_digester
+			.filter(|_| optimization_error.is_none())
+			.map(FileDigester::finish);
+		output_size = optimization_error.is_none().then_some(processed_size);
+	}
+
+	if let Some((run_manifest, input_digest)) = run_manifest {
+		run_manifest.record_file_digests(&pack_file_path, input_digest, output_digest);
 	}
 
 	let all_ok = optimization_error.is_none();
@@ -848,7 +2781,10 @@ async fn process_pack_file(
 			path: pack_file_path,
 			optimization_strategy,
 			optimization_error,
-			skipped: false
+			skipped: false,
+			processing_time: Some(start_instant.elapsed()),
+			input_size: Some(file_size_hint),
+			output_size
 		}))
 		.await
 		.ok();
@@ -0,0 +1,197 @@
+//! Contains routines to optimize nested archives, such as JAR files, that the pack author
+//! includes in the pack as custom files, processing their entries as if they were pack files.
+
+use std::borrow::Cow;
+use std::io::Cursor;
+
+use futures::StreamExt;
+
+use crate::config::{CustomFileFormat, CustomFileOptions, FileOptions, ProcessedSquashOptions};
+use crate::default_file_options;
+use crate::pack_file::OptimizationError;
+use crate::pack_file::asset_type::PackFileAssetTypeMatcher;
+use crate::pack_file::zip_archive::{ZipEntry, read_zip_entries, write_zip};
+use crate::squash_zip::relative_path::RelativePath;
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum depth of archives within nested archives that will be optimized. Deeper
+/// archives are kept as-is, which also stops archives that contain themselves.
+const MAXIMUM_NESTING_DEPTH: u8 = 8;
+
+/// Optimizes the specified ZIP archive, processing each of its entries with the pack file
+/// processor that matches its path within the archive, trying the file options that match
+/// that path and then the default ones, like for any other pack file. Entries that are not
+/// recognized as pack files, or that fail to be processed, are kept as-is, because archives
+/// may contain files that PackSquash does not know about, like mod class files.
+///
+/// The entries of signed JAR files are not processed, because that would invalidate their
+/// signatures, but they are still recompressed. The archive is then written again with a
+/// layout that only depends on its entries, compressing them with the same effort as the
+/// output ZIP file. If that does not make the archive smaller, it is returned unchanged.
+///
+/// Entries that the file options make nested archives are optimized the same way, up to a
+/// nesting depth. Nested archives are processed on their own: their entries are not
+/// considered when checking the references between pack files.
+pub async fn optimize_nested_archive(
+	archive: &[u8],
+	options_holder: &ProcessedSquashOptions,
+	asset_type_matcher: &PackFileAssetTypeMatcher
+) -> Result<(Cow<'static, str>, Vec<u8>), OptimizationError> {
+	optimize_archive(archive, options_holder, asset_type_matcher, 0).await
+}
+
+/// Optimizes the specified ZIP archive as described by [`optimize_nested_archive`], which is
+/// nested within the specified number of other nested archives.
+async fn optimize_archive(
+	archive: &[u8],
+	options_holder: &ProcessedSquashOptions,
+	asset_type_matcher: &PackFileAssetTypeMatcher,
+	nesting_depth: u8
+) -> Result<(Cow<'static, str>, Vec<u8>), OptimizationError> {
+	let mut entries = read_zip_entries(archive)?;
+
+	let is_signed = entries
+		.iter()
+		.any(|entry| entry.name.starts_with("META-INF/") && entry.name.ends_with(".SF"));
+
+	let mut optimized_entry_count = 0;
+	let mut unoptimizable_entry_count = 0;
+	if !is_signed {
+		for entry in &mut entries {
+			match optimize_entry(entry, options_holder, asset_type_matcher, nesting_depth).await {
+				Some(true) => optimized_entry_count += 1,
+				Some(false) => unoptimizable_entry_count += 1,
+				None => {}
+			}
+		}
+	}
+
+	let optimized_archive = write_zip(
+		&entries,
+		options_holder
+			.options
+			.global_options
+			.zip_compression_iterations
+	);
+
+	if optimized_archive.len() >= archive.len() {
+		return Ok((
+			Cow::Borrowed(
+				"Validated, but not optimized further. \
+				If not optimized externally, try tweaking options for extra savings"
+			),
+			archive.to_vec()
+		));
+	}
+
+	let optimization_strategy = match (is_signed, unoptimizable_entry_count) {
+		(true, _) => Cow::Borrowed("Recompressed signed archive, keeping its entries as-is"),
+		(false, 0) => Cow::Owned(format!(
+			"Optimized {optimized_entry_count} archive entries and recompressed"
+		)),
+		(false, _) => Cow::Owned(format!(
+			"Optimized {optimized_entry_count} archive entries and recompressed, \
+			keeping {unoptimizable_entry_count} entries that could not be optimized as-is"
+		))
+	};
+
+	Ok((optimization_strategy, optimized_archive))
+}
+
+/// Processes the specified archive entry of an archive at the specified nesting depth as a
+/// pack file or a nested archive, replacing its data and extension with the processed ones.
+/// Returns `None` if the entry is neither, and whether it could be processed otherwise.
+async fn optimize_entry(
+	entry: &mut ZipEntry,
+	options_holder: &ProcessedSquashOptions,
+	asset_type_matcher: &PackFileAssetTypeMatcher,
+	nesting_depth: u8
+) -> Option<bool> {
+	let is_nested_archive = options_holder
+		.file_options_globs
+		.matches(entry.name.as_str())
+		.into_iter()
+		.any(|i| {
+			matches!(
+				options_holder.options.file_options[i],
+				FileOptions::CustomFileOptions(CustomFileOptions {
+					force_include: true,
+					custom_file_format: CustomFileFormat::ZipArchive,
+					..
+				})
+			)
+		});
+
+	if is_nested_archive && nesting_depth < MAXIMUM_NESTING_DEPTH {
+		// Recursion in async functions requires boxing the recursive future
+		let optimized_archive = Box::pin(optimize_archive(
+			&entry.data,
+			options_holder,
+			asset_type_matcher,
+			nesting_depth + 1
+		))
+		.await;
+
+		return Some(match optimized_archive {
+			Ok((_, optimized_archive)) => {
+				entry.data = optimized_archive;
+				true
+			}
+			Err(_) => false
+		});
+	}
+
+	let relative_path = RelativePath::from_inner(entry.name.as_str());
+	let asset_type_matches = asset_type_matcher.matches_for(&relative_path);
+	if asset_type_matches.is_empty() {
+		return None;
+	}
+
+	let file_options = options_holder
+		.file_options_globs
+		.matches(relative_path.as_str())
+		.into_iter()
+		.map(|i| Some(options_holder.options.file_options[i].clone()))
+		.chain(default_file_options());
+
+	for file_options in file_options {
+		let file_options = file_options.map(|file_options| {
+			file_options
+				.tweak_from_global_options(&options_holder.options.global_options)
+				.tweak_from_relative_path(relative_path.as_str(), options_holder)
+		});
+
+		let Some(process_data) = asset_type_matches.process_data(file_options, || {
+			Some((Cursor::new(entry.data.clone()), entry.data.len() as u64))
+		}) else {
+			continue;
+		};
+
+		let mut optimized_data = Vec::with_capacity(entry.data.len());
+		let mut optimized_byte_chunks = process_data.optimized_byte_chunks_stream;
+		while let Some(optimized_byte_chunk) = optimized_byte_chunks.next().await {
+			let Ok((_, optimized_bytes)) = optimized_byte_chunk else {
+				return Some(false);
+			};
+
+			optimized_data.extend_from_slice((*optimized_bytes).as_ref());
+		}
+
+		if let Some(canonical_extension) = process_data.canonical_extension {
+			entry.name = relative_path
+				.with_extension(canonical_extension)
+				.into_os_string()
+				.into_string()
+				.unwrap();
+		}
+		entry.data = optimized_data;
+
+		return Some(true);
+	}
+
+	// No file options are appropriate for this entry, so it is skipped when it is a pack file.
+	// Archives are opaque to Minecraft, so keep it anyway
+	None
+}
@@ -0,0 +1,152 @@
+use std::path::PathBuf;
+
+use enumset::EnumSet;
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+
+use crate::config::{GlobalOptions, SquashOptions};
+
+use super::*;
+
+/// A pretty-printed item model, which is minified when processed.
+const ITEM_MODEL: &str = r#"{
+	"parent": "minecraft:item/generated",
+	"textures": {
+		"layer0": "mypack:item/gem"
+	}
+}"#;
+
+/// The start of a Java class file, which is not a pack file.
+const CLASS_FILE: &[u8] = &[0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 65];
+
+/// Builds a ZIP archive with the specified entries, storing them without compression.
+fn stored_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+	write_zip(
+		&entries
+			.iter()
+			.map(|(name, data)| ZipEntry {
+				name: (*name).to_owned(),
+				data: data.to_vec()
+			})
+			.collect::<Vec<_>>(),
+		0
+	)
+}
+
+/// Optimizes the specified nested archive with the default options, returning the names and
+/// contents of the entries of the optimized archive.
+async fn optimized_archive_entries(archive: &[u8]) -> Vec<(String, Vec<u8>)> {
+	optimized_archive_entries_with_file_options(archive, IndexMap::new()).await
+}
+
+/// Optimizes the specified nested archive with the specified file options, returning the
+/// names and contents of the entries of the optimized archive.
+async fn optimized_archive_entries_with_file_options(
+	archive: &[u8],
+	file_options: IndexMap<String, FileOptions>
+) -> Vec<(String, Vec<u8>)> {
+	let options_holder = ProcessedSquashOptions::try_from(SquashOptions {
+		pack_directory: PathBuf::new(),
+		global_options: GlobalOptions::default(),
+		file_options
+	})
+	.expect("The test options are assumed to be valid");
+
+	let (_, optimized_archive) = optimize_nested_archive(
+		archive,
+		&options_holder,
+		&PackFileAssetTypeMatcher::new(EnumSet::all())
+	)
+	.await
+	.expect("No error should happen while processing");
+
+	read_zip_entries(&optimized_archive)
+		.expect("The optimized archive should be valid")
+		.into_iter()
+		.map(|entry| (entry.name, entry.data))
+		.collect()
+}
+
+#[tokio::test]
+async fn pack_file_entries_are_optimized() {
+	let archive = stored_archive(&[
+		("assets/mypack/models/item/gem.json", ITEM_MODEL.as_bytes()),
+		("com/example/Mod.class", CLASS_FILE)
+	]);
+
+	assert_eq!(
+		optimized_archive_entries(&archive).await,
+		[
+			(
+				"assets/mypack/models/item/gem.json".to_owned(),
+				br#"{"parent":"minecraft:item/generated","textures":{"layer0":"mypack:item/gem"}}"#
+					.to_vec()
+			),
+			("com/example/Mod.class".to_owned(), CLASS_FILE.to_vec())
+		]
+	);
+}
+
+#[tokio::test]
+async fn signed_archive_entries_are_kept() {
+	let archive = stored_archive(&[
+		("META-INF/MYMOD.SF", b"Signature-Version: 1.0\r\n"),
+		("assets/mypack/models/item/gem.json", ITEM_MODEL.as_bytes())
+	]);
+
+	assert_eq!(
+		optimized_archive_entries(&archive).await,
+		[
+			(
+				"META-INF/MYMOD.SF".to_owned(),
+				b"Signature-Version: 1.0\r\n".to_vec()
+			),
+			(
+				"assets/mypack/models/item/gem.json".to_owned(),
+				ITEM_MODEL.as_bytes().to_vec()
+			)
+		]
+	);
+}
+
+#[tokio::test]
+async fn archives_within_nested_archives_are_optimized() {
+	let inner_archive =
+		stored_archive(&[("assets/mypack/models/item/gem.json", ITEM_MODEL.as_bytes())]);
+	let archive = stored_archive(&[
+		("META-INF/", b""),
+		("META-INF/jars/inner.jar", &inner_archive)
+	]);
+
+	let optimized_entries = optimized_archive_entries_with_file_options(
+		&archive,
+		IndexMap::from([(
+			"**/?*.jar".to_owned(),
+			FileOptions::CustomFileOptions(CustomFileOptions {
+				force_include: true,
+				custom_file_format: CustomFileFormat::ZipArchive
+			})
+		)])
+	)
+	.await;
+
+	assert_eq!(
+		optimized_entries
+			.iter()
+			.map(|(name, _)| name.as_str())
+			.collect::<Vec<_>>(),
+		["META-INF/", "META-INF/jars/inner.jar"]
+	);
+	assert_eq!(
+		read_zip_entries(&optimized_entries[1].1)
+			.expect("The optimized inner archive should be valid")
+			.into_iter()
+			.map(|entry| (entry.name, entry.data))
+			.collect::<Vec<_>>(),
+		[(
+			"assets/mypack/models/item/gem.json".to_owned(),
+			br#"{"parent":"minecraft:item/generated","textures":{"layer0":"mypack:item/gem"}}"#
+				.to_vec()
+		)]
+	);
+}
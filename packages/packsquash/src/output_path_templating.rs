@@ -0,0 +1,183 @@
+//! Contains routines to expand `{name}` placeholders in output file paths with build metadata,
+//! such as the pack format version of the pack or the Git commit it was built from, so that
+//! release artifacts can be named consistently.
+
+use std::{
+	env, fs,
+	path::{Path, PathBuf},
+	process::Command
+};
+
+use indexmap::IndexMap;
+use json_comments::StripComments;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::pack_file::strip_utf8_bom;
+
+#[cfg(test)]
+mod tests;
+
+/// The prefix of placeholders that are expanded with the value of an environment variable.
+const ENVIRONMENT_VARIABLE_PLACEHOLDER_PREFIX: &str = "env:";
+
+/// Represents an error that may happen while expanding an output file path template.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum OutputFilePathTemplateError {
+	#[error("The {{{0}}} placeholder refers to an undefined variable")]
+	UndefinedVariable(String),
+	#[error("The {{{0}}} placeholder refers to an undefined environment variable")]
+	UndefinedEnvironmentVariable(String),
+	#[error("Unbalanced braces. Literal braces must be written as {{{{ or }}}}")]
+	UnbalancedBraces
+}
+
+/// Returns the variables that describe the pack at the specified directory for the specified
+/// output file path template, which are read from its metadata file and the Git repository it
+/// is in:
+///
+/// - `pack_format`: the `pack_format` version of the pack.
+/// - `pack_description`: the description of the pack, if it is a plain string.
+/// - `version`: the `version` of the pack, if its metadata has one that is a string or number.
+/// - `git_hash` and `git_short_hash`: the full and abbreviated hashes of the checked out commit.
+/// - `git_branch`: the name of the checked out branch, unless `HEAD` is detached.
+/// - `git_describe`: the closest tag to the checked out commit, as described by `git describe`.
+///
+/// Only the variables that the template uses are determined, as running Git may be slow, and
+/// have side effects such as starting file system monitors. Variables whose value can't be
+/// determined, for example because the pack is not in a Git repository or Git is not
+/// installed, are not defined.
+pub fn output_file_path_variables(
+	pack_directory: &Path,
+	template: &Path
+) -> IndexMap<String, String> {
+	let mut used_variables = Vec::new();
+	if let Some(template) = template.to_str() {
+		// Invalid templates are reported when they are expanded
+		expand_template(template, |name| {
+			used_variables.push(name.to_owned());
+			Ok(String::new())
+		})
+		.ok();
+	}
+	let is_used = |variable: &str| used_variables.iter().any(|name| name == variable);
+
+	let mut variables = IndexMap::new();
+
+	// Prefer the same pack metadata file as the usual pack metadata validation
+	if ["pack_format", "pack_description", "version"]
+		.into_iter()
+		.any(is_used)
+		&& let Ok(pack_meta) = fs::read(pack_directory.join("pack.mcmetac"))
+			.or_else(|_| fs::read(pack_directory.join("pack.mcmeta")))
+		&& let Ok(Value::Object(mut pack_meta)) =
+			serde_json::from_reader(StripComments::new(strip_utf8_bom(&pack_meta)))
+		&& let Some(Value::Object(pack_metadata)) = pack_meta.remove("pack")
+	{
+		if let Some(Value::Number(pack_format)) = pack_metadata.get("pack_format") {
+			variables.insert("pack_format".into(), pack_format.to_string());
+		}
+
+		if let Some(Value::String(description)) = pack_metadata.get("description") {
+			variables.insert("pack_description".into(), description.clone());
+		}
+
+		match pack_metadata.get("version") {
+			Some(Value::String(version)) => {
+				variables.insert("version".into(), version.clone());
+			}
+			Some(Value::Number(version)) => {
+				variables.insert("version".into(), version.to_string());
+			}
+			_ => {}
+		}
+	}
+
+	for (variable, git_arguments) in [
+		("git_hash", &["rev-parse", "HEAD"][..]),
+		("git_short_hash", &["rev-parse", "--short", "HEAD"]),
+		("git_branch", &["symbolic-ref", "--short", "-q", "HEAD"]),
+		(
+			"git_describe",
+			&["describe", "--tags", "--always", "--dirty"]
+		)
+	] {
+		if is_used(variable)
+			&& let Some(value) = Command::new("git")
+				.args(git_arguments)
+				.current_dir(pack_directory)
+				.output()
+				.ok()
+				.filter(|output| output.status.success())
+				.and_then(|output| String::from_utf8(output.stdout).ok())
+				.and_then(|output| output.lines().next().map(str::to_owned))
+				.filter(|value| !value.is_empty())
+		{
+			variables.insert(variable.into(), value);
+		}
+	}
+
+	variables
+}
+
+/// Expands the `{name}` placeholders of an output file path template with the values of the
+/// specified variables. `{env:NAME}` placeholders are expanded with the value of the `NAME`
+/// environment variable, and `{{` and `}}` stand for literal braces. Path separators in the
+/// expanded values are replaced with dashes, so that they can't change the directory of the
+/// output file. Paths that are not valid UTF-8 are returned as-is.
+pub fn expand_output_file_path(
+	template: &Path,
+	variables: &IndexMap<String, String>
+) -> Result<PathBuf, OutputFilePathTemplateError> {
+	let Some(template) = template.to_str() else {
+		return Ok(template.to_path_buf());
+	};
+
+	expand_template(template, |name| {
+		match name.strip_prefix(ENVIRONMENT_VARIABLE_PLACEHOLDER_PREFIX) {
+			Some(environment_variable) => env::var(environment_variable)
+				.map_err(|_| OutputFilePathTemplateError::UndefinedEnvironmentVariable(name.into())),
+			None => variables
+				.get(name)
+				.cloned()
+				.ok_or_else(|| OutputFilePathTemplateError::UndefinedVariable(name.into()))
+		}
+	})
+	.map(Into::into)
+}
+
+/// Expands the `{name}` placeholders of the specified template with the values returned by
+/// the specified function for their names, in order. Path separators in the values are
+/// replaced with dashes, and `{{` and `}}` stand for literal braces.
+fn expand_template(
+	mut template: &str,
+	mut placeholder_value: impl FnMut(&str) -> Result<String, OutputFilePathTemplateError>
+) -> Result<String, OutputFilePathTemplateError> {
+	let mut expanded_template = String::with_capacity(template.len());
+	while let Some(brace_index) = template.find(['{', '}']) {
+		expanded_template.push_str(&template[..brace_index]);
+
+		let brace = &template[brace_index..brace_index + 1];
+		let after_brace = &template[brace_index + 1..];
+		if after_brace.starts_with(brace) {
+			expanded_template.push_str(brace);
+			template = &after_brace[1..];
+			continue;
+		}
+
+		let name_end = after_brace
+			.find('}')
+			.filter(|_| brace == "{")
+			.ok_or(OutputFilePathTemplateError::UnbalancedBraces)?;
+		expanded_template.extend(
+			placeholder_value(&after_brace[..name_end])?
+				.chars()
+				.map(|c| if matches!(c, '/' | '\\') { '-' } else { c })
+		);
+
+		template = &after_brace[name_end + 1..];
+	}
+	expanded_template.push_str(template);
+
+	Ok(expanded_template)
+}
@@ -0,0 +1,120 @@
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use super::*;
+
+/// Expands the specified output file path template with some example variables.
+fn expand(template: &str) -> Result<PathBuf, OutputFilePathTemplateError> {
+	expand_output_file_path(
+		Path::new(template),
+		&IndexMap::from([
+			("version".into(), "1.2.0".into()),
+			("date".into(), "2024-06-01".into()),
+			("git_branch".into(), "feature/sounds".into())
+		])
+	)
+}
+
+#[test]
+fn placeholders_are_expanded() {
+	assert_eq!(
+		expand("out/MyPack-{version}-{date}.zip"),
+		Ok("out/MyPack-1.2.0-2024-06-01.zip".into())
+	);
+	assert_eq!(expand("pack.zip"), Ok("pack.zip".into()));
+	assert_eq!(
+		expand("MyPack-{git_branch}.zip"),
+		Ok("MyPack-feature-sounds.zip".into())
+	);
+	assert_eq!(expand("{{{version}}}.zip"), Ok("{1.2.0}.zip".into()));
+}
+
+#[test]
+fn environment_variable_placeholders_are_expanded() {
+	let path = env::var("PATH").expect("The PATH environment variable is assumed to be defined");
+
+	assert_eq!(
+		expand("{env:PATH}.zip"),
+		Ok(format!("{}.zip", path.replace(['/', '\\'], "-")).into())
+	);
+	assert_eq!(
+		expand("{env:PACKSQUASH_UNDEFINED_TEST_VARIABLE}.zip"),
+		Err(OutputFilePathTemplateError::UndefinedEnvironmentVariable(
+			"env:PACKSQUASH_UNDEFINED_TEST_VARIABLE".into()
+		))
+	);
+}
+
+#[test]
+fn invalid_templates_are_rejected() {
+	assert_eq!(
+		expand("MyPack-{commit}.zip"),
+		Err(OutputFilePathTemplateError::UndefinedVariable(
+			"commit".into()
+		))
+	);
+
+	for template in ["MyPack-{version.zip", "MyPack-}.zip", "MyPack-{.zip"] {
+		assert_eq!(
+			expand(template),
+			Err(OutputFilePathTemplateError::UnbalancedBraces),
+			"{template} should be invalid"
+		);
+	}
+}
+
+#[test]
+fn pack_metadata_variables_are_read() {
+	let pack_directory = Builder::new()
+		.prefix("ps-output-path-templating-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	fs::write(
+		pack_directory.path().join("pack.mcmeta"),
+		"{\n\t// Comments are allowed\n\t\"pack\": { \"pack_format\": 34, \"description\": \"My pack\", \"version\": \"1.0\" }\n}"
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+
+	let variables = output_file_path_variables(
+		pack_directory.path(),
+		Path::new("{pack_description}-{version}-{pack_format}.zip")
+	);
+
+	assert_eq!(variables.get("pack_format").map(String::as_str), Some("34"));
+	assert_eq!(
+		variables.get("pack_description").map(String::as_str),
+		Some("My pack")
+	);
+	assert_eq!(variables.get("version").map(String::as_str), Some("1.0"));
+
+	fs::write(
+		pack_directory.path().join("pack.mcmetac"),
+		"{ \"pack\": { \"pack_format\": 46 } }"
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+
+	assert_eq!(
+		output_file_path_variables(pack_directory.path(), Path::new("{pack_format}.zip")),
+		IndexMap::from([("pack_format".to_string(), "46".to_string())]),
+		"The compacted pack metadata file should be preferred"
+	);
+}
+
+#[test]
+fn unused_variables_are_not_determined() {
+	let pack_directory = Builder::new()
+		.prefix("ps-output-path-templating-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	fs::write(
+		pack_directory.path().join("pack.mcmeta"),
+		"{ \"pack\": { \"pack_format\": 34 } }"
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+
+	assert!(
+		output_file_path_variables(pack_directory.path(), Path::new("pack-{{git_hash}}.zip"))
+			.is_empty(),
+		"Only the variables of placeholders should be determined"
+	);
+}
@@ -0,0 +1,209 @@
+//! Contains routines to compare two builds of a pack, which may be ZIP files generated by
+//! PackSquash or pack directories, so that the changes a release makes can be reviewed.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::pack_file::zip_archive::visit_zip_entries;
+use crate::run_manifest::FileDigest;
+use crate::vfs::os_fs::OsFilesystem;
+use crate::vfs::{IteratorTraversalOptions, VirtualFileSystem};
+
+#[cfg(test)]
+mod tests;
+
+/// Represents an error that may happen while comparing two pack builds.
+#[derive(Error, Debug)]
+pub enum PackDiffError {
+	#[error("Couldn't read {0}: {1}")]
+	Io(PathBuf, io::Error),
+	#[error("Couldn't read the ZIP file {0}: {1}")]
+	InvalidZip(PathBuf, String)
+}
+
+/// How a file differs between two pack builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackFileChange {
+	/// The file is only in the new build.
+	Added,
+	/// The file is only in the old build.
+	Removed,
+	/// The file is in both builds, but its contents are different.
+	Changed
+}
+
+/// A file that differs between two pack builds.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PackFileDiff {
+	path: String,
+	old_digest: Option<FileDigest>,
+	new_digest: Option<FileDigest>
+}
+
+impl PackFileDiff {
+	/// Gets the relative path of the file within the pack builds.
+	pub fn path(&self) -> &str {
+		&self.path
+	}
+
+	/// Gets how the file differs between the pack builds.
+	pub const fn change(&self) -> PackFileChange {
+		match (&self.old_digest, &self.new_digest) {
+			(None, _) => PackFileChange::Added,
+			(_, None) => PackFileChange::Removed,
+			_ => PackFileChange::Changed
+		}
+	}
+
+	/// Gets the size of the file in the old build, in bytes, or `None` if it was added.
+	pub fn old_size(&self) -> Option<u64> {
+		self.old_digest.as_ref().map(FileDigest::size)
+	}
+
+	/// Gets the size of the file in the new build, in bytes, or `None` if it was removed.
+	pub fn new_size(&self) -> Option<u64> {
+		self.new_digest.as_ref().map(FileDigest::size)
+	}
+
+	/// Gets the lowercase hexadecimal SHA-256 hash of the file in the old build, or `None`
+	/// if it was added.
+	pub fn old_sha256(&self) -> Option<&str> {
+		self.old_digest.as_ref().map(FileDigest::sha256)
+	}
+
+	/// Gets the lowercase hexadecimal SHA-256 hash of the file in the new build, or `None`
+	/// if it was removed.
+	pub fn new_sha256(&self) -> Option<&str> {
+		self.new_digest.as_ref().map(FileDigest::sha256)
+	}
+}
+
+/// The differences between two pack builds.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PackDiff {
+	file_diffs: Vec<PackFileDiff>,
+	unchanged_file_count: usize,
+	old_total_size: u64,
+	new_total_size: u64
+}
+
+impl PackDiff {
+	/// Gets the files that differ between the pack builds, sorted by path.
+	pub fn file_diffs(&self) -> &[PackFileDiff] {
+		&self.file_diffs
+	}
+
+	/// Gets the number of files that are the same in both pack builds.
+	pub const fn unchanged_file_count(&self) -> usize {
+		self.unchanged_file_count
+	}
+
+	/// Gets the total size of the files of the old build, in bytes. For ZIP files, this is
+	/// the size of their files once decompressed.
+	pub const fn old_total_size(&self) -> u64 {
+		self.old_total_size
+	}
+
+	/// Gets the total size of the files of the new build, in bytes. For ZIP files, this is
+	/// the size of their files once decompressed.
+	pub const fn new_total_size(&self) -> u64 {
+		self.new_total_size
+	}
+}
+
+/// Compares the files of the old and new pack builds at the specified paths, each of which
+/// may be a ZIP file, such as one generated by PackSquash, or a pack directory. Files are
+/// matched by their relative paths, and their contents are compared by their SHA-256 hashes.
+///
+/// A generated ZIP file can be compared with the pack directory it was generated from, but
+/// most of its files will differ, as PackSquash optimizes them.
+pub fn diff_packs(old_pack_path: &Path, new_pack_path: &Path) -> Result<PackDiff, PackDiffError> {
+	Ok(diff_pack_file_digests(
+		pack_file_digests(old_pack_path)?,
+		pack_file_digests(new_pack_path)?
+	))
+}
+
+/// Compares the specified digests of the files of two pack builds, keyed by relative path.
+fn diff_pack_file_digests(
+	old_file_digests: BTreeMap<String, FileDigest>,
+	mut new_file_digests: BTreeMap<String, FileDigest>
+) -> PackDiff {
+	let old_total_size = old_file_digests.values().map(FileDigest::size).sum();
+	let new_total_size = new_file_digests.values().map(FileDigest::size).sum();
+
+	let mut file_diffs = vec![];
+	let mut unchanged_file_count = 0;
+	for (path, old_digest) in old_file_digests {
+		match new_file_digests.remove(&path) {
+			Some(new_digest) if new_digest == old_digest => unchanged_file_count += 1,
+			new_digest => file_diffs.push(PackFileDiff {
+				path,
+				old_digest: Some(old_digest),
+				new_digest
+			})
+		}
+	}
+
+	file_diffs.extend(
+		new_file_digests
+			.into_iter()
+			.map(|(path, new_digest)| PackFileDiff {
+				path,
+				old_digest: None,
+				new_digest: Some(new_digest)
+			})
+	);
+	file_diffs.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+	PackDiff {
+		file_diffs,
+		unchanged_file_count,
+		old_total_size,
+		new_total_size
+	}
+}
+
+/// Computes the digests of the files of the pack build at the specified path, which may be a
+/// ZIP file or a pack directory, keyed by their relative path. System and hidden files of pack
+/// directories and directory entries of ZIP files are ignored, as they are not pack files.
+/// Files are read and hashed one at a time, in chunks, so that big packs can be compared
+/// without holding them in memory.
+fn pack_file_digests(pack_path: &Path) -> Result<BTreeMap<String, FileDigest>, PackDiffError> {
+	if pack_path.is_dir() {
+		OsFilesystem
+			.file_iterator(
+				pack_path,
+				IteratorTraversalOptions {
+					ignore_system_and_hidden_files: true
+				}
+			)
+			.map(|entry| {
+				let entry = entry.map_err(|err| PackDiffError::Io(pack_path.into(), err))?;
+				let digest = File::open(&entry.file_path)
+					.and_then(FileDigest::of_reader)
+					.map_err(|err| PackDiffError::Io(entry.file_path.clone(), err))?;
+
+				Ok((entry.relative_path.as_str().into(), digest))
+			})
+			.collect()
+	} else {
+		let zip = File::open(pack_path).map_err(|err| PackDiffError::Io(pack_path.into(), err))?;
+
+		let mut file_digests = BTreeMap::new();
+		visit_zip_entries(BufReader::new(zip), |name, data| {
+			if !name.ends_with('/') {
+				file_digests.insert(name, FileDigest::of_reader(data)?);
+			}
+
+			Ok(())
+		})
+		.map_err(|err| PackDiffError::InvalidZip(pack_path.into(), err.to_string()))?;
+
+		Ok(file_digests)
+	}
+}
@@ -0,0 +1,117 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use super::*;
+use crate::pack_file::zip_archive::{ZipEntry, write_zip};
+
+/// Computes the digests of the specified pack files.
+fn file_digests<'data>(
+	files: impl IntoIterator<Item = (&'static str, &'data [u8])>
+) -> BTreeMap<String, FileDigest> {
+	files
+		.into_iter()
+		.map(|(path, data)| (path.into(), FileDigest::of(data)))
+		.collect()
+}
+
+#[test]
+fn added_removed_and_changed_files_are_reported() {
+	let pack_diff = diff_pack_file_digests(
+		file_digests([
+			("pack.mcmeta", &b"{}"[..]),
+			("assets/minecraft/lang/en_us.json", b"{\"a\":\"b\"}"),
+			("assets/minecraft/textures/old.png", b"old")
+		]),
+		file_digests([
+			("pack.mcmeta", &b"{}"[..]),
+			("assets/minecraft/lang/en_us.json", b"{\"a\":\"bc\"}"),
+			("assets/minecraft/textures/new.png", b"newer")
+		])
+	);
+
+	assert_eq!(
+		pack_diff
+			.file_diffs()
+			.iter()
+			.map(|file_diff| (
+				file_diff.path(),
+				file_diff.change(),
+				file_diff.old_size(),
+				file_diff.new_size()
+			))
+			.collect::<Vec<_>>(),
+		[
+			(
+				"assets/minecraft/lang/en_us.json",
+				PackFileChange::Changed,
+				Some(9),
+				Some(10)
+			),
+			(
+				"assets/minecraft/textures/new.png",
+				PackFileChange::Added,
+				None,
+				Some(5)
+			),
+			(
+				"assets/minecraft/textures/old.png",
+				PackFileChange::Removed,
+				Some(3),
+				None
+			)
+		]
+	);
+	assert_eq!(pack_diff.unchanged_file_count(), 1);
+	assert_eq!(pack_diff.old_total_size(), 14);
+	assert_eq!(pack_diff.new_total_size(), 17);
+
+	let changed_file = &pack_diff.file_diffs()[0];
+	assert_eq!(
+		changed_file.old_sha256(),
+		Some(FileDigest::of(b"{\"a\":\"b\"}").sha256())
+	);
+	assert_ne!(changed_file.old_sha256(), changed_file.new_sha256());
+}
+
+#[test]
+fn zip_files_can_be_compared_with_directories() {
+	let pack_directory = Builder::new()
+		.prefix("ps-pack-diff-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	fs::create_dir(pack_directory.path().join("assets"))
+		.expect("I/O operations are assumed not to fail during tests");
+	fs::write(pack_directory.path().join("pack.mcmeta"), "{}")
+		.expect("I/O operations are assumed not to fail during tests");
+	fs::write(pack_directory.path().join("assets/sounds.json"), "{ }")
+		.expect("I/O operations are assumed not to fail during tests");
+
+	let zip_path = pack_directory.path().with_extension("zip");
+	fs::write(
+		&zip_path,
+		write_zip(
+			&[("pack.mcmeta", "{}"), ("assets/sounds.json", "{}")].map(|(name, data)| ZipEntry {
+				name: name.into(),
+				data: data.into()
+			}),
+			0
+		)
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+
+	let pack_diff = diff_packs(pack_directory.path(), &zip_path);
+	fs::remove_file(&zip_path).ok();
+	let pack_diff = pack_diff.expect("The pack builds should be readable");
+
+	assert_eq!(
+		pack_diff
+			.file_diffs()
+			.iter()
+			.map(|file_diff| (file_diff.path(), file_diff.change()))
+			.collect::<Vec<_>>(),
+		[("assets/sounds.json", PackFileChange::Changed)]
+	);
+	assert_eq!(pack_diff.unchanged_file_count(), 1);
+}
@@ -11,6 +11,12 @@ use thiserror::Error;
 use tokio::io::AsyncRead;
 use tokio_stream::Stream;
 
+pub use audio_file::{
+	AudioAnalysis, AudioDiagnostics, analyze_audio, concatenate_audio, decoded_audio_fingerprint,
+	is_ogg_extension, output_channel_count
+};
+pub use json_file::blank_out_relaxed_syntax;
+pub(crate) use png_file::AnimationFrameSize;
 pub use util::strip_utf8_bom;
 
 use crate::pack_file::asset_type::PackFileAssetType;
@@ -18,8 +24,11 @@ use crate::squash_zip::FileListingCircumstances;
 
 pub mod asset_type;
 
+mod resource_budget;
 mod util;
+pub(crate) mod zip_archive;
 
+mod anvil_region_file;
 mod audio_file;
 mod command_function_file;
 mod compressed_compound_nbt_tag_file;
@@ -28,6 +37,8 @@ mod legacy_lang_file;
 mod passthrough_file;
 mod png_file;
 mod shader_file;
+mod toml_file;
+mod zipped_unifont_hex_file;
 
 #[cfg(feature = "optifine")]
 #[doc(cfg(feature = "optifine"))]
@@ -47,6 +58,10 @@ pub enum OptimizationError {
 	LegacyLanguageFile(#[from] legacy_lang_file::OptimizationError),
 	CommandFunctionFile(#[from] command_function_file::OptimizationError),
 	CompressedCompoundNbtTagFile(#[from] compressed_compound_nbt_tag_file::OptimizationError),
+	AnvilRegionFile(#[from] anvil_region_file::OptimizationError),
+	TomlFile(#[from] toml_file::OptimizationError),
+	ZippedUnifontHexFile(#[from] zipped_unifont_hex_file::OptimizationError),
+	ZipArchive(#[from] zip_archive::ZipArchiveError),
 	IoError(#[from] io::Error)
 }
 
@@ -0,0 +1,292 @@
+use bytes::BytesMut;
+use flate2::bufread::{MultiGzDecoder, ZlibDecoder};
+use std::borrow::Cow;
+use std::io::{self, Read, Write};
+use std::num::NonZeroU64;
+use thiserror::Error;
+use tokio::io::AsyncRead;
+use tokio_util::codec::{Decoder, FramedRead};
+
+#[cfg(test)]
+mod tests;
+
+use super::{AsyncReadAndSizeHint, PackFile, PackFileConstructor};
+use crate::config::CompressedCompoundNbtTagFileOptions;
+use crate::pack_file::asset_type::PackFileAssetType;
+use crate::zopfli_iterations_time_model::ZopfliIterationsTimeModel;
+
+/// The size of the sectors that region files are divided in, in bytes.
+const SECTOR_SIZE: usize = 4096;
+
+/// The number of chunks that a region file may contain.
+const CHUNK_COUNT: usize = 32 * 32;
+
+/// The chunk compression type for gzip-compressed chunk data.
+const GZIP_COMPRESSION_TYPE: u8 = 1;
+
+/// The chunk compression type for zlib-compressed chunk data.
+const ZLIB_COMPRESSION_TYPE: u8 = 2;
+
+/// The chunk compression type for uncompressed chunk data, added in 1.15.1-pre1.
+const UNCOMPRESSED_COMPRESSION_TYPE: u8 = 3;
+
+/// Represents a region file in the Anvil format, which stores the compressed NBT data of up to
+/// 32x32 chunks of a Minecraft world, divided in 4 KiB sectors.
+///
+/// These files are found in the `region`, `entities` and `poi` folders of worlds. The
+/// compressed NBT data of each chunk is recompressed with zlib, the compression method the
+/// vanilla game uses by default, and the chunks are stored again without unused sectors
+/// between them. Chunks compressed with methods the game does not always support, such as
+/// LZ4, and chunks stored in external files are kept as-is.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Region_file_format>
+/// - Minecraft's 1.20.5 `net.minecraft.world.level.chunk.storage.RegionFile` class
+pub struct AnvilRegionFile<T: AsyncRead + Send + Unpin + 'static> {
+	read: T,
+	file_length_hint: usize,
+	optimization_settings: CompressedCompoundNbtTagFileOptions
+}
+
+pub struct OptimizerDecoder {
+	optimization_settings: CompressedCompoundNbtTagFileOptions,
+	reached_eof: bool
+}
+
+/// Represents an error that may happen while optimizing Anvil region files.
+#[derive(Error, Debug)]
+pub enum OptimizationError {
+	#[error("Invalid region file: {0}")]
+	InvalidRegion(Cow<'static, str>),
+	#[error("NBT serialization error: {0}")]
+	Nbt(#[from] fastnbt::error::Error),
+	#[error("I/O error: {0}")]
+	Io(#[from] io::Error)
+}
+
+impl Decoder for OptimizerDecoder {
+	type Item = (Cow<'static, str>, Vec<u8>);
+	type Error = OptimizationError;
+
+	fn decode(&mut self, _: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+		Ok(None)
+	}
+
+	fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+		// This method will be called when EOF is reached until it returns None. Because we
+		// will only ever output a single item in the stream, always return None if we have
+		// executed once already
+		if self.reached_eof {
+			return Ok(None);
+		}
+		self.reached_eof = true;
+
+		let region = &**src;
+
+		// Minecraft may create empty region files, which it treats as regions without chunks
+		if region.is_empty() {
+			return Ok(Some((Cow::Borrowed("Copied empty region"), vec![])));
+		}
+
+		// The header is made up of a sector with the location of every chunk, followed by
+		// a sector with their last modification timestamps, which are kept as-is
+		let header = region
+			.get(..2 * SECTOR_SIZE)
+			.ok_or(OptimizationError::InvalidRegion(Cow::Borrowed(
+				"The header is truncated"
+			)))?;
+		let mut optimized_region = header.to_vec();
+
+		let zopfli_iterations_time_model = ZopfliIterationsTimeModel::new(
+			self.optimization_settings.nbt_compression_iterations,
+			2.0
+		);
+
+		let mut recompressed_chunk_count = 0;
+		for (chunk_index, location) in header[..SECTOR_SIZE].chunks_exact(4).enumerate() {
+			let sector_offset =
+				u32::from_be_bytes([0, location[0], location[1], location[2]]) as usize;
+			let sector_count = location[3];
+
+			// Chunks that were not generated yet have no location
+			if sector_offset == 0 && sector_count == 0 {
+				continue;
+			}
+
+			let invalid_chunk_error = || {
+				OptimizationError::InvalidRegion(Cow::Owned(format!(
+					"The data of the chunk at index {chunk_index} is out of bounds or truncated"
+				)))
+			};
+
+			// The chunk data starts with its length, which includes the compression type byte
+			// that follows it
+			let chunk_start = sector_offset * SECTOR_SIZE;
+			let chunk_length = region
+				.get(chunk_start..chunk_start + 4)
+				.map(|length| u32::from_be_bytes(length.try_into().unwrap()) as usize)
+				.ok_or_else(invalid_chunk_error)?;
+
+			// Minecraft treats chunks that have a location but no data as not generated, so
+			// drop their location and timestamp instead of rejecting the whole region
+			if chunk_length == 0 {
+				optimized_region[chunk_index * 4..chunk_index * 4 + 4].fill(0);
+				optimized_region[SECTOR_SIZE + chunk_index * 4..SECTOR_SIZE + chunk_index * 4 + 4]
+					.fill(0);
+				continue;
+			}
+
+			let chunk_payload = region
+				.get(chunk_start + 4..chunk_start + 4 + chunk_length)
+				.ok_or_else(invalid_chunk_error)?;
+			let (&compression_type, compressed_nbt) = chunk_payload
+				.split_first()
+				.ok_or_else(invalid_chunk_error)?;
+
+			let optimized_chunk_payload = match compression_type {
+				GZIP_COMPRESSION_TYPE | ZLIB_COMPRESSION_TYPE | UNCOMPRESSED_COMPRESSION_TYPE => {
+					let nbt = decompress_chunk_nbt(compression_type, compressed_nbt)?;
+
+					// Make sure the chunk data parses as NBT before storing it again
+					fastnbt::from_bytes::<fastnbt::Value>(&nbt)?;
+
+					let mut recompressed_chunk_payload = vec![ZLIB_COMPRESSION_TYPE];
+					compress_chunk_nbt(
+						&nbt,
+						zopfli_iterations_time_model.iterations_for_data_size(
+							nbt.len().try_into().unwrap_or(u32::MAX),
+							0,
+							20
+						),
+						&mut recompressed_chunk_payload
+					)?;
+
+					if recompressed_chunk_payload.len() < chunk_payload.len() {
+						recompressed_chunk_count += 1;
+						Cow::Owned(recompressed_chunk_payload)
+					} else {
+						Cow::Borrowed(chunk_payload)
+					}
+				}
+				// Other compression types, including those of chunks stored in external files,
+				// which have their most significant bit set, are kept as-is
+				_ => Cow::Borrowed(chunk_payload)
+			};
+
+			// Store the chunk right after the previous one, padding it to a whole number of
+			// sectors as Minecraft expects
+			let optimized_sector_offset = optimized_region.len() / SECTOR_SIZE;
+			optimized_region.extend_from_slice(&(optimized_chunk_payload.len() as u32).to_be_bytes());
+			optimized_region.extend_from_slice(&optimized_chunk_payload);
+			optimized_region.resize(optimized_region.len().next_multiple_of(SECTOR_SIZE), 0);
+			let optimized_sector_count =
+				optimized_region.len() / SECTOR_SIZE - optimized_sector_offset;
+
+			let optimized_sector_offset = u32::try_from(optimized_sector_offset)
+				.ok()
+				.filter(|offset| *offset < 1 << 24)
+				.ok_or_else(invalid_chunk_error)?;
+			let optimized_sector_count =
+				u8::try_from(optimized_sector_count).map_err(|_| invalid_chunk_error())?;
+
+			let [_, offset_high, offset_middle, offset_low] = optimized_sector_offset.to_be_bytes();
+			optimized_region[chunk_index * 4..chunk_index * 4 + 4].copy_from_slice(&[
+				offset_high,
+				offset_middle,
+				offset_low,
+				optimized_sector_count
+			]);
+		}
+
+		Ok(Some((
+			if recompressed_chunk_count > 0 {
+				Cow::Owned(format!(
+					"Validated, recompressed {recompressed_chunk_count} chunks and compacted"
+				))
+			} else {
+				Cow::Borrowed("Validated and compacted")
+			},
+			optimized_region
+		)))
+	}
+}
+
+impl<T: AsyncRead + Send + Unpin + 'static> PackFile for AnvilRegionFile<T> {
+	type ByteChunkType = Vec<u8>;
+	type OptimizationError = OptimizationError;
+	type OptimizedByteChunksStream = FramedRead<T, OptimizerDecoder>;
+
+	fn process(self) -> Self::OptimizedByteChunksStream {
+		FramedRead::with_capacity(
+			self.read,
+			OptimizerDecoder {
+				optimization_settings: self.optimization_settings,
+				reached_eof: false
+			},
+			self.file_length_hint
+		)
+	}
+
+	fn is_compressed(&self) -> bool {
+		true
+	}
+}
+
+impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for AnvilRegionFile<T> {
+	type OptimizationSettings = CompressedCompoundNbtTagFileOptions;
+
+	fn new(
+		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<T>>,
+		_: PackFileAssetType,
+		optimization_settings: Self::OptimizationSettings
+	) -> Option<Self> {
+		file_read_producer().map(|(read, size_hint)| Self {
+			read,
+			file_length_hint: size_hint.try_into().unwrap_or(usize::MAX),
+			optimization_settings
+		})
+	}
+}
+
+/// Decompresses the NBT data of a chunk compressed with the specified compression type, which
+/// must be gzip, zlib or no compression.
+fn decompress_chunk_nbt(compression_type: u8, compressed_nbt: &[u8]) -> io::Result<Vec<u8>> {
+	let mut nbt = Vec::with_capacity(compressed_nbt.len() * 4);
+
+	match compression_type {
+		GZIP_COMPRESSION_TYPE => MultiGzDecoder::new(compressed_nbt).read_to_end(&mut nbt)?,
+		ZLIB_COMPRESSION_TYPE => ZlibDecoder::new(compressed_nbt).read_to_end(&mut nbt)?,
+		_ => {
+			nbt.extend_from_slice(compressed_nbt);
+			nbt.len()
+		}
+	};
+
+	Ok(nbt)
+}
+
+/// Compresses the specified chunk NBT data with zlib, using Zopfli or, if the Zopfli iteration
+/// count is zero, the best flate2 compression.
+fn compress_chunk_nbt(
+	nbt: &[u8],
+	zopfli_iteration_count: u8,
+	mut output: impl Write
+) -> io::Result<()> {
+	match NonZeroU64::new(zopfli_iteration_count as u64) {
+		Some(iteration_count) => zopfli::compress(
+			zopfli::Options {
+				iteration_count,
+				..zopfli::Options::default()
+			},
+			zopfli::Format::Zlib,
+			nbt,
+			output
+		),
+		None => {
+			let mut zlib_encoder =
+				flate2::write::ZlibEncoder::new(&mut output, flate2::Compression::best());
+			zlib_encoder.write_all(nbt)?;
+			zlib_encoder.finish().map(|_| ())
+		}
+	}
+}
@@ -0,0 +1,176 @@
+use super::*;
+
+use futures::StreamExt;
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+use tokio_test::io::Builder;
+
+/// Processes the given input data as an [AnvilRegionFile] with the default settings, returning
+/// the optimized data.
+async fn process(input: &[u8]) -> Vec<u8> {
+	AnvilRegionFile {
+		read: Builder::new().read(input).build(),
+		file_length_hint: input.len(),
+		optimization_settings: CompressedCompoundNbtTagFileOptions::default()
+	}
+	.process()
+	.map(|result| tokio_stream::iter(result.expect("No error should happen while processing").1))
+	.flatten()
+	.collect::<Vec<u8>>()
+	.await
+}
+
+/// Serializes a chunk NBT compound tag with some repetitive data.
+fn chunk_nbt(x_position: i32) -> Vec<u8> {
+	let mut chunk = HashMap::new();
+	chunk.insert("xPos".to_string(), fastnbt::Value::Int(x_position));
+	chunk.insert(
+		"Status".to_string(),
+		fastnbt::Value::String("minecraft:full".repeat(64))
+	);
+
+	fastnbt::to_bytes(&chunk).unwrap()
+}
+
+/// Builds a region file whose chunks are stored at the specified sector offsets, with the
+/// specified compression type and payload.
+fn region(chunks: &[(usize, u8, u32, Vec<u8>)]) -> Vec<u8> {
+	let mut region = vec![0; 2 * SECTOR_SIZE];
+
+	for (chunk_index, compression_type, sector_offset, payload) in chunks {
+		let chunk_start = *sector_offset as usize * SECTOR_SIZE;
+		let chunk_end = (chunk_start + 5 + payload.len()).next_multiple_of(SECTOR_SIZE);
+		region.resize(region.len().max(chunk_end), 0);
+
+		region[chunk_start..chunk_start + 4]
+			.copy_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+		region[chunk_start + 4] = *compression_type;
+		region[chunk_start + 5..chunk_start + 5 + payload.len()].copy_from_slice(payload);
+
+		let [_, offset_high, offset_middle, offset_low] = sector_offset.to_be_bytes();
+		region[chunk_index * 4..chunk_index * 4 + 4].copy_from_slice(&[
+			offset_high,
+			offset_middle,
+			offset_low,
+			((chunk_end - chunk_start) / SECTOR_SIZE) as u8
+		]);
+		region[SECTOR_SIZE + chunk_index * 4..SECTOR_SIZE + chunk_index * 4 + 4]
+			.copy_from_slice(&(*sector_offset * 1000).to_be_bytes());
+	}
+
+	region
+}
+
+/// Reads the decompressed NBT data of every chunk in the specified region file, alongside
+/// their timestamps.
+fn chunks(region: &[u8]) -> Vec<(usize, u32, Vec<u8>)> {
+	(0..CHUNK_COUNT)
+		.filter_map(|chunk_index| {
+			let location = &region[chunk_index * 4..chunk_index * 4 + 4];
+			let sector_offset =
+				u32::from_be_bytes([0, location[0], location[1], location[2]]) as usize;
+			(sector_offset != 0).then(|| {
+				let chunk_start = sector_offset * SECTOR_SIZE;
+				let chunk_length =
+					u32::from_be_bytes(region[chunk_start..chunk_start + 4].try_into().unwrap())
+						as usize;
+				let timestamp = u32::from_be_bytes(
+					region[SECTOR_SIZE + chunk_index * 4..SECTOR_SIZE + chunk_index * 4 + 4]
+						.try_into()
+						.unwrap()
+				);
+
+				let (&compression_type, compressed_nbt) = region
+					[chunk_start + 4..chunk_start + 4 + chunk_length]
+					.split_first()
+					.unwrap();
+
+				(
+					chunk_index,
+					timestamp,
+					decompress_chunk_nbt(compression_type, compressed_nbt).unwrap()
+				)
+			})
+		})
+		.collect()
+}
+
+#[tokio::test]
+async fn chunks_are_recompressed_and_compacted() {
+	let mut gzip_chunk = vec![];
+	let mut gzip_encoder =
+		flate2::write::GzEncoder::new(&mut gzip_chunk, flate2::Compression::fast());
+	gzip_encoder.write_all(&chunk_nbt(1)).unwrap();
+	gzip_encoder.finish().unwrap();
+
+	let input = region(&[
+		(0, UNCOMPRESSED_COMPRESSION_TYPE, 2, chunk_nbt(0)),
+		(1, GZIP_COMPRESSION_TYPE, 6, gzip_chunk),
+		(32, ZLIB_COMPRESSION_TYPE | 128, 9, vec![])
+	]);
+
+	let optimized_region = process(&input).await;
+
+	assert!(
+		optimized_region.len() < input.len(),
+		"Unused sectors should be removed"
+	);
+	assert_eq!(optimized_region.len() % SECTOR_SIZE, 0);
+	assert_eq!(
+		chunks(&optimized_region)
+			.into_iter()
+			.map(|(chunk_index, timestamp, _)| (chunk_index, timestamp))
+			.collect::<Vec<_>>(),
+		vec![(0, 2000), (1, 6000), (32, 9000)]
+	);
+	for (chunk_index, _, nbt) in chunks(&optimized_region).into_iter().take(2) {
+		assert_eq!(
+			fastnbt::from_bytes::<fastnbt::Value>(&nbt).unwrap(),
+			fastnbt::from_bytes::<fastnbt::Value>(&chunk_nbt(chunk_index as i32)).unwrap()
+		);
+	}
+}
+
+#[tokio::test]
+async fn empty_region_is_copied() {
+	assert_eq!(process(&[]).await, Vec::<u8>::new());
+}
+
+#[tokio::test]
+async fn zero_length_chunk_is_skipped() {
+	let mut input = region(&[
+		(0, ZLIB_COMPRESSION_TYPE, 2, vec![]),
+		(1, UNCOMPRESSED_COMPRESSION_TYPE, 3, chunk_nbt(1))
+	]);
+	input[2 * SECTOR_SIZE..2 * SECTOR_SIZE + 4].fill(0);
+
+	let optimized_region = process(&input).await;
+
+	assert_eq!(&optimized_region[..4], [0; 4]);
+	assert_eq!(&optimized_region[SECTOR_SIZE..SECTOR_SIZE + 4], [0; 4]);
+	assert_eq!(
+		chunks(&optimized_region)
+			.into_iter()
+			.map(|(chunk_index, timestamp, _)| (chunk_index, timestamp))
+			.collect::<Vec<_>>(),
+		vec![(1, 3000)]
+	);
+}
+
+#[tokio::test]
+async fn truncated_chunk_is_rejected() {
+	let mut input = region(&[(0, ZLIB_COMPRESSION_TYPE, 2, vec![0; 16])]);
+	input.truncate(2 * SECTOR_SIZE + 8);
+
+	let result = AnvilRegionFile {
+		read: Builder::new().read(&input).build(),
+		file_length_hint: input.len(),
+		optimization_settings: CompressedCompoundNbtTagFileOptions::default()
+	}
+	.process()
+	.next()
+	.await
+	.expect("A result should be produced");
+
+	assert!(matches!(result, Err(OptimizationError::InvalidRegion(_))));
+}
@@ -6,10 +6,12 @@ use std::{borrow::Cow, fmt::Debug};
 use enumset::{EnumSet, EnumSetType};
 use futures::StreamExt;
 use globset::{Glob, GlobSet, GlobSetBuilder};
-use tokio::io::AsyncRead;
+use tokio::io::{AsyncRead, Empty};
 
 use super::{AsyncReadAndSizeHint, PackFile, PackFileConstructor, PackFileProcessData};
-use crate::config::GlobalOptions;
+use crate::config::{FileTypeRoute, GlobalOptions, ProcessedSquashOptions};
+use crate::file_options_matching::FileOptionsGlobSet;
+use crate::pack_file::anvil_region_file::AnvilRegionFile;
 use crate::pack_file::audio_file::AudioFile;
 use crate::pack_file::command_function_file::CommandFunctionFile;
 use crate::pack_file::compressed_compound_nbt_tag_file::CompressedCompoundNbtTagFile;
@@ -20,10 +22,12 @@ use crate::pack_file::png_file::PngFile;
 #[cfg(feature = "optifine")]
 use crate::pack_file::properties_file::PropertiesFile;
 use crate::pack_file::shader_file::ShaderFile;
+use crate::pack_file::toml_file::TomlFile;
+use crate::pack_file::zipped_unifont_hex_file::ZippedUnifontHexFile;
 use crate::squash_zip::FileListingCircumstances;
 use crate::{
 	RelativePath,
-	config::{CustomFileOptions, FileOptions, compile_pack_file_glob_pattern}
+	config::{CustomFileFormat, CustomFileOptions, FileOptions, compile_pack_file_glob_pattern}
 };
 
 /// Represents a relevant pack file asset type, stored in a pack file. A [`PackFile`] can
@@ -50,6 +54,21 @@ pub enum PackFileAssetType {
 	/// A Minecraft block or entity model in vanilla format, maybe with comments and
 	/// `.jsonc` extension.
 	MinecraftModelWithComments,
+	/// A Minecraft blockstate definition, with `.json` extension. These files map the states
+	/// of a block to the models it is rendered with.
+	MinecraftBlockState,
+	/// A Minecraft blockstate definition, maybe with comments and `.jsonc` extension.
+	MinecraftBlockStateWithComments,
+	/// A Minecraft item model definition, with `.json` extension. These files define the
+	/// models an item is rendered with, depending on its properties and how it is displayed.
+	MinecraftItemModelDefinition,
+	/// A Minecraft item model definition, maybe with comments and `.jsonc` extension.
+	MinecraftItemModelDefinitionWithComments,
+	/// A Minecraft data pack tag, with `.json` extension. These files group blocks, items,
+	/// functions and other registry elements under a name.
+	MinecraftTag,
+	/// A Minecraft data pack tag, maybe with comments and `.jsonc` extension.
+	MinecraftTagWithComments,
 	/// An OptiFine custom entity model, with `.jem` extension.
 	#[cfg(feature = "optifine")]
 	#[doc(cfg(feature = "optifine"))]
@@ -123,6 +142,10 @@ pub enum PackFileAssetType {
 	/// A texture that may be used as an input render target in a shader program via a sampler
 	/// uniform.
 	AuxiliaryShaderTargetTexture,
+	/// A normal or specular map texture that follows the LabPBR convention used by shader
+	/// packs, with `_n.png` or `_s.png` suffix. Every channel of these textures stores data
+	/// that shaders read as-is, even in completely transparent pixels.
+	LabPbrTexture,
 	/// An OptiFine-specific texture, with `.png` extension.
 	#[cfg(feature = "optifine")]
 	#[doc(cfg(feature = "optifine"))]
@@ -135,6 +158,9 @@ pub enum PackFileAssetType {
 	Mtr3CustomGenericTexture,
 	/// Any texture in PNG format, with `.png` extension.
 	GenericTexture,
+	/// Any texture in WebP format, with `.webp` extension. As Minecraft does not support this
+	/// format, these textures will be converted to PNG, with `.png` extension.
+	WebpTexture,
 
 	/// A generic properties file added by OptiFine, with `.properties` extension.
 	#[cfg(feature = "optifine")]
@@ -195,6 +221,17 @@ pub enum PackFileAssetType {
 	/// (Minecraft 1.21).
 	CommandFunction,
 
+	/// A region file of a world, in the Anvil format, which stores the compressed NBT data of
+	/// its chunks, entities or points of interest. Its extension is `.mca`. Only matched when
+	/// packaging world templates.
+	AnvilRegion,
+	/// The `level.dat` file of a world, a gzip-compressed NBT file with its global settings.
+	/// Only matched when packaging world templates.
+	WorldLevelData,
+	/// The icon of a world, shown in the world selection screen. Only matched when packaging
+	/// world templates.
+	WorldIcon,
+
 	/// A custom asset type, defined by the end user, whose contents are opaque to PackSquash and
 	/// processed without any specific optimizations. Custom assets can never be matched by using
 	/// [`PackFileAssetTypeMatcher`].
@@ -232,6 +269,22 @@ impl PackFileAssetType {
 			Self::MinecraftModelWithComments => {
 				compile_hardcoded_pack_file_glob_pattern("assets/*/models/{block,item}/**/?*.jsonc")
 			}
+			Self::MinecraftBlockState => {
+				compile_hardcoded_pack_file_glob_pattern("assets/*/blockstates/**/?*.json")
+			}
+			Self::MinecraftBlockStateWithComments => {
+				compile_hardcoded_pack_file_glob_pattern("assets/*/blockstates/**/?*.jsonc")
+			}
+			Self::MinecraftItemModelDefinition => {
+				compile_hardcoded_pack_file_glob_pattern("assets/*/items/**/?*.json")
+			}
+			Self::MinecraftItemModelDefinitionWithComments => {
+				compile_hardcoded_pack_file_glob_pattern("assets/*/items/**/?*.jsonc")
+			}
+			Self::MinecraftTag => compile_hardcoded_pack_file_glob_pattern("data/*/tags/**/?*.json"),
+			Self::MinecraftTagWithComments => {
+				compile_hardcoded_pack_file_glob_pattern("data/*/tags/**/?*.jsonc")
+			}
 			#[cfg(feature = "optifine")]
 			Self::OptifineCustomEntityModel => compile_hardcoded_pack_file_glob_pattern(
 				"assets/minecraft/{mcpatcher,optifine}/cem/?*.jem"
@@ -321,6 +374,9 @@ impl PackFileAssetType {
 			Self::AuxiliaryShaderTargetTexture => {
 				compile_hardcoded_pack_file_glob_pattern("assets/minecraft/textures/effect/**/?*.png")
 			}
+			Self::LabPbrTexture => {
+				compile_hardcoded_pack_file_glob_pattern("assets/*/textures/**/?*_{n,s}.png")
+			}
 			#[cfg(feature = "optifine")]
 			Self::OptifineTexture => {
 				// OptiFine looks for PNGs in specific locations within its folder, but users can
@@ -336,6 +392,9 @@ impl PackFileAssetType {
 				// "textures" directory
 				compile_hardcoded_pack_file_glob_pattern("assets/*/textures/**/?*.png")
 			}
+			Self::WebpTexture => {
+				compile_hardcoded_pack_file_glob_pattern("assets/*/textures/**/?*.webp")
+			}
 
 			// Whitelist of OptiFine properties files. The following features are supported:
 			//
@@ -457,6 +516,12 @@ impl PackFileAssetType {
 				compile_hardcoded_pack_file_glob_pattern("data/*/function/**/*.mcfunction")
 			}
 
+			Self::AnvilRegion => {
+				compile_hardcoded_pack_file_glob_pattern("**/{region,entities,poi}/r.*.*.mca")
+			}
+			Self::WorldLevelData => compile_hardcoded_pack_file_glob_pattern("level.dat"),
+			Self::WorldIcon => compile_hardcoded_pack_file_glob_pattern("icon.png"),
+
 			Self::Custom => unreachable!()
 		}
 	}
@@ -468,7 +533,7 @@ impl PackFileAssetType {
 	/// The canonical extension may be `None` if the asset type is known to already have a
 	/// canonical extension by definition, and thus the extension does not need to be
 	/// canonicalized.
-	const fn canonical_extension(self) -> Option<&'static str> {
+	pub const fn canonical_extension(self) -> Option<&'static str> {
 		match self {
 			Self::MinecraftTextureMetadata => None,
 			Self::MinecraftTextureMetadataWithComments => Some("mcmeta"),
@@ -476,6 +541,12 @@ impl PackFileAssetType {
 			Self::MinecraftMetadataWithComments => Some("mcmeta"),
 			Self::MinecraftModel => None,
 			Self::MinecraftModelWithComments => Some("json"),
+			Self::MinecraftBlockState => None,
+			Self::MinecraftBlockStateWithComments => Some("json"),
+			Self::MinecraftItemModelDefinition => None,
+			Self::MinecraftItemModelDefinitionWithComments => Some("json"),
+			Self::MinecraftTag => None,
+			Self::MinecraftTagWithComments => Some("json"),
 			#[cfg(feature = "optifine")]
 			Self::OptifineCustomEntityModel => None,
 			#[cfg(feature = "optifine")]
@@ -503,12 +574,14 @@ impl PackFileAssetType {
 			Self::PackIcon
 			| Self::BannerLayer
 			| Self::EyeLayer
-			| Self::AuxiliaryShaderTargetTexture => None,
+			| Self::AuxiliaryShaderTargetTexture
+			| Self::LabPbrTexture => None,
 			#[cfg(feature = "optifine")]
 			Self::OptifineTexture => None,
 			#[cfg(feature = "mtr3")]
 			Self::Mtr3CustomGenericTexture => None,
 			Self::GenericTexture => None,
+			Self::WebpTexture => Some("png"),
 			#[cfg(feature = "optifine")]
 			Self::GenericProperties => None,
 			Self::VertexShader => None,
@@ -521,6 +594,7 @@ impl PackFileAssetType {
 			Self::Text | Self::ClosingCreditsText | Self::LegacyTextCredits => None,
 			Self::LegacyNbtStructure | Self::NbtStructure => None,
 			Self::LegacyCommandFunction | Self::CommandFunction => None,
+			Self::AnvilRegion | Self::WorldLevelData | Self::WorldIcon => None,
 			Self::Custom => None
 		}
 	}
@@ -531,7 +605,8 @@ impl PackFileAssetType {
 /// settings.
 pub struct PackFileAssetTypeMatcher {
 	asset_type_globset: GlobSet,
-	asset_types_mask: EnumSet<PackFileAssetType>
+	asset_types_mask: EnumSet<PackFileAssetType>,
+	file_type_routes: Option<(FileOptionsGlobSet, Vec<PackFileAssetType>)>
 }
 
 impl PackFileAssetTypeMatcher {
@@ -549,14 +624,51 @@ impl PackFileAssetTypeMatcher {
 
 		Self {
 			asset_type_globset: globset_builder.build().unwrap(),
-			asset_types_mask
+			asset_types_mask,
+			file_type_routes: None
+		}
+	}
+
+	/// Returns a new matcher for the asset types mask tweaked from the specified squash options,
+	/// which also matches the pack files routed to a file type by the `file_type_routes` global
+	/// option with the asset type of that file type, regardless of the mask.
+	pub fn for_options(
+		asset_types_mask: EnumSet<PackFileAssetType>,
+		options_holder: &ProcessedSquashOptions
+	) -> Self {
+		let global_options = &options_holder.options.global_options;
+		let mut matcher = Self::new(tweak_asset_types_mask_from_global_options(
+			asset_types_mask,
+			global_options
+		));
+
+		if !global_options.file_type_routes.is_empty() {
+			matcher.file_type_routes = Some((
+				options_holder.file_type_route_globs.clone(),
+				global_options
+					.file_type_routes
+					.values()
+					.map(|&file_type_route| file_type_route.into())
+					.collect()
+			));
 		}
+
+		matcher
 	}
 
 	/// Matches the corresponding asset types for the specified [`RelativePath`]. This operation
 	/// potentially involves regular expressions and heap allocations, so users of this method
 	/// are encouraged to not do gratuitous matches.
 	pub fn matches_for(&self, path: &RelativePath<'_>) -> PackFileAssetTypeMatches {
+		// Routed files are matched with the asset type of the winning route only
+		if let Some((route_globs, routed_asset_types)) = &self.file_type_routes
+			&& let Some(&route_index) = route_globs.matches(path.as_str()).first()
+		{
+			return PackFileAssetTypeMatches {
+				matches: Cow::Owned(vec![routed_asset_types[route_index]])
+			};
+		}
+
 		PackFileAssetTypeMatches {
 			matches: Cow::Owned(
 				self.asset_type_globset
@@ -571,6 +683,28 @@ impl PackFileAssetTypeMatcher {
 	}
 }
 
+impl From<FileTypeRoute> for PackFileAssetType {
+	fn from(file_type_route: FileTypeRoute) -> Self {
+		match file_type_route {
+			FileTypeRoute::Json => Self::GenericJson,
+			FileTypeRoute::JsonWithComments => Self::GenericJsonWithComments,
+			FileTypeRoute::Png => Self::GenericTexture,
+			FileTypeRoute::Webp => Self::WebpTexture,
+			FileTypeRoute::OggVorbisAudio => Self::GenericOggVorbisAudio,
+			FileTypeRoute::Audio => Self::GenericAudio,
+			FileTypeRoute::VertexShader => Self::VertexShader,
+			FileTypeRoute::FragmentShader => Self::FragmentShader,
+			FileTypeRoute::ShaderInclude => Self::TranslationUnitSegment,
+			FileTypeRoute::LegacyLanguage => Self::LegacyLanguageFile,
+			FileTypeRoute::Text => Self::Text,
+			#[cfg(feature = "optifine")]
+			FileTypeRoute::Properties => Self::GenericProperties,
+			FileTypeRoute::NbtStructure => Self::NbtStructure,
+			FileTypeRoute::CommandFunction => Self::CommandFunction
+		}
+	}
+}
+
 /// A set of asset type matches for a pack file, given its [`RelativePath`]. This struct is
 /// constructed by the [`PackFileAssetTypeMatcher::matches_for`] method.
 pub struct PackFileAssetTypeMatches {
@@ -658,6 +792,42 @@ impl PackFileAssetTypeMatches {
 				{
 					return_pack_file_to_process_data!(JsonFile, optimization_settings)
 				}
+				PackFileAssetType::MinecraftBlockState
+					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(JsonFile, optimization_settings)
+				}
+				PackFileAssetType::MinecraftBlockStateWithComments
+					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(JsonFile, optimization_settings)
+				}
+				PackFileAssetType::MinecraftItemModelDefinition
+					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(JsonFile, optimization_settings)
+				}
+				PackFileAssetType::MinecraftItemModelDefinitionWithComments
+					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(JsonFile, optimization_settings)
+				}
+				PackFileAssetType::MinecraftTag
+					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(JsonFile, optimization_settings)
+				}
+				PackFileAssetType::MinecraftTagWithComments
+					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(JsonFile, optimization_settings)
+				}
 				#[cfg(feature = "optifine")]
 				PackFileAssetType::OptifineCustomEntityModel
 					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
@@ -742,15 +912,15 @@ impl PackFileAssetTypeMatches {
 				}
 				PackFileAssetType::GenericOggVorbisAudio
 					if let Some(FileOptions::AudioFileOptions(optimization_settings)) =
-						file_options =>
+						&file_options =>
 				{
-					return_pack_file_to_process_data!(AudioFile, optimization_settings)
+					return_pack_file_to_process_data!(AudioFile, optimization_settings.clone())
 				}
 				PackFileAssetType::GenericAudio
 					if let Some(FileOptions::AudioFileOptions(optimization_settings)) =
-						file_options =>
+						&file_options =>
 				{
-					return_pack_file_to_process_data!(AudioFile, optimization_settings)
+					return_pack_file_to_process_data!(AudioFile, optimization_settings.clone())
 				}
 				PackFileAssetType::PackIcon
 					if let Some(FileOptions::PngFileOptions(optimization_settings)) =
@@ -776,6 +946,12 @@ impl PackFileAssetTypeMatches {
 				{
 					return_pack_file_to_process_data!(PngFile, optimization_settings)
 				}
+				PackFileAssetType::LabPbrTexture
+					if let Some(FileOptions::PngFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(PngFile, optimization_settings)
+				}
 				#[cfg(feature = "optifine")]
 				PackFileAssetType::OptifineTexture
 					if let Some(FileOptions::PngFileOptions(optimization_settings)) =
@@ -796,6 +972,12 @@ impl PackFileAssetTypeMatches {
 				{
 					return_pack_file_to_process_data!(PngFile, optimization_settings)
 				}
+				PackFileAssetType::WebpTexture
+					if let Some(FileOptions::PngFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(PngFile, optimization_settings)
+				}
 				#[cfg(feature = "optifine")]
 				PackFileAssetType::GenericProperties
 					if let Some(FileOptions::PropertiesFileOptions(optimization_settings)) =
@@ -843,9 +1025,37 @@ impl PackFileAssetTypeMatches {
 						optimization_settings
 					)
 				}
+				PackFileAssetType::WorldLevelData
+					if let Some(FileOptions::CompressedCompoundNbtTagFileOptions(
+						optimization_settings
+					)) = file_options =>
+				{
+					return_pack_file_to_process_data!(
+						CompressedCompoundNbtTagFile,
+						optimization_settings
+					)
+				}
+				PackFileAssetType::AnvilRegion
+					if let Some(FileOptions::CompressedCompoundNbtTagFileOptions(
+						optimization_settings
+					)) = file_options =>
+				{
+					return_pack_file_to_process_data!(AnvilRegionFile, optimization_settings)
+				}
+				PackFileAssetType::WorldIcon
+					if let Some(FileOptions::PngFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(PngFile, optimization_settings)
+				}
+				PackFileAssetType::ZippedUnifontHex
+					if let Some(FileOptions::ZippedUnifontHexFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(ZippedUnifontHexFile, optimization_settings)
+				}
 				PackFileAssetType::TrueTypeOrOpenTypeFont
 				| PackFileAssetType::TrueTypeFont
-				| PackFileAssetType::ZippedUnifontHex
 				| PackFileAssetType::LegacyUnicodeFontCharacterSizes
 				| PackFileAssetType::Text
 				| PackFileAssetType::ClosingCreditsText
@@ -857,11 +1067,21 @@ impl PackFileAssetTypeMatches {
 				PackFileAssetType::Custom
 					if let Some(FileOptions::CustomFileOptions(CustomFileOptions {
 						force_include: true,
+						custom_file_format: CustomFileFormat::Opaque,
 						..
 					})) = file_options =>
 				{
 					return_pack_file_to_process_data!(PassthroughFile, ())
 				}
+				PackFileAssetType::Custom
+					if let Some(FileOptions::CustomFileOptions(CustomFileOptions {
+						force_include: true,
+						custom_file_format: CustomFileFormat::Toml,
+						..
+					})) = file_options =>
+				{
+					return_pack_file_to_process_data!(TomlFile, ())
+				}
 				_ => {
 					// The file options do not match the asset type, but maybe we have more asset types to try
 					continue;
@@ -872,6 +1092,29 @@ impl PackFileAssetTypeMatches {
 		// The file options do not match with any matched asset type
 		None
 	}
+
+	/// Returns the matched asset type whose optimization strategy `process_data` would use for
+	/// this pack file with the specified file options, or `None` if it would not process it
+	/// with them. Unlike `process_data`, this does not read or process any pack file data.
+	pub fn processing_asset_type(
+		&self,
+		file_options: Option<&FileOptions>
+	) -> Option<PackFileAssetType> {
+		self.matches.iter().copied().find(|&asset_type| {
+			// Pack files only ask for their data when the file options are appropriate for
+			// their asset type, so a read producer that never yields data tells that apart
+			let mut data_requested = false;
+			Self {
+				matches: Cow::Owned(vec![asset_type])
+			}
+			.process_data::<Empty>(file_options.cloned(), || {
+				data_requested = true;
+				None
+			});
+
+			data_requested
+		})
+	}
 }
 
 /// Removes asset types from the specified mask that are not appropriate for the given global options.
@@ -887,6 +1130,12 @@ pub fn tweak_asset_types_mask_from_global_options(
 		asset_types_mask -= PackFileAssetType::PackIcon;
 	}
 
+	if !global_options.world_template_packaging {
+		asset_types_mask -= PackFileAssetType::AnvilRegion
+			| PackFileAssetType::WorldLevelData
+			| PackFileAssetType::WorldIcon;
+	}
+
 	#[cfg(feature = "optifine")]
 	if !global_options.allow_mods.contains(MinecraftMod::Optifine) {
 		asset_types_mask -= PackFileAssetType::OptifineCustomEntityModel
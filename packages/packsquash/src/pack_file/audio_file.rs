@@ -6,28 +6,46 @@ use optivorbis::{
 	Remuxer, VorbisCommentFieldsAction, VorbisOptimizerSettings, VorbisVendorStringAction
 };
 use rubato::ResampleError;
+use sha2::{Digest, Sha256};
 use std::borrow::Cow;
 use std::cell::Cell;
 use std::cmp;
 use std::io::{Cursor, Read, Seek};
-use std::num::NonZeroU32;
+use std::num::{NonZeroU8, NonZeroU32};
+use std::time::Duration;
 use thiserror::Error;
 use tokio::io::AsyncRead;
 use tokio_util::codec::{Decoder, FramedRead};
 use vorbis_rs::{VorbisBitrateManagementStrategy, VorbisEncoderBuilder};
 
-use crate::config::{AudioBitrateControlMode, AudioFileOptions, ChannelMixingOption};
+use crate::config::{
+	AudioBitrateControlMode, AudioFileOptions, ChannelCount, ChannelMixingOption, SoundCategory,
+	StereoPositionalSoundHandling
+};
 use crate::pack_file::AsyncReadAndSizeHint;
 use crate::pack_file::asset_type::PackFileAssetType;
-use signal_processor::decode_and_process_sample_blocks;
+use bandwidth_analyzer::BandwidthAnalyzer;
+use level_analyzer::LevelAnalyzer;
+use loudness_meter::{LoudnessMeasurement, LoudnessMeter};
+use signal_processor::{decode_and_process_sample_blocks, input_channel_count};
+use silence_trimmer::{SilenceTrimmer, has_loop_point_metadata};
+use streaming_transcode::StreamingTranscode;
+use vorbis_comments::{is_retained_vorbis_comment, ogg_packet, read_vorbis_comments};
 use vorbis_stream_mangler::ValidatingAndObfuscatingOggVorbisStreamMangler;
 
+use super::resource_budget::{MEMORY_BUDGET, ResourceBudget};
 use super::{PackFile, PackFileConstructor};
 
 #[cfg(test)]
 mod tests;
 
+mod bandwidth_analyzer;
+mod level_analyzer;
+mod loudness_meter;
 mod signal_processor;
+mod silence_trimmer;
+mod streaming_transcode;
+mod vorbis_comments;
 mod vorbis_stream_mangler;
 
 /// The default sampling frequency to resample positional (i.e., mono) sounds to.
@@ -53,6 +71,36 @@ const POSITIONAL_AUDIO_TARGET_QUALITY: f32 = 0.0;
 /// The default target quality for non-positional sounds, used when transcoding. For stereo,
 /// 44.1 kHz audio this translates to an average bitrate around ≈68 kbit/s.
 const NON_POSITIONAL_AUDIO_TARGET_QUALITY: f32 = 0.25;
+/// The default sampling frequency to resample music to, when sound category profiles are
+/// enabled. Music is usually listened to more attentively than other sounds, so it is worth
+/// keeping the whole audible spectrum.
+const MUSIC_SAMPLING_FREQUENCY: NonZeroU32 = if let Some(frequency) = NonZeroU32::new(44_100) {
+	frequency
+} else {
+	unreachable!()
+};
+/// The default target quality for music, used when transcoding with sound category profiles
+/// enabled. For stereo, 44.1 kHz audio this translates to an average bitrate around ≈80 kbit/s.
+const MUSIC_TARGET_QUALITY: f32 = 1.0;
+/// The maximum sampling frequency of audio previews, which are always mono.
+const PREVIEW_SAMPLING_FREQUENCY: NonZeroU32 = if let Some(frequency) = NonZeroU32::new(22_050) {
+	frequency
+} else {
+	unreachable!()
+};
+/// The target quality of audio previews, which is the lowest one libvorbis supports.
+const PREVIEW_TARGET_QUALITY: f32 = -0.1;
+
+/// The capacity of the buffer that input audio files are read into when they are transcoded
+/// while being read, which bounds the size of the chunks handed off to the transcoder.
+const STREAMING_INPUT_BUFFER_CAPACITY: usize = 64 * 1024;
+
+/// How many bytes of memory transcoding an audio file is estimated to take per byte of input
+/// file, as the compressed audio data is decoded to 32-bit floating point samples completely.
+const ESTIMATED_DECODED_AUDIO_SIZE_FACTOR: usize = 16;
+
+/// The budget of concurrent transcodes that every audio file transcode reserves a unit from.
+static TRANSCODE_BUDGET: ResourceBudget = ResourceBudget::new();
 
 /// Represents an audio file, that can be optimized and/or transcoded to Ogg.
 ///
@@ -65,11 +113,36 @@ pub struct AudioFile<T: AsyncRead + Send + Unpin + 'static> {
 	optimization_settings: AudioFileOptions
 }
 
+/// Diagnostics about the levels of the audio signal of an audio file, which flag issues that
+/// are better fixed in its source material. They are computed by [`analyze_audio`].
+#[derive(Debug, Clone, Copy)]
+pub struct AudioDiagnostics {
+	/// The gated integrated loudness of the audio, in LUFS. It is `None` for silent audio.
+	pub integrated_loudness: Option<f64>,
+	/// The estimated true peak level of the audio, in dBTP. It is negative infinity for
+	/// silent audio.
+	pub true_peak: f64,
+	/// The number of samples that are part of runs of consecutive samples at full scale,
+	/// which are likely to be clipped.
+	pub clipped_sample_count: u64,
+	/// The largest absolute mean sample value of any channel, relative to full scale.
+	pub dc_offset: f64
+}
+
+/// The results of analyzing the input data of an audio file with [`analyze_audio`].
+pub struct AudioAnalysis {
+	/// The preview of the audio file, if requested and it could be encoded.
+	pub preview: Option<Vec<u8>>,
+	/// The diagnostics about the levels of the audio file, if requested.
+	pub diagnostics: Option<AudioDiagnostics>
+}
+
 /// Optimizer decoder that transforms audio files to an optimized representation.
 pub struct OptimizerDecoder {
 	optimization_settings: AudioFileOptions,
 	is_ogg: bool,
-	reached_eof: bool
+	reached_eof: bool,
+	streaming_transcode: Option<StreamingTranscode>
 }
 
 /// Represents an error that may happen while optimizing audio files.
@@ -129,13 +202,19 @@ impl AsRef<[u8]> for ByteBuffer {
 	}
 }
 
-// FIXME: actual framing?
-// (i.e. do not hold the entire file in memory before decoding, so that frame != file)
 impl Decoder for OptimizerDecoder {
 	type Item = (Cow<'static, str>, ByteBuffer);
 	type Error = OptimizationError;
 
-	fn decode(&mut self, _: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+	fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+		// Hand off the input read so far to the streaming transcode, if any. Otherwise, keep
+		// accumulating it until the whole file is read
+		if let Some(streaming_transcode) = &self.streaming_transcode
+			&& !src.is_empty()
+		{
+			streaming_transcode.send_chunk(src.split().freeze());
+		}
+
 		Ok(None)
 	}
 
@@ -148,9 +227,21 @@ impl Decoder for OptimizerDecoder {
 		}
 		self.reached_eof = true;
 
+		let streaming_transcode = self.streaming_transcode.take();
+		if let Some(streaming_transcode) = &streaming_transcode
+			&& !src.is_empty()
+		{
+			streaming_transcode.send_chunk(src.split().freeze());
+		}
+
+		// This is empty when the input file was handed off to a streaming transcode. That
+		// never happens for Ogg files, which are the only ones that need it after transcoding
 		let input_file = src.split_off(0).freeze();
 
-		let skip_transcoding = self.is_ogg && !self.optimization_settings.transcode_ogg;
+		let skip_transcoding = self.is_ogg
+			&& (!self.optimization_settings.transcode_ogg
+				|| (self.optimization_settings.skip_redundant_ogg_transcoding
+					&& is_transcoding_redundant(&input_file, &self.optimization_settings)));
 		let do_two_pass_optimization_and_validation = self
 			.optimization_settings
 			.two_pass_vorbis_optimization_and_validation;
@@ -158,23 +249,48 @@ impl Decoder for OptimizerDecoder {
 			.optimization_settings
 			.minecraft_version_supports_ogg_obfuscation
 			&& self.optimization_settings.ogg_obfuscation;
+		let retain_comments = !self
+			.optimization_settings
+			.retained_vorbis_comment_fields
+			.is_empty();
 
 		// First pass: transcode the input audio file to an efficient Ogg Vorbis representation.
 		// This is necessary if the input audio file is not Ogg Vorbis, or if some modification
-		// to the audio data is done (currently, channel mixing, resampling and pitch shifting)
-		let (transcoded_file, channel_mixing_done, pitch_shifting_done) = if skip_transcoding {
-			(ByteBuffer::Bytes(input_file.clone()), false, false)
+		// to the audio data is done (currently, channel mixing, resampling, pitch shifting,
+		// loudness normalization and silence trimming)
+		let (transcoded_file, audio_data_changed) = if skip_transcoding {
+			(ByteBuffer::Bytes(input_file.clone()), false)
 		} else {
-			let (transcoded_file, channel_mixing_done) = process_and_transcode(
-				input_file.clone().reader(),
-				self.is_ogg,
-				&self.optimization_settings
-			)?;
+			let (transcoded_file, audio_data_changed) = match streaming_transcode {
+				Some(streaming_transcode) => streaming_transcode.finish()?,
+				None => {
+					// Wait for some other transcode to finish if too many are running at the same
+					// time, or, when a memory budget is set, they would take too much memory. The
+					// reservations are released when transcoding finishes
+					let _transcode_reservation = TRANSCODE_BUDGET
+						.reserve(1, self.optimization_settings.concurrent_transcodes_limit);
+					let _memory_reservation = (self.optimization_settings.optimization_memory_limit
+						!= usize::MAX)
+						.then(|| {
+							MEMORY_BUDGET.reserve(
+								input_file
+									.len()
+									.saturating_mul(ESTIMATED_DECODED_AUDIO_SIZE_FACTOR),
+								self.optimization_settings.optimization_memory_limit
+							)
+						});
+
+					process_and_transcode(
+						input_file.clone(),
+						self.is_ogg,
+						&self.optimization_settings
+					)?
+				}
+			};
 
 			(
 				ByteBuffer::CowSlice(transcoded_file),
-				channel_mixing_done,
-				self.optimization_settings.target_pitch != 1.0
+				audio_data_changed || self.optimization_settings.target_pitch != 1.0
 			)
 		};
 
@@ -183,8 +299,12 @@ impl Decoder for OptimizerDecoder {
 		// (think on OxiPNG, but much, much faster and less quirky)
 		let transcoded_and_optimized_file = if do_two_pass_optimization_and_validation {
 			ByteBuffer::CowSlice(
-				validate_and_optimize(Cursor::new(transcoded_file.as_ref()), do_ogg_obfuscation)?
-					.into()
+				validate_and_optimize(
+					Cursor::new(transcoded_file.as_ref()),
+					do_ogg_obfuscation,
+					retain_comments
+				)?
+				.into()
 			)
 		} else {
 			transcoded_file
@@ -194,7 +314,17 @@ impl Decoder for OptimizerDecoder {
 		// If not, quickly run OptiVorbis over the original file, which is practically guaranteed to
 		// never return a file bigger than its input, and return that
 		let optimized_file_is_input_file;
-		let can_use_input_as_output = self.is_ogg && !pitch_shifting_done && !channel_mixing_done;
+		// Reoptimizing the input file would retain all of its comments, so only do it if
+		// they all should be retained, or none of them should
+		let can_use_input_as_output = self.is_ogg
+			&& !audio_data_changed
+			&& (!retain_comments
+				|| read_vorbis_comments(&input_file).iter().all(|comment| {
+					is_retained_vorbis_comment(
+						comment,
+						&self.optimization_settings.retained_vorbis_comment_fields
+					)
+				}));
 
 		let optimized_file = if do_two_pass_optimization_and_validation
 			&& input_file.len() < transcoded_and_optimized_file.as_ref().len()
@@ -202,7 +332,12 @@ impl Decoder for OptimizerDecoder {
 		{
 			optimized_file_is_input_file = true;
 			ByteBuffer::CowSlice(
-				validate_and_optimize(Cursor::new(input_file.as_ref()), do_ogg_obfuscation)?.into()
+				validate_and_optimize(
+					Cursor::new(input_file.as_ref()),
+					do_ogg_obfuscation,
+					retain_comments
+				)?
+				.into()
 			)
 		} else {
 			optimized_file_is_input_file = false;
@@ -221,86 +356,168 @@ impl Decoder for OptimizerDecoder {
 	}
 }
 
+/// The results of analyzing a whole audio file before transcoding it, which tailor how it is
+/// processed by [`transcode`]. The default value corresponds to not needing any analysis.
+#[derive(Default)]
+struct TranscodingAnalysis {
+	bandwidth_limited_sampling_frequency: Option<NonZeroU32>,
+	loudness_normalization_gain: Option<f32>,
+	silence_trimmer: Option<SilenceTrimmer>,
+	retained_comments: Vec<(String, String)>
+}
+
 /// Processes the input audio file and transcodes it to Ogg Vorbis, according to the
 /// provided optimization settings. The audio signal processing done may include resampling,
-/// pitch shifting and channel mixing. Empty sound files (e.g., without audio samples, or only
+/// possibly adapted to the bandwidth of the audio, pitch shifting, channel mixing, loudness
+/// normalization and silence trimming. Empty sound files (e.g., without audio samples, or only
 /// containing audio samples which are complete silence) may be special-cased for optimization,
 /// yielding a minimal empty Ogg Vorbis file.
+///
+/// Besides the transcoded file, whether channel mixing, loudness normalization or silence
+/// trimming changed the audio data is returned.
 fn process_and_transcode(
-	input_file: impl Read + Send + Sync + 'static,
+	input_file: Bytes,
 	is_ogg: bool,
 	optimization_settings: &AudioFileOptions
+) -> Result<(Cow<'static, [u8]>, bool), OptimizationError> {
+	// Finding the lowest sampling frequency that keeps all the audible content of a sound
+	// requires analyzing all of it beforehand, so do it in a previous decoding pass
+	let bandwidth_limited_sampling_frequency =
+		if optimization_settings.bandwidth_adaptive_sampling_frequency {
+			analyze_bandwidth(input_file.clone().reader(), is_ogg, optimization_settings)?
+		} else {
+			None
+		};
+
+	// Normalizing the loudness of a sound requires knowing the loudness of all of it
+	// beforehand, so measure it in a previous decoding pass
+	let loudness_normalization_gain = match optimization_settings.target_loudness {
+		Some(target_loudness) => loudness_normalization_gain(
+			measure_loudness(
+				input_file.clone().reader(),
+				is_ogg,
+				optimization_settings,
+				bandwidth_limited_sampling_frequency
+			)?,
+			target_loudness,
+			optimization_settings.maximum_true_peak
+		),
+		None => None
+	};
+
+	// Trimming the silence of sounds with loop points would break their timing, so
+	// they are never trimmed
+	let silence_trimmer = optimization_settings
+		.silence_trimming_threshold
+		.filter(|_| !has_loop_point_metadata(&input_file))
+		.map(SilenceTrimmer::new);
+
+	// The encoder does not carry over the Vorbis comments of the input file, so copy the ones
+	// that should be retained
+	let retained_comments = read_vorbis_comments(&input_file)
+		.into_iter()
+		.filter(|comment| {
+			is_retained_vorbis_comment(
+				comment,
+				&optimization_settings.retained_vorbis_comment_fields
+			)
+		})
+		.collect::<Vec<_>>();
+
+	transcode(
+		input_file.reader(),
+		is_ogg,
+		optimization_settings,
+		TranscodingAnalysis {
+			bandwidth_limited_sampling_frequency,
+			loudness_normalization_gain,
+			silence_trimmer,
+			retained_comments
+		}
+	)
+}
+
+/// Transcodes the audio file read from the specified source to Ogg Vorbis like
+/// [`process_and_transcode`] does, according to the results of analyzing the whole file
+/// beforehand. As the source is read only once, it does not need to hold the whole file in
+/// memory, so that files which do not need such analysis can be transcoded while they are read.
+fn transcode(
+	source: impl Read + Send + Sync + 'static,
+	is_ogg: bool,
+	optimization_settings: &AudioFileOptions,
+	TranscodingAnalysis {
+		bandwidth_limited_sampling_frequency,
+		loudness_normalization_gain,
+		mut silence_trimmer,
+		retained_comments
+	}: TranscodingAnalysis
 ) -> Result<(Cow<'static, [u8]>, bool), OptimizationError> {
 	// FIXME write to a SpooledTempFile whose maximum memory buffer size
 	// is controlled by a global budget, once that refactor is complete
 	let mut transcoded_file = vec![];
 	let encoder = Cell::new(None);
+	let mut normalized_block = vec![];
+	let mut trimmed_block = vec![];
 
 	let mut channel_mixing_done = false;
 	let is_silence = decode_and_process_sample_blocks(
-		input_file,
+		source,
 		is_ogg,
-		match optimization_settings.channels {
-			ChannelMixingOption::ToChannels(count) => Some(count),
-			ChannelMixingOption::Skip => None
-		},
+		target_channels(optimization_settings),
 		|input_sampling_frequency, input_channel_count, output_channel_count| {
 			let is_positional_audio = output_channel_count.get() == 1;
 
 			channel_mixing_done = input_channel_count != output_channel_count;
 
-			// Resampling to a frequency higher than the input one is a bad idea at
-			// this point: it doesn't add meaningful audio information or helps to use
-			// different signal processing filters, but it definitely increases space
-			// costs. Let's not do that
-			let output_sampling_frequency = cmp::min(
-				optimization_settings
-					.sampling_frequency
-					.unwrap_or(if is_positional_audio {
-						POSITIONAL_AUDIO_SAMPLING_FREQUENCY
-					} else {
-						NON_POSITIONAL_AUDIO_SAMPLING_FREQUENCY
-					}),
-				input_sampling_frequency
+			let output_sampling_frequency = output_sampling_frequency(
+				optimization_settings,
+				input_sampling_frequency,
+				is_positional_audio,
+				bandwidth_limited_sampling_frequency
 			);
 
+			let mut encoder_builder = VorbisEncoderBuilder::new_with_serial(
+				output_sampling_frequency,
+				output_channel_count,
+				&mut transcoded_file,
+				// Use a fixed serial for better compressibility when not using OptiVorbis,
+				// which is non-zero to avoid some warnings
+				1
+			);
+			for (field_name, value) in &retained_comments {
+				encoder_builder.comment_tag(field_name.as_str(), value.as_str())?;
+			}
+
 			encoder.set(Some(
-				VorbisEncoderBuilder::new_with_serial(
-					output_sampling_frequency,
-					output_channel_count,
-					&mut transcoded_file,
-					// Use a fixed serial for better compressibility when not using OptiVorbis,
-					// which is non-zero to avoid some warnings
-					1
-				)
-				// Use jumbo Ogg pages for the least encapsulation overhead
-				.minimum_page_data_size(Some(u16::MAX))
-				.bitrate_management_strategy(match optimization_settings.bitrate_control_mode {
-					AudioBitrateControlMode::Cqf => VorbisBitrateManagementStrategy::QualityVbr {
-						target_quality: target_bitrate_control_metric_to_quality(
-							optimization_settings,
-							is_positional_audio
-						)
-					},
-					AudioBitrateControlMode::Vbr => VorbisBitrateManagementStrategy::Vbr {
-						target_bitrate: target_bitrate_control_metric_to_bitrate(
-							optimization_settings
-						)?
-					},
-					AudioBitrateControlMode::Abr => VorbisBitrateManagementStrategy::Abr {
-						average_bitrate: target_bitrate_control_metric_to_bitrate(
-							optimization_settings
-						)?
-					},
-					AudioBitrateControlMode::ConstrainedAbr => {
-						VorbisBitrateManagementStrategy::ConstrainedAbr {
-							maximum_bitrate: target_bitrate_control_metric_to_bitrate(
+				encoder_builder
+					// Use jumbo Ogg pages for the least encapsulation overhead
+					.minimum_page_data_size(Some(u16::MAX))
+					.bitrate_management_strategy(match optimization_settings.bitrate_control_mode {
+						AudioBitrateControlMode::Cqf => VorbisBitrateManagementStrategy::QualityVbr {
+							target_quality: target_bitrate_control_metric_to_quality(
+								optimization_settings,
+								is_positional_audio
+							)
+						},
+						AudioBitrateControlMode::Vbr => VorbisBitrateManagementStrategy::Vbr {
+							target_bitrate: target_bitrate_control_metric_to_bitrate(
+								optimization_settings
+							)?
+						},
+						AudioBitrateControlMode::Abr => VorbisBitrateManagementStrategy::Abr {
+							average_bitrate: target_bitrate_control_metric_to_bitrate(
 								optimization_settings
 							)?
+						},
+						AudioBitrateControlMode::ConstrainedAbr => {
+							VorbisBitrateManagementStrategy::ConstrainedAbr {
+								maximum_bitrate: target_bitrate_control_metric_to_bitrate(
+									optimization_settings
+								)?
+							}
 						}
-					}
-				})
-				.build()?
+					})
+					.build()?
 			));
 
 			Ok(output_sampling_frequency)
@@ -308,7 +525,32 @@ fn process_and_transcode(
 		optimization_settings.target_pitch,
 		|block| {
 			if let Some(mut vorbis_encoder) = encoder.take() {
-				vorbis_encoder.encode_audio_block(block)?;
+				let block = match loudness_normalization_gain {
+					Some(gain) => {
+						normalized_block.resize_with(block.len(), Vec::new);
+						for (normalized_samples, samples) in normalized_block.iter_mut().zip(block) {
+							normalized_samples.clear();
+							normalized_samples.extend(samples.iter().map(|sample| sample * gain));
+						}
+
+						&normalized_block[..]
+					}
+					None => block
+				};
+
+				let block = match &mut silence_trimmer {
+					Some(silence_trimmer) => {
+						silence_trimmer.trim_block(block, &mut trimmed_block);
+						&trimmed_block[..]
+					}
+					None => block
+				};
+
+				// Like the resampler, silence trimming may output empty blocks, which should
+				// not be passed to libvorbis
+				if !block[0].is_empty() {
+					vorbis_encoder.encode_audio_block(block)?;
+				}
 				encoder.set(Some(vorbis_encoder));
 			}
 
@@ -343,15 +585,547 @@ fn process_and_transcode(
 				channel_mixing_done
 			)
 		} else {
-			(Cow::Owned(transcoded_file), channel_mixing_done)
+			(
+				Cow::Owned(transcoded_file),
+				channel_mixing_done
+					|| loudness_normalization_gain.is_some()
+					|| silence_trimmer
+						.as_ref()
+						.is_some_and(SilenceTrimmer::trimmed_silence)
+			)
 		}
 	)
 }
 
+/// Decodes and processes the input audio file like [`process_and_transcode`] does, but
+/// measures the loudness of the processed audio instead of transcoding it.
+fn measure_loudness(
+	input_file: impl Read + Send + Sync + 'static,
+	is_ogg: bool,
+	optimization_settings: &AudioFileOptions,
+	bandwidth_limited_sampling_frequency: Option<NonZeroU32>
+) -> Result<Option<LoudnessMeasurement>, OptimizationError> {
+	let loudness_meter = Cell::new(None);
+
+	decode_and_process_sample_blocks(
+		input_file,
+		is_ogg,
+		target_channels(optimization_settings),
+		|input_sampling_frequency, _, output_channel_count| {
+			let output_sampling_frequency = output_sampling_frequency(
+				optimization_settings,
+				input_sampling_frequency,
+				output_channel_count.get() == 1,
+				bandwidth_limited_sampling_frequency
+			);
+
+			loudness_meter.set(Some(LoudnessMeter::new(
+				output_sampling_frequency.get(),
+				output_channel_count.get() as usize
+			)));
+
+			Ok(output_sampling_frequency)
+		},
+		optimization_settings.target_pitch,
+		|block| {
+			if let Some(mut meter) = loudness_meter.take() {
+				meter.add_sample_block(block);
+				loudness_meter.set(Some(meter));
+			}
+
+			Ok(())
+		}
+	)?;
+
+	Ok(loudness_meter.take().map(LoudnessMeter::finish))
+}
+
+/// Decodes and processes the input audio file like [`process_and_transcode`] does, but
+/// without resampling, analyzing its bandwidth to find the lowest sampling frequency it can
+/// be resampled to without losing audible content. `None` is returned if no such sampling
+/// frequency lower than the input one was found.
+fn analyze_bandwidth(
+	input_file: impl Read + Send + Sync + 'static,
+	is_ogg: bool,
+	optimization_settings: &AudioFileOptions
+) -> Result<Option<NonZeroU32>, OptimizationError> {
+	let bandwidth_analyzer = Cell::new(None);
+
+	decode_and_process_sample_blocks(
+		input_file,
+		is_ogg,
+		target_channels(optimization_settings),
+		|input_sampling_frequency, _, output_channel_count| {
+			bandwidth_analyzer.set(Some(BandwidthAnalyzer::new(
+				input_sampling_frequency,
+				output_channel_count.get() as usize
+			)));
+
+			Ok(input_sampling_frequency)
+		},
+		optimization_settings.target_pitch,
+		|block| {
+			if let Some(mut analyzer) = bandwidth_analyzer.take() {
+				analyzer.add_sample_block(block);
+				bandwidth_analyzer.set(Some(analyzer));
+			}
+
+			Ok(())
+		}
+	)?;
+
+	Ok(bandwidth_analyzer
+		.take()
+		.and_then(BandwidthAnalyzer::finish))
+}
+
+/// Computes the linear gain that should be applied to the samples of a sound with the
+/// specified loudness measurement to reach the target integrated loudness, in LUFS, without
+/// its true peak level exceeding the specified maximum, in dBTP. Returns `None` if no gain
+/// should be applied, which is the case for silent sounds.
+fn loudness_normalization_gain(
+	loudness_measurement: Option<LoudnessMeasurement>,
+	target_loudness: f32,
+	maximum_true_peak: f32
+) -> Option<f32> {
+	let LoudnessMeasurement {
+		integrated_loudness,
+		true_peak
+	} = loudness_measurement?;
+
+	let gain =
+		(target_loudness as f64 - integrated_loudness?).min(maximum_true_peak as f64 - true_peak);
+
+	(gain != 0.0).then(|| 10.0_f64.powf(gain / 20.0) as f32)
+}
+
+/// Checks whether an audio file can be transcoded while it is read, without holding all of it
+/// in memory. This requires the transcode to be done in a single decoding pass that does not
+/// need to look at the whole file beforehand, and the input file to not be needed after
+/// transcoding, which is not the case for Ogg files, as they may be reoptimized instead.
+fn can_transcode_while_reading(is_ogg: bool, optimization_settings: &AudioFileOptions) -> bool {
+	!is_ogg
+		&& !optimization_settings.bandwidth_adaptive_sampling_frequency
+		&& optimization_settings.target_loudness.is_none()
+		&& optimization_settings.silence_trimming_threshold.is_none()
+		&& optimization_settings
+			.retained_vorbis_comment_fields
+			.is_empty()
+}
+
+/// Returns the number of channels the audio should be mixed to, according to the
+/// specified optimization settings.
+fn target_channels(optimization_settings: &AudioFileOptions) -> Option<ChannelCount> {
+	match optimization_settings.channels {
+		Some(ChannelMixingOption::ToChannels(count)) => Some(count),
+		Some(ChannelMixingOption::Skip) => None,
+		// User interface sounds are short clicks and chimes that barely benefit from stereo,
+		// and Minecraft does not attenuate positional sounds unless they are mono
+		None if optimization_settings.sound_category == Some(SoundCategory::Ui)
+			|| optimization_settings.stereo_positional_sound_handling
+				== StereoPositionalSoundHandling::Downmix =>
+		{
+			ChannelCount::try_from(NonZeroU8::MIN).ok()
+		}
+		None => None
+	}
+}
+
+/// Returns the number of channels that the audio file generated from the specified input audio
+/// file would have with the specified optimization settings, or `None` if the input audio file
+/// could not be decoded.
+pub fn output_channel_count(
+	input_file: Bytes,
+	is_ogg: bool,
+	optimization_settings: &AudioFileOptions
+) -> Option<NonZeroU8> {
+	// Ogg files that are not transcoded keep their channels. Redundant transcodes are only
+	// skipped when they would not change the channels
+	match target_channels(optimization_settings) {
+		Some(target_channels) if !is_ogg || optimization_settings.transcode_ogg => {
+			Some(target_channels.into())
+		}
+		_ => input_channel_count(input_file.reader(), is_ogg).ok()
+	}
+}
+
+/// Checks whether transcoding the specified Ogg Vorbis file again would be redundant, because
+/// it was already encoded by PackSquash, or at or below the target bitrate, and transcoding it
+/// would not change its channels, sampling frequency, pitch, loudness or duration. Transcoding
+/// such a file would mostly add generational quality loss, without saving meaningful space.
+///
+/// Files encoded by PackSquash are recognized by their empty Vorbis vendor string, which
+/// PackSquash always empties and other encoders always fill with their name and version. The
+/// nominal bitrate of the file is only compared when a bitrate control mode other than CQF is
+/// used, as quality factors do not map to bitrates.
+fn is_transcoding_redundant(ogg_file: &[u8], optimization_settings: &AudioFileOptions) -> bool {
+	let (Some(identification_header), Some(comment_header)) =
+		(ogg_packet(ogg_file, 0), ogg_packet(ogg_file, 1))
+	else {
+		return false;
+	};
+
+	// References:
+	// - <https://xiph.org/vorbis/doc/Vorbis_I_spec.html#x1-630004.2.2>
+	if identification_header.len() < 30 || !identification_header.starts_with(b"\x01vorbis") {
+		return false;
+	}
+	let channels = identification_header[11];
+	let Some(sampling_frequency) = NonZeroU32::new(u32::from_le_bytes(
+		identification_header[12..16].try_into().unwrap()
+	)) else {
+		return false;
+	};
+	let nominal_bitrate = i32::from_le_bytes(identification_header[20..24].try_into().unwrap());
+
+	let would_change_audio = optimization_settings.target_pitch != 1.0
+		|| optimization_settings.target_loudness.is_some()
+		|| optimization_settings.silence_trimming_threshold.is_some()
+		|| optimization_settings.bandwidth_adaptive_sampling_frequency
+		|| target_channels(optimization_settings)
+			.is_some_and(|target_channels| NonZeroU8::from(target_channels).get() != channels)
+		|| output_sampling_frequency(
+			optimization_settings,
+			sampling_frequency,
+			channels == 1,
+			None
+		) != sampling_frequency;
+	if would_change_audio {
+		return false;
+	}
+
+	let encoded_by_packsquash = comment_header.get(7..11) == Some(&[0; 4]);
+	let at_or_below_target_bitrate = !matches!(
+		optimization_settings.bitrate_control_mode,
+		AudioBitrateControlMode::Cqf
+	) && nominal_bitrate > 0
+		&& target_bitrate_control_metric_to_bitrate(optimization_settings)
+			.is_ok_and(|target_bitrate| nominal_bitrate as u32 <= target_bitrate.get());
+
+	encoded_by_packsquash || at_or_below_target_bitrate
+}
+
+/// Returns the sampling frequency that audio with the specified input sampling frequency
+/// should be resampled to, according to the specified optimization settings and the lowest
+/// sampling frequency that keeps all of its audible content, if known.
+fn output_sampling_frequency(
+	optimization_settings: &AudioFileOptions,
+	input_sampling_frequency: NonZeroU32,
+	is_positional_audio: bool,
+	bandwidth_limited_sampling_frequency: Option<NonZeroU32>
+) -> NonZeroU32 {
+	// Resampling to a frequency higher than the input one is a bad idea at
+	// this point: it doesn't add meaningful audio information or helps to use
+	// different signal processing filters, but it definitely increases space
+	// costs. Let's not do that
+	cmp::min(
+		optimization_settings.sampling_frequency.unwrap_or(
+			match optimization_settings.sound_category {
+				Some(SoundCategory::Music) => MUSIC_SAMPLING_FREQUENCY,
+				Some(SoundCategory::Ambient) => POSITIONAL_AUDIO_SAMPLING_FREQUENCY,
+				_ if is_positional_audio => POSITIONAL_AUDIO_SAMPLING_FREQUENCY,
+				_ => NON_POSITIONAL_AUDIO_SAMPLING_FREQUENCY
+			}
+		),
+		bandwidth_limited_sampling_frequency.unwrap_or(input_sampling_frequency)
+	)
+}
+
+/// Computes a fingerprint of the audio data of the specified audio file, which is equal for
+/// audio files that decode to the same channel count, sampling frequency and samples,
+/// regardless of their container format, codec and metadata. The samples are quantized to 16
+/// bits before being fingerprinted, so tiny decoder rounding differences are ignored. `None`
+/// is returned if the audio file could not be decoded.
+pub fn decoded_audio_fingerprint(input_file: Bytes, is_ogg: bool) -> Option<[u8; 32]> {
+	let hasher = Cell::new(Some(Sha256::new()));
+
+	decode_and_process_sample_blocks(
+		input_file.reader(),
+		is_ogg,
+		None,
+		|input_sampling_frequency, input_channels, _| {
+			if let Some(mut sample_hasher) = hasher.take() {
+				sample_hasher.update([input_channels.get()]);
+				sample_hasher.update(input_sampling_frequency.get().to_le_bytes());
+				hasher.set(Some(sample_hasher));
+			}
+
+			Ok(input_sampling_frequency)
+		},
+		1.0,
+		|block| {
+			if let Some(mut sample_hasher) = hasher.take() {
+				// Hash the samples of each frame together, so that the fingerprint does not
+				// depend on how frames are split in blocks
+				for frame_index in 0..block[0].len() {
+					for channel_samples in block {
+						sample_hasher.update(
+							((channel_samples[frame_index] * 32767.0).round() as i16).to_le_bytes()
+						);
+					}
+				}
+				hasher.set(Some(sample_hasher));
+			}
+
+			Ok(())
+		}
+	)
+	.ok()?;
+
+	hasher
+		.take()
+		.map(|sample_hasher| sample_hasher.finalize().into())
+}
+
+/// Returns whether a file with the specified extension is an Ogg file, whose audio data is
+/// decoded with libvorbis instead of Symphonia. The comparison is case-insensitive.
+pub fn is_ogg_extension(extension: &str) -> bool {
+	extension.eq_ignore_ascii_case("ogg") || extension.eq_ignore_ascii_case("oga")
+}
+
+/// Analyzes the specified audio file, as decoded, without any processing, generating a preview
+/// of up to its first `preview_duration` of audio and diagnostics about its levels as
+/// requested. The audio file is decoded only once: the samples of the preview are kept
+/// aside while decoding and encoded by [`audio_preview`] afterwards. `None` is returned if
+/// the audio file could not be decoded.
+pub fn analyze_audio(
+	input_file: Bytes,
+	is_ogg: bool,
+	preview_duration: Option<Duration>,
+	diagnose: bool
+) -> Option<AudioAnalysis> {
+	let audio_format = Cell::new(None);
+	let analyzers = Cell::new(None);
+	let remaining_preview_frames = Cell::new(0);
+	let mut preview_samples = vec![];
+
+	decode_and_process_sample_blocks(
+		input_file.reader(),
+		is_ogg,
+		None,
+		|input_sampling_frequency, input_channel_count, _| {
+			audio_format.set(Some((input_sampling_frequency, input_channel_count)));
+
+			if let Some(preview_duration) = preview_duration {
+				remaining_preview_frames.set(
+					(preview_duration.as_secs_f64() * input_sampling_frequency.get() as f64) as usize
+				);
+			}
+
+			if diagnose {
+				analyzers.set(Some((
+					LoudnessMeter::new(
+						input_sampling_frequency.get(),
+						input_channel_count.get() as usize
+					),
+					LevelAnalyzer::new(input_channel_count.get() as usize)
+				)));
+			}
+
+			Ok(input_sampling_frequency)
+		},
+		1.0,
+		|block| {
+			let preview_frames = block[0].len().min(remaining_preview_frames.get());
+			for i in 0..preview_frames {
+				preview_samples.extend(block.iter().map(|channel| channel[i]));
+			}
+			remaining_preview_frames.set(remaining_preview_frames.get() - preview_frames);
+
+			if let Some((mut loudness_meter, mut level_analyzer)) = analyzers.take() {
+				loudness_meter.add_sample_block(block);
+				level_analyzer.add_sample_block(block);
+				analyzers.set(Some((loudness_meter, level_analyzer)));
+			}
+
+			Ok(())
+		}
+	)
+	.ok()?;
+
+	let (sampling_frequency, channels) = audio_format.take()?;
+
+	// The preview samples are few, so decoding them again from a WAV file to resample and
+	// downmix them is cheap
+	let preview = preview_duration.and_then(|preview_duration| {
+		audio_preview(
+			float_wav_file(sampling_frequency, channels, &preview_samples)?.into(),
+			false,
+			preview_duration
+		)
+	});
+
+	let diagnostics = analyzers.take().map(|(loudness_meter, level_analyzer)| {
+		let LoudnessMeasurement {
+			integrated_loudness,
+			true_peak
+		} = loudness_meter.finish();
+
+		AudioDiagnostics {
+			integrated_loudness,
+			true_peak,
+			clipped_sample_count: level_analyzer.clipped_sample_count(),
+			dc_offset: level_analyzer.dc_offset()
+		}
+	});
+
+	Some(AudioAnalysis {
+		preview,
+		diagnostics
+	})
+}
+
+/// Generates a low-bitrate, mono Ogg Vorbis preview of the specified audio file, containing
+/// up to its first `maximum_duration` of audio, which is meant to be embedded in pack
+/// browsers and review tools rather than played by Minecraft. `None` is returned if the
+/// audio file could not be decoded or the preview could not be encoded.
+fn audio_preview(input_file: Bytes, is_ogg: bool, maximum_duration: Duration) -> Option<Vec<u8>> {
+	let mut preview_file = vec![];
+	let encoder = Cell::new(None);
+	let remaining_frames = Cell::new(0);
+
+	decode_and_process_sample_blocks(
+		input_file.reader(),
+		is_ogg,
+		Some(ChannelCount::try_from(NonZeroU8::MIN).unwrap()),
+		|input_sampling_frequency, _, output_channel_count| {
+			let output_sampling_frequency = input_sampling_frequency.min(PREVIEW_SAMPLING_FREQUENCY);
+			remaining_frames.set(
+				(maximum_duration.as_secs_f64() * output_sampling_frequency.get() as f64) as usize
+			);
+
+			encoder.set(Some(
+				VorbisEncoderBuilder::new_with_serial(
+					output_sampling_frequency,
+					output_channel_count,
+					&mut preview_file,
+					1
+				)
+				.bitrate_management_strategy(VorbisBitrateManagementStrategy::QualityVbr {
+					target_quality: PREVIEW_TARGET_QUALITY
+				})
+				.build()?
+			));
+
+			Ok(output_sampling_frequency)
+		},
+		1.0,
+		|block| {
+			// The whole file is still decoded after the preview is complete, but decoding is
+			// much faster than encoding
+			let frames = block[0].len().min(remaining_frames.get());
+			if frames > 0
+				&& let Some(mut vorbis_encoder) = encoder.take()
+			{
+				vorbis_encoder.encode_audio_block([&block[0][..frames]])?;
+				remaining_frames.set(remaining_frames.get() - frames);
+				encoder.set(Some(vorbis_encoder));
+			}
+
+			Ok(())
+		}
+	)
+	.ok()?;
+
+	encoder.take()?.finish().ok()?;
+	drop(encoder);
+
+	Some(preview_file)
+}
+
+/// Concatenates the decoded audio data of the specified audio files, which are given with
+/// whether they are Ogg Vorbis files, into a single WAV file with 32-bit floating point samples.
+/// Samples are not altered in any way, so there are no seams between the parts, and the WAV
+/// file can be transcoded later without additional quality loss. `None` is returned if some
+/// audio file could not be decoded, or the audio files have different sampling frequencies or
+/// channel counts.
+pub fn concatenate_audio(parts: impl IntoIterator<Item = (Bytes, bool)>) -> Option<Vec<u8>> {
+	let mut audio_format = None;
+	let mut samples = Vec::new();
+
+	for (part, is_ogg) in parts {
+		let part_audio_format = Cell::new(None);
+		let mut part_samples = Vec::new();
+
+		decode_and_process_sample_blocks(
+			part.reader(),
+			is_ogg,
+			None,
+			|input_sampling_frequency, _, output_channel_count| {
+				part_audio_format.set(Some((input_sampling_frequency, output_channel_count)));
+				Ok(input_sampling_frequency)
+			},
+			1.0,
+			|block| {
+				// WAV files store samples interleaved
+				for i in 0..block[0].len() {
+					part_samples.extend(block.iter().map(|channel| channel[i]));
+				}
+
+				Ok(())
+			}
+		)
+		.ok()?;
+
+		let part_audio_format = part_audio_format.take()?;
+		if *audio_format.get_or_insert(part_audio_format) != part_audio_format {
+			return None;
+		}
+
+		samples.append(&mut part_samples);
+	}
+
+	let (sampling_frequency, channels) = audio_format?;
+
+	float_wav_file(sampling_frequency, channels, &samples)
+}
+
+/// Writes the specified interleaved samples to a WAV file with 32-bit floating point samples,
+/// which keeps them exactly as they are. `None` is returned if there are too many samples to
+/// fit in a WAV file.
+fn float_wav_file(
+	sampling_frequency: NonZeroU32,
+	channels: NonZeroU8,
+	samples: &[f32]
+) -> Option<Vec<u8>> {
+	let channels = channels.get() as u16;
+	let data_size = u32::try_from(samples.len() * size_of::<f32>()).ok()?;
+
+	// Write a WAVE_FORMAT_IEEE_FLOAT file, which requires a fact chunk with the frame count.
+	// References:
+	// - <https://www.mmsp.ece.mcgill.ca/Documents/AudioFormats/WAVE/WAVE.html>
+	let mut wav_file = Vec::with_capacity(58 + data_size as usize);
+	wav_file.extend_from_slice(b"RIFF");
+	wav_file.extend_from_slice(&(50 + data_size).to_le_bytes());
+	wav_file.extend_from_slice(b"WAVEfmt ");
+	wav_file.extend_from_slice(&18u32.to_le_bytes());
+	wav_file.extend_from_slice(&3u16.to_le_bytes());
+	wav_file.extend_from_slice(&channels.to_le_bytes());
+	wav_file.extend_from_slice(&sampling_frequency.get().to_le_bytes());
+	wav_file.extend_from_slice(
+		&(sampling_frequency.get() * channels as u32 * size_of::<f32>() as u32).to_le_bytes()
+	);
+	wav_file.extend_from_slice(&(channels * size_of::<f32>() as u16).to_le_bytes());
+	wav_file.extend_from_slice(&32u16.to_le_bytes());
+	wav_file.extend_from_slice(&0u16.to_le_bytes());
+	wav_file.extend_from_slice(b"fact");
+	wav_file.extend_from_slice(&4u32.to_le_bytes());
+	wav_file.extend_from_slice(&((samples.len() / channels as usize) as u32).to_le_bytes());
+	wav_file.extend_from_slice(b"data");
+	wav_file.extend_from_slice(&data_size.to_le_bytes());
+	for sample in samples {
+		wav_file.extend_from_slice(&sample.to_le_bytes());
+	}
+
+	Some(wav_file)
+}
+
 /// Validates and optimizes the specified Ogg Vorbis file in two passes, using OptiVorbis.
+/// Its Vorbis comments are deleted unless `retain_comments` is `true`.
 fn validate_and_optimize(
 	input_file: impl Read + Seek,
-	obfuscate: bool
+	obfuscate: bool,
+	retain_comments: bool
 ) -> Result<Vec<u8>, OptimizationError> {
 	let mut too_long_for_minecraft = false;
 	// FIXME write to a SpooledTempFile whose maximum memory buffer size
@@ -373,7 +1147,11 @@ fn validate_and_optimize(
 		},
 		{
 			let mut optimizer_settings = VorbisOptimizerSettings::default();
-			optimizer_settings.comment_fields_action = VorbisCommentFieldsAction::Delete;
+			optimizer_settings.comment_fields_action = if retain_comments {
+				VorbisCommentFieldsAction::Copy
+			} else {
+				VorbisCommentFieldsAction::Delete
+			};
 			optimizer_settings.vendor_string_action = VorbisVendorStringAction::Empty;
 			optimizer_settings
 		}
@@ -395,10 +1173,11 @@ fn target_bitrate_control_metric_to_quality(
 ) -> f32 {
 	let target_bitrate_control_metric = optimization_settings
 		.target_bitrate_control_metric
-		.unwrap_or(if is_positional_audio {
-			POSITIONAL_AUDIO_TARGET_QUALITY
-		} else {
-			NON_POSITIONAL_AUDIO_TARGET_QUALITY
+		.unwrap_or(match optimization_settings.sound_category {
+			Some(SoundCategory::Music) => MUSIC_TARGET_QUALITY,
+			Some(SoundCategory::Ambient) => POSITIONAL_AUDIO_TARGET_QUALITY,
+			_ if is_positional_audio => POSITIONAL_AUDIO_TARGET_QUALITY,
+			_ => NON_POSITIONAL_AUDIO_TARGET_QUALITY
 		});
 
 	// Convert the more user-friendly range of [-2, 10] to the
@@ -428,14 +1207,28 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFile for AudioFile<T> {
 	type OptimizedByteChunksStream = FramedRead<T, OptimizerDecoder>;
 
 	fn process(self) -> FramedRead<T, OptimizerDecoder> {
+		let streaming_transcode =
+			can_transcode_while_reading(self.is_ogg, &self.optimization_settings)
+				.then(|| StreamingTranscode::start(self.optimization_settings.clone()))
+				.flatten();
+
+		// Files transcoded while they are read are never held in memory as a whole, so there
+		// is no point in allocating a buffer for all of them
+		let buffer_capacity = if streaming_transcode.is_some() {
+			cmp::min(self.file_length_hint, STREAMING_INPUT_BUFFER_CAPACITY)
+		} else {
+			self.file_length_hint
+		};
+
 		FramedRead::with_capacity(
 			self.read,
 			OptimizerDecoder {
 				optimization_settings: self.optimization_settings,
 				is_ogg: self.is_ogg,
-				reached_eof: false
+				reached_eof: false,
+				streaming_transcode
 			},
-			self.file_length_hint
+			buffer_capacity
 		)
 	}
 
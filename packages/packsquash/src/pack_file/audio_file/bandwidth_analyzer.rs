@@ -0,0 +1,174 @@
+//! Implements the analysis of the effective bandwidth of audio signals, which is used to find
+//! the lowest sampling frequency they can be resampled to without losing audible content.
+
+use std::f64::consts::PI;
+use std::num::NonZeroU32;
+
+/// The common sampling frequencies, in Hz, that signals may be resampled to when their
+/// bandwidth allows it, in increasing order.
+const CANDIDATE_SAMPLING_FREQUENCIES: [u32; 6] = [8_000, 11_025, 16_000, 22_050, 24_000, 32_000];
+/// The number of samples of the frames whose spectrum is analyzed. This must be a power of two.
+/// Bigger frames have a finer frequency resolution.
+const FRAME_SIZE: usize = 2048;
+/// The maximum energy of the content above the Nyquist frequency of a candidate sampling
+/// frequency, relative to the energy of the whole signal, for that content to be considered
+/// inaudible. This is -60 dB.
+const INAUDIBLE_CONTENT_RELATIVE_ENERGY: f64 = 1e-6;
+
+/// Estimates the power spectrum of an audio signal, whose samples are fed to it in blocks,
+/// to find the lowest of several common sampling frequencies whose Nyquist frequency (half
+/// of it) has no audible content above it. The spectrum is estimated by averaging the
+/// spectrums of consecutive Hann-windowed frames of every channel.
+pub struct BandwidthAnalyzer {
+	sampling_frequency: NonZeroU32,
+	window: Vec<f64>,
+	twiddle_factors: Vec<(f64, f64)>,
+	pending_samples: Vec<Vec<f32>>,
+	frame_spectrum: Vec<(f64, f64)>,
+	power_spectrum: Vec<f64>
+}
+
+impl BandwidthAnalyzer {
+	/// Creates a new bandwidth analyzer for a signal with the specified sampling frequency,
+	/// in Hz, and number of channels.
+	pub fn new(sampling_frequency: NonZeroU32, channels: usize) -> Self {
+		Self {
+			sampling_frequency,
+			window: hann_window(FRAME_SIZE),
+			twiddle_factors: (0..FRAME_SIZE / 2)
+				.map(|k| {
+					let (sin, cos) = (-2.0 * PI * k as f64 / FRAME_SIZE as f64).sin_cos();
+					(cos, sin)
+				})
+				.collect(),
+			pending_samples: vec![Vec::with_capacity(FRAME_SIZE); channels],
+			frame_spectrum: Vec::with_capacity(FRAME_SIZE),
+			power_spectrum: vec![0.0; FRAME_SIZE / 2 + 1]
+		}
+	}
+
+	/// Feeds a block of samples to this analyzer. The block contains a sample vector for
+	/// each channel, all of them with the same length.
+	pub fn add_sample_block(&mut self, block: &[Vec<f32>]) {
+		for (pending_samples, samples) in self.pending_samples.iter_mut().zip(block) {
+			pending_samples.extend_from_slice(samples);
+		}
+
+		while self.pending_samples[0].len() >= FRAME_SIZE {
+			for channel in 0..self.pending_samples.len() {
+				self.analyze_frame(channel);
+				self.pending_samples[channel].drain(..FRAME_SIZE);
+			}
+		}
+	}
+
+	/// Finishes the analysis, returning the lowest candidate sampling frequency lower than
+	/// the sampling frequency of the signal that keeps all of its audible content, or `None`
+	/// if no candidate does, or the signal is silent.
+	pub fn finish(mut self) -> Option<NonZeroU32> {
+		// Analyze the last, incomplete frame as if it was padded with silence. Its samples
+		// are windowed on their own, as cutting them abruptly would add spurious content at
+		// every frequency
+		if !self.pending_samples[0].is_empty() {
+			self.window = hann_window(self.pending_samples[0].len());
+			for channel in 0..self.pending_samples.len() {
+				self.analyze_frame(channel);
+			}
+		}
+
+		let total_energy = self.power_spectrum.iter().sum::<f64>();
+		if total_energy == 0.0 {
+			return None;
+		}
+
+		let bin_bandwidth = self.sampling_frequency.get() as f64 / FRAME_SIZE as f64;
+		CANDIDATE_SAMPLING_FREQUENCIES
+			.into_iter()
+			.filter(|&frequency| frequency < self.sampling_frequency.get())
+			.find(|&frequency| {
+				let lost_energy = self
+					.power_spectrum
+					.iter()
+					.enumerate()
+					.filter(|(bin, _)| *bin as f64 * bin_bandwidth > frequency as f64 / 2.0)
+					.map(|(_, power)| power)
+					.sum::<f64>();
+
+				lost_energy <= total_energy * INAUDIBLE_CONTENT_RELATIVE_ENERGY
+			})
+			.and_then(NonZeroU32::new)
+	}
+
+	/// Adds the power spectrum of the first frame of pending samples of the specified channel,
+	/// padded with silence if needed, to the estimated power spectrum of the signal.
+	fn analyze_frame(&mut self, channel: usize) {
+		self.frame_spectrum.clear();
+		self.frame_spectrum.extend(
+			self.pending_samples[channel]
+				.iter()
+				.take(FRAME_SIZE)
+				.zip(&self.window)
+				.map(|(&sample, window)| (sample as f64 * window, 0.0))
+		);
+		self.frame_spectrum.resize(FRAME_SIZE, (0.0, 0.0));
+
+		fft(&mut self.frame_spectrum, &self.twiddle_factors);
+
+		for (power, (re, im)) in self.power_spectrum.iter_mut().zip(&self.frame_spectrum) {
+			*power += re * re + im * im;
+		}
+	}
+}
+
+/// Returns a Hann window with the specified length, which smoothly tapers the samples of a
+/// frame to zero at its edges to minimize spectral leakage.
+fn hann_window(length: usize) -> Vec<f64> {
+	(0..length)
+		.map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / length as f64).cos())
+		.collect()
+}
+
+/// Computes the discrete Fourier transform of the specified complex signal in place, using
+/// the iterative radix-2 Cooley-Tukey algorithm. The length of the signal must be a power of
+/// two, and the twiddle factors must be `e^(-2πik/n)` for `k` in `0..n / 2`, where `n` is that
+/// length.
+fn fft(signal: &mut [(f64, f64)], twiddle_factors: &[(f64, f64)]) {
+	let n = signal.len();
+
+	// Reorder the signal by bit-reversed indices, so that the butterflies can be done in place
+	let mut j = 0;
+	for i in 1..n {
+		let mut bit = n >> 1;
+		while j & bit != 0 {
+			j ^= bit;
+			bit >>= 1;
+		}
+		j |= bit;
+
+		if i < j {
+			signal.swap(i, j);
+		}
+	}
+
+	let mut length = 2;
+	while length <= n {
+		let twiddle_factor_stride = n / length;
+
+		for start in (0..n).step_by(length) {
+			for k in 0..length / 2 {
+				let (twiddle_re, twiddle_im) = twiddle_factors[k * twiddle_factor_stride];
+				let (odd_re, odd_im) = signal[start + k + length / 2];
+				let (even_re, even_im) = signal[start + k];
+				let twiddled_odd = (
+					odd_re * twiddle_re - odd_im * twiddle_im,
+					odd_re * twiddle_im + odd_im * twiddle_re
+				);
+
+				signal[start + k] = (even_re + twiddled_odd.0, even_im + twiddled_odd.1);
+				signal[start + k + length / 2] = (even_re - twiddled_odd.0, even_im - twiddled_odd.1);
+			}
+		}
+
+		length <<= 1;
+	}
+}
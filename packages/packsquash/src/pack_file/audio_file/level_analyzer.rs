@@ -0,0 +1,80 @@
+//! Implements the detection of level issues in audio signals that are better fixed in their
+//! source material, such as clipping and DC offset.
+
+/// The minimum absolute sample value that is considered to be at full scale. It is slightly
+/// below 1 to account for the rounding of integer sample formats.
+const FULL_SCALE_LEVEL: f32 = 0.999;
+/// The minimum number of consecutive full-scale samples of a channel that are considered to
+/// be clipped. Properly normalized audio may reach full scale at isolated samples, but clipped
+/// audio has its waveform peaks flattened at full scale.
+const MINIMUM_CLIPPED_RUN_LENGTH: u64 = 3;
+
+/// Analyzes the clipping and DC offset of a mono or stereo audio signal, whose samples are fed
+/// to it in blocks.
+pub struct LevelAnalyzer {
+	channel_sample_sums: Vec<f64>,
+	full_scale_run_lengths: Vec<u64>,
+	frame_count: u64,
+	clipped_sample_count: u64
+}
+
+impl LevelAnalyzer {
+	/// Creates a new level analyzer for a signal with the specified number of channels.
+	pub fn new(channels: usize) -> Self {
+		Self {
+			channel_sample_sums: vec![0.0; channels],
+			full_scale_run_lengths: vec![0; channels],
+			frame_count: 0,
+			clipped_sample_count: 0
+		}
+	}
+
+	/// Feeds a block of samples to this analyzer. The block contains a sample vector for
+	/// each channel, all of them with the same length.
+	pub fn add_sample_block(&mut self, block: &[Vec<f32>]) {
+		for ((channel_samples, sample_sum), full_scale_run_length) in block
+			.iter()
+			.zip(&mut self.channel_sample_sums)
+			.zip(&mut self.full_scale_run_lengths)
+		{
+			for &sample in channel_samples {
+				*sample_sum += sample as f64;
+
+				if sample.abs() >= FULL_SCALE_LEVEL {
+					*full_scale_run_length += 1;
+
+					// Count the whole run once it is long enough, and every sample after that
+					self.clipped_sample_count += match *full_scale_run_length {
+						MINIMUM_CLIPPED_RUN_LENGTH => MINIMUM_CLIPPED_RUN_LENGTH,
+						run_length if run_length > MINIMUM_CLIPPED_RUN_LENGTH => 1,
+						_ => 0
+					};
+				} else {
+					*full_scale_run_length = 0;
+				}
+			}
+		}
+
+		self.frame_count += block[0].len() as u64;
+	}
+
+	/// Returns the number of samples fed so far that are part of runs of consecutive samples
+	/// at full scale, which are likely to be clipped.
+	pub fn clipped_sample_count(&self) -> u64 {
+		self.clipped_sample_count
+	}
+
+	/// Returns the largest absolute mean sample value of any channel, relative to full scale,
+	/// which measures the DC offset of the signal. Signals without DC offset have a mean
+	/// sample value of zero.
+	pub fn dc_offset(&self) -> f64 {
+		if self.frame_count == 0 {
+			return 0.0;
+		}
+
+		self.channel_sample_sums
+			.iter()
+			.map(|sample_sum| (sample_sum / self.frame_count as f64).abs())
+			.fold(0.0, f64::max)
+	}
+}
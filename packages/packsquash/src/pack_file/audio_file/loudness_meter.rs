@@ -0,0 +1,241 @@
+//! Implements the measurement of the integrated loudness and true peak level of audio signals,
+//! as defined by the ITU-R BS.1770-4 recommendation that the EBU R128 recommendation is based on.
+//!
+//! References:
+//! - <https://www.itu.int/rec/R-REC-BS.1770>
+//! - <https://tech.ebu.ch/publications/r128>
+
+use std::f64::consts::PI;
+
+/// The duration of the segments whose energy is accumulated, in seconds. Gating blocks are
+/// made of [`SEGMENTS_PER_GATING_BLOCK`] consecutive segments, so this is the gating block
+/// step, for a 75% overlap between blocks.
+const SEGMENT_DURATION: f64 = 0.1;
+/// The number of segments in a 400 ms gating block.
+const SEGMENTS_PER_GATING_BLOCK: usize = 4;
+/// The loudness below which gating blocks are ignored, in LUFS.
+const ABSOLUTE_GATE: f64 = -70.0;
+/// The loudness below the ungated loudness of the signal at which gating blocks are
+/// ignored, in LU.
+const RELATIVE_GATE: f64 = -10.0;
+/// The oversampling factor used to estimate the true peak level of a signal.
+const TRUE_PEAK_OVERSAMPLING_FACTOR: usize = 4;
+/// The number of samples interpolated by the windowed sinc filter used to estimate the
+/// sample values between the actual samples of a signal.
+const TRUE_PEAK_INTERPOLATION_TAPS: usize = 12;
+
+/// Measures the integrated loudness and true peak level of a mono or stereo audio signal,
+/// whose samples are fed to it in blocks.
+pub struct LoudnessMeter {
+	k_weighting_filters: Vec<[Biquad; 2]>,
+	segment_length: usize,
+	segment_sample_count: usize,
+	segment_energy: f64,
+	segment_energies: Vec<f64>,
+	sample_history: Vec<[f32; TRUE_PEAK_INTERPOLATION_TAPS]>,
+	true_peak: f32
+}
+
+/// The result of a loudness measurement.
+#[derive(Debug, Clone, Copy)]
+pub struct LoudnessMeasurement {
+	/// The gated integrated loudness of the signal, in LUFS. It is `None` if every gating
+	/// block was below the absolute gate, such as for silent signals.
+	pub integrated_loudness: Option<f64>,
+	/// The estimated true peak level of the signal, in dBTP. It is negative infinity for
+	/// silent signals.
+	pub true_peak: f64
+}
+
+impl LoudnessMeter {
+	/// Creates a new loudness meter for a signal with the specified sampling frequency, in
+	/// Hz, and number of channels.
+	pub fn new(sampling_frequency: u32, channels: usize) -> Self {
+		Self {
+			k_weighting_filters: vec![k_weighting_filter(sampling_frequency as f64); channels],
+			segment_length: ((sampling_frequency as f64 * SEGMENT_DURATION).round() as usize).max(1),
+			segment_sample_count: 0,
+			segment_energy: 0.0,
+			segment_energies: vec![],
+			sample_history: vec![[0.0; TRUE_PEAK_INTERPOLATION_TAPS]; channels],
+			true_peak: 0.0
+		}
+	}
+
+	/// Feeds a block of samples to this meter. The block contains a sample vector for
+	/// each channel, all of them with the same length.
+	pub fn add_sample_block(&mut self, block: &[Vec<f32>]) {
+		for i in 0..block[0].len() {
+			for (channel, channel_samples) in block.iter().enumerate() {
+				let sample = channel_samples[i];
+
+				let [pre_filter, rlb_filter] = &mut self.k_weighting_filters[channel];
+				let weighted_sample = rlb_filter.process(pre_filter.process(sample as f64));
+				self.segment_energy += weighted_sample * weighted_sample;
+
+				self.true_peak = self.true_peak.max(sample.abs());
+				let history = &mut self.sample_history[channel];
+				history.rotate_left(1);
+				history[TRUE_PEAK_INTERPOLATION_TAPS - 1] = sample;
+				for phase in 1..TRUE_PEAK_OVERSAMPLING_FACTOR {
+					self.true_peak = self.true_peak.max(interpolate(history, phase).abs());
+				}
+			}
+
+			self.segment_sample_count += 1;
+			if self.segment_sample_count == self.segment_length {
+				self.segment_energies.push(self.segment_energy);
+				self.segment_sample_count = 0;
+				self.segment_energy = 0.0;
+			}
+		}
+	}
+
+	/// Finishes the measurement, returning its result.
+	pub fn finish(self) -> LoudnessMeasurement {
+		// Signals shorter than a gating block, which are common for sound effects, are
+		// measured as a single block with every sample
+		let gating_block_energies = if self.segment_energies.len() < SEGMENTS_PER_GATING_BLOCK {
+			let sample_count =
+				self.segment_energies.len() * self.segment_length + self.segment_sample_count;
+			let energy = self.segment_energies.iter().sum::<f64>() + self.segment_energy;
+
+			if sample_count == 0 {
+				vec![]
+			} else {
+				vec![energy / sample_count as f64]
+			}
+		} else {
+			self.segment_energies
+				.windows(SEGMENTS_PER_GATING_BLOCK)
+				.map(|segments| {
+					segments.iter().sum::<f64>()
+						/ (SEGMENTS_PER_GATING_BLOCK * self.segment_length) as f64
+				})
+				.collect()
+		};
+
+		let gated_loudness = |gate: f64| {
+			let (energy_sum, block_count) = gating_block_energies
+				.iter()
+				.filter(|&&energy| loudness(energy) > gate)
+				.fold((0.0, 0), |(energy_sum, block_count), energy| {
+					(energy_sum + energy, block_count + 1)
+				});
+
+			(block_count > 0).then(|| loudness(energy_sum / block_count as f64))
+		};
+
+		LoudnessMeasurement {
+			integrated_loudness: gated_loudness(ABSOLUTE_GATE)
+				.and_then(|ungated_loudness| gated_loudness(ungated_loudness + RELATIVE_GATE)),
+			true_peak: 20.0 * (self.true_peak as f64).log10()
+		}
+	}
+}
+
+/// Converts the mean square of the K-weighted samples of every channel of a gating block,
+/// summed, to a loudness in LUFS. Channel weights are not needed, as they are all 1 for mono
+/// and stereo signals.
+fn loudness(energy: f64) -> f64 {
+	-0.691 + 10.0 * energy.log10()
+}
+
+/// Estimates the value of a signal at the specified phase between the two central samples
+/// of the history, using a Hann-windowed sinc interpolation filter.
+fn interpolate(history: &[f32; TRUE_PEAK_INTERPOLATION_TAPS], phase: usize) -> f32 {
+	let offset = phase as f64 / TRUE_PEAK_OVERSAMPLING_FACTOR as f64;
+
+	history
+		.iter()
+		.enumerate()
+		.map(|(i, &sample)| {
+			let distance = i as f64 - (TRUE_PEAK_INTERPOLATION_TAPS / 2 - 1) as f64 - offset;
+			let sinc = if distance == 0.0 {
+				1.0
+			} else {
+				(PI * distance).sin() / (PI * distance)
+			};
+			let window =
+				0.5 * (1.0 + (PI * distance / (TRUE_PEAK_INTERPOLATION_TAPS / 2) as f64).cos());
+
+			sample as f64 * sinc * window
+		})
+		.sum::<f64>() as f32
+}
+
+/// A second order IIR filter, in direct form I.
+#[derive(Clone, Copy)]
+struct Biquad {
+	b: [f64; 3],
+	a: [f64; 2],
+	input_history: [f64; 2],
+	output_history: [f64; 2]
+}
+
+impl Biquad {
+	/// Filters the next sample of a signal.
+	fn process(&mut self, sample: f64) -> f64 {
+		let output = self.b[0] * sample
+			+ self.b[1] * self.input_history[0]
+			+ self.b[2] * self.input_history[1]
+			- self.a[0] * self.output_history[0]
+			- self.a[1] * self.output_history[1];
+
+		self.input_history = [sample, self.input_history[0]];
+		self.output_history = [output, self.output_history[0]];
+
+		output
+	}
+}
+
+/// Returns the two filters that apply the K-weighting curve to a signal with the specified
+/// sampling frequency: a high shelf pre-filter that models the acoustic effects of the head,
+/// and the RLB high-pass filter. The BS.1770 recommendation only defines their coefficients
+/// for 48 kHz, so they are derived for other frequencies from their analog prototypes.
+fn k_weighting_filter(sampling_frequency: f64) -> [Biquad; 2] {
+	let pre_filter = {
+		const CENTER_FREQUENCY: f64 = 1681.974450955533;
+		const GAIN: f64 = 3.999843853973347;
+		const QUALITY_FACTOR: f64 = 0.7071752369554196;
+
+		let k = (PI * CENTER_FREQUENCY / sampling_frequency).tan();
+		let high_frequency_gain = 10.0_f64.powf(GAIN / 20.0);
+		let band_gain = high_frequency_gain.powf(0.4996667741545416);
+		let a0 = 1.0 + k / QUALITY_FACTOR + k * k;
+
+		Biquad {
+			b: [
+				(high_frequency_gain + band_gain * k / QUALITY_FACTOR + k * k) / a0,
+				2.0 * (k * k - high_frequency_gain) / a0,
+				(high_frequency_gain - band_gain * k / QUALITY_FACTOR + k * k) / a0
+			],
+			a: [
+				2.0 * (k * k - 1.0) / a0,
+				(1.0 - k / QUALITY_FACTOR + k * k) / a0
+			],
+			input_history: [0.0; 2],
+			output_history: [0.0; 2]
+		}
+	};
+
+	let rlb_filter = {
+		const CUTOFF_FREQUENCY: f64 = 38.13547087602444;
+		const QUALITY_FACTOR: f64 = 0.5003270373238773;
+
+		let k = (PI * CUTOFF_FREQUENCY / sampling_frequency).tan();
+		let a0 = 1.0 + k / QUALITY_FACTOR + k * k;
+
+		Biquad {
+			b: [1.0, -2.0, 1.0],
+			a: [
+				2.0 * (k * k - 1.0) / a0,
+				(1.0 - k / QUALITY_FACTOR + k * k) / a0
+			],
+			input_history: [0.0; 2],
+			output_history: [0.0; 2]
+		}
+	};
+
+	[pre_filter, rlb_filter]
+}
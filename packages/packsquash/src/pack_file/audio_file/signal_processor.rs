@@ -158,6 +158,21 @@ pub fn decode_and_process_sample_blocks(
 	Ok(is_silent)
 }
 
+/// Returns the number of channels of the audio signal contained in the specified source,
+/// without decoding any audio samples.
+pub fn input_channel_count(
+	source: impl Read + Send + Sync + 'static,
+	is_ogg: bool
+) -> Result<NonZeroU8, OptimizationError> {
+	let mut decoder = if is_ogg {
+		SignalDecoder::Vorbis(VorbisDecoder::new(source)?)
+	} else {
+		SignalDecoder::Symphonia(SymphoniaDecoder::new(source)?)
+	};
+
+	Ok(decoder.channels())
+}
+
 /// Constructs and executes a dasp pipeline to process the audio frames returned by a dasp
 /// signal, applying resampling, channel mixing and pitch shifting as specified. The raw,
 /// processed samples are then yielded in blocks to the specified consumer. The returned
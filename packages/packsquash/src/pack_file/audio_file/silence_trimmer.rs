@@ -0,0 +1,145 @@
+//! Implements the trimming of leading and trailing silence from audio signals, and the detection
+//! of loop point metadata that makes trimming unsafe.
+
+use aho_corasick::AhoCorasick;
+use std::sync::LazyLock;
+
+/// The Vorbis comment field names that are commonly used to store loop points in Ogg Vorbis
+/// and FLAC files, followed by the field separator.
+static LOOP_POINT_COMMENT_FIELDS: LazyLock<AhoCorasick> = LazyLock::new(|| {
+	AhoCorasick::builder()
+		.ascii_case_insensitive(true)
+		.build([
+			"LOOPSTART=",
+			"LOOP_START=",
+			"LOOPLENGTH=",
+			"LOOPEND=",
+			"LOOP_END="
+		])
+		.unwrap()
+});
+
+/// Removes the leading and trailing silent frames of an audio signal whose samples are fed
+/// to it in blocks. Silent frames between non-silent ones are kept.
+pub struct SilenceTrimmer {
+	threshold: f32,
+	sound_started: bool,
+	trimmed_leading_silence: bool,
+	pending_silence: Vec<Vec<f32>>
+}
+
+impl SilenceTrimmer {
+	/// Creates a new silence trimmer that considers silent the frames whose samples are all
+	/// below the specified threshold, in dBFS.
+	pub fn new(threshold: f32) -> Self {
+		Self {
+			threshold: 10.0_f32.powf(threshold / 20.0),
+			sound_started: false,
+			trimmed_leading_silence: false,
+			pending_silence: vec![]
+		}
+	}
+
+	/// Trims the silence of the next block of samples, which contains a sample vector for each
+	/// channel, writing the samples that should be kept to the output block. Silent frames that
+	/// may be trailing silence are held back until a non-silent frame follows them, so the
+	/// output block may contain more or fewer frames than the input block, including none.
+	pub fn trim_block(&mut self, block: &[Vec<f32>], output_block: &mut Vec<Vec<f32>>) {
+		output_block.resize_with(block.len(), Vec::new);
+		self.pending_silence.resize_with(block.len(), Vec::new);
+		for channel_samples in output_block.iter_mut() {
+			channel_samples.clear();
+		}
+
+		let is_loud_frame = |i: usize| {
+			block
+				.iter()
+				.any(|channel_samples| channel_samples[i].abs() >= self.threshold)
+		};
+		let frame_count = block[0].len();
+		let first_loud_frame = (0..frame_count).find(|&i| is_loud_frame(i));
+		let last_loud_frame = (0..frame_count).rev().find(|&i| is_loud_frame(i));
+
+		let (Some(first_loud_frame), Some(last_loud_frame)) = (first_loud_frame, last_loud_frame)
+		else {
+			// This block is completely silent. It may be followed by more sound, so keep it
+			// if the sound already started
+			if self.sound_started {
+				for (pending_samples, samples) in self.pending_silence.iter_mut().zip(block) {
+					pending_samples.extend_from_slice(samples);
+				}
+			} else {
+				self.trimmed_leading_silence |= frame_count > 0;
+			}
+
+			return;
+		};
+
+		let kept_frames_start = if self.sound_started {
+			0
+		} else {
+			self.trimmed_leading_silence |= first_loud_frame > 0;
+			first_loud_frame
+		};
+		self.sound_started = true;
+
+		for ((output_samples, pending_samples), samples) in output_block
+			.iter_mut()
+			.zip(&mut self.pending_silence)
+			.zip(block)
+		{
+			output_samples.append(pending_samples);
+			output_samples.extend_from_slice(&samples[kept_frames_start..=last_loud_frame]);
+			pending_samples.extend_from_slice(&samples[last_loud_frame + 1..]);
+		}
+	}
+
+	/// Returns whether any silence was trimmed, taking into account that the frames held back
+	/// by this trimmer when no more blocks follow are trailing silence.
+	pub fn trimmed_silence(&self) -> bool {
+		self.trimmed_leading_silence
+			|| self
+				.pending_silence
+				.first()
+				.is_some_and(|pending_samples| !pending_samples.is_empty())
+	}
+}
+
+/// Checks whether the specified audio file contains loop point metadata, which is used to
+/// loop sounds seamlessly, and whose timing would be broken by trimming their silence. The
+/// loop point Vorbis comments of Ogg Vorbis and FLAC files, and the sampler chunk loops of
+/// WAV files, are detected.
+pub fn has_loop_point_metadata(audio_file: &[u8]) -> bool {
+	LOOP_POINT_COMMENT_FIELDS.is_match(audio_file) || has_wav_sampler_loops(audio_file)
+}
+
+/// Checks whether the specified audio file is a WAV file with a sampler (`smpl`) chunk that
+/// defines some loop.
+///
+/// References:
+/// - <https://www.recordingblogs.com/wiki/sample-chunk-of-a-wave-file>
+fn has_wav_sampler_loops(audio_file: &[u8]) -> bool {
+	if audio_file.get(0..4) != Some(b"RIFF") || audio_file.get(8..12) != Some(b"WAVE") {
+		return false;
+	}
+
+	let u32_at = |offset: usize| {
+		audio_file
+			.get(offset..offset + 4)
+			.map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+	};
+
+	let mut chunk_offset = 12;
+	while let Some(chunk_id) = audio_file.get(chunk_offset..chunk_offset + 4)
+		&& let Some(chunk_size) = u32_at(chunk_offset + 4)
+	{
+		if chunk_id == b"smpl" {
+			return u32_at(chunk_offset + 8 + 28).is_some_and(|loop_count| loop_count > 0);
+		}
+
+		// Chunks are padded to an even size
+		chunk_offset += 8 + chunk_size + chunk_size % 2;
+	}
+
+	false
+}
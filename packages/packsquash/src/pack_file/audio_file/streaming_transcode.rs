@@ -0,0 +1,139 @@
+//! Implements the transcoding of audio files while they are being read, which avoids holding
+//! whole files in memory when they can be transcoded in a single decoding pass.
+
+use std::borrow::Cow;
+use std::io::{self, Read};
+use std::panic;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+
+use bytes::{Buf, Bytes};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tokio::task;
+
+use super::{OptimizationError, ResourceBudget, TRANSCODE_BUDGET, TranscodingAnalysis, transcode};
+use crate::config::AudioFileOptions;
+
+/// The maximum number of input chunks that may be waiting to be decoded. When the transcoder
+/// falls behind, the reading of the input file waits for it, bounding the memory used by
+/// pending input to this number of chunks.
+const MAXIMUM_PENDING_CHUNKS: usize = 16;
+
+/// The budget of transcoder threads that every streaming transcode reserves a unit from, so
+/// that the number of threads spawned for them stays bounded by the configured thread count.
+static TRANSCODER_THREAD_BUDGET: ResourceBudget = ResourceBudget::new();
+
+/// A transcode of a non-Ogg audio file that runs in a dedicated thread as the file is read,
+/// fed with chunks of the file as they become available.
+pub struct StreamingTranscode {
+	chunk_sender: SyncSender<Bytes>,
+	transcoder_thread: JoinHandle<Result<(Cow<'static, [u8]>, bool), OptimizationError>>
+}
+
+impl StreamingTranscode {
+	/// Starts a streaming transcode of a non-Ogg audio file with the specified optimization
+	/// settings, which must not require analyzing the whole file beforehand. `None` is returned
+	/// if as many streaming transcodes as the settings allow are already running, or if the
+	/// transcoder thread could not be spawned, so that the file is transcoded once fully read.
+	/// Waiting for a transcoder thread to become available instead could block the reading of
+	/// the files being transcoded, which would never finish.
+	pub fn start(optimization_settings: AudioFileOptions) -> Option<Self> {
+		let transcoder_thread_reservation = TRANSCODER_THREAD_BUDGET
+			.try_reserve(1, optimization_settings.streaming_transcodes_limit)?;
+		let (chunk_sender, chunk_receiver) = mpsc::sync_channel(MAXIMUM_PENDING_CHUNKS);
+
+		let transcoder_thread = thread::Builder::new()
+			.name("audio-transcoder".into())
+			.spawn(move || {
+				let _transcoder_thread_reservation = transcoder_thread_reservation;
+
+				// Wait for some other transcode to finish if too many are running at the same
+				// time. Meanwhile, the channel fills up and the file stops being read
+				let _transcode_reservation =
+					TRANSCODE_BUDGET.reserve(1, optimization_settings.concurrent_transcodes_limit);
+
+				transcode(
+					ChunkReader::new(chunk_receiver),
+					false,
+					&optimization_settings,
+					TranscodingAnalysis::default()
+				)
+			})
+			.ok()?;
+
+		Some(Self {
+			chunk_sender,
+			transcoder_thread
+		})
+	}
+
+	/// Hands off the next chunk of the input file to the transcoder, waiting for it to make
+	/// room for the chunk if necessary.
+	pub fn send_chunk(&self, chunk: Bytes) {
+		// The transcoder stops receiving chunks when it fails, and the error is returned
+		// when finishing, so ignore send errors
+		let _ = block_in_place(|| self.chunk_sender.send(chunk));
+	}
+
+	/// Signals the end of the input file to the transcoder, waiting for it to finish and
+	/// returning the transcoded file and whether the audio data was changed.
+	pub fn finish(self) -> Result<(Cow<'static, [u8]>, bool), OptimizationError> {
+		drop(self.chunk_sender);
+
+		block_in_place(|| self.transcoder_thread.join())
+			.unwrap_or_else(|panic_payload| panic::resume_unwind(panic_payload))
+	}
+}
+
+/// A [`Read`] implementation that yields the bytes of the chunks received through a channel
+/// in order, waiting for the next chunk to be sent when necessary. The end of the data is
+/// reached when every sender of the channel is dropped.
+struct ChunkReader {
+	// Receivers are not Sync, but audio decoders require their sources to be. Reading requires
+	// exclusive access anyway, so the mutex is never actually locked
+	chunk_receiver: Mutex<Receiver<Bytes>>,
+	current_chunk: Bytes
+}
+
+impl ChunkReader {
+	/// Creates a new chunk reader that reads the chunks received by the specified receiver.
+	fn new(chunk_receiver: Receiver<Bytes>) -> Self {
+		Self {
+			chunk_receiver: Mutex::new(chunk_receiver),
+			current_chunk: Bytes::new()
+		}
+	}
+}
+
+impl Read for ChunkReader {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		while !self.current_chunk.has_remaining() {
+			let chunk_receiver = self
+				.chunk_receiver
+				.get_mut()
+				.unwrap_or_else(PoisonError::into_inner);
+
+			match chunk_receiver.recv() {
+				Ok(chunk) => self.current_chunk = chunk,
+				Err(_) => return Ok(0)
+			}
+		}
+
+		let read_bytes = buf.len().min(self.current_chunk.remaining());
+		self.current_chunk.copy_to_slice(&mut buf[..read_bytes]);
+
+		Ok(read_bytes)
+	}
+}
+
+/// Runs the specified blocking closure, letting the Tokio runtime hand the other tasks of
+/// the current worker thread to another thread while it runs, if possible.
+fn block_in_place<R>(f: impl FnOnce() -> R) -> R {
+	if Handle::try_current().is_ok_and(|handle| handle.runtime_flavor() == RuntimeFlavor::MultiThread)
+	{
+		task::block_in_place(f)
+	} else {
+		f()
+	}
+}
@@ -187,7 +187,9 @@ async fn channel_mixing_works() {
 		OGG_AUDIO_DATA,
 		true, // Is Ogg
 		AudioFileOptions {
-			channels: ChannelMixingOption::ToChannels(NonZeroU8::new(2).unwrap().try_into().unwrap()),
+			channels: Some(ChannelMixingOption::ToChannels(
+				NonZeroU8::new(2).unwrap().try_into().unwrap()
+			)),
 			..Default::default()
 		},
 		false,                                   // Bigger file size
@@ -204,7 +206,9 @@ async fn channel_mixing_and_pitch_shifting_work() {
 		true, // Is Ogg
 		AudioFileOptions {
 			target_pitch: 1.25,
-			channels: ChannelMixingOption::ToChannels(NonZeroU8::new(2).unwrap().try_into().unwrap()),
+			channels: Some(ChannelMixingOption::ToChannels(
+				NonZeroU8::new(2).unwrap().try_into().unwrap()
+			)),
 			..Default::default()
 		},
 		false,                                   // Smaller file size
@@ -227,6 +231,462 @@ async fn transcoded_audio_is_not_upsampled() {
 	.await
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn loudness_normalization_works() {
+	successful_process_test(
+		FLAC_AUDIO_DATA,
+		false, // Is Ogg
+		AudioFileOptions {
+			target_loudness: Some(-30.0),
+			..Default::default()
+		},
+		false,                               // Smaller file size
+		1,                                   // One channel (mono)
+		POSITIONAL_AUDIO_SAMPLING_FREQUENCY  // Default sampling frequency
+	)
+	.await
+}
+
+#[test]
+fn loudness_meter_works() {
+	// A 1 kHz stereo sine wave with an amplitude of -20 dBFS, which should measure as
+	// -20 LUFS according to the BS.1770 recommendation
+	let samples = (0..96_000)
+		.map(|i| 0.1 * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / 48_000.0).sin())
+		.collect::<Vec<_>>();
+
+	let mut loudness_meter = LoudnessMeter::new(48_000, 2);
+	for block in samples.chunks(1000) {
+		loudness_meter.add_sample_block(&[block.to_vec(), block.to_vec()]);
+	}
+	let loudness_measurement = loudness_meter.finish();
+
+	let integrated_loudness = loudness_measurement
+		.integrated_loudness
+		.expect("The sine wave should not be gated out");
+	assert!(
+		(integrated_loudness + 20.0).abs() < 0.2,
+		"Unexpected integrated loudness: {integrated_loudness} LUFS"
+	);
+	assert!(
+		(loudness_measurement.true_peak + 20.0).abs() < 0.2,
+		"Unexpected true peak: {} dBTP",
+		loudness_measurement.true_peak
+	);
+
+	let silence_measurement = LoudnessMeter::new(48_000, 1).finish();
+	assert!(
+		loudness_normalization_gain(Some(silence_measurement), -16.0, -1.0).is_none(),
+		"Silence should not be normalized"
+	);
+	assert_eq!(
+		loudness_normalization_gain(Some(loudness_measurement), 0.0, -1.0)
+			.map(|gain| (20.0 * gain.log10()).round()),
+		Some(19.0),
+		"The normalization gain should be limited by the maximum true peak"
+	);
+}
+
+#[test]
+fn bandwidth_analyzer_works() {
+	let analyze_sine_wave = |frequency: f64| {
+		// Generate the samples with double precision, so that rounding errors do not add
+		// noise to the signal
+		let samples = (0..96_000)
+			.map(|i| {
+				(0.5 * (2.0 * std::f64::consts::PI * frequency * i as f64 / 48_000.0).sin()) as f32
+			})
+			.collect::<Vec<_>>();
+
+		let mut bandwidth_analyzer = BandwidthAnalyzer::new(NonZeroU32::new(48_000).unwrap(), 2);
+		for block in samples.chunks(1000) {
+			bandwidth_analyzer.add_sample_block(&[block.to_vec(), block.to_vec()]);
+		}
+		bandwidth_analyzer.finish().map(NonZeroU32::get)
+	};
+
+	assert_eq!(analyze_sine_wave(1000.0), Some(8000));
+	assert_eq!(analyze_sine_wave(10_000.0), Some(22_050));
+	assert_eq!(
+		analyze_sine_wave(20_000.0),
+		None,
+		"Content close to the Nyquist frequency should not allow lowering the sampling frequency"
+	);
+	assert_eq!(
+		BandwidthAnalyzer::new(NonZeroU32::new(48_000).unwrap(), 1).finish(),
+		None,
+		"Silence should not have its sampling frequency lowered"
+	);
+}
+
+#[test]
+fn audio_preview_works() {
+	let preview = |input_data, maximum_duration| {
+		audio_preview(
+			Bytes::from_static(input_data),
+			false,
+			Duration::from_millis(maximum_duration)
+		)
+		.expect("The preview should be generated")
+	};
+
+	let short_preview = preview(FLAC_AUDIO_DATA, 100);
+	assert_eq!(
+		&short_preview[0..4],
+		b"OggS",
+		"The preview should be an Ogg file"
+	);
+	assert_eq!(short_preview[39], 1, "The preview should be mono");
+	assert_eq!(
+		short_preview[40..44],
+		22_050_u32.to_le_bytes(),
+		"The preview sampling frequency should be reduced"
+	);
+	assert!(
+		short_preview.len() < preview(FLAC_AUDIO_DATA, 10_000).len(),
+		"Shorter previews should be smaller"
+	);
+
+	assert_eq!(
+		preview(FLAC_AUDIO_DATA_8KHZ, 100)[40..44],
+		8_000_u32.to_le_bytes(),
+		"The preview sampling frequency should not be increased"
+	);
+}
+
+#[test]
+fn audio_concatenation_works() {
+	let part = || (Bytes::from_static(FLAC_AUDIO_DATA), false);
+
+	let single_part =
+		concatenate_audio([part()]).expect("A single part should be concatenated to itself");
+	let concatenated_parts = concatenate_audio([part(), part()])
+		.expect("Parts with the same format should be concatenated");
+
+	// The WAV header is 58 bytes long
+	assert_eq!(concatenated_parts.len() - 58, 2 * (single_part.len() - 58));
+	assert!(
+		decoded_audio_fingerprint(concatenated_parts.into(), false).is_some(),
+		"The concatenated audio should be decodable"
+	);
+	assert!(
+		concatenate_audio([part(), (Bytes::from_static(FLAC_AUDIO_DATA_8KHZ), false)]).is_none(),
+		"Parts with different sampling frequencies should not be concatenated"
+	);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn silence_trimming_works() {
+	successful_process_test(
+		FLAC_AUDIO_DATA,
+		false, // Is Ogg
+		AudioFileOptions {
+			silence_trimming_threshold: Some(-40.0),
+			..Default::default()
+		},
+		false,                               // Smaller file size
+		1,                                   // One channel (mono)
+		POSITIONAL_AUDIO_SAMPLING_FREQUENCY  // Default sampling frequency
+	)
+	.await
+}
+
+#[test]
+fn silence_trimmer_works() {
+	let mut silence_trimmer = SilenceTrimmer::new(-20.0);
+	let mut trimmed_block = vec![];
+
+	let mut trim_block = |samples: &[f32]| {
+		silence_trimmer.trim_block(&[samples.to_vec()], &mut trimmed_block);
+		trimmed_block[0].clone()
+	};
+
+	assert!(
+		trim_block(&[0.0, 0.01]).is_empty(),
+		"Leading silence should be trimmed"
+	);
+	assert_eq!(
+		trim_block(&[0.01, 0.5, 0.0, -0.5, 0.0]),
+		[0.5, 0.0, -0.5],
+		"Silence between sounds should be kept, and trailing silence held back"
+	);
+	assert!(
+		trim_block(&[0.0]).is_empty(),
+		"Trailing silence should be held back"
+	);
+	assert_eq!(
+		trim_block(&[0.5]),
+		[0.0, 0.0, 0.5],
+		"Held back silence followed by sound should be kept"
+	);
+	assert!(trim_block(&[0.01]).is_empty());
+	assert!(
+		silence_trimmer.trimmed_silence(),
+		"Leading and trailing silence should have been trimmed"
+	);
+}
+
+#[test]
+fn loop_point_metadata_is_detected() {
+	assert!(has_loop_point_metadata(
+		b"OggS...\x03vorbis...loopStart=1024..."
+	));
+	assert!(!has_loop_point_metadata(FLAC_AUDIO_DATA));
+
+	let mut wav_file = b"RIFF\0\0\0\0WAVEfmt \x02\0\0\0\0\0smpl\x24\0\0\0".to_vec();
+	wav_file.extend_from_slice(&[0; 28]);
+	wav_file.extend_from_slice(&1_u32.to_le_bytes());
+	assert!(has_loop_point_metadata(&wav_file));
+}
+
+#[test]
+fn vorbis_comments_are_read_and_retained() {
+	let comments = read_vorbis_comments(OGG_AUDIO_DATA_UNUSUAL_SAMPLE_RATE);
+	assert_eq!(
+		comments,
+		[("Comment".to_string(), "Processed by SoX".to_string())]
+	);
+	assert!(read_vorbis_comments(FLAC_AUDIO_DATA).is_empty());
+
+	assert!(
+		is_retained_vorbis_comment(&comments[0], &["COMMENT".to_string()]),
+		"Comment field names should be compared without regard to case"
+	);
+	assert!(!is_retained_vorbis_comment(
+		&comments[0],
+		&["ARTIST".to_string(), "LICENSE".to_string()]
+	));
+}
+
+#[test]
+fn streaming_transcode_matches_buffered_transcode() {
+	let settings = AudioFileOptions::default();
+	assert!(can_transcode_while_reading(false, &settings));
+	assert!(!can_transcode_while_reading(true, &settings));
+
+	let streaming_transcode =
+		StreamingTranscode::start(settings.clone()).expect("The transcoder thread should start");
+	for chunk in FLAC_AUDIO_DATA.chunks(1000) {
+		streaming_transcode.send_chunk(Bytes::from_static(chunk));
+	}
+	let (streamed_file, _) = streaming_transcode
+		.finish()
+		.expect("No error should happen while transcoding");
+
+	let (buffered_file, _) =
+		process_and_transcode(Bytes::from_static(FLAC_AUDIO_DATA), false, &settings)
+			.expect("No error should happen while transcoding");
+
+	assert!(
+		streamed_file == buffered_file,
+		"Transcoding while reading should not change the transcoded file"
+	);
+}
+
+#[test]
+fn sound_category_profiles_work() {
+	assert_eq!(
+		SoundCategory::from_relative_path("assets/minecraft/sounds/music/game/calm1.ogg"),
+		Some(SoundCategory::Music)
+	);
+	assert_eq!(
+		SoundCategory::from_relative_path("assets/example/sounds/ui/click.wav"),
+		Some(SoundCategory::Ui)
+	);
+	assert_eq!(
+		SoundCategory::from_relative_path("assets/example/sounds/ambient.ogg"),
+		None
+	);
+	assert_eq!(
+		SoundCategory::from_relative_path("assets/example/sounds/mob/ambient/growl.ogg"),
+		None
+	);
+
+	let ui_settings = AudioFileOptions {
+		sound_category: Some(SoundCategory::Ui),
+		..Default::default()
+	};
+	assert!(
+		target_channels(&ui_settings).is_some_and(|channels| NonZeroU8::from(channels).get() == 1)
+	);
+	assert!(
+		target_channels(&AudioFileOptions {
+			channels: Some(ChannelMixingOption::ToChannels(
+				NonZeroU8::new(2).unwrap().try_into().unwrap()
+			)),
+			..ui_settings.clone()
+		})
+		.is_some_and(|channels| NonZeroU8::from(channels).get() == 2),
+		"Explicitly set options should take precedence over category defaults"
+	);
+	assert!(
+		target_channels(&AudioFileOptions {
+			channels: Some(ChannelMixingOption::Skip),
+			..ui_settings
+		})
+		.is_none(),
+		"Explicitly kept channels should take precedence over category defaults"
+	);
+
+	let music_settings = AudioFileOptions {
+		sound_category: Some(SoundCategory::Music),
+		..Default::default()
+	};
+	let input_sampling_frequency = NonZeroU32::new(48_000).unwrap();
+	assert_eq!(
+		output_sampling_frequency(&music_settings, input_sampling_frequency, false, None),
+		MUSIC_SAMPLING_FREQUENCY
+	);
+	assert_eq!(
+		target_bitrate_control_metric_to_quality(&music_settings, false),
+		MUSIC_TARGET_QUALITY / 10.0
+	);
+	assert_eq!(
+		output_sampling_frequency(
+			&AudioFileOptions {
+				sampling_frequency: NonZeroU32::new(22_050),
+				..music_settings
+			},
+			input_sampling_frequency,
+			false,
+			None
+		),
+		NonZeroU32::new(22_050).unwrap()
+	);
+}
+
+#[test]
+fn stereo_positional_sounds_are_downmixed() {
+	let stereo_settings = AudioFileOptions {
+		channels: Some(ChannelMixingOption::ToChannels(
+			NonZeroU8::new(2).unwrap().try_into().unwrap()
+		)),
+		..Default::default()
+	};
+	assert_eq!(
+		output_channel_count(Bytes::from_static(FLAC_AUDIO_DATA), false, &stereo_settings),
+		NonZeroU8::new(2)
+	);
+	assert_eq!(
+		output_channel_count(
+			Bytes::from_static(FLAC_AUDIO_DATA),
+			false,
+			&AudioFileOptions::default()
+		),
+		NonZeroU8::new(1),
+		"The channels of the input file should be kept by default"
+	);
+
+	let downmixing_settings = AudioFileOptions {
+		stereo_positional_sound_handling: StereoPositionalSoundHandling::Downmix,
+		..Default::default()
+	};
+	assert!(
+		target_channels(&downmixing_settings)
+			.is_some_and(|channels| NonZeroU8::from(channels).get() == 1)
+	);
+	assert!(
+		target_channels(&AudioFileOptions {
+			stereo_positional_sound_handling: StereoPositionalSoundHandling::Downmix,
+			..stereo_settings
+		})
+		.is_some_and(|channels| NonZeroU8::from(channels).get() == 2),
+		"Explicitly set channels should take precedence over downmixing"
+	);
+	assert!(
+		target_channels(&AudioFileOptions {
+			channels: Some(ChannelMixingOption::Skip),
+			..downmixing_settings
+		})
+		.is_none(),
+		"Explicitly kept channels should take precedence over downmixing"
+	);
+}
+
+#[test]
+fn level_analyzer_works() {
+	// A square wave that is clipped at full scale in one channel, and is offset in the other
+	let mut level_analyzer = LevelAnalyzer::new(2);
+	level_analyzer.add_sample_block(&[
+		[1.0, 1.0, 1.0, 1.0, -0.5, -1.0, -1.0].to_vec(),
+		[0.6, 0.6, 0.6, 0.6, 0.4, 0.4, 0.4].to_vec()
+	]);
+	assert_eq!(level_analyzer.clipped_sample_count(), 4);
+	assert!((level_analyzer.dc_offset() - 0.514).abs() < 0.001);
+
+	let diagnostics = analyze_audio(Bytes::from_static(FLAC_AUDIO_DATA), false, None, true)
+		.expect("The audio file should be decodable")
+		.diagnostics
+		.expect("The diagnostics should be computed");
+	assert_eq!(diagnostics.clipped_sample_count, 0);
+	assert!(diagnostics.true_peak < 0.0);
+	assert!(analyze_audio(Bytes::from_static(b"Not audio"), false, None, true).is_none());
+}
+
+#[test]
+fn audio_analysis_generates_previews_and_diagnostics() {
+	let analysis = analyze_audio(
+		Bytes::from_static(FLAC_AUDIO_DATA),
+		false,
+		Some(Duration::from_millis(100)),
+		true
+	)
+	.expect("The audio file should be decodable");
+
+	let preview = analysis.preview.expect("The preview should be generated");
+	assert_eq!(&preview[0..4], b"OggS", "The preview should be an Ogg file");
+	assert_eq!(preview[39], 1, "The preview should be mono");
+	assert_eq!(
+		preview[40..44],
+		22_050_u32.to_le_bytes(),
+		"The preview sampling frequency should be reduced"
+	);
+	assert!(
+		preview.len()
+			< audio_preview(
+				Bytes::from_static(FLAC_AUDIO_DATA),
+				false,
+				Duration::from_secs(10)
+			)
+			.expect("The preview should be generated")
+			.len(),
+		"The preview should only contain the requested duration"
+	);
+	assert!(
+		analysis.diagnostics.is_some(),
+		"The diagnostics should be computed"
+	);
+
+	let analysis = analyze_audio(Bytes::from_static(FLAC_AUDIO_DATA), false, None, false)
+		.expect("The audio file should be decodable");
+	assert!(analysis.preview.is_none() && analysis.diagnostics.is_none());
+}
+
+#[test]
+fn redundant_transcoding_is_detected() {
+	// The crafted empty file has an empty vendor string, like files encoded by PackSquash
+	assert!(is_transcoding_redundant(
+		EMPTY_OGG_AUDIO_DATA,
+		&AudioFileOptions::default()
+	));
+	assert!(!is_transcoding_redundant(
+		EMPTY_OGG_AUDIO_DATA,
+		&AudioFileOptions {
+			target_pitch: 2.0,
+			..Default::default()
+		}
+	));
+
+	assert!(
+		!is_transcoding_redundant(OGG_AUDIO_DATA, &AudioFileOptions::default()),
+		"Files encoded by other encoders should be transcoded in CQF mode"
+	);
+	assert!(!is_transcoding_redundant(
+		FLAC_AUDIO_DATA,
+		&AudioFileOptions::default()
+	));
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn valid_empty_input_works() {
 	successful_process_test(
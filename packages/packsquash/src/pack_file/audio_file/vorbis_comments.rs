@@ -0,0 +1,129 @@
+//! Implements the reading of the Vorbis comments of audio files, which hold metadata such as
+//! the artist, title or license of the audio as `FIELD=value` pairs.
+
+/// Reads the Vorbis comments of the specified Ogg Vorbis or FLAC file, returning their field
+/// names and values in order. Comments of other audio formats are not read, and an empty list
+/// is returned for them, or if the comments are malformed.
+///
+/// References:
+/// - <https://xiph.org/vorbis/doc/v-comment.html>
+/// - <https://xiph.org/vorbis/doc/Vorbis_I_spec.html#x1-620004.2.3>
+/// - <https://www.rfc-editor.org/rfc/rfc9639.html#name-vorbis-comment>
+pub fn read_vorbis_comments(audio_file: &[u8]) -> Vec<(String, String)> {
+	let comments = if audio_file.starts_with(b"OggS") {
+		ogg_packet(audio_file, 1)
+			.and_then(|packet| parse_vorbis_comments(packet.strip_prefix(b"\x03vorbis".as_slice())?))
+	} else if let Some(metadata_blocks) = audio_file.strip_prefix(b"fLaC".as_slice()) {
+		flac_vorbis_comment_block(metadata_blocks).and_then(parse_vorbis_comments)
+	} else {
+		None
+	};
+
+	comments.unwrap_or_default()
+}
+
+/// Returns whether the field name of the specified Vorbis comment is in the specified list,
+/// ignoring ASCII case, as Vorbis comment field names are case-insensitive.
+pub fn is_retained_vorbis_comment(
+	(field_name, _): &(String, String),
+	retained_field_names: &[String]
+) -> bool {
+	retained_field_names
+		.iter()
+		.any(|retained_field_name| retained_field_name.eq_ignore_ascii_case(field_name))
+}
+
+/// Parses the vendor string and comment list of a Vorbis comment header, returning the
+/// comments.
+fn parse_vorbis_comments(comment_header: &[u8]) -> Option<Vec<(String, String)>> {
+	let mut offset = 0;
+	let read_u32 = |offset: &mut usize| {
+		let value = u32::from_le_bytes(comment_header.get(*offset..*offset + 4)?.try_into().ok()?);
+		*offset += 4;
+		Some(value as usize)
+	};
+
+	let vendor_length = read_u32(&mut offset)?;
+	offset += vendor_length;
+
+	let comment_count = read_u32(&mut offset)?;
+	let mut comments = Vec::with_capacity(comment_count.min(comment_header.len() / 4));
+	for _ in 0..comment_count {
+		let comment_length = read_u32(&mut offset)?;
+		let comment = comment_header.get(offset..offset + comment_length)?;
+		offset += comment_length;
+
+		let comment = String::from_utf8_lossy(comment);
+		if let Some((field_name, value)) = comment.split_once('=') {
+			comments.push((field_name.to_string(), value.to_string()));
+		}
+	}
+
+	Some(comments)
+}
+
+/// Reassembles the packet with the specified index of the first logical bitstream of the
+/// specified Ogg file.
+///
+/// References:
+/// - <https://www.rfc-editor.org/rfc/rfc3533.html#section-6>
+pub fn ogg_packet(ogg_file: &[u8], packet_index: usize) -> Option<Vec<u8>> {
+	let mut stream_serial = None;
+	let mut current_packet_index = 0;
+	let mut packet = Vec::new();
+
+	let mut page_offset = 0;
+	while ogg_file.get(page_offset..page_offset + 4)? == b"OggS" {
+		let page_serial = ogg_file.get(page_offset + 14..page_offset + 18)?;
+		let segment_count = *ogg_file.get(page_offset + 26)? as usize;
+		let segment_table = ogg_file.get(page_offset + 27..page_offset + 27 + segment_count)?;
+
+		let mut segment_offset = page_offset + 27 + segment_count;
+		let is_first_stream_page = *stream_serial.get_or_insert(page_serial) == page_serial;
+		for &segment_length in segment_table {
+			let segment = ogg_file.get(segment_offset..segment_offset + segment_length as usize)?;
+			segment_offset += segment_length as usize;
+
+			if !is_first_stream_page {
+				continue;
+			}
+
+			if current_packet_index == packet_index {
+				packet.extend_from_slice(segment);
+			}
+
+			// Segments shorter than 255 bytes end a packet
+			if segment_length < 255 {
+				if current_packet_index == packet_index {
+					return Some(packet);
+				}
+				current_packet_index += 1;
+			}
+		}
+
+		page_offset = segment_offset;
+	}
+
+	None
+}
+
+/// Returns the contents of the `VORBIS_COMMENT` block of the specified FLAC metadata blocks.
+fn flac_vorbis_comment_block(mut metadata_blocks: &[u8]) -> Option<&[u8]> {
+	const VORBIS_COMMENT_BLOCK_TYPE: u8 = 4;
+
+	loop {
+		let block_header = metadata_blocks.get(..4)?;
+		let is_last_block = block_header[0] & 0x80 != 0;
+		let block_length = u32::from_be_bytes([0, block_header[1], block_header[2], block_header[3]]);
+		let block = metadata_blocks.get(4..4 + block_length as usize)?;
+
+		if block_header[0] & 0x7F == VORBIS_COMMENT_BLOCK_TYPE {
+			return Some(block);
+		}
+
+		if is_last_block {
+			return None;
+		}
+		metadata_blocks = &metadata_blocks[4 + block_length as usize..];
+	}
+}
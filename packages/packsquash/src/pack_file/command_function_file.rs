@@ -1,10 +1,12 @@
 use futures::{StreamExt, future};
+use serde_json::Value;
 use thiserror::Error;
 use tokio::io::AsyncRead;
 use tokio_stream::Stream;
 use tokio_util::codec::{FramedRead, LinesCodec, LinesCodecError};
 
-use crate::config::CommandFunctionFileOptions;
+use crate::config::{CommandFunctionFileOptions, MinecraftVersion, MinecraftVersionRange};
+use crate::json_schema_validation::validate_text_component;
 use crate::pack_file::AsyncReadAndSizeHint;
 use crate::pack_file::asset_type::PackFileAssetType;
 use crate::pack_file::util::{BOM, LineNumber, MarkLastDecorator, prepare_line_for_output};
@@ -13,6 +15,119 @@ use super::{OptimizedBytesChunk, PackFile, PackFileConstructor};
 
 #[cfg(test)]
 mod tests;
+mod text_component;
+
+/// The names of the commands of every Minecraft version that supports data packs, with the
+/// first and, if they were removed, last Minecraft version that has them. Commands that are
+/// only available in development builds are not included.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Commands>
+const COMMANDS: &[(&str, MinecraftVersion, Option<MinecraftVersion>)] = {
+	const V1_13: MinecraftVersion = MinecraftVersion::new(1, 13, 0);
+
+	&[
+		("advancement", V1_13, None),
+		("attribute", MinecraftVersion::new(1, 16, 0), None),
+		("ban", V1_13, None),
+		("ban-ip", V1_13, None),
+		("banlist", V1_13, None),
+		("bossbar", V1_13, None),
+		("clear", V1_13, None),
+		("clone", V1_13, None),
+		("damage", MinecraftVersion::new(1, 19, 4), None),
+		("data", V1_13, None),
+		("datapack", V1_13, None),
+		("debug", V1_13, None),
+		("defaultgamemode", V1_13, None),
+		("deop", V1_13, None),
+		("dialog", MinecraftVersion::new(1, 21, 6), None),
+		("difficulty", V1_13, None),
+		("effect", V1_13, None),
+		("enchant", V1_13, None),
+		("execute", V1_13, None),
+		("experience", V1_13, None),
+		("fill", V1_13, None),
+		("fillbiome", MinecraftVersion::new(1, 19, 3), None),
+		("forceload", MinecraftVersion::new(1, 14, 0), None),
+		("function", V1_13, None),
+		("gamemode", V1_13, None),
+		("gamerule", V1_13, None),
+		("give", V1_13, None),
+		("help", V1_13, None),
+		("item", MinecraftVersion::new(1, 17, 0), None),
+		("jfr", MinecraftVersion::new(1, 18, 0), None),
+		("kick", V1_13, None),
+		("kill", V1_13, None),
+		("list", V1_13, None),
+		("locate", V1_13, None),
+		(
+			"locatebiome",
+			MinecraftVersion::new(1, 16, 0),
+			Some(MinecraftVersion::new(1, 18, 2))
+		),
+		("loot", MinecraftVersion::new(1, 14, 0), None),
+		("me", V1_13, None),
+		("msg", V1_13, None),
+		("op", V1_13, None),
+		("pardon", V1_13, None),
+		("pardon-ip", V1_13, None),
+		("particle", V1_13, None),
+		("perf", MinecraftVersion::new(1, 17, 0), None),
+		("place", MinecraftVersion::new(1, 19, 0), None),
+		("playsound", V1_13, None),
+		("publish", V1_13, None),
+		("random", MinecraftVersion::new(1, 20, 2), None),
+		("recipe", V1_13, None),
+		("reload", V1_13, None),
+		("replaceitem", V1_13, Some(MinecraftVersion::new(1, 16, 5))),
+		("return", MinecraftVersion::new(1, 20, 0), None),
+		("ride", MinecraftVersion::new(1, 19, 4), None),
+		("rotate", MinecraftVersion::new(1, 21, 2), None),
+		("save-all", V1_13, None),
+		("save-off", V1_13, None),
+		("save-on", V1_13, None),
+		("say", V1_13, None),
+		("schedule", MinecraftVersion::new(1, 14, 0), None),
+		("scoreboard", V1_13, None),
+		("seed", V1_13, None),
+		("setblock", V1_13, None),
+		("setidletimeout", V1_13, None),
+		("setworldspawn", V1_13, None),
+		("spawnpoint", V1_13, None),
+		("spectate", MinecraftVersion::new(1, 15, 0), None),
+		("spreadplayers", V1_13, None),
+		("stop", V1_13, None),
+		("stopsound", V1_13, None),
+		("summon", V1_13, None),
+		("tag", V1_13, None),
+		("team", V1_13, None),
+		("teammsg", MinecraftVersion::new(1, 14, 0), None),
+		("teleport", V1_13, None),
+		("tell", V1_13, None),
+		("tellraw", V1_13, None),
+		("test", MinecraftVersion::new(1, 21, 5), None),
+		("tick", MinecraftVersion::new(1, 20, 3), None),
+		("time", V1_13, None),
+		("title", V1_13, None),
+		("tm", MinecraftVersion::new(1, 14, 0), None),
+		("tp", V1_13, None),
+		("transfer", MinecraftVersion::new(1, 20, 5), None),
+		("trigger", V1_13, None),
+		("version", MinecraftVersion::new(1, 21, 6), None),
+		("w", V1_13, None),
+		(
+			"warden_spawn_tracker",
+			MinecraftVersion::new(1, 19, 0),
+			None
+		),
+		("waypoint", MinecraftVersion::new(1, 21, 6), None),
+		("weather", V1_13, None),
+		("whitelist", V1_13, None),
+		("worldborder", V1_13, None),
+		("xp", V1_13, None)
+	]
+};
 
 /// Represents a line-oriented text file that contains commands for a vanilla Minecraft command
 /// function. After being trimmed, each line is parsed as either a command, a comment or an empty
@@ -43,7 +158,13 @@ pub enum OptimizationError {
 	#[error("Format error: Gratuitous leading slash in command at line {0}. Please remove it")]
 	GratuitousLeadingSlash(LineNumber),
 	#[error("Format error: Comment delimited by a double slash at line {0}. Please use # instead")]
-	DoubleSlashComment(LineNumber)
+	DoubleSlashComment(LineNumber),
+	#[error(
+		"Format error: Unknown command {0:?} at line {1}, which is not available in every targeted Minecraft version"
+	)]
+	UnknownCommand(String, LineNumber),
+	#[error("Format error: Invalid text component at line {1}: {0}")]
+	InvalidTextComponent(String, LineNumber)
 }
 
 impl<T: AsyncRead + Send + Unpin + 'static> PackFile for CommandFunctionFile<T> {
@@ -54,8 +175,9 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFile for CommandFunctionFile<T>
 
 	fn process(self) -> Self::OptimizedByteChunksStream {
 		let mut line_number = LineNumber::new();
+		let mut previous_line_continues = false;
 
-		let minify = self.optimization_settings.minify;
+		let optimization_settings = self.optimization_settings;
 
 		MarkLastDecorator::new(FramedRead::new(
 			self.read,
@@ -66,7 +188,20 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFile for CommandFunctionFile<T>
 		.filter_map(move |(line_result, is_last)| {
 			let processed_line_result = line_result.map_or_else(
 				|err| Some(Err(err.into())),
-				|line| process_line(line, is_last, line_number, minify)
+				|line| {
+					// Since Minecraft 1.20.2, commands may continue in the next line if they end
+					// with a backslash
+					let continues_previous_line = previous_line_continues;
+					previous_line_continues = line.trim_end().ends_with('\\');
+
+					process_line(
+						line,
+						is_last,
+						line_number,
+						continues_previous_line,
+						optimization_settings
+					)
+				}
 			);
 
 			line_number.increment();
@@ -102,7 +237,8 @@ fn process_line(
 	line: impl Into<String>,
 	is_last: bool,
 	line_number: LineNumber,
-	minify: bool
+	continues_previous_line: bool,
+	optimization_settings: CommandFunctionFileOptions
 ) -> Option<OptimizedBytesChunk<Vec<u8>, OptimizationError>> {
 	const MINIFIED: &str = "Minified";
 	const NOT_MINIFIED: &str = "Copied";
@@ -119,6 +255,7 @@ fn process_line(
 	}
 
 	let trimmed_line = line.trim();
+	let minify = optimization_settings.minify;
 
 	// Check whether the line is empty or a comment. If so, bail out by copying or
 	// skipping it, depending on whether we're minifying
@@ -135,10 +272,90 @@ fn process_line(
 			return Some(Err(OptimizationError::GratuitousLeadingSlash(line_number)));
 		}
 
+		// Macro lines, which start with a dollar sign, may build command names from their
+		// arguments, so they can't be validated
+		if optimization_settings.validate_command_names
+			&& !continues_previous_line
+			&& !trimmed_line.starts_with('$')
+		{
+			let command_name = trimmed_line
+				.split_ascii_whitespace()
+				.next()
+				.unwrap_or_default();
+
+			if !is_known_command(
+				command_name,
+				optimization_settings.target_minecraft_version_range
+			) {
+				return Some(Err(OptimizationError::UnknownCommand(
+					command_name.into(),
+					line_number
+				)));
+			}
+		}
+
+		// Text components are only looked for in commands that fit in a single line and whose
+		// arguments are not substituted by macros
+		let text_component_range = (!continues_previous_line
+			&& !trimmed_line.starts_with('$')
+			&& !trimmed_line.ends_with('\\'))
+		.then(|| text_component::text_component_range(trimmed_line))
+		.flatten();
+
+		// Text components written in JSON are parsed as such first, because JSON is not a
+		// subset of SNBT. Other text components are parsed as SNBT
+		let json_text_component = text_component_range
+			.as_ref()
+			.and_then(|range| serde_json::from_str::<Value>(&trimmed_line[range.clone()]).ok());
+
+		if optimization_settings.validate_text_components
+			&& let Some(range) = &text_component_range
+		{
+			let Some(text_component) = json_text_component
+				.clone()
+				.or_else(|| text_component::parse_snbt(&trimmed_line[range.clone()]))
+			else {
+				return Some(Err(OptimizationError::InvalidTextComponent(
+					"Malformed JSON or SNBT".into(),
+					line_number
+				)));
+			};
+
+			let violations = validate_text_component(&text_component);
+			if !violations.is_empty() {
+				return Some(Err(OptimizationError::InvalidTextComponent(
+					violations.join("; "),
+					line_number
+				)));
+			}
+		}
+
 		if minify {
-			Some(prepare_line_for_output(trimmed_line, is_last, MINIFIED))
+			match (text_component_range, json_text_component) {
+				(Some(range), Some(text_component)) => Some(prepare_line_for_output(
+					format!("{}{text_component}", &trimmed_line[..range.start]),
+					is_last,
+					MINIFIED
+				)),
+				_ => Some(prepare_line_for_output(trimmed_line, is_last, MINIFIED))
+			}
 		} else {
 			Some(prepare_line_for_output(line, is_last, NOT_MINIFIED))
 		}
 	}
 }
+
+/// Checks whether a command with the specified name is available in every Minecraft version of
+/// the specified range, or in some Minecraft version if no range is specified.
+fn is_known_command(
+	command_name: &str,
+	target_minecraft_version_range: Option<MinecraftVersionRange>
+) -> bool {
+	COMMANDS.iter().any(|(name, first_version, last_version)| {
+		*name == command_name
+			&& target_minecraft_version_range.is_none_or(|version_range| {
+				*first_version <= version_range.minimum()
+					&& last_version.is_none_or(|last_version| last_version >= version_range.maximum())
+			})
+	})
+}
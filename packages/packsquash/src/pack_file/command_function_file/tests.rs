@@ -166,3 +166,108 @@ async fn comment_with_double_slash_delimiter_is_handled() {
 	)
 	.await
 }
+
+#[tokio::test]
+async fn command_names_are_validated() {
+	let validation_settings = |target_minecraft_version_range| CommandFunctionFileOptions {
+		validate_command_names: true,
+		target_minecraft_version_range,
+		..Default::default()
+	};
+	let version_range = |minimum: &str, maximum: &str| {
+		Some(
+			MinecraftVersionRange::try_from([
+				MinecraftVersion::try_from(minimum.to_string()).unwrap(),
+				MinecraftVersion::try_from(maximum.to_string()).unwrap()
+			])
+			.unwrap()
+		)
+	};
+
+	successful_process_test(
+		FUNCTION_DATA,
+		false,
+		validation_settings(None),
+		MINIFIED_FUNCTION_DATA
+	)
+	.await;
+
+	successful_process_test(
+		"# Macro lines and continued lines are not validated\n\
+		$$(command) @s\n\
+		tick \\\n\
+		  freeze\n\
+		item replace entity @s weapon with stick",
+		false,
+		validation_settings(version_range("1.20.3", "1.21.4")),
+		"$$(command) @s\ntick \\\nfreeze\nitem replace entity @s weapon with stick"
+	)
+	.await;
+
+	unsuccessful_process_test(
+		"say Hi\ntelport @s ~ ~1 ~",
+		false,
+		validation_settings(None),
+		|err| matches!(err, OptimizationError::UnknownCommand(command, _) if command == "telport"),
+		"Expected an unknown command error for a misspelled command"
+	)
+	.await;
+
+	unsuccessful_process_test(
+		"item replace entity @s weapon with stick",
+		false,
+		validation_settings(version_range("1.16", "1.21")),
+		|err| matches!(err, OptimizationError::UnknownCommand(command, _) if command == "item"),
+		"Expected an unknown command error for a command some targeted versions lack"
+	)
+	.await;
+}
+
+#[tokio::test]
+async fn text_components_are_minified_and_validated() {
+	let validation_settings = CommandFunctionFileOptions {
+		validate_text_components: true,
+		..Default::default()
+	};
+
+	successful_process_test(
+		"tellraw @a[tag=a, limit=1] { \"text\": \"Hi there\", \"color\": \"gold\" }\n\
+		execute as @a run title @s actionbar [ \"\", { \"translate\": \"a\" } ]\n\
+		title @a subtitle {text:'SNBT is left as-is',bold:1b}\n\
+		$tellraw @a { \"text\": \"$(text)\" }",
+		false,
+		validation_settings,
+		"tellraw @a[tag=a, limit=1] {\"text\":\"Hi there\",\"color\":\"gold\"}\n\
+		execute as @a run title @s actionbar [\"\",{\"translate\":\"a\"}]\n\
+		title @a subtitle {text:'SNBT is left as-is',bold:1b}\n\
+		$tellraw @a { \"text\": \"$(text)\" }"
+	)
+	.await;
+
+	unsuccessful_process_test(
+		"tellraw @a {\"text\":\"Click\",\"clickEvent\":{\"action\":\"run_command\",\"comand\":\"/say\"}}",
+		false,
+		validation_settings,
+		|err| matches!(err, OptimizationError::InvalidTextComponent(violations, _) if violations.contains("comand")),
+		"Expected an invalid text component error for a misspelled click event key"
+	)
+	.await;
+
+	unsuccessful_process_test(
+		"title @a title {text:'Hi',hover_event:{action:show_text,value:{text:'a'}},bold:2}",
+		false,
+		validation_settings,
+		|err| matches!(err, OptimizationError::InvalidTextComponent(_, _)),
+		"Expected an invalid text component error for a SNBT component with a non-boolean byte"
+	)
+	.await;
+
+	unsuccessful_process_test(
+		"tellraw @a {\"text\":\"Unclosed\"",
+		false,
+		validation_settings,
+		|err| matches!(err, OptimizationError::InvalidTextComponent(_, _)),
+		"Expected an invalid text component error for a malformed component"
+	)
+	.await;
+}
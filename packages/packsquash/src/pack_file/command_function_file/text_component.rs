@@ -0,0 +1,316 @@
+//! Contains routines to find and parse the text components that commands take as arguments.
+
+use std::ops::Range;
+
+use serde_json::{Map, Number, Value};
+
+/// Returns the byte range of the text component argument of the specified command, if it is a
+/// command whose last argument is a text component: `tellraw`, or `title` with the `title`,
+/// `subtitle` or `actionbar` actions. Commands run by `execute` are considered too.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Commands/tellraw>
+/// - <https://minecraft.wiki/w/Commands/title>
+pub fn text_component_range(command: &str) -> Option<Range<usize>> {
+	let mut arguments = Arguments {
+		command,
+		position: 0
+	};
+
+	loop {
+		match arguments.next()? {
+			"execute" => while arguments.next()? != "run" {},
+			"tellraw" => {
+				// Skip the targets
+				arguments.next()?;
+				break;
+			}
+			"title" => {
+				arguments.next()?;
+				if !matches!(arguments.next()?, "title" | "subtitle" | "actionbar") {
+					return None;
+				}
+				break;
+			}
+			_ => return None
+		}
+	}
+
+	let remaining_arguments = &command[arguments.position..];
+	let start =
+		arguments.position + remaining_arguments.len() - remaining_arguments.trim_start().len();
+	let end = command.trim_end().len();
+
+	(start < end).then_some(start..end)
+}
+
+/// An iterator over the space-separated arguments of a command. Spaces within brackets, braces
+/// and quoted strings, such as those of target selectors and NBT data, do not separate
+/// arguments.
+struct Arguments<'command> {
+	command: &'command str,
+	position: usize
+}
+
+impl<'command> Iterator for Arguments<'command> {
+	type Item = &'command str;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let remaining_command = &self.command[self.position..];
+		let start = self.position + remaining_command.len() - remaining_command.trim_start().len();
+
+		let mut depth = 0_usize;
+		let mut quote = None;
+		let mut escaped = false;
+		let mut end = self.command.len();
+		for (index, character) in self.command[start..].char_indices() {
+			match (quote, character) {
+				(Some(_), _) if escaped => escaped = false,
+				(Some(_), '\\') => escaped = true,
+				(Some(quote_character), _) if character == quote_character => quote = None,
+				(Some(_), _) => {}
+				(None, '"' | '\'') => quote = Some(character),
+				(None, '[' | '{') => depth += 1,
+				(None, ']' | '}') => depth = depth.saturating_sub(1),
+				(None, _) if depth == 0 && character.is_whitespace() => {
+					end = start + index;
+					break;
+				}
+				(None, _) => {}
+			}
+		}
+
+		self.position = end;
+		(start < end).then(|| &self.command[start..end])
+	}
+}
+
+/// Parses a text component written in SNBT, the format that commands take text components in
+/// since Minecraft 1.21.5, into an equivalent JSON value, so that it can be validated against
+/// the same schema as JSON text components. `None` is returned if the SNBT is malformed.
+///
+/// Numeric type suffixes are discarded, and bytes with a value of 0 or 1 are converted to
+/// booleans, as SNBT represents booleans with them. Typed arrays are converted to lists.
+///
+/// References:
+/// - <https://minecraft.wiki/w/NBT_format#SNBT_format>
+pub fn parse_snbt(snbt: &str) -> Option<Value> {
+	let mut parser = SnbtParser {
+		snbt: snbt.as_bytes(),
+		position: 0
+	};
+
+	let value = parser.value()?;
+	parser.skip_whitespace();
+
+	(parser.position == parser.snbt.len()).then_some(value)
+}
+
+/// A recursive descent parser of SNBT values.
+struct SnbtParser<'snbt> {
+	snbt: &'snbt [u8],
+	position: usize
+}
+
+impl SnbtParser<'_> {
+	/// Parses the value that follows the current position.
+	fn value(&mut self) -> Option<Value> {
+		self.skip_whitespace();
+
+		match self.peek()? {
+			b'{' => self.compound(),
+			b'[' => self.list(),
+			b'"' | b'\'' => self.quoted_string().map(Value::String),
+			_ => Some(unquoted_value(self.unquoted_string()?))
+		}
+	}
+
+	/// Parses the compound that starts at the current position.
+	fn compound(&mut self) -> Option<Value> {
+		self.position += 1;
+
+		let mut compound = Map::new();
+		loop {
+			self.skip_whitespace();
+			if self.peek()? == b'}' {
+				self.position += 1;
+				return Some(Value::Object(compound));
+			}
+
+			let key = match self.peek()? {
+				b'"' | b'\'' => self.quoted_string()?,
+				_ => self.unquoted_string()?.to_owned()
+			};
+
+			self.skip_whitespace();
+			self.expect(b':')?;
+			compound.insert(key, self.value()?);
+
+			if !self.separator(b'}')? {
+				self.position += 1;
+				return Some(Value::Object(compound));
+			}
+		}
+	}
+
+	/// Parses the list or typed array that starts at the current position.
+	fn list(&mut self) -> Option<Value> {
+		self.position += 1;
+
+		// Typed arrays start with their element type, followed by a semicolon
+		if matches!(
+			self.snbt.get(self.position..self.position + 2),
+			Some([b'B' | b'I' | b'L', b';'])
+		) {
+			self.position += 2;
+		}
+
+		let mut list = vec![];
+		loop {
+			self.skip_whitespace();
+			if self.peek()? == b']' {
+				self.position += 1;
+				return Some(Value::Array(list));
+			}
+
+			list.push(self.value()?);
+
+			if !self.separator(b']')? {
+				self.position += 1;
+				return Some(Value::Array(list));
+			}
+		}
+	}
+
+	/// Parses the quoted string that starts at the current position, decoding its escape
+	/// sequences.
+	fn quoted_string(&mut self) -> Option<String> {
+		let quote = self.peek()?;
+		self.position += 1;
+
+		let mut string = Vec::new();
+		loop {
+			let byte = *self.snbt.get(self.position)?;
+			self.position += 1;
+
+			match byte {
+				b'\\' => {
+					let escaped_byte = *self.snbt.get(self.position)?;
+					self.position += 1;
+
+					match escaped_byte {
+						b'n' => string.push(b'\n'),
+						b't' => string.push(b'\t'),
+						b'r' => string.push(b'\r'),
+						b'b' => string.push(0x08),
+						b'f' => string.push(0x0C),
+						b's' => string.push(b' '),
+						b'x' | b'u' | b'U' => {
+							let digit_count = match escaped_byte {
+								b'x' => 2,
+								b'u' => 4,
+								_ => 8
+							};
+							let digits = self.snbt.get(self.position..self.position + digit_count)?;
+							let codepoint =
+								u32::from_str_radix(str::from_utf8(digits).ok()?, 16).ok()?;
+							self.position += digit_count;
+
+							let mut buffer = [0; 4];
+							string.extend_from_slice(
+								char::from_u32(codepoint)?
+									.encode_utf8(&mut buffer)
+									.as_bytes()
+							);
+						}
+						_ => string.push(escaped_byte)
+					}
+				}
+				_ if byte == quote => return String::from_utf8(string).ok(),
+				_ => string.push(byte)
+			}
+		}
+	}
+
+	/// Parses the unquoted string that starts at the current position, which may be a number,
+	/// a boolean or a plain string.
+	fn unquoted_string(&mut self) -> Option<&str> {
+		let start = self.position;
+		while self.peek().is_some_and(|byte| {
+			byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'-' | b'.' | b'+')
+		}) {
+			self.position += 1;
+		}
+
+		(self.position > start)
+			.then(|| str::from_utf8(&self.snbt[start..self.position]).ok())
+			.flatten()
+	}
+
+	/// Skips the separator that follows a value of a compound or list, returning whether more
+	/// values follow. `false` is returned if the specified closing delimiter follows instead.
+	/// A trailing separator before the closing delimiter is accepted.
+	fn separator(&mut self, closing_delimiter: u8) -> Option<bool> {
+		self.skip_whitespace();
+
+		match self.peek()? {
+			b',' => {
+				self.position += 1;
+				Some(true)
+			}
+			byte if byte == closing_delimiter => Some(false),
+			_ => None
+		}
+	}
+
+	/// Skips the specified byte, returning `None` if another byte follows.
+	fn expect(&mut self, byte: u8) -> Option<()> {
+		(self.peek()? == byte).then(|| self.position += 1)
+	}
+
+	/// Skips the whitespace that follows the current position.
+	fn skip_whitespace(&mut self) {
+		while self.peek().is_some_and(|byte| byte.is_ascii_whitespace()) {
+			self.position += 1;
+		}
+	}
+
+	/// Returns the byte at the current position, if any.
+	fn peek(&self) -> Option<u8> {
+		self.snbt.get(self.position).copied()
+	}
+}
+
+/// Converts an unquoted SNBT string to the JSON value it represents.
+fn unquoted_value(unquoted_string: &str) -> Value {
+	match unquoted_string {
+		"true" => return Value::Bool(true),
+		"false" => return Value::Bool(false),
+		_ => {}
+	}
+
+	let (number, suffix) = match unquoted_string.as_bytes().last() {
+		Some(suffix @ (b'b' | b'B' | b's' | b'S' | b'l' | b'L' | b'f' | b'F' | b'd' | b'D')) => (
+			&unquoted_string[..unquoted_string.len() - 1],
+			Some(suffix.to_ascii_lowercase())
+		),
+		_ => (unquoted_string, None)
+	};
+
+	if let Ok(integer) = number.parse::<i64>() {
+		match (suffix, integer) {
+			(Some(b'b'), 0 | 1) => Value::Bool(integer == 1),
+			(Some(b'f' | b'd'), _) => {
+				Number::from_f64(integer as f64).map_or(Value::Null, Value::Number)
+			}
+			_ => Value::Number(integer.into())
+		}
+	} else if let Ok(float) = number.parse::<f64>()
+		&& matches!(suffix, None | Some(b'f' | b'd'))
+		&& let Some(float) = Number::from_f64(float)
+	{
+		Value::Number(float)
+	} else {
+		Value::String(unquoted_string.to_owned())
+	}
+}
@@ -75,12 +75,16 @@ impl Decoder for OptimizerDecoder {
 		// MultiGzEncoder is used to handle gzip streams with several members (i.e., files) by
 		// concatenating them into a single stream of bytes, like Java's `GZIPInputStream` does
 		let mut decompressed_nbt_size = 0;
-		let nbt_compound_tag: HashMap<String, fastnbt::Value, ahash::RandomState> =
+		let mut nbt_compound_tag: HashMap<String, fastnbt::Value, ahash::RandomState> =
 			fastnbt::from_reader(AccountingRead::new(
 				MultiGzDecoder::new(&**src),
 				&mut decompressed_nbt_size
 			))?;
 
+		if self.optimization_settings.strip_redundant_structure_tags {
+			strip_redundant_structure_tags(&mut nbt_compound_tag);
+		}
+
 		let zopfli_iteration_count = ZopfliIterationsTimeModel::new(
 			self.optimization_settings.nbt_compression_iterations,
 			2.0
@@ -187,6 +191,61 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T>
 	}
 }
 
+/// Removes the tags of a structure template root compound tag that Minecraft ignores or
+/// treats the same as missing: the `author` tag, which is only displayed by structure blocks
+/// in old versions, empty `entities` lists, and empty `Properties` compounds of block states
+/// in palettes. Compound tags that do not look like a structure template are left as-is.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Structure_file>
+/// - Minecraft's 24w04a `net.minecraft.world.level.levelgen.structure.templatesystem.StructureTemplate` class
+/// - Minecraft's 24w04a `net.minecraft.nbt.NbtUtils#readBlockState` method
+fn strip_redundant_structure_tags(
+	structure: &mut HashMap<String, fastnbt::Value, ahash::RandomState>
+) {
+	let is_structure = matches!(structure.get("size"), Some(fastnbt::Value::List(_)))
+		&& (structure.contains_key("palette") || structure.contains_key("palettes"));
+	if !is_structure {
+		return;
+	}
+
+	structure.remove("author");
+
+	if matches!(structure.get("entities"), Some(fastnbt::Value::List(entities)) if entities.is_empty())
+	{
+		structure.remove("entities");
+	}
+
+	// A structure has either a single palette or a list of palettes, one of which is chosen
+	// at random when placing it
+	let mut palettes = vec![];
+	for (key, value) in structure.iter_mut() {
+		match (key.as_str(), value) {
+			("palette", palette) => palettes.push(palette),
+			("palettes", fastnbt::Value::List(structure_palettes)) => {
+				palettes.extend(structure_palettes)
+			}
+			_ => ()
+		}
+	}
+
+	let palette_block_states = palettes
+		.into_iter()
+		.filter_map(|palette| match palette {
+			fastnbt::Value::List(block_states) => Some(block_states),
+			_ => None
+		})
+		.flatten();
+
+	for block_state in palette_block_states {
+		if let fastnbt::Value::Compound(block_state) = block_state
+			&& matches!(block_state.get("Properties"), Some(fastnbt::Value::Compound(properties)) if properties.is_empty())
+		{
+			block_state.remove("Properties");
+		}
+	}
+}
+
 /// Goes through the members of an assumed-valid [gzip] source and writes them to an output gzip
 /// sink, keeping the compressed data as-is but replacing gzip member headers with the smallest
 /// legal gzip header, dropping metadata fields.
@@ -14,15 +14,16 @@ static OPTIMIZED_REAL_STRUCTURE_FILE: &[u8] = include_bytes!("verdalia32.nbt");
 static OPTIMIZED_REAL_STRUCTURE_FILE_SPLITTED_IN_TWO_GZIP_MEMBERS: &[u8] =
 	include_bytes!("verdalia32_splitted.nbt");
 
-/// Processes the given input data as a [CompressedCompoundNbtTagFile], expecting a successful result
-/// that equals the input data while potentially being smaller.
-async fn successful_process_test(input: &[u8], expect_smaller: bool) {
-	let input_nbt = parse_compressed_nbt(input);
-
+/// Processes the given input data as a [CompressedCompoundNbtTagFile] with the given settings,
+/// returning the optimized data.
+async fn process(
+	input: &[u8],
+	optimization_settings: CompressedCompoundNbtTagFileOptions
+) -> Vec<u8> {
 	let optimized_data_stream = CompressedCompoundNbtTagFile {
 		read: Builder::new().read(input).build(),
 		file_length_hint: input.len(),
-		optimization_settings: CompressedCompoundNbtTagFileOptions::default()
+		optimization_settings
 	}
 	.process();
 
@@ -37,6 +38,23 @@ async fn successful_process_test(input: &[u8], expect_smaller: bool) {
 		"No valid output was produced"
 	);
 
+	optimization_output
+}
+
+/// Processes the given input data as a [CompressedCompoundNbtTagFile], expecting a successful result
+/// that equals the input data while potentially being smaller.
+async fn successful_process_test(input: &[u8], expect_smaller: bool) {
+	let input_nbt = parse_compressed_nbt(input);
+
+	let optimization_output = process(
+		input,
+		CompressedCompoundNbtTagFileOptions {
+			strip_redundant_structure_tags: false,
+			..Default::default()
+		}
+	)
+	.await;
+
 	println!("Original size: {} bytes", input.len());
 	println!("Optimized size: {} bytes", optimization_output.len());
 
@@ -78,3 +96,30 @@ async fn minifying_several_gzip_members_works() {
 	)
 	.await
 }
+
+#[tokio::test]
+async fn redundant_structure_tags_are_stripped() {
+	// Use a file that is known to be optimizable, so that the optimized data does not
+	// fall back to the original data with the redundant tags
+	let mut expected_nbt =
+		parse_compressed_nbt(OPTIMIZED_REAL_STRUCTURE_FILE_SPLITTED_IN_TWO_GZIP_MEMBERS);
+	let fastnbt::Value::Compound(expected_structure) = &mut expected_nbt else {
+		panic!("The root tag of a structure file should be a compound tag");
+	};
+	assert!(
+		expected_structure.remove("author").is_some(),
+		"The test structure file should have an author tag"
+	);
+	assert!(
+		expected_structure.remove("entities").is_some(),
+		"The test structure file should have an empty entities list"
+	);
+
+	let optimization_output = process(
+		OPTIMIZED_REAL_STRUCTURE_FILE_SPLITTED_IN_TWO_GZIP_MEMBERS,
+		CompressedCompoundNbtTagFileOptions::default()
+	)
+	.await;
+
+	assert_eq!(parse_compressed_nbt(&optimization_output), expected_nbt);
+}
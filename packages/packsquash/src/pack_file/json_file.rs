@@ -3,22 +3,40 @@
 use std::borrow::Cow;
 
 use bytes::{BufMut, BytesMut};
-use json_comments::StripComments;
 use serde_json::Value;
 use thiserror::Error;
 use tokio::io::AsyncRead;
 use tokio_util::codec::{Decoder, FramedRead};
 
-use crate::config::JsonFileOptions;
+use crate::config::{JsonFileOptions, JsonObjectKeySorting};
 use crate::pack_file::AsyncReadAndSizeHint;
 use crate::pack_file::asset_type::PackFileAssetType;
 
-use super::{PackFile, PackFileConstructor, util::strip_utf8_bom};
+use super::util::{BOM_UTF8, starts_with_bom};
+use super::{PackFile, PackFileConstructor};
 
+use self::blockstate_optimizer::optimize_blockstate;
+use self::canonicalizer::canonicalize;
+#[cfg(feature = "optifine")]
+use self::custom_entity_model_optimizer::optimize_custom_entity_model;
 use self::debloater::Debloater;
+use self::item_model_definition_optimizer::optimize_item_model_definition;
+use self::model_optimizer::optimize_model;
+use self::number_rounding::round_numbers;
+pub use self::relaxed_syntax::blank_out_relaxed_syntax;
+use self::tag_optimizer::optimize_tag;
 use self::unbounded_depth_json_value::UnboundedDepthJsonValue;
 
+mod blockstate_optimizer;
+mod canonicalizer;
+#[cfg(feature = "optifine")]
+mod custom_entity_model_optimizer;
 mod debloater;
+mod item_model_definition_optimizer;
+mod model_optimizer;
+mod number_rounding;
+mod relaxed_syntax;
+mod tag_optimizer;
 mod unbounded_depth_json_value;
 
 #[cfg(test)]
@@ -51,6 +69,8 @@ pub enum OptimizationError {
 	JsonSerde(#[from] serde_json::Error),
 	#[error("Unexpected JSON value: {0}")]
 	UnexpectedValue(&'static str),
+	#[error("Unterminated block comment at line {line} column {column}")]
+	UnterminatedComment { line: usize, column: usize },
 	#[error("I/O error: {0}")]
 	Io(#[from] std::io::Error)
 }
@@ -76,21 +96,26 @@ impl Decoder for OptimizerDecoder {
 		}
 		self.reached_eof = true;
 
-		// Parse the JSON, so we know how to serialize it again in a compact manner, and whether
-		// it's valid. Check whether we should parse and discard comments, too. We also disable
-		// recursion limits to support complex JSON structures, which may be found in some practical
-		// packs
-		let mut json_value = if self.optimization_settings.always_allow_comments
-			|| asset_type_has_comments_extension(self.asset_type)
-		{
-			UnboundedDepthJsonValue::deserialize(|| {
-				serde_json::de::IoRead::new(StripComments::new(strip_utf8_bom(src)))
-			})?
+		// Check whether we should accept comments and trailing commas, which are common in
+		// hand-edited files, and remove them if so. They are replaced with whitespace, so the
+		// locations in parsing errors still match the original file
+		let json_start_index = if starts_with_bom(&src) {
+			BOM_UTF8.len()
 		} else {
-			UnboundedDepthJsonValue::deserialize(|| {
-				serde_json::de::SliceRead::new(strip_utf8_bom(src))
-			})?
+			0
 		};
+		if self.optimization_settings.always_allow_comments
+			|| asset_type_has_comments_extension(self.asset_type)
+		{
+			blank_out_relaxed_syntax(&mut src[json_start_index..])?;
+		}
+
+		// Parse the JSON, so we know how to serialize it again in a compact manner, and whether
+		// it's valid. We also disable recursion limits to support complex JSON structures, which
+		// may be found in some practical packs
+		let mut json_value = UnboundedDepthJsonValue::deserialize(|| {
+			serde_json::de::SliceRead::new(&src[json_start_index..])
+		})?;
 
 		// All concrete asset types start with a JSON object (aka struct, map)
 		if self.asset_type != PackFileAssetType::GenericJson
@@ -107,7 +132,7 @@ impl Decoder for OptimizerDecoder {
 		src.clear();
 
 		// Debloat the read value
-		let debloated = if self.optimization_settings.delete_bloat {
+		let mut debloated = if self.optimization_settings.delete_bloat {
 			DEBLOATER.with(|debloater| {
 				json_value.with_safe_stack_mut(|value| debloater.debloat(value, self.asset_type))
 			})
@@ -115,12 +140,89 @@ impl Decoder for OptimizerDecoder {
 			false
 		};
 
+		// Round numbers before optimizing models, so that values that only differ from the
+		// defaults due to floating-point noise can be removed too
+		if let Some(decimal_places) = self.optimization_settings.maximum_decimal_places {
+			debloated |= json_value.with_safe_stack_mut(|value| round_numbers(value, decimal_places));
+		}
+
+		// Remove the default values and redundant elements of models, which is a form of
+		// debloating too. Like key sorting, this is not done for deeply nested models
+		if self.optimization_settings.optimize_model_structure
+			&& matches!(
+				self.asset_type,
+				PackFileAssetType::MinecraftModel | PackFileAssetType::MinecraftModelWithComments
+			) && !json_value.has_deeply_nested_value()
+		{
+			let shorten_texture_variables = self.optimization_settings.shorten_texture_variables;
+			debloated |= json_value
+				.with_safe_stack_mut(|value| optimize_model(value, shorten_texture_variables));
+		}
+
+		// Blockstate definitions are structurally optimized likewise
+		if self.optimization_settings.optimize_blockstate_structure
+			&& matches!(
+				self.asset_type,
+				PackFileAssetType::MinecraftBlockState
+					| PackFileAssetType::MinecraftBlockStateWithComments
+			) && !json_value.has_deeply_nested_value()
+		{
+			debloated |= json_value.with_safe_stack_mut(optimize_blockstate);
+		}
+
+		// And so are item model definitions
+		if self
+			.optimization_settings
+			.optimize_item_model_definition_structure
+			&& matches!(
+				self.asset_type,
+				PackFileAssetType::MinecraftItemModelDefinition
+					| PackFileAssetType::MinecraftItemModelDefinitionWithComments
+			) && !json_value.has_deeply_nested_value()
+		{
+			debloated |= json_value.with_safe_stack_mut(optimize_item_model_definition);
+		}
+
+		// And so are data pack tags
+		if self.optimization_settings.optimize_tag_structure
+			&& matches!(
+				self.asset_type,
+				PackFileAssetType::MinecraftTag | PackFileAssetType::MinecraftTagWithComments
+			) && !json_value.has_deeply_nested_value()
+		{
+			debloated |= json_value.with_safe_stack_mut(optimize_tag);
+		}
+
+		// And so are OptiFine custom entity models and their parts
+		#[cfg(feature = "optifine")]
+		if self
+			.optimization_settings
+			.optimize_custom_entity_model_structure
+			&& matches!(
+				self.asset_type,
+				PackFileAssetType::OptifineCustomEntityModel
+					| PackFileAssetType::OptifineCustomEntityModelWithComments
+					| PackFileAssetType::OptifineCustomEntityModelPart
+					| PackFileAssetType::OptifineCustomEntityModelPartWithComments
+			) && !json_value.has_deeply_nested_value()
+		{
+			debloated |= json_value.with_safe_stack_mut(optimize_custom_entity_model);
+		}
+
 		// Sort the keys of all JSON objects, if requested and the JSON is not so deep that it could
 		// cause too much memory to be allocated. On a small corpus of 4 resource packs, this provided
 		// ~0.005% space savings at negligible performance cost, in addition to unmeasurable improvements
 		// in human readability and ease of diffing
-		if self.optimization_settings.sort_object_keys && !json_value.has_deeply_nested_value() {
-			json_value.with_safe_stack_mut(|value| value.sort_all_objects());
+		if !json_value.has_deeply_nested_value() {
+			match self.optimization_settings.sort_object_keys {
+				JsonObjectKeySorting::Disabled => {}
+				JsonObjectKeySorting::Lexicographic => {
+					json_value.with_safe_stack_mut(|value| value.sort_all_objects());
+				}
+				JsonObjectKeySorting::Canonical => {
+					json_value.with_safe_stack_mut(canonicalize);
+				}
+			}
 		}
 
 		let mut json_writer = src.split_off(0).writer();
@@ -238,6 +340,9 @@ const fn asset_type_has_comments_extension(asset_type: PackFileAssetType) -> boo
 		PackFileAssetType::MinecraftTextureMetadataWithComments
 		| PackFileAssetType::MinecraftMetadataWithComments
 		| PackFileAssetType::MinecraftModelWithComments
+		| PackFileAssetType::MinecraftBlockStateWithComments
+		| PackFileAssetType::MinecraftItemModelDefinitionWithComments
+		| PackFileAssetType::MinecraftTagWithComments
 		| PackFileAssetType::GenericJsonWithComments => true,
 		#[cfg(feature = "optifine")]
 		PackFileAssetType::OptifineCustomEntityModelWithComments
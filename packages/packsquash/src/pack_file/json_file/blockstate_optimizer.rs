@@ -0,0 +1,244 @@
+//! Implements structural optimizations for Minecraft blockstate definitions, which shrink them
+//! further than minification without changing which models blocks are rendered with.
+
+use std::mem;
+
+use ahash::AHashSet;
+use itertools::Itertools;
+use serde_json::{Map, Value};
+
+use super::model_optimizer::remove_if_equals;
+
+/// Structurally optimizes a parsed Minecraft blockstate definition in place, returning whether
+/// it was changed.
+///
+/// The models that variants and multipart cases use are simplified: keys whose values are the
+/// same as the defaults Minecraft uses for missing keys are removed, such as `"uvlock": false`
+/// or `"weight": 1`, consecutive identical models of a weighted list are merged into a model
+/// with the sum of their weights, which keeps the model picked for every block position, and
+/// lists of a single model are replaced with that model. The conditions of multipart cases are
+/// simplified by flattening nested `OR` and `AND` conditions, removing duplicate conditions and
+/// property values, and replacing combined conditions of a single condition with it. Finally,
+/// multipart cases that apply the same single model under the same condition are removed, as
+/// they would render the same faces again.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Tutorial:Models#Block_states>
+/// - Minecraft classes `net.minecraft.client.renderer.block.model.BlockModelDefinition`,
+///   `net.minecraft.client.renderer.block.model.Variant` and
+///   `net.minecraft.client.renderer.block.model.multipart.Selector`
+pub fn optimize_blockstate(blockstate: &mut Value) -> bool {
+	let Some(blockstate) = blockstate.as_object_mut() else {
+		return false;
+	};
+
+	let mut changed = false;
+
+	if let Some(variants) = blockstate
+		.get_mut("variants")
+		.and_then(Value::as_object_mut)
+	{
+		for models in variants.values_mut() {
+			changed |= optimize_models(models);
+		}
+	}
+
+	if let Some(multipart) = blockstate
+		.get_mut("multipart")
+		.and_then(Value::as_array_mut)
+	{
+		for case in multipart.iter_mut().filter_map(Value::as_object_mut) {
+			if let Some(models) = case.get_mut("apply") {
+				changed |= optimize_models(models);
+			}
+
+			if let Some(condition) = case.get_mut("when") {
+				changed |= simplify_condition(condition);
+			}
+		}
+
+		// Cases that pick a model at random may not pick the same model each time, so only
+		// cases that apply a single model are known to render the same faces again
+		let case_count = multipart.len();
+		let mut seen_cases = AHashSet::with_capacity(case_count);
+		multipart.retain(|case| {
+			!case.get("apply").is_some_and(Value::is_object) || seen_cases.insert(case.to_string())
+		});
+		changed |= multipart.len() != case_count;
+	}
+
+	changed
+}
+
+/// Optimizes the specified model, or weighted list of models, of a variant or multipart case,
+/// returning whether it was changed.
+fn optimize_models(models: &mut Value) -> bool {
+	let mut changed = false;
+
+	if let Some(model_list) = models.as_array_mut() {
+		for model in model_list.iter_mut().filter_map(Value::as_object_mut) {
+			changed |= remove_model_defaults(model);
+		}
+
+		// The model picked for a block position depends on where the random weight falls among
+		// the cumulative weights of the models, so merging consecutive models keeps it
+		let model_count = model_list.len();
+		*model_list = mem::take(model_list)
+			.into_iter()
+			.coalesce(|previous_model, model| {
+				match (weight_and_model(&previous_model), weight_and_model(&model)) {
+					(Some((previous_weight, previous_model_data)), Some((weight, model_data)))
+						if previous_model_data == model_data =>
+					{
+						let mut merged_model = model_data;
+						merged_model.insert(
+							"weight".into(),
+							(previous_weight.saturating_add(weight)).into()
+						);
+						Ok(Value::Object(merged_model))
+					}
+					_ => Err((previous_model, model))
+				}
+			})
+			.collect();
+		changed |= model_list.len() != model_count;
+
+		if model_list.len() == 1 {
+			*models = model_list.pop().unwrap();
+			changed = true;
+		}
+	}
+
+	if let Some(model) = models.as_object_mut() {
+		changed |= remove_model_defaults(model);
+	}
+
+	changed
+}
+
+/// Returns the weight of the specified model of a weighted list, and the model without its
+/// weight, or `None` if it is not a model with a valid weight.
+fn weight_and_model(model: &Value) -> Option<(u64, Map<String, Value>)> {
+	let mut model = model.as_object()?.clone();
+	let weight = match model.shift_remove("weight") {
+		Some(weight) => weight.as_u64().filter(|weight| *weight > 0)?,
+		None => 1
+	};
+
+	Some((weight, model))
+}
+
+/// Removes the keys of a model of a variant or multipart case whose values are the defaults,
+/// returning whether some key was removed.
+fn remove_model_defaults(model: &mut Map<String, Value>) -> bool {
+	let mut changed = remove_if_equals(model, "x", &0.into());
+	changed |= remove_if_equals(model, "y", &0.into());
+	changed |= remove_if_equals(model, "uvlock", &Value::Bool(false));
+	changed |= remove_if_equals(model, "weight", &1.into());
+
+	changed
+}
+
+/// Simplifies the specified multipart case condition, returning whether it was changed.
+fn simplify_condition(condition: &mut Value) -> bool {
+	let Some(condition_object) = condition.as_object_mut() else {
+		return false;
+	};
+
+	let operator = match condition_object.keys().exactly_one() {
+		Ok(operator) if operator == "OR" || operator == "AND" => operator.clone(),
+		_ => return simplify_property_condition(condition_object)
+	};
+	let Some(operands) = condition_object
+		.get_mut(&operator)
+		.and_then(Value::as_array_mut)
+	else {
+		return false;
+	};
+
+	// Nested conditions with the same operator can be flattened, and duplicate conditions
+	// don't change the result
+	let mut changed = false;
+	let mut seen_operands = AHashSet::with_capacity(operands.len());
+	let mut simplified_operands = Vec::with_capacity(operands.len());
+	for mut operand in mem::take(operands) {
+		changed |= simplify_condition(&mut operand);
+
+		let nested_operands = operand
+			.as_object_mut()
+			.filter(|operand| operand.len() == 1)
+			.and_then(|operand| operand.get_mut(&operator))
+			.and_then(Value::as_array_mut)
+			.map(mem::take);
+
+		match nested_operands {
+			Some(nested_operands) => {
+				simplified_operands.extend(
+					nested_operands
+						.into_iter()
+						.filter(|operand| seen_operands.insert(operand.to_string()))
+				);
+				changed = true;
+			}
+			None if seen_operands.insert(operand.to_string()) => simplified_operands.push(operand),
+			None => changed = true
+		}
+	}
+	*operands = simplified_operands;
+
+	// Property conditions on different properties are implicitly combined with AND, so such
+	// an AND condition is equivalent to a single property condition with every property
+	let is_mergeable_and_condition = operator == "AND"
+		&& operands.iter().all(|operand| {
+			operand
+				.as_object()
+				.is_some_and(|operand| !operand.contains_key("OR") && !operand.contains_key("AND"))
+		}) && operands
+		.iter()
+		.flat_map(|operand| operand.as_object().unwrap().keys())
+		.all_unique();
+
+	if operands.len() == 1 {
+		*condition = operands.pop().unwrap();
+		changed = true;
+	} else if is_mergeable_and_condition && !operands.is_empty() {
+		*condition = Value::Object(
+			mem::take(operands)
+				.into_iter()
+				.flat_map(|operand| match operand {
+					Value::Object(operand) => operand,
+					_ => unreachable!("Mergeable operands are objects")
+				})
+				.collect()
+		);
+		changed = true;
+	}
+
+	changed
+}
+
+/// Removes duplicate values of the `|`-separated value lists of a property condition,
+/// returning whether some value was removed. Values may be negated as a whole with a leading
+/// `!`, which is kept.
+fn simplify_property_condition(condition: &mut Map<String, Value>) -> bool {
+	let mut changed = false;
+
+	for value in condition.values_mut() {
+		let Some(values) = value.as_str() else {
+			continue;
+		};
+
+		let (negation, values) = match values.strip_prefix('!') {
+			Some(values) => ("!", values),
+			None => ("", values)
+		};
+		let unique_values = values.split('|').unique().join("|");
+
+		if unique_values.len() != values.len() {
+			*value = Value::String(format!("{negation}{unique_values}"));
+			changed = true;
+		}
+	}
+
+	changed
+}
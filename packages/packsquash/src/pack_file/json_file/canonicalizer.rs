@@ -0,0 +1,135 @@
+//! Implements the canonicalization of JSON values, which makes semantically identical values
+//! serialize to the same bytes.
+
+use std::cmp::Ordering;
+use std::mem;
+
+use serde_json::{Number, Value};
+
+/// Well-known keys of Minecraft JSON files, in the order they are conventionally written in
+/// vanilla assets: general keys before specific ones, and coordinates and faces in their usual
+/// order. Keys not listed here are put after them, in lexicographic order.
+const CANONICAL_KEY_ORDER: &[&str] = &[
+	// Pack metadata
+	"pack",
+	"pack_format",
+	"description",
+	"supported_formats",
+	// Models
+	"parent",
+	"loader",
+	"ambientocclusion",
+	"gui_light",
+	"display",
+	"thirdperson_righthand",
+	"thirdperson_lefthand",
+	"firstperson_righthand",
+	"firstperson_lefthand",
+	"gui",
+	"head",
+	"ground",
+	"fixed",
+	"rotation",
+	"translation",
+	"scale",
+	"textures",
+	"particle",
+	"elements",
+	"from",
+	"to",
+	"origin",
+	"axis",
+	"angle",
+	"rescale",
+	"shade",
+	"light_emission",
+	"faces",
+	"down",
+	"up",
+	"north",
+	"south",
+	"west",
+	"east",
+	"uv",
+	"texture",
+	"cullface",
+	"tintindex",
+	"overrides",
+	"predicate",
+	// Block states
+	"variants",
+	"multipart",
+	"when",
+	"apply",
+	"model",
+	"x",
+	"y",
+	"uvlock",
+	"weight",
+	// Texture metadata
+	"animation",
+	"interpolate",
+	"width",
+	"height",
+	"frametime",
+	"frames",
+	"index",
+	"time"
+];
+
+/// The magnitude below which every integer can be represented exactly by an `f64`, which is
+/// 2^53.
+const MAXIMUM_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// Canonicalizes a JSON value in place, so that semantically identical values serialize to
+/// the same bytes. The keys of every object are sorted in [`CANONICAL_KEY_ORDER`], and numbers
+/// with an integral value are written as integers, as Minecraft reads every JSON number the
+/// same way no matter how it is written.
+///
+/// This function does not recurse, so it is safe to use with deeply nested values.
+pub fn canonicalize(value: &mut Value) {
+	let mut pending_values = vec![value];
+
+	while let Some(value) = pending_values.pop() {
+		match value {
+			Value::Object(object) => {
+				let mut entries = mem::take(object).into_iter().collect::<Vec<_>>();
+				entries.sort_unstable_by(|(a, _), (b, _)| compare_keys(a, b));
+				object.extend(entries);
+
+				pending_values.extend(object.values_mut());
+			}
+			Value::Array(array) => pending_values.extend(array),
+			Value::Number(number) => {
+				if let Some(integer) = integral_float_value(number) {
+					*number = integer.into();
+				}
+			}
+			_ => {}
+		}
+	}
+}
+
+/// Compares two object keys according to their canonical order.
+fn compare_keys(a: &str, b: &str) -> Ordering {
+	let key_rank = |key| {
+		CANONICAL_KEY_ORDER
+			.iter()
+			.position(|&canonical_key| canonical_key == key)
+			.unwrap_or(CANONICAL_KEY_ORDER.len())
+	};
+
+	key_rank(a).cmp(&key_rank(b)).then_with(|| a.cmp(b))
+}
+
+/// Returns the integer value of a floating-point JSON number, if it has an integral value
+/// that can be converted to an integer exactly. Negative zero is left alone, as it has no
+/// integer representation.
+fn integral_float_value(number: &Number) -> Option<i64> {
+	let value = number.as_f64().filter(|_| number.is_f64())?;
+
+	(value.fract() == 0.0
+		&& value.abs() < MAXIMUM_EXACT_INTEGER
+		&& !(value == 0.0 && value.is_sign_negative()))
+	.then_some(value as i64)
+}
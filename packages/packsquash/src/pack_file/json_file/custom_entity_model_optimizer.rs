@@ -0,0 +1,83 @@
+//! Implements structural optimizations for OptiFine custom entity models and part models,
+//! which shrink them further than minification without changing how entities are rendered.
+
+use serde_json::{Map, Value};
+
+use super::model_optimizer::remove_if_equals;
+use super::number_rounding::round_numbers;
+
+/// The maximum number of decimal places the numbers of custom entity models are rounded to.
+/// Coordinates are in model pixels and rotations in degrees, so differences below this
+/// precision are not noticeable in game.
+const DECIMAL_PLACES: u8 = 4;
+
+/// Structurally optimizes a parsed OptiFine custom entity model (`.jem` file) or part model
+/// (`.jpm` file) in place, returning whether it was changed.
+///
+/// Keys of model parts, boxes and sprites whose values are the same as the defaults OptiFine
+/// uses for missing keys are removed, such as `"translate": [0, 0, 0]`, `"invertAxis": ""` or
+/// `"sizeAdd": 0`, and so are empty lists of boxes, sprites, submodels and animations. Numbers
+/// are rounded to a few decimal places, which gets rid of the floating-point noise that
+/// modelling tools like Blockbench write without any noticeable difference.
+///
+/// References:
+/// - <https://optifine.readthedocs.io/cem_model.html>
+/// - <https://optifine.readthedocs.io/cem_part.html>
+pub fn optimize_custom_entity_model(custom_entity_model: &mut Value) -> bool {
+	let mut changed = round_numbers(custom_entity_model, DECIMAL_PLACES);
+
+	let Some(custom_entity_model) = custom_entity_model.as_object_mut() else {
+		return changed;
+	};
+
+	// Entity models have a list of models that are parts, while part models are a part
+	let mut pending_parts = vec![];
+	if !custom_entity_model.contains_key("models") {
+		pending_parts.push(custom_entity_model);
+	} else if let Some(Value::Array(models)) = custom_entity_model.get_mut("models") {
+		pending_parts.extend(models.iter_mut().filter_map(Value::as_object_mut));
+	}
+
+	while let Some(part) = pending_parts.pop() {
+		changed |= optimize_part(part);
+
+		for (key, value) in part.iter_mut() {
+			match (key.as_str(), value) {
+				("submodel", Value::Object(submodel)) => pending_parts.push(submodel),
+				("submodels", Value::Array(submodels)) => {
+					pending_parts.extend(submodels.iter_mut().filter_map(Value::as_object_mut));
+				}
+				_ => {}
+			}
+		}
+	}
+
+	changed
+}
+
+/// Optimizes the keys of the specified model part, and its boxes and sprites, but not its
+/// submodels, returning whether it was changed.
+fn optimize_part(part: &mut Map<String, Value>) -> bool {
+	let origin = Value::Array(vec![0.into(), 0.into(), 0.into()]);
+	let empty_string = Value::String(String::new());
+
+	let mut changed = remove_if_equals(part, "invertAxis", &empty_string);
+	changed |= remove_if_equals(part, "mirrorTexture", &empty_string);
+	changed |= remove_if_equals(part, "translate", &origin);
+	changed |= remove_if_equals(part, "rotate", &origin);
+	changed |= remove_if_equals(part, "scale", &1.into());
+
+	for key in ["boxes", "sprites", "submodels", "animations"] {
+		changed |= remove_if_equals(part, key, &Value::Array(vec![]));
+	}
+
+	for key in ["boxes", "sprites"] {
+		if let Some(Value::Array(elements)) = part.get_mut(key) {
+			for element in elements.iter_mut().filter_map(Value::as_object_mut) {
+				changed |= remove_if_equals(element, "sizeAdd", &0.into());
+			}
+		}
+	}
+
+	changed
+}
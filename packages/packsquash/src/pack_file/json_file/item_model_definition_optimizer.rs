@@ -0,0 +1,169 @@
+//! Implements structural optimizations for Minecraft item model definitions, which shrink them
+//! further than minification without changing which models items are rendered with.
+
+use serde_json::{Map, Value};
+
+use super::model_optimizer::remove_if_equals;
+
+/// The namespace that resource locations which do not specify any default to.
+const VANILLA_NAMESPACE_PREFIX: &str = "minecraft:";
+
+/// Structurally optimizes a parsed Minecraft item model definition in place, returning whether
+/// it was changed.
+///
+/// Keys whose values are the same as the defaults Minecraft uses for missing keys are removed,
+/// such as `"hand_animation_on_swap": true` or empty tint lists, and the `minecraft:` namespace
+/// of item model, tint and special model types, properties and model references is removed,
+/// as it is the namespace of resource locations that do not specify any. Item models that
+/// choose between other item models are simplified: `condition` models whose branches are
+/// identical, `composite` models of a single model and `select` or `range_dispatch` models
+/// without cases or entries are replaced with the model they always render, and `select` cases
+/// that render the same model as the fallback are removed.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Items_model_definition>
+/// - Minecraft classes `net.minecraft.client.renderer.item.ClientItem` and
+///   `net.minecraft.client.renderer.item.ItemModels`
+pub fn optimize_item_model_definition(item_model_definition: &mut Value) -> bool {
+	let Some(item_model_definition) = item_model_definition.as_object_mut() else {
+		return false;
+	};
+
+	let mut changed = remove_if_equals(
+		item_model_definition,
+		"hand_animation_on_swap",
+		&Value::Bool(true)
+	);
+	changed |= remove_if_equals(
+		item_model_definition,
+		"oversized_in_gui",
+		&Value::Bool(false)
+	);
+	changed |= remove_if_equals(item_model_definition, "swap_animation_scale", &1.into());
+
+	if let Some(item_model) = item_model_definition.get_mut("model") {
+		changed |= optimize_item_model(item_model);
+	}
+
+	changed
+}
+
+/// Optimizes the specified item model, and the item models nested within it, returning whether
+/// it was changed.
+fn optimize_item_model(item_model: &mut Value) -> bool {
+	let Some(item_model_object) = item_model.as_object_mut() else {
+		return false;
+	};
+
+	let mut changed = false;
+
+	for key in ["type", "property", "model", "base"] {
+		if let Some(Value::String(resource_location)) = item_model_object.get_mut(key) {
+			changed |= remove_vanilla_namespace(resource_location);
+		}
+	}
+
+	// Special models have a nested object with their type under the model key, which is
+	// optimized like an item model too
+	for key in ["model", "on_true", "on_false", "fallback"] {
+		if let Some(nested_item_model) = item_model_object.get_mut(key) {
+			changed |= optimize_item_model(nested_item_model);
+		}
+	}
+
+	for nested_item_model in item_model_object
+		.get_mut("models")
+		.and_then(Value::as_array_mut)
+		.into_iter()
+		.flatten()
+	{
+		changed |= optimize_item_model(nested_item_model);
+	}
+
+	for key in ["cases", "entries"] {
+		for nested_item_model in item_model_object
+			.get_mut(key)
+			.and_then(Value::as_array_mut)
+			.into_iter()
+			.flatten()
+			.filter_map(|case| case.get_mut("model"))
+		{
+			changed |= optimize_item_model(nested_item_model);
+		}
+	}
+
+	for tint_type in item_model_object
+		.get_mut("tints")
+		.and_then(Value::as_array_mut)
+		.into_iter()
+		.flatten()
+		.filter_map(|tint| tint.get_mut("type"))
+	{
+		if let Value::String(tint_type) = tint_type {
+			changed |= remove_vanilla_namespace(tint_type);
+		}
+	}
+
+	changed |= remove_if_equals(item_model_object, "tints", &Value::Array(vec![]));
+
+	match item_model_object.get("type").and_then(Value::as_str) {
+		Some("select") => {
+			if let Some(fallback) = item_model_object.get("fallback").cloned()
+				&& let Some(cases) = item_model_object
+					.get_mut("cases")
+					.and_then(Value::as_array_mut)
+			{
+				// Values that no case matches render the fallback model, so cases that render
+				// it are redundant
+				let case_count = cases.len();
+				cases.retain(|case| case.get("model") != Some(&fallback));
+				changed |= cases.len() != case_count;
+			}
+		}
+		Some("range_dispatch") => {
+			changed |= remove_if_equals(item_model_object, "scale", &1.into());
+		}
+		_ => {}
+	}
+
+	if let Some(simplified_item_model) = simplified_item_model(item_model_object) {
+		*item_model = simplified_item_model;
+		changed = true;
+	}
+
+	changed
+}
+
+/// Returns the item model that the specified item model always renders, if it is a different,
+/// simpler one.
+fn simplified_item_model(item_model: &Map<String, Value>) -> Option<Value> {
+	let nested_item_models = |key: &str| item_model.get(key).and_then(Value::as_array);
+
+	match item_model.get("type").and_then(Value::as_str)? {
+		"condition" => {
+			let on_true = item_model.get("on_true")?;
+			(Some(on_true) == item_model.get("on_false")).then(|| on_true.clone())
+		}
+		"composite" => match nested_item_models("models")?.as_slice() {
+			[model] => Some(model.clone()),
+			_ => None
+		},
+		"select" if nested_item_models("cases")?.is_empty() => item_model.get("fallback").cloned(),
+		"range_dispatch" if nested_item_models("entries")?.is_empty() => {
+			item_model.get("fallback").cloned()
+		}
+		_ => None
+	}
+}
+
+/// Removes the vanilla namespace from the specified resource location, returning whether it
+/// was removed.
+fn remove_vanilla_namespace(resource_location: &mut String) -> bool {
+	match resource_location.strip_prefix(VANILLA_NAMESPACE_PREFIX) {
+		Some(path) if !path.is_empty() => {
+			*resource_location = path.to_owned();
+			true
+		}
+		_ => false
+	}
+}
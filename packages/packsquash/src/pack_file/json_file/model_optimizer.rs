@@ -0,0 +1,251 @@
+//! Implements structural optimizations for Minecraft block and item models, which shrink them
+//! further than minification without changing how they are rendered.
+
+use ahash::{AHashMap, AHashSet};
+use serde_json::{Map, Value, json};
+
+/// The texture variable that Minecraft uses for the particles of a model, which is read by the
+/// game itself and must keep its name.
+const PARTICLE_TEXTURE_VARIABLE: &str = "particle";
+
+/// Structurally optimizes a parsed Minecraft model in place, returning whether it was changed.
+///
+/// Keys whose values are the same as the defaults Minecraft uses for missing keys are removed,
+/// such as `"shade": true` or zero-angle element rotations, and elements that are identical to
+/// a previous element of the model are removed, as they would render the same faces again. If
+/// `shorten_texture_variables` is `true`, texture variables that are defined and used within
+/// the model are renamed to shorter names. That is only done for models without a parent, but
+/// it still breaks other models that use the model as a parent and set its texture variables,
+/// so it must be explicitly requested.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Model#Block_models>
+/// - Minecraft classes `net.minecraft.client.renderer.block.model.BlockModel`,
+///   `net.minecraft.client.renderer.block.model.BlockElement` and
+///   `net.minecraft.client.renderer.block.model.ItemTransform`
+pub fn optimize_model(model: &mut Value, shorten_texture_variables: bool) -> bool {
+	let Some(model) = model.as_object_mut() else {
+		return false;
+	};
+
+	let mut changed = false;
+
+	// Ambient occlusion is inherited from the parent model when the key is missing, so it can
+	// only be assumed to be enabled by default for models without a parent
+	if !model.contains_key("parent") {
+		changed |= remove_if_equals(model, "ambientocclusion", &Value::Bool(true));
+	}
+
+	if let Some(display) = model.get_mut("display").and_then(Value::as_object_mut) {
+		for transform in display.values_mut().filter_map(Value::as_object_mut) {
+			changed |= remove_if_equals(transform, "rotation", &json!([0, 0, 0]));
+			changed |= remove_if_equals(transform, "translation", &json!([0, 0, 0]));
+			changed |= remove_if_equals(transform, "scale", &json!([1, 1, 1]));
+		}
+	}
+
+	if let Some(elements) = model.get_mut("elements").and_then(Value::as_array_mut) {
+		for element in elements.iter_mut().filter_map(Value::as_object_mut) {
+			changed |= remove_element_defaults(element);
+		}
+
+		let element_count = elements.len();
+		let mut seen_elements = AHashSet::with_capacity(element_count);
+		elements.retain(|element| seen_elements.insert(element.to_string()));
+		changed |= elements.len() != element_count;
+	}
+
+	if shorten_texture_variables && !model.contains_key("parent") {
+		changed |= shorten_model_texture_variables(model);
+	}
+
+	changed
+}
+
+/// Removes the keys of a model element whose values are the defaults, returning whether some
+/// key was removed.
+fn remove_element_defaults(element: &mut Map<String, Value>) -> bool {
+	let mut changed = remove_if_equals(element, "shade", &Value::Bool(true));
+	changed |= remove_if_equals(element, "light_emission", &0.into());
+
+	// Rotating by zero degrees does nothing, and the rescaling factor for that angle is one
+	if element
+		.get("rotation")
+		.and_then(|rotation| rotation.get("angle"))
+		.and_then(Value::as_f64)
+		== Some(0.0)
+	{
+		element.shift_remove("rotation");
+		changed = true;
+	} else if let Some(rotation) = element.get_mut("rotation").and_then(Value::as_object_mut) {
+		changed |= remove_if_equals(rotation, "rescale", &Value::Bool(false));
+	}
+
+	if let Some(faces) = element.get_mut("faces").and_then(Value::as_object_mut) {
+		for face in faces.values_mut().filter_map(Value::as_object_mut) {
+			changed |= remove_if_equals(face, "rotation", &0.into());
+			changed |= remove_if_equals(face, "tintindex", &(-1).into());
+		}
+	}
+
+	changed
+}
+
+/// Renames the texture variables defined in the `textures` object of a model and referenced
+/// by its element faces or other texture variables to the shortest names not used by other
+/// variables, returning whether some variable was renamed.
+fn shorten_model_texture_variables(model: &mut Map<String, Value>) -> bool {
+	let Some(textures) = model.get("textures").and_then(Value::as_object) else {
+		return false;
+	};
+
+	// Do not reuse the names of the variables defined by this model, nor of the variables it
+	// references but does not define, which can't be renamed
+	let mut used_names = referenced_texture_variables(model)
+		.chain(textures.keys().map(String::as_str))
+		.map(str::to_string)
+		.collect::<AHashSet<_>>();
+
+	let mut new_names = AHashMap::new();
+	let mut name_generator = (0..).map(short_variable_name);
+	for variable in textures.keys() {
+		if variable == PARTICLE_TEXTURE_VARIABLE {
+			continue;
+		}
+
+		let new_name = name_generator
+			.by_ref()
+			.find(|name| !used_names.contains(name))
+			.unwrap();
+
+		if new_name.len() < variable.len() {
+			used_names.insert(new_name.clone());
+			new_names.insert(variable.clone(), new_name);
+		}
+	}
+
+	if new_names.is_empty() {
+		return false;
+	}
+
+	let rename_reference = |reference: &mut String| {
+		let (prefix, variable) = match reference.strip_prefix('#') {
+			Some(variable) => ("#", variable),
+			None => ("", reference.as_str())
+		};
+
+		if let Some(new_name) = new_names.get(variable) {
+			*reference = format!("{prefix}{new_name}");
+		}
+	};
+
+	if let Some(textures) = model.get_mut("textures").and_then(Value::as_object_mut) {
+		*textures = textures
+			.iter_mut()
+			.map(|(variable, value)| {
+				if let Value::String(value) = value
+					&& value.starts_with('#')
+				{
+					rename_reference(value);
+				}
+
+				(
+					new_names.get(variable).unwrap_or(variable).clone(),
+					value.take()
+				)
+			})
+			.collect();
+	}
+
+	for face_texture in element_face_textures(model) {
+		rename_reference(face_texture);
+	}
+
+	true
+}
+
+/// Returns an iterator over the names of the texture variables referenced by the element faces
+/// and texture variables of a model.
+fn referenced_texture_variables(model: &Map<String, Value>) -> impl Iterator<Item = &str> {
+	let texture_references = model
+		.get("textures")
+		.and_then(Value::as_object)
+		.into_iter()
+		.flat_map(|textures| textures.values())
+		.filter_map(|value| value.as_str()?.strip_prefix('#'));
+
+	let face_textures = model
+		.get("elements")
+		.and_then(Value::as_array)
+		.into_iter()
+		.flatten()
+		.filter_map(|element| element.get("faces")?.as_object())
+		.flat_map(|faces| faces.values())
+		.filter_map(|face| face.get("texture")?.as_str())
+		.map(|texture| texture.strip_prefix('#').unwrap_or(texture));
+
+	texture_references.chain(face_textures)
+}
+
+/// Returns an iterator over the texture references of the element faces of a model.
+fn element_face_textures(model: &mut Map<String, Value>) -> impl Iterator<Item = &mut String> {
+	model
+		.get_mut("elements")
+		.and_then(Value::as_array_mut)
+		.into_iter()
+		.flatten()
+		.filter_map(|element| element.get_mut("faces")?.as_object_mut())
+		.flat_map(|faces| faces.values_mut())
+		.filter_map(|face| match face.get_mut("texture")? {
+			Value::String(texture) => Some(texture),
+			_ => None
+		})
+}
+
+/// Returns the short texture variable name with the specified index: `a` to `z`, followed by
+/// `aa`, `ab` and so on.
+fn short_variable_name(mut index: usize) -> String {
+	let mut name = Vec::new();
+	loop {
+		name.push(b'a' + (index % 26) as u8);
+		index /= 26;
+		if index == 0 {
+			break;
+		}
+		index -= 1;
+	}
+	name.reverse();
+
+	String::from_utf8(name).unwrap()
+}
+
+/// Removes the specified key from a JSON object if its value is equal to the specified
+/// default value, returning whether it was removed. Numbers are compared by value, so that
+/// integers and floating-point numbers with the same value are considered equal.
+pub(super) fn remove_if_equals(
+	object: &mut Map<String, Value>,
+	key: &str,
+	default_value: &Value
+) -> bool {
+	let is_default = object
+		.get(key)
+		.is_some_and(|value| json_values_equal(value, default_value));
+
+	if is_default {
+		// Keep the order of the other keys, in case they are not sorted
+		object.shift_remove(key);
+	}
+
+	is_default
+}
+
+/// Checks whether two JSON values are equal, comparing numbers by their value.
+fn json_values_equal(a: &Value, b: &Value) -> bool {
+	match (a, b) {
+		(Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+		(Value::Array(a), Value::Array(b)) => {
+			a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_values_equal(a, b))
+		}
+		_ => a == b
+	}
+}
@@ -0,0 +1,58 @@
+//! Implements the rounding of JSON numbers to a maximum number of decimal places, which gets
+//! rid of the floating-point noise that some pack authoring tools write.
+
+use serde_json::{Number, Value};
+
+/// The magnitude below which every integer can be represented exactly by an `f64`, which is
+/// 2^53.
+const MAXIMUM_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// Rounds every floating-point number of a JSON value to the specified maximum number of
+/// decimal places in place, returning whether some number was changed. Integers are left
+/// alone, as they have no decimal places, and so are numbers whose magnitude is too big
+/// for rounding to make a difference.
+///
+/// Pack authoring tools such as Blockbench often write values like `22.499999999999996`
+/// instead of `22.5` due to floating-point rounding errors, which are not relevant for the
+/// precision Minecraft renders with, but make files bigger and less compressible.
+///
+/// This function does not recurse, so it is safe to use with deeply nested values.
+pub fn round_numbers(value: &mut Value, decimal_places: u8) -> bool {
+	let scale = 10_f64.powi(decimal_places.into());
+	let mut changed = false;
+
+	let mut pending_values = vec![value];
+	while let Some(value) = pending_values.pop() {
+		match value {
+			Value::Object(object) => pending_values.extend(object.values_mut()),
+			Value::Array(array) => pending_values.extend(array),
+			Value::Number(number) => {
+				if let Some(rounded_number) = rounded_float_value(number, scale) {
+					*number = rounded_number;
+					changed = true;
+				}
+			}
+			_ => {}
+		}
+	}
+
+	changed
+}
+
+/// Returns the value of a floating-point JSON number rounded to the decimal places given by
+/// the specified power of ten, if rounding changes it.
+fn rounded_float_value(number: &Number, scale: f64) -> Option<Number> {
+	let value = number.as_f64().filter(|_| number.is_f64())?;
+
+	let scaled_value = value * scale;
+	if !scaled_value.is_finite() || scaled_value.abs() >= MAXIMUM_EXACT_INTEGER {
+		return None;
+	}
+
+	// Adding zero turns negative zero, which small negative numbers may round to, into zero
+	let rounded_value = scaled_value.round() / scale + 0.0;
+
+	(rounded_value != value || rounded_value.is_sign_negative() != value.is_sign_negative())
+		.then(|| Number::from_f64(rounded_value))
+		.flatten()
+}
@@ -0,0 +1,102 @@
+//! Implements the removal of the relaxed JSON syntax extensions that hand-edited pack files
+//! commonly use, such as comments and trailing commas, so that they can be parsed as strict
+//! JSON.
+
+use super::OptimizationError;
+
+/// Replaces the comments and trailing commas of a JSON document with whitespace in place,
+/// turning it into a strict JSON document with the same value. Line breaks are kept, so the
+/// line and column locations that JSON parsing errors refer to match the original document.
+///
+/// Line comments start with `//` or `#`, and block comments are delimited by `/*` and `*/`,
+/// like in the JSON with comments dialect most editors understand. Trailing commas are commas
+/// after the last element of an array or the last member of an object.
+pub fn blank_out_relaxed_syntax(json: &mut [u8]) -> Result<(), OptimizationError> {
+	// The index of the last comma outside strings, if only whitespace and comments follow it
+	let mut pending_comma_index = None;
+
+	let mut i = 0;
+	while i < json.len() {
+		match json[i] {
+			b'"' => {
+				pending_comma_index = None;
+
+				// Skip the string contents, which may contain comment-like sequences. Parsing
+				// will fail later on if the string is not terminated
+				i += 1;
+				while i < json.len() && json[i] != b'"' {
+					i += if json[i] == b'\\' { 2 } else { 1 };
+				}
+			}
+			b'/' if json.get(i + 1) == Some(&b'/') => {
+				i = blank_out_until(json, i, 2, b"\n").unwrap_or(json.len()) - 1;
+			}
+			b'#' => {
+				i = blank_out_until(json, i, 1, b"\n").unwrap_or(json.len()) - 1;
+			}
+			b'/' if json.get(i + 1) == Some(&b'*') => {
+				let Some(comment_end) = blank_out_until(json, i, 2, b"*/") else {
+					let (line, column) = line_and_column(json, i);
+					return Err(OptimizationError::UnterminatedComment { line, column });
+				};
+
+				// Blank out the comment terminator too
+				json[comment_end..comment_end + 2].fill(b' ');
+				i = comment_end + 1;
+			}
+			b',' => pending_comma_index = Some(i),
+			b']' | b'}' => {
+				if let Some(comma_index) = pending_comma_index.take() {
+					json[comma_index] = b' ';
+				}
+			}
+			byte if byte.is_ascii_whitespace() => {}
+			_ => pending_comma_index = None
+		}
+
+		i += 1;
+	}
+
+	Ok(())
+}
+
+/// Replaces the bytes of a JSON document from the start index of a comment with spaces until
+/// the specified terminator is found after the comment opener, keeping line breaks. The index
+/// of the terminator is returned, or `None` if it was not found, in which case every byte
+/// until the end is replaced.
+fn blank_out_until(
+	json: &mut [u8],
+	start_index: usize,
+	opener_length: usize,
+	terminator: &[u8]
+) -> Option<usize> {
+	// Skip the comment opener, so that the opener of a block comment is not taken as part
+	// of its terminator
+	let terminator_index = json[start_index + opener_length..]
+		.windows(terminator.len())
+		.position(|window| window == terminator)
+		.map(|position| start_index + opener_length + position);
+
+	let end_index = terminator_index.unwrap_or(json.len());
+	for byte in &mut json[start_index..end_index] {
+		if *byte != b'\n' && *byte != b'\r' {
+			*byte = b' ';
+		}
+	}
+
+	terminator_index
+}
+
+/// Returns the 1-based line and column numbers of the byte at the specified index of a JSON
+/// document, counting columns in bytes, like JSON parsing errors do.
+fn line_and_column(json: &[u8], index: usize) -> (usize, usize) {
+	let line_start_index = json[..index]
+		.iter()
+		.rposition(|&byte| byte == b'\n')
+		.map_or(0, |line_break_index| line_break_index + 1);
+
+	(
+		json[..index].iter().filter(|&&byte| byte == b'\n').count() + 1,
+		index - line_start_index + 1
+	)
+}
@@ -0,0 +1,59 @@
+//! Implements structural optimizations for Minecraft data pack tags, which shrink them further
+//! than minification without changing which elements they contain.
+
+use serde_json::Value;
+
+use super::model_optimizer::remove_if_equals;
+
+/// Structurally optimizes a parsed Minecraft data pack tag in place, returning whether it was
+/// changed.
+///
+/// `"replace": false` is removed, as it is the default, required entries in object form, like
+/// `{ "id": "minecraft:stone" }`, are written as the equivalent `"minecraft:stone"` string,
+/// and entries that are identical to a previous one are removed, because Minecraft collects
+/// the entries of a tag into a set.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Tag_(Java_Edition)#JSON_format>
+/// - Minecraft classes `net.minecraft.tags.TagFile` and `net.minecraft.tags.TagEntry`
+pub fn optimize_tag(tag: &mut Value) -> bool {
+	let Some(tag) = tag.as_object_mut() else {
+		return false;
+	};
+
+	let mut changed = remove_if_equals(tag, "replace", &Value::Bool(false));
+
+	if let Some(values) = tag.get_mut("values").and_then(Value::as_array_mut) {
+		for value in values.iter_mut() {
+			let Some(entry) = value.as_object() else {
+				continue;
+			};
+
+			let is_required = entry
+				.get("required")
+				.is_none_or(|required| required.as_bool() == Some(true));
+			let has_known_keys_only = entry.keys().all(|key| key == "id" || key == "required");
+
+			if is_required
+				&& has_known_keys_only
+				&& let Some(Value::String(id)) = entry.get("id")
+			{
+				*value = Value::String(id.clone());
+				changed = true;
+			}
+		}
+
+		let value_count = values.len();
+		let mut index = 0;
+		while index < values.len() {
+			if values[..index].contains(&values[index]) {
+				values.remove(index);
+			} else {
+				index += 1;
+			}
+		}
+		changed |= values.len() != value_count;
+	}
+
+	changed
+}
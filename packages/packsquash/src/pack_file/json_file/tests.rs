@@ -79,7 +79,7 @@ async fn minifying_works() {
 		PackFileAssetType::GenericJson,
 		JsonFileOptions {
 			minify: true,
-			sort_object_keys: false,
+			sort_object_keys: JsonObjectKeySorting::Disabled,
 			..Default::default()
 		},
 		MINIFIED_JSON_DATA
@@ -97,7 +97,7 @@ async fn minifying_with_bom_works() {
 		PackFileAssetType::GenericJson,
 		JsonFileOptions {
 			minify: true,
-			sort_object_keys: false,
+			sort_object_keys: JsonObjectKeySorting::Disabled,
 			..Default::default()
 		},
 		MINIFIED_JSON_DATA
@@ -116,7 +116,7 @@ async fn minifying_with_comments_works() {
 		JsonFileOptions {
 			minify: true,
 			always_allow_comments: true,
-			sort_object_keys: false,
+			sort_object_keys: JsonObjectKeySorting::Disabled,
 			..Default::default()
 		},
 		MINIFIED_JSON_DATA
@@ -131,7 +131,7 @@ async fn prettifying_works() {
 		PackFileAssetType::GenericJson,
 		JsonFileOptions {
 			minify: false,
-			sort_object_keys: false,
+			sort_object_keys: JsonObjectKeySorting::Disabled,
 			..Default::default()
 		},
 		PRETTIFIED_JSON_DATA
@@ -146,7 +146,7 @@ async fn prettifying_with_key_sorting_works() {
 		PackFileAssetType::GenericJson,
 		JsonFileOptions {
 			minify: false,
-			sort_object_keys: true,
+			sort_object_keys: JsonObjectKeySorting::Lexicographic,
 			..Default::default()
 		},
 		PRETTIFIED_SORTED_JSON_DATA
@@ -154,6 +154,218 @@ async fn prettifying_with_key_sorting_works() {
 	.await
 }
 
+#[tokio::test]
+async fn canonicalization_works() {
+	const CANONICAL_MODEL: &str = r##"{"parent":"block/cube","textures":{"particle":"#all","all":"block/stone"},"elements":[{"from":[0,0,0],"to":[16,16,16.5],"faces":{"north":{"uv":[0,0,16,16],"texture":"#all"}}}],"credit":"Me","custom":-0.0}"##;
+
+	// Differently ordered and written, but semantically identical models
+	for model in [
+		r##"{"elements":[{"faces":{"north":{"texture":"#all","uv":[0,0,16,16]}},"to":[16,16,16.5],"from":[0,0,0]}],"textures":{"all":"block/stone","particle":"#all"},"parent":"block/cube","custom":-0.0,"credit":"Me"}"##,
+		r##"{"credit":"Me","custom":-0e0,"parent":"block/cube","textures":{"particle":"#all","all":"block/stone"},"elements":[{"from":[0.0,0,0],"to":[1.6e1,16.0,16.5],"faces":{"north":{"uv":[0,0.0,16,16],"texture":"#all"}}}]}"##
+	] {
+		successful_process_test(
+			model,
+			PackFileAssetType::GenericJson,
+			JsonFileOptions {
+				sort_object_keys: JsonObjectKeySorting::Canonical,
+				..Default::default()
+			},
+			CANONICAL_MODEL
+		)
+		.await;
+	}
+}
+
+#[tokio::test]
+async fn model_structure_optimization_works() {
+	successful_process_test(
+		r##"{
+			"ambientocclusion": true,
+			"display": { "gui": { "rotation": [30, 225, 0], "translation": [0, 0, 0], "scale": [1.0, 1, 1] } },
+			"textures": { "particle": "#wool_texture", "wool_texture": "block/white_wool", "x": "block/stone" },
+			"elements": [
+				{
+					"from": [0, 0, 0],
+					"to": [16, 16, 16],
+					"shade": true,
+					"rotation": { "origin": [8, 8, 8], "axis": "y", "angle": 0, "rescale": true },
+					"faces": { "up": { "texture": "#wool_texture", "rotation": 0, "tintindex": -1 } }
+				},
+				{
+					"from": [0, 0, 0],
+					"to": [16, 16, 16],
+					"faces": { "up": { "texture": "#wool_texture" } }
+				},
+				{
+					"from": [4, 4, 4],
+					"to": [12, 12, 12],
+					"shade": false,
+					"rotation": { "origin": [8, 8, 8], "axis": "x", "angle": 22.5, "rescale": false },
+					"faces": { "down": { "texture": "#missing", "tintindex": 0 }, "up": { "texture": "x" } }
+				}
+			]
+		}"##,
+		PackFileAssetType::MinecraftModel,
+		JsonFileOptions {
+			sort_object_keys: JsonObjectKeySorting::Disabled,
+			shorten_texture_variables: true,
+			..Default::default()
+		},
+		r##"{"display":{"gui":{"rotation":[30,225,0]}},"textures":{"particle":"#a","a":"block/white_wool","x":"block/stone"},"elements":[{"from":[0,0,0],"to":[16,16,16],"faces":{"up":{"texture":"#a"}}},{"from":[4,4,4],"to":[12,12,12],"shade":false,"rotation":{"origin":[8,8,8],"axis":"x","angle":22.5},"faces":{"down":{"texture":"#missing","tintindex":0},"up":{"texture":"x"}}}]}"##
+	)
+	.await
+}
+
+#[tokio::test]
+async fn model_structure_optimization_respects_parents() {
+	const CHILD_MODEL: &str =
+		r##"{"parent":"block/cube_all","ambientocclusion":true,"textures":{"all":"block/stone"}}"##;
+
+	successful_process_test(
+		CHILD_MODEL,
+		PackFileAssetType::MinecraftModel,
+		JsonFileOptions {
+			sort_object_keys: JsonObjectKeySorting::Disabled,
+			shorten_texture_variables: true,
+			..Default::default()
+		},
+		CHILD_MODEL
+	)
+	.await
+}
+
+#[tokio::test]
+async fn blockstate_structure_optimization_works() {
+	successful_process_test(
+		r#"{
+			"variants": {
+				"facing=north": [
+					{ "model": "block/a", "weight": 1 },
+					{ "model": "block/a" },
+					{ "model": "block/b", "x": 0, "uvlock": false }
+				],
+				"facing=south": [{ "model": "block/c", "y": 180, "weight": 1 }]
+			},
+			"multipart": [
+				{
+					"when": { "OR": [{ "OR": [{ "up": "true" }, { "up": "true" }] }, { "down": "true|true" }] },
+					"apply": { "model": "block/d" }
+				},
+				{ "when": { "AND": [{ "north": "true" }, { "east": "!low|low" }] }, "apply": { "model": "block/e" } },
+				{ "when": { "AND": [{ "north": "true" }, { "east": "!low|low" }] }, "apply": { "model": "block/e" } },
+				{
+					"when": { "OR": [{ "west": "true" }] },
+					"apply": [{ "model": "block/f" }, { "model": "block/g", "weight": 2 }]
+				}
+			]
+		}"#,
+		PackFileAssetType::MinecraftBlockState,
+		JsonFileOptions {
+			sort_object_keys: JsonObjectKeySorting::Disabled,
+			..Default::default()
+		},
+		r#"{"variants":{"facing=north":[{"model":"block/a","weight":2},{"model":"block/b"}],"facing=south":{"model":"block/c","y":180}},"multipart":[{"when":{"OR":[{"up":"true"},{"down":"true"}]},"apply":{"model":"block/d"}},{"when":{"north":"true","east":"!low"},"apply":{"model":"block/e"}},{"when":{"west":"true"},"apply":[{"model":"block/f"},{"model":"block/g","weight":2}]}]}"#
+	)
+	.await
+}
+
+#[tokio::test]
+async fn item_model_definition_structure_optimization_works() {
+	successful_process_test(
+		r#"{
+			"model": {
+				"type": "minecraft:select",
+				"property": "minecraft:display_context",
+				"cases": [
+					{ "when": "gui", "model": { "type": "minecraft:model", "model": "minecraft:item/gem_icon" } },
+					{ "when": "head", "model": { "type": "minecraft:model", "model": "mypack:item/gem", "tints": [] } }
+				],
+				"fallback": {
+					"type": "minecraft:condition",
+					"property": "minecraft:using_item",
+					"on_true": { "type": "minecraft:model", "model": "mypack:item/gem" },
+					"on_false": { "type": "minecraft:composite", "models": [{ "type": "minecraft:model", "model": "mypack:item/gem" }] }
+				}
+			},
+			"hand_animation_on_swap": true
+		}"#,
+		PackFileAssetType::MinecraftItemModelDefinition,
+		JsonFileOptions {
+			sort_object_keys: JsonObjectKeySorting::Disabled,
+			..Default::default()
+		},
+		r#"{"model":{"type":"select","property":"display_context","cases":[{"when":"gui","model":{"type":"model","model":"item/gem_icon"}}],"fallback":{"type":"model","model":"mypack:item/gem"}}}"#
+	)
+	.await
+}
+
+#[tokio::test]
+async fn tag_structure_optimization_works() {
+	successful_process_test(
+		r##"{
+			"replace": false,
+			"values": [
+				"minecraft:stone",
+				{ "id": "#mypack:ores", "required": true },
+				{ "id": "othermod:ruby_ore", "required": false },
+				{ "id": "minecraft:stone" }
+			]
+		}"##,
+		PackFileAssetType::MinecraftTag,
+		JsonFileOptions {
+			sort_object_keys: JsonObjectKeySorting::Disabled,
+			..Default::default()
+		},
+		r##"{"values":["minecraft:stone","#mypack:ores",{"id":"othermod:ruby_ore","required":false}]}"##
+	)
+	.await
+}
+
+#[cfg(feature = "optifine")]
+#[tokio::test]
+async fn custom_entity_model_structure_optimization_works() {
+	successful_process_test(
+		r#"{
+			"texture": "creeper_custom",
+			"models": [
+				{
+					"part": "head",
+					"id": "head",
+					"invertAxis": "xy",
+					"translate": [0, -24.000000000000004, 0],
+					"rotate": [0.0, 0.0, 0.0],
+					"mirrorTexture": "",
+					"boxes": [{ "coordinates": [-4, 24, -4, 8, 8, 8], "sizeAdd": 0 }],
+					"submodels": [{ "id": "snout", "sprites": [], "translate": [1.5, 0, 0] }],
+					"animations": []
+				}
+			]
+		}"#,
+		PackFileAssetType::OptifineCustomEntityModel,
+		JsonFileOptions {
+			sort_object_keys: JsonObjectKeySorting::Disabled,
+			..Default::default()
+		},
+		r#"{"texture":"creeper_custom","models":[{"part":"head","id":"head","invertAxis":"xy","translate":[0,-24.0,0],"boxes":[{"coordinates":[-4,24,-4,8,8,8]}],"submodels":[{"id":"snout","translate":[1.5,0,0]}]}]}"#
+	)
+	.await
+}
+
+#[tokio::test]
+async fn number_rounding_works() {
+	successful_process_test(
+		r#"{"display":{"gui":{"scale":[1.00000001,0.9999999,1],"translation":[0,-0.000001,0]}},"elements":[{"from":[0,0,0],"to":[16,16,16],"rotation":{"origin":[8,8,8],"axis":"y","angle":22.499999999999996}}],"custom":1e300}"#,
+		PackFileAssetType::MinecraftModel,
+		JsonFileOptions {
+			sort_object_keys: JsonObjectKeySorting::Disabled,
+			maximum_decimal_places: Some(4),
+			..Default::default()
+		},
+		r#"{"display":{"gui":{}},"elements":[{"from":[0,0,0],"to":[16,16,16],"rotation":{"origin":[8,8,8],"axis":"y","angle":22.5}}],"custom":1e300}"#
+	)
+	.await
+}
+
 #[tokio::test]
 async fn minifying_and_debloating_model_works() {
 	successful_process_test(
@@ -161,7 +373,7 @@ async fn minifying_and_debloating_model_works() {
 		PackFileAssetType::MinecraftModel,
 		JsonFileOptions {
 			minify: true,
-			sort_object_keys: false,
+			sort_object_keys: JsonObjectKeySorting::Disabled,
 			..Default::default()
 		},
 		MINIFIED_AND_DEBLOATED_JSON_DATA
@@ -181,7 +393,7 @@ async fn comments_are_always_allowed_for_specific_extensions() {
 			minify: true,
 			delete_bloat: false,
 			always_allow_comments: false,
-			sort_object_keys: false,
+			sort_object_keys: JsonObjectKeySorting::Disabled,
 			..Default::default()
 		},
 		MINIFIED_JSON_DATA
@@ -189,6 +401,44 @@ async fn comments_are_always_allowed_for_specific_extensions() {
 	.await;
 }
 
+#[tokio::test]
+async fn relaxed_syntax_is_accepted() {
+	successful_process_test(
+		"{\n\t\"a\": [1, 2, /* three, */],\n\t# Hash comment\n\t\"b\": \"// not a comment, \\\" /*\", // Trailing\n}\n",
+		PackFileAssetType::GenericJson,
+		JsonFileOptions {
+			minify: true,
+			sort_object_keys: JsonObjectKeySorting::Disabled,
+			..Default::default()
+		},
+		r#"{"a":[1,2],"b":"// not a comment, \" /*"}"#
+	)
+	.await
+}
+
+#[tokio::test]
+async fn trailing_commas_are_rejected_when_comments_are_not_allowed() {
+	unsuccessful_process_test(
+		b"{\"a\": [1, 2,]}",
+		PackFileAssetType::GenericJson,
+		JsonFileOptions {
+			always_allow_comments: false,
+			..Default::default()
+		}
+	)
+	.await;
+}
+
+#[test]
+fn unterminated_comments_are_located() {
+	let mut json_data = *b"{\n\t\"a\": 1 /* Unterminated\n}";
+
+	assert!(matches!(
+		blank_out_relaxed_syntax(&mut json_data),
+		Err(OptimizationError::UnterminatedComment { line: 2, column: 9 })
+	));
+}
+
 #[tokio::test]
 async fn comments_are_rejected_when_not_allowed() {
 	let mut json_data_with_comment = String::from(JSON_DATA);
@@ -255,7 +505,7 @@ async fn deeply_nested_value_works() {
 		JsonFileOptions {
 			minify: true,
 			delete_bloat: true,
-			sort_object_keys: false, // Actually sorting keys in this file is prone to allocation errors
+			sort_object_keys: JsonObjectKeySorting::Disabled, // Actually sorting keys in this file is prone to allocation errors
 			..JsonFileOptions::default()
 		},
 		MINIFIED_DEEPLY_NESTED_JSON_DATA
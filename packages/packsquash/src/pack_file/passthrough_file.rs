@@ -86,10 +86,7 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for Passthrou
 					is_force_included: false
 				})
 			}
-			// TODO: ZippedUnifontHex could be optimized by dropping any non-`.hex` file inside it
-			//       and recompressing the remaining `.hex` files with our efficient Zopfli algorithm
-			PackFileAssetType::ZippedUnifontHex
-			| PackFileAssetType::LegacyUnicodeFontCharacterSizes => {
+			PackFileAssetType::LegacyUnicodeFontCharacterSizes => {
 				file_read_producer().map(|(read, _)| Self {
 					read,
 					optimization_strategy_message: "Copied",
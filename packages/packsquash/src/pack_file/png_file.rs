@@ -1,23 +1,102 @@
 //! Contains code to optimize PNG files.
 
 use std::borrow::Cow;
+use std::cmp;
+use std::io::Read;
+use std::num::{NonZeroU16, NonZeroU32};
 
 use bytes::BytesMut;
+use json_comments::StripComments;
+use serde_json::Value;
 use thiserror::Error;
 use tokio::io::AsyncRead;
 use tokio_util::codec::{Decoder, FramedRead};
 
-use crate::config::PngFileOptions;
+use crate::config::{
+	BitDepthReductionDithering, ColorQuantizationTarget, PngAncillaryChunk, PngFileOptions,
+	QuantizationQualityRange
+};
 
+use super::resource_budget::MEMORY_BUDGET;
+use super::strip_utf8_bom;
 use super::{AsyncReadAndSizeHint, PackFile, PackFileAssetType, PackFileConstructor};
 
 use image_processor::{ImageProcessingError, ProcessedImage};
 
 mod image_processor;
+mod recovery;
 
 #[cfg(test)]
 mod tests;
 
+/// The maximum number of mipmap levels that Minecraft generates for texture atlases.
+const MAXIMUM_MIPMAP_LEVELS: u8 = 4;
+
+/// The estimated number of copies of the decoded pixels of an image that are kept in memory at
+/// the same time while optimizing it, accounting for the images generated by every pass and the
+/// filter trials done by OxiPNG.
+const ESTIMATED_DECODED_PIXEL_COPIES: usize = 8;
+
+/// The maximum qualities, in decreasing order, that color quantization is tried with to make an
+/// image fit in its size budget.
+const SIZE_BUDGET_QUANTIZATION_QUALITIES: [u8; 4] = [80, 60, 40, 20];
+
+/// The size of the frames of an animated texture, as declared by its animation metadata file.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Resource_pack#Animation>
+/// - Minecraft class `net.minecraft.client.resources.metadata.animation.AnimationMetadataSection`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct AnimationFrameSize {
+	width: Option<NonZeroU32>,
+	height: Option<NonZeroU32>
+}
+
+impl AnimationFrameSize {
+	/// Parses the frame size declared by the specified animation metadata file. Returns `None`
+	/// if the file is not valid or does not describe an animation.
+	pub(crate) fn from_animation_metadata(animation_metadata: &[u8]) -> Option<Self> {
+		let animation_metadata: Value =
+			serde_json::from_reader(StripComments::new(strip_utf8_bom(animation_metadata))).ok()?;
+		let animation = animation_metadata.get("animation")?.as_object()?;
+
+		let frame_dimension = |key| match animation.get(key) {
+			Some(dimension) => dimension
+				.as_u64()
+				.and_then(|dimension| u32::try_from(dimension).ok())
+				.and_then(NonZeroU32::new)
+				.map(Some),
+			None => Some(None)
+		};
+
+		Some(Self {
+			width: frame_dimension("width")?,
+			height: frame_dimension("height")?
+		})
+	}
+
+	/// Checks whether any frame dimension is declared explicitly, in which case the animation
+	/// metadata file would no longer match the texture if the texture was resized.
+	const fn is_explicit(self) -> bool {
+		self.width.is_some() || self.height.is_some()
+	}
+
+	/// Returns the width and height of the frames of an animated texture with the specified
+	/// dimensions. Frames are square and as wide as the texture by default.
+	fn frame_dimensions(self, width: NonZeroU16, height: NonZeroU16) -> (u32, u32) {
+		let (width, height) = (width.get() as u32, height.get() as u32);
+
+		match (self.width, self.height) {
+			(Some(frame_width), frame_height) => (
+				frame_width.get(),
+				frame_height.map_or(height, NonZeroU32::get)
+			),
+			(None, Some(frame_height)) => (width, frame_height.get()),
+			(None, None) => (cmp::min(width, height), cmp::min(width, height))
+		}
+	}
+}
+
 /// Represents a resource pack PNG image file, which is used for in-game textures.
 ///
 /// The optimization process may be customized via [PngFileOptions].
@@ -74,7 +153,10 @@ impl Decoder for OptimizerDecoder {
 			{
 				false
 			}
-			_ => true
+			_ => {
+				self.optimization_settings
+					.color_type_and_bit_depth_reduction
+			}
 		};
 		let can_change_transparent_pixel_colors = match self.asset_type {
 			PackFileAssetType::EyeLayer
@@ -86,25 +168,201 @@ impl Decoder for OptimizerDecoder {
 			}
 			// These textures may be used to pass data to shaders: their RGB values may
 			// be arbitrarily used for computation. Leave them untouched
-			PackFileAssetType::AuxiliaryShaderTargetTexture => false,
+			PackFileAssetType::AuxiliaryShaderTargetTexture | PackFileAssetType::LabPbrTexture => {
+				false
+			}
 			_ => !self.optimization_settings.skip_alpha_optimizations
 		};
+		// Notes about problems found in the file that are worth reporting
+		let mut optimization_notes = Vec::new();
+
 		let is_auxiliary_shader_target_texture = matches!(
 			self.asset_type,
 			PackFileAssetType::AuxiliaryShaderTargetTexture
 		);
-		let color_quantization_target = self.optimization_settings.color_quantization_target;
+		// The channels of LabPBR textures store material data, not colors, so only optimizations
+		// that preserve every channel value exactly are done to them. Ditto for downscaling and
+		// single-color downsizing, as shaders expect them to have the same size as the textures
+		// they belong to
+		let is_lab_pbr_texture = matches!(self.asset_type, PackFileAssetType::LabPbrTexture);
+		let color_quantization_target = if is_lab_pbr_texture {
+			ColorQuantizationTarget::None
+		} else {
+			self.optimization_settings.color_quantization_target
+		};
 
 		// First pass: strip non-critical PNG chunks we won't use. At worst this does nothing
 		// to the input PNG, and at best it reduces its size, reducing memory requirements for
 		// the next passes. It's relatively cheap to do this, although not free.
 		// This pass also guarantees that both the width and height are not greater than
-		// maximum_width_and_height
-		let first_pass_png = image_processor::strip_unnecessary_chunks(
-			src.split_off(0),
-			self.optimization_settings.maximum_width_and_height
-		)?;
-		let mut first_pass_image = ProcessedImage::read(&*first_pass_png)?;
+		// maximum_width_and_height. WebP textures are not PNG files, so they are just decoded,
+		// and there is no first pass PNG to fall back to. Ancillary chunks that must be preserved
+		// are kept in the first pass PNG, and set aside to insert them in the third pass PNG
+		let preserved_ancillary_chunks = self.optimization_settings.preserved_ancillary_chunks;
+		let is_webp_texture = matches!(self.asset_type, PackFileAssetType::WebpTexture);
+		let mut is_cgbi_png = false;
+		let mut icc_profile = None;
+		let (first_pass_png, preserved_chunks) = if is_webp_texture {
+			(None, Vec::new())
+		} else {
+			// Repair corrupt or nonconformant PNGs beforehand, if desired
+			let mut input_png = src.split_off(0);
+			if self.optimization_settings.corrupt_png_recovery
+				&& let Some(repaired_png) = recovery::repair_png(
+					&input_png,
+					self.optimization_settings.maximum_width_and_height
+				) {
+				input_png = repaired_png.png.as_slice().into();
+				is_cgbi_png = repaired_png.is_cgbi;
+				optimization_notes.push(format!(
+					"Recovered from a corrupt PNG ({})",
+					repaired_png.problems
+				));
+			}
+
+			// Set aside the ICC profile to convert the pixels to sRGB, as stripping removes it.
+			// Pixels are not converted from preserved profiles, so that they still match
+			if self.optimization_settings.color_profile_conversion
+				&& !is_lab_pbr_texture
+				&& !preserved_ancillary_chunks.contains(PngAncillaryChunk::IccProfile)
+			{
+				icc_profile = image_processor::icc_profile(&input_png);
+			}
+
+			let (first_pass_png, preserved_chunks) = image_processor::strip_unnecessary_chunks(
+				input_png,
+				self.optimization_settings.maximum_width_and_height,
+				preserved_ancillary_chunks
+			)?;
+
+			(Some(first_pass_png), preserved_chunks)
+		};
+		// Gamma correction would make the pixels not match a preserved image gamma chunk. ICC
+		// profiles take precedence over the image gamma, so it's not corrected for them either
+		let apply_gamma_correction = !preserved_ancillary_chunks
+			.contains(PngAncillaryChunk::ImageGamma)
+			&& icc_profile.is_none()
+			&& !is_lab_pbr_texture;
+		let bit_depth_reduction_dithering = if is_lab_pbr_texture {
+			BitDepthReductionDithering::None
+		} else {
+			self.optimization_settings.bit_depth_reduction_dithering
+		};
+		let mut bit_depth_reduced = false;
+		let mut first_pass_image = match &first_pass_png {
+			// 16-bit images that should be dithered need to be decoded with their full precision
+			Some(first_pass_png)
+				if bit_depth_reduction_dithering != BitDepthReductionDithering::None
+					&& image_processor::bit_depth(first_pass_png) == 16 =>
+			{
+				bit_depth_reduced = true;
+				ProcessedImage::read_16_bit(
+					&**first_pass_png,
+					apply_gamma_correction,
+					bit_depth_reduction_dithering
+				)?
+			}
+			Some(first_pass_png) => ProcessedImage::read(&**first_pass_png, apply_gamma_correction)?,
+			None => {
+				ProcessedImage::read_webp(src, self.optimization_settings.maximum_width_and_height)?
+			}
+		};
+		if is_cgbi_png {
+			first_pass_image.convert_cgbi_pixels()?;
+		}
+		let color_profile_converted = match &icc_profile {
+			Some(icc_profile) => first_pass_image.convert_to_srgb(icc_profile)?,
+			None => false
+		};
+
+		// Reserve the memory that the next passes are estimated to use, which may wait for other
+		// PNG files to finish being optimized. The reservation is released when this method returns
+		let _memory_reservation = MEMORY_BUDGET.reserve(
+			(first_pass_image.width().get() as usize * first_pass_image.height().get() as usize * 4)
+				.saturating_mul(ESTIMATED_DECODED_PIXEL_COPIES),
+			self.optimization_settings.optimization_memory_limit
+		);
+
+		// Animated textures are vertical strips of frames, so their size limits apply to each
+		// frame. If the frame size is declared in their animation metadata file, resizing them
+		// would make that file no longer match the texture
+		let animation_frame_size = self.optimization_settings.animation_frame_size;
+		let can_resize = !is_auxiliary_shader_target_texture
+			&& !is_lab_pbr_texture
+			&& !animation_frame_size.is_some_and(AnimationFrameSize::is_explicit);
+
+		// Downscaling pass: if the image frames exceed the maximum texture size, replace it with
+		// a downscaled version. Because downscaling is requested explicitly, the downscaled
+		// image is always used, even if it turns out to be bigger than the first pass PNG
+		let mut downscaled = false;
+		if let Some(maximum_texture_size) = self.optimization_settings.maximum_texture_size
+			&& can_resize
+			&& let Some(downscaled_image) = first_pass_image.downscale(
+				maximum_texture_size,
+				largest_frame_dimension(&first_pass_image, animation_frame_size),
+				self.optimization_settings.texture_downscaling_filter
+			)? {
+			first_pass_image = downscaled_image;
+			downscaled = true;
+		}
+
+		// Mipmap level check pass: report textures whose dimensions would make Minecraft reduce
+		// the mipmap levels of their atlas below the required ones, and fix them if desired.
+		// Like downscaling, fixing them is requested explicitly, so the fixed image is always used
+		let required_mipmap_levels = cmp::min(
+			self.optimization_settings.required_mipmap_levels,
+			MAXIMUM_MIPMAP_LEVELS
+		);
+		let mut mipmap_levels_fixed = false;
+		let mipmap_levels = frame_mipmap_levels(&first_pass_image, animation_frame_size);
+		if self.optimization_settings.is_atlas_texture
+			&& !is_auxiliary_shader_target_texture
+			&& !is_lab_pbr_texture
+			&& mipmap_levels < required_mipmap_levels as u32
+		{
+			let (width, height) = (first_pass_image.width(), first_pass_image.height());
+
+			// Padding or resizing a strip of animation frames would misalign its frames, so
+			// animated textures are only reported
+			optimization_notes.push(
+				if animation_frame_size.is_none()
+					&& let Some(fixed_image) = first_pass_image.fix_mipmap_levels(
+						required_mipmap_levels,
+						self.optimization_settings.mipmap_level_reduction_fix,
+						self.optimization_settings.texture_downscaling_filter
+					)? {
+					let note = format!(
+						"{width}x{height} size changed to {}x{} \
+						to allow {required_mipmap_levels} mipmap levels",
+						fixed_image.width(),
+						fixed_image.height()
+					);
+					first_pass_image = fixed_image;
+					mipmap_levels_fixed = true;
+
+					note
+				} else if let Some(animation_frame_size) = animation_frame_size {
+					let (frame_width, frame_height) =
+						animation_frame_size.frame_dimensions(width, height);
+
+					format!(
+						"{frame_width}x{frame_height} animation frame size limits mipmap levels \
+						to {mipmap_levels}"
+					)
+				} else {
+					format!("{width}x{height} size limits mipmap levels to {mipmap_levels}")
+				}
+			);
+		}
+
+		// Transparent pixel color normalization pass: if a normalization strategy other than
+		// letting the optimizer choose was requested, set the colors of transparent pixels now.
+		// The optimizer must keep these colors, and its result must be used
+		let transparent_pixel_colors_normalized = can_change_transparent_pixel_colors
+			&& first_pass_image.normalize_transparent_pixel_colors(
+				self.optimization_settings
+					.transparent_pixel_color_normalization
+			)?;
 
 		// Second pass: downsize most textures that consist of a single color to the minimum
 		// size that does not cause side effects in Minecraft. If that can't be done, then
@@ -115,7 +373,8 @@ impl Decoder for OptimizerDecoder {
 		// both of these operations may change the color type (i.e., turn an indexed image to RGBA,
 		// or vice versa)
 		let second_pass_image = match (can_change_color_type
-			&& self.optimization_settings.downsize_if_single_color)
+			&& self.optimization_settings.downsize_if_single_color
+			&& !is_lab_pbr_texture)
 			.then(|| {
 				first_pass_image
 					.downsize_single_color(can_change_color_type, is_auxiliary_shader_target_texture)
@@ -134,6 +393,7 @@ impl Decoder for OptimizerDecoder {
 				// The image could not be downsized, but it could be quantized
 				first_pass_image.quantize_color(
 					color_quantization_target,
+					self.optimization_settings.image_quantization_quality,
 					self.optimization_settings
 						.color_quantization_dithering_level
 						.into()
@@ -143,25 +403,47 @@ impl Decoder for OptimizerDecoder {
 			None => None
 		};
 
+		// Keep a copy of the pixels before the third pass consumes them, in case quality has to
+		// be traded for size to make the image fit in its size budget
+		let size_budget_image = self
+			.optimization_settings
+			.maximum_file_size
+			.map(|_| first_pass_image.decoded_copy())
+			.transpose()?
+			.flatten();
+
 		let quantization_quality = second_pass_image
 			.as_ref()
 			.and_then(|image| image.quantization_quality());
 		let have_second_pass_result = second_pass_image.is_some();
-		let must_use_second_pass_result =
-			color_quantization_target.is_quantization_required() && quantization_quality.is_some();
+		// The pixels of CgBI PNGs and PNGs with a color profile were converted, and the pixels of
+		// 16-bit PNGs were dithered, so the first pass PNG does not have them
+		let must_use_third_pass = is_cgbi_png
+			|| color_profile_converted
+			|| bit_depth_reduced
+			|| downscaled
+			|| mipmap_levels_fixed
+			|| transparent_pixel_colors_normalized
+			|| color_quantization_target.is_quantization_required() && quantization_quality.is_some();
 
 		// Third pass: complete lossless optimization of the second pass PNG, if quantization
 		// or downsizing was done, or else the first pass PNG
-		let third_pass_png = second_pass_image
-			.unwrap_or(first_pass_image)
-			.visually_lossless_optimize(
+		let optimize = |image: ProcessedImage<_>| {
+			let mut png = image.visually_lossless_optimize(
 				self.optimization_settings.image_data_compression_iterations,
+				self.optimization_settings.image_data_filter_strategy,
 				can_change_color_type,
-				can_change_transparent_pixel_colors,
+				can_change_transparent_pixel_colors && !transparent_pixel_colors_normalized,
 				!self
 					.optimization_settings
 					.working_around_grayscale_reduction_quirk
+					&& !preserved_ancillary_chunks.contains(PngAncillaryChunk::IccProfile)
 			)?;
+			image_processor::insert_chunks(&mut png, &preserved_chunks);
+
+			Ok::<_, ImageProcessingError>(png)
+		};
+		let third_pass_png = optimize(second_pass_image.unwrap_or(first_pass_image))?;
 
 		// Now decide the result of what pass to keep. The third pass is either an optimized
 		// representation of the first pass, or an optimized representation of the second
@@ -196,8 +478,11 @@ impl Decoder for OptimizerDecoder {
 		// size when quantization is not forced and executing each pass a single time, explicit
 		// user configuration of the quantization parameters may be needed to achieve the most
 		// optimal PNG we are capable of. Luckily, the points above are fairly rare
-		let (mut optimized_png, optimization_strategy_message) =
-			if !must_use_second_pass_result && first_pass_png.len() < third_pass_png.len() {
+		let smaller_first_pass_png = first_pass_png.filter(|first_pass_png| {
+			!must_use_third_pass && first_pass_png.len() < third_pass_png.len()
+		});
+		let (mut optimized_png, mut optimization_strategy_message) =
+			if let Some(first_pass_png) = smaller_first_pass_png {
 				(
 					first_pass_png,
 					Cow::Borrowed(
@@ -212,7 +497,7 @@ impl Decoder for OptimizerDecoder {
 						Cow::Owned(format!(
 							"Optimized with {quantization_quality}% quality color quantization"
 						))
-					} else if have_second_pass_result {
+					} else if have_second_pass_result || downscaled {
 						Cow::Borrowed("Downsized and optimized")
 					} else if can_change_transparent_pixel_colors {
 						Cow::Borrowed("Optimized with no visible color loss")
@@ -222,6 +507,42 @@ impl Decoder for OptimizerDecoder {
 				)
 			};
 
+		// Size budget pass: if the optimized PNG does not fit in its size budget, trade quality
+		// for size until it does, and report that the budget could not be met losslessly
+		if let Some(maximum_file_size) = self.optimization_settings.maximum_file_size
+			&& optimized_png.len() > maximum_file_size.get() as usize
+			&& let Some(size_budget_image) = size_budget_image
+		{
+			if let Some((size_budget_png, size_budget_message)) = fit_in_size_budget(
+				size_budget_image,
+				maximum_file_size.get() as usize,
+				&self.optimization_settings,
+				can_change_color_type && can_change_transparent_pixel_colors,
+				can_resize,
+				optimize
+			)? {
+				optimized_png = size_budget_png;
+				optimization_strategy_message = Cow::Owned(size_budget_message);
+				optimization_notes.push(format!(
+					"The {maximum_file_size} byte size budget could not be met losslessly"
+				));
+			} else {
+				optimization_notes.push(format!(
+					"The {maximum_file_size} byte size budget could not be met"
+				));
+			}
+		}
+
+		// Append the optimization notes, if any, so that the affected files are reported
+		let optimization_strategy_message = if optimization_notes.is_empty() {
+			optimization_strategy_message
+		} else {
+			Cow::Owned(format!(
+				"{optimization_strategy_message}. {}",
+				optimization_notes.join(". ")
+			))
+		};
+
 		// Final pass: apply obfuscation to the optimized result if possible and desired
 		if self
 			.optimization_settings
@@ -235,6 +556,135 @@ impl Decoder for OptimizerDecoder {
 	}
 }
 
+/// Makes the specified image fit in a size budget, in bytes, by color quantizing it with
+/// progressively lower qualities and downscaling it by progressively greater powers of two
+/// factors, as allowed, optimizing every attempt with the specified function. The image is
+/// assumed to not fit in the budget when optimized losslessly at its current size.
+///
+/// Returns the first optimized PNG that fits in the budget, along with a message that describes
+/// how it was obtained, or `None` if the budget could not be met.
+fn fit_in_size_budget<R: Read>(
+	mut image: ProcessedImage<R>,
+	size_budget: usize,
+	optimization_settings: &PngFileOptions,
+	can_quantize: bool,
+	can_downscale: bool,
+	optimize: impl Fn(ProcessedImage<R>) -> Result<Vec<u8>, ImageProcessingError>
+) -> Result<Option<(Vec<u8>, String)>, ImageProcessingError> {
+	// An explicit size budget warrants quantization even if it was not asked for
+	let quantization_target = if optimization_settings
+		.color_quantization_target
+		.should_quantize()
+	{
+		optimization_settings.color_quantization_target
+	} else {
+		ColorQuantizationTarget::EightBitDepth
+	};
+	let mut downscaled = false;
+
+	loop {
+		let size = format!("{}x{}", image.width(), image.height());
+
+		// The image at its original size was already optimized losslessly
+		if downscaled && let Some(image_copy) = image.decoded_copy()? {
+			let png = optimize(image_copy)?;
+			if png.len() <= size_budget {
+				return Ok(Some((
+					png,
+					format!("Downsized to {size} and optimized to fit in the size budget")
+				)));
+			}
+		}
+
+		if can_quantize
+			&& image.width().get() as u32 * image.height().get() as u32
+				> quantization_target.max_colors()
+		{
+			for maximum_quality in SIZE_BUDGET_QUANTIZATION_QUALITIES {
+				let Some(quantized_image) = image.quantize_color(
+					quantization_target,
+					QuantizationQualityRange::try_from([0, maximum_quality]).unwrap(),
+					optimization_settings
+						.color_quantization_dithering_level
+						.into()
+				)?
+				else {
+					break;
+				};
+				let quantization_quality = quantized_image
+					.quantization_quality()
+					.unwrap_or(maximum_quality);
+
+				let png = optimize(quantized_image)?;
+				if png.len() <= size_budget {
+					let quantization_message = format!(
+						"{quantization_quality}% quality color quantization to fit in the size budget"
+					);
+
+					return Ok(Some((
+						png,
+						if downscaled {
+							format!("Downsized to {size} and optimized with {quantization_message}")
+						} else {
+							format!("Optimized with {quantization_message}")
+						}
+					)));
+				}
+			}
+		}
+
+		// Halve the dimensions of the image for the next attempt, if possible
+		let largest_dimension = cmp::max(image.width(), image.height()).get();
+		if !can_downscale || largest_dimension == 1 {
+			return Ok(None);
+		}
+
+		match image.downscale(
+			NonZeroU16::new(largest_dimension / 2).unwrap(),
+			largest_dimension as u32,
+			optimization_settings.texture_downscaling_filter
+		)? {
+			Some(downscaled_image) => image = downscaled_image,
+			None => return Ok(None)
+		}
+		downscaled = true;
+	}
+}
+
+/// Returns the width and height of the frames of the specified image, which has frames of the
+/// specified size if it is an animated texture, or a single frame otherwise.
+fn frame_dimensions<R: Read>(
+	image: &ProcessedImage<R>,
+	animation_frame_size: Option<AnimationFrameSize>
+) -> (u32, u32) {
+	animation_frame_size.map_or(
+		(image.width().get() as u32, image.height().get() as u32),
+		|animation_frame_size| animation_frame_size.frame_dimensions(image.width(), image.height())
+	)
+}
+
+/// Returns the largest dimension of the frames of the specified image, as computed by
+/// [`frame_dimensions`].
+fn largest_frame_dimension<R: Read>(
+	image: &ProcessedImage<R>,
+	animation_frame_size: Option<AnimationFrameSize>
+) -> u32 {
+	let (frame_width, frame_height) = frame_dimensions(image, animation_frame_size);
+
+	cmp::max(frame_width, frame_height)
+}
+
+/// Returns the maximum number of mipmap levels that the frames of the specified image allow
+/// Minecraft to generate when stitched to a texture atlas, as computed by [`frame_dimensions`].
+fn frame_mipmap_levels<R: Read>(
+	image: &ProcessedImage<R>,
+	animation_frame_size: Option<AnimationFrameSize>
+) -> u32 {
+	let (frame_width, frame_height) = frame_dimensions(image, animation_frame_size);
+
+	cmp::min(frame_width.trailing_zeros(), frame_height.trailing_zeros())
+}
+
 impl<T: AsyncRead + Send + Unpin + 'static> PackFile for PngFile<T> {
 	type ByteChunkType = Vec<u8>;
 	type OptimizationError = OptimizationError;
@@ -267,7 +717,12 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFile for PngFile<T> {
 			return true;
 		}
 
-		matches!(self.asset_type, PackFileAssetType::GenericTexture)
+		matches!(
+			self.asset_type,
+			PackFileAssetType::GenericTexture
+				| PackFileAssetType::WebpTexture
+				| PackFileAssetType::LabPbrTexture
+		)
 	}
 }
 
@@ -1,18 +1,26 @@
 //! Contains routines and data types for processing single images.
 
-use crate::config::ColorQuantizationTarget;
+use crate::config::{
+	BitDepthReductionDithering, ColorQuantizationTarget, ImageDataFilterStrategy,
+	MipmapLevelReductionFix, PngAncillaryChunk, QuantizationQualityRange, TextureDownscalingFilter,
+	TransparentPixelColorNormalization
+};
 use crate::zopfli_iterations_time_model::ZopfliIterationsTimeModel;
 use bytes::BytesMut;
+use enumset::EnumSet;
+use flate2::read::ZlibDecoder;
 use imagequant::{Attributes, liq_error};
 use itertools::Itertools;
 use obfstr::random;
 use oxipng::{
 	BitDepth, ColorType, Deflater, FilterStrategy, Options, StripChunks, ZopfliOptions, indexset
 };
-use rgb::{AsPixels, RGBA8};
+use rgb::{AsPixels, FromSlice, RGBA8};
 use spng::{ContextFlags, DecodeFlags, Format};
 use std::cmp;
-use std::io::Read;
+use std::f32::consts::PI;
+use std::io::{Cursor, Read};
+use std::mem;
 use std::num::{NonZeroU16, NonZeroU64};
 use std::time::Duration;
 use thiserror::Error;
@@ -23,22 +31,46 @@ pub enum ImageProcessingError {
 	StripValidateError(&'static str),
 	#[error("PNG decode error: {0}")]
 	PngDecoding(#[from] spng::Error),
+	#[error("WebP decode error: {0}")]
+	WebPDecoding(#[from] image_webp::DecodingError),
 	#[error("OxiPNG optimization error: {0}")]
 	OxipngError(#[from] oxipng::PngError),
 	#[error("libimagequant optimization error: {0}")]
 	LiqError(#[from] liq_error)
 }
 
+/// The maximum size of the decompressed ICC profiles that will be read from PNG files, which
+/// is well above the size of any sensible RGB profile.
+const MAXIMUM_ICC_PROFILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// The 8x8 Bayer matrix used for ordered dithering, whose elements are the thresholds of each
+/// pixel position, scaled to the `0-63` range.
+const BAYER_MATRIX: [[u8; 8]; 8] = [
+	[0, 32, 8, 40, 2, 34, 10, 42],
+	[48, 16, 56, 24, 50, 18, 58, 26],
+	[12, 44, 4, 36, 14, 46, 6, 38],
+	[60, 28, 52, 20, 62, 30, 54, 22],
+	[3, 35, 11, 43, 1, 33, 9, 41],
+	[51, 19, 59, 27, 49, 17, 57, 25],
+	[15, 47, 7, 39, 13, 45, 5, 37],
+	[63, 31, 55, 23, 61, 29, 53, 21]
+];
+
 /// Performs a first fast optimization to an input PNG image: remove non-critical chunks
-/// that will not be parsed by the expected downstream decoders. This can never increase
-/// the input PNG image size, only decrease or maintain it.
+/// that will not be parsed by the expected downstream decoders, except the specified
+/// ancillary chunks to preserve. This can never increase the input PNG image size, only
+/// decrease or maintain it.
 ///
-/// It also validates that neither image dimension exceeds the specified threshold.
+/// It also validates that neither image dimension exceeds the specified threshold. Besides
+/// the stripped PNG, the preserved ancillary chunks are returned, ready to be inserted in
+/// other PNG datastreams with [`insert_chunks`].
 pub fn strip_unnecessary_chunks(
 	input_png: BytesMut,
-	maximum_dimension: NonZeroU16
-) -> Result<Vec<u8>, ImageProcessingError> {
+	maximum_dimension: NonZeroU16,
+	preserved_ancillary_chunks: EnumSet<PngAncillaryChunk>
+) -> Result<(Vec<u8>, Vec<u8>), ImageProcessingError> {
 	let mut stripped_png = Vec::with_capacity(input_png.len());
+	let mut preserved_chunks = Vec::new();
 
 	// Helper macro to avoid non-panicking bounds checking verbosity
 	macro_rules! get_or_err {
@@ -78,14 +110,18 @@ pub fn strip_unnecessary_chunks(
 		let data_length =
 			u32::from_be_bytes(data_length_and_chunk_type[..4].try_into().unwrap()) as usize;
 		let chunk_type = &data_length_and_chunk_type[4..];
+		let is_preserved_ancillary_chunk = preserved_ancillary_chunks
+			.iter()
+			.any(|ancillary_chunk| ancillary_chunk.chunk_type() == chunk_type);
 
-		if matches!(
-			chunk_type,
-			// gAMA may be used by spng later on. PLTE is necessary for palette color images,
-			// which may have its transparency stored in a tRNS chunk. IHDR, IDAT and IEND
-			// are critical and must appear
-			b"IHDR" | b"IDAT" | b"IEND" | b"PLTE" | b"tRNS" | b"gAMA"
-		) {
+		if is_preserved_ancillary_chunk
+			|| matches!(
+				chunk_type,
+				// gAMA may be used by spng later on. PLTE is necessary for palette color images,
+				// which may have its transparency stored in a tRNS chunk. IHDR, IDAT and IEND
+				// are critical and must appear
+				b"IHDR" | b"IDAT" | b"IEND" | b"PLTE" | b"tRNS" | b"gAMA"
+			) {
 			let chunk_data = get_or_err!(i + 8..i + 8 + data_length);
 			let chunk_crc = get_or_err!(i + 8 + data_length..i + 8 + data_length + 4);
 
@@ -110,6 +146,12 @@ pub fn strip_unnecessary_chunks(
 			stripped_png.extend_from_slice(data_length_and_chunk_type);
 			stripped_png.extend_from_slice(chunk_data);
 			stripped_png.extend_from_slice(chunk_crc);
+
+			if is_preserved_ancillary_chunk {
+				preserved_chunks.extend_from_slice(data_length_and_chunk_type);
+				preserved_chunks.extend_from_slice(chunk_data);
+				preserved_chunks.extend_from_slice(chunk_crc);
+			}
 		}
 
 		i += 8 + data_length + 4;
@@ -120,7 +162,61 @@ pub fn strip_unnecessary_chunks(
 		}
 	}
 
-	Ok(stripped_png)
+	Ok((stripped_png, preserved_chunks))
+}
+
+/// Returns the decompressed ICC profile embedded in the specified PNG datastream, if any. `None`
+/// is returned if the datastream has no ICC profile chunk, the profile can't be decompressed,
+/// or the datastream has an sRGB chunk, which means that its pixels are already in the sRGB
+/// color space.
+pub fn icc_profile(png: &[u8]) -> Option<Vec<u8>> {
+	let mut icc_profile_chunk_data = None;
+
+	let mut i = 8;
+	while let Some(data_length_and_chunk_type) = png.get(i..i + 8) {
+		let data_length =
+			u32::from_be_bytes(data_length_and_chunk_type[..4].try_into().unwrap()) as usize;
+
+		match &data_length_and_chunk_type[4..] {
+			b"iCCP" => icc_profile_chunk_data = png.get(i + 8..i + 8 + data_length),
+			b"sRGB" => return None,
+			// Color space chunks must appear before image data
+			b"IDAT" | b"IEND" => break,
+			_ => {}
+		}
+
+		i += 8 + data_length + 4;
+	}
+
+	// The chunk data is a null-terminated profile name, a compression method byte that is
+	// always zero (zlib), and the compressed profile
+	let icc_profile_chunk_data = icc_profile_chunk_data?;
+	let name_length = icc_profile_chunk_data.iter().position(|byte| *byte == 0)?;
+	let compressed_profile = icc_profile_chunk_data.get(name_length + 2..)?;
+
+	let mut icc_profile = Vec::new();
+	ZlibDecoder::new(compressed_profile)
+		.take(MAXIMUM_ICC_PROFILE_SIZE)
+		.read_to_end(&mut icc_profile)
+		.ok()?;
+
+	Some(icc_profile)
+}
+
+/// Returns the bit depth of the specified PNG datastream, which must start with its header
+/// chunk, as the PNG datastreams returned by [`strip_unnecessary_chunks`] do.
+pub fn bit_depth(png: &[u8]) -> u8 {
+	png[24]
+}
+
+/// Inserts the specified chunks, as returned by [`strip_unnecessary_chunks`], right after the
+/// header chunk of the given known-valid PNG datastream. Every ancillary chunk that can be
+/// preserved is allowed to appear at that position.
+pub fn insert_chunks(png: &mut Vec<u8>, chunks: &[u8]) {
+	// The signature and IHDR chunk have a fixed length
+	const IHDR_END_OFFSET: usize = 8 + 4 + 4 + 13 + 4;
+
+	png.splice(IHDR_END_OFFSET..IHDR_END_OFFSET, chunks.iter().copied());
 }
 
 /// Obfuscates the given known-valid PNG datastream in place to make it less likely to be readable
@@ -228,10 +324,17 @@ impl<R: Read> ProcessedImage<R> {
 	/// Creates a new [`ProcessedImage`] by reading it from the PNG at the specified reader.
 	///
 	/// The PNG file will be validated, but not decoded. This method assumes that the width
-	/// and height of the image fit in a 16-bit unsigned integer.
-	pub fn read(reader: R) -> Result<Self, ImageProcessingError> {
+	/// and height of the image fit in a 16-bit unsigned integer. If gamma correction is
+	/// requested, the pixels will be normalized to sRGB according to the image gamma.
+	pub fn read(reader: R, apply_gamma_correction: bool) -> Result<Self, ImageProcessingError> {
+		let decode_flags = if apply_gamma_correction {
+			DecodeFlags::GAMMA | DecodeFlags::TRANSPARENCY
+		} else {
+			DecodeFlags::TRANSPARENCY
+		};
+
 		spng::Decoder::new(reader)
-			.with_decode_flags(DecodeFlags::GAMMA | DecodeFlags::TRANSPARENCY) // Apply gamma correction to normalize to sRGB
+			.with_decode_flags(decode_flags)
 			.with_context_flags(ContextFlags::IGNORE_ADLER32)
 			.with_output_format(Format::Rgba8) // The RGBA8 format is necessary for PixelArray
 			.read_info()
@@ -241,6 +344,88 @@ impl<R: Read> ProcessedImage<R> {
 			)
 	}
 
+	/// Creates a new [`ProcessedImage`] by decoding the PNG image with 16 bits per channel at the
+	/// specified reader, and reducing its pixels to 8 bits per channel using the specified
+	/// dithering method. Like [`read`](Self::read), this method assumes that the width and
+	/// height of the image fit in a 16-bit unsigned integer.
+	pub fn read_16_bit(
+		reader: R,
+		apply_gamma_correction: bool,
+		dithering: BitDepthReductionDithering
+	) -> Result<Self, ImageProcessingError> {
+		let decode_flags = if apply_gamma_correction {
+			DecodeFlags::GAMMA | DecodeFlags::TRANSPARENCY
+		} else {
+			DecodeFlags::TRANSPARENCY
+		};
+
+		let mut png_reader = spng::Decoder::new(reader)
+			.with_decode_flags(decode_flags)
+			.with_context_flags(ContextFlags::IGNORE_ADLER32)
+			.with_output_format(Format::Rgba16)
+			.read_info()?;
+
+		let mut buf = vec![0; png_reader.output_buffer_size()];
+		png_reader.next_frame(&mut buf)?;
+
+		// The samples of the RGBA16 format are in host byte order
+		let samples = buf
+			.chunks_exact(2)
+			.map(|sample| u16::from_ne_bytes([sample[0], sample[1]]))
+			.collect::<Vec<_>>();
+		let (width, height) = (png_reader.info().width, png_reader.info().height);
+
+		Ok(PixelArray {
+			width: NonZeroU16::new(width as u16).unwrap(),
+			height: NonZeroU16::new(height as u16).unwrap(),
+			buf: reduce_bit_depth(&samples, width as usize, dithering)
+		}
+		.into())
+	}
+
+	/// Creates a new [`ProcessedImage`] by decoding the WebP image at the specified buffer.
+	///
+	/// Unlike PNG images, WebP images are decoded right away. The image dimensions are
+	/// validated to not exceed the specified threshold before decoding them. For animated
+	/// WebP images, only the first frame is decoded.
+	pub fn read_webp(
+		webp: &[u8],
+		maximum_dimension: NonZeroU16
+	) -> Result<Self, ImageProcessingError> {
+		let mut webp_decoder = image_webp::WebPDecoder::new(Cursor::new(webp))?;
+
+		let (width, height) = webp_decoder.dimensions();
+		if width > maximum_dimension.get() as u32 || height > maximum_dimension.get() as u32 {
+			return Err(ImageProcessingError::StripValidateError(
+				"The texture width or height exceeds the configured maximum size. \
+				More information: <https://packsquash.aylas.org/links/Too-big-PNG-help>"
+			));
+		}
+
+		let mut buf = vec![
+			0;
+			webp_decoder
+				.output_buffer_size()
+				.ok_or(image_webp::DecodingError::ImageTooLarge)?
+		];
+		webp_decoder.read_image(&mut buf)?;
+
+		// Images without an alpha channel are decoded to RGB8, but PixelArray needs RGBA8
+		if !webp_decoder.has_alpha() {
+			buf = buf
+				.chunks_exact(3)
+				.flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX])
+				.collect();
+		}
+
+		Ok(PixelArray {
+			width: NonZeroU16::new(width as u16).unwrap(),
+			height: NonZeroU16::new(height as u16).unwrap(),
+			buf
+		}
+		.into())
+	}
+
 	/// Returns the width of this image, in pixels.
 	pub fn width(&self) -> NonZeroU16 {
 		match self {
@@ -322,10 +507,17 @@ impl<R: Read> ProcessedImage<R> {
 	/// with another optimizer, this may be a size-increasing operation, depending on the
 	/// dithering pattern compressibility and how optimal the input image already was.
 	///
-	/// Returns `Ok(None)` if the image was already quantized.
+	/// The quantization is aborted if the resulting image quality, in the `0-100` range,
+	/// would be lower than the minimum of the specified quality range. The quantizer will
+	/// try to use fewer colors than the maximum allowed by the quantization target if that
+	/// suffices to reach the maximum of that range.
+	///
+	/// Returns `Ok(None)` if the image was already quantized, or if the minimum quality
+	/// could not be achieved.
 	pub fn quantize_color(
 		&mut self,
 		quantization_target: ColorQuantizationTarget,
+		quality_range: QuantizationQualityRange,
 		dithering_level: f32
 	) -> Result<Option<Self>, ImageProcessingError> {
 		let width = self.width();
@@ -335,6 +527,7 @@ impl<R: Read> ProcessedImage<R> {
 		let mut quantization_attributes = Attributes::new();
 		quantization_attributes.set_max_colors(quantization_target.max_colors())?;
 		quantization_attributes.set_speed(2)?;
+		quantization_attributes.set_quality(quality_range.minimum(), quality_range.maximum())?;
 
 		let bitmap = if let Some(pixel_array) = self.as_pixel_array()? {
 			pixel_array.as_slice()
@@ -351,7 +544,12 @@ impl<R: Read> ProcessedImage<R> {
 		)?;
 
 		// Configure the quantization operation for the image from its attributes
-		let mut quantization_result = quantization_attributes.quantize(&mut iq_image)?;
+		let mut quantization_result = match quantization_attributes.quantize(&mut iq_image) {
+			Ok(quantization_result) => quantization_result,
+			// The image can't be quantized with the minimum quality, so don't quantize it
+			Err(liq_error::QualityTooLow) => return Ok(None),
+			Err(err) => return Err(err.into())
+		};
 		quantization_result.set_dithering_level(dithering_level)?;
 
 		// Quantize the image
@@ -366,6 +564,234 @@ impl<R: Read> ProcessedImage<R> {
 		}))
 	}
 
+	/// Downscales this image by the smallest power of two factor that makes the specified largest
+	/// dimension of its frames not greater than the specified maximum size, using the specified
+	/// resampling filter, and returns the resulting image. Images that are not animated have a
+	/// single frame as big as the image. Downscaling by powers of two keeps the boundaries between
+	/// animation frames and mipmap levels intact for textures with power of two dimensions.
+	///
+	/// Returns `Ok(None)` if the frames do not exceed the maximum size, or if the image was color
+	/// quantized.
+	pub fn downscale(
+		&mut self,
+		maximum_size: NonZeroU16,
+		largest_frame_dimension: u32,
+		filter: TextureDownscalingFilter
+	) -> Result<Option<Self>, ImageProcessingError> {
+		if largest_frame_dimension <= maximum_size.get() as u32 {
+			return Ok(None);
+		}
+
+		let mut scale_shift = 1;
+		while largest_frame_dimension >> scale_shift > maximum_size.get() as u32 {
+			scale_shift += 1;
+		}
+
+		Ok(self.as_pixel_array()?.map(|pixel_array| {
+			let new_width = cmp::max(pixel_array.width().get() as usize >> scale_shift, 1);
+			let new_height = cmp::max(pixel_array.height().get() as usize >> scale_shift, 1);
+
+			PixelArray {
+				width: NonZeroU16::new(new_width as u16).unwrap(),
+				height: NonZeroU16::new(new_height as u16).unwrap(),
+				buf: resample(pixel_array, new_width, new_height, filter)
+			}
+			.into()
+		}))
+	}
+
+	/// Returns a copy of this image with its pixels decoded, which can be processed further
+	/// after this image is consumed.
+	///
+	/// Returns `Ok(None)` if the image was color quantized.
+	pub fn decoded_copy(&mut self) -> Result<Option<Self>, ImageProcessingError> {
+		Ok(self.as_pixel_array()?.map(|pixel_array| {
+			PixelArray {
+				width: pixel_array.width,
+				height: pixel_array.height,
+				buf: pixel_array.buf.clone()
+			}
+			.into()
+		}))
+	}
+
+	/// Returns the number of mipmap levels that Minecraft can generate for this image when it is
+	/// stitched to a texture atlas, which is limited by the greatest power of two that divides
+	/// both of its dimensions.
+	pub fn mipmap_levels(&self) -> u32 {
+		cmp::min(
+			self.width().trailing_zeros(),
+			self.height().trailing_zeros()
+		)
+	}
+
+	/// Pads or resizes this image to the closest dimensions that are multiples of 2 raised to the
+	/// specified mipmap levels, as determined by the specified fix, and returns the resulting image.
+	/// Resizing uses the specified resampling filter.
+	///
+	/// Returns `Ok(None)` if the image already allows that many mipmap levels, the fix is to leave
+	/// the image as-is, the fixed dimensions would exceed the maximum image size, or the image was
+	/// color quantized.
+	pub fn fix_mipmap_levels(
+		&mut self,
+		mipmap_levels: u8,
+		fix: MipmapLevelReductionFix,
+		filter: TextureDownscalingFilter
+	) -> Result<Option<Self>, ImageProcessingError> {
+		if self.mipmap_levels() >= mipmap_levels as u32 || fix == MipmapLevelReductionFix::None {
+			return Ok(None);
+		}
+
+		let alignment = 1u32 << mipmap_levels;
+		let fixed_dimension = |dimension: NonZeroU16| {
+			let dimension = dimension.get() as u32;
+			let multiple = match fix {
+				MipmapLevelReductionFix::None => unreachable!(),
+				MipmapLevelReductionFix::Pad => dimension.div_ceil(alignment),
+				MipmapLevelReductionFix::Resize => {
+					cmp::max((dimension + alignment / 2) / alignment, 1)
+				}
+			};
+
+			u16::try_from(multiple * alignment)
+				.ok()
+				.and_then(NonZeroU16::new)
+		};
+
+		let (Some(new_width), Some(new_height)) = (
+			fixed_dimension(self.width()),
+			fixed_dimension(self.height())
+		) else {
+			return Ok(None);
+		};
+
+		Ok(self.as_pixel_array()?.map(|pixel_array| {
+			let width = pixel_array.width().get() as usize;
+			let new_width_bytes = new_width.get() as usize * 4;
+			let new_buf_length = new_width_bytes * new_height.get() as usize;
+
+			let buf = match fix {
+				MipmapLevelReductionFix::None => unreachable!(),
+				MipmapLevelReductionFix::Pad => {
+					let mut buf = Vec::with_capacity(new_buf_length);
+					for row in pixel_array.buf.chunks_exact(width * 4) {
+						buf.extend_from_slice(row);
+						buf.resize(buf.len() + new_width_bytes - row.len(), 0);
+					}
+					buf.resize(new_buf_length, 0);
+
+					buf
+				}
+				MipmapLevelReductionFix::Resize => resample(
+					pixel_array,
+					new_width.get() as usize,
+					new_height.get() as usize,
+					filter
+				)
+			};
+
+			PixelArray {
+				width: new_width,
+				height: new_height,
+				buf
+			}
+			.into()
+		}))
+	}
+
+	/// Converts the premultiplied BGRA pixels of an image decoded from a CgBI file, as generated
+	/// by Apple tools, to straight RGBA pixels.
+	pub fn convert_cgbi_pixels(&mut self) -> Result<(), ImageProcessingError> {
+		if let Some(pixel_array) = self.as_pixel_array()? {
+			for pixel in pixel_array.buf.as_rgba_mut() {
+				let alpha = pixel.a as u32;
+				let unpremultiply = |component: u8| {
+					if alpha > 0 {
+						cmp::min((component as u32 * 255 + alpha / 2) / alpha, 255) as u8
+					} else {
+						component
+					}
+				};
+
+				*pixel = RGBA8::new(
+					unpremultiply(pixel.b),
+					unpremultiply(pixel.g),
+					unpremultiply(pixel.r),
+					pixel.a
+				);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Converts the pixels of this image from the color space described by the specified ICC
+	/// profile to the sRGB color space.
+	///
+	/// Returns `Ok(false)` if no conversion was done, because the profile is invalid or does
+	/// not describe an RGB color space, or this image was color quantized.
+	pub fn convert_to_srgb(&mut self, icc_profile: &[u8]) -> Result<bool, ImageProcessingError> {
+		let Some(input_profile) = qcms::Profile::new_from_slice(icc_profile, false) else {
+			return Ok(false);
+		};
+
+		let mut srgb_profile = qcms::Profile::new_sRGB();
+		srgb_profile.precache_output_transform();
+
+		let Some(transform) = qcms::Transform::new(
+			&input_profile,
+			&srgb_profile,
+			qcms::DataType::RGBA8,
+			qcms::Intent::Perceptual
+		) else {
+			return Ok(false);
+		};
+
+		match self.as_pixel_array()? {
+			Some(pixel_array) => {
+				transform.apply(&mut pixel_array.buf);
+				Ok(true)
+			}
+			None => Ok(false)
+		}
+	}
+
+	/// Sets the colors of the completely transparent pixels of this image as specified by the
+	/// normalization strategy. This is a visually lossless operation.
+	///
+	/// Returns `Ok(false)` if no normalization was done, because the strategy is to let the
+	/// optimizer choose these colors, or this image was color quantized.
+	pub fn normalize_transparent_pixel_colors(
+		&mut self,
+		normalization: TransparentPixelColorNormalization
+	) -> Result<bool, ImageProcessingError> {
+		if normalization == TransparentPixelColorNormalization::Optimize {
+			return Ok(false);
+		}
+
+		let pixel_array = if let Some(pixel_array) = self.as_pixel_array()? {
+			pixel_array
+		} else {
+			return Ok(false);
+		};
+
+		let width = pixel_array.width().get() as usize;
+		let height = pixel_array.height().get() as usize;
+		let pixels = pixel_array.buf.as_rgba_mut();
+
+		match normalization {
+			TransparentPixelColorNormalization::Optimize => unreachable!(),
+			TransparentPixelColorNormalization::Constant([r, g, b]) => {
+				for pixel in pixels.iter_mut().filter(|pixel| pixel.a == 0) {
+					*pixel = RGBA8::new(r, g, b, 0);
+				}
+			}
+			TransparentPixelColorNormalization::Bleed => bleed_colors(pixels, width, height)
+		}
+
+		Ok(true)
+	}
+
 	/// Downsizes this image to the most space-efficient dimensions if it is single-color and such
 	/// resizing is not expected to impact how the pack looks, and returns the resulting image. This
 	/// may significantly decrease file sizes and improve client stitching performance and memory
@@ -432,6 +858,7 @@ impl<R: Read> ProcessedImage<R> {
 	pub fn visually_lossless_optimize(
 		self,
 		zopfli_compression_iterations: u8,
+		filter_strategy: ImageDataFilterStrategy,
 		can_change_color_type: bool,
 		can_change_transparent_pixel_colors: bool,
 		can_convert_to_grayscale: bool
@@ -441,16 +868,29 @@ impl<R: Read> ProcessedImage<R> {
 
 		let pixel_count = self.width().get() as u32 * self.height().get() as u32;
 
-		let filter_strategies = indexset! {
-			// The usually most promising filters. Only a single Zopfli run with
-			// the estimated best filter will be attempted
-			FilterStrategy::NONE,
-			FilterStrategy::Bigrams,
-			FilterStrategy::BigEnt,
-			FilterStrategy::MinSum,
-			FilterStrategy::Brute {
-				num_lines: 8,
-				level: 1
+		// Only a single Zopfli run with the estimated best filter will be attempted
+		let filter_strategies = match filter_strategy {
+			// The usually most promising filters
+			ImageDataFilterStrategy::Auto => indexset! {
+				FilterStrategy::NONE,
+				FilterStrategy::Bigrams,
+				FilterStrategy::BigEnt,
+				FilterStrategy::MinSum,
+				FilterStrategy::Brute {
+					num_lines: 8,
+					level: 1
+				}
+			},
+			ImageDataFilterStrategy::NoFiltering => indexset! { FilterStrategy::NONE },
+			// Unfiltered data rarely compresses well for continuous tone images
+			ImageDataFilterStrategy::Adaptive => indexset! {
+				FilterStrategy::Bigrams,
+				FilterStrategy::BigEnt,
+				FilterStrategy::MinSum,
+				FilterStrategy::Brute {
+					num_lines: 8,
+					level: 1
+				}
 			}
 		};
 
@@ -536,3 +976,290 @@ impl<R: Read> From<PixelArray> for ProcessedImage<R> {
 		Self::RGBA8 { pixels }
 	}
 }
+
+/// Reduces the specified RGBA16 samples of an image with the specified width to RGBA8 samples,
+/// dithering their color channels with the specified method.
+fn reduce_bit_depth(samples: &[u16], width: usize, dithering: BitDepthReductionDithering) -> Vec<u8> {
+	// Scale the samples to the 8-bit range, without rounding them
+	let to_8_bit_range = |sample: u16| sample as f32 / 257.0;
+	let quantize = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+
+	let mut reduced_samples = Vec::with_capacity(samples.len() / 2);
+	match dithering {
+		BitDepthReductionDithering::None => {
+			reduced_samples.extend(samples.iter().map(|sample| (sample >> 8) as u8));
+		}
+		BitDepthReductionDithering::Ordered => {
+			for (i, pixel) in samples.chunks_exact(4).enumerate() {
+				let (x, y) = (i % width, i / width);
+				let threshold = (BAYER_MATRIX[y % 8][x % 8] as f32 + 0.5) / 64.0 - 0.5;
+
+				reduced_samples.extend(
+					pixel[..3]
+						.iter()
+						.map(|sample| quantize(to_8_bit_range(*sample) + threshold))
+				);
+				reduced_samples.push(quantize(to_8_bit_range(pixel[3])));
+			}
+		}
+		BitDepthReductionDithering::FloydSteinberg => {
+			// The accumulated errors of the color channels of the pixels of the current and
+			// next rows
+			let mut row_errors = vec![0.0; width * 3];
+			let mut next_row_errors = vec![0.0; width * 3];
+
+			for row in samples.chunks_exact(width * 4) {
+				for (x, pixel) in row.chunks_exact(4).enumerate() {
+					for channel in 0..3 {
+						let value = to_8_bit_range(pixel[channel]) + row_errors[x * 3 + channel];
+						let reduced_value = quantize(value);
+						let error = value - reduced_value as f32;
+						reduced_samples.push(reduced_value);
+
+						if x + 1 < width {
+							row_errors[(x + 1) * 3 + channel] += error * 7.0 / 16.0;
+							next_row_errors[(x + 1) * 3 + channel] += error / 16.0;
+						}
+						if x > 0 {
+							next_row_errors[(x - 1) * 3 + channel] += error * 3.0 / 16.0;
+						}
+						next_row_errors[x * 3 + channel] += error * 5.0 / 16.0;
+					}
+					reduced_samples.push(quantize(to_8_bit_range(pixel[3])));
+				}
+
+				row_errors = mem::replace(&mut next_row_errors, vec![0.0; width * 3]);
+			}
+		}
+	}
+
+	reduced_samples
+}
+
+/// Sets the color of every completely transparent pixel to the average color of its closest
+/// visible pixels, in layers that grow outwards from the edges of the visible areas. Pixels in
+/// the same layer don't influence each other. Images without visible pixels are not changed.
+fn bleed_colors(pixels: &mut [RGBA8], width: usize, height: usize) {
+	let neighbors = |index: usize| {
+		let (x, y) = (index % width, index / width);
+
+		(-1..=1)
+			.cartesian_product(-1..=1)
+			.filter(|offset| *offset != (0, 0))
+			.filter_map(move |(x_offset, y_offset)| {
+				let neighbor_x = x.checked_add_signed(x_offset).filter(|x| *x < width)?;
+				let neighbor_y = y.checked_add_signed(y_offset).filter(|y| *y < height)?;
+
+				Some(neighbor_y * width + neighbor_x)
+			})
+	};
+
+	let mut has_color = pixels.iter().map(|pixel| pixel.a > 0).collect::<Vec<_>>();
+	let mut is_queued = has_color.clone();
+	let mut layer = (0..pixels.len())
+		.filter(|index| !has_color[*index] && neighbors(*index).any(|neighbor| has_color[neighbor]))
+		.collect::<Vec<_>>();
+	layer.iter().for_each(|index| is_queued[*index] = true);
+
+	while !layer.is_empty() {
+		let layer_colors = layer
+			.iter()
+			.map(|index| {
+				let (sum, count) = neighbors(*index)
+					.filter(|neighbor| has_color[*neighbor])
+					.fold(([0u32; 3], 0), |(mut sum, count), neighbor| {
+						let neighbor = pixels[neighbor];
+						sum[0] += neighbor.r as u32;
+						sum[1] += neighbor.g as u32;
+						sum[2] += neighbor.b as u32;
+						(sum, count + 1)
+					});
+
+				sum.map(|component_sum| ((component_sum + count / 2) / count) as u8)
+			})
+			.collect::<Vec<_>>();
+
+		let mut next_layer = Vec::new();
+		for (index, [r, g, b]) in layer.into_iter().zip(layer_colors) {
+			pixels[index] = RGBA8::new(r, g, b, 0);
+			has_color[index] = true;
+
+			for neighbor in neighbors(index) {
+				if !is_queued[neighbor] {
+					is_queued[neighbor] = true;
+					next_layer.push(neighbor);
+				}
+			}
+		}
+
+		layer = next_layer;
+	}
+}
+
+/// Resamples the specified pixel array to the specified dimensions using the specified filter,
+/// returning a byte buffer with the resampled pixels.
+fn resample(
+	pixel_array: &PixelArray,
+	new_width: usize,
+	new_height: usize,
+	filter: TextureDownscalingFilter
+) -> Vec<u8> {
+	let width = pixel_array.width().get() as usize;
+	let height = pixel_array.height().get() as usize;
+	let pixels = pixel_array.as_slice();
+
+	match filter {
+		TextureDownscalingFilter::Nearest => (0..new_height)
+			.flat_map(|y| {
+				let source_y = nearest_source_coordinate(y, height, new_height);
+				(0..new_width)
+					.map(move |x| (source_y, nearest_source_coordinate(x, width, new_width)))
+			})
+			.flat_map(|(source_y, source_x)| {
+				<RGBA8 as Into<[u8; 4]>>::into(pixels[source_y * width + source_x])
+			})
+			.collect(),
+		TextureDownscalingFilter::Bilinear | TextureDownscalingFilter::Lanczos3 => {
+			// Work with premultiplied alpha, so that the colors of transparent pixels
+			// do not bleed into the colors of visible pixels
+			let premultiplied_pixels = pixels
+				.iter()
+				.map(|pixel| {
+					let alpha = pixel.a as f32 / 255.0;
+					[
+						pixel.r as f32 * alpha,
+						pixel.g as f32 * alpha,
+						pixel.b as f32 * alpha,
+						pixel.a as f32
+					]
+				})
+				.collect::<Vec<_>>();
+
+			// Resample horizontally first, and then vertically
+			let horizontal_weights = resampling_weights(width, new_width, filter);
+			let vertical_weights = resampling_weights(height, new_height, filter);
+
+			let horizontally_resampled_pixels = (0..height)
+				.flat_map(|y| {
+					let row = &premultiplied_pixels[y * width..(y + 1) * width];
+					horizontal_weights
+						.iter()
+						.map(move |(start, weights)| weighted_sum(&row[*start..], weights, 1))
+				})
+				.collect::<Vec<_>>();
+
+			(0..new_height)
+				.flat_map(|y| {
+					let (start, weights) = &vertical_weights[y];
+					let horizontally_resampled_pixels = &horizontally_resampled_pixels;
+					(0..new_width).map(move |x| {
+						weighted_sum(
+							&horizontally_resampled_pixels[*start * new_width + x..],
+							weights,
+							new_width
+						)
+					})
+				})
+				.flat_map(|[r, g, b, a]| {
+					let alpha = a.clamp(0.0, 255.0);
+					let unpremultiply = |component: f32| {
+						if alpha > 0.0 {
+							(component * 255.0 / alpha).round().clamp(0.0, 255.0) as u8
+						} else {
+							0
+						}
+					};
+
+					[
+						unpremultiply(r),
+						unpremultiply(g),
+						unpremultiply(b),
+						alpha.round() as u8
+					]
+				})
+				.collect()
+		}
+	}
+}
+
+/// Returns the source coordinate whose pixel center is the closest to the center of the
+/// specified target coordinate, when scaling a dimension of `source_size` pixels to
+/// `target_size` pixels.
+fn nearest_source_coordinate(
+	target_coordinate: usize,
+	source_size: usize,
+	target_size: usize
+) -> usize {
+	cmp::min(
+		(2 * target_coordinate + 1) * source_size / (2 * target_size),
+		source_size - 1
+	)
+}
+
+/// Computes the normalized resampling filter weights for every target coordinate when scaling
+/// a dimension of `source_size` pixels to `target_size` pixels. Each target coordinate
+/// has associated the first source coordinate it samples and the weights of the consecutive
+/// source coordinates that contribute to it.
+fn resampling_weights(
+	source_size: usize,
+	target_size: usize,
+	filter: TextureDownscalingFilter
+) -> Vec<(usize, Vec<f32>)> {
+	let (kernel_radius, kernel): (f32, fn(f32) -> f32) = match filter {
+		TextureDownscalingFilter::Nearest | TextureDownscalingFilter::Bilinear => {
+			(1.0, |x| (1.0 - x.abs()).max(0.0))
+		}
+		TextureDownscalingFilter::Lanczos3 => (3.0, |x| {
+			if x == 0.0 {
+				1.0
+			} else if x.abs() < 3.0 {
+				let pi_x = PI * x;
+				3.0 * pi_x.sin() * (pi_x / 3.0).sin() / (pi_x * pi_x)
+			} else {
+				0.0
+			}
+		})
+	};
+
+	let scale = source_size as f32 / target_size as f32;
+	// When scaling up, the kernel should not be narrower than a source pixel
+	let kernel_scale = scale.max(1.0);
+	let support = kernel_radius * kernel_scale;
+
+	(0..target_size)
+		.map(|target_coordinate| {
+			let center = (target_coordinate as f32 + 0.5) * scale;
+			let start = (center - support).floor().max(0.0) as usize;
+			let end = cmp::min((center + support).ceil() as usize, source_size);
+
+			let mut weights = (start..end)
+				.map(|source_coordinate| {
+					kernel((source_coordinate as f32 + 0.5 - center) / kernel_scale)
+				})
+				.collect::<Vec<_>>();
+
+			let weight_sum = weights.iter().sum::<f32>();
+			if weight_sum != 0.0 {
+				weights.iter_mut().for_each(|weight| *weight /= weight_sum);
+			}
+
+			(start, weights)
+		})
+		.collect()
+}
+
+/// Computes the weighted sum of the components of the pixels at the start of the specified
+/// slice, taking a pixel every `stride` pixels.
+fn weighted_sum(pixels: &[[f32; 4]], weights: &[f32], stride: usize) -> [f32; 4] {
+	pixels
+		.iter()
+		.step_by(stride)
+		.zip(weights)
+		.fold([0.0; 4], |mut sum, (pixel, weight)| {
+			for (sum_component, pixel_component) in sum.iter_mut().zip(pixel) {
+				*sum_component += pixel_component * weight;
+			}
+
+			sum
+		})
+}
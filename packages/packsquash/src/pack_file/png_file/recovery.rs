@@ -0,0 +1,224 @@
+//! Contains routines to repair corrupt or nonconformant PNG files, so that they can be decoded.
+//!
+//! Packs assembled from mixed sources sometimes contain PNG files that most image viewers and
+//! Minecraft load fine, but that strict decoders reject: files with chunk CRCs that do not match
+//! their data, truncated files, or files mangled by Apple tools to the CgBI format used on iOS,
+//! which stores raw DEFLATE streams and premultiplied BGRA pixels. Repairing them rebuilds their
+//! PNG datastream from the data that can be salvaged.
+//!
+//! References:
+//! - <https://www.w3.org/TR/png-3/>
+//! - <https://theapplewiki.com/wiki/PNG_CgBI_Format>
+
+use std::io::{Read, Write};
+use std::num::NonZeroU16;
+
+use flate2::Compression;
+use flate2::read::{DeflateDecoder, ZlibDecoder};
+use flate2::write::ZlibEncoder;
+
+/// The signature every PNG file starts with.
+const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// The starting pixel coordinates and pixel steps of each pass of the Adam7 interlacing method,
+/// as `(x, y, x step, y step)` tuples.
+const ADAM7_PASSES: [(u32, u32, u32, u32); 7] = [
+	(0, 0, 8, 8),
+	(4, 0, 8, 8),
+	(0, 4, 4, 8),
+	(2, 0, 4, 4),
+	(0, 2, 2, 4),
+	(1, 0, 2, 2),
+	(0, 1, 1, 2)
+];
+
+/// A PNG file that was repaired by [`repair_png`].
+pub struct RepairedPng {
+	/// The repaired PNG datastream, which conforms to the PNG specification.
+	pub png: Vec<u8>,
+	/// Whether the original file was in the CgBI format. The pixels of the repaired PNG are
+	/// still premultiplied BGRA pixels in that case, which must be converted to RGBA.
+	pub is_cgbi: bool,
+	/// A human-readable description of the problems that were repaired.
+	pub problems: String
+}
+
+/// Repairs the specified PNG file if it has chunks whose CRC does not match their data, is
+/// truncated, or is in the CgBI format, and returns the repaired PNG. Missing pixel data is
+/// filled with zeros.
+///
+/// `None` is returned if the file has none of these problems, or they can't be repaired. Files
+/// whose width or height exceeds the specified threshold are not repaired either.
+pub fn repair_png(png: &[u8], maximum_dimension: NonZeroU16) -> Option<RepairedPng> {
+	let mut remaining_data = png.strip_prefix(&PNG_SIGNATURE)?;
+
+	let mut has_crc_mismatches = false;
+	let mut is_truncated = true;
+	let mut is_cgbi = false;
+	let mut header = None;
+	let mut chunks = Vec::new();
+	let mut image_data = Vec::new();
+	while !remaining_data.is_empty() {
+		let Some((chunk_type, chunk_data, crc_matches, next_chunk_data)) = next_chunk(remaining_data)
+		else {
+			// Salvage the image data of truncated image data chunks
+			if let Some((length, remaining_data)) = remaining_data.split_first_chunk::<4>()
+				&& let Some((b"IDAT", chunk_data)) = remaining_data.split_first_chunk::<4>()
+			{
+				image_data.extend_from_slice(
+					&chunk_data[..chunk_data.len().min(u32::from_be_bytes(*length) as usize)]
+				);
+			}
+
+			break;
+		};
+		remaining_data = next_chunk_data;
+		has_crc_mismatches |= !crc_matches;
+
+		match &chunk_type {
+			b"CgBI" => is_cgbi = true,
+			b"IHDR" => header = Some(chunk_data),
+			b"IDAT" => image_data.extend_from_slice(chunk_data),
+			b"IEND" => {
+				is_truncated = false;
+				break;
+			}
+			// Ancillary chunks with mismatched CRCs are likely corrupt, and not needed
+			_ if !crc_matches && chunk_type[0].is_ascii_lowercase() => {}
+			_ => chunks.push((chunk_type, chunk_data))
+		}
+	}
+
+	if !has_crc_mismatches && !is_truncated && !is_cgbi {
+		return None;
+	}
+
+	let header: &[u8; 13] = header?.try_into().ok()?;
+	let width = u32::from_be_bytes(header[0..4].try_into().unwrap());
+	let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+	if width == 0
+		|| height == 0
+		|| width > maximum_dimension.get() as u32
+		|| height > maximum_dimension.get() as u32
+	{
+		return None;
+	}
+
+	let mut pixel_data = Vec::new();
+	// Keep the pixel data decompressed before any error, which happens for truncated data.
+	// CgBI files store raw DEFLATE streams instead of zlib streams
+	if is_cgbi {
+		DeflateDecoder::new(&*image_data)
+			.read_to_end(&mut pixel_data)
+			.ok();
+	} else {
+		ZlibDecoder::new(&*image_data)
+			.read_to_end(&mut pixel_data)
+			.ok();
+	}
+	pixel_data.resize(pixel_data_length(header)?, 0);
+
+	let mut pixel_data_encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+	pixel_data_encoder.write_all(&pixel_data).ok()?;
+	let image_data = pixel_data_encoder.finish().ok()?;
+
+	let mut repaired_png = PNG_SIGNATURE.to_vec();
+	write_chunk(&mut repaired_png, b"IHDR", header);
+	for (chunk_type, chunk_data) in chunks {
+		write_chunk(&mut repaired_png, &chunk_type, chunk_data);
+	}
+	write_chunk(&mut repaired_png, b"IDAT", &image_data);
+	write_chunk(&mut repaired_png, b"IEND", &[]);
+
+	let problems = [
+		(has_crc_mismatches, "chunk CRC mismatches"),
+		(is_truncated, "truncated data"),
+		(is_cgbi, "Apple CgBI format")
+	]
+	.into_iter()
+	.filter_map(|(has_problem, problem)| has_problem.then_some(problem))
+	.collect::<Vec<_>>()
+	.join(", ");
+
+	Some(RepairedPng {
+		png: repaired_png,
+		is_cgbi,
+		problems
+	})
+}
+
+/// Splits the next chunk from the specified PNG data, returning its type, its data, whether its
+/// CRC matches and the data that follows it. `None` is returned if the chunk is truncated.
+fn next_chunk(png_data: &[u8]) -> Option<([u8; 4], &[u8], bool, &[u8])> {
+	let (length, png_data) = png_data.split_first_chunk::<4>()?;
+	let (chunk_type, png_data) = png_data.split_first_chunk::<4>()?;
+	let (chunk_data, png_data) = png_data.split_at_checked(u32::from_be_bytes(*length) as usize)?;
+	let (crc, png_data) = png_data.split_first_chunk::<4>()?;
+
+	let mut crc_hasher = crc32fast::Hasher::new();
+	crc_hasher.update(chunk_type);
+	crc_hasher.update(chunk_data);
+
+	Some((
+		*chunk_type,
+		chunk_data,
+		crc_hasher.finalize() == u32::from_be_bytes(*crc),
+		png_data
+	))
+}
+
+/// Appends a PNG chunk with the specified type and data to a PNG file.
+fn write_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], chunk_data: &[u8]) {
+	let mut crc_hasher = crc32fast::Hasher::new();
+	crc_hasher.update(chunk_type);
+	crc_hasher.update(chunk_data);
+
+	png.extend_from_slice(&(chunk_data.len() as u32).to_be_bytes());
+	png.extend_from_slice(chunk_type);
+	png.extend_from_slice(chunk_data);
+	png.extend_from_slice(&crc_hasher.finalize().to_be_bytes());
+}
+
+/// Returns the length of the decompressed pixel data of a PNG file with the specified header
+/// chunk data, including the filter type byte of each scanline. `None` is returned if the
+/// header is not valid.
+fn pixel_data_length(header: &[u8; 13]) -> Option<usize> {
+	let width = u32::from_be_bytes(header[0..4].try_into().unwrap());
+	let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+	let bit_depth = match header[8] {
+		bit_depth @ (1 | 2 | 4 | 8 | 16) => bit_depth as usize,
+		_ => return None
+	};
+	let samples_per_pixel = match header[9] {
+		0 | 3 => 1,
+		2 => 3,
+		4 => 2,
+		6 => 4,
+		_ => return None
+	};
+	let bits_per_pixel = samples_per_pixel * bit_depth;
+
+	let image_length = |width: u32, height: u32| {
+		if width == 0 || height == 0 {
+			0
+		} else {
+			height as usize * (1 + (width as usize * bits_per_pixel).div_ceil(8))
+		}
+	};
+
+	match header[12] {
+		0 => Some(image_length(width, height)),
+		1 => Some(
+			ADAM7_PASSES
+				.iter()
+				.map(|(x, y, x_step, y_step)| {
+					image_length(
+						width.saturating_sub(*x).div_ceil(*x_step),
+						height.saturating_sub(*y).div_ceil(*y_step)
+					)
+				})
+				.sum()
+		),
+		_ => None
+	}
+}
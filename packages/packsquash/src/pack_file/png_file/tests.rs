@@ -1,12 +1,18 @@
+use enumset::EnumSet;
 use futures::FutureExt;
 use rgb::FromSlice;
 use spng::{ContextFlags, CrcAction, DecodeFlags, Format};
+use std::io::Write;
+use std::num::{NonZeroU16, NonZeroU32};
 use std::panic::AssertUnwindSafe;
 use std::{env, fs};
 use tokio_stream::StreamExt;
 use tokio_test::io::Builder;
 
-use crate::config::ColorQuantizationTarget;
+use crate::config::{
+	BitDepthReductionDithering, ColorQuantizationTarget, MipmapLevelReductionFix, PngAncillaryChunk,
+	TextureDownscalingFilter, TransparentPixelColorNormalization
+};
 
 use super::*;
 
@@ -180,6 +186,47 @@ async fn lossy_optimization_works() {
 	.await
 }
 
+#[tokio::test]
+async fn quantization_below_minimum_quality_is_skipped() {
+	successful_process_test(
+		PNG_DATA,
+		PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::FourBitDepth,
+			image_quantization_quality: [100, 100].try_into().unwrap(),
+			skip_alpha_optimizations: true,
+			..Default::default()
+		},
+		true,           // Same pixels
+		true,           // Smaller size
+		false,          // Not necessarily the same color type
+		Some((16, 16)), // Same resolution
+		false,          // The PNG datastream should be standards-compliant
+		PackFileAssetType::GenericTexture,
+		"quantization_below_minimum_quality_is_skipped"
+	)
+	.await
+}
+
+#[tokio::test]
+async fn oversized_texture_is_downscaled() {
+	successful_process_test(
+		PNG_DATA,
+		PngFileOptions {
+			maximum_texture_size: Some(NonZeroU16::new(6).unwrap()),
+			texture_downscaling_filter: TextureDownscalingFilter::Nearest,
+			..Default::default()
+		},
+		false,        // Not the same pixels
+		true,         // Smaller size
+		false,        // Not necessarily the same color type
+		Some((4, 4)), // Downscaled by the smallest power of two that fits
+		false,        // The PNG datastream should be standards-compliant
+		PackFileAssetType::GenericTexture,
+		"oversized_texture_is_downscaled"
+	)
+	.await
+}
+
 #[tokio::test]
 async fn entity_eye_blending_workaround_works() {
 	successful_process_test(
@@ -220,6 +267,760 @@ async fn banner_layer_check_workaround_works() {
 	.await
 }
 
+#[tokio::test]
+async fn color_type_and_bit_depth_reduction_can_be_disabled() {
+	successful_process_test(
+		PNG_DATA,
+		PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::EightBitDepth,
+			color_type_and_bit_depth_reduction: false,
+			..Default::default()
+		},
+		false,          // Transparent pixel colors may change
+		false,          // Not necessarily smaller
+		true,           // Same color type
+		Some((16, 16)), // Same resolution
+		false,          // The PNG datastream should be standards-compliant
+		PackFileAssetType::GenericTexture,
+		"color_type_and_bit_depth_reduction_can_be_disabled"
+	)
+	.await
+}
+
+#[tokio::test]
+async fn transparent_pixel_colors_can_be_bled() {
+	const RED: [u8; 4] = [255, 0, 0, 255];
+	const BLUE: [u8; 4] = [0, 0, 255, 255];
+	const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+	let input_data = oxipng::RawImage::new(
+		4,
+		1,
+		oxipng::ColorType::RGBA,
+		oxipng::BitDepth::Eight,
+		[RED, TRANSPARENT, TRANSPARENT, BLUE].concat()
+	)
+	.expect("The test image is assumed to be valid")
+	.create_optimized_png(&oxipng::Options {
+		optimize_alpha: false,
+		..oxipng::Options::from_preset(0)
+	})
+	.expect("Encoding the test image is assumed not to fail");
+
+	let data_stream = PngFile {
+		read: Builder::new().read(&input_data).build(),
+		asset_type: PackFileAssetType::GenericTexture,
+		file_length_hint: input_data.len(),
+		optimization_settings: PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::None,
+			transparent_pixel_color_normalization: TransparentPixelColorNormalization::Bleed,
+			..Default::default()
+		}
+	}
+	.process();
+
+	let processed_data = data_stream
+		.map(|result| result.expect("No error should happen while decoding").1)
+		.collect::<Vec<_>>()
+		.await
+		.concat();
+
+	let mut png_reader = spng::Decoder::new(&*processed_data)
+		.with_decode_flags(DecodeFlags::TRANSPARENCY)
+		.with_output_format(Format::Rgba8)
+		.read_info()
+		.expect("No error should happen while decoding processed PNG");
+	let mut decoded_pixels = vec![0; png_reader.output_buffer_size()];
+	png_reader
+		.next_frame(&mut decoded_pixels)
+		.expect("No error should happen while reading processed PNG frame");
+
+	assert_eq!(
+		decoded_pixels,
+		[RED, [255, 0, 0, 0], [0, 0, 255, 0], BLUE].concat(),
+		"Transparent pixels should take the color of their closest visible pixels"
+	);
+}
+
+#[tokio::test]
+async fn lab_pbr_texture_channels_are_preserved() {
+	// Every channel of LabPBR textures stores material data, even for transparent pixels.
+	// There are more distinct colors than fit in a palette, so quantization would be lossy
+	let pixels = (0..512_u16)
+		.map(|index| {
+			let value = index as u8;
+			[
+				value,
+				(index >> 1) as u8 ^ 0x55,
+				value.wrapping_mul(13),
+				value.wrapping_mul(31)
+			]
+		})
+		.collect::<Vec<_>>();
+	let input_data = oxipng::RawImage::new(
+		32,
+		16,
+		oxipng::ColorType::RGBA,
+		oxipng::BitDepth::Eight,
+		pixels.concat()
+	)
+	.expect("The test image is assumed to be valid")
+	.create_optimized_png(&oxipng::Options {
+		optimize_alpha: false,
+		..oxipng::Options::from_preset(0)
+	})
+	.expect("Encoding the test image is assumed not to fail");
+
+	let data_stream = PngFile {
+		read: Builder::new().read(&input_data).build(),
+		asset_type: PackFileAssetType::LabPbrTexture,
+		file_length_hint: input_data.len(),
+		optimization_settings: PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::EightBitDepth,
+			transparent_pixel_color_normalization: TransparentPixelColorNormalization::Bleed,
+			..Default::default()
+		}
+	}
+	.process();
+
+	let processed_data = data_stream
+		.map(|result| result.expect("No error should happen while decoding").1)
+		.collect::<Vec<_>>()
+		.await
+		.concat();
+
+	let mut png_reader = spng::Decoder::new(&*processed_data)
+		.with_decode_flags(DecodeFlags::TRANSPARENCY)
+		.with_output_format(Format::Rgba8)
+		.read_info()
+		.expect("No error should happen while decoding processed PNG");
+	let mut decoded_pixels = vec![0; png_reader.output_buffer_size()];
+	png_reader
+		.next_frame(&mut decoded_pixels)
+		.expect("No error should happen while reading processed PNG frame");
+
+	assert_eq!(
+		decoded_pixels,
+		pixels.concat(),
+		"The channels of LabPBR textures should not be changed"
+	);
+}
+
+#[tokio::test]
+async fn lab_pbr_textures_are_not_resized() {
+	let input_data = noisy_png(12, 6);
+
+	let processed_data = PngFile {
+		read: Builder::new().read(&input_data).build(),
+		asset_type: PackFileAssetType::LabPbrTexture,
+		file_length_hint: input_data.len(),
+		optimization_settings: PngFileOptions {
+			maximum_texture_size: Some(NonZeroU16::new(4).unwrap()),
+			required_mipmap_levels: 2,
+			is_atlas_texture: true,
+			mipmap_level_reduction_fix: MipmapLevelReductionFix::Pad,
+			maximum_file_size: Some(NonZeroU32::new(1).unwrap()),
+			..Default::default()
+		}
+	}
+	.process()
+	.map(|result| result.expect("No error should happen while decoding").1)
+	.collect::<Vec<_>>()
+	.await
+	.concat();
+
+	let png_reader = spng::Decoder::new(&*processed_data)
+		.read_info()
+		.expect("No error should happen while decoding processed PNG");
+	assert_eq!(
+		(png_reader.info().width, png_reader.info().height),
+		(12, 6),
+		"LabPBR textures should keep the size of the textures they belong to"
+	);
+}
+
+#[tokio::test]
+async fn animated_textures_are_downscaled_per_frame() {
+	let input_data = noisy_png(8, 32);
+
+	for (animation_metadata, maximum_texture_size, expected_size) in [
+		(&br#"{"animation": {}}"#[..], 8, (8, 32)),
+		(br#"{"animation": {}}"#, 4, (4, 16)),
+		(br#"{"animation": {"height": 8}}"#, 4, (8, 32))
+	] {
+		let (_, processed_data) = process_png(
+			&input_data,
+			PngFileOptions {
+				maximum_texture_size: NonZeroU16::new(maximum_texture_size),
+				animation_frame_size: AnimationFrameSize::from_animation_metadata(animation_metadata),
+				..Default::default()
+			}
+		)
+		.await;
+
+		let png_reader = spng::Decoder::new(&*processed_data)
+			.read_info()
+			.expect("No error should happen while decoding processed PNG");
+		assert_eq!(
+			(png_reader.info().width, png_reader.info().height),
+			expected_size,
+			"Unexpected size for animation metadata {}",
+			String::from_utf8_lossy(animation_metadata)
+		);
+	}
+}
+
+/// Encodes a visually lossless PNG with the specified RGBA pixels.
+fn rgba_png(width: u32, height: u32, pixels: &[[u8; 4]]) -> Vec<u8> {
+	oxipng::RawImage::new(
+		width,
+		height,
+		oxipng::ColorType::RGBA,
+		oxipng::BitDepth::Eight,
+		pixels.concat()
+	)
+	.expect("The test image is assumed to be valid")
+	.create_optimized_png(&oxipng::Options::from_preset(0))
+	.expect("Encoding the test image is assumed not to fail")
+}
+
+/// Processes the specified PNG as a generic texture with the specified settings, returning
+/// the optimization strategy message and the processed PNG.
+async fn process_png(input_data: &[u8], settings: PngFileOptions) -> (String, Vec<u8>) {
+	let (optimization_strategy_messages, processed_data): (Vec<_>, Vec<_>) = PngFile {
+		read: Builder::new().read(input_data).build(),
+		asset_type: PackFileAssetType::GenericTexture,
+		file_length_hint: input_data.len(),
+		optimization_settings: settings
+	}
+	.process()
+	.map(|result| result.expect("No error should happen while decoding"))
+	.collect::<Vec<_>>()
+	.await
+	.into_iter()
+	.unzip();
+
+	(
+		optimization_strategy_messages.concat(),
+		processed_data.concat()
+	)
+}
+
+#[tokio::test]
+async fn mipmap_level_reductions_are_reported() {
+	const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+	let (optimization_strategy_message, processed_data) = process_png(
+		&rgba_png(6, 4, &[WHITE; 24]),
+		PngFileOptions {
+			required_mipmap_levels: 2,
+			is_atlas_texture: true,
+			..Default::default()
+		}
+	)
+	.await;
+
+	assert!(
+		optimization_strategy_message.ends_with("6x4 size limits mipmap levels to 1"),
+		"The mipmap level reduction should be reported, but got: {optimization_strategy_message}"
+	);
+
+	let png_reader = spng::Decoder::new(&*processed_data)
+		.read_info()
+		.expect("No error should happen while decoding processed PNG");
+	assert_eq!(
+		(png_reader.info().width, png_reader.info().height),
+		(6, 4),
+		"Reported textures should not be resized"
+	);
+}
+
+#[tokio::test]
+async fn textures_outside_atlases_are_not_checked_for_mipmap_levels() {
+	const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+	let (optimization_strategy_message, _) = process_png(
+		&rgba_png(6, 4, &[WHITE; 24]),
+		PngFileOptions {
+			required_mipmap_levels: 2,
+			..Default::default()
+		}
+	)
+	.await;
+
+	assert!(
+		!optimization_strategy_message.contains("mipmap levels"),
+		"Textures outside atlases should not be reported, but got: {optimization_strategy_message}"
+	);
+}
+
+#[tokio::test]
+async fn animated_textures_are_checked_per_frame_for_mipmap_levels() {
+	const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+	let (optimization_strategy_message, processed_data) = process_png(
+		&rgba_png(6, 24, &[WHITE; 144]),
+		PngFileOptions {
+			required_mipmap_levels: 2,
+			is_atlas_texture: true,
+			mipmap_level_reduction_fix: MipmapLevelReductionFix::Pad,
+			animation_frame_size: AnimationFrameSize::from_animation_metadata(
+				br#"{"animation": {}}"#
+			),
+			..Default::default()
+		}
+	)
+	.await;
+
+	assert!(
+		optimization_strategy_message.ends_with("6x6 animation frame size limits mipmap levels to 1"),
+		"The frame mipmap level reduction should be reported, but got: {optimization_strategy_message}"
+	);
+
+	let png_reader = spng::Decoder::new(&*processed_data)
+		.read_info()
+		.expect("No error should happen while decoding processed PNG");
+	assert_eq!(
+		(png_reader.info().width, png_reader.info().height),
+		(6, 24),
+		"Animated textures should not be padded"
+	);
+}
+
+#[tokio::test]
+async fn textures_can_be_padded_to_allow_mipmap_levels() {
+	const RED: [u8; 4] = [255, 0, 0, 255];
+	const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+	let (optimization_strategy_message, processed_data) = process_png(
+		&rgba_png(3, 2, &[RED; 6]),
+		PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::None,
+			required_mipmap_levels: 1,
+			is_atlas_texture: true,
+			mipmap_level_reduction_fix: MipmapLevelReductionFix::Pad,
+			transparent_pixel_color_normalization: TransparentPixelColorNormalization::Constant([
+				0, 0, 0
+			]),
+			..Default::default()
+		}
+	)
+	.await;
+
+	assert!(
+		optimization_strategy_message.ends_with("3x2 size changed to 4x2 to allow 1 mipmap levels"),
+		"The padded texture should be reported, but got: {optimization_strategy_message}"
+	);
+
+	let mut png_reader = spng::Decoder::new(&*processed_data)
+		.with_decode_flags(DecodeFlags::TRANSPARENCY)
+		.with_output_format(Format::Rgba8)
+		.read_info()
+		.expect("No error should happen while decoding processed PNG");
+	let mut decoded_pixels = vec![0; png_reader.output_buffer_size()];
+	png_reader
+		.next_frame(&mut decoded_pixels)
+		.expect("No error should happen while reading processed PNG frame");
+
+	assert_eq!(
+		decoded_pixels,
+		[RED, RED, RED, TRANSPARENT, RED, RED, RED, TRANSPARENT].concat(),
+		"Transparent pixels should be added to the right edge of the texture"
+	);
+}
+
+/// Returns the types of the chunks of the specified PNG datastream.
+fn chunk_types(png: &[u8]) -> Vec<[u8; 4]> {
+	let mut chunk_types = Vec::new();
+
+	let mut i = 8;
+	while i < png.len() {
+		let data_length = u32::from_be_bytes(png[i..i + 4].try_into().unwrap()) as usize;
+		chunk_types.push(png[i + 4..i + 8].try_into().unwrap());
+		i += 8 + data_length + 4;
+	}
+
+	chunk_types
+}
+
+#[tokio::test]
+async fn preserved_ancillary_chunks_are_kept() {
+	const LICENSE_TEXT: &[u8] = b"License\0CC-BY-4.0";
+
+	let mut text_chunk = (LICENSE_TEXT.len() as u32).to_be_bytes().to_vec();
+	text_chunk.extend_from_slice(b"tEXt");
+	text_chunk.extend_from_slice(LICENSE_TEXT);
+	text_chunk.extend_from_slice(&crc32fast::hash(&text_chunk[4..]).to_be_bytes());
+
+	let mut input_data = rgba_png(2, 2, &[[255, 0, 0, 255]; 4]);
+	image_processor::insert_chunks(&mut input_data, &text_chunk);
+
+	for preserved_ancillary_chunks in [EnumSet::empty(), PngAncillaryChunk::Text.into()] {
+		let (_, processed_data) = process_png(
+			&input_data,
+			PngFileOptions {
+				preserved_ancillary_chunks,
+				..Default::default()
+			}
+		)
+		.await;
+
+		assert_eq!(
+			chunk_types(&processed_data).contains(b"tEXt"),
+			!preserved_ancillary_chunks.is_empty(),
+			"Only the preserved ancillary chunks should be kept"
+		);
+		assert!(
+			spng::Decoder::new(&*processed_data).read_info().is_ok(),
+			"The processed PNG should be valid"
+		);
+	}
+}
+
+#[tokio::test]
+async fn png_with_crc_mismatches_is_recovered() {
+	let mut input_data = rgba_png(2, 2, &[[255, 0, 0, 255]; 4]);
+	// Corrupt the CRC of the IHDR chunk
+	input_data[29] ^= 0xFF;
+
+	let (optimization_strategy_message, processed_data) =
+		process_png(&input_data, Default::default()).await;
+
+	assert!(
+		optimization_strategy_message.contains("Recovered from a corrupt PNG (chunk CRC mismatches)"),
+		"The recovery should be reported, but got: {optimization_strategy_message}"
+	);
+	assert!(
+		spng::Decoder::new(&*processed_data).read_info().is_ok(),
+		"The processed PNG should be valid"
+	);
+}
+
+#[tokio::test]
+async fn truncated_png_is_recovered() {
+	let input_data = rgba_png(8, 8, &[[0, 255, 0, 255]; 64]);
+	let truncated_input_data = &input_data[..input_data.len() - 20];
+
+	let (optimization_strategy_message, processed_data) =
+		process_png(truncated_input_data, Default::default()).await;
+
+	assert!(
+		optimization_strategy_message.contains("truncated data"),
+		"The recovery should be reported, but got: {optimization_strategy_message}"
+	);
+
+	let png_reader = spng::Decoder::new(&*processed_data)
+		.read_info()
+		.expect("No error should happen while decoding processed PNG");
+	assert_eq!(
+		(png_reader.info().width, png_reader.info().height),
+		(8, 8),
+		"Recovered textures should keep their size"
+	);
+}
+
+#[tokio::test]
+async fn corrupt_png_recovery_can_be_disabled() {
+	let input_data = rgba_png(8, 8, &[[0, 255, 0, 255]; 64]);
+	let truncated_input_data = &input_data[..input_data.len() - 20];
+
+	let mut data_stream = PngFile {
+		read: Builder::new().read(truncated_input_data).build(),
+		asset_type: PackFileAssetType::GenericTexture,
+		file_length_hint: truncated_input_data.len(),
+		optimization_settings: PngFileOptions {
+			corrupt_png_recovery: false,
+			..Default::default()
+		}
+	}
+	.process();
+
+	data_stream
+		.next()
+		.await
+		.expect("Expected some result for this input")
+		.expect_err("Expected an error for this input");
+}
+
+#[tokio::test]
+async fn sixteen_bit_png_is_dithered() {
+	// A channel value halfway between two 8-bit values, which dithering should alternate between
+	const SAMPLE: [u8; 2] = 33024_u16.to_be_bytes();
+
+	let input_data = oxipng::RawImage::new(
+		8,
+		8,
+		oxipng::ColorType::RGBA,
+		oxipng::BitDepth::Sixteen,
+		[SAMPLE, SAMPLE, SAMPLE, [u8::MAX; 2]].concat().repeat(64)
+	)
+	.expect("The test image is assumed to be valid")
+	.create_optimized_png(&oxipng::Options::from_preset(0))
+	.expect("Encoding the test image is assumed not to fail");
+
+	for dithering in [
+		BitDepthReductionDithering::Ordered,
+		BitDepthReductionDithering::FloydSteinberg
+	] {
+		let (_, processed_data) = process_png(
+			&input_data,
+			PngFileOptions {
+				color_quantization_target: ColorQuantizationTarget::None,
+				bit_depth_reduction_dithering: dithering,
+				..Default::default()
+			}
+		)
+		.await;
+
+		assert!(
+			image_processor::bit_depth(&processed_data) <= 8,
+			"The processed PNG should not have 16 bits per channel"
+		);
+
+		let mut png_reader = spng::Decoder::new(&*processed_data)
+			.with_output_format(Format::Rgba8)
+			.read_info()
+			.expect("No error should happen while decoding processed PNG");
+
+		let mut decoded_pixels = vec![0; png_reader.output_buffer_size()];
+		png_reader
+			.next_frame(&mut decoded_pixels)
+			.expect("No error should happen while reading processed PNG frame");
+
+		for value in [128, 129] {
+			assert!(
+				decoded_pixels.contains(&value),
+				"Dithered pixels should contain the channel value {value}"
+			);
+		}
+	}
+}
+
+/// Returns an iCCP chunk with an ICC profile for the linear sRGB color space, which has the
+/// sRGB primaries and a gamma of 1.
+fn linear_srgb_icc_profile_chunk() -> Vec<u8> {
+	// XYZ values are stored in s15Fixed16Number format
+	let xyz_tag = |xyz: [f64; 3]| {
+		let mut tag = b"XYZ \0\0\0\0".to_vec();
+		for value in xyz {
+			tag.extend_from_slice(&((value * 65536.0).round() as i32).to_be_bytes());
+		}
+		tag
+	};
+	// A curve with a single entry is a gamma exponent, in u8Fixed8Number format
+	let linear_curve_tag = b"curv\0\0\0\0\0\0\0\x01\x01\0\0\0".to_vec();
+	let tags = [
+		(b"wtpt", xyz_tag([0.9642, 1.0, 0.8249])),
+		(b"rXYZ", xyz_tag([0.4361, 0.2225, 0.0139])),
+		(b"gXYZ", xyz_tag([0.3851, 0.7169, 0.0971])),
+		(b"bXYZ", xyz_tag([0.1431, 0.0606, 0.7141])),
+		(b"rTRC", linear_curve_tag.clone()),
+		(b"gTRC", linear_curve_tag.clone()),
+		(b"bTRC", linear_curve_tag)
+	];
+
+	let mut tag_table = (tags.len() as u32).to_be_bytes().to_vec();
+	let mut tag_data = Vec::new();
+	let tag_data_offset = 128 + 4 + tags.len() * 12;
+	for (signature, data) in &tags {
+		tag_table.extend_from_slice(*signature);
+		tag_table.extend_from_slice(&((tag_data_offset + tag_data.len()) as u32).to_be_bytes());
+		tag_table.extend_from_slice(&(data.len() as u32).to_be_bytes());
+		tag_data.extend_from_slice(data);
+	}
+
+	let mut header = [0; 128];
+	header[0..4].copy_from_slice(&((128 + tag_table.len() + tag_data.len()) as u32).to_be_bytes());
+	header[8..12].copy_from_slice(&[2, 0x10, 0, 0]); // Version 2.1
+	header[12..16].copy_from_slice(b"mntr");
+	header[16..20].copy_from_slice(b"RGB ");
+	header[20..24].copy_from_slice(b"XYZ ");
+	header[36..40].copy_from_slice(b"acsp");
+	header[68..80].copy_from_slice(&xyz_tag([0.9642, 1.0, 0.8249])[8..]);
+
+	let mut profile_encoder =
+		flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+	profile_encoder
+		.write_all(&[header.as_slice(), &tag_table, &tag_data].concat())
+		.expect("Compressing the test profile is assumed not to fail");
+	let chunk_data = [
+		b"Linear sRGB\0\0".as_slice(),
+		&profile_encoder
+			.finish()
+			.expect("Compressing the test profile is assumed not to fail")
+	]
+	.concat();
+
+	let mut chunk = (chunk_data.len() as u32).to_be_bytes().to_vec();
+	chunk.extend_from_slice(b"iCCP");
+	chunk.extend_from_slice(&chunk_data);
+	chunk.extend_from_slice(&crc32fast::hash(&chunk[4..]).to_be_bytes());
+	chunk
+}
+
+#[tokio::test]
+async fn color_profile_is_converted_to_srgb() {
+	let mut input_data = rgba_png(1, 1, &[[128, 128, 128, 255]]);
+	image_processor::insert_chunks(&mut input_data, &linear_srgb_icc_profile_chunk());
+
+	for color_profile_conversion in [false, true] {
+		let (_, processed_data) = process_png(
+			&input_data,
+			PngFileOptions {
+				color_quantization_target: ColorQuantizationTarget::None,
+				color_profile_conversion,
+				..Default::default()
+			}
+		)
+		.await;
+
+		let mut png_reader = spng::Decoder::new(&*processed_data)
+			.with_output_format(Format::Rgba8)
+			.read_info()
+			.expect("No error should happen while decoding processed PNG");
+		let mut decoded_pixel = [0; 4];
+		png_reader
+			.next_frame(&mut decoded_pixel)
+			.expect("No error should happen while reading processed PNG frame");
+
+		// A linear value of 128 is encoded as about 188 with the sRGB transfer function
+		if color_profile_conversion {
+			assert!(
+				(180..=195).contains(&decoded_pixel[0]),
+				"The pixel should be converted to sRGB, but got: {decoded_pixel:?}"
+			);
+		} else {
+			assert_eq!(
+				decoded_pixel,
+				[128, 128, 128, 255],
+				"The pixel should not be converted to sRGB"
+			);
+		}
+	}
+}
+
+/// Returns a PNG with noisy pixels, which can't be compressed much losslessly.
+fn noisy_png(width: u32, height: u32) -> Vec<u8> {
+	// A linear congruential generator is good enough to generate noise
+	let mut state = 1_u32;
+	let pixels = (0..width * height)
+		.map(|_| {
+			state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+			let [red, green, blue, _] = state.to_be_bytes();
+			[red, green, blue, 255]
+		})
+		.collect::<Vec<_>>();
+
+	rgba_png(width, height, &pixels)
+}
+
+#[tokio::test]
+async fn image_is_fitted_in_size_budget() {
+	const SIZE_BUDGET: u32 = 2048;
+
+	let (optimization_strategy_message, processed_data) = process_png(
+		&noisy_png(64, 64),
+		PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::None,
+			maximum_file_size: NonZeroU32::new(SIZE_BUDGET),
+			..Default::default()
+		}
+	)
+	.await;
+
+	assert!(
+		processed_data.len() <= SIZE_BUDGET as usize,
+		"The processed PNG should fit in the size budget, but it has {} bytes",
+		processed_data.len()
+	);
+	assert!(
+		optimization_strategy_message.contains("could not be met losslessly"),
+		"The lossy size budget pass should be reported, but got: {optimization_strategy_message}"
+	);
+}
+
+#[tokio::test]
+async fn impossible_size_budget_is_reported() {
+	let input_data = noisy_png(16, 16);
+
+	let (optimization_strategy_message, processed_data) = process_png(
+		&input_data,
+		PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::None,
+			maximum_file_size: NonZeroU32::new(8),
+			..Default::default()
+		}
+	)
+	.await;
+
+	assert!(
+		optimization_strategy_message.contains("could not be met"),
+		"The impossible size budget should be reported, but got: {optimization_strategy_message}"
+	);
+	assert_eq!(
+		processed_data,
+		process_png(
+			&input_data,
+			PngFileOptions {
+				color_quantization_target: ColorQuantizationTarget::None,
+				..Default::default()
+			}
+		)
+		.await
+		.1,
+		"Images whose size budget can't be met should be optimized as if it was not set"
+	);
+}
+
+#[tokio::test]
+async fn webp_texture_is_converted() {
+	let pixels = [
+		[255, 0, 0, 255],
+		[0, 255, 0, 128],
+		[0, 0, 255, 255],
+		[255, 255, 255, 0]
+	]
+	.concat();
+
+	let mut input_data = Vec::new();
+	image_webp::WebPEncoder::new(&mut input_data)
+		.encode(&pixels, 2, 2, image_webp::ColorType::Rgba8)
+		.expect("Encoding the test image is assumed not to fail");
+
+	let data_stream = PngFile {
+		read: Builder::new().read(&input_data).build(),
+		asset_type: PackFileAssetType::WebpTexture,
+		file_length_hint: input_data.len(),
+		optimization_settings: PngFileOptions {
+			color_quantization_target: ColorQuantizationTarget::None,
+			skip_alpha_optimizations: true,
+			..Default::default()
+		}
+	}
+	.process();
+
+	let processed_data = data_stream
+		.map(|result| result.expect("No error should happen while decoding").1)
+		.collect::<Vec<_>>()
+		.await
+		.concat();
+
+	let mut png_reader = spng::Decoder::new(&*processed_data)
+		.with_decode_flags(DecodeFlags::TRANSPARENCY)
+		.with_output_format(Format::Rgba8)
+		.read_info()
+		.expect("The converted texture should be a valid PNG");
+	let mut decoded_pixels = vec![0; png_reader.output_buffer_size()];
+	png_reader
+		.next_frame(&mut decoded_pixels)
+		.expect("No error should happen while reading processed PNG frame");
+
+	assert_eq!(
+		decoded_pixels, pixels,
+		"Lossless WebP textures should be converted without changing their pixels"
+	);
+}
+
 #[tokio::test]
 async fn ditherbomb_does_not_get_bigger() {
 	successful_process_test(
@@ -1,8 +1,13 @@
 //! Contains code to optimize properties files.
 
-use std::{borrow::Cow, io};
+use std::{
+	borrow::Cow,
+	collections::{BTreeMap, BTreeSet},
+	io
+};
 
 use bytes::BytesMut;
+use itertools::Itertools;
 use java_properties::{LineEnding, PropertiesError, PropertiesIter, PropertiesWriter};
 use thiserror::Error;
 use tokio::io::AsyncRead;
@@ -92,15 +97,45 @@ impl Decoder for OptimizerDecoder {
 			minified_properties_writer.set_line_ending(LineEnding::LF);
 			minified_properties_writer.set_kv_separator("=").unwrap();
 
+			// Like Java's Properties class does, only keep the last value of keys defined
+			// several times. Sort the keys, as their order does not matter, to make the output
+			// more compressible and independent of how the source file is laid out
+			let mut properties = BTreeMap::new();
+			let mut conflicting_keys = BTreeSet::new();
 			PropertiesIter::new(&**src).read_into(|key, value| {
-				minified_properties_writer.write(&key, &value).unwrap();
+				if let Some(previous_value) = properties.insert(key.clone(), value)
+					&& previous_value != properties[&key]
+				{
+					conflicting_keys.insert(key);
+				}
 			})?;
 
+			for (key, value) in &properties {
+				if self.optimization_settings.is_custom_item_texture_properties
+					&& (key == "texture" || key.starts_with("texture."))
+				{
+					minified_properties_writer
+						.write(key, &normalize_custom_item_texture_path(value))
+						.unwrap();
+				} else {
+					minified_properties_writer.write(key, value).unwrap();
+				}
+			}
+
 			// We should clear the source buffer when handing off a decoded frame
 			src.clear();
 
+			let optimization_strategy_message = if conflicting_keys.is_empty() {
+				Cow::Borrowed("Minified")
+			} else {
+				Cow::Owned(format!(
+					"Minified, but some keys have several different values, of which only the last is used: {}",
+					conflicting_keys.into_iter().join(", ")
+				))
+			};
+
 			Ok(Some((
-				Cow::Borrowed("Minified"),
+				optimization_strategy_message,
 				ByteBuffer::Vec(minified_file_buf)
 			)))
 		} else {
@@ -116,6 +151,37 @@ impl Decoder for OptimizerDecoder {
 	}
 }
 
+/// Returns the shortest equivalent form of a texture path of an OptiFine custom item texture
+/// (CIT) properties file: the `.png` extension and `assets/minecraft/` prefix are implied, and
+/// the `./` prefix is too when the rest of the path is not resolved differently without it.
+///
+/// References:
+/// - <https://optifine.readthedocs.io/cit.html>
+/// - OptiFine's `net.optifine.CustomItemProperties#fixTextureName` method
+/// - OptiFine's `net.optifine.util.TextureUtils#fixResourcePath` method
+fn normalize_custom_item_texture_path(path: &str) -> String {
+	let path = path.strip_suffix(".png").unwrap_or(path);
+	let path = path.strip_prefix("assets/minecraft/").unwrap_or(path);
+
+	match path.strip_prefix("./") {
+		Some(relative_path)
+			if ![
+				"textures/",
+				"optifine/",
+				"mcpatcher/",
+				"assets/minecraft/",
+				"/",
+				"~"
+			]
+			.iter()
+			.any(|prefix| relative_path.starts_with(prefix)) =>
+		{
+			relative_path.to_string()
+		}
+		_ => path.to_string()
+	}
+}
+
 impl<T: AsyncRead + Send + Unpin + 'static> PackFile for PropertiesFile<T> {
 	type ByteChunkType = ByteBuffer;
 	type OptimizationError = OptimizationError;
@@ -80,3 +80,25 @@ async fn passthrough_works() {
 async fn empty_input_is_handled_properly() {
 	successful_process_test(b"", PropertiesFileOptions::default(), b"").await
 }
+
+#[tokio::test]
+async fn duplicated_keys_are_collapsed_and_texture_paths_are_normalized() {
+	successful_process_test(
+		b"texture.bow_pulling_0 = ./bow_0.png\n\
+		  type = item\n\
+		  texture = assets/minecraft/textures/item/bow.png\n\
+		  items = bow\n\
+		  texture.bow_pulling_1 = ./textures/bow_1\n\
+		  type = item\n",
+		PropertiesFileOptions {
+			minify: true,
+			is_custom_item_texture_properties: true
+		},
+		b"items=bow\n\
+		  texture=textures/item/bow\n\
+		  texture.bow_pulling_0=bow_0\n\
+		  texture.bow_pulling_1=./textures/bow_1\n\
+		  type=item\n"
+	)
+	.await
+}
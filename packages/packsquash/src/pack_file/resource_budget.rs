@@ -0,0 +1,100 @@
+//! Contains a resource budget that bounds the resources used by concurrent pack file
+//! optimizations, such as memory or encoder instances.
+
+use std::cmp;
+use std::sync::{Condvar, Mutex};
+
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tokio::task;
+
+#[cfg(test)]
+mod tests;
+
+/// The budget that every pack file optimization reserves its estimated memory usage from, so
+/// that the memory limits of different file types bound their combined memory usage.
+pub static MEMORY_BUDGET: ResourceBudget = ResourceBudget::new();
+
+/// A budget of some resource, measured in arbitrary units, that pack file optimizations
+/// running at the same time in different threads reserve units from, which makes
+/// optimizations wait for others to finish when the total reserved units would exceed a limit.
+pub struct ResourceBudget {
+	reserved_units: Mutex<usize>,
+	reservation_released: Condvar
+}
+
+/// A reservation of units from a [`ResourceBudget`], which is released when dropped.
+#[must_use]
+pub struct ResourceReservation<'budget> {
+	budget: &'budget ResourceBudget,
+	units: usize
+}
+
+impl ResourceBudget {
+	/// Creates a new resource budget without any reserved units.
+	pub const fn new() -> Self {
+		Self {
+			reserved_units: Mutex::new(0),
+			reservation_released: Condvar::new()
+		}
+	}
+
+	/// Reserves the specified amount of units from this budget, blocking the current thread
+	/// until the total reserved units would not exceed the specified limit. Reservations are
+	/// always granted when no units are reserved, so that a single reservation that exceeds
+	/// the limit on its own does not wait forever. Such reservations reserve the limit instead.
+	pub fn reserve(&self, units: usize, limit: usize) -> ResourceReservation<'_> {
+		let units = cmp::min(units, limit);
+		let mut reserved_units = self.reserved_units.lock().unwrap();
+
+		let must_wait = |reserved_units: &mut usize| {
+			*reserved_units != 0 && reserved_units.saturating_add(units) > limit
+		};
+		if must_wait(&mut *reserved_units) {
+			let wait = || {
+				self.reservation_released
+					.wait_while(reserved_units, must_wait)
+					.unwrap()
+			};
+
+			// Let the Tokio runtime hand the other tasks of this worker thread to another
+			// thread while waiting, if possible
+			reserved_units = if Handle::try_current()
+				.is_ok_and(|handle| handle.runtime_flavor() == RuntimeFlavor::MultiThread)
+			{
+				task::block_in_place(wait)
+			} else {
+				wait()
+			};
+		}
+
+		*reserved_units += units;
+
+		ResourceReservation {
+			budget: self,
+			units
+		}
+	}
+
+	/// Reserves the specified amount of units from this budget if the total reserved units
+	/// would not exceed the specified limit, returning `None` without blocking otherwise.
+	pub fn try_reserve(&self, units: usize, limit: usize) -> Option<ResourceReservation<'_>> {
+		let mut reserved_units = self.reserved_units.lock().unwrap();
+		if reserved_units.saturating_add(units) > limit {
+			return None;
+		}
+
+		*reserved_units += units;
+
+		Some(ResourceReservation {
+			budget: self,
+			units
+		})
+	}
+}
+
+impl Drop for ResourceReservation<'_> {
+	fn drop(&mut self) {
+		*self.budget.reserved_units.lock().unwrap() -= self.units;
+		self.budget.reservation_released.notify_all();
+	}
+}
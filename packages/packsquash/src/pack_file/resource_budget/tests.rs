@@ -0,0 +1,60 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use super::*;
+
+#[test]
+fn reservations_exceeding_the_limit_are_granted_when_nothing_is_reserved() {
+	let budget = ResourceBudget::new();
+
+	let reservation = budget.reserve(200, 100);
+	assert_eq!(*budget.reserved_units.lock().unwrap(), 100);
+
+	drop(reservation);
+	assert_eq!(*budget.reserved_units.lock().unwrap(), 0);
+}
+
+#[test]
+fn reservations_wait_until_there_are_enough_units() {
+	static BUDGET: ResourceBudget = ResourceBudget::new();
+
+	let first_reservation = BUDGET.reserve(60, 100);
+	let (reserved_sender, reserved_receiver) = mpsc::channel();
+	let reserving_thread = thread::spawn(move || {
+		let _second_reservation = BUDGET.reserve(60, 100);
+		reserved_sender.send(()).unwrap();
+	});
+
+	assert!(
+		reserved_receiver
+			.recv_timeout(Duration::from_millis(100))
+			.is_err(),
+		"The second reservation should wait for the first one to be released"
+	);
+
+	drop(first_reservation);
+	reserved_receiver
+		.recv_timeout(Duration::from_secs(10))
+		.expect("The second reservation should be granted after the first one is released");
+	reserving_thread.join().unwrap();
+}
+
+#[test]
+fn reservation_attempts_exceeding_the_limit_are_declined() {
+	let budget = ResourceBudget::new();
+
+	let first_reservation = budget
+		.try_reserve(60, 100)
+		.expect("The first reservation should be granted");
+	assert!(
+		budget.try_reserve(60, 100).is_none(),
+		"The second reservation should be declined while the first one is held"
+	);
+
+	drop(first_reservation);
+	assert!(
+		budget.try_reserve(60, 100).is_some(),
+		"The second reservation should be granted after the first one is released"
+	);
+}
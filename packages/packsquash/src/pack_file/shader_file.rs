@@ -1,19 +1,22 @@
 //! Contains code to optimize shader files.
 
-use std::{borrow::Cow, io, str::Utf8Error};
+use std::{borrow::Cow, io, str::Utf8Error, sync::LazyLock};
 
 use bytes::BytesMut;
 use glsl_lang::ast::{Expr, Statement, TranslationUnit};
 use glsl_lang::parse::Extractable;
+use regex::Regex;
 use thiserror::Error;
 use tokio::io::AsyncRead;
 use tokio_util::codec::{Decoder, FramedRead};
 
-use crate::config::{ShaderFileOptions, ShaderSourceTransformationStrategy};
+use crate::config::{
+	MinecraftVersion, MinecraftVersionRange, ShaderFileOptions, ShaderSourceTransformationStrategy
+};
 use crate::pack_file::shader_file::parser::{ParsedSymbol, Transpilable};
-use parser::{ParseError, Parser};
+use parser::{ParseError, Parser, SourcePosition};
 
-use super::{AsyncReadAndSizeHint, PackFile, PackFileAssetType, PackFileConstructor};
+use super::{AsyncReadAndSizeHint, PackFile, PackFileAssetType, PackFileConstructor, strip_utf8_bom};
 
 mod parser;
 
@@ -50,6 +53,14 @@ pub enum OptimizationError {
 	InvalidEncoding(#[from] Utf8Error),
 	#[error("Shader error: {0}")]
 	InvalidShader(#[from] ParseError),
+	#[error("Invalid #version directive at {position}: {reason}")]
+	InvalidVersionDirective {
+		position: SourcePosition,
+		reason: String
+	},
+	#[cfg(feature = "shader-compilation-check")]
+	#[error("Compilation error: {0}")]
+	Compilation(String),
 	#[error("I/O error: {0}")]
 	Io(#[from] io::Error)
 }
@@ -76,15 +87,70 @@ impl Decoder for OptimizerDecoder {
 			self.optimization_settings.source_transformation_strategy;
 
 		if self.is_vertex_or_fragment_shader {
-			// Vertex or fragment shaders must be parseable as translation units
-			process_shader_as::<TranslationUnit>(
+			#[cfg_attr(not(feature = "shader-compilation-check"), expect(unused_variables))]
+			let glsl_version = validate_version_directive(
+				std::str::from_utf8(strip_utf8_bom(src))?,
+				self.optimization_settings.maximum_glsl_version.or_else(|| {
+					self.optimization_settings
+						.target_minecraft_version_range
+						.map(guaranteed_glsl_version)
+				})
+			)?;
+
+			// Vertex or fragment shaders must be parseable as translation units. Unused code
+			// can only be stripped from top-level ones, and their identifiers renamed, as
+			// otherwise other shaders may refer to them
+			let is_top_level_shader = self
+				.optimization_settings
+				.is_top_level_shader
+				.unwrap_or(true);
+			let strip_unused_code =
+				is_top_level_shader && self.optimization_settings.strip_unused_code;
+			let rename_identifiers = is_top_level_shader
+				&& matches!(
+					source_transformation_strategy,
+					ShaderSourceTransformationStrategy::MinifyAndRenameIdentifiers
+				);
+
+			let processing_result = process_shader_as::<TranslationUnit>(
 				src,
 				&shader_parser,
-				self.optimization_settings
-					.is_top_level_shader
-					.unwrap_or(true),
-				source_transformation_strategy
-			)
+				is_top_level_shader,
+				source_transformation_strategy,
+				|translation_unit| {
+					let mut transformations = Vec::new();
+
+					if strip_unused_code && translation_unit.strip_unused_declarations() > 0 {
+						transformations.push("unused code stripped");
+					}
+
+					if rename_identifiers && translation_unit.rename_identifiers() {
+						transformations.push("identifiers renamed");
+					}
+
+					transformations
+				}
+			)?;
+
+			// Compile the processed source code of top-level shaders without imports, if
+			// requested, to check that it is what compilers stricter than the game expect
+			#[cfg(feature = "shader-compilation-check")]
+			if is_top_level_shader
+				&& self.optimization_settings.compilation_check
+				&& let Some((_, processed_source)) = &processing_result
+			{
+				let processed_source = std::str::from_utf8(strip_utf8_bom(processed_source))?;
+
+				if !processed_source.contains("moj_import") {
+					check_compilation(
+						processed_source,
+						glsl_version,
+						self.optimization_settings.is_fragment_shader
+					)?;
+				}
+			}
+
+			Ok(processing_result)
 		} else {
 			// Include shaders may not necessarily be a translation unit. In fact, they technically
 			// can be any text, as long as its inclusion in a top-level shader yields valid GLSL. As
@@ -97,18 +163,26 @@ impl Decoder for OptimizerDecoder {
 				src,
 				&shader_parser,
 				false,
-				source_transformation_strategy
+				source_transformation_strategy,
+				|_| Vec::new()
 			)
 			.or_else(|_| {
 				process_shader_as::<Vec<Statement>>(
 					src,
 					&shader_parser,
 					false,
-					source_transformation_strategy
+					source_transformation_strategy,
+					|_| Vec::new()
 				)
 			})
 			.or_else(|_| {
-				process_shader_as::<Expr>(src, &shader_parser, false, source_transformation_strategy)
+				process_shader_as::<Expr>(
+					src,
+					&shader_parser,
+					false,
+					source_transformation_strategy,
+					|_| Vec::new()
+				)
 			})
 			.or_else(|err| {
 				if let OptimizationError::InvalidShader(err) = err {
@@ -167,14 +241,155 @@ impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for ShaderFil
 	}
 }
 
+/// The GLSL versions that desktop OpenGL implementations may support.
+const DESKTOP_GLSL_VERSIONS: &[u16] = &[
+	110, 120, 130, 140, 150, 330, 400, 410, 420, 430, 440, 450, 460
+];
+
+/// Returns the newest GLSL version that every Minecraft version in the specified range is
+/// guaranteed to support, according to the OpenGL version it requires.
+fn guaranteed_glsl_version(version_range: MinecraftVersionRange) -> u16 {
+	let minimum_version = version_range.minimum();
+
+	// Minecraft requires OpenGL 3.3 since 1.21.5, whose newest GLSL version is 3.30, and its
+	// core shaders declare that version. From 1.17 on, it requires OpenGL 3.2, whose newest
+	// GLSL version is 1.50. Older versions run on OpenGL 2.1 hardware, whose newest GLSL
+	// version is 1.20
+	if minimum_version >= MinecraftVersion::new(1, 21, 5) {
+		330
+	} else if minimum_version >= MinecraftVersion::new(1, 17, 0) {
+		150
+	} else {
+		120
+	}
+}
+
+/// Validates the `#version` directive of a vertex or fragment shader source, if it has one,
+/// checking that it declares a desktop GLSL version not newer than the specified maximum,
+/// with a valid profile, and returning that version. Shaders without a `#version` directive
+/// target GLSL 1.10, which is always supported.
+fn validate_version_directive(
+	source: &str,
+	maximum_glsl_version: Option<u16>
+) -> Result<u16, OptimizationError> {
+	static VERSION_DIRECTIVE: LazyLock<Regex> = LazyLock::new(|| {
+		Regex::new(r"(?m)^[ \t]*#[ \t]*version[ \t]+(\S+)(?:[ \t]+([^\s/]+))?").unwrap()
+	});
+
+	let Some(version_directive) = VERSION_DIRECTIVE.captures(source) else {
+		return Ok(110);
+	};
+
+	let version_match = version_directive.get(1).unwrap();
+	let invalid_version_directive = |reason| OptimizationError::InvalidVersionDirective {
+		position: SourcePosition::from_offset(source, version_match.start()),
+		reason
+	};
+
+	let version = version_match
+		.as_str()
+		.parse::<u16>()
+		.ok()
+		.filter(|version| DESKTOP_GLSL_VERSIONS.contains(version))
+		.ok_or_else(|| {
+			invalid_version_directive(format!(
+				"{} is not a desktop GLSL version",
+				version_match.as_str()
+			))
+		})?;
+
+	match version_directive.get(2).map(|profile| profile.as_str()) {
+		None => {}
+		Some("core" | "compatibility") if version >= 150 => {}
+		Some("core" | "compatibility") => {
+			return Err(invalid_version_directive(format!(
+				"profiles can't be declared before GLSL 1.50, but the version is {version}"
+			)));
+		}
+		Some("es") => {
+			return Err(invalid_version_directive(
+				"the es profile is not available in desktop OpenGL".into()
+			));
+		}
+		Some(profile) => {
+			return Err(invalid_version_directive(format!(
+				"unknown profile {profile}"
+			)));
+		}
+	}
+
+	if let Some(maximum_glsl_version) = maximum_glsl_version
+		&& version > maximum_glsl_version
+	{
+		return Err(invalid_version_directive(format!(
+			"GLSL version {version} is newer than the maximum allowed version, {maximum_glsl_version}"
+		)));
+	}
+
+	Ok(version)
+}
+
+/// Compiles the source code of a top-level vertex or fragment shader that declares the
+/// specified GLSL version to SPIR-V with the glslang reference compiler, discarding the
+/// result. This catches errors that the lenient OpenGL drivers of some players may accept,
+/// but others may not, such as implicit type conversions the declared GLSL version does not
+/// allow. SPIR-V can't be generated from shaders for GLSL versions older than 3.30, so shaders
+/// that declare GLSL 1.50, like the Minecraft core shaders, are compiled as GLSL 3.30, which
+/// has the same semantics for them, and shaders for older GLSL versions are not compiled.
+#[cfg(feature = "shader-compilation-check")]
+fn check_compilation(
+	source: &str,
+	glsl_version: u16,
+	is_fragment_shader: bool
+) -> Result<(), OptimizationError> {
+	use shaderc::{CompileOptions, Compiler, EnvVersion, GlslProfile, ShaderKind, TargetEnv};
+
+	if glsl_version < 150 {
+		return Ok(());
+	}
+
+	let (Some(compiler), Some(mut compile_options)) = (Compiler::new(), CompileOptions::new()) else {
+		return Err(OptimizationError::Compilation(
+			"the shader compiler could not be initialized".into()
+		));
+	};
+
+	compile_options.set_target_env(TargetEnv::OpenGL, EnvVersion::OpenGL4_5 as u32);
+	// Minecraft binds uniforms and vertex attributes by name, so it is fine for them not to
+	// have the explicit locations SPIR-V requires
+	compile_options.set_auto_map_locations(true);
+	compile_options.set_auto_bind_uniforms(true);
+	if glsl_version < 330 {
+		compile_options.set_forced_version_profile(330, GlslProfile::Core);
+	}
+
+	compiler
+		.compile_into_spirv(
+			source,
+			if is_fragment_shader {
+				ShaderKind::Fragment
+			} else {
+				ShaderKind::Vertex
+			},
+			"shader",
+			"main",
+			Some(&compile_options)
+		)
+		.map(|_| ())
+		.map_err(|error| OptimizationError::Compilation(error.to_string()))
+}
+
 /// Processes the shader code at the specified source buffer, trying to parse it as `T`.
 /// An error is returned when the source can't be parsed as the specified symbol, which
 /// may or may not be a format error depending on how the source is interpreted by Minecraft.
+/// `transform_ast` is executed on the parsed symbol before transpiling it, and returns short
+/// descriptions of the additional transformations it did to it.
 fn process_shader_as<T: Extractable<TranslationUnit> + 'static>(
 	src: &mut BytesMut,
 	shader_parser: &Parser,
 	is_top_level_translation_unit: bool,
-	source_transformation_strategy: ShaderSourceTransformationStrategy
+	source_transformation_strategy: ShaderSourceTransformationStrategy,
+	transform_ast: impl FnOnce(&mut ParsedSymbol<T>) -> Vec<&'static str>
 ) -> Result<Option<<OptimizerDecoder as Decoder>::Item>, OptimizationError>
 where
 	ParsedSymbol<T>: Transpilable
@@ -184,22 +399,33 @@ where
 		source_transformation_strategy
 	) {
 		(
-			Ok(Some(symbol)),
-			ShaderSourceTransformationStrategy::Minify | ShaderSourceTransformationStrategy::Prettify
+			Ok(Some(mut symbol)),
+			ShaderSourceTransformationStrategy::Minify
+			| ShaderSourceTransformationStrategy::MinifyAndRenameIdentifiers
+			| ShaderSourceTransformationStrategy::Prettify
 		) => {
 			// The shader is valid and safe to transform, and we want to transform it
-			let minify = matches!(
+			let minify = !matches!(
 				source_transformation_strategy,
-				ShaderSourceTransformationStrategy::Minify
+				ShaderSourceTransformationStrategy::Prettify
 			);
+			let transformations = transform_ast(&mut symbol);
 
 			let mut buf = src.split_off(0);
 			buf.clear();
 
 			buf.extend_from_slice(symbol.transpile(minify).as_bytes());
 
+			let strategy_message = if minify { "Minified" } else { "Prettified" };
 			Ok(Some((
-				Cow::Borrowed(if minify { "Minified" } else { "Prettified" }),
+				if transformations.is_empty() {
+					Cow::Borrowed(strategy_message)
+				} else {
+					Cow::Owned(format!(
+						"{strategy_message}, with {}",
+						transformations.join(" and ")
+					))
+				},
 				buf
 			)))
 		}
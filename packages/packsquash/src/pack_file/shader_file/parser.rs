@@ -1,12 +1,18 @@
 //! GLSL parsing and transpilation code, based on `glsl_lang` and its companion crates.
 
 use crate::pack_file::strip_utf8_bom;
+use ahash::{AHashMap, AHashSet};
 use aho_corasick::AhoCorasick;
 use glsl_lang::ast::{
-	Expr, ExternalDeclarationData, FileId, Statement, TranslationUnit, TypeSpecifierNonArrayData
+	Block, Condition, ConditionData, DeclarationData, Expr, ExprData, ExternalDeclaration,
+	ExternalDeclarationData, FileId, FunIdentifierData, FunctionParameterDeclarator, Identifier,
+	LayoutQualifierSpec, LayoutQualifierSpecData, SingleDeclaration, SingleDeclarationNoType,
+	Statement, StorageQualifierData, StructSpecifier, TranslationUnit, TypeName,
+	TypeQualifierSpecData, TypeSpecifierNonArrayData
 };
 use glsl_lang::parse::{Extractable, Parse};
 use glsl_lang::transpiler::glsl::{FormattingSettings, FormattingState};
+use glsl_lang::visitor::{Host, HostMut, Visit, Visitor, VisitorMut};
 use glsl_lang_lexer::ParseOptions;
 use glsl_lang_lexer::full::LexicalError;
 use glsl_lang_lexer::full::fs::PreprocessorExt;
@@ -42,9 +48,11 @@ type PreprocessingLexer<'r, 'p, 'flag> =
 /// An error that may happen while parsing a GLSL grammar symbol.
 #[derive(Error, Debug)]
 pub enum ParseError {
-	#[error("Syntax error: {error}")]
+	#[error("Syntax error at {position}: {error}")]
 	Syntax {
 		error: glsl_lang::parse::ParseError<LexicalError<Infallible>>,
+		/// The position in the source code where the syntax error was found.
+		position: SourcePosition,
 		/// Represents whether this syntax error happened when the source file contained
 		/// an unresolvable `#moj_import` directive. This indicates that the syntax error
 		/// might be a false positive, as it can be caused due to preprocessor directives
@@ -60,10 +68,44 @@ pub enum ParseError {
 		/// the preprocessing stage compared to during runtime.
 		found_unresolvable_moj_import: bool
 	},
+	#[error("Undeclared identifier \"{identifier}\"{}", position.map_or_else(String::new, |position| format!(" at {position}")))]
+	UndeclaredIdentifier {
+		identifier: String,
+		/// The position in the source code where the identifier was first used, if known.
+		position: Option<SourcePosition>
+	},
 	#[error("Invalid encoding: {0}")]
 	InvalidEncoding(#[from] Utf8Error)
 }
 
+/// A line and column position in GLSL source code, used to report precise diagnostics.
+/// Both numbers start at 1, and columns are counted in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourcePosition {
+	line: usize,
+	column: usize
+}
+
+impl SourcePosition {
+	/// Computes the position of the character at the specified byte offset of a source code.
+	/// Offsets past the end of the source code are clamped to it.
+	pub fn from_offset(source: &str, offset: usize) -> Self {
+		let preceding_source = source.get(..offset).unwrap_or(source);
+		let line_start = preceding_source.rfind('\n').map_or(0, |i| i + 1);
+
+		Self {
+			line: preceding_source.matches('\n').count() + 1,
+			column: preceding_source[line_start..].chars().count() + 1
+		}
+	}
+}
+
+impl fmt::Display for SourcePosition {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "line {}, column {}", self.line, self.column)
+	}
+}
+
 /// A GLSL parser capable of parsing a GLSL grammar symbol.
 pub struct Parser {
 	parse_options: ParseOptions
@@ -162,12 +204,23 @@ impl Parser {
 							&source
 						));
 
-				Ok(safe_to_transpile.then(|| {
-					preprocessor_directives.inject(&mut translation_unit);
+				if !safe_to_transpile {
+					return Ok(None);
+				}
 
-					// Wrapping and extracting a TU into itself is a no-op, so this always returns Some
-					T::extract(translation_unit)
-				}))
+				// We know the complete source code of the shader now, so we can check that every
+				// identifier it uses is declared somewhere
+				if let Some((identifier, offset)) = first_undeclared_identifier(&translation_unit) {
+					return Err(ParseError::UndeclaredIdentifier {
+						identifier,
+						position: offset.map(|offset| SourcePosition::from_offset(&source, offset))
+					});
+				}
+
+				preprocessor_directives.inject(&mut translation_unit);
+
+				// Wrapping and extracting a TU into itself is a no-op, so this always returns Some
+				Ok(Some(T::extract(translation_unit)))
 			} else {
 				// - Non-TUs are parsed by extracting their AST node from a TU, which discards any
 				//   preprocessor directive nodes, and may thus change the semantics of the source code.
@@ -179,6 +232,10 @@ impl Parser {
 		.map_or_else(
 			|error| {
 				Err(ParseError::Syntax {
+					position: SourcePosition::from_offset(
+						&source,
+						error.pos().range().start().into()
+					),
 					error,
 					found_unresolvable_moj_import: found_unresolvable_moj_import.get()
 				})
@@ -292,6 +349,804 @@ pub struct ParsedSymbol<T> {
 	symbol: T
 }
 
+impl ParsedSymbol<TranslationUnit> {
+	/// Removes the function definitions, function prototypes and uniform variable declarations
+	/// that are not referenced, directly or indirectly, by the rest of this translation unit,
+	/// returning how many external declarations were removed.
+	///
+	/// This is only safe to do for top-level translation units without preprocessor directives
+	/// that may reference such declarations, as other source code can't reference them then.
+	/// Like GLSL compilers do, uniforms that are not referenced are considered inactive, and
+	/// Minecraft handles missing uniforms by ignoring them.
+	pub fn strip_unused_declarations(&mut self) -> usize {
+		let external_declarations = &self.symbol.0;
+
+		// The names a removable external declaration defines, or None if it is not removable
+		let declared_names = external_declarations
+			.iter()
+			.map(removable_declaration_names)
+			.collect::<Vec<_>>();
+
+		// Start with the references of the declarations that are always kept, and keep the
+		// removable declarations that define referenced names until no more are referenced
+		let mut is_kept = declared_names
+			.iter()
+			.map(Option::is_none)
+			.collect::<Vec<_>>();
+		let mut pending_declarations = (0..external_declarations.len())
+			.filter(|&i| is_kept[i])
+			.collect::<Vec<_>>();
+
+		while let Some(i) = pending_declarations.pop() {
+			let mut identifier_collector = IdentifierCollector {
+				identifiers: AHashSet::new()
+			};
+			external_declarations[i].visit(&mut identifier_collector);
+
+			for identifier in identifier_collector.identifiers {
+				for (j, names) in declared_names.iter().enumerate() {
+					if !is_kept[j]
+						&& names
+							.as_ref()
+							.is_some_and(|names| names.contains(&identifier))
+					{
+						is_kept[j] = true;
+						pending_declarations.push(j);
+					}
+				}
+			}
+		}
+
+		let original_declaration_count = external_declarations.len();
+		let mut is_kept = is_kept.into_iter();
+		self.symbol.0.retain(|_| is_kept.next().unwrap());
+
+		original_declaration_count - self.symbol.0.len()
+	}
+}
+
+impl ParsedSymbol<TranslationUnit> {
+	/// Renames the local variables, function parameters and functions of this translation unit
+	/// to short identifiers, returning whether any identifier was renamed. The most used
+	/// identifiers get the shortest names.
+	///
+	/// Like [`Self::strip_unused_declarations`], this is only safe to do for top-level
+	/// translation units without preprocessor directives that may refer to such identifiers.
+	/// Global variables, such as uniforms, inputs and outputs, structure and interface block
+	/// members and the `main` function are never renamed, as the game or other shaders may
+	/// refer to them, and neither are identifiers that share their name with any of these. As
+	/// every occurrence of a renamable identifier is renamed to a different, previously unused
+	/// name, scoping rules keep working as before.
+	pub fn rename_identifiers(&mut self) -> bool {
+		let mut identifier_classifier = IdentifierClassifier {
+			in_function: false,
+			renamable_identifiers: AHashMap::new(),
+			fixed_identifiers: AHashSet::new(),
+			used_names: AHashSet::new()
+		};
+
+		for external_declaration in &self.symbol.0 {
+			identifier_classifier.in_function = match &external_declaration.content {
+				ExternalDeclarationData::FunctionDefinition(function_definition) => {
+					identifier_classifier.add_function_name(&function_definition.prototype.name);
+					true
+				}
+				ExternalDeclarationData::Declaration(declaration) => {
+					if let DeclarationData::FunctionPrototype(function_prototype) =
+						&declaration.content
+					{
+						identifier_classifier.add_function_name(&function_prototype.name);
+						true
+					} else {
+						false
+					}
+				}
+				_ => false
+			};
+
+			external_declaration.visit(&mut identifier_classifier);
+		}
+
+		let IdentifierClassifier {
+			renamable_identifiers,
+			fixed_identifiers,
+			used_names,
+			..
+		} = identifier_classifier;
+
+		let mut renamable_identifiers = renamable_identifiers
+			.into_iter()
+			.filter(|(identifier, _)| !fixed_identifiers.contains(identifier))
+			.collect::<Vec<_>>();
+		renamable_identifiers.sort_unstable_by(|(identifier_a, uses_a), (identifier_b, uses_b)| {
+			uses_b
+				.cmp(uses_a)
+				.then_with(|| identifier_a.cmp(identifier_b))
+		});
+
+		let new_names = short_identifiers().filter(|name| {
+			!used_names.contains(name)
+				&& !RESERVED_WORDS.contains(&name.as_str())
+				&& !BUILT_IN_FUNCTIONS.contains(&name.as_str())
+		});
+
+		let mut identifier_renamer = IdentifierRenamer {
+			new_names: renamable_identifiers
+				.into_iter()
+				.map(|(identifier, _)| identifier)
+				.zip(new_names)
+				.collect()
+		};
+
+		if identifier_renamer.new_names.is_empty() {
+			return false;
+		}
+
+		self.symbol.visit_mut(&mut identifier_renamer);
+
+		true
+	}
+}
+
+/// The reserved words of every desktop GLSL version, which can't be used as identifiers.
+const RESERVED_WORDS: &[&str] = &[
+	"active",
+	"asm",
+	"atomic_uint",
+	"attribute",
+	"bool",
+	"break",
+	"buffer",
+	"bvec2",
+	"bvec3",
+	"bvec4",
+	"case",
+	"cast",
+	"centroid",
+	"class",
+	"coherent",
+	"common",
+	"const",
+	"continue",
+	"default",
+	"discard",
+	"dmat2",
+	"dmat2x2",
+	"dmat2x3",
+	"dmat2x4",
+	"dmat3",
+	"dmat3x2",
+	"dmat3x3",
+	"dmat3x4",
+	"dmat4",
+	"dmat4x2",
+	"dmat4x3",
+	"dmat4x4",
+	"do",
+	"double",
+	"dvec2",
+	"dvec3",
+	"dvec4",
+	"else",
+	"enum",
+	"extern",
+	"external",
+	"false",
+	"filter",
+	"fixed",
+	"flat",
+	"float",
+	"for",
+	"fvec2",
+	"fvec3",
+	"fvec4",
+	"goto",
+	"half",
+	"highp",
+	"hvec2",
+	"hvec3",
+	"hvec4",
+	"if",
+	"in",
+	"inline",
+	"inout",
+	"input",
+	"int",
+	"interface",
+	"invariant",
+	"isampler1D",
+	"isampler2D",
+	"isampler3D",
+	"isamplerCube",
+	"ivec2",
+	"ivec3",
+	"ivec4",
+	"layout",
+	"long",
+	"lowp",
+	"mat2",
+	"mat2x2",
+	"mat2x3",
+	"mat2x4",
+	"mat3",
+	"mat3x2",
+	"mat3x3",
+	"mat3x4",
+	"mat4",
+	"mat4x2",
+	"mat4x3",
+	"mat4x4",
+	"mediump",
+	"namespace",
+	"noinline",
+	"noperspective",
+	"out",
+	"output",
+	"partition",
+	"patch",
+	"precise",
+	"precision",
+	"public",
+	"readonly",
+	"resource",
+	"restrict",
+	"return",
+	"sample",
+	"sampler1D",
+	"sampler2D",
+	"sampler3D",
+	"samplerCube",
+	"shared",
+	"short",
+	"sizeof",
+	"smooth",
+	"static",
+	"struct",
+	"subroutine",
+	"superp",
+	"switch",
+	"template",
+	"this",
+	"true",
+	"typedef",
+	"uint",
+	"uniform",
+	"union",
+	"unsigned",
+	"using",
+	"usampler1D",
+	"usampler2D",
+	"usampler3D",
+	"usamplerCube",
+	"uvec2",
+	"uvec3",
+	"uvec4",
+	"varying",
+	"vec2",
+	"vec3",
+	"vec4",
+	"void",
+	"volatile",
+	"while",
+	"writeonly"
+];
+
+/// Returns an endless iterator over the identifiers made of ASCII letters, shortest first.
+fn short_identifiers() -> impl Iterator<Item = String> {
+	const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+	(1..).flat_map(|length| {
+		(0..LETTERS.len().pow(length)).map(move |mut index| {
+			let mut identifier = String::with_capacity(length as usize);
+			for _ in 0..length {
+				identifier.push(LETTERS[index % LETTERS.len()] as char);
+				index /= LETTERS.len();
+			}
+			identifier
+		})
+	})
+}
+
+/// A GLSL AST visitor that classifies the identifiers of a translation unit in those that
+/// may be renamed, with their number of uses, and those that must keep their name.
+struct IdentifierClassifier {
+	/// Whether the visited external declaration is a function definition or prototype.
+	in_function: bool,
+	renamable_identifiers: AHashMap<String, usize>,
+	fixed_identifiers: AHashSet<String>,
+	/// Every identifier and type name of the translation unit.
+	used_names: AHashSet<String>
+}
+
+impl IdentifierClassifier {
+	/// Classifies the name of a function definition or prototype.
+	fn add_function_name(&mut self, name: &Identifier) {
+		if name.0 == "main" {
+			self.fixed_identifiers.insert(name.0.to_string());
+		} else {
+			self.renamable_identifiers
+				.entry(name.0.to_string())
+				.or_default();
+		}
+	}
+
+	/// Classifies the name of a variable declaration, which may be renamed if it is local.
+	fn add_variable_name(&mut self, name: &Identifier) {
+		if self.in_function {
+			self.renamable_identifiers
+				.entry(name.0.to_string())
+				.or_default();
+		} else {
+			self.fixed_identifiers.insert(name.0.to_string());
+		}
+	}
+}
+
+impl Visitor for IdentifierClassifier {
+	fn visit_single_declaration(&mut self, declaration: &SingleDeclaration) -> Visit {
+		if let Some(name) = &declaration.name {
+			self.add_variable_name(name);
+		}
+		Visit::Children
+	}
+
+	fn visit_single_declaration_no_type(&mut self, declaration: &SingleDeclarationNoType) -> Visit {
+		self.add_variable_name(&declaration.ident.ident);
+		Visit::Children
+	}
+
+	fn visit_function_parameter_declarator(
+		&mut self,
+		declarator: &FunctionParameterDeclarator
+	) -> Visit {
+		self.add_variable_name(&declarator.ident.ident);
+		Visit::Children
+	}
+
+	fn visit_condition(&mut self, condition: &Condition) -> Visit {
+		if let ConditionData::Assignment(_, name, _) = &condition.content {
+			self.add_variable_name(name);
+		}
+		Visit::Children
+	}
+
+	fn visit_struct_specifier(&mut self, struct_specifier: &StructSpecifier) -> Visit {
+		for field in &struct_specifier.fields {
+			for name in &field.identifiers {
+				self.fixed_identifiers.insert(name.ident.0.to_string());
+			}
+		}
+		Visit::Children
+	}
+
+	fn visit_block(&mut self, block: &Block) -> Visit {
+		self.fixed_identifiers.insert(block.name.0.to_string());
+		for field in &block.fields {
+			for name in &field.identifiers {
+				self.fixed_identifiers.insert(name.ident.0.to_string());
+			}
+		}
+		if let Some(name) = &block.identifier {
+			self.fixed_identifiers.insert(name.ident.0.to_string());
+		}
+		Visit::Children
+	}
+
+	fn visit_expr(&mut self, expr: &Expr) -> Visit {
+		// Fields and swizzles are accessed with the dot operator
+		if let ExprData::Dot(_, field) = &expr.content {
+			self.fixed_identifiers.insert(field.0.to_string());
+		}
+		Visit::Children
+	}
+
+	fn visit_layout_qualifier_spec(&mut self, layout_qualifier_spec: &LayoutQualifierSpec) -> Visit {
+		if let LayoutQualifierSpecData::Identifier(name, _) = &layout_qualifier_spec.content {
+			self.fixed_identifiers.insert(name.0.to_string());
+		}
+		Visit::Children
+	}
+
+	fn visit_identifier(&mut self, identifier: &Identifier) -> Visit {
+		if let Some(uses) = self.renamable_identifiers.get_mut(identifier.0.as_str()) {
+			*uses += 1;
+		}
+		self.used_names.insert(identifier.0.to_string());
+		Visit::Children
+	}
+
+	fn visit_type_name(&mut self, type_name: &TypeName) -> Visit {
+		// Type names other than those of functions can't be renamed. Functions may be called
+		// like constructors of types with their name
+		self.used_names.insert(type_name.0.to_string());
+		Visit::Children
+	}
+}
+
+/// A GLSL AST visitor that renames identifiers, including the type names used to call
+/// functions, according to a map of their current names to their new names.
+struct IdentifierRenamer {
+	new_names: AHashMap<String, String>
+}
+
+impl VisitorMut for IdentifierRenamer {
+	fn visit_identifier(&mut self, identifier: &mut Identifier) -> Visit {
+		if let Some(new_name) = self.new_names.get(identifier.0.as_str()) {
+			identifier.0 = new_name.as_str().into();
+		}
+		Visit::Children
+	}
+
+	fn visit_type_name(&mut self, type_name: &mut TypeName) -> Visit {
+		if let Some(new_name) = self.new_names.get(type_name.0.as_str()) {
+			type_name.0 = new_name.as_str().into();
+		}
+		Visit::Children
+	}
+}
+
+/// Returns the names an external declaration defines if it may be removed when such names
+/// are not referenced, or `None` if it must always be kept. Function definitions and
+/// prototypes are removable, except those of the `main` function, and so are declarations
+/// of uniform variables.
+fn removable_declaration_names(external_declaration: &ExternalDeclaration) -> Option<Vec<String>> {
+	match &external_declaration.content {
+		ExternalDeclarationData::FunctionDefinition(function_definition) => {
+			let name = function_definition.prototype.name.0.as_str();
+			(name != "main").then(|| vec![name.to_owned()])
+		}
+		ExternalDeclarationData::Declaration(declaration) => match &declaration.content {
+			DeclarationData::FunctionPrototype(function_prototype) => {
+				let name = function_prototype.name.0.as_str();
+				(name != "main").then(|| vec![name.to_owned()])
+			}
+			DeclarationData::InitDeclaratorList(init_declarator_list) => {
+				let head = &init_declarator_list.head;
+
+				head.ty
+					.qualifier
+					.as_ref()
+					.is_some_and(|qualifier| {
+						qualifier.qualifiers.iter().any(|qualifier| {
+							matches!(
+								&qualifier.content,
+								TypeQualifierSpecData::Storage(storage_qualifier)
+									if matches!(storage_qualifier.content, StorageQualifierData::Uniform)
+							)
+						})
+					})
+					.then(|| {
+						head.name
+							.iter()
+							.chain(
+								init_declarator_list
+									.tail
+									.iter()
+									.map(|declaration| &declaration.ident.ident)
+							)
+							.map(|name| name.0.to_string())
+							.collect()
+					})
+			}
+			_ => None
+		},
+		_ => None
+	}
+}
+
+/// The names of the built-in functions of every desktop GLSL version. Built-in variables and
+/// constants are not listed, as their names start with the reserved `gl_` prefix.
+const BUILT_IN_FUNCTIONS: &[&str] = &[
+	"abs",
+	"acos",
+	"acosh",
+	"all",
+	"allInvocations",
+	"allInvocationsEqual",
+	"any",
+	"anyInvocation",
+	"asin",
+	"asinh",
+	"atan",
+	"atanh",
+	"atomicAdd",
+	"atomicAnd",
+	"atomicCompSwap",
+	"atomicCounter",
+	"atomicCounterDecrement",
+	"atomicCounterIncrement",
+	"atomicExchange",
+	"atomicMax",
+	"atomicMin",
+	"atomicOr",
+	"atomicXor",
+	"barrier",
+	"bitCount",
+	"bitfieldExtract",
+	"bitfieldInsert",
+	"bitfieldReverse",
+	"ceil",
+	"clamp",
+	"cos",
+	"cosh",
+	"cross",
+	"dFdx",
+	"dFdxCoarse",
+	"dFdxFine",
+	"dFdy",
+	"dFdyCoarse",
+	"dFdyFine",
+	"degrees",
+	"determinant",
+	"distance",
+	"dot",
+	"EmitStreamVertex",
+	"EmitVertex",
+	"EndPrimitive",
+	"EndStreamPrimitive",
+	"equal",
+	"exp",
+	"exp2",
+	"faceforward",
+	"findLSB",
+	"findMSB",
+	"floatBitsToInt",
+	"floatBitsToUint",
+	"floor",
+	"fma",
+	"fract",
+	"frexp",
+	"ftransform",
+	"fwidth",
+	"fwidthCoarse",
+	"fwidthFine",
+	"greaterThan",
+	"greaterThanEqual",
+	"groupMemoryBarrier",
+	"imageAtomicAdd",
+	"imageAtomicAnd",
+	"imageAtomicCompSwap",
+	"imageAtomicExchange",
+	"imageAtomicMax",
+	"imageAtomicMin",
+	"imageAtomicOr",
+	"imageAtomicXor",
+	"imageLoad",
+	"imageSamples",
+	"imageSize",
+	"imageStore",
+	"imulExtended",
+	"intBitsToFloat",
+	"interpolateAtCentroid",
+	"interpolateAtOffset",
+	"interpolateAtSample",
+	"inverse",
+	"inversesqrt",
+	"isinf",
+	"isnan",
+	"ldexp",
+	"length",
+	"lessThan",
+	"lessThanEqual",
+	"log",
+	"log2",
+	"matrixCompMult",
+	"max",
+	"memoryBarrier",
+	"memoryBarrierAtomicCounter",
+	"memoryBarrierBuffer",
+	"memoryBarrierImage",
+	"memoryBarrierShared",
+	"min",
+	"mix",
+	"mod",
+	"modf",
+	"noise1",
+	"noise2",
+	"noise3",
+	"noise4",
+	"normalize",
+	"not",
+	"notEqual",
+	"outerProduct",
+	"packDouble2x32",
+	"packHalf2x16",
+	"packSnorm2x16",
+	"packSnorm4x8",
+	"packUnorm2x16",
+	"packUnorm4x8",
+	"pow",
+	"radians",
+	"reflect",
+	"refract",
+	"round",
+	"roundEven",
+	"shadow1D",
+	"shadow1DLod",
+	"shadow1DProj",
+	"shadow1DProjLod",
+	"shadow2D",
+	"shadow2DLod",
+	"shadow2DProj",
+	"shadow2DProjLod",
+	"sign",
+	"sin",
+	"sinh",
+	"smoothstep",
+	"sqrt",
+	"step",
+	"tan",
+	"tanh",
+	"texelFetch",
+	"texelFetchOffset",
+	"texture",
+	"texture1D",
+	"texture1DLod",
+	"texture1DProj",
+	"texture1DProjLod",
+	"texture2D",
+	"texture2DLod",
+	"texture2DProj",
+	"texture2DProjLod",
+	"texture3D",
+	"texture3DLod",
+	"texture3DProj",
+	"texture3DProjLod",
+	"textureCube",
+	"textureCubeLod",
+	"textureGather",
+	"textureGatherOffset",
+	"textureGatherOffsets",
+	"textureGrad",
+	"textureGradOffset",
+	"textureLod",
+	"textureLodOffset",
+	"textureOffset",
+	"textureProj",
+	"textureProjGrad",
+	"textureProjGradOffset",
+	"textureProjLod",
+	"textureProjLodOffset",
+	"textureProjOffset",
+	"textureQueryLevels",
+	"textureQueryLod",
+	"textureSamples",
+	"textureSize",
+	"transpose",
+	"trunc",
+	"uaddCarry",
+	"uintBitsToFloat",
+	"umulExtended",
+	"unpackDouble2x32",
+	"unpackHalf2x16",
+	"unpackSnorm2x16",
+	"unpackSnorm4x8",
+	"unpackUnorm2x16",
+	"unpackUnorm4x8",
+	"usubBorrow"
+];
+
+/// Returns the first identifier used as a variable or function name in the specified
+/// translation unit that is not declared anywhere in it, nor is a built-in one, with the
+/// byte offset of its first use, if known.
+///
+/// Scopes are not taken into account, so identifiers declared in some scope are considered
+/// declared everywhere. This way, undeclared identifiers are only reported when there is no
+/// doubt the shader will fail to compile. Identifiers reserved for built-in variables and
+/// functions introduced by extensions are never reported, and neither are identifiers that
+/// look like macro names, as Minecraft may define macros before compiling shaders.
+fn first_undeclared_identifier(
+	translation_unit: &TranslationUnit
+) -> Option<(String, Option<usize>)> {
+	let mut identifier_collector = VariableIdentifierCollector {
+		declared_identifiers: AHashSet::new(),
+		used_identifiers: Vec::new()
+	};
+	translation_unit.visit(&mut identifier_collector);
+
+	identifier_collector
+		.used_identifiers
+		.into_iter()
+		.filter(|(identifier, _)| {
+			!identifier_collector
+				.declared_identifiers
+				.contains(identifier)
+				&& !BUILT_IN_FUNCTIONS.contains(&identifier.as_str())
+				&& !identifier.starts_with("gl_")
+				&& !["ARB", "EXT", "NV", "AMD", "INTEL", "KHR", "OES"]
+					.iter()
+					.any(|vendor_suffix| identifier.ends_with(vendor_suffix))
+				&& !identifier
+					.bytes()
+					.all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit() || byte == b'_')
+		})
+		.min_by_key(|(_, offset)| offset.unwrap_or(usize::MAX))
+}
+
+/// A GLSL AST visitor that collects the identifiers used as variables or called as functions,
+/// and every other identifier, which is considered declared.
+struct VariableIdentifierCollector {
+	declared_identifiers: AHashSet<String>,
+	used_identifiers: Vec<(String, Option<usize>)>
+}
+
+impl Visitor for VariableIdentifierCollector {
+	fn visit_expr(&mut self, expr: &Expr) -> Visit {
+		match &expr.content {
+			ExprData::Variable(identifier) => {
+				self.used_identifiers.push((
+					identifier.0.to_string(),
+					identifier
+						.span
+						.as_ref()
+						.map(|span| span.range().start().into())
+				));
+
+				Visit::Parent
+			}
+			// Calls to functions are parsed as calls to constructors of types with their name,
+			// which must not be considered declared by the call itself
+			ExprData::FunCall(function_identifier, arguments) => {
+				let FunIdentifierData::TypeSpecifier(type_specifier) = &function_identifier.content
+				else {
+					return Visit::Children;
+				};
+				let TypeSpecifierNonArrayData::TypeName(function_name) = &type_specifier.ty.content
+				else {
+					return Visit::Children;
+				};
+
+				self.used_identifiers.push((
+					function_name.0.to_string(),
+					function_name
+						.span
+						.as_ref()
+						.map(|span| span.range().start().into())
+				));
+
+				if let Some(array_specifier) = &type_specifier.array_specifier {
+					array_specifier.visit(self);
+				}
+				for argument in arguments {
+					argument.visit(self);
+				}
+
+				Visit::Parent
+			}
+			_ => Visit::Children
+		}
+	}
+
+	fn visit_identifier(&mut self, identifier: &Identifier) -> Visit {
+		self.declared_identifiers.insert(identifier.0.to_string());
+		Visit::Children
+	}
+
+	// Struct types are declared with a type name
+	fn visit_type_name(&mut self, type_name: &TypeName) -> Visit {
+		self.declared_identifiers.insert(type_name.0.to_string());
+		Visit::Children
+	}
+}
+
+/// A GLSL AST visitor that collects every identifier it visits.
+struct IdentifierCollector {
+	identifiers: AHashSet<String>
+}
+
+impl Visitor for IdentifierCollector {
+	fn visit_identifier(&mut self, identifier: &Identifier) -> Visit {
+		self.identifiers.insert(identifier.0.to_string());
+		Visit::Children
+	}
+
+	// Calls to functions may be parsed as calls to constructors of types with their name
+	fn visit_type_name(&mut self, type_name: &TypeName) -> Visit {
+		self.identifiers.insert(type_name.0.to_string());
+		Visit::Children
+	}
+}
+
 /// Represents a GLSL grammar symbol that can be transpiled back to GLSL.
 pub trait Transpilable {
 	/// Transpiles this GLSL symbol to GLSL. The output code is minified
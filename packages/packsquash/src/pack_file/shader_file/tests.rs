@@ -1,4 +1,5 @@
 use crate::pack_file::util::BOM_UTF8;
+use glsl_lang::ast::ExternalDeclarationData;
 use pretty_assertions::assert_eq;
 use std::fmt::Debug;
 use tokio_stream::StreamExt;
@@ -264,3 +265,242 @@ async fn include_shaders_with_preprocessor_directives_are_passed_through() {
 	)
 	.await
 }
+
+#[tokio::test]
+async fn unused_code_is_stripped() {
+	let shader_parser = Parser::new();
+
+	let mut data_stream = ShaderFile {
+		read: Builder::new().read(FRAGMENT_SHADER_DATA).build(),
+		file_length_hint: FRAGMENT_SHADER_DATA.len(),
+		is_vertex_or_fragment_shader: true,
+		optimization_settings: ShaderFileOptions {
+			strip_unused_code: true,
+			..Default::default()
+		}
+	}
+	.process();
+
+	let (strategy_message, data) = data_stream
+		.next()
+		.await
+		.expect("Expected some result for this input")
+		.expect("No error should happen while decoding");
+
+	assert_eq!(strategy_message, "Minified, with unused code stripped");
+
+	let mut expected_ast = shader_parser
+		.parse::<TranslationUnit>(FRAGMENT_SHADER_DATA, true)
+		.expect("The test input data should be a valid GLSL symbol")
+		.expect("The test input data should be safe to transform");
+	// The unused rand function should be stripped, but the used uniforms should be kept
+	expected_ast.0.retain(|external_declaration| {
+		!matches!(
+			&external_declaration.content,
+			ExternalDeclarationData::FunctionDefinition(function_definition)
+				if function_definition.prototype.name.0 == "rand"
+		)
+	});
+
+	assert_eq!(
+		shader_parser
+			.parse::<TranslationUnit>(&data, true)
+			.expect("The result should be a valid GLSL symbol"),
+		Some(expected_ast)
+	);
+}
+
+/// Processes the given vertex or fragment shader source with the given settings, expecting
+/// an error whose message equals the expected one.
+async fn failed_process_test(input_data: &[u8], settings: ShaderFileOptions, expected_error: &str) {
+	let mut data_stream = ShaderFile {
+		read: Builder::new().read(input_data).build(),
+		file_length_hint: input_data.len(),
+		is_vertex_or_fragment_shader: true,
+		optimization_settings: settings
+	}
+	.process();
+
+	let error = data_stream
+		.next()
+		.await
+		.expect("Expected some result for this input")
+		.expect_err("Expected an error for this input");
+
+	assert_eq!(error.to_string(), expected_error);
+}
+
+#[tokio::test]
+async fn diagnostics_are_precise() {
+	failed_process_test(
+		b"#version 150\n\nvoid main() {\n\tfloat x = 1.0;\n\tgl_Position = vec4(y);\n}\n",
+		ShaderFileOptions::default(),
+		"Shader error: Undeclared identifier \"y\" at line 5, column 21"
+	)
+	.await;
+
+	failed_process_test(
+		b"#version 150\n\nvoid main() {\n\tgl_Position = vec4(undeclaredFunction(1.0));\n}\n",
+		ShaderFileOptions::default(),
+		"Shader error: Undeclared identifier \"undeclaredFunction\" at line 4, column 21"
+	)
+	.await;
+
+	failed_process_test(
+		b"#version 150 es\n\nvoid main() {}\n",
+		ShaderFileOptions::default(),
+		"Invalid #version directive at line 1, column 10: the es profile is not available in desktop OpenGL"
+	)
+	.await;
+
+	failed_process_test(
+		b"#version 330 core\n\nvoid main() {}\n",
+		ShaderFileOptions {
+			maximum_glsl_version: Some(150),
+			..Default::default()
+		},
+		"Invalid #version directive at line 1, column 10: GLSL version 330 is newer than the maximum allowed version, 150"
+	)
+	.await;
+}
+
+#[test]
+fn guaranteed_glsl_versions_follow_minecraft_requirements() {
+	let guaranteed_version = |minimum: &str| {
+		guaranteed_glsl_version(
+			MinecraftVersionRange::try_from([
+				MinecraftVersion::try_from(minimum.to_string()).unwrap(),
+				MinecraftVersion::try_from("1.21.8".to_string()).unwrap()
+			])
+			.unwrap()
+		)
+	};
+
+	assert_eq!(guaranteed_version("1.16.5"), 120);
+	assert_eq!(guaranteed_version("1.17"), 150);
+	assert_eq!(guaranteed_version("1.21.4"), 150);
+	assert_eq!(guaranteed_version("1.21.5"), 330);
+	assert!(validate_version_directive("#version 330\n", Some(guaranteed_version("1.21.5"))).is_ok());
+}
+
+#[test]
+fn source_positions_are_computed() {
+	let source = "void main() {\n\tfloat ñ = 1.0;\n}";
+
+	assert_eq!(
+		SourcePosition::from_offset(source, source.find('=').unwrap()).to_string(),
+		"line 2, column 10"
+	);
+	assert_eq!(
+		SourcePosition::from_offset(source, usize::MAX).to_string(),
+		"line 3, column 2"
+	);
+}
+
+#[tokio::test]
+async fn identifier_renaming_works() {
+	const SHADER_SOURCE: &[u8] = b"#version 150
+
+uniform sampler2D Sampler0;
+in vec2 texCoord0;
+out vec4 fragColor;
+
+vec4 sampleTexture(vec2 coordinates) {
+	vec4 sampledColor = texture(Sampler0, coordinates);
+	return sampledColor;
+}
+
+void main() {
+	vec4 textureColor = sampleTexture(texCoord0);
+	fragColor = textureColor.rgba;
+}
+";
+
+	let mut data_stream = ShaderFile {
+		read: Builder::new().read(SHADER_SOURCE).build(),
+		file_length_hint: SHADER_SOURCE.len(),
+		is_vertex_or_fragment_shader: true,
+		optimization_settings: ShaderFileOptions {
+			source_transformation_strategy:
+				ShaderSourceTransformationStrategy::MinifyAndRenameIdentifiers,
+			..Default::default()
+		}
+	}
+	.process();
+
+	let (strategy_message, data) = data_stream
+		.next()
+		.await
+		.expect("Expected some result for this input")
+		.expect("No error should happen while decoding");
+
+	assert_eq!(strategy_message, "Minified, with identifiers renamed");
+
+	let output_data_str = std::str::from_utf8(&data).expect("The output should be valid UTF-8");
+	for preserved_identifier in ["Sampler0", "texCoord0", "fragColor", "main", ".rgba"] {
+		assert!(
+			output_data_str.contains(preserved_identifier),
+			"{preserved_identifier} should not be renamed"
+		);
+	}
+	for renamed_identifier in [
+		"sampleTexture",
+		"coordinates",
+		"sampledColor",
+		"textureColor"
+	] {
+		assert!(
+			!output_data_str.contains(renamed_identifier),
+			"{renamed_identifier} should be renamed"
+		);
+	}
+
+	assert!(
+		Parser::new()
+			.parse::<TranslationUnit>(&data, true)
+			.expect("The result should be a valid GLSL symbol")
+			.is_some(),
+		"The result should be safe to transform"
+	);
+}
+
+#[cfg(feature = "shader-compilation-check")]
+#[tokio::test]
+async fn compilation_errors_are_caught() {
+	let settings = ShaderFileOptions {
+		compilation_check: true,
+		is_fragment_shader: true,
+		..Default::default()
+	};
+
+	successful_process_test::<TranslationUnit>(
+		b"#version 150\n\nout vec4 fragColor;\n\nvoid main() {\n\tfragColor = vec4(1.0);\n}\n",
+		false,
+		settings,
+		true,
+		true,
+		true,
+		false,
+		false
+	)
+	.await;
+
+	let input_data =
+		b"#version 150\n\nout vec4 fragColor;\n\nvoid main() {\n\tvec3 color = vec4(1.0);\n\tfragColor = vec4(color, 1.0);\n}\n";
+	let error = ShaderFile {
+		read: Builder::new().read(input_data).build(),
+		file_length_hint: input_data.len(),
+		is_vertex_or_fragment_shader: true,
+		optimization_settings: settings
+	}
+	.process()
+	.next()
+	.await
+	.expect("Expected some result for this input")
+	.expect_err("Expected an error for this input");
+
+	assert!(
+		matches!(error, OptimizationError::Compilation(_)),
+		"Unexpected error: {error}"
+	);
+}
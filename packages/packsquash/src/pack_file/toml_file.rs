@@ -0,0 +1,157 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::str::Utf8Error;
+
+use bytes::BytesMut;
+use thiserror::Error;
+use tokio::io::AsyncRead;
+use tokio_util::codec::{Decoder, FramedRead};
+use toml::{Table, Value};
+
+use super::{AsyncReadAndSizeHint, PackFile, PackFileConstructor};
+use crate::pack_file::asset_type::PackFileAssetType;
+use crate::pack_file::strip_utf8_bom;
+
+#[cfg(test)]
+mod tests;
+
+/// Represents a TOML file, which Minecraft does not read, but some mods and plugins use for
+/// their configuration and assets. These files are only processed when the user opts into it
+/// by marking them as custom files in TOML format.
+///
+/// References:
+/// - <https://toml.io/en/v1.0.0>
+pub struct TomlFile<T: AsyncRead + Send + Unpin + 'static> {
+	read: T,
+	file_length_hint: usize
+}
+
+pub struct OptimizerDecoder {
+	reached_eof: bool
+}
+
+/// Represents an error that may happen while optimizing TOML files.
+#[derive(Error, Debug)]
+pub enum OptimizationError {
+	#[error("Invalid UTF-8 character encoding: {0}")]
+	InvalidUtf8(#[from] Utf8Error),
+	#[error("TOML error: {0}")]
+	Toml(#[from] toml::de::Error),
+	#[error("I/O error: {0}")]
+	Io(#[from] std::io::Error)
+}
+
+impl Decoder for OptimizerDecoder {
+	type Item = (Cow<'static, str>, String);
+	type Error = OptimizationError;
+
+	fn decode(&mut self, _: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+		Ok(None)
+	}
+
+	fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+		// This method will be called when EOF is reached until it returns None. Because we
+		// will only ever output a single item in the stream, always return None if we have
+		// executed once already
+		if self.reached_eof {
+			return Ok(None);
+		}
+		self.reached_eof = true;
+
+		let table: Table = toml::from_str(std::str::from_utf8(strip_utf8_bom(src))?)?;
+
+		let mut minified_toml = String::with_capacity(src.len());
+		for (key, value) in &table {
+			write_key(key, &mut minified_toml);
+			minified_toml.push('=');
+			write_value(value, &mut minified_toml);
+			minified_toml.push('\n');
+		}
+
+		Ok(Some((
+			Cow::Borrowed("Minified (custom asset)"),
+			minified_toml
+		)))
+	}
+}
+
+/// Writes a TOML key to the output, quoting it only if it can't be a bare key.
+fn write_key(key: &str, output: &mut String) {
+	let is_bare_key = !key.is_empty()
+		&& key
+			.bytes()
+			.all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-');
+
+	if is_bare_key {
+		output.push_str(key);
+	} else {
+		write!(output, "{}", Value::String(key.into())).unwrap();
+	}
+}
+
+/// Writes a TOML value to the output in its most compact representation, which uses inline
+/// tables for nested tables and no whitespace between elements.
+fn write_value(value: &Value, output: &mut String) {
+	match value {
+		Value::Array(array) => {
+			output.push('[');
+			for (i, element) in array.iter().enumerate() {
+				if i > 0 {
+					output.push(',');
+				}
+				write_value(element, output);
+			}
+			output.push(']');
+		}
+		Value::Table(table) => {
+			output.push('{');
+			for (i, (key, value)) in table.iter().enumerate() {
+				if i > 0 {
+					output.push(',');
+				}
+				write_key(key, output);
+				output.push('=');
+				write_value(value, output);
+			}
+			output.push('}');
+		}
+		scalar => write!(output, "{scalar}").unwrap()
+	}
+}
+
+impl<T: AsyncRead + Send + Unpin + 'static> PackFile for TomlFile<T> {
+	type ByteChunkType = String;
+	type OptimizationError = OptimizationError;
+	type OptimizedByteChunksStream = FramedRead<T, OptimizerDecoder>;
+
+	fn process(self) -> Self::OptimizedByteChunksStream {
+		FramedRead::with_capacity(
+			self.read,
+			OptimizerDecoder { reached_eof: false },
+			self.file_length_hint
+		)
+	}
+
+	fn is_compressed(&self) -> bool {
+		false
+	}
+
+	fn is_force_included(&self) -> bool {
+		true
+	}
+}
+
+impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for TomlFile<T> {
+	type OptimizationSettings = ();
+
+	fn new(
+		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<T>>,
+		_: PackFileAssetType,
+		_: Self::OptimizationSettings
+	) -> Option<Self> {
+		file_read_producer().map(|(read, size_hint)| Self {
+			read,
+			file_length_hint: size_hint.try_into().unwrap_or(usize::MAX)
+		})
+	}
+}
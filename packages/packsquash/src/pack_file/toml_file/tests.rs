@@ -0,0 +1,65 @@
+use futures::StreamExt;
+use pretty_assertions::assert_eq;
+use tokio_test::io::Builder;
+
+use super::*;
+
+/// Processes the given input data as a [TomlFile], returning the optimized data or the first
+/// error that happened.
+async fn process(input: &str) -> Result<String, OptimizationError> {
+	let mut optimized_data_stream = TomlFile {
+		read: Builder::new().read(input.as_bytes()).build(),
+		file_length_hint: input.len()
+	}
+	.process();
+
+	let mut data = String::new();
+	while let Some(result) = optimized_data_stream.next().await {
+		data.push_str(&result?.1);
+	}
+
+	Ok(data)
+}
+
+#[tokio::test]
+async fn minifying_works() {
+	let input = "\u{feff}# Mod settings
+title = \"Ruby \\\"tools\\\"\"
+enabled = true
+
+[tools]
+durability = 250
+speed = 6.5
+names = [ \"pickaxe\", \"axe\" ]
+
+[tools.\"special tool\"]
+glow = false
+
+[[recipes]]
+id = 1
+";
+
+	let minified = process(input)
+		.await
+		.expect("No error should happen while processing");
+
+	assert_eq!(
+		toml::from_str::<Table>(&minified).expect("The minified TOML should be valid"),
+		toml::from_str::<Table>(input.trim_start_matches('\u{feff}')).unwrap()
+	);
+	assert!(
+		minified.len() < input.len(),
+		"Minifying this file should yield a smaller result"
+	);
+}
+
+#[tokio::test]
+async fn invalid_toml_is_rejected() {
+	assert!(
+		matches!(
+			process("key = \"unterminated").await,
+			Err(OptimizationError::Toml(_))
+		),
+		"Expected a TOML error"
+	);
+}
@@ -0,0 +1,356 @@
+//! Contains routines to read and write the ZIP files that some pack files are, such as the
+//! ZIP files of Unifont `.hex` glyphs and nested archives.
+
+use flate2::read::DeflateDecoder;
+use std::cmp;
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Take};
+use std::num::NonZeroU64;
+use thiserror::Error;
+
+use crate::zopfli_iterations_time_model::ZopfliIterationsTimeModel;
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum number of Zopfli iterations that will be done to compress a ZIP file entry,
+/// which matches the maximum the output ZIP file uses.
+const MAXIMUM_ZOPFLI_ITERATIONS: u8 = 20;
+
+/// The DOS date of every entry of the written ZIP files, which is January 1, 1980: the
+/// earliest date that can be represented. Minecraft does not use entry dates.
+const DOS_EPOCH_DATE: u16 = (1 << 5) | 1;
+
+/// The maximum ratio between the uncompressed and compressed sizes of Deflate data. The sizes
+/// declared by ZIP file headers can't be trusted, so this bounds the memory allocated upfront
+/// to decompress an entry by the size of its compressed data.
+const MAXIMUM_DEFLATE_COMPRESSION_RATIO: usize = 1032;
+
+/// The MS-DOS directory attribute, set on the external attributes of directory entries.
+const DOS_DIRECTORY_ATTRIBUTE: u32 = 0x10;
+
+/// Represents an error that may happen while reading a ZIP file.
+#[derive(Error, Debug)]
+pub enum ZipArchiveError {
+	#[error("Invalid ZIP file: {0}")]
+	InvalidZip(&'static str),
+	#[error("The ZIP file entry {0} uses an unsupported compression method or is encrypted")]
+	UnsupportedZipEntry(String),
+	#[error("The ZIP file entry {0} is corrupt: its size or CRC does not match its data")]
+	CorruptZipEntry(String),
+	#[error("I/O error: {0}")]
+	Io(#[from] io::Error)
+}
+
+/// A file or directory stored in a ZIP file. Directories have a name that ends with a slash
+/// and no data.
+pub struct ZipEntry {
+	pub name: String,
+	pub data: Vec<u8>
+}
+
+/// The data of a file stored in a ZIP file, which is decompressed as it is read, so that
+/// entries can be processed without holding their whole data in memory.
+pub struct ZipEntryData<'zip, R: Read> {
+	reader: ZipEntryDataReader<'zip, R>,
+	size_hint: usize,
+	read_size: usize,
+	crc_hasher: crc32fast::Hasher
+}
+
+/// The reader of the possibly compressed data of a ZIP file entry, which stops at the size
+/// declared for it.
+enum ZipEntryDataReader<'zip, R: Read> {
+	Stored(Take<&'zip mut R>),
+	Deflated(Take<DeflateDecoder<Take<&'zip mut R>>>)
+}
+
+impl<R: Read> ZipEntryData<'_, R> {
+	/// Returns a size, in bytes, suitable to allocate upfront to hold the data of this entry.
+	/// As the sizes declared by ZIP file headers can't be trusted, this is bounded by the size
+	/// the compressed data of the entry may decompress to.
+	pub const fn size_hint(&self) -> usize {
+		self.size_hint
+	}
+}
+
+impl<R: Read> Read for ZipEntryData<'_, R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let read_bytes = match &mut self.reader {
+			ZipEntryDataReader::Stored(reader) => reader.read(buf)?,
+			ZipEntryDataReader::Deflated(reader) => reader.read(buf)?
+		};
+
+		self.read_size += read_bytes;
+		self.crc_hasher.update(&buf[..read_bytes]);
+
+		Ok(read_bytes)
+	}
+}
+
+/// Reads the entries of the specified ZIP file from its central directory. Only the ZIP
+/// features `java.util.zip.ZipInputStream` supports are supported: ZIP64 extensions,
+/// encryption and compression methods other than stored and Deflate are not. The data of every
+/// file is checked against the size and CRC declared for it. Directory entries are returned
+/// too, so that writing the entries again keeps them.
+pub fn read_zip_entries(zip: &[u8]) -> Result<Vec<ZipEntry>, ZipArchiveError> {
+	let mut entries = vec![];
+
+	visit_zip_entries(Cursor::new(zip), |name, data| {
+		let mut entry_data = Vec::with_capacity(data.size_hint());
+		data.read_to_end(&mut entry_data)?;
+
+		entries.push(ZipEntry {
+			name,
+			data: entry_data
+		});
+
+		Ok(())
+	})?;
+
+	Ok(entries)
+}
+
+/// Reads the entries of the specified ZIP file from its central directory one at a time,
+/// calling the specified visitor with the name and data of each entry, in central directory
+/// order, without reading the whole ZIP file into memory. The same ZIP features as
+/// [`read_zip_entries`] are supported, and the data of every file is checked against the size
+/// and CRC declared for it after it is visited, so the visitor may see the data of a corrupt
+/// entry before an error is returned for it. Directory entries are visited too, with no data.
+///
+/// References:
+/// - <https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT>
+pub fn visit_zip_entries<R: Read + Seek>(
+	mut zip: R,
+	mut visitor: impl FnMut(String, &mut ZipEntryData<'_, R>) -> io::Result<()>
+) -> Result<(), ZipArchiveError> {
+	const END_OF_CENTRAL_DIRECTORY_SIGNATURE: [u8; 4] = [b'P', b'K', 5, 6];
+	const CENTRAL_DIRECTORY_HEADER_SIGNATURE: [u8; 4] = [b'P', b'K', 1, 2];
+	const LOCAL_FILE_HEADER_SIGNATURE: [u8; 4] = [b'P', b'K', 3, 4];
+
+	let zip_length = zip.seek(SeekFrom::End(0))?;
+
+	// The end of central directory record is at the end of the file, followed by a comment
+	// of up to 65535 bytes
+	let tail_length = cmp::min(zip_length, 22 + u16::MAX as u64);
+	let tail_offset = zip_length - tail_length;
+	let mut tail = vec![0; tail_length as usize];
+	zip.seek(SeekFrom::Start(tail_offset))?;
+	zip.read_exact(&mut tail)?;
+
+	let end_of_central_directory_offset = tail
+		.windows(4)
+		.rev()
+		.position(|window| window == END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+		.map(|reverse_position| tail.len() - 4 - reverse_position)
+		.ok_or(ZipArchiveError::InvalidZip(
+			"missing end of central directory"
+		))?;
+	let entry_count = u16_at(&tail, end_of_central_directory_offset + 10)?;
+	let central_directory_offset = u32_at(&tail, end_of_central_directory_offset + 16)?;
+
+	// ZIP64 files signal that their actual values are in ZIP64 records with saturated fields
+	if entry_count == u16::MAX as usize || central_directory_offset == u32::MAX as usize {
+		return Err(ZipArchiveError::InvalidZip("ZIP64 files are not supported"));
+	}
+
+	// The central directory goes right before its end record, and is read whole, as it is
+	// small compared to the entry data
+	let central_directory_length = (tail_offset + end_of_central_directory_offset as u64)
+		.checked_sub(central_directory_offset as u64)
+		.ok_or(ZipArchiveError::InvalidZip("invalid central directory"))?;
+	let mut central_directory = vec![0; central_directory_length as usize];
+	zip.seek(SeekFrom::Start(central_directory_offset as u64))?;
+	zip.read_exact(&mut central_directory)?;
+
+	let mut header_offset = 0;
+	for _ in 0..entry_count {
+		if central_directory.get(header_offset..header_offset + 4)
+			!= Some(&CENTRAL_DIRECTORY_HEADER_SIGNATURE[..])
+		{
+			return Err(ZipArchiveError::InvalidZip("invalid central directory"));
+		}
+
+		let flags = u16_at(&central_directory, header_offset + 8)?;
+		let compression_method = u16_at(&central_directory, header_offset + 10)?;
+		let crc = u32_at(&central_directory, header_offset + 16)? as u32;
+		let compressed_size = u32_at(&central_directory, header_offset + 20)?;
+		let uncompressed_size = u32_at(&central_directory, header_offset + 24)?;
+		let name_length = u16_at(&central_directory, header_offset + 28)?;
+		let extra_field_length = u16_at(&central_directory, header_offset + 30)?;
+		let comment_length = u16_at(&central_directory, header_offset + 32)?;
+		let local_file_header_offset = u32_at(&central_directory, header_offset + 42)? as u64;
+		let name = central_directory
+			.get(header_offset + 46..header_offset + 46 + name_length)
+			.map(|name| String::from_utf8_lossy(name).into_owned())
+			.ok_or(ZipArchiveError::InvalidZip("truncated file"))?;
+		header_offset += 46 + name_length + extra_field_length + comment_length;
+
+		// Directories have no data
+		if name.ends_with('/') {
+			visitor(
+				name,
+				&mut ZipEntryData {
+					reader: ZipEntryDataReader::Stored(zip.by_ref().take(0)),
+					size_hint: 0,
+					read_size: 0,
+					crc_hasher: crc32fast::Hasher::new()
+				}
+			)?;
+			continue;
+		}
+
+		let mut local_file_header = [0; 30];
+		if local_file_header_offset + 30 > zip_length {
+			return Err(ZipArchiveError::InvalidZip("truncated file"));
+		}
+		zip.seek(SeekFrom::Start(local_file_header_offset))?;
+		zip.read_exact(&mut local_file_header)?;
+		if local_file_header[..4] != LOCAL_FILE_HEADER_SIGNATURE {
+			return Err(ZipArchiveError::InvalidZip("invalid local file header"));
+		}
+
+		let data_offset = local_file_header_offset
+			+ 30 + u16_at(&local_file_header, 26)? as u64
+			+ u16_at(&local_file_header, 28)? as u64;
+		if data_offset + compressed_size as u64 > zip_length {
+			return Err(ZipArchiveError::InvalidZip("truncated file"));
+		}
+		zip.seek(SeekFrom::Start(data_offset))?;
+		let compressed_data = zip.by_ref().take(compressed_size as u64);
+
+		let (reader, size_hint) = match compression_method {
+			_ if flags & 1 != 0 => return Err(ZipArchiveError::UnsupportedZipEntry(name)),
+			0 => (
+				ZipEntryDataReader::Stored(compressed_data),
+				cmp::min(uncompressed_size, compressed_size)
+			),
+			8 => (
+				ZipEntryDataReader::Deflated(
+					DeflateDecoder::new(compressed_data).take(uncompressed_size as u64)
+				),
+				cmp::min(
+					uncompressed_size,
+					compressed_size.saturating_mul(MAXIMUM_DEFLATE_COMPRESSION_RATIO)
+				)
+			),
+			_ => return Err(ZipArchiveError::UnsupportedZipEntry(name))
+		};
+
+		let mut data = ZipEntryData {
+			reader,
+			size_hint,
+			read_size: 0,
+			crc_hasher: crc32fast::Hasher::new()
+		};
+		visitor(name.clone(), &mut data)?;
+
+		// Check the data the visitor did not read too
+		io::copy(&mut data, &mut io::sink())?;
+		if data.read_size != uncompressed_size || data.crc_hasher.finalize() != crc {
+			return Err(ZipArchiveError::CorruptZipEntry(name));
+		}
+	}
+
+	Ok(())
+}
+
+/// Reads the little-endian 16-bit unsigned integer at the specified offset of some ZIP data.
+fn u16_at(data: &[u8], offset: usize) -> Result<usize, ZipArchiveError> {
+	data.get(offset..offset + 2)
+		.map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()) as usize)
+		.ok_or(ZipArchiveError::InvalidZip("truncated file"))
+}
+
+/// Reads the little-endian 32-bit unsigned integer at the specified offset of some ZIP data.
+fn u32_at(data: &[u8], offset: usize) -> Result<usize, ZipArchiveError> {
+	data.get(offset..offset + 4)
+		.map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+		.ok_or(ZipArchiveError::InvalidZip("truncated file"))
+}
+
+/// Writes a ZIP file with the specified entries, compressing them with Zopfli, unless that
+/// does not save space. The number of Zopfli iterations is chosen like the output ZIP file
+/// does, from the specified number of iterations for a magnitude of 1 MiB of data. The
+/// generated ZIP file does not depend on anything else, so it is always the same for the
+/// same entries. Directory entries are written with the MS-DOS directory attribute.
+pub fn write_zip(entries: &[ZipEntry], compression_iterations: u8) -> Vec<u8> {
+	let zopfli_iterations_time_model =
+		ZopfliIterationsTimeModel::new(compression_iterations, 5.0 / 6.0);
+
+	let mut zip = Vec::new();
+	let mut central_directory = Vec::new();
+	for entry in entries {
+		let mut compressed_data = Vec::new();
+		if compression_iterations > 0 && !entry.data.is_empty() {
+			zopfli::compress(
+				zopfli::Options {
+					iteration_count: NonZeroU64::new(
+						zopfli_iterations_time_model.iterations_for_data_size(
+							entry.data.len().try_into().unwrap_or(u32::MAX),
+							1,
+							MAXIMUM_ZOPFLI_ITERATIONS
+						) as u64
+					)
+					.unwrap(),
+					..Default::default()
+				},
+				zopfli::Format::Deflate,
+				&*entry.data,
+				&mut compressed_data
+			)
+			.ok();
+		}
+
+		let (compression_method, version_needed_to_extract, data) =
+			if !compressed_data.is_empty() && compressed_data.len() < entry.data.len() {
+				(8_u16, 20_u16, &compressed_data)
+			} else {
+				(0, 10, &entry.data)
+			};
+
+		// The fields that the local file header and central directory header have in common
+		let mut common_header_fields = Vec::with_capacity(26);
+		common_header_fields.extend_from_slice(&version_needed_to_extract.to_le_bytes());
+		common_header_fields.extend_from_slice(&0_u16.to_le_bytes());
+		common_header_fields.extend_from_slice(&compression_method.to_le_bytes());
+		common_header_fields.extend_from_slice(&0_u16.to_le_bytes());
+		common_header_fields.extend_from_slice(&DOS_EPOCH_DATE.to_le_bytes());
+		common_header_fields.extend_from_slice(&crc32fast::hash(&entry.data).to_le_bytes());
+		common_header_fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+		common_header_fields.extend_from_slice(&(entry.data.len() as u32).to_le_bytes());
+		common_header_fields.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+		common_header_fields.extend_from_slice(&0_u16.to_le_bytes());
+
+		central_directory.extend_from_slice(&[b'P', b'K', 1, 2]);
+		central_directory.extend_from_slice(&version_needed_to_extract.to_le_bytes());
+		central_directory.extend_from_slice(&common_header_fields);
+		central_directory.extend_from_slice(&[0; 6]);
+		central_directory.extend_from_slice(
+			&if entry.name.ends_with('/') {
+				DOS_DIRECTORY_ATTRIBUTE
+			} else {
+				0
+			}
+			.to_le_bytes()
+		);
+		central_directory.extend_from_slice(&(zip.len() as u32).to_le_bytes());
+		central_directory.extend_from_slice(entry.name.as_bytes());
+
+		zip.extend_from_slice(&[b'P', b'K', 3, 4]);
+		zip.extend_from_slice(&common_header_fields);
+		zip.extend_from_slice(entry.name.as_bytes());
+		zip.extend_from_slice(data);
+	}
+
+	let central_directory_offset = zip.len() as u32;
+	zip.extend_from_slice(&central_directory);
+	zip.extend_from_slice(&[b'P', b'K', 5, 6]);
+	zip.extend_from_slice(&[0; 4]);
+	zip.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+	zip.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+	zip.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+	zip.extend_from_slice(&central_directory_offset.to_le_bytes());
+	zip.extend_from_slice(&0_u16.to_le_bytes());
+
+	zip
+}
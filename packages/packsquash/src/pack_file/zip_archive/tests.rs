@@ -0,0 +1,75 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+#[test]
+fn written_zip_files_can_be_read_back() {
+	let entries = [
+		(
+			"META-INF/MANIFEST.MF",
+			b"Manifest-Version: 1.0\r\n".to_vec()
+		),
+		("assets/mypack/lang/", vec![]),
+		("assets/mypack/lang/en_us.lang", b"a=b\n".repeat(1024)),
+		("empty.txt", vec![])
+	]
+	.map(|(name, data)| ZipEntry {
+		name: name.to_owned(),
+		data
+	});
+
+	let zip = write_zip(&entries, 1);
+
+	// Entries that compress well are compressed, and the output does not depend on anything
+	// other than the entries
+	assert!(zip.len() < entries.iter().map(|entry| entry.data.len()).sum());
+	assert_eq!(zip, write_zip(&entries, 1));
+
+	let read_entries = read_zip_entries(&zip).expect("The written ZIP file should be valid");
+	assert_eq!(
+		read_entries
+			.iter()
+			.map(|entry| (entry.name.as_str(), entry.data.as_slice()))
+			.collect::<Vec<_>>(),
+		entries
+			.iter()
+			.map(|entry| (entry.name.as_str(), entry.data.as_slice()))
+			.collect::<Vec<_>>()
+	);
+}
+
+#[test]
+fn truncated_zip_files_are_rejected() {
+	let zip = write_zip(
+		&[ZipEntry {
+			name: "unifont.hex".to_owned(),
+			data: b"0041:0000000018242442427E424242420000\n".to_vec()
+		}],
+		0
+	);
+
+	assert!(matches!(
+		read_zip_entries(&zip[..zip.len() / 2]),
+		Err(ZipArchiveError::InvalidZip(_))
+	));
+}
+
+#[test]
+fn corrupt_zip_entries_are_rejected() {
+	let mut zip = write_zip(
+		&[ZipEntry {
+			name: "unifont.hex".to_owned(),
+			data: b"0041:0000000018242442427E424242420000\n".to_vec()
+		}],
+		0
+	);
+
+	// Flip a bit of the stored entry data, which follows the 30 byte local file header and
+	// the 11 byte entry name
+	zip[30 + 11] ^= 1;
+
+	assert!(matches!(
+		read_zip_entries(&zip),
+		Err(ZipArchiveError::CorruptZipEntry(name)) if name == "unifont.hex"
+	));
+}
@@ -0,0 +1,201 @@
+use bytes::BytesMut;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use thiserror::Error;
+use tokio::io::AsyncRead;
+use tokio_util::codec::{Decoder, FramedRead};
+
+#[cfg(test)]
+mod tests;
+
+use super::{AsyncReadAndSizeHint, PackFile, PackFileConstructor};
+use crate::config::ZippedUnifontHexFileOptions;
+use crate::pack_file::asset_type::PackFileAssetType;
+use crate::pack_file::zip_archive::{ZipArchiveError, ZipEntry, read_zip_entries, write_zip};
+
+/// Represents a ZIP file that contains glyphs in Unifont's `.hex` format, which is read by
+/// the `unihex` font provider.
+///
+/// Minecraft reads these files with the `java.util.zip.ZipInputStream` class, which only
+/// supports the stored and Deflate compression methods, and parses every entry with a `.hex`
+/// extension in order, ignoring any other entry. A glyph defined by several entries takes
+/// its last definition.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Font#Unihex_provider>
+/// - <https://en.wikipedia.org/wiki/GNU_Unifont#.hex_format>
+/// - Minecraft's 23w17a `net.minecraft.client.gui.font.providers.UnihexProvider` class
+pub struct ZippedUnifontHexFile<T: AsyncRead + Send + Unpin + 'static> {
+	read: T,
+	file_length_hint: usize,
+	optimization_settings: ZippedUnifontHexFileOptions
+}
+
+pub struct OptimizerDecoder {
+	optimization_settings: ZippedUnifontHexFileOptions,
+	reached_eof: bool
+}
+
+/// Represents an error that may happen while optimizing ZIP files of Unifont `.hex` files.
+#[derive(Error, Debug)]
+pub enum OptimizationError {
+	#[error("{0}")]
+	ZipArchive(#[from] ZipArchiveError),
+	#[error("Line {line_number} of {entry_name} is not a valid Unifont .hex glyph definition")]
+	InvalidHexLine {
+		entry_name: String,
+		line_number: usize
+	}
+}
+
+// FIXME: actual framing?
+// (i.e. do not hold the entire file in memory before decoding, so that frame != file)
+impl Decoder for OptimizerDecoder {
+	type Item = (Cow<'static, str>, Vec<u8>);
+	type Error = OptimizationError;
+
+	fn decode(&mut self, _: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+		Ok(None)
+	}
+
+	fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+		// This method will be called when EOF is reached until it returns None. Because we
+		// will only ever output a single item in the stream, always return None if we have
+		// executed once already
+		if self.reached_eof {
+			return Ok(None);
+		}
+		self.reached_eof = true;
+
+		// Minecraft ignores entries without a .hex extension, so drop them
+		let hex_entries = read_zip_entries(src)?
+			.into_iter()
+			.filter(|entry| entry.name.ends_with(".hex"))
+			.collect::<Vec<_>>();
+
+		// Read every glyph definition, validating them. Glyphs defined several times with the
+		// same bitmap are only kept once
+		let mut glyph_definitions = BTreeMap::new();
+		let mut has_conflicting_definitions = false;
+		for entry in &hex_entries {
+			for (codepoint, definition) in read_glyph_definitions(entry)? {
+				if let Some(previous_definition) = glyph_definitions.insert(codepoint, definition) {
+					has_conflicting_definitions |= previous_definition != definition;
+				}
+			}
+		}
+
+		// Merging entries that define some glyph differently is possible, but not worth the
+		// risk of changing which definition is used, as that depends on entry order
+		let (optimization_strategy, optimized_entries) =
+			if self.optimization_settings.merge_unifont_hex_files
+				&& !has_conflicting_definitions
+				&& let Some(first_entry) = hex_entries.first()
+			{
+				let mut merged_data = Vec::new();
+				for definition in glyph_definitions.values() {
+					merged_data.extend_from_slice(definition.as_bytes());
+					merged_data.push(b'\n');
+				}
+
+				(
+					"Validated, merged and recompressed",
+					vec![ZipEntry {
+						name: first_entry.name.clone(),
+						data: merged_data
+					}]
+				)
+			} else {
+				("Validated and recompressed", hex_entries)
+			};
+
+		let optimized_zip = write_zip(
+			&optimized_entries,
+			self.optimization_settings.compression_iterations
+		);
+
+		// Recompressing might not pay off if the input was already compressed better than we
+		// could, in which case the input is kept as-is
+		if optimized_zip.len() < src.len() {
+			Ok(Some((Cow::Borrowed(optimization_strategy), optimized_zip)))
+		} else {
+			Ok(Some((
+				Cow::Borrowed(
+					"Validated, but not optimized further. \
+					If not optimized externally, try tweaking options for extra savings"
+				),
+				src.to_vec()
+			)))
+		}
+	}
+}
+
+impl<T: AsyncRead + Send + Unpin + 'static> PackFile for ZippedUnifontHexFile<T> {
+	type ByteChunkType = Vec<u8>;
+	type OptimizationError = OptimizationError;
+	type OptimizedByteChunksStream = FramedRead<T, OptimizerDecoder>;
+
+	fn process(self) -> Self::OptimizedByteChunksStream {
+		FramedRead::with_capacity(
+			self.read,
+			OptimizerDecoder {
+				optimization_settings: self.optimization_settings,
+				reached_eof: false
+			},
+			// FIXME consider refactoring this when we have a global memory budget
+			self.file_length_hint
+		)
+	}
+
+	fn is_compressed(&self) -> bool {
+		true
+	}
+}
+
+impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for ZippedUnifontHexFile<T> {
+	type OptimizationSettings = ZippedUnifontHexFileOptions;
+
+	fn new(
+		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<T>>,
+		_: PackFileAssetType,
+		optimization_settings: Self::OptimizationSettings
+	) -> Option<Self> {
+		file_read_producer().map(|(read, size_hint)| Self {
+			read,
+			file_length_hint: size_hint.try_into().unwrap_or(usize::MAX),
+			optimization_settings
+		})
+	}
+}
+
+/// Reads the glyph definitions of the specified `.hex` file entry, returning pairs of
+/// codepoints and the lines that define their glyphs. Glyph bitmaps may be 8, 16, 24 or 32
+/// pixels wide, so they are made up of 32, 64, 96 or 128 hexadecimal digits. Empty lines
+/// are skipped.
+fn read_glyph_definitions(entry: &ZipEntry) -> Result<Vec<(u32, &str)>, OptimizationError> {
+	let mut glyph_definitions = vec![];
+	for (line_index, line) in entry.data.split(|byte| *byte == b'\n').enumerate() {
+		let line = line.strip_suffix(b"\r").unwrap_or(line);
+		if line.is_empty() {
+			continue;
+		}
+
+		let glyph_definition = std::str::from_utf8(line)
+			.ok()
+			.and_then(|line| Some((line, line.split_once(':')?)))
+			.filter(|(_, (codepoint, bitmap))| {
+				(1..=6).contains(&codepoint.len())
+					&& [32, 64, 96, 128].contains(&bitmap.len())
+					&& bitmap.bytes().all(|digit| digit.is_ascii_hexdigit())
+			})
+			.and_then(|(line, (codepoint, _))| Some((u32::from_str_radix(codepoint, 16).ok()?, line)))
+			.ok_or_else(|| OptimizationError::InvalidHexLine {
+				entry_name: entry.name.clone(),
+				line_number: line_index + 1
+			})?;
+
+		glyph_definitions.push(glyph_definition);
+	}
+
+	Ok(glyph_definitions)
+}
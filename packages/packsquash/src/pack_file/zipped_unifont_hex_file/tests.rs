@@ -0,0 +1,107 @@
+use super::*;
+
+use futures::StreamExt;
+use pretty_assertions::assert_eq;
+use tokio_test::io::Builder;
+
+/// The glyph of the `A` character in GNU Unifont.
+const GLYPH_A: &str = "0041:0000000018242442427E424242420000";
+/// The glyph of the `B` character in GNU Unifont.
+const GLYPH_B: &str = "0042:000000007C4242427C424242427C0000";
+/// A wider, 16 pixels wide glyph for the `B` character.
+const WIDE_GLYPH_B: &str = "0042:00000000000000007FF0401040104010401040107FF000000000000000000000";
+
+/// Builds a ZIP file with the specified entries, storing them without compression.
+fn stored_zip(entries: &[(&str, &str)]) -> Vec<u8> {
+	write_zip(
+		&entries
+			.iter()
+			.map(|(name, data)| ZipEntry {
+				name: (*name).to_owned(),
+				data: data.as_bytes().to_vec()
+			})
+			.collect::<Vec<_>>(),
+		0
+	)
+}
+
+/// Processes the given input data as a [ZippedUnifontHexFile] with the given settings,
+/// returning the result.
+async fn process(
+	input: &[u8],
+	optimization_settings: ZippedUnifontHexFileOptions
+) -> Result<(Cow<'static, str>, Vec<u8>), OptimizationError> {
+	ZippedUnifontHexFile {
+		read: Builder::new().read(input).build(),
+		file_length_hint: input.len(),
+		optimization_settings
+	}
+	.process()
+	.next()
+	.await
+	.expect("Some result should be produced")
+}
+
+/// Returns the names and contents of the entries of the specified ZIP file.
+fn zip_entries(zip: &[u8]) -> Vec<(String, String)> {
+	read_zip_entries(zip)
+		.expect("The ZIP file should be valid")
+		.into_iter()
+		.map(|entry| {
+			(
+				entry.name,
+				String::from_utf8(entry.data).expect("The entry should be valid UTF-8")
+			)
+		})
+		.collect()
+}
+
+#[tokio::test]
+async fn hex_files_are_merged_and_recompressed() {
+	let input = stored_zip(&[
+		("unifont.hex", &format!("{GLYPH_B}\n{GLYPH_A}\n")),
+		("README.txt", "These glyphs are licensed under the GNU GPL"),
+		("unifont_upper.hex", &format!("\n{GLYPH_A}\n"))
+	]);
+
+	let (_, optimized_zip) = process(&input, ZippedUnifontHexFileOptions::default())
+		.await
+		.expect("No error should happen while processing");
+
+	assert!(optimized_zip.len() < input.len());
+	assert_eq!(
+		zip_entries(&optimized_zip),
+		[("unifont.hex".to_owned(), format!("{GLYPH_A}\n{GLYPH_B}\n"))]
+	);
+}
+
+#[tokio::test]
+async fn hex_files_with_conflicting_glyphs_are_not_merged() {
+	let input = stored_zip(&[
+		("unifont.hex", &format!("{GLYPH_A}\n{GLYPH_B}\n")),
+		("README.txt", "These glyphs are licensed under the GNU GPL"),
+		("unifont_upper.hex", &format!("{WIDE_GLYPH_B}\n"))
+	]);
+
+	let (_, optimized_zip) = process(&input, ZippedUnifontHexFileOptions::default())
+		.await
+		.expect("No error should happen while processing");
+
+	assert_eq!(
+		zip_entries(&optimized_zip),
+		[
+			("unifont.hex".to_owned(), format!("{GLYPH_A}\n{GLYPH_B}\n")),
+			("unifont_upper.hex".to_owned(), format!("{WIDE_GLYPH_B}\n"))
+		]
+	);
+}
+
+#[tokio::test]
+async fn invalid_hex_lines_are_rejected() {
+	let input = stored_zip(&[("unifont.hex", &format!("{GLYPH_A}\n0042:00FF\n"))]);
+
+	assert!(matches!(
+		process(&input, ZippedUnifontHexFileOptions::default()).await,
+		Err(OptimizationError::InvalidHexLine { line_number: 2, .. })
+	));
+}
@@ -0,0 +1,231 @@
+//! Contains the filters that decide whether pack files are included in the generated ZIP file
+//! depending on their size and modification time, in addition to their path.
+
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::config::ProcessedSquashOptions;
+use crate::vfs::{VfsPackFileIterEntry, VirtualFileSystem};
+
+#[cfg(test)]
+mod tests;
+
+/// A filter that only includes the pack files that meet every one of its conditions. A filter
+/// without conditions includes every pack file.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PackFileFilter {
+	/// The minimum size, in bytes, that pack files must have to be included.
+	///
+	/// **Default value**: none (there is no minimum size)
+	pub minimum_size: Option<u64>,
+	/// The maximum size, in bytes, that pack files can have to be included.
+	///
+	/// **Default value**: none (there is no maximum size)
+	pub maximum_size: Option<u64>,
+	/// The time after which pack files must have been last modified to be included.
+	///
+	/// **Default value**: none (pack files modified at any time are included)
+	pub modified_after: Option<FilterTimestamp>,
+	/// The time before which pack files must have been last modified to be included.
+	///
+	/// **Default value**: none (pack files modified at any time are included)
+	pub modified_before: Option<FilterTimestamp>
+}
+
+impl PackFileFilter {
+	/// Checks whether a pack file with the specified size and modification time meets the
+	/// conditions of this filter. Pack files whose modification time is unknown only meet
+	/// the conditions of filters that do not depend on it.
+	pub fn includes(&self, file_size: u64, modification_time: Option<SystemTime>) -> bool {
+		let time_condition_met =
+			|timestamp: Option<FilterTimestamp>, condition: fn(SystemTime, SystemTime) -> bool| {
+				timestamp.is_none_or(|FilterTimestamp(timestamp)| {
+					modification_time.is_some_and(|time| condition(time, timestamp))
+				})
+			};
+
+		self.minimum_size
+			.is_none_or(|minimum_size| file_size >= minimum_size)
+			&& self
+				.maximum_size
+				.is_none_or(|maximum_size| file_size <= maximum_size)
+			&& time_condition_met(self.modified_after, |time, timestamp| time > timestamp)
+			&& time_condition_met(self.modified_before, |time, timestamp| time < timestamp)
+	}
+
+	/// Returns a brief, user-friendly description of why a pack file with the specified size
+	/// and modification time does not meet the conditions of this filter.
+	pub fn exclusion_reason(
+		&self,
+		file_size: u64,
+		modification_time: Option<SystemTime>
+	) -> &'static str {
+		if self
+			.minimum_size
+			.is_some_and(|minimum_size| file_size < minimum_size)
+		{
+			"smaller than the minimum size of its pack file filter"
+		} else if self
+			.maximum_size
+			.is_some_and(|maximum_size| file_size > maximum_size)
+		{
+			"larger than the maximum size of its pack file filter"
+		} else if modification_time.is_none() {
+			"its modification time is unknown, but its pack file filter depends on it"
+		} else {
+			"modified outside the time window of its pack file filter"
+		}
+	}
+}
+
+/// Checks whether the specified pack file is excluded from the generated ZIP file by the pack
+/// file filters of the specified options, returning a user-friendly reason if it is. The pack
+/// file is only opened to get its size and modification time if some filter applies to it.
+pub fn pack_file_exclusion_reason(
+	pack_file_data: &VfsPackFileIterEntry,
+	options_holder: &ProcessedSquashOptions,
+	vfs: &impl VirtualFileSystem
+) -> Result<Option<&'static str>, io::Error> {
+	// The first match is the one that takes precedence
+	let Some(&pack_file_filter_index) = options_holder
+		.pack_file_filter_globs
+		.matches(pack_file_data.relative_path.as_str())
+		.first()
+	else {
+		return Ok(None);
+	};
+	let pack_file_filter =
+		&options_holder.options.global_options.pack_file_filters[pack_file_filter_index];
+
+	let vfs_file = vfs.open(&pack_file_data.file_path)?;
+	let (file_size, modification_time) =
+		(vfs_file.file_size_hint, vfs_file.metadata.modification_time);
+
+	Ok((!pack_file_filter.includes(file_size, modification_time))
+		.then(|| pack_file_filter.exclusion_reason(file_size, modification_time)))
+}
+
+/// A helper struct that contains a point in time parsed from an RFC 3339 date, such as
+/// `2024-06-01`, or date and time, such as `2024-06-01T12:00:00Z` or
+/// `2024-06-01T14:00:00+02:00`. Dates without a time refer to the start of that day, in UTC.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(try_from = "String")]
+#[repr(transparent)]
+pub struct FilterTimestamp(SystemTime);
+
+impl TryFrom<String> for FilterTimestamp {
+	type Error = &'static str;
+
+	fn try_from(timestamp: String) -> Result<Self, Self::Error> {
+		const INVALID_TIMESTAMP: &str = "The specified value is not a RFC 3339 date or date \
+			and time after 1970, such as 2024-06-01 or 2024-06-01T12:00:00Z";
+
+		/// Parses a fixed-width decimal number within the specified bounds.
+		fn parse_number(digits: Option<&str>, minimum: i64, maximum: i64) -> Option<i64> {
+			let digits = digits?;
+			let number = digits
+				.bytes()
+				.all(|byte| byte.is_ascii_digit())
+				.then(|| digits.parse::<i64>().ok())
+				.flatten()?;
+
+			(minimum..=maximum).contains(&number).then_some(number)
+		}
+
+		let (date, time) = timestamp
+			.split_once(['T', 't', ' '])
+			.map_or((&*timestamp, None), |(date, time)| (date, Some(time)));
+
+		let (year, month, day) = (
+			parse_number(date.get(0..4), 1970, 9999),
+			parse_number(date.get(5..7), 1, 12),
+			parse_number(date.get(8..10), 1, 31)
+		);
+		let (Some(year), Some(month), Some(day), 10, Some("-"), Some("-")) =
+			(year, month, day, date.len(), date.get(4..5), date.get(7..8))
+		else {
+			return Err(INVALID_TIMESTAMP);
+		};
+
+		let seconds_of_day = match time {
+			None => 0,
+			Some(time) => {
+				// The time is followed by a Z or an offset from UTC
+				let (time, offset_seconds) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+					(time, 0)
+				} else {
+					let offset_start = time.rfind(['+', '-']).ok_or(INVALID_TIMESTAMP)?;
+					let (time, offset) = time.split_at(offset_start);
+					let (Some(offset_hours), Some(offset_minutes), 6, Some(":")) = (
+						parse_number(offset.get(1..3), 0, 23),
+						parse_number(offset.get(4..6), 0, 59),
+						offset.len(),
+						offset.get(3..4)
+					) else {
+						return Err(INVALID_TIMESTAMP);
+					};
+
+					let offset_seconds = offset_hours * 3600 + offset_minutes * 60;
+					(
+						time,
+						if offset.starts_with('-') {
+							-offset_seconds
+						} else {
+							offset_seconds
+						}
+					)
+				};
+
+				// Fractions of a second are allowed, but ignored
+				let time = time.split_once('.').map_or(time, |(time, _)| time);
+				let (Some(hours), Some(minutes), Some(seconds), 8, Some(":"), Some(":")) = (
+					parse_number(time.get(0..2), 0, 23),
+					parse_number(time.get(3..5), 0, 59),
+					parse_number(time.get(6..8), 0, 60),
+					time.len(),
+					time.get(2..3),
+					time.get(5..6)
+				) else {
+					return Err(INVALID_TIMESTAMP);
+				};
+
+				hours * 3600 + minutes * 60 + seconds - offset_seconds
+			}
+		};
+
+		if day > days_in_month(year, month) {
+			return Err(INVALID_TIMESTAMP);
+		}
+
+		u64::try_from(days_since_unix_epoch(year, month, day) * 86400 + seconds_of_day)
+			.map(|seconds| Self(UNIX_EPOCH + Duration::from_secs(seconds)))
+			.map_err(|_| INVALID_TIMESTAMP)
+	}
+}
+
+/// Returns the number of days of the specified month of the specified year of the proleptic
+/// Gregorian calendar.
+const fn days_in_month(year: i64, month: i64) -> i64 {
+	match month {
+		2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+		2 => 28,
+		4 | 6 | 9 | 11 => 30,
+		_ => 31
+	}
+}
+
+/// Returns the number of days between the Unix epoch and the specified date of the proleptic
+/// Gregorian calendar, using the algorithm described at
+/// <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+const fn days_since_unix_epoch(year: i64, month: i64, day: i64) -> i64 {
+	let year = if month <= 2 { year - 1 } else { year };
+	let era = year.div_euclid(400);
+	let year_of_era = year - era * 400;
+	let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+	era * 146_097 + day_of_era - 719_468
+}
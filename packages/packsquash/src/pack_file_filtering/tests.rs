@@ -0,0 +1,77 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+/// Parses the specified timestamp, returning the number of seconds since the Unix epoch it
+/// refers to.
+fn unix_time(timestamp: &str) -> Result<u64, &'static str> {
+	FilterTimestamp::try_from(timestamp.to_string()).map(|FilterTimestamp(time)| {
+		time.duration_since(UNIX_EPOCH)
+			.expect("Parsed timestamps are not before the Unix epoch")
+			.as_secs()
+	})
+}
+
+#[test]
+fn timestamps_are_parsed() {
+	assert_eq!(unix_time("1970-01-01"), Ok(0));
+	assert_eq!(unix_time("2024-06-01"), Ok(1_717_200_000));
+	assert_eq!(unix_time("2024-02-29T12:30:15Z"), Ok(1_709_209_815));
+	assert_eq!(
+		unix_time("2024-06-01T14:00:00.250+02:00"),
+		Ok(1_717_243_200)
+	);
+	assert_eq!(unix_time("2024-06-01t10:00:00-02:00"), Ok(1_717_243_200));
+}
+
+#[test]
+fn invalid_timestamps_are_rejected() {
+	for timestamp in [
+		"",
+		"2024",
+		"2024-6-1",
+		"2023-02-29",
+		"2024-13-01",
+		"1969-12-31",
+		"2024-06-01T12:00:00",
+		"2024-06-01T25:00:00Z",
+		"2024-06-01T12:00:00+2:00",
+		"+2024-06-01"
+	] {
+		assert!(
+			unix_time(timestamp).is_err(),
+			"{timestamp} should be invalid"
+		);
+	}
+}
+
+#[test]
+fn filters_include_files_that_meet_every_condition() {
+	let filter = PackFileFilter {
+		minimum_size: Some(16),
+		maximum_size: Some(1024),
+		modified_after: Some(FilterTimestamp(UNIX_EPOCH + Duration::from_secs(100))),
+		modified_before: None
+	};
+	let modification_time = Some(UNIX_EPOCH + Duration::from_secs(200));
+
+	assert!(filter.includes(512, modification_time));
+	assert!(!filter.includes(8, modification_time));
+	assert!(!filter.includes(2048, modification_time));
+	assert!(!filter.includes(512, Some(UNIX_EPOCH)));
+	assert!(!filter.includes(512, None));
+
+	assert_eq!(
+		filter.exclusion_reason(2048, modification_time),
+		"larger than the maximum size of its pack file filter"
+	);
+	assert_eq!(
+		filter.exclusion_reason(512, Some(UNIX_EPOCH)),
+		"modified outside the time window of its pack file filter"
+	);
+}
+
+#[test]
+fn filters_without_conditions_include_every_file() {
+	assert!(PackFileFilter::default().includes(u64::MAX, None));
+}
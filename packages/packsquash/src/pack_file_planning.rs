@@ -0,0 +1,160 @@
+//! Contains routines to plan what a squash operation would do with each pack file, without
+//! reading or processing any of them, so that users can check how their options apply.
+
+use crate::config::{CustomFileFormat, CustomFileOptions, FileOptions, ProcessedSquashOptions};
+use crate::default_file_options;
+use crate::pack_file::asset_type::{PackFileAssetTypeMatcher, PackFileAssetTypeMatches};
+use crate::squash_zip::relative_path::RelativePath;
+use crate::world_template;
+
+#[cfg(test)]
+mod tests;
+
+/// What a squash operation would do with some pack file, according to its path and the
+/// options it would be processed with.
+pub struct PlannedPackFile {
+	path: RelativePath<'static>,
+	asset_type: Option<String>,
+	file_options_pattern: Option<String>,
+	output_path: Option<RelativePath<'static>>,
+	skip_reason: Option<&'static str>
+}
+
+impl PlannedPackFile {
+	/// Creates a plan for a pack file that would be skipped for the specified reason.
+	pub(crate) const fn skipped(path: RelativePath<'static>, skip_reason: &'static str) -> Self {
+		Self {
+			path,
+			asset_type: None,
+			file_options_pattern: None,
+			output_path: None,
+			skip_reason: Some(skip_reason)
+		}
+	}
+
+	/// Gets the relative path of the pack file this plan is for.
+	pub const fn path(&self) -> &RelativePath<'static> {
+		&self.path
+	}
+
+	/// Gets the name of the asset type the pack file would be processed as, which determines
+	/// its optimization strategy, or `None` if it would be skipped. Like the optimization
+	/// strategies of processed pack files, these names may change between releases.
+	pub fn asset_type(&self) -> Option<&str> {
+		self.asset_type.as_deref()
+	}
+
+	/// Gets the glob pattern of the file options table the pack file would be processed
+	/// with, or `None` if it would be processed with the default options, or skipped.
+	pub fn file_options_pattern(&self) -> Option<&str> {
+		self.file_options_pattern.as_deref()
+	}
+
+	/// Gets the path the pack file would have in the output ZIP file, before any entry name
+	/// transformation is applied, or `None` if it would be skipped.
+	pub const fn output_path(&self) -> Option<&RelativePath<'static>> {
+		self.output_path.as_ref()
+	}
+
+	/// Gets a brief, user-friendly description of why the pack file would not be included
+	/// in the output ZIP file, or `None` if it would be included.
+	pub const fn skip_reason(&self) -> Option<&'static str> {
+		self.skip_reason
+	}
+}
+
+/// Plans what a squash operation would do with the pack file at the specified relative path,
+/// trying the file options that match its path and then the default ones, in the same order
+/// the squash operation does. Pack files that would be skipped due to analyses of the pack
+/// contents, such as unreferenced assets, are not considered skipped here.
+pub fn plan_pack_file(
+	relative_path: RelativePath<'static>,
+	options_holder: &ProcessedSquashOptions,
+	asset_type_matcher: &PackFileAssetTypeMatcher
+) -> PlannedPackFile {
+	// The files of data packs in world folders are identified by their path within the data pack
+	let asset_type_matches = if options_holder
+		.options
+		.global_options
+		.world_template_packaging
+	{
+		asset_type_matcher.matches_for(&world_template::pack_relative_path(&relative_path))
+	} else {
+		asset_type_matcher.matches_for(&relative_path)
+	};
+
+	// Files that match no asset type can only be custom files, which have no default options
+	let have_default_options = !asset_type_matches.is_empty();
+	let asset_type_matches = if have_default_options {
+		asset_type_matches
+	} else {
+		PackFileAssetTypeMatches::of_custom_asset_type()
+	};
+
+	let file_options = options_holder
+		.file_options_globs
+		.matches(relative_path.as_str())
+		.into_iter()
+		.map(|i| {
+			let (file_options_pattern, file_options) =
+				options_holder.options.file_options.get_index(i).unwrap();
+
+			(Some(file_options_pattern), Some(file_options.clone()))
+		})
+		.chain(
+			default_file_options()
+				.filter(|_| have_default_options)
+				.map(|file_options| (None, file_options))
+		);
+
+	for (file_options_pattern, file_options) in file_options {
+		let file_options = file_options.map(|file_options| {
+			file_options
+				.tweak_from_global_options(&options_holder.options.global_options)
+				.tweak_from_relative_path(relative_path.as_str(), options_holder)
+		});
+
+		// Nested archives are custom files that are kept at their path
+		if !have_default_options
+			&& let Some(FileOptions::CustomFileOptions(CustomFileOptions {
+				force_include: true,
+				custom_file_format: CustomFileFormat::ZipArchive,
+				..
+			})) = file_options
+		{
+			return PlannedPackFile {
+				output_path: Some(relative_path.clone()),
+				path: relative_path,
+				asset_type: Some("NestedArchive".into()),
+				file_options_pattern: file_options_pattern.cloned(),
+				skip_reason: None
+			};
+		}
+
+		if let Some(asset_type) = asset_type_matches.processing_asset_type(file_options.as_ref()) {
+			let output_path = match asset_type.canonical_extension() {
+				Some(canonical_extension) => RelativePath::from_inner(
+					relative_path
+						.with_extension(canonical_extension)
+						.into_os_string()
+						.into_string()
+						.unwrap()
+				),
+				None => relative_path.clone()
+			};
+
+			return PlannedPackFile {
+				path: relative_path,
+				asset_type: Some(format!("{asset_type:?}")),
+				file_options_pattern: file_options_pattern.cloned(),
+				output_path: Some(output_path),
+				skip_reason: None
+			};
+		}
+	}
+
+	PlannedPackFile::skipped(
+		relative_path,
+		"it matched no file options appropriate for its type"
+	)
+}
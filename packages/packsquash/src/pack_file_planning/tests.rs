@@ -0,0 +1,145 @@
+use std::path::PathBuf;
+
+use enumset::EnumSet;
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+
+use crate::config::{FileTypeRoute, GlobalOptions, PngFileOptions, SquashOptions};
+
+use super::*;
+
+/// Plans what a squash operation with the specified file options would do with the pack file
+/// at the specified path, returning its asset type, file options pattern and output path.
+fn plan(
+	relative_path: &'static str,
+	file_options: IndexMap<String, FileOptions>
+) -> (Option<String>, Option<String>, Option<String>) {
+	plan_with_global_options(relative_path, GlobalOptions::default(), file_options)
+}
+
+/// Like [`plan`], but with the specified global options.
+fn plan_with_global_options(
+	relative_path: &'static str,
+	global_options: GlobalOptions,
+	file_options: IndexMap<String, FileOptions>
+) -> (Option<String>, Option<String>, Option<String>) {
+	let options_holder = ProcessedSquashOptions::try_from(SquashOptions {
+		pack_directory: PathBuf::new(),
+		global_options,
+		file_options
+	})
+	.expect("The test options are assumed to be valid");
+
+	let planned_pack_file = plan_pack_file(
+		RelativePath::from_inner(relative_path),
+		&options_holder,
+		&PackFileAssetTypeMatcher::for_options(EnumSet::all(), &options_holder)
+	);
+
+	assert_eq!(planned_pack_file.path().as_str(), relative_path);
+
+	(
+		planned_pack_file.asset_type().map(str::to_owned),
+		planned_pack_file.file_options_pattern().map(str::to_owned),
+		planned_pack_file
+			.output_path()
+			.map(|output_path| output_path.as_str().to_owned())
+	)
+}
+
+#[test]
+fn default_options_are_planned_for_unmatched_pack_files() {
+	assert_eq!(
+		plan("assets/minecraft/models/block/stone.jsonc", IndexMap::new()),
+		(
+			Some("MinecraftModelWithComments".to_owned()),
+			None,
+			Some("assets/minecraft/models/block/stone.json".to_owned())
+		)
+	);
+}
+
+#[test]
+fn matching_file_options_are_planned() {
+	assert_eq!(
+		plan(
+			"assets/minecraft/textures/block/stone.png",
+			IndexMap::from([(
+				"**/*?.png".to_owned(),
+				FileOptions::PngFileOptions(PngFileOptions::default())
+			)])
+		),
+		(
+			Some("GenericTexture".to_owned()),
+			Some("**/*?.png".to_owned()),
+			Some("assets/minecraft/textures/block/stone.png".to_owned())
+		)
+	);
+}
+
+#[test]
+fn inappropriate_file_options_fall_back_to_the_default_ones() {
+	assert_eq!(
+		plan(
+			"assets/minecraft/textures/block/stone.png",
+			IndexMap::from([(
+				"**/*".to_owned(),
+				FileOptions::JsonFileOptions(Default::default())
+			)])
+		)
+		.1,
+		None
+	);
+}
+
+#[test]
+fn unrecognized_pack_files_are_skipped() {
+	assert_eq!(plan("README.md", IndexMap::new()), (None, None, None));
+}
+
+#[test]
+fn routed_pack_files_are_planned_with_their_file_type() {
+	let global_options = GlobalOptions {
+		file_type_routes: IndexMap::from([
+			("**/*?.fsh2".to_owned(), FileTypeRoute::FragmentShader),
+			("**/*?.mcmetac".to_owned(), FileTypeRoute::JsonWithComments),
+			("**/textures/**/*?.mcmetac".to_owned(), FileTypeRoute::Json),
+			("**/*?.jsonc".to_owned(), FileTypeRoute::Text)
+		]),
+		..Default::default()
+	};
+
+	assert_eq!(
+		plan_with_global_options(
+			"assets/minecraft/shaders/core/rendertype_solid.fsh2",
+			global_options.clone(),
+			IndexMap::new()
+		),
+		(
+			Some("FragmentShader".to_owned()),
+			None,
+			Some("assets/minecraft/shaders/core/rendertype_solid.fsh2".to_owned())
+		)
+	);
+	assert_eq!(
+		plan_with_global_options(
+			"assets/minecraft/textures/block/stone.png.mcmetac",
+			global_options.clone(),
+			IndexMap::new()
+		)
+		.0,
+		Some("GenericJson".to_owned())
+	);
+	assert_eq!(
+		plan_with_global_options(
+			"assets/minecraft/models/block/stone.jsonc",
+			global_options,
+			IndexMap::new()
+		),
+		(
+			Some("Text".to_owned()),
+			None,
+			Some("assets/minecraft/models/block/stone.jsonc".to_owned())
+		)
+	);
+}
@@ -0,0 +1,544 @@
+//! Contains a virtual file system adapter that rewrites the pack metadata file with the
+//! `pack_format` versions that correspond to a range of Minecraft versions.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf},
+	str::FromStr,
+	time::SystemTime
+};
+
+use bytes::Bytes;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use tokio::io::AsyncReadExt;
+use tokio_util::either::Either;
+
+use crate::pack_file::{blank_out_relaxed_syntax, strip_utf8_bom};
+use crate::vfs::{IteratorTraversalOptions, VfsFile, VirtualFileSystem, open_in_memory_or_inner};
+
+#[cfg(test)]
+mod tests;
+
+/// The resource and data pack format versions of every range of Minecraft release versions
+/// that share them, in ascending order. Data packs were introduced in Minecraft 1.13.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Pack_format>
+const PACK_FORMAT_VERSIONS: &[(MinecraftVersion, MinecraftVersion, i32, Option<i32>)] = &[
+	(
+		MinecraftVersion::new(1, 6, 1),
+		MinecraftVersion::new(1, 8, 9),
+		1,
+		None
+	),
+	(
+		MinecraftVersion::new(1, 9, 0),
+		MinecraftVersion::new(1, 10, 2),
+		2,
+		None
+	),
+	(
+		MinecraftVersion::new(1, 11, 0),
+		MinecraftVersion::new(1, 12, 2),
+		3,
+		None
+	),
+	(
+		MinecraftVersion::new(1, 13, 0),
+		MinecraftVersion::new(1, 14, 4),
+		4,
+		Some(4)
+	),
+	(
+		MinecraftVersion::new(1, 15, 0),
+		MinecraftVersion::new(1, 16, 1),
+		5,
+		Some(5)
+	),
+	(
+		MinecraftVersion::new(1, 16, 2),
+		MinecraftVersion::new(1, 16, 5),
+		6,
+		Some(6)
+	),
+	(
+		MinecraftVersion::new(1, 17, 0),
+		MinecraftVersion::new(1, 17, 1),
+		7,
+		Some(7)
+	),
+	(
+		MinecraftVersion::new(1, 18, 0),
+		MinecraftVersion::new(1, 18, 1),
+		8,
+		Some(8)
+	),
+	(
+		MinecraftVersion::new(1, 18, 2),
+		MinecraftVersion::new(1, 18, 2),
+		8,
+		Some(9)
+	),
+	(
+		MinecraftVersion::new(1, 19, 0),
+		MinecraftVersion::new(1, 19, 2),
+		9,
+		Some(10)
+	),
+	(
+		MinecraftVersion::new(1, 19, 3),
+		MinecraftVersion::new(1, 19, 3),
+		12,
+		Some(10)
+	),
+	(
+		MinecraftVersion::new(1, 19, 4),
+		MinecraftVersion::new(1, 19, 4),
+		13,
+		Some(12)
+	),
+	(
+		MinecraftVersion::new(1, 20, 0),
+		MinecraftVersion::new(1, 20, 1),
+		15,
+		Some(15)
+	),
+	(
+		MinecraftVersion::new(1, 20, 2),
+		MinecraftVersion::new(1, 20, 2),
+		18,
+		Some(18)
+	),
+	(
+		MinecraftVersion::new(1, 20, 3),
+		MinecraftVersion::new(1, 20, 4),
+		22,
+		Some(26)
+	),
+	(
+		MinecraftVersion::new(1, 20, 5),
+		MinecraftVersion::new(1, 20, 6),
+		32,
+		Some(41)
+	),
+	(
+		MinecraftVersion::new(1, 21, 0),
+		MinecraftVersion::new(1, 21, 1),
+		34,
+		Some(48)
+	),
+	(
+		MinecraftVersion::new(1, 21, 2),
+		MinecraftVersion::new(1, 21, 3),
+		42,
+		Some(57)
+	),
+	(
+		MinecraftVersion::new(1, 21, 4),
+		MinecraftVersion::new(1, 21, 4),
+		46,
+		Some(61)
+	),
+	(
+		MinecraftVersion::new(1, 21, 5),
+		MinecraftVersion::new(1, 21, 5),
+		55,
+		Some(71)
+	),
+	(
+		MinecraftVersion::new(1, 21, 6),
+		MinecraftVersion::new(1, 21, 6),
+		63,
+		Some(80)
+	),
+	(
+		MinecraftVersion::new(1, 21, 7),
+		MinecraftVersion::new(1, 21, 8),
+		64,
+		Some(81)
+	)
+];
+
+/// A Minecraft release version, such as `1.20.4`, that PackSquash knows the pack format
+/// versions of.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "String")]
+pub struct MinecraftVersion {
+	major: u16,
+	minor: u16,
+	patch: u16
+}
+
+impl MinecraftVersion {
+	/// Creates a new Minecraft version from its components. The version is not validated to be
+	/// known.
+	pub(crate) const fn new(major: u16, minor: u16, patch: u16) -> Self {
+		Self {
+			major,
+			minor,
+			patch
+		}
+	}
+
+	/// Returns the resource and data pack format versions of this Minecraft version. Versions
+	/// that do not support data packs have no data pack format version.
+	fn pack_format_versions(self) -> (i32, Option<i32>) {
+		let (_, _, resource_pack_format_version, data_pack_format_version) = PACK_FORMAT_VERSIONS
+			.iter()
+			.find(|(first_version, last_version, _, _)| {
+				(*first_version..=*last_version).contains(&self)
+			})
+			.expect("Minecraft versions are validated on construction");
+
+		(*resource_pack_format_version, *data_pack_format_version)
+	}
+
+	/// Returns the version that immediately precedes this one in version order, which may
+	/// not be a known Minecraft version, or this version if there is none.
+	const fn predecessor(self) -> Self {
+		match (self.major, self.minor, self.patch) {
+			(0, 0, 0) => self,
+			(major, 0, 0) => Self::new(major - 1, u16::MAX, u16::MAX),
+			(major, minor, 0) => Self::new(major, minor - 1, u16::MAX),
+			(major, minor, patch) => Self::new(major, minor, patch - 1)
+		}
+	}
+
+	/// Returns the version that immediately follows this one in version order, which may not
+	/// be a known Minecraft version, or this version if there is none.
+	const fn successor(self) -> Self {
+		match (self.major, self.minor, self.patch) {
+			(u16::MAX, u16::MAX, u16::MAX) => self,
+			(major, u16::MAX, u16::MAX) => Self::new(major + 1, 0, 0),
+			(major, minor, u16::MAX) => Self::new(major, minor + 1, 0),
+			(major, minor, patch) => Self::new(major, minor, patch + 1)
+		}
+	}
+}
+
+impl TryFrom<String> for MinecraftVersion {
+	type Error = &'static str;
+
+	fn try_from(version: String) -> Result<Self, Self::Error> {
+		const UNKNOWN_VERSION: &str = "The specified value is not a Minecraft release version \
+			PackSquash knows the pack format versions of, such as 1.20.4";
+
+		let mut version_components = version.split('.').map(str::parse::<u16>);
+		let (Some(Ok(major)), Some(Ok(minor)), patch, None) = (
+			version_components.next(),
+			version_components.next(),
+			version_components.next().unwrap_or(Ok(0)),
+			version_components.next()
+		) else {
+			return Err(UNKNOWN_VERSION);
+		};
+		let version = Self::new(major, minor, patch.map_err(|_| UNKNOWN_VERSION)?);
+
+		PACK_FORMAT_VERSIONS
+			.iter()
+			.any(|(first_version, last_version, _, _)| {
+				(*first_version..=*last_version).contains(&version)
+			})
+			.then_some(version)
+			.ok_or(UNKNOWN_VERSION)
+	}
+}
+
+/// A helper struct that contains a range of Minecraft release versions, whose minimum is
+/// guaranteed to not be newer than its maximum.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(try_from = "[MinecraftVersion; 2]")]
+pub struct MinecraftVersionRange {
+	minimum: MinecraftVersion,
+	maximum: MinecraftVersion
+}
+
+impl MinecraftVersionRange {
+	/// Returns the oldest Minecraft version of this range.
+	pub const fn minimum(&self) -> MinecraftVersion {
+		self.minimum
+	}
+
+	/// Returns the newest Minecraft version of this range.
+	pub const fn maximum(&self) -> MinecraftVersion {
+		self.maximum
+	}
+
+	/// Returns the minimum and maximum pack format versions of the Minecraft versions of this
+	/// range, for resource packs or data packs. `None` is returned if no version of the range
+	/// supports data packs and data pack format versions are requested.
+	fn pack_format_versions(&self, data_pack: bool) -> Option<(i32, i32)> {
+		let pack_format_version = |version: MinecraftVersion| {
+			let (resource_pack_format_version, data_pack_format_version) =
+				version.pack_format_versions();
+
+			if data_pack {
+				data_pack_format_version
+			} else {
+				Some(resource_pack_format_version)
+			}
+		};
+
+		// The first Minecraft version that supports data packs is the oldest one a data pack
+		// can target
+		let minimum_pack_format_version = pack_format_version(self.minimum).or_else(|| {
+			PACK_FORMAT_VERSIONS
+				.iter()
+				.filter(|(first_version, _, _, _)| *first_version <= self.maximum)
+				.find_map(|(first_version, _, _, _)| pack_format_version(*first_version))
+		})?;
+
+		Some((
+			minimum_pack_format_version,
+			pack_format_version(self.maximum)?
+		))
+	}
+}
+
+impl TryFrom<[MinecraftVersion; 2]> for MinecraftVersionRange {
+	type Error = &'static str;
+
+	fn try_from([minimum, maximum]: [MinecraftVersion; 2]) -> Result<Self, Self::Error> {
+		(minimum <= maximum)
+			.then_some(Self { minimum, maximum })
+			.ok_or(
+				"The specified value is not a [min, max] Minecraft version range, where min <= max"
+			)
+	}
+}
+
+/// A condition on the Minecraft versions a pack targets, made of comma-separated comparisons
+/// with Minecraft release versions that must all hold, such as `>=1.13, <1.20.5`. The
+/// supported comparison operators are `<`, `<=`, `>`, `>=` and `=`, which is implied when no
+/// operator is given.
+#[derive(Clone, Copy)]
+pub struct MinecraftVersionCondition {
+	minimum: MinecraftVersion,
+	maximum: MinecraftVersion
+}
+
+impl MinecraftVersionCondition {
+	/// Returns whether any Minecraft version of the specified range satisfies this condition.
+	pub fn matches(&self, version_range: &MinecraftVersionRange) -> bool {
+		self.minimum.max(version_range.minimum) <= self.maximum.min(version_range.maximum)
+	}
+}
+
+impl FromStr for MinecraftVersionCondition {
+	type Err = &'static str;
+
+	fn from_str(condition: &str) -> Result<Self, Self::Err> {
+		let mut minimum = MinecraftVersion::new(0, 0, 0);
+		let mut maximum = MinecraftVersion::new(u16::MAX, u16::MAX, u16::MAX);
+
+		for comparison in condition.split(',').map(str::trim) {
+			// Try longer operators first, so that their first character is not taken as a
+			// shorter operator
+			let (operator, version) = ["<=", ">=", "<", ">", "="]
+				.into_iter()
+				.find_map(|operator| Some((operator, comparison.strip_prefix(operator)?)))
+				.unwrap_or(("=", comparison));
+			let version = MinecraftVersion::try_from(version.trim_start().to_string())?;
+
+			let (comparison_minimum, comparison_maximum) = match operator {
+				"<" => (minimum, version.predecessor()),
+				"<=" => (minimum, version),
+				">" => (version.successor(), maximum),
+				">=" => (version, maximum),
+				_ => (version, version)
+			};
+			minimum = minimum.max(comparison_minimum);
+			maximum = maximum.min(comparison_maximum);
+		}
+
+		Ok(Self { minimum, maximum })
+	}
+}
+
+/// The pack metadata file generated by [`PackFormatStampingVfs`].
+struct GeneratedFile {
+	path: PathBuf,
+	data: Bytes,
+	modification_time: Option<SystemTime>
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently replacing the pack metadata
+/// file with one whose `pack_format` and `supported_formats` keys, and optionally the format
+/// ranges of its overlays, match the pack format versions of a range of Minecraft versions.
+///
+/// The generated file is computed eagerly by [`Self::stamp_pack_format`] and kept in memory,
+/// because the [`VirtualFileSystem::open`] method is synchronous. Other files are read from
+/// the wrapped file system as-is.
+pub struct PackFormatStampingVfs<V: VirtualFileSystem> {
+	inner: V,
+	generated_file: Option<GeneratedFile>
+}
+
+impl<V: VirtualFileSystem> PackFormatStampingVfs<V> {
+	/// Wraps the specified virtual file system. The pack metadata file will not be changed
+	/// until [`Self::stamp_pack_format`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			generated_file: None
+		}
+	}
+
+	/// Rewrites the pack metadata file of the pack at the specified root path for it to target
+	/// the specified range of Minecraft versions, which are assumed to be data pack versions
+	/// if the pack has a `data` folder but no `assets` folder. The `pack_format` key is set to
+	/// the pack format version of the oldest Minecraft version, which is the only key that
+	/// versions before 1.20.2 read, and the `supported_formats` key is set to the range of
+	/// pack format versions, or removed if the range contains a single version. If some overlay
+	/// version ranges are specified, the overlay entries are replaced with entries for the
+	/// specified overlay directories. Pack metadata files that can't be read or parsed are left
+	/// as-is, so that the usual pack metadata validation reports any relevant error.
+	///
+	/// References:
+	/// - <https://minecraft.wiki/w/Pack.mcmeta>
+	pub async fn stamp_pack_format(
+		&mut self,
+		root_path: &Path,
+		version_range: MinecraftVersionRange,
+		overlay_version_ranges: &IndexMap<String, MinecraftVersionRange>
+	) {
+		let data_pack = !self
+			.inner
+			.file_type(root_path.join("assets"))
+			.is_ok_and(|file_type| file_type.is_dir())
+			&& self
+				.inner
+				.file_type(root_path.join("data"))
+				.is_ok_and(|file_type| file_type.is_dir());
+
+		// Prefer the same pack metadata file as the usual pack metadata validation
+		let mut pack_meta_path = root_path.join("pack.mcmetac");
+		let mut pack_meta_file = self.inner.open(&pack_meta_path);
+		if pack_meta_file.is_err() {
+			pack_meta_path = root_path.join("pack.mcmeta");
+			pack_meta_file = self.inner.open(&pack_meta_path);
+		}
+		let Ok(mut pack_meta_file) = pack_meta_file else {
+			return;
+		};
+
+		let mut data = Vec::with_capacity(
+			pack_meta_file
+				.file_size_hint
+				.try_into()
+				.unwrap_or(usize::MAX)
+		);
+		if pack_meta_file
+			.file_read
+			.read_to_end(&mut data)
+			.await
+			.is_err()
+		{
+			return;
+		}
+
+		let bom_length = data.len() - strip_utf8_bom(&data).len();
+		let json = &mut data[bom_length..];
+		if blank_out_relaxed_syntax(json).is_err() {
+			return;
+		}
+		let Ok(mut pack_meta) = serde_json::from_slice::<Map<String, Value>>(json) else {
+			return;
+		};
+
+		if !stamp_pack_meta(
+			&mut pack_meta,
+			data_pack,
+			version_range,
+			overlay_version_ranges
+		) {
+			return;
+		}
+
+		self.generated_file = Some(GeneratedFile {
+			path: pack_meta_path,
+			data: serde_json::to_vec(&pack_meta).unwrap().into(),
+			modification_time: pack_meta_file.metadata.modification_time
+		});
+	}
+}
+
+/// Sets the pack format versions of a parsed pack metadata file, returning whether it has
+/// the expected structure to do so.
+fn stamp_pack_meta(
+	pack_meta: &mut Map<String, Value>,
+	data_pack: bool,
+	version_range: MinecraftVersionRange,
+	overlay_version_ranges: &IndexMap<String, MinecraftVersionRange>
+) -> bool {
+	let Some((minimum_pack_format_version, maximum_pack_format_version)) =
+		version_range.pack_format_versions(data_pack)
+	else {
+		return false;
+	};
+
+	let Some(pack) = pack_meta.get_mut("pack").and_then(Value::as_object_mut) else {
+		return false;
+	};
+
+	pack.insert("pack_format".into(), minimum_pack_format_version.into());
+	if minimum_pack_format_version == maximum_pack_format_version {
+		pack.shift_remove("supported_formats");
+	} else {
+		pack.insert(
+			"supported_formats".into(),
+			json!([minimum_pack_format_version, maximum_pack_format_version])
+		);
+	}
+
+	if !overlay_version_ranges.is_empty() {
+		let overlay_entries = overlay_version_ranges
+			.iter()
+			.filter_map(|(directory, overlay_version_range)| {
+				let (minimum_pack_format_version, maximum_pack_format_version) =
+					overlay_version_range.pack_format_versions(data_pack)?;
+
+				Some(json!({
+					"formats": [minimum_pack_format_version, maximum_pack_format_version],
+					"directory": directory
+				}))
+			})
+			.collect::<Vec<_>>();
+
+		pack_meta.insert("overlays".into(), json!({ "entries": overlay_entries }));
+	}
+
+	true
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for PackFormatStampingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = V::FileIter;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.generated_file
+				.as_ref()
+				.filter(|generated_file| generated_file.path == path.as_ref())
+				.map(|generated_file| (&generated_file.data, generated_file.modification_time))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
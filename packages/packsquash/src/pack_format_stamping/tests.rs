@@ -0,0 +1,177 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+/// Stamps the pack format versions of the specified Minecraft version ranges into a pack with
+/// the specified files, returning the parsed contents of the resulting pack metadata file.
+fn stamp_pack_format(
+	files: &[(&str, &str)],
+	version_range: [&str; 2],
+	overlay_version_ranges: &[(&str, [&str; 2])]
+) -> Value {
+	let root_dir = Builder::new()
+		.prefix("ps-pack-format-stamping-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in files {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let parse_version_range = |[minimum, maximum]: [&str; 2]| {
+		MinecraftVersionRange::try_from([
+			MinecraftVersion::try_from(minimum.to_string()).unwrap(),
+			MinecraftVersion::try_from(maximum.to_string()).unwrap()
+		])
+		.unwrap()
+	};
+
+	let mut vfs = PackFormatStampingVfs::new(OsFilesystem);
+	tokio_test::block_on(
+		vfs.stamp_pack_format(
+			root_dir.path(),
+			parse_version_range(version_range),
+			&overlay_version_ranges
+				.iter()
+				.map(|(directory, range)| (directory.to_string(), parse_version_range(*range)))
+				.collect()
+		)
+	);
+
+	let mut file = vfs
+		.open(root_dir.path().join("pack.mcmeta"))
+		.expect("I/O operations are assumed not to fail during tests");
+	let mut data = Vec::new();
+	tokio_test::block_on(file.file_read.read_to_end(&mut data))
+		.expect("I/O operations are assumed not to fail during tests");
+
+	serde_json::from_slice(&data).expect("The pack metadata file should be valid JSON")
+}
+
+#[test]
+fn minecraft_versions_are_validated() {
+	for version in ["1.20", "1.20.0", "1.21.4", "1.6.1"] {
+		assert!(
+			MinecraftVersion::try_from(version.to_string()).is_ok(),
+			"{version} should be a known Minecraft version"
+		);
+	}
+
+	for version in ["", "1", "1.5.2", "1.20.a", "1.20.4.1", "99.0", "24w14a"] {
+		assert!(
+			MinecraftVersion::try_from(version.to_string()).is_err(),
+			"{version} should not be a known Minecraft version"
+		);
+	}
+}
+
+#[test]
+fn pack_format_is_stamped() {
+	let pack_meta = r#"{
+		// Comments are allowed, like in the usual pack metadata processing
+		"pack": {
+			"pack_format": 1,
+			"supported_formats": [1, 2],
+			"description": "A pack"
+		},
+		"overlays": { "entries": [{ "formats": [1, 2], "directory": "old" }] }
+	}"#;
+
+	// Resource pack spanning several pack formats, with overlays
+	assert_eq!(
+		stamp_pack_format(
+			&[
+				("pack.mcmeta", pack_meta),
+				("assets/minecraft/lang/en_us.json", "{}")
+			],
+			["1.20", "1.21.4"],
+			&[
+				("legacy", ["1.20", "1.20.1"]),
+				("modern", ["1.21", "1.21.4"])
+			]
+		),
+		json!({
+			"pack": {
+				"pack_format": 15,
+				"supported_formats": [15, 46],
+				"description": "A pack"
+			},
+			"overlays": {
+				"entries": [
+					{ "formats": [15, 15], "directory": "legacy" },
+					{ "formats": [34, 46], "directory": "modern" }
+				]
+			}
+		})
+	);
+
+	// Data pack targeting a single pack format, whose oldest version does not support data
+	// packs. Overlay entries are left as-is when no overlay ranges are configured
+	assert_eq!(
+		stamp_pack_format(
+			&[
+				("pack.mcmeta", pack_meta),
+				("data/mypack/tags/blocks/a.json", "{}")
+			],
+			["1.12", "1.14.4"],
+			&[]
+		),
+		json!({
+			"pack": {
+				"pack_format": 4,
+				"description": "A pack"
+			},
+			"overlays": { "entries": [{ "formats": [1, 2], "directory": "old" }] }
+		})
+	);
+}
+
+#[test]
+fn version_conditions_match_overlapping_version_ranges() {
+	let version_range = |minimum: &str, maximum: &str| {
+		MinecraftVersionRange::try_from([
+			MinecraftVersion::try_from(minimum.to_string()).unwrap(),
+			MinecraftVersion::try_from(maximum.to_string()).unwrap()
+		])
+		.unwrap()
+	};
+
+	for (condition, minimum, maximum, expected_match) in [
+		("<1.13", "1.12", "1.12.2", true),
+		("<1.13", "1.12.2", "1.20", true),
+		("<1.13", "1.13", "1.20", false),
+		("<=1.13", "1.13", "1.20", true),
+		(">1.20.4", "1.20", "1.20.4", false),
+		(">1.20.4", "1.20", "1.20.5", true),
+		(">= 1.16, <1.20.5", "1.13", "1.15.2", false),
+		(">= 1.16, <1.20.5", "1.13", "1.16", true),
+		("1.21.4", "1.21", "1.21.5", true),
+		("=1.21.4", "1.21.5", "1.21.5", false)
+	] {
+		assert_eq!(
+			condition
+				.parse::<MinecraftVersionCondition>()
+				.unwrap()
+				.matches(&version_range(minimum, maximum)),
+			expected_match,
+			"Unexpected match result for {condition} and [{minimum}, {maximum}]"
+		);
+	}
+}
+
+#[test]
+fn invalid_version_conditions_are_rejected() {
+	for condition in ["", "<", "<1.13,", "~1.20", "<1.999"] {
+		assert!(
+			condition.parse::<MinecraftVersionCondition>().is_err(),
+			"{condition} should be rejected"
+		);
+	}
+}
@@ -0,0 +1,186 @@
+//! Contains a virtual file system adapter that merges the files of several packs into one,
+//! resolving the conflicts between files at the same relative path according to a policy.
+
+use std::{
+	fs::FileType,
+	io,
+	path::{Path, PathBuf},
+	sync::Arc
+};
+
+use ahash::{AHashMap, AHashSet};
+use indexmap::IndexMap;
+
+use crate::RelativePath;
+use crate::config::PackMergeConflictHandling;
+use crate::vfs::{IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem};
+
+#[cfg(test)]
+mod tests;
+
+/// A conflict between files of several merged packs that are at the same relative path.
+pub struct PackMergeConflict {
+	/// The relative path of the conflicting files within their packs.
+	pub relative_path: RelativePath<'static>,
+	/// The path of the file that was kept, which depends on the conflict handling policy.
+	pub kept_file_path: PathBuf,
+	/// The paths of the files that were discarded, in pack order.
+	pub discarded_file_paths: Vec<PathBuf>
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently adding the files of other
+/// packs to the files of the pack at some root path. Only one file is yielded for each
+/// relative path, which is chosen when the packs are merged by [`Self::merge_packs`].
+///
+/// Files are read from the wrapped file system as-is, and iterating over paths other than
+/// the root path of the merged packs is not affected. Paths of files under the root path of
+/// the main pack refer to the file that was kept for their relative path, even if it belongs
+/// to another pack, so that files such as the pack metadata file can be opened by path.
+pub struct PackMergingVfs<V: VirtualFileSystem> {
+	inner: V,
+	/// The root paths of the merged packs, in order, starting with the main pack.
+	pack_directories: Vec<PathBuf>,
+	/// The paths of the files that were discarded due to conflicts.
+	discarded_file_paths: Arc<AHashSet<PathBuf>>,
+	/// The paths of the files of other packs that were kept for each relative path, keyed
+	/// by the path that relative path has under the root path of the main pack.
+	kept_file_paths: AHashMap<PathBuf, PathBuf>
+}
+
+impl<V: VirtualFileSystem> PackMergingVfs<V> {
+	/// Wraps the specified virtual file system. No packs will be merged until
+	/// [`Self::merge_packs`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			pack_directories: vec![],
+			discarded_file_paths: Arc::new(AHashSet::new()),
+			kept_file_paths: AHashMap::new()
+		}
+	}
+
+	/// Merges the packs at the specified root paths into the pack at the main root path, so
+	/// that iterating over the files of the main root path yields the files of every pack.
+	/// Files at the same relative path of several packs are resolved with the specified
+	/// policy, and the conflicts between them are returned, so that they can be reported.
+	/// When conflicts are handled as errors, the files of the first pack are kept.
+	pub fn merge_packs(
+		&mut self,
+		root_path: &Path,
+		merged_root_paths: &[PathBuf],
+		iterator_traversal_options: IteratorTraversalOptions,
+		conflict_handling: PackMergeConflictHandling
+	) -> Vec<PackMergeConflict> {
+		if merged_root_paths.is_empty() {
+			return vec![];
+		}
+
+		let mut file_paths = IndexMap::<_, Vec<_>>::new();
+		for root_path in [root_path]
+			.into_iter()
+			.chain(merged_root_paths.iter().map(PathBuf::as_path))
+		{
+			// I/O errors will be yielded again when iterating over the merged packs
+			for VfsPackFileIterEntry {
+				relative_path,
+				file_path
+			} in self
+				.inner
+				.file_iterator(root_path, iterator_traversal_options)
+				.flatten()
+			{
+				file_paths.entry(relative_path).or_default().push(file_path);
+			}
+		}
+
+		let mut discarded_file_paths = AHashSet::new();
+		let mut kept_file_paths = AHashMap::new();
+		let conflicts = file_paths
+			.into_iter()
+			.filter_map(|(relative_path, mut file_paths)| {
+				let kept_file_path = match conflict_handling {
+					PackMergeConflictHandling::FirstWins | PackMergeConflictHandling::Error => {
+						file_paths.remove(0)
+					}
+					PackMergeConflictHandling::LastWins => file_paths.pop().unwrap()
+				};
+
+				let main_pack_file_path = root_path.join(relative_path.as_str());
+				if kept_file_path != main_pack_file_path {
+					kept_file_paths.insert(main_pack_file_path, kept_file_path.clone());
+				}
+
+				if file_paths.is_empty() {
+					return None;
+				}
+				discarded_file_paths.extend(file_paths.iter().cloned());
+
+				Some(PackMergeConflict {
+					relative_path,
+					kept_file_path,
+					discarded_file_paths: file_paths
+				})
+			})
+			.collect();
+
+		self.pack_directories = [root_path.to_path_buf()]
+			.into_iter()
+			.chain(merged_root_paths.iter().cloned())
+			.collect();
+		self.discarded_file_paths = Arc::new(discarded_file_paths);
+		self.kept_file_paths = kept_file_paths;
+
+		conflicts
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for PackMergingVfs<V> {
+	type FileRead = V::FileRead;
+	type FileIter = impl Iterator<Item = Result<VfsPackFileIterEntry, io::Error>>;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		let root_paths = if self
+			.pack_directories
+			.first()
+			.is_some_and(|path| path == root_path)
+		{
+			self.pack_directories.clone()
+		} else {
+			vec![root_path.to_path_buf()]
+		};
+		let discarded_file_paths = Arc::clone(&self.discarded_file_paths);
+
+		root_paths
+			.into_iter()
+			.map(|root_path| {
+				self.inner
+					.file_iterator(&root_path, iterator_traversal_options)
+			})
+			.collect::<Vec<_>>()
+			.into_iter()
+			.flatten()
+			.filter(move |entry| {
+				!entry
+					.as_ref()
+					.is_ok_and(|entry| discarded_file_paths.contains(&entry.file_path))
+			})
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		match self.kept_file_paths.get(path.as_ref()) {
+			Some(kept_file_path) => self.inner.open(kept_file_path),
+			None => self.inner.open(path)
+		}
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		match self.kept_file_paths.get(path.as_ref()) {
+			Some(kept_file_path) => self.inner.file_type(kept_file_path),
+			None => self.inner.file_type(path)
+		}
+	}
+}
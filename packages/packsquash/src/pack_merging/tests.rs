@@ -0,0 +1,135 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::{Builder, TempDir};
+use tokio::io::AsyncReadExt;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+/// Creates a temporary pack directory with the specified files.
+fn pack_directory(files: &[(&str, &str)]) -> TempDir {
+	let root_dir = Builder::new()
+		.prefix("ps-pack-merging-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in files {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	root_dir
+}
+
+/// Merges the specified packs with the specified conflict handling policy, returning the
+/// sorted relative and file paths of the files of the merged pack, and the relative paths
+/// of the conflicting files.
+fn merge(
+	packs: &[&TempDir],
+	conflict_handling: PackMergeConflictHandling
+) -> (Vec<(String, PathBuf)>, Vec<String>) {
+	let pack_directories = packs
+		.iter()
+		.map(|pack| pack.path().to_path_buf())
+		.collect::<Vec<_>>();
+
+	let mut vfs = PackMergingVfs::new(OsFilesystem);
+	let conflicts = vfs.merge_packs(
+		&pack_directories[0],
+		&pack_directories[1..],
+		IteratorTraversalOptions::default(),
+		conflict_handling
+	);
+
+	let mut files = vfs
+		.file_iterator(&pack_directories[0], IteratorTraversalOptions::default())
+		.map(|entry| {
+			let entry = entry.expect("I/O operations are assumed not to fail during tests");
+			(entry.relative_path.as_str().to_owned(), entry.file_path)
+		})
+		.collect::<Vec<_>>();
+	files.sort();
+
+	(
+		files,
+		conflicts
+			.into_iter()
+			.map(|conflict| conflict.relative_path.as_str().to_owned())
+			.collect()
+	)
+}
+
+#[test]
+fn packs_are_merged_with_the_specified_conflict_handling() {
+	let main_pack = pack_directory(&[("pack.mcmeta", "{}"), ("assets/a/lang/en_us.json", "{}")]);
+	let merged_pack = pack_directory(&[("pack.mcmeta", "{}"), ("assets/b/lang/en_us.json", "{}")]);
+
+	for (conflict_handling, pack_metadata_file_pack) in [
+		(PackMergeConflictHandling::FirstWins, &main_pack),
+		(PackMergeConflictHandling::LastWins, &merged_pack),
+		(PackMergeConflictHandling::Error, &main_pack)
+	] {
+		assert_eq!(
+			merge(&[&main_pack, &merged_pack], conflict_handling),
+			(
+				vec![
+					(
+						"assets/a/lang/en_us.json".to_owned(),
+						main_pack.path().join("assets/a/lang/en_us.json")
+					),
+					(
+						"assets/b/lang/en_us.json".to_owned(),
+						merged_pack.path().join("assets/b/lang/en_us.json")
+					),
+					(
+						"pack.mcmeta".to_owned(),
+						pack_metadata_file_pack.path().join("pack.mcmeta")
+					)
+				],
+				vec!["pack.mcmeta".to_owned()]
+			)
+		);
+	}
+}
+
+#[test]
+fn single_packs_are_not_changed() {
+	let pack = pack_directory(&[("pack.mcmeta", "{}")]);
+
+	assert_eq!(
+		merge(&[&pack], PackMergeConflictHandling::Error),
+		(
+			vec![("pack.mcmeta".to_owned(), pack.path().join("pack.mcmeta"))],
+			vec![]
+		)
+	);
+}
+
+#[tokio::test]
+async fn main_pack_paths_open_the_kept_files() {
+	let main_pack = pack_directory(&[("pack.mcmeta", "main")]);
+	let merged_pack = pack_directory(&[("pack.mcmeta", "merged"), ("pack.png", "icon")]);
+
+	let mut vfs = PackMergingVfs::new(OsFilesystem);
+	vfs.merge_packs(
+		main_pack.path(),
+		&[merged_pack.path().to_path_buf()],
+		IteratorTraversalOptions::default(),
+		PackMergeConflictHandling::LastWins
+	);
+
+	for (relative_path, expected_data) in [("pack.mcmeta", "merged"), ("pack.png", "icon")] {
+		let mut data = String::new();
+		vfs.open(main_pack.path().join(relative_path))
+			.expect("I/O operations are assumed not to fail during tests")
+			.file_read
+			.read_to_string(&mut data)
+			.await
+			.expect("I/O operations are assumed not to fail during tests");
+
+		assert_eq!(data, expected_data);
+	}
+}
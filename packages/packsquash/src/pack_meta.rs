@@ -17,6 +17,8 @@ use crate::{config::MinecraftQuirk, vfs::VirtualFileSystem};
 #[cfg(test)]
 mod tests;
 
+/// The pack format version used in Minecraft versions from 1.11 to 1.12.2.
+pub const PACK_FORMAT_VERSION_1_11: i32 = 3;
 /// The pack format version used in Minecraft versions from 1.13 to 1.14.4.
 pub const PACK_FORMAT_VERSION_1_13: i32 = 4;
 /// The pack format version used in Minecraft versions from 1.15 to 1.16.1.
@@ -33,6 +35,12 @@ pub const PACK_FORMAT_RESOURCE_PACK_VERSION_1_18: i32 = 8;
 pub const PACK_FORMAT_RESOURCE_PACK_VERSION_24W_13A: i32 = 31;
 /// The resource pack format version used in Minecraft version 24w40a (1.21.2 snapshot).
 pub const PACK_FORMAT_RESOURCE_PACK_VERSION_24W_40A: i32 = 40;
+/// The resource pack format version used in Minecraft versions from 1.21 to 1.21.1.
+pub const PACK_FORMAT_RESOURCE_PACK_VERSION_1_21: i32 = 34;
+/// The resource pack format version used in Minecraft version 1.21.6.
+pub const PACK_FORMAT_RESOURCE_PACK_VERSION_1_21_6: i32 = 63;
+/// The data pack format version used in Minecraft versions from 1.20.5 to 1.20.6.
+pub const PACK_FORMAT_DATA_PACK_VERSION_1_20_5: i32 = 41;
 /// The data pack format version used in Minecraft versions from 24w21a (1.21 snapshot)
 /// to 1.21-pre1.
 pub const PACK_FORMAT_DATA_PACK_VERSION_24W_21A: i32 = 45;
@@ -45,7 +53,8 @@ pub const PACK_FORMAT_DATA_PACK_VERSION_24W_21A: i32 = 45;
 /// - <https://minecraft.wiki/w/Data_Pack#pack.mcmeta>
 /// - Minecraft class `net.minecraft.server.packs.metadata.pack.PackMetadataSectionSerializer`
 pub struct PackMeta {
-	pack_format_version: i32
+	pack_format_version: i32,
+	forge_resource_pack_format_version: Option<i32>
 }
 
 /// Represents an error that may happen while parsing pack metadata files.
@@ -69,6 +78,7 @@ impl PackMeta {
 			"\"pack_format\" version is not a Java integer";
 
 		let pack_format_version;
+		let mut forge_resource_pack_format_version = None;
 
 		let mut file = vfs
 			.open(root_path.as_ref().join("pack.mcmetac"))
@@ -114,6 +124,13 @@ impl PackMeta {
 							}
 						};
 
+						// Forge reads the resource pack format version of mod resources from a
+						// different key, as their pack_format key holds the data pack one
+						forge_resource_pack_format_version = pack_meta_object
+							.get("forge:resource_pack_format")
+							.and_then(Value::as_i64)
+							.and_then(|version| i32::try_from(version).ok());
+
 						// Also validate the pack description, because it is required by Minecraft
 						match pack_meta_object.get("description") {
 							Some(Value::String(_))
@@ -149,10 +166,29 @@ impl PackMeta {
 		};
 
 		Ok(Self {
-			pack_format_version
+			pack_format_version,
+			forge_resource_pack_format_version
 		})
 	}
 
+	/// Makes the `forge:resource_pack_format` version of the pack, if it has one, its
+	/// `pack_format` version. Forge reads the resource pack format version of the resources of
+	/// mods from that key, because their `pack_format` key holds their data pack format version.
+	///
+	/// References:
+	/// - Forge 1.20.1 `net.minecraftforge.common.ForgeHooks` class
+	pub fn use_forge_resource_pack_format_version(&mut self) {
+		if let Some(forge_resource_pack_format_version) = self.forge_resource_pack_format_version {
+			self.pack_format_version = forge_resource_pack_format_version;
+		}
+	}
+
+	/// Returns the `pack_format` version of the pack, which identifies the Minecraft versions
+	/// it targets.
+	pub const fn pack_format_version(&self) -> i32 {
+		self.pack_format_version
+	}
+
 	/// Returns a maybe pessimistic set of Minecraft quirks that will need to be
 	/// worked around to guarantee that the pack will work as expected.
 	///
@@ -172,3 +172,26 @@ async fn pack_mcmeta_without_expected_structure() {
 		"Expected failure reading pack metadata"
 	);
 }
+
+#[tokio::test]
+async fn forge_resource_pack_format_is_used() {
+	let mut pack_meta = PackMeta::new(
+		&MockVfs(
+			r#"
+				{
+					"pack": {
+						"pack_format": 12,
+						"forge:resource_pack_format": 15,
+						"description": "My mod resources"
+					}
+				}"#
+		),
+		""
+	)
+	.await
+	.expect("Unexpected failure reading pack metadata");
+
+	assert_eq!(pack_meta.pack_format_version(), 12);
+	pack_meta.use_forge_resource_pack_format_version();
+	assert_eq!(pack_meta.pack_format_version(), 15);
+}
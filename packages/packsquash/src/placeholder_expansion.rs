@@ -0,0 +1,166 @@
+//! Contains a virtual file system adapter that expands `${name}` placeholders in text pack
+//! files with the values of user-defined variables.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf}
+};
+
+use ahash::AHashMap;
+use bytes::Bytes;
+use indexmap::IndexMap;
+use tokio_util::either::Either;
+
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file_to_string
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently replacing `${name}`
+/// placeholders in JSON, legacy language and text files with the values of the variables
+/// with that name. Placeholders of undefined variables are left as-is, so that files that
+/// happen to contain that syntax for other purposes are not broken.
+///
+/// The expanded files are computed eagerly by [`Self::expand_placeholders`] and kept in
+/// memory, because the [`VirtualFileSystem::open`] method is synchronous. Other files are
+/// read from the wrapped file system as-is.
+pub struct PlaceholderExpandingVfs<V: VirtualFileSystem> {
+	inner: V,
+	/// The contents of the files whose placeholders were expanded.
+	expanded_files: AHashMap<PathBuf, Bytes>
+}
+
+impl<V: VirtualFileSystem> PlaceholderExpandingVfs<V> {
+	/// Wraps the specified virtual file system. No placeholders will be expanded until
+	/// [`Self::expand_placeholders`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			expanded_files: AHashMap::new()
+		}
+	}
+
+	/// Scans the pack at the specified root path for JSON, legacy language and text files,
+	/// expanding the placeholders of the specified variables they contain. Values are escaped
+	/// as JSON string contents in JSON files, as placeholders are expected to be in strings
+	/// there. Files that can't be read or are not valid UTF-8 are left as-is.
+	pub async fn expand_placeholders(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions,
+		variables: &IndexMap<String, String>
+	) {
+		if variables.is_empty() {
+			return;
+		}
+
+		let text_files = self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+			.filter_map(|VfsPackFileIterEntry { file_path, .. }| {
+				let is_json = match file_path.extension()?.to_str()? {
+					"json" | "jsonc" | "mcmeta" | "mcmetac" => true,
+					"lang" | "txt" => false,
+					_ => return None
+				};
+
+				Some((file_path, is_json))
+			})
+			.collect::<Vec<_>>();
+
+		for (file_path, is_json) in text_files {
+			let Ok(text) = read_file_to_string(&self.inner, &file_path).await else {
+				continue;
+			};
+
+			if let Some(expanded_text) = expand_placeholders(&text, variables, is_json) {
+				self.expanded_files.insert(file_path, expanded_text.into());
+			}
+		}
+	}
+}
+
+/// Replaces the `${name}` placeholders of the specified variables in a text, returning `None`
+/// if it does not contain any. When `escape_for_json` is `true`, the values are escaped as the
+/// contents of a JSON string.
+fn expand_placeholders(
+	text: &str,
+	variables: &IndexMap<String, String>,
+	escape_for_json: bool
+) -> Option<String> {
+	let mut expanded_text = String::new();
+	let mut expanded_up_to = 0;
+	let mut search_start = 0;
+
+	while let Some(placeholder_start) = text[search_start..]
+		.find("${")
+		.map(|offset| search_start + offset)
+	{
+		let name_start = placeholder_start + 2;
+		let Some(name_end) = text[name_start..]
+			.find('}')
+			.map(|offset| name_start + offset)
+		else {
+			break;
+		};
+
+		let Some(value) = variables.get(&text[name_start..name_end]) else {
+			// Not a placeholder of a defined variable. Keep looking right after its start,
+			// as it may contain the start of another placeholder
+			search_start = name_start;
+			continue;
+		};
+
+		expanded_text.push_str(&text[expanded_up_to..placeholder_start]);
+		if escape_for_json {
+			let quoted_value = serde_json::to_string(value).unwrap();
+			expanded_text.push_str(&quoted_value[1..quoted_value.len() - 1]);
+		} else {
+			expanded_text.push_str(value);
+		}
+
+		expanded_up_to = name_end + 1;
+		search_start = expanded_up_to;
+	}
+
+	(expanded_up_to > 0).then(|| {
+		expanded_text.push_str(&text[expanded_up_to..]);
+		expanded_text
+	})
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for PlaceholderExpandingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = V::FileIter;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		// The values of the variables may change without the file being modified, so
+		// expanded files must always be processed again instead of reusing their
+		// previous version
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.expanded_files
+				.get(path.as_ref())
+				.map(|expanded_file| (expanded_file, None))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
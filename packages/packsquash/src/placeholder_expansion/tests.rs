@@ -0,0 +1,76 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+use tokio::io::AsyncReadExt;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[test]
+fn placeholders_are_expanded() {
+	let root_dir = Builder::new()
+		.prefix("ps-placeholder-expansion-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"pack.mcmeta",
+			r#"{ "pack": { "pack_format": 15, "description": "v${version} (${unknown}) ${${version}" } }"#
+		),
+		("assets/mypack/lang/en_us.lang", "credits=By ${author}\n"),
+		("credits.txt", "Built on ${build_date} by ${author}"),
+		("assets/mypack/textures/block/ruby.png", "${version}"),
+		("notes.txt", "Nothing to expand here ${version")
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mut vfs = PlaceholderExpandingVfs::new(OsFilesystem);
+	tokio_test::block_on(
+		vfs.expand_placeholders(
+			root_dir.path(),
+			IteratorTraversalOptions::default(),
+			&[
+				("version", "1.2.0"),
+				("build_date", "2024-05-01"),
+				("author", r#"Ana "The Builder""#)
+			]
+			.into_iter()
+			.map(|(name, value)| (name.to_string(), value.to_string()))
+			.collect()
+		)
+	);
+
+	let read_file = |relative_path: &str| {
+		let mut file = vfs
+			.open(root_dir.path().join(relative_path))
+			.expect("I/O operations are assumed not to fail during tests");
+		let mut data = String::new();
+		tokio_test::block_on(file.file_read.read_to_string(&mut data))
+			.expect("I/O operations are assumed not to fail during tests");
+		data
+	};
+
+	assert_eq!(
+		read_file("pack.mcmeta"),
+		r#"{ "pack": { "pack_format": 15, "description": "v1.2.0 (${unknown}) ${1.2.0" } }"#
+	);
+	assert_eq!(
+		read_file("assets/mypack/lang/en_us.lang"),
+		"credits=By Ana \"The Builder\"\n"
+	);
+	assert_eq!(
+		read_file("credits.txt"),
+		"Built on 2024-05-01 by Ana \"The Builder\""
+	);
+	assert_eq!(
+		read_file("assets/mypack/textures/block/ruby.png"),
+		"${version}"
+	);
+	assert_eq!(read_file("notes.txt"), "Nothing to expand here ${version");
+}
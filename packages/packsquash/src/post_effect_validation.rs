@@ -0,0 +1,416 @@
+//! Contains routines to validate the post-processing effect pipelines of a pack, checking how
+//! their passes are wired to render targets and to the programs they run.
+
+use std::path::{Path, PathBuf};
+
+use ahash::{AHashMap, AHashSet};
+use json_comments::StripComments;
+use serde_json::Value;
+
+use crate::RelativePath;
+use crate::pack_file::strip_utf8_bom;
+use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem, read_file};
+
+#[cfg(test)]
+mod tests;
+
+/// The namespace of vanilla assets, which is also the namespace of resource locations that do
+/// not specify any.
+const VANILLA_NAMESPACE: &str = "minecraft";
+
+/// The render target Minecraft draws the world to, which post-processing effects read from
+/// and write to without declaring it.
+const MAIN_TARGET: &str = "minecraft:main";
+
+/// A pass of a post-processing effect pipeline, normalized from any of its JSON formats.
+#[derive(Default)]
+struct Pass {
+	/// The name of the program the pass runs, as written in the JSON file.
+	program_name: String,
+	/// The relative paths of the files that define the program the pass runs.
+	program_paths: Vec<String>,
+	/// The relative path of the JSON definition of the program the pass runs, if it has one.
+	program_definition_path: Option<String>,
+	/// The names of the program samplers the pass binds.
+	bound_samplers: Vec<String>,
+	/// The render targets the pass reads from.
+	input_targets: Vec<String>,
+	/// The relative paths of the textures the pass reads from.
+	input_textures: Vec<String>,
+	/// The render target the pass writes to.
+	output_target: Option<String>
+}
+
+/// Parses the post-processing effect pipelines of the pack at the specified root path, in
+/// both the `post_effect` folder Minecraft reads them from since 1.21.2 and the `shaders/post`
+/// folder used before, finding wiring mistakes that Minecraft does not report or only reports
+/// with cryptic log messages. The following is checked:
+///
+/// - That the programs and textures passes use are in the pack. Assets of the vanilla
+///   namespace are not checked, because they may be provided by the game.
+/// - That every render target passes read from or write to is declared, that every declared
+///   render target is used, and that no pass reads from a render target no pass writes to.
+/// - That passes bind every sampler of the programs they run, and only those, when the JSON
+///   definitions of the programs are in the pack.
+///
+/// Files that can't be read or parsed are ignored, so that the usual pack file processing
+/// reports any relevant error. Mismatches are sorted by path.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Shader#Post-processing_effects>
+pub async fn validate_post_effects<V: VirtualFileSystem>(
+	vfs: &V,
+	root_path: &Path,
+	iterator_traversal_options: IteratorTraversalOptions
+) -> Vec<(RelativePath<'static>, String)> {
+	let mut pack_files = AHashMap::new();
+	let mut post_effect_files = vec![];
+	for VfsPackFileIterEntry {
+		relative_path,
+		file_path
+	} in vfs
+		.file_iterator(root_path, iterator_traversal_options)
+		.flatten()
+	{
+		if is_post_effect(relative_path.as_str()) {
+			post_effect_files.push((relative_path.clone(), file_path.clone()));
+		}
+
+		pack_files.insert(relative_path.as_str().to_owned(), file_path);
+	}
+	post_effect_files.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+	let mut mismatches = vec![];
+	for (relative_path, file_path) in post_effect_files {
+		let Some(post_effect) = read_json(vfs, &file_path).await else {
+			continue;
+		};
+
+		let (declared_targets, passes) = if relative_path.as_str().contains("/post_effect/") {
+			parse_post_effect(&post_effect)
+		} else {
+			parse_legacy_post_effect(&post_effect)
+		};
+
+		let mut program_samplers = AHashMap::new();
+		for program_definition_path in passes
+			.iter()
+			.filter_map(|pass| pass.program_definition_path.as_ref())
+		{
+			if !program_samplers.contains_key(program_definition_path)
+				&& let Some(file_path) = pack_files.get(program_definition_path)
+			{
+				program_samplers.insert(
+					program_definition_path.clone(),
+					read_program_samplers(vfs, file_path).await
+				);
+			}
+		}
+
+		for mismatch in validate_passes(&declared_targets, &passes, &pack_files, &program_samplers) {
+			mismatches.push((relative_path.clone(), mismatch));
+		}
+	}
+
+	mismatches
+}
+
+/// Checks whether the specified relative path is of a post-processing effect pipeline.
+fn is_post_effect(relative_path: &str) -> bool {
+	relative_path.ends_with(".json")
+		&& relative_path
+			.strip_prefix("assets/")
+			.and_then(|path| path.split_once('/'))
+			.is_some_and(|(_, path)| {
+				path.starts_with("post_effect/") || path.starts_with("shaders/post/")
+			})
+}
+
+/// Adds the vanilla namespace to the specified resource location if it does not have any.
+fn namespaced(resource_location: &str) -> String {
+	if resource_location.contains(':') {
+		resource_location.to_owned()
+	} else {
+		format!("{VANILLA_NAMESPACE}:{resource_location}")
+	}
+}
+
+/// Returns the relative path of the asset with the specified resource location, which is
+/// located within the specified folder of its namespace and has the specified extension.
+fn asset_path(resource_location: &str, folder: &str, extension: &str) -> String {
+	let (namespace, path) = resource_location
+		.split_once(':')
+		.unwrap_or((VANILLA_NAMESPACE, resource_location));
+
+	format!("assets/{namespace}/{folder}/{path}.{extension}")
+}
+
+/// Parses a post-processing effect pipeline in the format Minecraft reads from the
+/// `post_effect` folder since 1.21.2, returning its declared render targets and passes.
+fn parse_post_effect(post_effect: &Value) -> (AHashSet<String>, Vec<Pass>) {
+	let declared_targets = post_effect
+		.get("targets")
+		.and_then(Value::as_object)
+		.into_iter()
+		.flatten()
+		.map(|(target, _)| namespaced(target))
+		.collect();
+
+	let passes = post_effect
+		.get("passes")
+		.and_then(Value::as_array)
+		.into_iter()
+		.flatten()
+		.map(|pass| {
+			let mut parsed_pass = Pass {
+				output_target: pass.get("output").and_then(Value::as_str).map(namespaced),
+				..Default::default()
+			};
+
+			if let Some(program) = pass.get("program").and_then(Value::as_str) {
+				let program_definition_path = asset_path(program, "shaders", "json");
+
+				parsed_pass.program_name = program.to_owned();
+				parsed_pass.program_paths = vec![program_definition_path.clone()];
+				parsed_pass.program_definition_path = Some(program_definition_path);
+			} else {
+				// Since 1.21.5, passes reference their shaders directly
+				for (key, extension) in [("vertex_shader", "vsh"), ("fragment_shader", "fsh")] {
+					if let Some(shader) = pass.get(key).and_then(Value::as_str) {
+						parsed_pass
+							.program_paths
+							.push(asset_path(shader, "shaders", extension));
+						parsed_pass.program_name = shader.to_owned();
+					}
+				}
+			}
+
+			for input in pass
+				.get("inputs")
+				.and_then(Value::as_array)
+				.into_iter()
+				.flatten()
+			{
+				if let Some(sampler_name) = input.get("sampler_name").and_then(Value::as_str) {
+					parsed_pass
+						.bound_samplers
+						.push(format!("{sampler_name}Sampler"));
+				}
+
+				if let Some(target) = input.get("target").and_then(Value::as_str) {
+					parsed_pass.input_targets.push(namespaced(target));
+				} else if let Some(texture) = input.get("location").and_then(Value::as_str) {
+					parsed_pass
+						.input_textures
+						.push(asset_path(texture, "textures", "png"));
+				}
+			}
+
+			parsed_pass
+		})
+		.collect();
+
+	(declared_targets, passes)
+}
+
+/// Parses a post-processing effect pipeline in the format Minecraft reads from the
+/// `shaders/post` folder before 1.21.2, returning its declared render targets and passes.
+fn parse_legacy_post_effect(post_effect: &Value) -> (AHashSet<String>, Vec<Pass>) {
+	let declared_targets = post_effect
+		.get("targets")
+		.and_then(Value::as_array)
+		.into_iter()
+		.flatten()
+		.filter_map(|target| match target {
+			Value::String(target) => Some(namespaced(target)),
+			target => Some(namespaced(target.get("name")?.as_str()?))
+		})
+		.collect::<AHashSet<_>>();
+
+	let passes = post_effect
+		.get("passes")
+		.and_then(Value::as_array)
+		.into_iter()
+		.flatten()
+		.map(|pass| {
+			let mut parsed_pass = Pass {
+				output_target: pass
+					.get("outtarget")
+					.and_then(Value::as_str)
+					.map(namespaced),
+				..Default::default()
+			};
+
+			if let Some(program) = pass.get("name").and_then(Value::as_str) {
+				let program_definition_path = asset_path(program, "shaders/program", "json");
+
+				parsed_pass.program_name = program.to_owned();
+				parsed_pass.program_paths = vec![program_definition_path.clone()];
+				parsed_pass.program_definition_path = Some(program_definition_path);
+			}
+
+			// The input target is always bound to the diffuse sampler
+			if let Some(target) = pass.get("intarget").and_then(Value::as_str) {
+				parsed_pass.bound_samplers.push("DiffuseSampler".into());
+				parsed_pass.input_targets.push(namespaced(target));
+			}
+
+			for auxiliary_target in pass
+				.get("auxtargets")
+				.and_then(Value::as_array)
+				.into_iter()
+				.flatten()
+			{
+				if let Some(sampler_name) = auxiliary_target.get("name").and_then(Value::as_str) {
+					parsed_pass.bound_samplers.push(sampler_name.to_owned());
+				}
+
+				// Auxiliary targets whose identifier is not a declared target are textures
+				let Some(id) = auxiliary_target.get("id").and_then(Value::as_str) else {
+					continue;
+				};
+				let target = namespaced(id.strip_suffix(":depth").unwrap_or(id));
+				if target == MAIN_TARGET || declared_targets.contains(&target) {
+					parsed_pass.input_targets.push(target);
+				} else {
+					parsed_pass
+						.input_textures
+						.push(asset_path(id, "textures/effect", "png"));
+				}
+			}
+
+			parsed_pass
+		})
+		.collect();
+
+	(declared_targets, passes)
+}
+
+/// Validates the wiring of the specified passes of a post-processing effect pipeline,
+/// returning a description of every mismatch found.
+fn validate_passes(
+	declared_targets: &AHashSet<String>,
+	passes: &[Pass],
+	pack_files: &AHashMap<String, PathBuf>,
+	program_samplers: &AHashMap<String, Option<Vec<String>>>
+) -> Vec<String> {
+	let is_in_pack = |relative_path: &str| {
+		pack_files.contains_key(relative_path)
+			|| relative_path.starts_with(&format!("assets/{VANILLA_NAMESPACE}/"))
+	};
+	let is_declared = |target: &String| target == MAIN_TARGET || declared_targets.contains(target);
+
+	let mut mismatches = vec![];
+	for (index, pass) in passes.iter().enumerate() {
+		let pass_description = format!("pass {} ({})", index + 1, pass.program_name);
+
+		for program_path in &pass.program_paths {
+			if !is_in_pack(program_path) {
+				mismatches.push(format!(
+					"{pass_description} uses {program_path}, which is not in the pack"
+				));
+			}
+		}
+
+		for texture_path in &pass.input_textures {
+			if !is_in_pack(texture_path) {
+				mismatches.push(format!(
+					"{pass_description} reads the texture {texture_path}, which is not in the pack"
+				));
+			}
+		}
+
+		for target in pass
+			.input_targets
+			.iter()
+			.filter(|target| !is_declared(*target))
+		{
+			mismatches.push(format!(
+				"{pass_description} reads the undeclared target {target}"
+			));
+		}
+
+		if let Some(target) = pass
+			.output_target
+			.as_ref()
+			.filter(|target| !is_declared(*target))
+		{
+			mismatches.push(format!(
+				"{pass_description} writes to the undeclared target {target}"
+			));
+		}
+
+		if let Some(Some(samplers)) = pass
+			.program_definition_path
+			.as_ref()
+			.and_then(|path| program_samplers.get(path))
+		{
+			for sampler in &pass.bound_samplers {
+				if !samplers.contains(sampler) {
+					mismatches.push(format!(
+						"{pass_description} binds the sampler {sampler}, which its program does not define"
+					));
+				}
+			}
+
+			for sampler in samplers {
+				if !pass.bound_samplers.contains(sampler) {
+					mismatches.push(format!(
+						"{pass_description} does not bind the sampler {sampler} of its program"
+					));
+				}
+			}
+		}
+	}
+
+	let written_targets = passes
+		.iter()
+		.filter_map(|pass| pass.output_target.as_ref())
+		.collect::<AHashSet<_>>();
+	let read_targets = passes
+		.iter()
+		.flat_map(|pass| &pass.input_targets)
+		.collect::<AHashSet<_>>();
+
+	let mut declared_targets = declared_targets.iter().collect::<Vec<_>>();
+	declared_targets.sort_unstable();
+	for target in declared_targets {
+		match (
+			written_targets.contains(target),
+			read_targets.contains(target)
+		) {
+			(false, false) => mismatches.push(format!(
+				"the target {target} is declared, but no pass uses it"
+			)),
+			(false, true) => mismatches.push(format!(
+				"the target {target} is read, but no pass writes to it"
+			)),
+			_ => {}
+		}
+	}
+
+	mismatches
+}
+
+/// Reads the names of the samplers defined by the shader program JSON definition at the
+/// specified path of a virtual file system, returning `None` if some I/O or parsing error
+/// occurs.
+async fn read_program_samplers<V: VirtualFileSystem>(vfs: &V, path: &Path) -> Option<Vec<String>> {
+	Some(
+		read_json(vfs, path)
+			.await?
+			.get("samplers")?
+			.as_array()?
+			.iter()
+			.filter_map(|sampler| Some(sampler.get("name")?.as_str()?.to_owned()))
+			.collect()
+	)
+}
+
+/// Reads and parses the JSON file at the specified path of a virtual file system, returning
+/// `None` if some I/O or parsing error occurs.
+async fn read_json<V: VirtualFileSystem>(vfs: &V, path: &Path) -> Option<Value> {
+	let (data, _) = read_file(vfs, path).await.ok()?;
+
+	serde_json::from_reader(StripComments::new(strip_utf8_bom(&data))).ok()
+}
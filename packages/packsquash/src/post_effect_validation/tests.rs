@@ -0,0 +1,114 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[test]
+fn post_effects_are_validated() {
+	let root_dir = Builder::new()
+		.prefix("ps-post-effect-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"assets/mypack/post_effect/glow.json",
+			r#"{
+				"targets": { "swap": {}, "unused": {}, "blank": {} },
+				"passes": [
+					{
+						"program": "mypack:post/blur",
+						"inputs": [
+							{ "sampler_name": "In", "target": "minecraft:main" },
+							{ "sampler_name": "Noise", "location": "mypack:effect/noise", "width": 4, "height": 4 }
+						],
+						"output": "swap"
+					},
+					{
+						"program": "mypack:post/missing",
+						"inputs": [{ "sampler_name": "In", "target": "blank" }],
+						"output": "minecraft:main"
+					},
+					{
+						"program": "minecraft:post/blit",
+						"inputs": [{ "sampler_name": "In", "target": "undeclared" }],
+						"output": "minecraft:main"
+					}
+				]
+			}"#
+		),
+		(
+			"assets/mypack/shaders/post/blur.json",
+			r#"{ "samplers": [{ "name": "InSampler" }, { "name": "DepthSampler" }] }"#
+		),
+		(
+			"assets/minecraft/shaders/post/legacy.json",
+			r#"{
+				"targets": ["swap"],
+				"passes": [
+					{ "name": "blit", "intarget": "minecraft:main", "outtarget": "swap" },
+					{
+						"name": "blit",
+						"intarget": "swap",
+						"outtarget": "minecraft:main",
+						"auxtargets": [{ "name": "NoiseSampler", "id": "noise", "width": 4, "height": 4 }]
+					}
+				]
+			}"#
+		)
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mismatches = tokio_test::block_on(validate_post_effects(
+		&OsFilesystem,
+		root_dir.path(),
+		IteratorTraversalOptions::default()
+	));
+
+	assert_eq!(
+		mismatches
+			.iter()
+			.map(|(relative_path, mismatch)| (relative_path.as_str(), mismatch.as_str()))
+			.collect::<Vec<_>>(),
+		[
+			(
+				"assets/mypack/post_effect/glow.json",
+				"pass 1 (mypack:post/blur) reads the texture assets/mypack/textures/effect/noise.png, \
+				which is not in the pack"
+			),
+			(
+				"assets/mypack/post_effect/glow.json",
+				"pass 1 (mypack:post/blur) binds the sampler NoiseSampler, which its program does not \
+				define"
+			),
+			(
+				"assets/mypack/post_effect/glow.json",
+				"pass 1 (mypack:post/blur) does not bind the sampler DepthSampler of its program"
+			),
+			(
+				"assets/mypack/post_effect/glow.json",
+				"pass 2 (mypack:post/missing) uses assets/mypack/shaders/post/missing.json, which is \
+				not in the pack"
+			),
+			(
+				"assets/mypack/post_effect/glow.json",
+				"pass 3 (minecraft:post/blit) reads the undeclared target minecraft:undeclared"
+			),
+			(
+				"assets/mypack/post_effect/glow.json",
+				"the target minecraft:blank is read, but no pass writes to it"
+			),
+			(
+				"assets/mypack/post_effect/glow.json",
+				"the target minecraft:unused is declared, but no pass uses it"
+			)
+		]
+	);
+}
@@ -0,0 +1,295 @@
+//! Contains the data structures and routines used to generate machine-readable manifests that
+//! describe the outcome of a squash operation, so that downstream tooling, such as launchers
+//! or CDN uploaders, can consume it.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+use std::slice;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::WarningCategory;
+use crate::{PackFileStatus, PackSquasherStatus, PackSquasherWarning, RelativePath};
+
+#[cfg(test)]
+mod tests;
+
+/// The size and content hash of some file data.
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct FileDigest {
+	/// The size of the data, in bytes.
+	size: u64,
+	/// The lowercase hexadecimal SHA-256 hash of the data.
+	sha256: String
+}
+
+impl FileDigest {
+	/// Computes the digest of the specified data.
+	pub fn of(data: &[u8]) -> Self {
+		let mut digester = FileDigester::default();
+		digester.update(data);
+		digester.finish()
+	}
+
+	/// Gets the size of the data, in bytes.
+	pub const fn size(&self) -> u64 {
+		self.size
+	}
+
+	/// Gets the lowercase hexadecimal SHA-256 hash of the data.
+	pub fn sha256(&self) -> &str {
+		&self.sha256
+	}
+
+	/// Computes the digest of the file at the specified path, reading it in chunks.
+	fn of_file(path: &Path) -> Result<Self, io::Error> {
+		Self::of_reader(File::open(path)?)
+	}
+
+	/// Computes the digest of the data yielded by the specified reader, reading it in chunks.
+	pub fn of_reader(mut read: impl Read) -> Result<Self, io::Error> {
+		let mut digester = FileDigester::default();
+		let mut buf = vec![0; 64 * 1024];
+
+		loop {
+			match read.read(&mut buf)? {
+				0 => break Ok(digester.finish()),
+				read_bytes => digester.update(&buf[..read_bytes])
+			}
+		}
+	}
+}
+
+/// Computes a [`FileDigest`] of data that is not available all at once.
+#[derive(Default)]
+pub struct FileDigester {
+	hasher: Sha256,
+	size: u64
+}
+
+impl FileDigester {
+	/// Feeds the specified chunk of data to this digester.
+	pub fn update(&mut self, data: &[u8]) {
+		self.hasher.update(data);
+		self.size += data.len() as u64;
+	}
+
+	/// Returns the digest of the data fed to this digester, consuming it.
+	pub fn finish(self) -> FileDigest {
+		FileDigest {
+			size: self.size,
+			sha256: self
+				.hasher
+				.finalize()
+				.iter()
+				.fold(String::with_capacity(64), |mut hex, byte| {
+					write!(hex, "{byte:02x}").unwrap();
+					hex
+				})
+		}
+	}
+}
+
+/// What happened to a single pack file during a squash operation.
+#[derive(Serialize, Default, Clone, Debug)]
+#[non_exhaustive]
+pub struct RunManifestFile {
+	/// The optimization strategy that was applied to the file, which describes the
+	/// transformations done to it, or why it was skipped. These strings are user-friendly,
+	/// and may change between releases.
+	pub optimization_strategy: Option<String>,
+	/// The error that occurred while processing the file, if any.
+	pub error: Option<String>,
+	/// Whether the file was not included in the output ZIP file.
+	pub skipped: bool,
+	/// The size and hash of the input file. It is `None` if the file was not processed or
+	/// could not be read again.
+	pub input: Option<FileDigest>,
+	/// The size and hash of the file stored in the output ZIP file, before compression. It is
+	/// `None` if the file was not processed, or was copied from a previous run.
+	pub output: Option<FileDigest>
+}
+
+/// A warning emitted during a squash operation.
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub struct RunManifestWarning {
+	/// The category of the warning, which is serialized as a stable, snake case identifier.
+	pub kind: WarningCategory,
+	/// The relative paths of the pack files the warning is about, if any.
+	pub paths: Vec<String>,
+	/// Further details about the warning, if any. These strings are meant for humans, and may
+	/// change between releases.
+	pub detail: Option<String>
+}
+
+impl From<&PackSquasherWarning> for RunManifestWarning {
+	fn from(warning: &PackSquasherWarning) -> Self {
+		let (paths, detail): (&[RelativePath<'static>], _) = match warning {
+			PackSquasherWarning::UnusablePreviousZip(err) => (&[], Some(err.to_string())),
+			PackSquasherWarning::PredictableSystemTimeSanitizationKey => (&[], None),
+			PackSquasherWarning::VolatileSystemTimeSanitizationKey => (&[], None),
+			#[cfg(unix)]
+			PackSquasherWarning::ConcurrencyLimitedDueToOpenFdLimits => (&[], None),
+			PackSquasherWarning::VanillaIdenticalTexture(path) => (slice::from_ref(path), None),
+			#[cfg(feature = "optifine")]
+			PackSquasherWarning::MissingConnectedTextureTiles(path, tiles) => {
+				(slice::from_ref(path), Some(tiles.join(", ")))
+			}
+			#[cfg(feature = "optifine")]
+			PackSquasherWarning::CustomEntityModelMismatch(path, detail) => {
+				(slice::from_ref(path), Some(detail.clone()))
+			}
+			#[cfg(feature = "optifine")]
+			PackSquasherWarning::EmissiveTextureMismatch(path, detail) => {
+				(slice::from_ref(path), Some(detail.clone()))
+			}
+			PackSquasherWarning::DuplicateSounds(paths) => (paths, None),
+			PackSquasherWarning::MissingSound(path, sound) => {
+				(slice::from_ref(path), Some(sound.clone()))
+			}
+			PackSquasherWarning::UnreferencedSound(path) => (slice::from_ref(path), None),
+			PackSquasherWarning::AudioPreviewNotGenerated(path) => (slice::from_ref(path), None),
+			PackSquasherWarning::StereoPositionalSound(path) => (slice::from_ref(path), None),
+			PackSquasherWarning::PackMergeConflict(path, kept_file_path, _) => (
+				slice::from_ref(path),
+				Some(format!("Kept {}", kept_file_path.display()))
+			),
+			PackSquasherWarning::JsonSchemaViolation(path, detail) => {
+				(slice::from_ref(path), Some(detail.clone()))
+			}
+			PackSquasherWarning::UnreferencedAsset(path) => (slice::from_ref(path), None),
+			PackSquasherWarning::DuplicateTranslations(path, count) => (
+				slice::from_ref(path),
+				Some(format!("{count} duplicate translations"))
+			),
+			PackSquasherWarning::ShaderProgramMismatch(path, detail) => {
+				(slice::from_ref(path), Some(detail.clone()))
+			}
+			PackSquasherWarning::PostEffectMismatch(path, detail) => {
+				(slice::from_ref(path), Some(detail.clone()))
+			}
+			PackSquasherWarning::FontProviderMismatch(path, detail) => {
+				(slice::from_ref(path), Some(detail.clone()))
+			}
+			PackSquasherWarning::ItemModelDefinitionMismatch(path, detail) => {
+				(slice::from_ref(path), Some(detail.clone()))
+			}
+			PackSquasherWarning::AtlasSourceMismatch(path, detail) => {
+				(slice::from_ref(path), Some(detail.clone()))
+			}
+			PackSquasherWarning::DataPackReferenceMismatch(path, detail) => {
+				(slice::from_ref(path), Some(detail.clone()))
+			}
+		};
+
+		Self {
+			kind: warning.category(),
+			paths: paths.iter().map(|path| path.as_str().to_owned()).collect(),
+			detail
+		}
+	}
+}
+
+/// A manifest that lists every pack file of a squash operation, with their sizes, hashes and
+/// applied optimization strategies, and the warnings emitted during it. It is collected from
+/// the status updates of the squash operation and the data of the processed pack files.
+#[derive(Default)]
+pub struct RunManifest {
+	files: Mutex<BTreeMap<String, RunManifestFile>>,
+	warnings: Mutex<Vec<RunManifestWarning>>
+}
+
+/// The JSON representation of a finished [`RunManifest`].
+#[derive(Serialize)]
+struct RunManifestSummary<'manifest> {
+	/// The path, size and hash of the output ZIP file.
+	output_file: OutputFileSummary<'manifest>,
+	/// The number of pack files in the manifest.
+	file_count: usize,
+	/// The number of pack files that were not included in the output ZIP file.
+	skipped_file_count: usize,
+	/// The outcome of every pack file, by relative path. Files that were stored in the output
+	/// ZIP file are listed by their path in it.
+	files: &'manifest BTreeMap<String, RunManifestFile>,
+	/// The warnings emitted during the squash operation, in emission order.
+	warnings: &'manifest [RunManifestWarning]
+}
+
+/// The JSON representation of the output ZIP file in a [`RunManifestSummary`].
+#[derive(Serialize)]
+struct OutputFileSummary<'path> {
+	path: &'path Path,
+	#[serde(flatten)]
+	digest: FileDigest
+}
+
+impl RunManifest {
+	/// Records the information about pack files and warnings conveyed by the specified status
+	/// update of the squash operation. Other status updates are ignored.
+	pub fn record_status(&self, status: &PackSquasherStatus) {
+		match status {
+			PackSquasherStatus::PackFileProcessed(PackFileStatus {
+				path,
+				optimization_strategy,
+				optimization_error,
+				skipped,
+				..
+			}) => {
+				let mut files = self.files.lock().unwrap();
+				let file = files.entry(path.as_str().to_owned()).or_default();
+
+				file.optimization_strategy = Some(optimization_strategy.to_string());
+				file.error.clone_from(optimization_error);
+				file.skipped = *skipped;
+			}
+			PackSquasherStatus::Warning(warning) => {
+				self.warnings.lock().unwrap().push(warning.into());
+			}
+			_ => {}
+		}
+	}
+
+	/// Records the digests of the input and output data of the pack file stored at the
+	/// specified path of the output ZIP file.
+	pub fn record_file_digests(
+		&self,
+		path: &RelativePath<'_>,
+		input: Option<FileDigest>,
+		output: Option<FileDigest>
+	) {
+		let mut files = self.files.lock().unwrap();
+		let file = files.entry(path.as_str().to_owned()).or_default();
+
+		file.input = input;
+		file.output = output;
+	}
+
+	/// Writes this manifest to a file at the specified path in JSON format, consuming it. The
+	/// output ZIP file at the specified path is read to compute its digest.
+	pub fn write(self, file_path: &Path, output_file_path: &Path) -> Result<(), io::Error> {
+		let files = self.files.into_inner().unwrap();
+		let warnings = self.warnings.into_inner().unwrap();
+
+		let summary = RunManifestSummary {
+			output_file: OutputFileSummary {
+				path: output_file_path,
+				digest: FileDigest::of_file(output_file_path)?
+			},
+			file_count: files.len(),
+			skipped_file_count: files.values().filter(|file| file.skipped).count(),
+			files: &files,
+			warnings: &warnings
+		};
+
+		fs::write(
+			file_path,
+			serde_json::to_vec_pretty(&summary).map_err(io::Error::from)?
+		)
+	}
+}
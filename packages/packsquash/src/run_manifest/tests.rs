@@ -0,0 +1,117 @@
+use std::borrow::Cow;
+
+use pretty_assertions::assert_eq;
+use tempfile::NamedTempFile;
+
+use super::*;
+
+#[test]
+fn file_digests_are_computed() {
+	assert_eq!(
+		FileDigest::of(b"abc"),
+		FileDigest {
+			size: 3,
+			sha256: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".into()
+		}
+	);
+
+	let mut digester = FileDigester::default();
+	digester.update(b"a");
+	digester.update(b"");
+	digester.update(b"bc");
+	assert_eq!(digester.finish(), FileDigest::of(b"abc"));
+}
+
+#[test]
+fn statuses_and_digests_are_recorded() {
+	let run_manifest = RunManifest::default();
+
+	run_manifest.record_file_digests(
+		&RelativePath::from_inner("assets/example/sounds/step.ogg"),
+		Some(FileDigest::of(b"input")),
+		Some(FileDigest::of(b"output"))
+	);
+	for (path, optimization_strategy, skipped) in [
+		("assets/example/sounds/step.ogg", "Optimized", false),
+		("notes.txt", "Skipped", true)
+	] {
+		run_manifest.record_status(&PackSquasherStatus::PackFileProcessed(PackFileStatus {
+			path: RelativePath::from_inner(path),
+			optimization_strategy: Cow::Borrowed(optimization_strategy),
+			optimization_error: None,
+			skipped,
+			processing_time: None,
+			input_size: None,
+			output_size: None
+		}));
+	}
+	run_manifest.record_status(&PackSquasherStatus::Warning(
+		PackSquasherWarning::StereoPositionalSound(RelativePath::from_inner(
+			"assets/example/sounds/step.ogg"
+		))
+	));
+	run_manifest.record_status(&PackSquasherStatus::ZipFinish);
+
+	let files = run_manifest.files.into_inner().unwrap();
+	assert_eq!(
+		files
+			.iter()
+			.map(|(path, file)| (
+				path.as_str(),
+				file.optimization_strategy.as_deref(),
+				file.skipped,
+				file.input.is_some(),
+				file.output.as_ref().map(|output| output.size)
+			))
+			.collect::<Vec<_>>(),
+		[
+			(
+				"assets/example/sounds/step.ogg",
+				Some("Optimized"),
+				false,
+				true,
+				Some(6)
+			),
+			("notes.txt", Some("Skipped"), true, false, None)
+		]
+	);
+
+	let warnings = run_manifest.warnings.into_inner().unwrap();
+	assert_eq!(
+		warnings,
+		[RunManifestWarning {
+			kind: WarningCategory::StereoPositionalSound,
+			paths: vec!["assets/example/sounds/step.ogg".into()],
+			detail: None
+		}]
+	);
+	assert_eq!(
+		serde_json::to_value(&warnings[0]).unwrap()["kind"],
+		"stereo_positional_sound"
+	);
+}
+
+#[test]
+fn manifests_are_written() {
+	let output_file =
+		NamedTempFile::new().expect("I/O operations are assumed not to fail during tests");
+	fs::write(output_file.path(), b"abc")
+		.expect("I/O operations are assumed not to fail during tests");
+	let manifest_file =
+		NamedTempFile::new().expect("I/O operations are assumed not to fail during tests");
+
+	RunManifest::default()
+		.write(manifest_file.path(), output_file.path())
+		.expect("I/O operations are assumed not to fail during tests");
+
+	let manifest: serde_json::Value = serde_json::from_slice(
+		&fs::read(manifest_file.path()).expect("I/O operations are assumed not to fail during tests")
+	)
+	.expect("Written manifests are valid JSON");
+	assert_eq!(manifest["output_file"]["size"], 3);
+	assert_eq!(
+		manifest["output_file"]["sha256"],
+		"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+	);
+	assert_eq!(manifest["file_count"], 0);
+}
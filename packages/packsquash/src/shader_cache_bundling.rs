@@ -0,0 +1,154 @@
+//! Contains a virtual file system adapter that bundles precompiled shader artifacts, kept in a
+//! directory outside the pack, next to the shaders they were compiled from.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf},
+	sync::Arc
+};
+
+use ahash::{AHashMap, AHashSet};
+use bytes::Bytes;
+use tokio_util::either::Either;
+
+use crate::RelativePath;
+use crate::config::ShaderCacheOptions;
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently adding the precompiled
+/// artifacts of its shaders found in an external directory as if they were pack files
+/// located next to the shaders. This is an extension point for clients that accept
+/// precompiled shader caches, which PackSquash can't generate by itself.
+///
+/// The artifacts are read eagerly by [`Self::bundle_artifacts`] and kept in memory, because
+/// the [`VirtualFileSystem::open`] method is synchronous. Other files are read from the
+/// wrapped file system as-is.
+pub struct ShaderCacheBundlingVfs<V: VirtualFileSystem> {
+	inner: V,
+	/// The contents of the bundled artifacts.
+	artifacts: AHashMap<PathBuf, Bytes>,
+	/// The relative and file paths of the bundled artifacts, which are added to file iterators.
+	artifact_paths: Arc<Vec<(String, PathBuf)>>
+}
+
+impl<V: VirtualFileSystem> ShaderCacheBundlingVfs<V> {
+	/// Wraps the specified virtual file system. No artifacts will be bundled until
+	/// [`Self::bundle_artifacts`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			artifacts: AHashMap::new(),
+			artifact_paths: Arc::new(Vec::new())
+		}
+	}
+
+	/// Scans the pack at the specified root path for shaders, bundling the artifacts with the
+	/// configured extensions that the configured artifacts directory contains for them.
+	/// Artifacts of shaders that are not in the pack are not bundled, so that stale artifacts
+	/// are left out, and artifacts that already are in the pack are not replaced. Returns the
+	/// number of bundled artifacts.
+	pub async fn bundle_artifacts(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions,
+		shader_cache_options: &ShaderCacheOptions
+	) -> usize {
+		let Some(artifacts_directory) = &shader_cache_options.artifacts_directory else {
+			return 0;
+		};
+
+		let pack_files = self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+			.map(|VfsPackFileIterEntry { relative_path, .. }| relative_path.as_str().to_owned())
+			.collect::<AHashSet<_>>();
+		let mut shader_paths = pack_files
+			.iter()
+			.filter(|relative_path| {
+				relative_path.starts_with("assets/")
+					&& relative_path.contains("/shaders/")
+					&& [".vsh", ".fsh", ".glsl"]
+						.iter()
+						.any(|extension| relative_path.ends_with(extension))
+			})
+			.collect::<Vec<_>>();
+		shader_paths.sort_unstable();
+
+		let mut artifact_paths = Vec::new();
+		for relative_path in shader_paths {
+			for extension in &shader_cache_options.artifact_extensions {
+				let artifact_relative_path = format!("{relative_path}.{extension}");
+				if pack_files.contains(&artifact_relative_path) {
+					continue;
+				}
+
+				let Ok(artifact) =
+					tokio::fs::read(artifacts_directory.join(&artifact_relative_path)).await
+				else {
+					continue;
+				};
+
+				let artifact_file_path = root_path.join(&artifact_relative_path);
+				self.artifacts
+					.insert(artifact_file_path.clone(), artifact.into());
+				artifact_paths.push((artifact_relative_path, artifact_file_path));
+			}
+		}
+
+		let bundled_artifact_count = artifact_paths.len();
+		self.artifact_paths = Arc::new(artifact_paths);
+
+		bundled_artifact_count
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for ShaderCacheBundlingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = impl Iterator<Item = Result<VfsPackFileIterEntry, io::Error>>;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		let artifact_entries = self
+			.artifact_paths
+			.iter()
+			.filter(|(_, file_path)| file_path.starts_with(root_path))
+			.map(|(relative_path, file_path)| {
+				Ok(VfsPackFileIterEntry {
+					relative_path: RelativePath::from_inner(relative_path.clone()),
+					file_path: file_path.clone()
+				})
+			})
+			.collect::<Vec<_>>();
+
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.chain(artifact_entries)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		// Artifacts are outside the pack directory, so their modification time is not
+		// tracked, and they must always be processed again
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.artifacts
+				.get(path.as_ref())
+				.map(|artifact| (artifact, None))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
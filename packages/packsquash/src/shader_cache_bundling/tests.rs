@@ -0,0 +1,89 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+use tokio::io::AsyncReadExt;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[tokio::test]
+async fn shader_artifacts_are_bundled() {
+	let pack_dir = Builder::new()
+		.prefix("ps-shader-cache-pack-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	let artifacts_dir = Builder::new()
+		.prefix("ps-shader-cache-artifacts-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (root_dir, relative_path, data) in [
+		(
+			&pack_dir,
+			"assets/minecraft/shaders/core/position.vsh",
+			"void main() {}"
+		),
+		(
+			&pack_dir,
+			"assets/minecraft/shaders/core/position.fsh",
+			"void main() {}"
+		),
+		(
+			&pack_dir,
+			"assets/minecraft/shaders/core/position.fsh.bin",
+			"in pack"
+		),
+		(
+			&artifacts_dir,
+			"assets/minecraft/shaders/core/position.vsh.bin",
+			"vertex"
+		),
+		(
+			&artifacts_dir,
+			"assets/minecraft/shaders/core/position.fsh.bin",
+			"fragment"
+		),
+		(
+			&artifacts_dir,
+			"assets/minecraft/shaders/core/removed.vsh.bin",
+			"stale"
+		)
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mut vfs = ShaderCacheBundlingVfs::new(OsFilesystem);
+	let bundled_artifact_count = vfs
+		.bundle_artifacts(
+			pack_dir.path(),
+			IteratorTraversalOptions::default(),
+			&ShaderCacheOptions {
+				artifacts_directory: Some(artifacts_dir.path().to_path_buf()),
+				artifact_extensions: vec!["bin".into()]
+			}
+		)
+		.await;
+
+	assert_eq!(bundled_artifact_count, 1);
+
+	let artifact_entry = vfs
+		.file_iterator(pack_dir.path(), IteratorTraversalOptions::default())
+		.flatten()
+		.find(|entry| {
+			entry.relative_path.as_str() == "assets/minecraft/shaders/core/position.vsh.bin"
+		})
+		.expect("The artifact should be bundled");
+
+	let mut artifact = String::new();
+	vfs.open(&artifact_entry.file_path)
+		.expect("The artifact should be readable")
+		.file_read
+		.read_to_string(&mut artifact)
+		.await
+		.expect("The artifact should be readable");
+	assert_eq!(artifact, "vertex");
+}
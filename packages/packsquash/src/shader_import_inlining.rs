@@ -0,0 +1,264 @@
+//! Contains a virtual file system adapter that inlines the shaders imported with `#moj_import`
+//! directives into the vertex and fragment shaders that import them.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf},
+	sync::LazyLock
+};
+
+use ahash::{AHashMap, AHashSet};
+use bytes::Bytes;
+use regex::Regex;
+use tokio_util::either::Either;
+
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file_to_string
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Matches a `#moj_import` directive line, capturing the imported path in the first group for
+/// relative imports, and in the second group for system imports.
+static MOJ_IMPORT_DIRECTIVE: LazyLock<Regex> = LazyLock::new(|| {
+	Regex::new(r#"(?m)^[ \t]*#[ \t]*moj_import[ \t]*(?:"([^"\n]*)"|<([^>\n]*)>)[^\n]*$"#).unwrap()
+});
+
+/// Matches a `#version` directive line.
+static VERSION_DIRECTIVE: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r"(?m)^[ \t]*#[ \t]*version\b").unwrap());
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently replacing the `#moj_import`
+/// directives of vanilla namespace vertex and fragment shaders with the source code of the
+/// shaders they import, like Minecraft does before compiling them. This gives the shader
+/// optimizer complete knowledge of the source code of those shaders, which is necessary to
+/// transform them safely.
+///
+/// The inlined shaders are computed eagerly by [`Self::inline_imports`] and kept in memory,
+/// because the [`VirtualFileSystem::open`] method is synchronous. Other files, including the
+/// imported shaders, are read from the wrapped file system as-is.
+pub struct ShaderImportInliningVfs<V: VirtualFileSystem> {
+	inner: V,
+	/// The contents of the shaders whose imports were inlined.
+	inlined_shaders: AHashMap<PathBuf, Bytes>
+}
+
+impl<V: VirtualFileSystem> ShaderImportInliningVfs<V> {
+	/// Wraps the specified virtual file system. No imports will be inlined until
+	/// [`Self::inline_imports`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			inlined_shaders: AHashMap::new()
+		}
+	}
+
+	/// Scans the pack at the specified root path for vanilla namespace vertex and fragment
+	/// shaders, inlining the shaders they import. Shaders are left as-is if some shader they
+	/// import is not in the pack or has a `#version` directive, as Minecraft may then import
+	/// a vanilla shader or merge the `#version` directives of several shaders, respectively.
+	pub async fn inline_imports(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) {
+		let shader_files = self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+			.filter(|VfsPackFileIterEntry { relative_path, .. }| {
+				let relative_path = relative_path.as_str();
+
+				relative_path.starts_with("assets/")
+					&& relative_path.contains("/shaders/")
+					&& [".vsh", ".fsh", ".glsl"]
+						.iter()
+						.any(|extension| relative_path.ends_with(extension))
+			})
+			.collect::<Vec<_>>();
+
+		let mut shader_sources = AHashMap::with_capacity(shader_files.len());
+		let mut top_level_shaders = Vec::new();
+		for VfsPackFileIterEntry {
+			relative_path,
+			file_path
+		} in shader_files
+		{
+			let Ok(source) = read_file_to_string(&self.inner, &file_path).await else {
+				continue;
+			};
+
+			let relative_path = relative_path.as_str().to_owned();
+			if relative_path.starts_with("assets/minecraft/shaders/")
+				&& !relative_path.ends_with(".glsl")
+			{
+				top_level_shaders.push((relative_path.clone(), file_path));
+			}
+			shader_sources.insert(relative_path, source);
+		}
+
+		for (relative_path, file_path) in top_level_shaders {
+			if let Some(inlined_source) = inline_imports(&relative_path, &shader_sources) {
+				self.inlined_shaders
+					.insert(file_path, inlined_source.into());
+			}
+		}
+	}
+}
+
+/// Inlines the shaders imported by the shader at the specified relative path, recursively,
+/// returning `None` if it does not import any shader or some import can't be inlined. Like
+/// Minecraft does, every shader is imported at most once, and relative imports are resolved
+/// against the directory of the shader that contains them, or of the top-level shader for
+/// shaders imported with a system import.
+fn inline_imports(shader_path: &str, shader_sources: &AHashMap<String, String>) -> Option<String> {
+	let mut imported_paths = AHashSet::new();
+
+	let inlined_source = inline_imports_of(
+		shader_sources.get(shader_path)?,
+		&shader_path[..shader_path.rfind('/')? + 1],
+		"",
+		shader_sources,
+		&mut imported_paths
+	)?;
+
+	(!imported_paths.is_empty()).then_some(inlined_source)
+}
+
+/// Returns the source code of the shader at the specified relative path with its imports
+/// inlined, which is the shader source itself if it does not import any shader, or `None`
+/// if it is not available or some import can't be inlined.
+pub(crate) fn resolve_imports(
+	shader_path: &str,
+	shader_sources: &AHashMap<String, String>
+) -> Option<String> {
+	let source = shader_sources.get(shader_path)?;
+
+	if MOJ_IMPORT_DIRECTIVE.is_match(source) {
+		inline_imports(shader_path, shader_sources)
+	} else {
+		Some(source.clone())
+	}
+}
+
+/// Recursive helper function for [`inline_imports`]. `relative_import_directory` is the
+/// directory relative imports in `source` are resolved against, relative to the directory
+/// of the top-level shader.
+fn inline_imports_of(
+	source: &str,
+	top_level_shader_directory: &str,
+	relative_import_directory: &str,
+	shader_sources: &AHashMap<String, String>,
+	imported_paths: &mut AHashSet<String>
+) -> Option<String> {
+	let mut inlined_source = String::with_capacity(source.len());
+	let mut inlined_up_to = 0;
+
+	for import_directive in MOJ_IMPORT_DIRECTIVE.captures_iter(source) {
+		let directive = import_directive.get(0).unwrap();
+		inlined_source.push_str(&source[inlined_up_to..directive.start()]);
+		inlined_up_to = directive.end();
+
+		let (import_path, imported_relative_import_directory) = if let Some(relative_import) =
+			import_directive.get(1)
+		{
+			let relative_import = format!("{relative_import_directory}{}", relative_import.as_str());
+			let imported_relative_import_directory =
+				relative_import[..relative_import.rfind('/').map_or(0, |i| i + 1)].to_owned();
+
+			(
+				normalize_path(&format!("{top_level_shader_directory}{relative_import}"))?,
+				imported_relative_import_directory
+			)
+		} else {
+			let system_import = import_directive.get(2).unwrap().as_str();
+			let (namespace, path) = system_import
+				.split_once(':')
+				.unwrap_or(("minecraft", system_import));
+
+			(
+				normalize_path(&format!("assets/{namespace}/shaders/include/{path}"))?,
+				String::new()
+			)
+		};
+
+		// Minecraft ignores imports of already imported shaders
+		if !imported_paths.insert(import_path.clone()) {
+			continue;
+		}
+
+		let imported_source = shader_sources.get(&import_path)?;
+		if VERSION_DIRECTIVE.is_match(imported_source) {
+			return None;
+		}
+
+		let inlined_import = inline_imports_of(
+			imported_source,
+			top_level_shader_directory,
+			&imported_relative_import_directory,
+			shader_sources,
+			imported_paths
+		)?;
+
+		inlined_source.push_str(&inlined_import);
+		if !inlined_import.ends_with('\n') {
+			inlined_source.push('\n');
+		}
+	}
+
+	inlined_source.push_str(&source[inlined_up_to..]);
+
+	Some(inlined_source)
+}
+
+/// Normalizes the `.` and `..` components of a relative path, returning `None` if the
+/// path points outside the pack.
+fn normalize_path(path: &str) -> Option<String> {
+	let mut components = Vec::new();
+
+	for component in path.split('/') {
+		match component {
+			"" | "." => {}
+			".." => {
+				components.pop()?;
+			}
+			component => components.push(component)
+		}
+	}
+
+	Some(components.join("/"))
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for ShaderImportInliningVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = V::FileIter;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		// Imported shaders may change without the importing shader being modified, so
+		// inlined shaders must always be processed again instead of reusing their
+		// previous version
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.inlined_shaders
+				.get(path.as_ref())
+				.map(|inlined_shader| (inlined_shader, None))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
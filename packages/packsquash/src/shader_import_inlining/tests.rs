@@ -0,0 +1,79 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+#[test]
+fn imports_are_inlined() {
+	let shader_sources = [
+		(
+			"assets/minecraft/shaders/core/rendertype_solid.vsh",
+			"#version 150\n\n#moj_import <light.glsl>\n#moj_import \"util/fog.glsl\"\n#moj_import <minecraft:light.glsl>\n\nvoid main() {}\n"
+		),
+		(
+			"assets/minecraft/shaders/include/light.glsl",
+			"float light() { return 1.0; }"
+		),
+		(
+			"assets/minecraft/shaders/core/util/fog.glsl",
+			"#moj_import \"../../include/light.glsl\"\n#moj_import \"noise.glsl\"\nfloat fog() { return noise(); }\n"
+		),
+		(
+			"assets/minecraft/shaders/core/util/noise.glsl",
+			"float noise() { return 0.5; }\n"
+		),
+		(
+			"assets/minecraft/shaders/core/rendertype_cutout.fsh",
+			"#version 150\n\n#moj_import <fog.glsl>\n\nvoid main() {}\n"
+		),
+		(
+			"assets/minecraft/shaders/core/rendertype_translucent.fsh",
+			"#version 150\n\nvoid main() {}\n"
+		),
+		(
+			"assets/minecraft/shaders/core/rendertype_glint.fsh",
+			"#version 150\n\n#moj_import <versioned.glsl>\n\nvoid main() {}\n"
+		),
+		(
+			"assets/minecraft/shaders/include/versioned.glsl",
+			"#version 330\n"
+		)
+	]
+	.into_iter()
+	.map(|(path, source)| (path.to_string(), source.to_string()))
+	.collect();
+
+	assert_eq!(
+		inline_imports(
+			"assets/minecraft/shaders/core/rendertype_solid.vsh",
+			&shader_sources
+		)
+		.as_deref(),
+		Some(
+			"#version 150\n\nfloat light() { return 1.0; }\n\n\nfloat noise() { return 0.5; }\n\nfloat fog() { return noise(); }\n\n\n\nvoid main() {}\n"
+		)
+	);
+	assert_eq!(
+		inline_imports(
+			"assets/minecraft/shaders/core/rendertype_cutout.fsh",
+			&shader_sources
+		),
+		None,
+		"Shaders that import shaders not in the pack should not be inlined"
+	);
+	assert_eq!(
+		inline_imports(
+			"assets/minecraft/shaders/core/rendertype_translucent.fsh",
+			&shader_sources
+		),
+		None,
+		"Shaders without imports should not be inlined"
+	);
+	assert_eq!(
+		inline_imports(
+			"assets/minecraft/shaders/core/rendertype_glint.fsh",
+			&shader_sources
+		),
+		None,
+		"Shaders that import shaders with a version directive should not be inlined"
+	);
+}
@@ -0,0 +1,289 @@
+//! Contains routines to cross-validate the shader program JSON definitions of a pack against
+//! the GLSL source code of the vertex and fragment shaders they reference.
+
+use std::{path::Path, sync::LazyLock};
+
+use ahash::{AHashMap, AHashSet};
+use json_comments::StripComments;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::RelativePath;
+use crate::pack_file::strip_utf8_bom;
+use crate::shader_import_inlining::resolve_imports;
+use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem, read_file};
+
+#[cfg(test)]
+mod tests;
+
+/// The namespace of vanilla shaders, which is also the namespace of shader names that do not
+/// specify any.
+const VANILLA_NAMESPACE: &str = "minecraft";
+
+/// Matches GLSL line and block comments.
+static COMMENT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)//[^\n]*|/\*.*?\*/").unwrap());
+
+/// Matches a declaration of a single uniform variable, capturing its type in the first group,
+/// its name in the second group, and its array size, if any, in the third group. Uniform
+/// blocks are not matched.
+static UNIFORM_DECLARATION: LazyLock<Regex> = LazyLock::new(|| {
+	Regex::new(r"\buniform\s+(?:(?:lowp|mediump|highp)\s+)?(\w+)\s+(\w+)\s*(?:\[\s*(\d+)\s*\])?\s*;")
+		.unwrap()
+});
+
+/// A uniform variable declared by the GLSL source code of a shader.
+struct UniformDeclaration {
+	/// The GLSL type of the uniform.
+	glsl_type: String,
+	/// The size of the uniform array, if the uniform is an array.
+	array_size: Option<u32>
+}
+
+/// Parses the shader program JSON definitions of the pack at the specified root path, finding
+/// the mismatches between them and the vertex and fragment shaders they reference, which
+/// Minecraft only reports with cryptic log messages, if at all. The following is checked:
+///
+/// - That the referenced vertex and fragment shaders are in the pack. Shaders of the vanilla
+///   namespace are not checked, because they may be provided by the game.
+/// - That every sampler and uniform defined in the JSON is declared by some shader, and that
+///   the types of uniforms match.
+/// - That every sampler and uniform declared by the shaders is defined in the JSON, as it
+///   will not be bound otherwise.
+///
+/// Declarations are only checked when both shaders and every shader they import are in the
+/// pack. Files that can't be read or parsed are ignored, so that the usual pack file
+/// processing reports any relevant error. Mismatches are sorted by path.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Shader#Core_shaders>
+pub async fn validate_shader_programs<V: VirtualFileSystem>(
+	vfs: &V,
+	root_path: &Path,
+	iterator_traversal_options: IteratorTraversalOptions
+) -> Vec<(RelativePath<'static>, String)> {
+	let mut shader_sources = AHashMap::new();
+	let mut program_files = vec![];
+	for VfsPackFileIterEntry {
+		relative_path,
+		file_path
+	} in vfs
+		.file_iterator(root_path, iterator_traversal_options)
+		.flatten()
+	{
+		let path = relative_path.as_str();
+		if !path.starts_with("assets/") || !path.contains("/shaders/") {
+			continue;
+		}
+
+		if path.ends_with(".json") {
+			if program_directory(path).is_some() {
+				program_files.push((relative_path, file_path));
+			}
+		} else if [".vsh", ".fsh", ".glsl"]
+			.iter()
+			.any(|extension| path.ends_with(extension))
+			&& let Ok((data, _)) = read_file(vfs, &file_path).await
+			&& let Ok(source) = String::from_utf8(data)
+		{
+			shader_sources.insert(path.to_owned(), source);
+		}
+	}
+	program_files.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+	let mut mismatches = vec![];
+	for (relative_path, file_path) in program_files {
+		let Some(program) = read_file(vfs, &file_path).await.ok().and_then(|(data, _)| {
+			serde_json::from_reader::<_, Value>(StripComments::new(strip_utf8_bom(&data))).ok()
+		}) else {
+			continue;
+		};
+
+		for mismatch in validate_shader_program(relative_path.as_str(), &program, &shader_sources) {
+			mismatches.push((relative_path.clone(), mismatch));
+		}
+	}
+
+	mismatches
+}
+
+/// Returns the directory of the shader program JSON definition at the specified relative
+/// path, which is also the directory its shaders are in, or `None` if the path is not of
+/// a shader program JSON definition.
+fn program_directory(relative_path: &str) -> Option<&'static str> {
+	let (namespace, path) = relative_path
+		.strip_prefix("assets/")?
+		.split_once("/shaders/")?;
+
+	if namespace.contains('/') {
+		None
+	} else if path.starts_with("core/") {
+		Some("core")
+	} else if path.starts_with("program/") {
+		Some("program")
+	} else {
+		None
+	}
+}
+
+/// Validates the specified shader program JSON definition, at the specified relative path,
+/// against the specified shader sources, returning a description of every mismatch found.
+fn validate_shader_program(
+	relative_path: &str,
+	program: &Value,
+	shader_sources: &AHashMap<String, String>
+) -> Vec<String> {
+	let mut mismatches = vec![];
+	let Some(program_directory) = program_directory(relative_path) else {
+		return mismatches;
+	};
+
+	let mut stage_sources = Vec::with_capacity(2);
+	for (stage, extension) in [("vertex", "vsh"), ("fragment", "fsh")] {
+		let Some(shader_name) = program.get(stage).and_then(Value::as_str) else {
+			mismatches.push(format!("the {stage} shader is not specified"));
+			continue;
+		};
+
+		let (namespace, path) = shader_name
+			.split_once(':')
+			.unwrap_or((VANILLA_NAMESPACE, shader_name));
+		let shader_path =
+			format!("assets/{namespace}/shaders/{program_directory}/{path}.{extension}");
+
+		if !shader_sources.contains_key(&shader_path) {
+			if namespace != VANILLA_NAMESPACE {
+				mismatches.push(format!(
+					"the {stage} shader {shader_name} is not in the pack"
+				));
+			}
+			continue;
+		}
+
+		stage_sources.push(resolve_imports(&shader_path, shader_sources));
+	}
+
+	// Declarations can only be cross-validated when the complete source code of both shaders
+	// is known, as any missing shader code may declare samplers and uniforms
+	if stage_sources.len() < 2 {
+		return mismatches;
+	}
+	let Some(stage_sources) = stage_sources.into_iter().collect::<Option<Vec<_>>>() else {
+		return mismatches;
+	};
+
+	let mut declared_samplers = AHashSet::new();
+	let mut declared_uniforms = AHashMap::new();
+	for source in &stage_sources {
+		let source = COMMENT.replace_all(source, " ");
+		for declaration in UNIFORM_DECLARATION.captures_iter(&source) {
+			let glsl_type = &declaration[1];
+			let name = declaration[2].to_owned();
+
+			if glsl_type.contains("sampler") {
+				declared_samplers.insert(name);
+			} else {
+				declared_uniforms.insert(
+					name,
+					UniformDeclaration {
+						glsl_type: glsl_type.to_owned(),
+						array_size: declaration
+							.get(3)
+							.and_then(|size| size.as_str().parse().ok())
+					}
+				);
+			}
+		}
+	}
+
+	for sampler in program
+		.get("samplers")
+		.and_then(Value::as_array)
+		.into_iter()
+		.flatten()
+		.filter_map(|sampler| sampler.get("name")?.as_str())
+	{
+		if !declared_samplers.remove(sampler) {
+			mismatches.push(format!(
+				"the sampler {sampler} is not declared by its shaders"
+			));
+		}
+	}
+
+	for uniform in program
+		.get("uniforms")
+		.and_then(Value::as_array)
+		.into_iter()
+		.flatten()
+	{
+		let Some(name) = uniform.get("name").and_then(Value::as_str) else {
+			continue;
+		};
+
+		let Some(declaration) = declared_uniforms.remove(name) else {
+			mismatches.push(format!("the uniform {name} is not declared by its shaders"));
+			continue;
+		};
+
+		let uniform_type = uniform.get("type").and_then(Value::as_str);
+		let count = uniform.get("count").and_then(Value::as_u64);
+		if let Some(uniform_type) = uniform_type
+			&& let Some(expected_glsl_type) = expected_glsl_type(uniform_type, count)
+			&& declaration.array_size.is_none()
+			&& normalize_glsl_type(&declaration.glsl_type) != expected_glsl_type
+		{
+			mismatches.push(format!(
+				"the uniform {name} is defined with type {uniform_type}, but declared as {} \
+				instead of {expected_glsl_type}",
+				declaration.glsl_type
+			));
+		}
+	}
+
+	let mut undefined_samplers = declared_samplers.into_iter().collect::<Vec<_>>();
+	undefined_samplers.sort_unstable();
+	for sampler in undefined_samplers {
+		mismatches.push(format!(
+			"the sampler {sampler} is declared by its shaders, but not defined"
+		));
+	}
+
+	let mut undefined_uniforms = declared_uniforms.into_keys().collect::<Vec<_>>();
+	undefined_uniforms.sort_unstable();
+	for uniform in undefined_uniforms {
+		mismatches.push(format!(
+			"the uniform {uniform} is declared by its shaders, but not defined"
+		));
+	}
+
+	mismatches
+}
+
+/// Returns the GLSL type a uniform defined in a shader program JSON definition with the
+/// specified type and value count is expected to be declared as, or `None` if the type is
+/// unknown or its value count is out of range.
+fn expected_glsl_type(uniform_type: &str, count: Option<u64>) -> Option<&'static str> {
+	Some(match (uniform_type, count.unwrap_or(1)) {
+		("float", 1) => "float",
+		("float", 2) => "vec2",
+		("float", 3) => "vec3",
+		("float", 4) => "vec4",
+		("int", 1) => "int",
+		("int", 2) => "ivec2",
+		("int", 3) => "ivec3",
+		("int", 4) => "ivec4",
+		("matrix2x2", _) => "mat2",
+		("matrix3x3", _) => "mat3",
+		("matrix4x4", _) => "mat4",
+		_ => return None
+	})
+}
+
+/// Normalizes the name of a GLSL square matrix type to its shortest form.
+fn normalize_glsl_type(glsl_type: &str) -> &str {
+	match glsl_type {
+		"mat2x2" => "mat2",
+		"mat3x3" => "mat3",
+		"mat4x4" => "mat4",
+		glsl_type => glsl_type
+	}
+}
@@ -0,0 +1,96 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[test]
+fn shader_programs_are_validated() {
+	let root_dir = Builder::new()
+		.prefix("ps-shader-program-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"assets/minecraft/shaders/core/rendertype_solid.json",
+			r#"{
+				"vertex": "rendertype_solid",
+				"fragment": "rendertype_solid",
+				"samplers": [{ "name": "Sampler0" }, { "name": "Sampler2" }],
+				"uniforms": [
+					{ "name": "ModelViewMat", "type": "matrix4x4", "count": 16, "values": [] },
+					{ "name": "ColorModulator", "type": "float", "count": 3, "values": [] },
+					{ "name": "FogStart", "type": "float", "count": 1, "values": [0.0] }
+				]
+			}"#
+		),
+		(
+			"assets/minecraft/shaders/core/rendertype_solid.vsh",
+			"#version 150\n\n#moj_import <matrices.glsl>\n\nuniform sampler2D Sampler2;\n\nvoid main() {}\n"
+		),
+		(
+			"assets/minecraft/shaders/include/matrices.glsl",
+			"uniform mat4x4 ModelViewMat;\n// uniform mat4 ProjMat;\n"
+		),
+		(
+			"assets/minecraft/shaders/core/rendertype_solid.fsh",
+			"#version 150\n\nuniform sampler2D Sampler1;\nuniform vec4 ColorModulator;\nuniform float FogEnd;\n\nvoid main() {}\n"
+		),
+		(
+			"assets/minecraft/shaders/core/rendertype_cutout.json",
+			r#"{ "vertex": "rendertype_cutout", "fragment": "mypack:missing", "uniforms": [{ "name": "Unknown" }] }"#
+		),
+		(
+			"assets/minecraft/shaders/core/rendertype_cutout.vsh",
+			"#version 150\n\nvoid main() {}\n"
+		)
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mismatches = tokio_test::block_on(validate_shader_programs(
+		&OsFilesystem,
+		root_dir.path(),
+		IteratorTraversalOptions::default()
+	));
+
+	assert_eq!(
+		mismatches
+			.iter()
+			.map(|(relative_path, mismatch)| (relative_path.as_str(), mismatch.as_str()))
+			.collect::<Vec<_>>(),
+		[
+			(
+				"assets/minecraft/shaders/core/rendertype_cutout.json",
+				"the fragment shader mypack:missing is not in the pack"
+			),
+			(
+				"assets/minecraft/shaders/core/rendertype_solid.json",
+				"the sampler Sampler0 is not declared by its shaders"
+			),
+			(
+				"assets/minecraft/shaders/core/rendertype_solid.json",
+				"the uniform ColorModulator is defined with type float, but declared as vec4 instead \
+				of vec3"
+			),
+			(
+				"assets/minecraft/shaders/core/rendertype_solid.json",
+				"the uniform FogStart is not declared by its shaders"
+			),
+			(
+				"assets/minecraft/shaders/core/rendertype_solid.json",
+				"the sampler Sampler1 is declared by its shaders, but not defined"
+			),
+			(
+				"assets/minecraft/shaders/core/rendertype_solid.json",
+				"the uniform FogEnd is declared by its shaders, but not defined"
+			)
+		]
+	);
+}
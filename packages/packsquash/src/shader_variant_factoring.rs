@@ -0,0 +1,337 @@
+//! Contains a virtual file system adapter that factors families of core shaders that only
+//! differ in a few numeric constants into a single shared shader, which each shader of the
+//! family imports after defining its constants.
+
+use std::{
+	collections::BTreeMap,
+	fs::FileType,
+	io::{self, Cursor},
+	ops::Range,
+	path::{Path, PathBuf},
+	sync::{Arc, LazyLock}
+};
+
+use ahash::AHashMap;
+use bytes::Bytes;
+use regex::Regex;
+use tokio_util::either::Either;
+
+use crate::RelativePath;
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file_to_string
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum number of different numeric constants the shaders of a family may have. Shaders
+/// that differ in more constants are not considered near-identical.
+const MAXIMUM_VARIANT_CONSTANTS: usize = 8;
+
+/// The prefix of the names of the macros that replace the different constants of a family.
+const VARIANT_CONSTANT_MACRO_PREFIX: &str = "PS_VARIANT_";
+
+/// Matches the `#version` directive line of a shader, which must stay in the shaders of a
+/// family, as shaders with a `#version` directive can't be imported.
+static VERSION_DIRECTIVE_LINE: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r"(?m)^[ \t]*#[ \t]*version\b[^\n]*\n").unwrap());
+
+/// Matches a GLSL token. Whitespace and comments are matched together as a single token, and
+/// operators are matched character by character, which is enough to compare shaders.
+static TOKEN: LazyLock<Regex> = LazyLock::new(|| {
+	Regex::new(
+		r"(?s)(?P<space>(?:\s|//[^\n]*|/\*.*?\*/)+)|(?P<number>0[xX][0-9a-fA-F]+[uU]?|(?:\d+\.\d*|\.\d+|\d+)(?:[eE][+-]?\d+)?(?:[fF]|[lL][fF]|[uU])?)|(?P<identifier>[A-Za-z_]\w*)|."
+	)
+	.unwrap()
+});
+
+/// A family of near-identical shaders factored by [`factor_shader_variants`].
+#[derive(Debug, PartialEq)]
+struct FactoredFamily {
+	/// The relative path of the shared shader the shaders of the family import.
+	shared_shader_path: String,
+	/// The source code of the shared shader.
+	shared_shader_source: String,
+	/// The relative paths and new source code of the shaders of the family.
+	variants: Vec<(String, String)>
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently replacing families of
+/// vanilla namespace core shaders that only differ in a few numeric constants with a shared
+/// shader that contains their common source code, and short shaders that define the
+/// constants of each variant as macros before importing the shared shader. This is common
+/// in packs that ship several versions of the same effect with different parameters.
+///
+/// The factored shaders are computed eagerly by [`Self::factor_variants`] and kept in memory,
+/// because the [`VirtualFileSystem::open`] method is synchronous. Other files are read from
+/// the wrapped file system as-is.
+pub struct ShaderVariantFactoringVfs<V: VirtualFileSystem> {
+	inner: V,
+	/// The contents of the shared shaders and the shaders that import them.
+	generated_files: AHashMap<PathBuf, Bytes>,
+	/// The relative and file paths of the shared shaders, which are added to file iterators.
+	shared_shader_paths: Arc<Vec<(String, PathBuf)>>
+}
+
+impl<V: VirtualFileSystem> ShaderVariantFactoringVfs<V> {
+	/// Wraps the specified virtual file system. No shaders will be factored until
+	/// [`Self::factor_variants`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			generated_files: AHashMap::new(),
+			shared_shader_paths: Arc::new(Vec::new())
+		}
+	}
+
+	/// Scans the pack at the specified root path for families of near-identical vanilla
+	/// namespace core shaders, factoring them into shared shaders. Returns the relative paths
+	/// of the shaders of each factored family, followed by the relative path of the shared
+	/// shader they import. Families are only factored if that makes them smaller.
+	pub async fn factor_variants(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Vec<(Vec<RelativePath<'static>>, RelativePath<'static>)> {
+		let mut shader_sources = BTreeMap::new();
+		let mut file_paths = AHashMap::new();
+		for VfsPackFileIterEntry {
+			relative_path,
+			file_path
+		} in self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+		{
+			let relative_path = relative_path.as_str().to_owned();
+
+			if relative_path.starts_with("assets/minecraft/shaders/core/")
+				&& (relative_path.ends_with(".vsh") || relative_path.ends_with(".fsh"))
+				&& let Ok(source) = read_file_to_string(&self.inner, &file_path).await
+			{
+				shader_sources.insert(relative_path.clone(), source);
+			}
+
+			file_paths.insert(relative_path, file_path);
+		}
+
+		let mut factored_families = Vec::new();
+		let mut shared_shader_paths = Vec::new();
+		for family in factor_shader_variants(&shader_sources) {
+			// Never overwrite a file of the pack with a shared shader
+			if file_paths.contains_key(&family.shared_shader_path) {
+				continue;
+			}
+
+			let shared_shader_file_path = root_path.join(&family.shared_shader_path);
+			self.generated_files.insert(
+				shared_shader_file_path.clone(),
+				family.shared_shader_source.into()
+			);
+			shared_shader_paths.push((family.shared_shader_path.clone(), shared_shader_file_path));
+
+			let mut variant_paths = Vec::with_capacity(family.variants.len());
+			for (relative_path, source) in family.variants {
+				self.generated_files
+					.insert(file_paths[&relative_path].clone(), source.into());
+				variant_paths.push(RelativePath::from_inner(relative_path));
+			}
+
+			factored_families.push((
+				variant_paths,
+				RelativePath::from_inner(family.shared_shader_path)
+			));
+		}
+		self.shared_shader_paths = Arc::new(shared_shader_paths);
+
+		factored_families
+	}
+}
+
+/// Finds the families of near-identical shaders among the specified shaders, indexed by their
+/// relative path, and factors them. Shaders are near-identical when their `#version`
+/// directives are equal, and the rest of their tokens are equal except for at most
+/// [`MAXIMUM_VARIANT_CONSTANTS`] numeric literals. Whitespace and comments are not compared.
+fn factor_shader_variants(shader_sources: &BTreeMap<String, String>) -> Vec<FactoredFamily> {
+	let mut families = BTreeMap::<_, Vec<_>>::new();
+	for (relative_path, source) in shader_sources {
+		let Some(version_directive) = VERSION_DIRECTIVE_LINE.find(source) else {
+			continue;
+		};
+		let (prologue, body) = source.split_at(version_directive.end());
+
+		// The shared shader is imported right after the #version directive, so nothing but
+		// whitespace and comments may precede it
+		if TOKEN
+			.captures_iter(&source[..version_directive.start()])
+			.any(|token| token.name("space").is_none())
+		{
+			continue;
+		}
+
+		let mut skeleton = String::with_capacity(body.len());
+		let mut numbers = Vec::new();
+		for token in TOKEN.captures_iter(body) {
+			if let Some(space) = token.name("space") {
+				skeleton.push(if space.as_str().contains('\n') {
+					'\n'
+				} else {
+					' '
+				});
+			} else if let Some(number) = token.name("number") {
+				skeleton.push('#');
+				numbers.push(number.range());
+			} else {
+				skeleton.push_str(&token[0]);
+			}
+			skeleton.push('\0');
+		}
+
+		families
+			.entry((prologue, skeleton))
+			.or_default()
+			.push((relative_path, body, numbers));
+	}
+
+	families
+		.into_iter()
+		.filter(|(_, members)| members.len() > 1)
+		.filter_map(|((prologue, _), members)| factor_family(prologue, &members))
+		.collect()
+}
+
+/// Factors a family of shaders whose `#version` directive and preceding text is the
+/// specified prologue, and whose bodies only differ in the numeric literals at the specified
+/// ranges. Literals that have the same value as each other in every shader are replaced with
+/// the same macro. `None` is returned if the family can't be factored, or factoring it would
+/// not make it smaller.
+fn factor_family(
+	prologue: &str,
+	members: &[(&String, &str, Vec<Range<usize>>)]
+) -> Option<FactoredFamily> {
+	let (first_path, first_body, first_numbers) = &members[0];
+	if first_body.contains(VARIANT_CONSTANT_MACRO_PREFIX) {
+		return None;
+	}
+
+	// Map every literal that differs between shaders to the index of the macro that replaces
+	// it, keeping the values of each macro in every shader
+	let mut macro_values = Vec::<Vec<&str>>::new();
+	let mut literal_macros = Vec::new();
+	for literal_index in 0..first_numbers.len() {
+		let values = members
+			.iter()
+			.map(|(_, body, numbers)| &body[numbers[literal_index].clone()])
+			.collect::<Vec<_>>();
+		if values.iter().all(|value| *value == values[0]) {
+			continue;
+		}
+
+		let macro_index = macro_values
+			.iter()
+			.position(|existing_values| *existing_values == values)
+			.unwrap_or_else(|| {
+				macro_values.push(values);
+				macro_values.len() - 1
+			});
+		literal_macros.push((literal_index, macro_index));
+	}
+	if macro_values.is_empty() || macro_values.len() > MAXIMUM_VARIANT_CONSTANTS {
+		return None;
+	}
+
+	let shared_shader_name = format!(
+		"{}_variants.glsl",
+		first_path
+			.strip_prefix("assets/minecraft/shaders/core/")?
+			.replace(['/', '.'], "_")
+	);
+	let shared_shader_path = format!("assets/minecraft/shaders/include/{shared_shader_name}");
+
+	let mut shared_shader_source = String::with_capacity(first_body.len());
+	let mut copied_up_to = 0;
+	for (literal_index, macro_index) in literal_macros {
+		let literal_range = &first_numbers[literal_index];
+		shared_shader_source.push_str(&first_body[copied_up_to..literal_range.start]);
+		shared_shader_source.push_str(&format!("{VARIANT_CONSTANT_MACRO_PREFIX}{macro_index}"));
+		copied_up_to = literal_range.end;
+	}
+	shared_shader_source.push_str(&first_body[copied_up_to..]);
+
+	let variants = members
+		.iter()
+		.enumerate()
+		.map(|(member_index, (relative_path, _, _))| {
+			let mut source = prologue.to_owned();
+			for (macro_index, values) in macro_values.iter().enumerate() {
+				source.push_str(&format!(
+					"#define {VARIANT_CONSTANT_MACRO_PREFIX}{macro_index} {}\n",
+					values[member_index]
+				));
+			}
+			source.push_str(&format!("#moj_import <{shared_shader_name}>\n"));
+
+			((*relative_path).clone(), source)
+		})
+		.collect::<Vec<_>>();
+
+	let original_size = members
+		.iter()
+		.map(|(_, body, _)| prologue.len() + body.len())
+		.sum::<usize>();
+	let factored_size = shared_shader_source.len()
+		+ variants
+			.iter()
+			.map(|(_, source)| source.len())
+			.sum::<usize>();
+
+	(factored_size < original_size).then_some(FactoredFamily {
+		shared_shader_path,
+		shared_shader_source,
+		variants
+	})
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for ShaderVariantFactoringVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = impl Iterator<Item = Result<VfsPackFileIterEntry, io::Error>>;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		let shared_shader_entries = self
+			.shared_shader_paths
+			.iter()
+			.filter(|(_, file_path)| file_path.starts_with(root_path))
+			.map(|(relative_path, file_path)| {
+				Ok(VfsPackFileIterEntry {
+					relative_path: RelativePath::from_inner(relative_path.clone()),
+					file_path: file_path.clone()
+				})
+			})
+			.collect::<Vec<_>>();
+
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.chain(shared_shader_entries)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		// Factored shaders depend on the contents of every shader of their family, so
+		// they must always be processed again instead of reusing their previous version
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.generated_files
+				.get(path.as_ref())
+				.map(|generated_file| (generated_file, None))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
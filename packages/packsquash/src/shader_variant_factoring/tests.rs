@@ -0,0 +1,66 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+#[test]
+fn shader_variants_are_factored() {
+	let body = |radius: &str, strength: &str| {
+		format!(
+			"\nuniform sampler2D Sampler0;\n\nin vec2 texCoord0;\nout vec4 fragColor;\n\n\
+			// Blurs the texture with a fixed radius\nvoid main() {{\n\tvec4 color = vec4(0.0);\n\
+			\tfor (int i = -{radius}; i <= {radius}; i++) {{\n\t\tcolor += texture(Sampler0, texCoord0 + \
+			vec2(float(i) / 256.0, 0.0));\n\t}}\n\tfragColor = color / float({radius} * 2 + 1) * {strength};\n}}\n"
+		)
+	};
+
+	let shader_sources = [
+		(
+			"assets/minecraft/shaders/core/blur/small.fsh",
+			format!("#version 150\n{}", body("2", "1.0"))
+		),
+		(
+			"assets/minecraft/shaders/core/blur/large.fsh",
+			format!("#version 150\n{}", body("8", "1.5"))
+		),
+		(
+			"assets/minecraft/shaders/core/blur/newer.fsh",
+			format!("#version 330\n{}", body("4", "1.0"))
+		),
+		(
+			"assets/minecraft/shaders/core/other.fsh",
+			"#version 150\n\nvoid main() {}\n".to_owned()
+		)
+	]
+	.into_iter()
+	.map(|(relative_path, source)| (relative_path.to_owned(), source))
+	.collect::<BTreeMap<_, _>>();
+
+	let families = factor_shader_variants(&shader_sources);
+
+	assert_eq!(families.len(), 1, "Exactly one family should be factored");
+	assert_eq!(
+		families[0].shared_shader_path,
+		"assets/minecraft/shaders/include/blur_large_fsh_variants.glsl"
+	);
+	assert_eq!(
+		families[0].shared_shader_source,
+		body("PS_VARIANT_0", "PS_VARIANT_1")
+	);
+	assert_eq!(
+		families[0].variants,
+		[
+			(
+				"assets/minecraft/shaders/core/blur/large.fsh".to_owned(),
+				"#version 150\n#define PS_VARIANT_0 8\n#define PS_VARIANT_1 1.5\n\
+				#moj_import <blur_large_fsh_variants.glsl>\n"
+					.to_owned()
+			),
+			(
+				"assets/minecraft/shaders/core/blur/small.fsh".to_owned(),
+				"#version 150\n#define PS_VARIANT_0 2\n#define PS_VARIANT_1 1.0\n\
+				#moj_import <blur_large_fsh_variants.glsl>\n"
+					.to_owned()
+			)
+		]
+	);
+}
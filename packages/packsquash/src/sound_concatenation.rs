@@ -0,0 +1,256 @@
+//! Contains a virtual file system adapter that concatenates sounds split into several part
+//! files into a single sound file, according to manifests that list the parts in order.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::SystemTime
+};
+
+use ahash::{AHashMap, AHashSet};
+use bytes::Bytes;
+use json_comments::StripComments;
+use serde::Deserialize;
+use tokio_util::either::Either;
+
+use crate::RelativePath;
+use crate::pack_file::{concatenate_audio, is_ogg_extension, strip_utf8_bom};
+use crate::sound_deduplication::sound_file_extension;
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The suffix of the file names of multi-part sound manifests. The name of the concatenated
+/// sound is the name of its manifest without this suffix.
+const MANIFEST_FILE_NAME_SUFFIX: &str = ".parts.json";
+
+/// A sound file generated by [`SoundConcatenatingVfs`].
+struct GeneratedFile {
+	data: Bytes,
+	modification_time: Option<SystemTime>
+}
+
+/// A manifest that lists the part files of a sound, in playback order. The paths of the
+/// parts are relative to the folder that contains the manifest.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SoundPartsManifest {
+	parts: Vec<String>
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently replacing the part files
+/// of sounds listed by `.parts.json` manifests with a single WAV file that contains their
+/// audio data one after another. For example, a `music/theme.parts.json` manifest in the
+/// `sounds` folder of a namespace generates a `music/theme.wav` sound, which is later
+/// transcoded to `music/theme.ogg` like any other sound. Because the samples are concatenated
+/// before transcoding, there are no seams between parts, and the container overhead of every
+/// part but one is saved.
+///
+/// The generated files are computed eagerly by [`Self::concatenate_sounds`] and kept in
+/// memory, because the [`VirtualFileSystem::open`] method is synchronous. Other files are
+/// read from the wrapped file system as-is.
+pub struct SoundConcatenatingVfs<V: VirtualFileSystem> {
+	inner: V,
+	generated_files: AHashMap<PathBuf, GeneratedFile>,
+	/// The paths of the manifests and part files that were concatenated, which are removed
+	/// from file iterators.
+	concatenated_file_paths: Arc<AHashSet<PathBuf>>,
+	/// The relative and file paths of the generated sounds, which are added to file iterators.
+	generated_sound_paths: Arc<Vec<(String, PathBuf)>>
+}
+
+impl<V: VirtualFileSystem> SoundConcatenatingVfs<V> {
+	/// Wraps the specified virtual file system. No sounds will be concatenated until
+	/// [`Self::concatenate_sounds`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			generated_files: AHashMap::new(),
+			concatenated_file_paths: Arc::new(AHashSet::new()),
+			generated_sound_paths: Arc::new(Vec::new())
+		}
+	}
+
+	/// Scans the pack at the specified root path for multi-part sound manifests, concatenating
+	/// the parts they list. Manifests whose concatenated sound would overwrite a sound in the
+	/// pack, and manifests or parts that can't be read, parsed or decoded are left as-is, so
+	/// that the usual pack file processing reports any relevant error.
+	pub async fn concatenate_sounds(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) {
+		let pack_files = self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+			.map(|entry| {
+				(
+					entry.relative_path.into_inner().into_owned(),
+					entry.file_path
+				)
+			})
+			.collect::<AHashMap<_, _>>();
+
+		// Sort the manifests to generate the same sounds consistently, no matter the order the
+		// file system lists them in
+		let mut manifests = pack_files
+			.iter()
+			.filter(|(relative_path, _)| {
+				relative_path.starts_with("assets/")
+					&& relative_path.contains("/sounds/")
+					&& relative_path.ends_with(MANIFEST_FILE_NAME_SUFFIX)
+			})
+			.collect::<Vec<_>>();
+		manifests.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+		let mut concatenated_file_paths = AHashSet::new();
+		let mut generated_sound_paths = Vec::new();
+		for (manifest_relative_path, manifest_file_path) in manifests {
+			let Ok((manifest, manifest_modification_time)) =
+				read_file(&self.inner, manifest_file_path).await
+			else {
+				continue;
+			};
+			let Ok(SoundPartsManifest { parts }) =
+				serde_json::from_reader(StripComments::new(strip_utf8_bom(&manifest)))
+			else {
+				continue;
+			};
+			if parts.is_empty() {
+				continue;
+			}
+
+			let sound_name = &manifest_relative_path
+				[..manifest_relative_path.len() - MANIFEST_FILE_NAME_SUFFIX.len()];
+			let folder = sound_name.rsplit_once('/').map_or("", |(folder, _)| folder);
+			let part_relative_paths = parts
+				.iter()
+				.map(|part| format!("{folder}/{part}"))
+				.collect::<Vec<_>>();
+
+			// Never overwrite sounds that are in the pack or were generated before, except
+			// for the parts themselves
+			let sound_relative_path = format!("{sound_name}.wav");
+			let sound_file_path = root_path.join(&sound_relative_path);
+			if self.generated_files.contains_key(&sound_file_path)
+				|| pack_files.keys().any(|relative_path| {
+					sound_file_extension(relative_path).is_some()
+						&& relative_path
+							.rsplit_once('.')
+							.is_some_and(|(stem, _)| stem == sound_name)
+						&& !part_relative_paths.contains(relative_path)
+				}) {
+				continue;
+			}
+
+			// The concatenated sound depends on every part, so consider it modified when any of
+			// them is, so that stale versions of it are not reused from previous runs
+			let mut sound_modification_time = manifest_modification_time;
+			let mut part_files = Vec::with_capacity(part_relative_paths.len());
+			for part_relative_path in &part_relative_paths {
+				let Some(extension) = sound_file_extension(part_relative_path) else {
+					break;
+				};
+				let Some(part_file_path) = pack_files.get(part_relative_path) else {
+					break;
+				};
+				let Ok((part, part_modification_time)) = read_file(&self.inner, part_file_path).await
+				else {
+					break;
+				};
+
+				sound_modification_time = sound_modification_time
+					.zip(part_modification_time)
+					.map(|(sound_time, part_time)| sound_time.max(part_time));
+				part_files.push((
+					part_file_path,
+					Bytes::from(part),
+					is_ogg_extension(&extension)
+				));
+			}
+			if part_files.len() != part_relative_paths.len() {
+				continue;
+			}
+
+			let Some(sound) = concatenate_audio(
+				part_files
+					.iter()
+					.map(|(_, part, is_ogg)| (part.clone(), *is_ogg))
+			) else {
+				continue;
+			};
+
+			concatenated_file_paths.insert(manifest_file_path.clone());
+			concatenated_file_paths.extend(
+				part_files
+					.into_iter()
+					.map(|(part_file_path, _, _)| part_file_path.clone())
+			);
+			self.generated_files.insert(
+				sound_file_path.clone(),
+				GeneratedFile {
+					data: sound.into(),
+					modification_time: sound_modification_time
+				}
+			);
+			generated_sound_paths.push((sound_relative_path, sound_file_path));
+		}
+
+		self.concatenated_file_paths = Arc::new(concatenated_file_paths);
+		self.generated_sound_paths = Arc::new(generated_sound_paths);
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for SoundConcatenatingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = impl Iterator<Item = Result<VfsPackFileIterEntry, io::Error>>;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		let concatenated_file_paths = Arc::clone(&self.concatenated_file_paths);
+		let generated_sound_entries = self
+			.generated_sound_paths
+			.iter()
+			.filter(|(_, file_path)| file_path.starts_with(root_path))
+			.map(|(relative_path, file_path)| {
+				Ok(VfsPackFileIterEntry {
+					relative_path: RelativePath::from_inner(relative_path.clone()),
+					file_path: file_path.clone()
+				})
+			})
+			.collect::<Vec<_>>();
+
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.filter(move |entry| {
+				!entry
+					.as_ref()
+					.is_ok_and(|entry| concatenated_file_paths.contains(&entry.file_path))
+			})
+			.chain(generated_sound_entries)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.generated_files
+				.get(path.as_ref())
+				.map(|generated_file| (&generated_file.data, generated_file.modification_time))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
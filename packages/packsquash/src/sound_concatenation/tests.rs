@@ -0,0 +1,95 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+use tokio::io::AsyncReadExt;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+static FLAC_AUDIO_DATA: &[u8] = include_bytes!("../pack_file/audio_file/dtmf_tone.flac");
+static FLAC_AUDIO_DATA_8KHZ: &[u8] = include_bytes!("../pack_file/audio_file/dtmf_tone_8khz.flac");
+
+#[test]
+fn sound_parts_are_concatenated() {
+	let root_dir = Builder::new()
+		.prefix("ps-sound-concatenation-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"assets/mypack/sounds/music/theme.parts.json",
+			br#"{ "parts": ["theme_1.flac", "parts/theme_2.flac"] }"#.as_slice()
+		),
+		("assets/mypack/sounds/music/theme_1.flac", FLAC_AUDIO_DATA),
+		(
+			"assets/mypack/sounds/music/parts/theme_2.flac",
+			FLAC_AUDIO_DATA
+		),
+		(
+			"assets/mypack/sounds/music/mismatched.parts.json",
+			br#"{ "parts": ["theme_1.flac", "low_tone.flac"] }"#.as_slice()
+		),
+		(
+			"assets/mypack/sounds/music/low_tone.flac",
+			FLAC_AUDIO_DATA_8KHZ
+		),
+		(
+			"assets/mypack/sounds/music/existing.parts.json",
+			br#"{ "parts": ["low_tone.flac"] }"#.as_slice()
+		),
+		("assets/mypack/sounds/music/existing.ogg", b"".as_slice())
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let mut vfs = SoundConcatenatingVfs::new(OsFilesystem);
+	tokio_test::block_on(
+		vfs.concatenate_sounds(root_dir.path(), IteratorTraversalOptions::default())
+	);
+
+	let mut relative_paths = vfs
+		.file_iterator(root_dir.path(), IteratorTraversalOptions::default())
+		.map(|entry| {
+			entry
+				.expect("I/O operations are assumed not to fail during tests")
+				.relative_path
+				.as_str()
+				.to_string()
+		})
+		.collect::<Vec<_>>();
+	relative_paths.sort_unstable();
+
+	// Manifests that can't be concatenated, and the parts they reference, should be left as-is
+	assert_eq!(
+		relative_paths,
+		[
+			"assets/mypack/sounds/music/existing.ogg",
+			"assets/mypack/sounds/music/existing.parts.json",
+			"assets/mypack/sounds/music/low_tone.flac",
+			"assets/mypack/sounds/music/mismatched.parts.json",
+			"assets/mypack/sounds/music/theme.wav"
+		]
+	);
+
+	let mut concatenated_sound = vec![];
+	tokio_test::block_on(
+		vfs.open(root_dir.path().join("assets/mypack/sounds/music/theme.wav"))
+			.expect("The concatenated sound should be generated")
+			.file_read
+			.read_to_end(&mut concatenated_sound)
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+	assert_eq!(
+		concatenated_sound,
+		concatenate_audio([
+			(Bytes::from_static(FLAC_AUDIO_DATA), false),
+			(Bytes::from_static(FLAC_AUDIO_DATA), false)
+		])
+		.expect("The parts should be concatenated")
+	);
+}
@@ -0,0 +1,237 @@
+//! Contains a virtual file system adapter that finds sounds with the same audio data under
+//! different paths, which are common in packs merged from several sources, and optionally
+//! makes their duplicate copies read as the same file.
+
+use std::{
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf},
+	time::SystemTime
+};
+
+use ahash::AHashMap;
+use bytes::Bytes;
+use tokio_util::either::Either;
+
+use crate::RelativePath;
+use crate::pack_file::{decoded_audio_fingerprint, is_ogg_extension};
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The extensions of the audio files that are looked for duplicates, which are the audio
+/// formats PackSquash can decode.
+const SOUND_FILE_EXTENSIONS: [&str; 6] = ["ogg", "oga", "mp3", "flac", "wav", "m4a"];
+
+/// A pack file whose contents were replaced by [`SoundDeduplicatingVfs`].
+struct ReplacedFile {
+	data: Bytes,
+	modification_time: Option<SystemTime>
+}
+
+/// A sound file of the pack that may have the same audio data as other sound files.
+struct SoundFile {
+	relative_path: RelativePath<'static>,
+	file_path: PathBuf,
+	extension: String
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently replacing the contents of
+/// sound files whose audio data is the same as that of another sound file of the same format
+/// with the contents of that file. Because both files are then processed identically, the
+/// generated ZIP file may store their data just once.
+///
+/// The replaced files are computed eagerly by [`Self::find_duplicate_sounds`] and kept in
+/// memory, because the [`VirtualFileSystem::open`] method is synchronous. Every duplicate of
+/// a sound shares the same buffer. Other files are read from the wrapped file system as-is.
+pub struct SoundDeduplicatingVfs<V: VirtualFileSystem> {
+	inner: V,
+	replaced_files: AHashMap<PathBuf, ReplacedFile>
+}
+
+impl<V: VirtualFileSystem> SoundDeduplicatingVfs<V> {
+	/// Wraps the specified virtual file system. No files will be replaced until
+	/// [`Self::find_duplicate_sounds`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			replaced_files: AHashMap::new()
+		}
+	}
+
+	/// Scans the pack at the specified root path for sounds that decode to the same audio
+	/// data, returning the paths of each group of duplicate sounds, sorted. If `deduplicate`
+	/// is `true`, the contents of every duplicate sound are also replaced with the contents
+	/// of the first sound of its group that has the same file extension, so that sounds in
+	/// different formats are never mixed up, and only the groups that contain sounds in
+	/// different formats are returned. Sounds that can't be read or decoded are left as-is,
+	/// so that the usual pack file processing reports any relevant error.
+	pub async fn find_duplicate_sounds(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions,
+		deduplicate: bool
+	) -> Vec<Vec<RelativePath<'static>>> {
+		let mut sound_files = self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+			.filter_map(
+				|VfsPackFileIterEntry {
+				     relative_path,
+				     file_path
+				 }| {
+					let extension = sound_file_extension(relative_path.as_str())?;
+
+					Some(SoundFile {
+						relative_path,
+						file_path,
+						extension
+					})
+				}
+			)
+			.collect::<Vec<_>>();
+
+		// Sort the sounds to choose the same sound of each group consistently, no matter the
+		// order the file system lists them in
+		sound_files.sort_unstable_by(|a, b| a.relative_path.as_str().cmp(b.relative_path.as_str()));
+
+		let mut sounds_by_fingerprint = AHashMap::<_, Vec<_>>::new();
+		for sound_file in sound_files {
+			let Ok((data, _)) = read_file(&self.inner, &sound_file.file_path).await else {
+				continue;
+			};
+
+			let is_ogg = is_ogg_extension(&sound_file.extension);
+			if let Some(fingerprint) = decoded_audio_fingerprint(data.into(), is_ogg) {
+				sounds_by_fingerprint
+					.entry(fingerprint)
+					.or_default()
+					.push(sound_file);
+			}
+		}
+
+		let mut duplicate_sound_groups = sounds_by_fingerprint
+			.into_values()
+			.filter(|sounds| sounds.len() > 1)
+			.collect::<Vec<_>>();
+		duplicate_sound_groups
+			.sort_unstable_by(|a, b| a[0].relative_path.as_str().cmp(b[0].relative_path.as_str()));
+
+		if deduplicate {
+			for sounds in &duplicate_sound_groups {
+				self.replace_duplicate_sounds(sounds).await;
+			}
+
+			duplicate_sound_groups.retain(|sounds| {
+				sounds
+					.iter()
+					.any(|sound| sound.extension != sounds[0].extension)
+			});
+		}
+
+		duplicate_sound_groups
+			.into_iter()
+			.map(|sounds| {
+				sounds
+					.into_iter()
+					.map(|sound| sound.relative_path)
+					.collect()
+			})
+			.collect()
+	}
+
+	/// Replaces the contents of every sound of the specified group of duplicates with the
+	/// contents of the first sound of the group with the same file extension.
+	async fn replace_duplicate_sounds(&mut self, sounds: &[SoundFile]) {
+		let mut original_sounds = AHashMap::<&str, Option<(Bytes, Option<SystemTime>)>>::new();
+
+		for sound in sounds {
+			let extension = sound.extension.as_str();
+
+			if !original_sounds.contains_key(extension) {
+				// This is the first sound with this extension, so keep it as-is
+				let original_sound = read_file(&self.inner, &sound.file_path)
+					.await
+					.ok()
+					.map(|(data, modification_time)| (data.into(), modification_time));
+				original_sounds.insert(extension, original_sound);
+				continue;
+			}
+
+			let Some((original_data, original_modification_time)) = &original_sounds[extension]
+			else {
+				continue;
+			};
+
+			let Ok(file) = self.inner.open(&sound.file_path) else {
+				continue;
+			};
+
+			// The replaced file depends on the original one now, so consider it modified when
+			// any of them is, so that stale versions of it are not reused from previous runs
+			let modification_time = file
+				.metadata
+				.modification_time
+				.zip(*original_modification_time)
+				.map(|(modification_time, original_modification_time)| {
+					modification_time.max(original_modification_time)
+				});
+
+			self.replaced_files.insert(
+				sound.file_path.clone(),
+				ReplacedFile {
+					data: original_data.clone(),
+					modification_time
+				}
+			);
+		}
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for SoundDeduplicatingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = V::FileIter;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.replaced_files
+				.get(path.as_ref())
+				.map(|replaced_file| (&replaced_file.data, replaced_file.modification_time))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
+
+/// Returns the lowercase extension of the file at the specified relative path if it is a
+/// sound file, in the `sounds` folder of some namespace, or `None` otherwise.
+pub fn sound_file_extension(relative_path: &str) -> Option<String> {
+	if !relative_path.starts_with("assets/") || !relative_path.contains("/sounds/") {
+		return None;
+	}
+
+	let (_, extension) = relative_path.rsplit_once('.')?;
+	let extension = extension.to_ascii_lowercase();
+
+	SOUND_FILE_EXTENSIONS
+		.contains(&extension.as_str())
+		.then_some(extension)
+}
@@ -0,0 +1,103 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+static FLAC_AUDIO_DATA: &[u8] = include_bytes!("../pack_file/audio_file/dtmf_tone.flac");
+static FLAC_AUDIO_DATA_8KHZ: &[u8] = include_bytes!("../pack_file/audio_file/dtmf_tone_8khz.flac");
+static OGG_AUDIO_DATA: &[u8] = include_bytes!("../pack_file/audio_file/dtmf_tone.ogg");
+
+#[test]
+fn duplicate_sounds_are_found_and_deduplicated() {
+	let root_dir = Builder::new()
+		.prefix("ps-sound-deduplication-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		("assets/first/sounds/tone.flac", FLAC_AUDIO_DATA),
+		("assets/second/sounds/tone.flac", FLAC_AUDIO_DATA),
+		("assets/second/sounds/low_tone.flac", FLAC_AUDIO_DATA_8KHZ),
+		("assets/first/sounds/lossy_tone.ogg", OGG_AUDIO_DATA),
+		("assets/second/sounds/lossy_tone.oga", OGG_AUDIO_DATA),
+		("assets/first/textures/tone.flac", FLAC_AUDIO_DATA)
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let find_duplicate_sounds = |deduplicate| {
+		let mut vfs = SoundDeduplicatingVfs::new(OsFilesystem);
+		let duplicate_sounds = tokio_test::block_on(vfs.find_duplicate_sounds(
+			root_dir.path(),
+			IteratorTraversalOptions::default(),
+			deduplicate
+		))
+		.iter()
+		.map(|sounds| {
+			sounds
+				.iter()
+				.map(|sound| sound.as_str().to_string())
+				.collect::<Vec<_>>()
+		})
+		.collect::<Vec<_>>();
+
+		(duplicate_sounds, vfs.replaced_files)
+	};
+
+	let (duplicate_sounds, replaced_files) = find_duplicate_sounds(false);
+	assert_eq!(
+		duplicate_sounds,
+		[
+			[
+				"assets/first/sounds/lossy_tone.ogg",
+				"assets/second/sounds/lossy_tone.oga"
+			],
+			[
+				"assets/first/sounds/tone.flac",
+				"assets/second/sounds/tone.flac"
+			]
+		]
+	);
+	assert!(
+		replaced_files.is_empty(),
+		"No sounds should be replaced when not deduplicating"
+	);
+
+	// Only the duplicate sound with the same extension as the first one should be replaced,
+	// and only the sounds in different formats should be reported
+	let (duplicate_sounds, replaced_files) = find_duplicate_sounds(true);
+	assert_eq!(
+		duplicate_sounds,
+		[[
+			"assets/first/sounds/lossy_tone.ogg",
+			"assets/second/sounds/lossy_tone.oga"
+		]]
+	);
+	assert_eq!(
+		replaced_files.into_keys().collect::<Vec<_>>(),
+		[root_dir.path().join("assets/second/sounds/tone.flac")]
+	);
+}
+
+#[test]
+fn sound_file_extensions_are_recognized() {
+	assert_eq!(
+		sound_file_extension("assets/minecraft/sounds/ambient/cave.OGG").as_deref(),
+		Some("ogg")
+	);
+	assert_eq!(
+		sound_file_extension("assets/minecraft/sounds/music.mp3").as_deref(),
+		Some("mp3")
+	);
+	assert_eq!(sound_file_extension("assets/minecraft/sounds.json"), None);
+	assert_eq!(
+		sound_file_extension("assets/minecraft/textures/sounds/icon.png"),
+		None
+	);
+}
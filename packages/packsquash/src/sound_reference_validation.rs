@@ -0,0 +1,152 @@
+//! Contains routines to cross-validate the sounds referenced by the `sounds.json` files of a
+//! pack against the sound files it contains.
+
+use std::path::Path;
+
+use ahash::{AHashMap, AHashSet};
+use json_comments::StripComments;
+use serde_json::Value;
+
+use crate::RelativePath;
+use crate::pack_file::strip_utf8_bom;
+use crate::sound_deduplication::sound_file_extension;
+use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem, read_file};
+
+#[cfg(test)]
+mod tests;
+
+/// The namespace of vanilla sounds, which is also the namespace of sound names that do not
+/// specify any.
+const VANILLA_NAMESPACE: &str = "minecraft";
+
+/// The result of cross-validating the `sounds.json` files of a pack against its sound files.
+#[derive(Debug, Default)]
+pub struct SoundReferences {
+	/// The sounds referenced by a `sounds.json` file that are not in the pack, with the path of
+	/// the `sounds.json` file that references them. Sounds in the vanilla namespace are not
+	/// checked, because they may be provided by the game.
+	pub missing_sounds: Vec<(RelativePath<'static>, String)>,
+	/// The sound files of the pack that no `sounds.json` file references. Only the sounds of
+	/// namespaces other than the vanilla one that have a `sounds.json` file in the pack are
+	/// considered, because vanilla sounds and sounds of namespaces without a `sounds.json` file
+	/// are usually referenced by the game or mods instead.
+	pub unreferenced_sounds: AHashSet<RelativePath<'static>>
+}
+
+/// Parses the `sounds.json` files of the pack at the specified root path, finding the sounds
+/// they reference that are not in the pack, and the sound files of the pack that they do not
+/// reference. `sounds.json` files that can't be read or parsed are ignored, so that the usual
+/// pack file processing reports any relevant error. Missing sounds are sorted by path.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Sounds.json>
+pub async fn validate_sound_references<V: VirtualFileSystem>(
+	vfs: &V,
+	root_path: &Path,
+	iterator_traversal_options: IteratorTraversalOptions
+) -> SoundReferences {
+	// Index the sound files of the pack by their sound name, which is their path relative to
+	// the sounds folder of their namespace, without extension
+	let mut sound_files = AHashMap::<(String, String), Vec<RelativePath<'static>>>::new();
+	let mut sounds_json_files = vec![];
+	for VfsPackFileIterEntry {
+		relative_path,
+		file_path
+	} in vfs
+		.file_iterator(root_path, iterator_traversal_options)
+		.flatten()
+	{
+		if let Some(namespace) = relative_path
+			.as_str()
+			.strip_prefix("assets/")
+			.and_then(|path| path.strip_suffix("/sounds.json"))
+			.filter(|namespace| !namespace.contains('/'))
+		{
+			sounds_json_files.push((namespace.to_string(), relative_path, file_path));
+		} else if sound_file_extension(relative_path.as_str()).is_some()
+			&& let Some(sound_name) = sound_file_name(relative_path.as_str())
+		{
+			sound_files
+				.entry(sound_name)
+				.or_default()
+				.push(relative_path);
+		}
+	}
+	sounds_json_files.sort_unstable_by(|(_, a, _), (_, b, _)| a.as_str().cmp(b.as_str()));
+
+	let mut sound_references = SoundReferences::default();
+	let mut referenced_sounds = AHashSet::new();
+	for (_, relative_path, file_path) in &sounds_json_files {
+		let Some(sound_names) = read_sound_names(vfs, file_path).await else {
+			continue;
+		};
+
+		for sound_name in sound_names {
+			let (namespace, path) = sound_name
+				.split_once(':')
+				.unwrap_or((VANILLA_NAMESPACE, sound_name.as_str()));
+			let sound_name_key = (namespace.to_string(), path.to_string());
+
+			if namespace != VANILLA_NAMESPACE && !sound_files.contains_key(&sound_name_key) {
+				sound_references
+					.missing_sounds
+					.push((relative_path.clone(), sound_name.clone()));
+			}
+
+			referenced_sounds.insert(sound_name_key);
+		}
+	}
+
+	let namespaces_with_sounds_json = sounds_json_files
+		.iter()
+		.map(|(namespace, _, _)| namespace.as_str())
+		.collect::<AHashSet<_>>();
+	sound_references.unreferenced_sounds = sound_files
+		.into_iter()
+		.filter(|((namespace, _), _)| {
+			namespace != VANILLA_NAMESPACE && namespaces_with_sounds_json.contains(namespace.as_str())
+		})
+		.filter(|(sound_name, _)| !referenced_sounds.contains(sound_name))
+		.flat_map(|(_, relative_paths)| relative_paths)
+		.collect();
+
+	sound_references
+}
+
+/// Returns the namespace and sound name of the sound file at the specified relative path,
+/// or `None` if it is not in the sounds folder of a namespace.
+fn sound_file_name(relative_path: &str) -> Option<(String, String)> {
+	let (namespace, path) = relative_path
+		.strip_prefix("assets/")?
+		.split_once("/sounds/")?;
+	let (path, _) = path.rsplit_once('.')?;
+
+	(!namespace.contains('/')).then(|| (namespace.to_string(), path.to_string()))
+}
+
+/// Reads the names of the sound files referenced by the `sounds.json` file at the specified
+/// path of a virtual file system, returning `None` if some I/O or parsing error occurs.
+/// References to other sound events are not returned.
+async fn read_sound_names<V: VirtualFileSystem>(vfs: &V, path: &Path) -> Option<Vec<String>> {
+	let (data, _) = read_file(vfs, path).await.ok()?;
+
+	let sound_events: Value =
+		serde_json::from_reader(StripComments::new(strip_utf8_bom(&data))).ok()?;
+
+	Some(
+		sound_events
+			.as_object()?
+			.values()
+			.filter_map(|sound_event| sound_event.get("sounds")?.as_array())
+			.flatten()
+			.filter_map(|sound| match sound {
+				Value::String(name) => Some(name.clone()),
+				Value::Object(sound) => match sound.get("type").and_then(Value::as_str) {
+					None | Some("file") => Some(sound.get("name")?.as_str()?.to_string()),
+					Some(_) => None
+				},
+				_ => None
+			})
+			.collect()
+	)
+}
@@ -0,0 +1,71 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[test]
+fn sound_references_are_validated() {
+	let root_dir = Builder::new()
+		.prefix("ps-sound-reference-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"assets/mypack/sounds.json",
+			r#"{
+				// Comments are allowed, like in Minecraft
+				"music.theme": {
+					"sounds": [
+						"mypack:music/theme",
+						{ "name": "mypack:music/theme_alt", "stream": true },
+						{ "name": "minecraft:music.game", "type": "event" }
+					]
+				},
+				"block.bell": { "sounds": ["mypack:block/bell", "mypack:block/missing"] }
+			}"#
+		),
+		(
+			"assets/minecraft/sounds.json",
+			r#"{ "ambient.cave": { "sounds": ["ambient/cave/cave1", "mypack:ambient/drip"] } }"#
+		),
+		("assets/mypack/sounds/music/theme.ogg", ""),
+		("assets/mypack/sounds/music/theme_alt.flac", ""),
+		("assets/mypack/sounds/block/bell.ogg", ""),
+		("assets/mypack/sounds/block/unused.ogg", ""),
+		("assets/mypack/sounds/ambient/drip.wav", ""),
+		("assets/minecraft/sounds/ambient/cave/cave2.ogg", ""),
+		("assets/othermod/sounds/unused.ogg", "")
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let sound_references = tokio_test::block_on(validate_sound_references(
+		&OsFilesystem,
+		root_dir.path(),
+		IteratorTraversalOptions::default()
+	));
+
+	assert_eq!(
+		sound_references
+			.missing_sounds
+			.iter()
+			.map(|(relative_path, sound_name)| (relative_path.as_str(), sound_name.as_str()))
+			.collect::<Vec<_>>(),
+		[("assets/mypack/sounds.json", "mypack:block/missing")]
+	);
+	assert_eq!(
+		sound_references
+			.unreferenced_sounds
+			.iter()
+			.map(RelativePath::as_str)
+			.collect::<Vec<_>>(),
+		["assets/mypack/sounds/block/unused.ogg"]
+	);
+}
@@ -0,0 +1,98 @@
+//! Contains routines to compute which entries of the output ZIP file are added to each split
+//! output ZIP file, which contain a subset of the pack for modular distribution.
+
+use std::path::{Path, PathBuf};
+
+use ahash::AHashSet;
+use globset::GlobSet;
+
+#[cfg(test)]
+mod tests;
+
+/// The files that a split output ZIP file contains, in addition to those outside of namespace
+/// folders, which every split output ZIP file contains.
+enum SplitOutputContents<'globs> {
+	/// The files whose entry names match these glob patterns.
+	GlobPatterns(&'globs GlobSet),
+	/// The files within the `assets` and `data` folders of this namespace.
+	Namespace(String)
+}
+
+/// Returns the paths of the split output ZIP files to write, paired with a filter that accepts
+/// the names of the output ZIP file entries they contain. Split output ZIP files for glob
+/// pattern groups come first, followed by those for the namespaces of the specified entry
+/// names, if requested, which are placed next to the output file and named after it.
+pub fn split_outputs<'entry>(
+	output_file_path: &Path,
+	split_output_group_globs: &[(PathBuf, GlobSet)],
+	split_output_by_namespace: bool,
+	entry_names: impl Iterator<Item = &'entry str>
+) -> Vec<(PathBuf, impl Fn(&str) -> bool)> {
+	let mut split_outputs = split_output_group_globs
+		.iter()
+		.map(|(split_output_path, globs)| {
+			(
+				split_output_path.clone(),
+				SplitOutputContents::GlobPatterns(globs)
+			)
+		})
+		.collect::<Vec<_>>();
+
+	if split_output_by_namespace {
+		let mut namespaces = entry_names
+			.filter_map(entry_namespace)
+			.collect::<AHashSet<_>>()
+			.into_iter()
+			.collect::<Vec<_>>();
+		namespaces.sort_unstable();
+
+		let output_file_stem = output_file_path
+			.file_stem()
+			.unwrap_or_default()
+			.to_string_lossy();
+		let output_file_extension = output_file_path
+			.extension()
+			.map_or_else(String::new, |extension| {
+				format!(".{}", extension.to_string_lossy())
+			});
+
+		split_outputs.extend(namespaces.into_iter().map(|namespace| {
+			(
+				output_file_path.with_file_name(format!(
+					"{output_file_stem}-{namespace}{output_file_extension}"
+				)),
+				SplitOutputContents::Namespace(namespace.into())
+			)
+		}));
+	}
+
+	split_outputs
+		.into_iter()
+		.map(|(split_output_path, contents)| {
+			(split_output_path, move |entry_name: &str| {
+				match (entry_namespace(entry_name), &contents) {
+					(None, _) => true,
+					(Some(_), SplitOutputContents::GlobPatterns(globs)) => globs.is_match(entry_name),
+					(Some(entry_namespace), SplitOutputContents::Namespace(namespace)) => {
+						entry_namespace == namespace
+					}
+				}
+			})
+		})
+		.collect()
+}
+
+/// Returns the namespace that the ZIP entry with the specified name belongs to, if it is a
+/// file within the `assets` or `data` folder of some namespace.
+fn entry_namespace(entry_name: &str) -> Option<&str> {
+	let mut components = entry_name.split('/');
+
+	if matches!(components.next(), Some("assets" | "data"))
+		&& let Some(namespace) = components.next()
+		&& components.next().is_some()
+	{
+		Some(namespace)
+	} else {
+		None
+	}
+}
@@ -0,0 +1,73 @@
+use globset::GlobSetBuilder;
+use pretty_assertions::assert_eq;
+
+use crate::config::compile_pack_file_glob_pattern;
+
+use super::*;
+
+static ENTRY_NAMES: [&str; 6] = [
+	"pack.mcmeta",
+	"assets/lobby/textures/item/compass.png",
+	"assets/minecraft/models/item/compass.json",
+	"data/lobby/function/join.mcfunction",
+	"data/bedwars/function/start.mcfunction",
+	"assets/bedwars/lang/en_us.json"
+];
+
+/// Returns the entry names that the specified split output filter accepts.
+fn accepted_entry_names(entry_filter: impl Fn(&str) -> bool) -> Vec<&'static str> {
+	ENTRY_NAMES
+		.into_iter()
+		.filter(|entry_name| entry_filter(entry_name))
+		.collect()
+}
+
+#[test]
+fn split_outputs_are_computed() {
+	let mut globset_builder = GlobSetBuilder::new();
+	globset_builder.add(compile_pack_file_glob_pattern("assets/minecraft/**").unwrap());
+	let split_output_group_globs = [(
+		PathBuf::from("vanilla.zip"),
+		globset_builder.build().unwrap()
+	)];
+
+	let split_outputs = split_outputs(
+		Path::new("out/pack.zip"),
+		&split_output_group_globs,
+		true,
+		ENTRY_NAMES.into_iter()
+	)
+	.into_iter()
+	.map(|(split_output_path, entry_filter)| (split_output_path, accepted_entry_names(entry_filter)))
+	.collect::<Vec<_>>();
+
+	assert_eq!(
+		split_outputs,
+		[
+			(
+				PathBuf::from("vanilla.zip"),
+				vec!["pack.mcmeta", "assets/minecraft/models/item/compass.json"]
+			),
+			(
+				PathBuf::from("out/pack-bedwars.zip"),
+				vec![
+					"pack.mcmeta",
+					"data/bedwars/function/start.mcfunction",
+					"assets/bedwars/lang/en_us.json"
+				]
+			),
+			(
+				PathBuf::from("out/pack-lobby.zip"),
+				vec![
+					"pack.mcmeta",
+					"assets/lobby/textures/item/compass.png",
+					"data/lobby/function/join.mcfunction"
+				]
+			),
+			(
+				PathBuf::from("out/pack-minecraft.zip"),
+				vec!["pack.mcmeta", "assets/minecraft/models/item/compass.json"]
+			)
+		]
+	);
+}
@@ -7,8 +7,8 @@ use std::{
 	num::{NonZeroU64, TryFromIntError},
 	path::Path,
 	string::FromUtf8Error,
-	sync::LazyLock,
-	time::SystemTime
+	sync::{Arc, LazyLock},
+	time::{Duration, Instant, SystemTime}
 };
 
 use aes::Aes256;
@@ -25,22 +25,30 @@ use tokio_util::io::ReaderStream;
 use zopfli::Format;
 
 use self::{
+	entry_name_transformation::{InvalidEntryNameError, transform_entry_name},
 	obfuscation_engine::ObfuscationEngine,
+	statistics::ZipEntryStatistics,
 	system_time_sanitizer::{SystemTimeSanitizationError, SystemTimeSanitizer},
 	zip_file_record::{
 		CentralDirectoryHeader, CompressionMethod, EndOfCentralDirectory, LocalFileHeader
 	}
 };
 use crate::{
-	RelativePath, buffered_async_spooled_temp_file::BufferedAsyncSpooledTempFile,
-	config::PercentageInteger, zopfli_iterations_time_model::ZopfliIterationsTimeModel
+	RelativePath,
+	buffered_async_spooled_temp_file::{BufferedAsyncSpooledTempFile, TemporaryStorageQuota},
+	config::PercentageInteger,
+	zopfli_iterations_time_model::ZopfliIterationsTimeModel
 };
 
+pub use self::entry_name_transformation::{EntryNameRegex, ZipEntryNameTransformation};
 pub use self::obfuscation_engine::FileListingCircumstances;
+pub use self::statistics::{SquashZipStatistics, ZipEntryCompressionMethod};
 pub use self::zip_archive_comment_string::ZipArchiveCommentString;
 
+mod entry_name_transformation;
 mod obfuscation_engine;
 pub mod relative_path;
+mod statistics;
 mod system_id;
 pub mod system_time_sanitizer;
 mod zip_archive_comment_string;
@@ -110,6 +118,8 @@ pub enum SquashZipError {
 	NoSuchPreviousFile(String),
 	#[error("Tried to add a file with the same path twice: {0}")]
 	FileAlreadyAdded(RelativePath<'static>),
+	#[error("{0}")]
+	InvalidEntryName(#[from] InvalidEntryNameError),
 	#[error("I/O error: {0}")]
 	Io(#[from] io::Error)
 }
@@ -170,8 +180,17 @@ pub struct SquashZipSettings {
 	/// are extremely temporary, being only valid during a call to `add_file`, and each of
 	/// them will have a buffer `spool_buffer_size / 2` bytes big.
 	pub spool_buffer_size: usize,
+	/// The maximum number of bytes that the spooled temporary files rolled over to disk
+	/// may take altogether, if any. Operations that would exceed it fail with an I/O error.
+	pub temporary_storage_limit: Option<u64>,
 	/// The comment that will be attached to the output ZIP file.
-	pub zip_comment: ZipArchiveCommentString
+	pub zip_comment: ZipArchiveCommentString,
+	/// The transformations that will be applied, in order, to the relative path of every
+	/// file added to the ZIP to compute its entry name.
+	pub entry_name_transformations: Vec<ZipEntryNameTransformation>,
+	/// Whether to collect statistics about every entry added to the ZIP file, such as
+	/// their compression time and ratio, which will be returned when finishing it.
+	pub collect_statistics: bool
 }
 
 /// A custom, minimalistic ZIP compressor, which exploits its great control
@@ -182,6 +201,7 @@ pub struct SquashZip<F: AsyncRead + AsyncSeek + Unpin> {
 	zopfli_iterations_time_model: ZopfliIterationsTimeModel,
 	obfuscation_engine: ObfuscationEngine,
 	previous_zip_contents: AHashMap<RelativePath<'static>, PreviousFile>,
+	temporary_storage_quota: Option<Arc<TemporaryStorageQuota>>,
 	state: Mutex<MutableSquashZipState<F>>
 }
 
@@ -191,7 +211,8 @@ struct MutableSquashZipState<F: AsyncRead + AsyncSeek + Unpin> {
 	output_zip: BufferedAsyncSpooledTempFile,
 	previous_zip: Option<F>,
 	processed_local_headers: AHashMap<HashAndSize, Vec<(u64, u32)>>,
-	central_directory_data: AHashMap<RelativePath<'static>, PartialCentralDirectoryHeader>
+	central_directory_data: AHashMap<RelativePath<'static>, PartialCentralDirectoryHeader>,
+	entry_statistics: Option<Vec<ZipEntryStatistics>>
 }
 
 /// The system time sanitizer that SquashZip will use for sanitizing and
@@ -223,7 +244,11 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 		settings: SquashZipSettings
 	) -> Result<Self, (SquashZipError, SquashZipSettings)> {
 		let obfuscation_engine = ObfuscationEngine::from_squash_zip_settings(&settings);
-		let mut output_zip = BufferedAsyncSpooledTempFile::new(settings.spool_buffer_size);
+		let temporary_storage_quota = settings
+			.temporary_storage_limit
+			.map(|limit| Arc::new(TemporaryStorageQuota::new(limit)));
+		let mut output_zip = BufferedAsyncSpooledTempFile::new(settings.spool_buffer_size)
+			.with_temporary_storage_quota(temporary_storage_quota.clone());
 
 		let previous_zip_contents = if let Some(previous_zip) = &mut previous_zip {
 			match read_previous_zip_contents(previous_zip, &obfuscation_engine).await {
@@ -252,11 +277,15 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 			),
 			settings,
 			obfuscation_engine,
+			temporary_storage_quota,
 			state: Mutex::new(MutableSquashZipState {
 				output_zip,
 				previous_zip,
 				processed_local_headers: AHashMap::with_capacity(previous_zip_contents.len()),
-				central_directory_data: AHashMap::with_capacity(previous_zip_contents.len())
+				central_directory_data: AHashMap::with_capacity(previous_zip_contents.len()),
+				entry_statistics: settings
+					.collect_statistics
+					.then(|| Vec::with_capacity(previous_zip_contents.len()))
 			}),
 			previous_zip_contents
 		})
@@ -285,7 +314,10 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 		file_size_hint: usize,
 		listing_circumstances: FileListingCircumstances
 	) -> Result<(), SquashZipError> {
-		let (mut local_file_header, mut compressed_data_scratch_file) = self
+		let start_instant = Instant::now();
+		let path = &transform_entry_name(&self.settings.entry_name_transformations, path)?;
+
+		let (mut local_file_header, mut compressed_data_scratch_file, compression_time) = self
 			.compress_and_generate_local_header(
 				path,
 				processed_data,
@@ -408,16 +440,29 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 			tokio::io::copy(&mut compressed_data_scratch_file, output_zip).await?;
 		}
 
+		if let Some(entry_statistics) = &mut state.entry_statistics {
+			entry_statistics.push(ZipEntryStatistics::new(
+				path.as_str().into(),
+				local_file_header.compression_method,
+				local_file_header.uncompressed_size,
+				local_file_header.compressed_size,
+				compression_time,
+				start_instant.elapsed().saturating_sub(compression_time),
+				already_stored,
+				false
+			));
+		}
+
 		Ok(())
 	}
 
 	/// Returns the time the specified file was added to the ZIP file generated by
 	/// SquashZip in a previous run. `None` may be returned if, for instance, the
-	/// file didn't exist before, or there is no available data about when this file
-	/// was added.
+	/// file didn't exist before, there is no available data about when this file
+	/// was added, or its transformed entry name is invalid.
 	pub fn file_process_time(&self, file_path: &RelativePath<'_>) -> Option<SystemTime> {
 		self.previous_zip_contents
-			.get(file_path)
+			.get(&transform_entry_name(&self.settings.entry_name_transformations, file_path).ok()?)
 			.map(|previous_file| previous_file.squash_time)
 	}
 
@@ -427,6 +472,11 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 		self.previous_zip_contents.len()
 	}
 
+	/// Returns the names of the entries that were added to the ZIP file so far.
+	pub fn entry_names(&mut self) -> impl Iterator<Item = &RelativePath<'static>> {
+		self.state.get_mut().central_directory_data.keys()
+	}
+
 	/// Cheaply adds the specified previous run file to the ZIP file that is being generated
 	/// right now. By default, all previous run files are not added again to the output ZIP
 	/// file.
@@ -453,6 +503,9 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 		//         add new LOC to 1), add CEN entry to 2) and copy previous file data to the
 		//         output file.
 
+		let start_instant = Instant::now();
+		let path = &transform_entry_name(&self.settings.entry_name_transformations, path)?;
+
 		let previous_file = if let Some(previous_file) = self.previous_zip_contents.get(path) {
 			previous_file
 		} else {
@@ -629,6 +682,19 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 			.await?;
 		}
 
+		if let Some(entry_statistics) = &mut state.entry_statistics {
+			entry_statistics.push(ZipEntryStatistics::new(
+				path.as_str().into(),
+				previous_file.compression_method,
+				previous_file.uncompressed_size,
+				previous_file.compressed_size,
+				Duration::ZERO,
+				start_instant.elapsed(),
+				already_stored,
+				true
+			));
+		}
+
 		Ok(())
 	}
 
@@ -637,71 +703,117 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 	///
 	/// This operation ends the lifecycle of this SquashZip instance, consuming it, so no
 	/// further operations can be done on the ZIP file after this method returns.
-	pub async fn finish(self, path: impl AsRef<Path>) -> Result<(), SquashZipError> {
-		let state = self.state.into_inner();
+	///
+	/// If statistics collection was enabled in the settings, a summary of the statistics
+	/// about every entry added to the ZIP file is returned.
+	pub async fn finish(
+		self,
+		path: impl AsRef<Path>
+	) -> Result<Option<SquashZipStatistics>, SquashZipError> {
+		self.finish_with_split_outputs(path, &[] as &[(&Path, fn(&str) -> bool)])
+			.await
+	}
+
+	/// Like [`Self::finish()`], but also writes a split output ZIP file to each specified path,
+	/// which contains the entries whose name is accepted by the paired filter. The entry records
+	/// are copied from the finished ZIP file as-is, so their data is not compressed again, and
+	/// entries that share data in the finished ZIP file also share it in the split ones.
+	pub async fn finish_with_split_outputs(
+		self,
+		path: impl AsRef<Path>,
+		split_outputs: &[(impl AsRef<Path>, impl Fn(&str) -> bool)]
+	) -> Result<Option<SquashZipStatistics>, SquashZipError> {
+		let Self {
+			settings,
+			obfuscation_engine,
+			previous_zip_contents,
+			temporary_storage_quota,
+			state,
+			..
+		} = self;
+		let state = state.into_inner();
 		let central_directory_data = state.central_directory_data;
 		let mut output_zip = state.output_zip;
 
-		let central_directory_entry_count = u64::try_from(central_directory_data.len())?;
-		let central_directory_start_offset = output_zip.stream_position().await?;
-
-		// First, write the central directory file headers
-		for (file_name, header_data) in central_directory_data {
-			let mut central_directory_header = CentralDirectoryHeader {
-				compression_method: header_data.compression_method,
-				squash_time: header_data.squash_time,
-				crc32: header_data.crc32,
-				compressed_size: header_data.compressed_size,
-				uncompressed_size: header_data.uncompressed_size,
-				local_header_disk_number: 0,
-				local_header_offset: header_data.local_header_offset,
-				file_name,
-				spoof_version_made_by: false
-			};
+		write_central_directory(
+			&mut output_zip,
+			central_directory_data
+				.iter()
+				.map(|(file_name, header_data)| {
+					(file_name, header_data, header_data.local_header_offset)
+				})
+				.collect(),
+			&obfuscation_engine,
+			&settings.zip_comment
+		)
+		.await?;
 
-			self.obfuscation_engine.obfuscate_central_directory_header(
-				&mut central_directory_header,
-				header_data.listing_circumstances
-			);
+		// Finally, write the generated ZIP file to its place!
+		// This also implicitly flushes any buffer, so any error during flushing will be returned
+		output_zip.rewind().await?;
 
-			central_directory_header.write(&mut output_zip).await?;
-		}
+		tokio::io::copy(&mut output_zip, &mut File::create(path).await?).await?;
 
-		let central_directory_end_offset = output_zip.stream_position().await?;
-
-		// Now write the end of central directory
-		let mut end_of_central_directory = EndOfCentralDirectory {
-			disk_number: 0,
-			central_directory_start_disk_number: 0,
-			central_directory_entry_count_current_disk: central_directory_entry_count,
-			total_central_directory_entry_count: central_directory_entry_count,
-			central_directory_size: central_directory_end_offset - central_directory_start_offset,
-			central_directory_start_offset,
-			total_number_of_disks: 1,
-			current_file_offset: central_directory_end_offset,
-			zip64_record_size_offset: 0,
-			spoof_version_made_by: false,
-			zero_out_unused_zip64_fields: false,
-			archive_comment: self.settings.zip_comment
-		};
+		for (split_output_path, entry_filter) in split_outputs {
+			let mut split_output_zip = BufferedAsyncSpooledTempFile::new(settings.spool_buffer_size)
+				.with_temporary_storage_quota(temporary_storage_quota.clone());
 
-		self.obfuscation_engine
-			.obfuscate_end_of_central_directory(&mut end_of_central_directory);
+			obfuscation_engine
+				.obfuscating_header(
+					&mut split_output_zip,
+					(previous_zip_contents.len() ^ settings.spool_buffer_size) as u64
+				)
+				.await?;
 
-		end_of_central_directory.write(&mut output_zip).await?;
+			// Copy the local file records of the accepted entries, remembering where they were
+			// copied to, so that deduplicated entries keep pointing to the same record
+			let mut copied_local_header_offsets = AHashMap::new();
+			let mut split_central_directory_entries = vec![];
+			for (file_name, header_data) in &central_directory_data {
+				if !entry_filter(file_name.as_str()) {
+					continue;
+				}
 
-		// Finally, write the generated ZIP file to its place!
-		// This also implicitly flushes any buffer, so any error during flushing will be returned
-		output_zip.rewind().await?;
+				let local_header_offset =
+					match copied_local_header_offsets.entry(header_data.local_header_offset) {
+						Entry::Occupied(entry) => *entry.get(),
+						Entry::Vacant(entry) => *entry.insert(
+							copy_local_file_record(
+								&mut output_zip,
+								header_data.local_header_offset,
+								header_data.compressed_size,
+								&mut split_output_zip
+							)
+							.await?
+						)
+					};
+
+				split_central_directory_entries.push((file_name, header_data, local_header_offset));
+			}
 
-		tokio::io::copy(&mut output_zip, &mut File::create(path).await?).await?;
+			write_central_directory(
+				&mut split_output_zip,
+				split_central_directory_entries,
+				&obfuscation_engine,
+				&settings.zip_comment
+			)
+			.await?;
 
-		Ok(())
+			split_output_zip.rewind().await?;
+
+			tokio::io::copy(
+				&mut split_output_zip,
+				&mut File::create(split_output_path).await?
+			)
+			.await?;
+		}
+
+		Ok(state.entry_statistics.map(SquashZipStatistics::new))
 	}
 
 	/// Compresses a stream of processed data for the given ZIP file path, returning its corresponding
-	/// local file header and a scratch data file that contains its most efficient representation in
-	/// terms of size. The scratch data file stream position is just after the compressed contents, so
+	/// local file header, a scratch data file that contains its most efficient representation in
+	/// terms of size, and the time spent compressing it. The scratch data file stream position is just after the compressed contents, so
 	/// to read the compressed data back client code may need to rewind the file first.
 	async fn compress_and_generate_local_header<'a, T: AsRef<[u8]>>(
 		&self,
@@ -709,7 +821,7 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 		mut processed_data: impl Stream<Item = T> + Unpin,
 		skip_compression: bool,
 		file_size_hint: usize
-	) -> Result<(LocalFileHeader<'a>, BufferedAsyncSpooledTempFile), SquashZipError> {
+	) -> Result<(LocalFileHeader<'a>, BufferedAsyncSpooledTempFile, Duration), SquashZipError> {
 		// Get the Squash Time right now, so it is as close as possible to the time when
 		// we saw whether it was modified or not, which is a good thing. Instantiate the
 		// local file header now, so we validate the path as early as possible
@@ -720,11 +832,13 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 		let mut processed_data_scratch_file = BufferedAsyncSpooledTempFile::with_capacity(
 			file_size_hint,
 			self.settings.spool_buffer_size / 2
-		);
+		)
+		.with_temporary_storage_quota(self.temporary_storage_quota.clone());
 		let mut compressed_data_scratch_file = BufferedAsyncSpooledTempFile::with_capacity(
 			file_size_hint,
 			self.settings.spool_buffer_size / 2
-		);
+		)
+		.with_temporary_storage_quota(self.temporary_storage_quota.clone());
 
 		// Store the processed data in the scratch file we created for that purpose.
 		// Compute its hash and size
@@ -744,6 +858,7 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 		let processed_data_crc = crc32_hasher.finalize();
 
 		let mut compressed_data_size;
+		let mut compression_time = Duration::ZERO;
 		if skip_compression || self.settings.zopfli_iterations == 0 || processed_data_size == 0 {
 			// Perform no compression and treat uncompressed data as if it was compressed.
 			// Because this never saves space, we don't actually get to use compressed_data_scratch_file
@@ -752,6 +867,8 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 			// Rewind scratch file to read it back for compression
 			processed_data_scratch_file.rewind().await?;
 
+			let compression_start_instant = Instant::now();
+
 			zopfli::compress(
 				zopfli::Options {
 					iteration_count: NonZeroU64::new(
@@ -769,6 +886,7 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 				&mut compressed_data_scratch_file
 			)?;
 
+			compression_time = compression_start_instant.elapsed();
 			compressed_data_size = compressed_data_scratch_file.stream_position().await?;
 		}
 
@@ -799,7 +917,11 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 				SYSTEM_TIME_SANITIZER.sanitize(&squash_time, &processed_data_crc.to_le_bytes())?;
 		}
 
-		Ok((local_file_header, compressed_data_scratch_file))
+		Ok((
+			local_file_header,
+			compressed_data_scratch_file,
+			compression_time
+		))
 	}
 }
 
@@ -1061,3 +1183,97 @@ fn add_partial_central_directory_header(
 		Entry::Occupied(entry) => Err(SquashZipError::FileAlreadyAdded(entry.key().clone()))
 	}
 }
+
+/// Writes the central directory and end of central directory records for the specified
+/// entries, and their local header offsets, at the current position of the specified
+/// ZIP file.
+async fn write_central_directory(
+	output_zip: &mut BufferedAsyncSpooledTempFile,
+	entries: Vec<(&RelativePath<'static>, &PartialCentralDirectoryHeader, u64)>,
+	obfuscation_engine: &ObfuscationEngine,
+	zip_comment: &ZipArchiveCommentString
+) -> Result<(), SquashZipError> {
+	let central_directory_entry_count = u64::try_from(entries.len())?;
+	let central_directory_start_offset = output_zip.stream_position().await?;
+
+	// First, write the central directory file headers
+	for (file_name, header_data, local_header_offset) in entries {
+		let mut central_directory_header = CentralDirectoryHeader {
+			compression_method: header_data.compression_method,
+			squash_time: header_data.squash_time,
+			crc32: header_data.crc32,
+			compressed_size: header_data.compressed_size,
+			uncompressed_size: header_data.uncompressed_size,
+			local_header_disk_number: 0,
+			local_header_offset,
+			file_name: file_name.clone(),
+			spoof_version_made_by: false
+		};
+
+		obfuscation_engine.obfuscate_central_directory_header(
+			&mut central_directory_header,
+			header_data.listing_circumstances
+		);
+
+		central_directory_header.write(&mut *output_zip).await?;
+	}
+
+	let central_directory_end_offset = output_zip.stream_position().await?;
+
+	// Now write the end of central directory
+	let mut end_of_central_directory = EndOfCentralDirectory {
+		disk_number: 0,
+		central_directory_start_disk_number: 0,
+		central_directory_entry_count_current_disk: central_directory_entry_count,
+		total_central_directory_entry_count: central_directory_entry_count,
+		central_directory_size: central_directory_end_offset - central_directory_start_offset,
+		central_directory_start_offset,
+		total_number_of_disks: 1,
+		current_file_offset: central_directory_end_offset,
+		zip64_record_size_offset: 0,
+		spoof_version_made_by: false,
+		zero_out_unused_zip64_fields: false,
+		archive_comment: zip_comment.clone()
+	};
+
+	obfuscation_engine.obfuscate_end_of_central_directory(&mut end_of_central_directory);
+
+	end_of_central_directory.write(&mut *output_zip).await?;
+
+	Ok(())
+}
+
+/// Copies the local file record, made up of a local file header and the compressed data that
+/// follows it, at the specified offset of a ZIP file to the current position of another ZIP
+/// file, returning the offset it was copied to. The size of the local file header is read
+/// from it, as obfuscation may alter its compressed size field.
+async fn copy_local_file_record(
+	source_zip: &mut BufferedAsyncSpooledTempFile,
+	local_header_offset: u64,
+	compressed_size: u32,
+	destination_zip: &mut BufferedAsyncSpooledTempFile
+) -> Result<u64, SquashZipError> {
+	let destination_offset = destination_zip.stream_position().await?;
+
+	source_zip
+		.seek(SeekFrom::Start(local_header_offset))
+		.await?;
+
+	let mut fixed_size_fields = [0; 30];
+	source_zip.read_exact(&mut fixed_size_fields).await?;
+	destination_zip.write_all(&fixed_size_fields).await?;
+
+	let file_name_length = u16::from_le_bytes([fixed_size_fields[26], fixed_size_fields[27]]);
+	let extra_field_length = u16::from_le_bytes([fixed_size_fields[28], fixed_size_fields[29]]);
+
+	tokio::io::copy(
+		&mut AsyncReadExt::take(
+			&mut *source_zip,
+			file_name_length as u64 + extra_field_length as u64 + compressed_size as u64
+		),
+		destination_zip
+	)
+	.await?;
+
+	Ok(destination_offset)
+}
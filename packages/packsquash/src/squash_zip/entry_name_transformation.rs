@@ -0,0 +1,130 @@
+//! Contains the transformations that SquashZip can apply to the names of the entries it
+//! stores, before any ZIP record that refers to them is written.
+
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::RelativePath;
+
+#[cfg(test)]
+mod tests;
+
+/// A transformation that is applied to the relative path of a pack file to compute the
+/// name of its entry in the output ZIP file.
+#[derive(Clone, Deserialize)]
+#[serde(tag = "transformation", rename_all = "snake_case", deny_unknown_fields)]
+#[non_exhaustive]
+pub enum ZipEntryNameTransformation {
+	/// Converts every uppercase character of the entry name to lowercase. This is useful
+	/// to fix packs with inconsistent casing, which work on case-insensitive file systems
+	/// but break when loaded from case-sensitive ones.
+	Lowercase,
+	/// Removes the specified prefix from the beginning of the entry name, if the name starts
+	/// with it. Names that do not start with the prefix are left untouched.
+	StripPrefix {
+		/// The prefix to remove.
+		prefix: String
+	},
+	/// Replaces every match of a regular expression in the entry name with a replacement
+	/// string, which may refer to capture groups with the `$name` or `${name}` syntax.
+	RegexReplace {
+		/// The regular expression to match.
+		pattern: EntryNameRegex,
+		/// The replacement string for each match.
+		replacement: String
+	}
+}
+
+/// A helper struct that contains a compiled regular expression to match ZIP entry names.
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "Cow<'_, str>")]
+#[repr(transparent)]
+pub struct EntryNameRegex(Regex);
+
+impl TryFrom<Cow<'_, str>> for EntryNameRegex {
+	type Error = regex::Error;
+
+	fn try_from(value: Cow<'_, str>) -> Result<Self, Self::Error> {
+		Regex::new(&value).map(Self)
+	}
+}
+
+/// Represents an error that may happen when the transformed name of a ZIP entry is not a
+/// valid relative path.
+#[derive(Error, Debug)]
+#[error(
+	"The name transformations turned {original_name} into the invalid entry name \"{transformed_name}\""
+)]
+pub struct InvalidEntryNameError {
+	original_name: String,
+	transformed_name: String
+}
+
+/// Applies the specified transformations, in order, to the given relative path, and returns
+/// the resulting ZIP entry name. No memory is allocated if the transformations do not change
+/// the path.
+///
+/// An error is returned if the transformed name is no longer a normalized relative path:
+/// it must not be empty, begin with a slash, contain empty, `.` or `..` components, or
+/// exceed 65535 bytes in size.
+pub fn transform_entry_name<'p>(
+	transformations: &[ZipEntryNameTransformation],
+	path: &'p RelativePath<'_>
+) -> Result<RelativePath<'p>, InvalidEntryNameError> {
+	let mut name = Cow::Borrowed(path.as_str());
+
+	if transformations.is_empty() {
+		return Ok(RelativePath::from_inner(name));
+	}
+
+	for transformation in transformations {
+		match transformation {
+			ZipEntryNameTransformation::Lowercase => {
+				if name.chars().any(char::is_uppercase) {
+					name = Cow::Owned(name.to_lowercase());
+				}
+			}
+			ZipEntryNameTransformation::StripPrefix { prefix } => {
+				if !prefix.is_empty() && name.starts_with(prefix.as_str()) {
+					name = match name {
+						Cow::Borrowed(name) => Cow::Borrowed(&name[prefix.len()..]),
+						Cow::Owned(mut name) => {
+							name.drain(..prefix.len());
+							Cow::Owned(name)
+						}
+					};
+				}
+			}
+			ZipEntryNameTransformation::RegexReplace {
+				pattern: EntryNameRegex(pattern),
+				replacement
+			} => {
+				let replaced_name = match pattern.replace_all(&name, replacement.as_str()) {
+					Cow::Owned(replaced_name) => Some(replaced_name),
+					Cow::Borrowed(_) => None
+				};
+
+				if let Some(replaced_name) = replaced_name {
+					name = Cow::Owned(replaced_name);
+				}
+			}
+		}
+	}
+
+	if name.is_empty()
+		|| name.len() > u16::MAX as usize
+		|| name
+			.split('/')
+			.any(|component| matches!(component, "" | "." | ".."))
+	{
+		return Err(InvalidEntryNameError {
+			original_name: path.as_str().into(),
+			transformed_name: name.into_owned()
+		});
+	}
+
+	Ok(RelativePath::from_inner(name))
+}
@@ -0,0 +1,106 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+/// Transforms the specified path with the given transformations, expecting a successful
+/// result, and returns the transformed path as an owned string.
+fn transform(transformations: &[ZipEntryNameTransformation], path: &'static str) -> String {
+	transform_entry_name(transformations, &RelativePath::from_inner(path))
+		.expect("The transformed entry name should be valid")
+		.as_str()
+		.into()
+}
+
+#[test]
+fn no_transformations_borrow_the_path() {
+	let path = RelativePath::from_inner("assets/minecraft/textures/Stone.png");
+
+	assert!(matches!(
+		transform_entry_name(&[], &path).unwrap().into_inner(),
+		Cow::Borrowed("assets/minecraft/textures/Stone.png")
+	));
+}
+
+#[test]
+fn lowercase_works() {
+	assert_eq!(
+		transform(
+			&[ZipEntryNameTransformation::Lowercase],
+			"assets/MyPack/textures/Block/Stone.PNG"
+		),
+		"assets/mypack/textures/block/stone.png"
+	);
+}
+
+#[test]
+fn strip_prefix_works() {
+	let transformations = [ZipEntryNameTransformation::StripPrefix {
+		prefix: "src/main/resources/".into()
+	}];
+
+	assert_eq!(
+		transform(&transformations, "src/main/resources/pack.mcmeta"),
+		"pack.mcmeta"
+	);
+	assert_eq!(transform(&transformations, "pack.png"), "pack.png");
+}
+
+#[test]
+fn transformations_are_applied_in_order() {
+	let transformations = [
+		ZipEntryNameTransformation::Lowercase,
+		ZipEntryNameTransformation::StripPrefix {
+			prefix: "build/".into()
+		},
+		ZipEntryNameTransformation::RegexReplace {
+			pattern: EntryNameRegex::try_from(Cow::Borrowed(r"\.jpe?g$")).unwrap(),
+			replacement: ".png".into()
+		}
+	];
+
+	assert_eq!(
+		transform(&transformations, "Build/assets/x/textures/A.JPEG"),
+		"assets/x/textures/a.png"
+	);
+}
+
+#[test]
+fn regex_replace_supports_capture_groups() {
+	assert_eq!(
+		transform(
+			&[ZipEntryNameTransformation::RegexReplace {
+				pattern: EntryNameRegex::try_from(Cow::Borrowed(r"^assets/(?<ns>[^/]+)_old/"))
+					.unwrap(),
+				replacement: "assets/${ns}/".into()
+			}],
+			"assets/mypack_old/lang/en_us.json"
+		),
+		"assets/mypack/lang/en_us.json"
+	);
+}
+
+#[test]
+fn invalid_transformed_names_are_rejected() {
+	let transformations = [ZipEntryNameTransformation::RegexReplace {
+		pattern: EntryNameRegex::try_from(Cow::Borrowed("textures")).unwrap(),
+		replacement: "..".into()
+	}];
+
+	assert!(
+		transform_entry_name(
+			&transformations,
+			&RelativePath::from_inner("assets/minecraft/textures/stone.png")
+		)
+		.is_err()
+	);
+
+	assert!(
+		transform_entry_name(
+			&[ZipEntryNameTransformation::StripPrefix {
+				prefix: "pack.png".into()
+			}],
+			&RelativePath::from_inner("pack.png")
+		)
+		.is_err()
+	);
+}
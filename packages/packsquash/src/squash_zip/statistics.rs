@@ -0,0 +1,148 @@
+//! Contains the data structures that SquashZip uses to report statistics about the entries
+//! it adds to a ZIP file, when requested to do so.
+
+use std::time::Duration;
+
+use serde::{Serialize, Serializer};
+
+use super::zip_file_record::CompressionMethod;
+
+/// The compression method that was chosen for storing a ZIP entry.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ZipEntryCompressionMethod {
+	/// The entry data was stored without compression, because compressing it did not
+	/// save space, or compression was skipped.
+	Store,
+	/// The entry data was compressed with the Deflate algorithm.
+	Deflate
+}
+
+impl From<CompressionMethod> for ZipEntryCompressionMethod {
+	fn from(compression_method: CompressionMethod) -> Self {
+		match compression_method {
+			CompressionMethod::Store => Self::Store,
+			CompressionMethod::Deflate => Self::Deflate
+		}
+	}
+}
+
+/// Statistics about how a single entry was added to a ZIP file by SquashZip.
+#[derive(Serialize, Clone, Debug)]
+#[non_exhaustive]
+pub struct ZipEntryStatistics {
+	/// The name of the entry in the ZIP file.
+	pub name: String,
+	/// The compression method that was chosen for the entry data.
+	pub compression_method: ZipEntryCompressionMethod,
+	/// The size of the entry data before compression, in bytes.
+	pub uncompressed_size: u32,
+	/// The size of the entry data as stored in the ZIP file, in bytes.
+	pub compressed_size: u32,
+	/// The ratio between the compressed and uncompressed sizes of the entry data. Lower
+	/// is better. Empty entries have a ratio of 1.
+	pub compression_ratio: f64,
+	/// The time spent compressing the entry data, in seconds.
+	#[serde(serialize_with = "serialize_duration_as_seconds")]
+	pub compression_time: Duration,
+	/// The time spent on anything else than compression while adding the entry, in seconds.
+	/// This includes waiting for its processed data to be available, reading and writing
+	/// scratch and ZIP data, and waiting for other entries to be written.
+	#[serde(serialize_with = "serialize_duration_as_seconds")]
+	pub io_wait_time: Duration,
+	/// Whether the entry data was not written again because identical data was already
+	/// stored in the ZIP file.
+	pub deduplicated: bool,
+	/// Whether the entry data was copied from a ZIP file generated in a previous run.
+	pub reused_from_previous_zip: bool
+}
+
+impl ZipEntryStatistics {
+	/// Creates a new statistics record for a ZIP entry from its data.
+	#[allow(clippy::too_many_arguments)] // Alternatives are not really more readable
+	pub(super) fn new(
+		name: String,
+		compression_method: CompressionMethod,
+		uncompressed_size: u32,
+		compressed_size: u32,
+		compression_time: Duration,
+		io_wait_time: Duration,
+		deduplicated: bool,
+		reused_from_previous_zip: bool
+	) -> Self {
+		Self {
+			name,
+			compression_method: compression_method.into(),
+			uncompressed_size,
+			compressed_size,
+			compression_ratio: if uncompressed_size == 0 {
+				1.0
+			} else {
+				compressed_size as f64 / uncompressed_size as f64
+			},
+			compression_time,
+			io_wait_time,
+			deduplicated,
+			reused_from_previous_zip
+		}
+	}
+}
+
+/// A machine-readable summary of the statistics collected by SquashZip while generating a
+/// ZIP file, which can be used to identify which entries dominate the time and size of
+/// the result.
+#[derive(Serialize, Clone, Debug)]
+#[non_exhaustive]
+pub struct SquashZipStatistics {
+	/// The number of entries in the ZIP file.
+	pub entry_count: usize,
+	/// The sum of the uncompressed sizes of every entry, in bytes.
+	pub total_uncompressed_size: u64,
+	/// The sum of the compressed sizes of every entry, in bytes. Deduplicated entries
+	/// are counted only once.
+	pub total_compressed_size: u64,
+	/// The sum of the compression times of every entry, in seconds.
+	#[serde(serialize_with = "serialize_duration_as_seconds")]
+	pub total_compression_time: Duration,
+	/// The sum of the I/O wait times of every entry, in seconds.
+	#[serde(serialize_with = "serialize_duration_as_seconds")]
+	pub total_io_wait_time: Duration,
+	/// The statistics of each entry, sorted by descending total processing time.
+	pub entries: Vec<ZipEntryStatistics>
+}
+
+impl SquashZipStatistics {
+	/// Summarizes the statistics collected for the specified ZIP entries.
+	pub(super) fn new(mut entries: Vec<ZipEntryStatistics>) -> Self {
+		entries.sort_unstable_by(|a, b| {
+			(b.compression_time + b.io_wait_time)
+				.cmp(&(a.compression_time + a.io_wait_time))
+				.then_with(|| a.name.cmp(&b.name))
+		});
+
+		Self {
+			entry_count: entries.len(),
+			total_uncompressed_size: entries
+				.iter()
+				.map(|entry| entry.uncompressed_size as u64)
+				.sum(),
+			total_compressed_size: entries
+				.iter()
+				.filter(|entry| !entry.deduplicated)
+				.map(|entry| entry.compressed_size as u64)
+				.sum(),
+			total_compression_time: entries.iter().map(|entry| entry.compression_time).sum(),
+			total_io_wait_time: entries.iter().map(|entry| entry.io_wait_time).sum(),
+			entries
+		}
+	}
+}
+
+/// Serializes a [`Duration`] as a floating point number of seconds, which is easier to
+/// consume by other programs than the default representation.
+fn serialize_duration_as_seconds<S: Serializer>(
+	duration: &Duration,
+	serializer: S
+) -> Result<S::Ok, S::Error> {
+	serializer.serialize_f64(duration.as_secs_f64())
+}
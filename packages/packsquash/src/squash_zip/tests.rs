@@ -69,8 +69,11 @@ async fn add_files_finish_and_read_back_test(
 				percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
 				workaround_old_java_obfuscation_quirks: false,
 				spool_buffer_size: spool_buffer_size.unwrap_or(DEFAULT_SPOOL_BUFFER_SIZE),
+				temporary_storage_limit: None,
 				zip_comment: ZipArchiveCommentString::new("Test comment")
-					.expect("The archive comment is assumed to be valid")
+					.expect("The archive comment is assumed to be valid"),
+				entry_name_transformations: Vec::new(),
+				collect_statistics: false
 			}
 		)
 		.await
@@ -119,7 +122,10 @@ async fn add_files_finish_and_read_back_test(
 			percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
 			workaround_old_java_obfuscation_quirks: false,
 			spool_buffer_size: spool_buffer_size.unwrap_or(DEFAULT_SPOOL_BUFFER_SIZE),
-			zip_comment: ZipArchiveCommentString::default()
+			temporary_storage_limit: None,
+			zip_comment: ZipArchiveCommentString::default(),
+			entry_name_transformations: Vec::new(),
+			collect_statistics: false
 		}
 	)
 	.await
@@ -334,7 +340,10 @@ async fn add_several_finish_then_reuse_and_add_works() {
 			percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
 			workaround_old_java_obfuscation_quirks: false,
 			spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
-			zip_comment: ZipArchiveCommentString::default()
+			temporary_storage_limit: None,
+			zip_comment: ZipArchiveCommentString::default(),
+			entry_name_transformations: Vec::new(),
+			collect_statistics: false
 		}
 	)
 	.await
@@ -383,7 +392,10 @@ async fn add_several_finish_then_reuse_and_add_works() {
 			percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
 			workaround_old_java_obfuscation_quirks: false,
 			spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
-			zip_comment: ZipArchiveCommentString::default()
+			temporary_storage_limit: None,
+			zip_comment: ZipArchiveCommentString::default(),
+			entry_name_transformations: Vec::new(),
+			collect_statistics: false
 		}
 	)
 	.await
@@ -414,7 +426,10 @@ async fn several_files_with_same_path_are_handled_properly() {
 			percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
 			workaround_old_java_obfuscation_quirks: false,
 			spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
-			zip_comment: ZipArchiveCommentString::default()
+			temporary_storage_limit: None,
+			zip_comment: ZipArchiveCommentString::default(),
+			entry_name_transformations: Vec::new(),
+			collect_statistics: false
 		}
 	)
 	.await
@@ -438,3 +453,155 @@ async fn several_files_with_same_path_are_handled_properly() {
 	add_file().await.expect(UNEXPECTED_OPERATION_FAILURE);
 	add_file().await.expect_err(UNEXPECTED_OPERATION_FAILURE);
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn statistics_are_collected_when_requested() {
+	let squash_zip = SquashZip::new(
+		None::<File>,
+		SquashZipSettings {
+			zopfli_iterations: 1,
+			store_squash_time: false,
+			enable_obfuscation: false,
+			enable_deduplication: true,
+			enable_size_increasing_obfuscation: false,
+			percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
+			workaround_old_java_obfuscation_quirks: false,
+			spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
+			temporary_storage_limit: None,
+			zip_comment: ZipArchiveCommentString::default(),
+			entry_name_transformations: Vec::new(),
+			collect_statistics: true
+		}
+	)
+	.await
+	.map_err(|(err, _)| err)
+	.expect(INSTANTIATION_FAILURE);
+
+	for file in ["virtual/visions0.bin", "virtual/visions1.bin"] {
+		squash_zip
+			.add_file(
+				&RelativePath::from_inner(file),
+				tokio_stream::once([b'a'; FILE_SIZE]),
+				false,
+				FILE_SIZE,
+				FileListingCircumstances {
+					may_be_read_and_provided_by_mods: false,
+					is_force_included: false
+				}
+			)
+			.await
+			.expect(UNEXPECTED_OPERATION_FAILURE);
+	}
+
+	let statistics = squash_zip
+		.finish(create_temporary_output_file(
+			"statistics_are_collected_when_requested"
+		))
+		.await
+		.expect(UNEXPECTED_OPERATION_FAILURE)
+		.expect("Statistics should be returned when their collection is enabled");
+
+	assert_eq!(statistics.entry_count, 2);
+	assert_eq!(statistics.total_uncompressed_size, 2 * FILE_SIZE as u64);
+	assert_eq!(
+		statistics
+			.entries
+			.iter()
+			.filter(|entry| entry.deduplicated)
+			.count(),
+		1,
+		"The second file should be deduplicated"
+	);
+	assert_eq!(
+		statistics.total_compressed_size, statistics.entries[0].compressed_size as u64,
+		"Deduplicated entries should not be counted twice"
+	);
+
+	for entry in &statistics.entries {
+		assert_eq!(entry.compression_method, ZipEntryCompressionMethod::Deflate);
+		assert!(entry.compression_ratio < 1.0);
+	}
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn split_outputs_are_written_and_read_back() {
+	let squash_zip_settings = || SquashZipSettings {
+		zopfli_iterations: 1,
+		store_squash_time: true,
+		enable_obfuscation: false,
+		enable_deduplication: true,
+		enable_size_increasing_obfuscation: false,
+		percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
+		workaround_old_java_obfuscation_quirks: false,
+		spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
+		temporary_storage_limit: None,
+		zip_comment: ZipArchiveCommentString::default(),
+		entry_name_transformations: Vec::new(),
+		collect_statistics: false
+	};
+
+	let squash_zip = SquashZip::new(None::<File>, squash_zip_settings())
+		.await
+		.map_err(|(err, _)| err)
+		.expect(INSTANTIATION_FAILURE);
+
+	for (file, byte) in [
+		("assets/first/visions0.bin", b'a'),
+		("assets/first/visions1.bin", b'a'),
+		("assets/second/visions2.bin", b'b')
+	] {
+		squash_zip
+			.add_file(
+				&RelativePath::from_inner(file),
+				tokio_stream::once([byte; FILE_SIZE]),
+				false,
+				FILE_SIZE,
+				FileListingCircumstances {
+					may_be_read_and_provided_by_mods: false,
+					is_force_included: false
+				}
+			)
+			.await
+			.expect(UNEXPECTED_OPERATION_FAILURE);
+	}
+
+	let split_outputs = [
+		(
+			create_temporary_output_file("split_outputs_are_written_and_read_back"),
+			"assets/first/"
+		),
+		(
+			create_temporary_output_file("split_outputs_are_written_and_read_back"),
+			"assets/second/"
+		)
+	]
+	.map(|(file_path, prefix)| {
+		(file_path, move |entry_name: &str| {
+			entry_name.starts_with(prefix)
+		})
+	});
+
+	squash_zip
+		.finish_with_split_outputs(
+			create_temporary_output_file("split_outputs_are_written_and_read_back"),
+			&split_outputs
+		)
+		.await
+		.expect(UNEXPECTED_OPERATION_FAILURE);
+
+	for ((file_path, _), expected_file_count) in split_outputs.iter().zip([2, 1]) {
+		let squash_zip = SquashZip::new(
+			Some(File::open(file_path).await.expect(UNEXPECTED_IO_FAILURE)),
+			squash_zip_settings()
+		)
+		.await
+		.map_err(|(err, _)| err)
+		.expect(INSTANTIATION_FAILURE);
+
+		assert_eq!(
+			squash_zip.previous_file_count(),
+			expected_file_count,
+			"Unexpected number of ZIP files read back from a split output"
+		);
+	}
+}
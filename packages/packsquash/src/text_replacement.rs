@@ -0,0 +1,176 @@
+//! Contains a virtual file system adapter that applies user-defined regular expression
+//! replacement rules to the text pack files whose relative paths match some glob patterns.
+
+use std::{
+	borrow::Cow,
+	fs::FileType,
+	io::{self, Cursor},
+	path::{Path, PathBuf}
+};
+
+use ahash::AHashMap;
+use bytes::Bytes;
+use globset::GlobSet;
+use indexmap::IndexMap;
+use regex::Regex;
+use serde::Deserialize;
+use tokio_util::either::Either;
+
+use crate::vfs::{
+	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VirtualFileSystem,
+	open_in_memory_or_inner, read_file_to_string
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A rule that replaces every match of a regular expression in the text of a pack file.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TextReplacementRule {
+	/// The regular expression to match.
+	pub pattern: TextReplacementRegex,
+	/// The replacement string for each match, which may refer to capture groups with the
+	/// `$name` or `${name}` syntax.
+	pub replacement: String
+}
+
+/// A helper struct that contains a compiled regular expression to match pack file text.
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "Cow<'_, str>")]
+#[repr(transparent)]
+pub struct TextReplacementRegex(Regex);
+
+impl TryFrom<Cow<'_, str>> for TextReplacementRegex {
+	type Error = regex::Error;
+
+	fn try_from(value: Cow<'_, str>) -> Result<Self, Self::Error> {
+		Regex::new(&value).map(Self)
+	}
+}
+
+/// A [`VirtualFileSystem`] that wraps another one, transparently applying text replacement
+/// rules to the pack files whose relative paths match the glob patterns of the rules.
+///
+/// The replaced files are computed eagerly by [`Self::replace_text`] and kept in memory,
+/// because the [`VirtualFileSystem::open`] method is synchronous. Other files are read from
+/// the wrapped file system as-is.
+pub struct TextReplacingVfs<V: VirtualFileSystem> {
+	inner: V,
+	/// The contents of the files whose text was changed by some rule.
+	replaced_files: AHashMap<PathBuf, Bytes>
+}
+
+impl<V: VirtualFileSystem> TextReplacingVfs<V> {
+	/// Wraps the specified virtual file system. No text will be replaced until
+	/// [`Self::replace_text`] is called.
+	pub fn new(inner: V) -> Self {
+		Self {
+			inner,
+			replaced_files: AHashMap::new()
+		}
+	}
+
+	/// Scans the pack at the specified root path for files whose relative paths match the
+	/// specified glob set, applying the rules associated to every matching glob pattern, in
+	/// order. `rules` must map each pattern of the glob set to its rules, in the same order as
+	/// the patterns were added to it. Files that can't be read or are not valid UTF-8 are left
+	/// as-is.
+	pub async fn replace_text(
+		&mut self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions,
+		globs: &GlobSet,
+		rules: &IndexMap<String, Vec<TextReplacementRule>>
+	) {
+		if globs.is_empty() {
+			return;
+		}
+
+		let matching_files = self
+			.inner
+			.file_iterator(root_path, iterator_traversal_options)
+			.flatten()
+			.filter_map(
+				|VfsPackFileIterEntry {
+				     relative_path,
+				     file_path
+				 }| {
+					let matching_globs = globs.matches(relative_path.as_str());
+					(!matching_globs.is_empty()).then_some((file_path, matching_globs))
+				}
+			)
+			.collect::<Vec<_>>();
+
+		for (file_path, matching_globs) in matching_files {
+			let Ok(text) = read_file_to_string(&self.inner, &file_path).await else {
+				continue;
+			};
+
+			if let Some(replaced_text) = apply_rules(
+				&text,
+				matching_globs
+					.into_iter()
+					.flat_map(|glob_index| &rules[glob_index])
+			) {
+				self.replaced_files.insert(file_path, replaced_text.into());
+			}
+		}
+	}
+}
+
+/// Applies the specified rules, in order, to a text, returning `None` if none of them
+/// changed it.
+fn apply_rules<'r>(
+	text: &str,
+	rules: impl IntoIterator<Item = &'r TextReplacementRule>
+) -> Option<String> {
+	let mut replaced_text = Cow::Borrowed(text);
+
+	for TextReplacementRule {
+		pattern: TextReplacementRegex(pattern),
+		replacement
+	} in rules
+	{
+		let new_text = match pattern.replace_all(&replaced_text, replacement.as_str()) {
+			Cow::Owned(new_text) => new_text,
+			Cow::Borrowed(_) => continue
+		};
+		replaced_text = Cow::Owned(new_text);
+	}
+
+	match replaced_text {
+		Cow::Owned(replaced_text) if replaced_text != text => Some(replaced_text),
+		_ => None
+	}
+}
+
+impl<V: VirtualFileSystem> VirtualFileSystem for TextReplacingVfs<V> {
+	type FileRead = Either<V::FileRead, Cursor<Bytes>>;
+	type FileIter = V::FileIter;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		self.inner
+			.file_iterator(root_path, iterator_traversal_options)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		// The rules may change without the file being modified, so replaced files
+		// must always be processed again instead of reusing their previous version
+		open_in_memory_or_inner(
+			&self.inner,
+			&path,
+			self.replaced_files
+				.get(path.as_ref())
+				.map(|replaced_file| (replaced_file, None))
+		)
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
+		self.inner.file_type(path)
+	}
+}
@@ -0,0 +1,107 @@
+use std::fs;
+
+use globset::GlobSetBuilder;
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+use tokio::io::AsyncReadExt;
+
+use crate::config::compile_pack_file_glob_pattern;
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+#[test]
+fn text_is_replaced() {
+	let root_dir = Builder::new()
+		.prefix("ps-text-replacement-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	for (relative_path, data) in [
+		(
+			"pack.mcmeta",
+			r#"{ "pack": { "pack_format": 15, "description": "Join discord.gg/abc123!" } }"#
+		),
+		(
+			"credits.txt",
+			"Textures by Ana (https://internal.example.com/ana)\nJoin discord.gg/abc123"
+		),
+		(
+			"assets/mypack/lang/en_us.lang",
+			"site=https://internal.example.com\n"
+		),
+		("notes.txt", "Nothing to replace here")
+	] {
+		let path = root_dir.path().join(relative_path);
+		fs::create_dir_all(path.parent().unwrap())
+			.expect("I/O operations are assumed not to fail during tests");
+		fs::write(path, data).expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let rule = |pattern: &str, replacement: &str| TextReplacementRule {
+		pattern: Cow::Borrowed(pattern)
+			.try_into()
+			.expect("The test regular expressions are valid"),
+		replacement: replacement.to_string()
+	};
+
+	let rules = IndexMap::from([
+		(
+			"*.txt".to_string(),
+			vec![rule(
+				r"https://internal\.example\.com/(\w+)",
+				"https://example.com/$1"
+			)]
+		),
+		(
+			"{*.txt,pack.mcmeta}".to_string(),
+			vec![rule(r"discord\.gg/\w+", "our Discord server")]
+		)
+	]);
+
+	let mut globs = GlobSetBuilder::new();
+	for glob_pattern in rules.keys() {
+		globs.add(compile_pack_file_glob_pattern(glob_pattern).unwrap());
+	}
+
+	let mut vfs = TextReplacingVfs::new(OsFilesystem);
+	tokio_test::block_on(vfs.replace_text(
+		root_dir.path(),
+		IteratorTraversalOptions::default(),
+		&globs.build().unwrap(),
+		&rules
+	));
+
+	let read_file = |relative_path: &str| {
+		let mut file = vfs
+			.open(root_dir.path().join(relative_path))
+			.expect("I/O operations are assumed not to fail during tests");
+		let mut data = String::new();
+		tokio_test::block_on(file.file_read.read_to_string(&mut data))
+			.expect("I/O operations are assumed not to fail during tests");
+		(data, file.metadata.modification_time.is_some())
+	};
+
+	assert_eq!(
+		read_file("pack.mcmeta"),
+		(
+			r#"{ "pack": { "pack_format": 15, "description": "Join our Discord server!" } }"#
+				.to_string(),
+			false
+		)
+	);
+	assert_eq!(
+		read_file("credits.txt"),
+		(
+			"Textures by Ana (https://example.com/ana)\nJoin our Discord server".to_string(),
+			false
+		)
+	);
+	assert_eq!(
+		read_file("assets/mypack/lang/en_us.lang"),
+		("site=https://internal.example.com\n".to_string(), true)
+	);
+	assert_eq!(
+		read_file("notes.txt"),
+		("Nothing to replace here".to_string(), true)
+	);
+}
@@ -0,0 +1,492 @@
+//! Contains the data structures and routines used to generate reports that compare the
+//! textures of a pack before and after being optimized, so that the changes made by lossy
+//! optimizations can be reviewed before distributing the pack.
+
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use spng::{ContextFlags, DecodeFlags, Format};
+
+use crate::RelativePath;
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum width and height of the texture thumbnails included in HTML reports, in pixels.
+const THUMBNAIL_SIZE: usize = 64;
+
+/// The width and height of the square pixel windows whose structural similarity indices are
+/// averaged to get the SSIM of a texture, in pixels.
+const SSIM_WINDOW_SIZE: usize = 8;
+
+/// The format of a texture optimization report file.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextureOptimizationReportFormat {
+	/// A machine-readable JSON document.
+	Json,
+	/// A self-contained HTML page, with thumbnails of the textures before and after being
+	/// optimized.
+	Html
+}
+
+impl TextureOptimizationReportFormat {
+	/// Returns the report format that should be used for a report file at the specified
+	/// path, according to its extension. Files with `.html` or `.htm` extensions are HTML
+	/// reports, and any other file is a JSON report.
+	pub fn for_file_path(file_path: &Path) -> Self {
+		match file_path.extension() {
+			Some(extension)
+				if extension.eq_ignore_ascii_case("html")
+					|| extension.eq_ignore_ascii_case("htm") =>
+			{
+				Self::Html
+			}
+			_ => Self::Json
+		}
+	}
+}
+
+/// How a single texture was changed by the optimization process.
+#[derive(Serialize, Clone, Debug)]
+#[non_exhaustive]
+pub struct TextureReportEntry {
+	/// The relative path of the texture in the output ZIP file.
+	pub path: String,
+	/// The optimization strategy that was used for the texture.
+	pub optimization_strategy: String,
+	/// The size of the input texture file, in bytes.
+	pub input_size: u64,
+	/// The size of the optimized texture file, in bytes. It is `None` if the texture was
+	/// copied from a previous run, because in that case it was not optimized again.
+	pub output_size: Option<u64>,
+	/// The width and height of the input texture, in pixels. It is `None` if the input
+	/// texture is not a PNG file that could be decoded.
+	pub input_dimensions: Option<[u32; 2]>,
+	/// The width and height of the optimized texture, in pixels. It is `None` if it is not
+	/// known.
+	pub output_dimensions: Option<[u32; 2]>,
+	/// Whether the optimization changed the texture dimensions or any of its visible pixels.
+	/// The colors of completely transparent pixels are not compared, as they are invisible.
+	/// It is `None` if the input and output textures could not be compared.
+	pub lossy: Option<bool>,
+	/// The peak signal-to-noise ratio between the input and optimized texture pixels, in
+	/// decibels. Higher is better. It is only computed when the optimization was lossy
+	/// but did not change the texture dimensions.
+	pub psnr: Option<f64>,
+	/// The mean structural similarity index between the input and optimized texture pixels,
+	/// between -1 and 1. Higher is better. It is only computed when the optimization was
+	/// lossy but did not change the texture dimensions.
+	pub ssim: Option<f64>,
+	/// PNG thumbnails of the input and optimized textures, included in HTML reports only.
+	#[serde(skip)]
+	thumbnails: [Option<Vec<u8>>; 2]
+}
+
+/// A report about how every texture of a pack was changed by the optimization process,
+/// which is collected while pack files are processed.
+pub struct TextureOptimizationReport {
+	format: TextureOptimizationReportFormat,
+	entries: Mutex<Vec<TextureReportEntry>>
+}
+
+/// The JSON representation of a finished [`TextureOptimizationReport`].
+#[derive(Serialize)]
+struct TextureOptimizationReportSummary<'entries> {
+	/// The number of textures in the report.
+	texture_count: usize,
+	/// The number of textures whose optimization was lossy.
+	lossy_texture_count: usize,
+	/// The sum of the input sizes of the textures with a known output size, in bytes.
+	total_input_size: u64,
+	/// The sum of the known output sizes of the textures, in bytes.
+	total_output_size: u64,
+	/// The report entry of every texture, sorted by path.
+	textures: &'entries [TextureReportEntry]
+}
+
+impl TextureOptimizationReport {
+	/// Creates a new, empty texture optimization report that will be written in the
+	/// specified format.
+	pub fn new(format: TextureOptimizationReportFormat) -> Self {
+		Self {
+			format,
+			entries: Mutex::new(Vec::new())
+		}
+	}
+
+	/// Adds an entry to this report for the texture at the specified path, comparing its
+	/// input and output PNG files. The output data is `None` if the texture was not optimized
+	/// again because it was copied from a previous run.
+	pub fn add_texture(
+		&self,
+		path: &RelativePath<'_>,
+		optimization_strategy: &str,
+		input_data: &[u8],
+		output_data: Option<&[u8]>
+	) {
+		let input_texture = DecodedTexture::new(input_data);
+		let output_texture = output_data.and_then(DecodedTexture::new);
+
+		let (lossy, psnr, ssim) = match (&input_texture, &output_texture) {
+			(Some(input_texture), Some(output_texture)) => {
+				if input_texture.dimensions() != output_texture.dimensions() {
+					(Some(true), None, None)
+				} else if input_texture.pixels == output_texture.pixels {
+					(Some(false), None, None)
+				} else {
+					(
+						Some(true),
+						Some(psnr(input_texture, output_texture)),
+						Some(ssim(input_texture, output_texture))
+					)
+				}
+			}
+			_ => (None, None, None)
+		};
+
+		let thumbnails = if self.format == TextureOptimizationReportFormat::Html {
+			[
+				input_texture.as_ref().and_then(DecodedTexture::thumbnail),
+				output_texture.as_ref().and_then(DecodedTexture::thumbnail)
+			]
+		} else {
+			[None, None]
+		};
+
+		self.entries.lock().unwrap().push(TextureReportEntry {
+			path: path.as_str().to_string(),
+			optimization_strategy: optimization_strategy.to_string(),
+			input_size: input_data.len() as u64,
+			output_size: output_data.map(|output_data| output_data.len() as u64),
+			input_dimensions: input_texture.as_ref().map(DecodedTexture::dimensions),
+			output_dimensions: output_texture.as_ref().map(DecodedTexture::dimensions),
+			lossy,
+			psnr,
+			ssim,
+			thumbnails
+		});
+	}
+
+	/// Writes this report to a file at the specified path, consuming it.
+	pub async fn write(self, file_path: &Path) -> Result<(), io::Error> {
+		let mut entries = self.entries.into_inner().unwrap();
+		entries.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+		let summary = TextureOptimizationReportSummary {
+			texture_count: entries.len(),
+			lossy_texture_count: entries
+				.iter()
+				.filter(|entry| entry.lossy == Some(true))
+				.count(),
+			total_input_size: entries
+				.iter()
+				.filter(|entry| entry.output_size.is_some())
+				.map(|entry| entry.input_size)
+				.sum(),
+			total_output_size: entries.iter().filter_map(|entry| entry.output_size).sum(),
+			textures: &entries
+		};
+
+		let report = match self.format {
+			TextureOptimizationReportFormat::Json => {
+				serde_json::to_vec_pretty(&summary).map_err(io::Error::from)?
+			}
+			TextureOptimizationReportFormat::Html => html_report(&summary).into_bytes()
+		};
+
+		tokio::fs::write(file_path, report).await
+	}
+}
+
+/// The pixels of a decoded texture, with the colors of completely transparent pixels
+/// normalized to black.
+struct DecodedTexture {
+	width: usize,
+	height: usize,
+	pixels: Vec<[u8; 4]>
+}
+
+impl DecodedTexture {
+	/// Decodes the specified PNG file, returning `None` if it is not a valid PNG file.
+	fn new(png: &[u8]) -> Option<Self> {
+		// Minecraft does not apply gamma correction to textures, so neither do we
+		let mut png_reader = spng::Decoder::new(png)
+			.with_decode_flags(DecodeFlags::TRANSPARENCY)
+			.with_context_flags(ContextFlags::IGNORE_ADLER32)
+			.with_output_format(Format::Rgba8)
+			.read_info()
+			.ok()?;
+		let width = png_reader.info().width as usize;
+		let height = png_reader.info().height as usize;
+
+		let mut pixel_data = vec![0; png_reader.output_buffer_size()];
+		png_reader.next_frame(&mut pixel_data).ok()?;
+
+		let pixels = pixel_data
+			.chunks_exact(4)
+			.map(|pixel| {
+				if pixel[3] == 0 {
+					[0; 4]
+				} else {
+					pixel.try_into().unwrap()
+				}
+			})
+			.collect();
+
+		Some(Self {
+			width,
+			height,
+			pixels
+		})
+	}
+
+	/// Returns the width and height of this texture, in pixels.
+	fn dimensions(&self) -> [u32; 2] {
+		[self.width as u32, self.height as u32]
+	}
+
+	/// Generates a PNG thumbnail of this texture that is at most [`THUMBNAIL_SIZE`] pixels
+	/// wide and tall, using nearest-neighbor sampling to keep the look of pixel art. Returns
+	/// `None` if the thumbnail could not be encoded.
+	fn thumbnail(&self) -> Option<Vec<u8>> {
+		let scale = (self.width.max(self.height) as f64 / THUMBNAIL_SIZE as f64).max(1.0);
+		let thumbnail_width = ((self.width as f64 / scale) as usize).max(1);
+		let thumbnail_height = ((self.height as f64 / scale) as usize).max(1);
+
+		let mut thumbnail_pixels = Vec::with_capacity(thumbnail_width * thumbnail_height * 4);
+		for y in 0..thumbnail_height {
+			let source_y = ((y as f64 * scale) as usize).min(self.height - 1);
+			for x in 0..thumbnail_width {
+				let source_x = ((x as f64 * scale) as usize).min(self.width - 1);
+				thumbnail_pixels.extend_from_slice(&self.pixels[source_y * self.width + source_x]);
+			}
+		}
+
+		oxipng::RawImage::new(
+			thumbnail_width as u32,
+			thumbnail_height as u32,
+			oxipng::ColorType::RGBA,
+			oxipng::BitDepth::Eight,
+			thumbnail_pixels
+		)
+		.ok()?
+		.create_optimized_png(&oxipng::Options::from_preset(0))
+		.ok()
+	}
+}
+
+/// Computes the peak signal-to-noise ratio between the pixels of two textures with the same
+/// dimensions and different pixels, in decibels.
+fn psnr(texture: &DecodedTexture, other_texture: &DecodedTexture) -> f64 {
+	let squared_error_sum = texture
+		.pixels
+		.iter()
+		.flatten()
+		.zip(other_texture.pixels.iter().flatten())
+		.map(|(&sample, &other_sample)| (sample as f64 - other_sample as f64).powi(2))
+		.sum::<f64>();
+	let mean_squared_error = squared_error_sum / (texture.pixels.len() * 4) as f64;
+
+	10.0 * (255.0_f64.powi(2) / mean_squared_error).log10()
+}
+
+/// Computes the mean structural similarity index between the pixels of two textures with
+/// the same dimensions, averaging the index of every channel in square windows of
+/// [`SSIM_WINDOW_SIZE`] pixels.
+///
+/// References:
+/// - <https://ece.uwaterloo.ca/~z70wang/publications/ssim.pdf>
+fn ssim(texture: &DecodedTexture, other_texture: &DecodedTexture) -> f64 {
+	const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+	const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+	let mut ssim_sum = 0.0;
+	let mut window_count = 0;
+
+	for window_y in (0..texture.height).step_by(SSIM_WINDOW_SIZE) {
+		for window_x in (0..texture.width).step_by(SSIM_WINDOW_SIZE) {
+			let window_pixel_indices = (window_y..(window_y + SSIM_WINDOW_SIZE).min(texture.height))
+				.flat_map(|y| {
+					(window_x..(window_x + SSIM_WINDOW_SIZE).min(texture.width))
+						.map(move |x| y * texture.width + x)
+				});
+
+			for channel in 0..4 {
+				let samples = window_pixel_indices.clone().map(|i| {
+					(
+						texture.pixels[i][channel] as f64,
+						other_texture.pixels[i][channel] as f64
+					)
+				});
+				let sample_count = samples.clone().count() as f64;
+
+				let (sum, other_sum) =
+					samples
+						.clone()
+						.fold((0.0, 0.0), |(sum, other_sum), (sample, other_sample)| {
+							(sum + sample, other_sum + other_sample)
+						});
+				let (mean, other_mean) = (sum / sample_count, other_sum / sample_count);
+
+				let (variance, other_variance, covariance) = samples.fold(
+					(0.0, 0.0, 0.0),
+					|(variance, other_variance, covariance), (sample, other_sample)| {
+						(
+							variance + (sample - mean).powi(2) / sample_count,
+							other_variance + (other_sample - other_mean).powi(2) / sample_count,
+							covariance + (sample - mean) * (other_sample - other_mean) / sample_count
+						)
+					}
+				);
+
+				ssim_sum += ((2.0 * mean * other_mean + C1) * (2.0 * covariance + C2))
+					/ ((mean.powi(2) + other_mean.powi(2) + C1) * (variance + other_variance + C2));
+				window_count += 1;
+			}
+		}
+	}
+
+	ssim_sum / window_count as f64
+}
+
+/// Renders a self-contained HTML page for the specified report summary.
+fn html_report(summary: &TextureOptimizationReportSummary) -> String {
+	let mut html = String::from(concat!(
+		"<!DOCTYPE html>\n",
+		"<html lang=\"en\">\n",
+		"<head>\n",
+		"<meta charset=\"utf-8\">\n",
+		"<title>PackSquash texture optimization report</title>\n",
+		"<style>\n",
+		"body { font-family: sans-serif; }\n",
+		"table { border-collapse: collapse; }\n",
+		"th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }\n",
+		"img { width: 64px; height: 64px; object-fit: contain; image-rendering: pixelated; ",
+		"background: repeating-conic-gradient(#ddd 0% 25%, #fff 0% 50%) 0 0 / 16px 16px; }\n",
+		".lossy { background: #fff3cd; }\n",
+		"</style>\n",
+		"</head>\n",
+		"<body>\n",
+		"<h1>Texture optimization report</h1>\n"
+	));
+
+	writeln!(
+		html,
+		"<p>{} textures, {} of them optimized lossily. Textures that were optimized in this run \
+		went from {} to {} bytes.</p>",
+		summary.texture_count,
+		summary.lossy_texture_count,
+		summary.total_input_size,
+		summary.total_output_size
+	)
+	.unwrap();
+
+	html.push_str(concat!(
+		"<table>\n",
+		"<thead><tr><th>Texture</th><th>Before</th><th>After</th><th>Input size</th>",
+		"<th>Output size</th><th>Dimensions</th><th>PSNR (dB)</th><th>SSIM</th>",
+		"<th>Optimization strategy</th></tr></thead>\n",
+		"<tbody>\n"
+	));
+
+	for entry in summary.textures {
+		let [input_thumbnail, output_thumbnail] = entry.thumbnails.each_ref().map(|thumbnail| {
+			thumbnail.as_ref().map_or_else(String::new, |thumbnail| {
+				format!(
+					"<img src=\"data:image/png;base64,{}\" alt=\"\">",
+					base64(thumbnail)
+				)
+			})
+		});
+		let format_dimensions = |dimensions: Option<[u32; 2]>| {
+			dimensions.map_or_else(String::new, |[w, h]| format!("{w}x{h}"))
+		};
+
+		writeln!(
+			html,
+			"<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+			<td>{}</td><td>{}</td></tr>",
+			if entry.lossy == Some(true) {
+				" class=\"lossy\""
+			} else {
+				""
+			},
+			escape_html(&entry.path),
+			input_thumbnail,
+			output_thumbnail,
+			entry.input_size,
+			entry
+				.output_size
+				.map_or_else(String::new, |output_size| output_size.to_string()),
+			match (entry.input_dimensions, entry.output_dimensions) {
+				(input_dimensions, output_dimensions) if input_dimensions == output_dimensions => {
+					format_dimensions(input_dimensions)
+				}
+				(input_dimensions, output_dimensions) => format!(
+					"{} → {}",
+					format_dimensions(input_dimensions),
+					format_dimensions(output_dimensions)
+				)
+			},
+			entry
+				.psnr
+				.map_or_else(String::new, |psnr| format!("{psnr:.2}")),
+			entry
+				.ssim
+				.map_or_else(String::new, |ssim| format!("{ssim:.4}")),
+			escape_html(&entry.optimization_strategy)
+		)
+		.unwrap();
+	}
+
+	html.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+
+	html
+}
+
+/// Escapes the characters of the specified text that have a special meaning in HTML.
+fn escape_html(text: &str) -> String {
+	let mut escaped_text = String::with_capacity(text.len());
+
+	for character in text.chars() {
+		match character {
+			'&' => escaped_text.push_str("&amp;"),
+			'<' => escaped_text.push_str("&lt;"),
+			'>' => escaped_text.push_str("&gt;"),
+			'"' => escaped_text.push_str("&quot;"),
+			'\'' => escaped_text.push_str("&#39;"),
+			_ => escaped_text.push(character)
+		}
+	}
+
+	escaped_text
+}
+
+/// Encodes the specified data with the standard, padded Base64 encoding.
+///
+/// References:
+/// - <https://datatracker.ietf.org/doc/html/rfc4648#section-4>
+fn base64(data: &[u8]) -> String {
+	const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+	let mut encoded_data = String::with_capacity(data.len().div_ceil(3) * 4);
+
+	for chunk in data.chunks(3) {
+		let bits = (chunk[0] as u32) << 16
+			| (chunk.get(1).copied().unwrap_or(0) as u32) << 8
+			| chunk.get(2).copied().unwrap_or(0) as u32;
+
+		for i in 0..4 {
+			if i <= chunk.len() {
+				encoded_data.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+			} else {
+				encoded_data.push('=');
+			}
+		}
+	}
+
+	encoded_data
+}
@@ -0,0 +1,142 @@
+use oxipng::{BitDepth, ColorType, Options, RawImage};
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+/// Encodes a single row of pixels with the specified RGBA colors as a PNG.
+fn pixel_row(colors: &[[u8; 4]]) -> Vec<u8> {
+	RawImage::new(
+		colors.len() as u32,
+		1,
+		ColorType::RGBA,
+		BitDepth::Eight,
+		colors.concat()
+	)
+	.expect("The test image is assumed to be valid")
+	.create_optimized_png(&Options::from_preset(0))
+	.expect("Encoding the test image is assumed not to fail")
+}
+
+#[test]
+fn textures_are_compared() {
+	let input_texture = pixel_row(&[[255, 0, 0, 255], [0, 255, 0, 0], [0, 0, 255, 255]]);
+
+	let report = TextureOptimizationReport::new(TextureOptimizationReportFormat::Json);
+	report.add_texture(
+		&RelativePath::from_inner("lossless.png"),
+		"Optimized",
+		&input_texture,
+		// Only the color of a fully transparent pixel differs, which is invisible
+		Some(&pixel_row(&[
+			[255, 0, 0, 255],
+			[0, 0, 0, 0],
+			[0, 0, 255, 255]
+		]))
+	);
+	report.add_texture(
+		&RelativePath::from_inner("lossy.png"),
+		"Optimized",
+		&input_texture,
+		Some(&pixel_row(&[
+			[250, 0, 0, 255],
+			[0, 0, 0, 0],
+			[0, 0, 250, 255]
+		]))
+	);
+	report.add_texture(
+		&RelativePath::from_inner("downscaled.png"),
+		"Optimized",
+		&input_texture,
+		Some(&pixel_row(&[[255, 0, 0, 255]]))
+	);
+	report.add_texture(
+		&RelativePath::from_inner("reused.png"),
+		"Copied from previous run",
+		&input_texture,
+		None
+	);
+
+	let mut entries = report.entries.into_inner().unwrap();
+	entries.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+	assert_eq!(
+		entries
+			.iter()
+			.map(|entry| (entry.path.as_str(), entry.lossy, entry.output_dimensions))
+			.collect::<Vec<_>>(),
+		[
+			("downscaled.png", Some(true), Some([1, 1])),
+			("lossless.png", Some(false), Some([3, 1])),
+			("lossy.png", Some(true), Some([3, 1])),
+			("reused.png", None, None)
+		]
+	);
+
+	let lossy_entry = &entries[2];
+	assert!(
+		lossy_entry.psnr.is_some_and(|psnr| psnr > 30.0),
+		"Slightly different pixels should have a high PSNR, but got {:?}",
+		lossy_entry.psnr
+	);
+	assert!(
+		lossy_entry
+			.ssim
+			.is_some_and(|ssim| ssim > 0.9 && ssim < 1.0),
+		"Slightly different pixels should have a high SSIM, but got {:?}",
+		lossy_entry.ssim
+	);
+	assert!(
+		entries
+			.iter()
+			.filter(|entry| entry.path != "lossy.png")
+			.all(|entry| entry.psnr.is_none() && entry.ssim.is_none()),
+		"Quality metrics should only be computed for lossy optimizations of the same dimensions"
+	);
+	assert!(
+		entries.iter().all(|entry| entry.thumbnails == [None, None]),
+		"Thumbnails should only be generated for HTML reports"
+	);
+}
+
+#[test]
+fn html_report_has_thumbnails() {
+	let input_texture = pixel_row(&[[255, 0, 0, 255]; 128]);
+
+	let report = TextureOptimizationReport::new(TextureOptimizationReportFormat::for_file_path(
+		Path::new("report.HTML")
+	));
+	report.add_texture(
+		&RelativePath::from_inner("<texture>.png"),
+		"Optimized",
+		&input_texture,
+		Some(&input_texture)
+	);
+
+	let entries = report.entries.into_inner().unwrap();
+	let html = html_report(&TextureOptimizationReportSummary {
+		texture_count: 1,
+		lossy_texture_count: 0,
+		total_input_size: 0,
+		total_output_size: 0,
+		textures: &entries
+	});
+
+	assert!(
+		html.contains("&lt;texture&gt;.png"),
+		"Texture paths should be escaped"
+	);
+	assert_eq!(html.matches("<img src=\"data:image/png;base64,").count(), 2);
+
+	let thumbnail = DecodedTexture::new(entries[0].thumbnails[0].as_ref().unwrap())
+		.expect("The thumbnail should be a valid PNG");
+	assert_eq!(thumbnail.dimensions(), [THUMBNAIL_SIZE as u32, 1]);
+}
+
+#[test]
+fn base64_encoding_works() {
+	assert_eq!(base64(b""), "");
+	assert_eq!(base64(b"f"), "Zg==");
+	assert_eq!(base64(b"fo"), "Zm8=");
+	assert_eq!(base64(b"foo"), "Zm9v");
+	assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+}
@@ -0,0 +1,243 @@
+//! Contains routines to find pack textures that are identical to the textures of the vanilla
+//! Minecraft client, which are dead weight because Minecraft would load the same texture if the
+//! pack did not contain them.
+
+use std::io::{self, ErrorKind, Read};
+use std::path::Path;
+
+use ahash::AHashMap;
+use flate2::read::DeflateDecoder;
+use spng::{ContextFlags, DecodeFlags, Format};
+
+use crate::RelativePath;
+use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem, read_file};
+
+#[cfg(test)]
+mod tests;
+
+/// The prefix of the paths of the vanilla textures, both in packs and in the client JAR.
+const VANILLA_TEXTURES_PATH_PREFIX: &str = "assets/minecraft/textures/";
+
+/// How a pack texture is identical to the vanilla texture at the same path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VanillaTextureMatch {
+	/// The texture files are exactly the same.
+	ByteIdentical,
+	/// The texture files are different, but they have the same dimensions and visible pixels.
+	/// The colors of completely transparent pixels are not compared, as they are invisible.
+	PixelIdentical
+}
+
+impl VanillaTextureMatch {
+	/// Returns a user-friendly description of this match.
+	pub const fn description(&self) -> &'static str {
+		match self {
+			Self::ByteIdentical => "byte-identical to the vanilla texture",
+			Self::PixelIdentical => "pixel-identical to the vanilla texture"
+		}
+	}
+}
+
+/// Finds the textures of the pack at the specified root path that are identical to the
+/// textures at the same path in the specified vanilla client JAR file, which is a ZIP file.
+/// Textures that can't be read or decoded are considered different.
+pub async fn find_vanilla_identical_textures<V: VirtualFileSystem>(
+	vfs: &V,
+	root_path: &Path,
+	iterator_traversal_options: IteratorTraversalOptions,
+	client_jar_path: &Path
+) -> Result<AHashMap<RelativePath<'static>, VanillaTextureMatch>, io::Error> {
+	let mut pack_textures = vfs
+		.file_iterator(root_path, iterator_traversal_options)
+		.flatten()
+		.filter(|VfsPackFileIterEntry { relative_path, .. }| {
+			relative_path
+				.as_str()
+				.starts_with(VANILLA_TEXTURES_PATH_PREFIX)
+				&& relative_path.as_str().ends_with(".png")
+		})
+		.map(|entry| (entry.relative_path.as_str().to_string(), entry))
+		.collect::<AHashMap<_, _>>();
+
+	let client_jar = tokio::fs::read(client_jar_path).await?;
+
+	let mut vanilla_identical_textures = AHashMap::new();
+	for jar_entry in jar_entries(&client_jar)? {
+		let jar_entry = jar_entry?;
+		let Some(VfsPackFileIterEntry {
+			relative_path,
+			file_path
+		}) = pack_textures.remove(jar_entry.name)
+		else {
+			continue;
+		};
+
+		let (Ok((pack_texture, _)), Ok(vanilla_texture)) =
+			(read_file(vfs, &file_path).await, jar_entry.data())
+		else {
+			continue;
+		};
+
+		let texture_match = if pack_texture == vanilla_texture {
+			VanillaTextureMatch::ByteIdentical
+		} else if have_same_visible_pixels(&pack_texture, &vanilla_texture) {
+			VanillaTextureMatch::PixelIdentical
+		} else {
+			continue;
+		};
+
+		vanilla_identical_textures.insert(relative_path, texture_match);
+	}
+
+	Ok(vanilla_identical_textures)
+}
+
+/// Returns whether the specified PNG files have the same dimensions and visible pixels.
+fn have_same_visible_pixels(png: &[u8], other_png: &[u8]) -> bool {
+	let decode = |png: &[u8]| {
+		// Minecraft does not apply gamma correction to textures, so neither do we
+		let mut png_reader = spng::Decoder::new(png)
+			.with_decode_flags(DecodeFlags::TRANSPARENCY)
+			.with_context_flags(ContextFlags::IGNORE_ADLER32)
+			.with_output_format(Format::Rgba8)
+			.read_info()
+			.ok()?;
+		let dimensions = (png_reader.info().width, png_reader.info().height);
+
+		let mut pixels = vec![0; png_reader.output_buffer_size()];
+		png_reader.next_frame(&mut pixels).ok()?;
+
+		Some((dimensions, pixels))
+	};
+
+	let (Some((dimensions, pixels)), Some((other_dimensions, other_pixels))) =
+		(decode(png), decode(other_png))
+	else {
+		return false;
+	};
+
+	dimensions == other_dimensions
+		&& pixels
+			.chunks_exact(4)
+			.zip(other_pixels.chunks_exact(4))
+			.all(|(pixel, other_pixel)| pixel == other_pixel || pixel[3] == 0 && other_pixel[3] == 0)
+}
+
+/// A file stored in a JAR file.
+struct JarEntry<'jar> {
+	name: &'jar str,
+	compression_method: u16,
+	compressed_data: &'jar [u8],
+	uncompressed_size: usize
+}
+
+impl JarEntry<'_> {
+	/// Returns the decompressed data of this entry. Only the stored and Deflate compression
+	/// methods are supported, as they are the only ones JAR files use.
+	fn data(&self) -> Result<Vec<u8>, io::Error> {
+		match self.compression_method {
+			0 => Ok(self.compressed_data.to_vec()),
+			8 => {
+				let mut data = Vec::with_capacity(self.uncompressed_size);
+				DeflateDecoder::new(self.compressed_data)
+					.take(self.uncompressed_size as u64)
+					.read_to_end(&mut data)?;
+
+				Ok(data)
+			}
+			_ => Err(invalid_jar_error(
+				"The vanilla client JAR uses an unsupported compression method"
+			))
+		}
+	}
+}
+
+/// Returns an iterator over the entries of the specified JAR file, read from its central
+/// directory. JAR files are ZIP files, and the ZIP features that are not used by JAR files,
+/// such as ZIP64 extensions and encryption, are not supported.
+///
+/// References:
+/// - <https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT>
+fn jar_entries(
+	jar: &[u8]
+) -> Result<impl Iterator<Item = Result<JarEntry<'_>, io::Error>>, io::Error> {
+	const END_OF_CENTRAL_DIRECTORY_SIGNATURE: [u8; 4] = [b'P', b'K', 5, 6];
+	const CENTRAL_DIRECTORY_HEADER_SIGNATURE: [u8; 4] = [b'P', b'K', 1, 2];
+	const LOCAL_FILE_HEADER_SIGNATURE: [u8; 4] = [b'P', b'K', 3, 4];
+
+	let u16_at = |offset: usize| {
+		jar.get(offset..offset + 2)
+			.map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()) as usize)
+			.ok_or_else(|| invalid_jar_error("The vanilla client JAR is truncated"))
+	};
+	let u32_at = |offset: usize| {
+		jar.get(offset..offset + 4)
+			.map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+			.ok_or_else(|| invalid_jar_error("The vanilla client JAR is truncated"))
+	};
+
+	// The end of central directory record is at the end of the file, followed by a comment
+	// of up to 65535 bytes
+	let end_of_central_directory_offset = jar
+		.windows(4)
+		.rev()
+		.take(22 + u16::MAX as usize)
+		.position(|window| window == END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+		.map(|reverse_position| jar.len() - 4 - reverse_position)
+		.ok_or_else(|| invalid_jar_error("The vanilla client JAR is not a ZIP file"))?;
+	let entry_count = u16_at(end_of_central_directory_offset + 10)?;
+	let mut central_directory_header_offset = u32_at(end_of_central_directory_offset + 16)?;
+
+	Ok((0..entry_count).map(move |_| {
+		let header_offset = central_directory_header_offset;
+		if jar.get(header_offset..header_offset + 4) != Some(&CENTRAL_DIRECTORY_HEADER_SIGNATURE[..])
+		{
+			return Err(invalid_jar_error(
+				"The vanilla client JAR has an invalid central directory"
+			));
+		}
+
+		let compression_method = u16_at(header_offset + 10)? as u16;
+		let compressed_size = u32_at(header_offset + 20)?;
+		let uncompressed_size = u32_at(header_offset + 24)?;
+		let name_length = u16_at(header_offset + 28)?;
+		let extra_field_length = u16_at(header_offset + 30)?;
+		let comment_length = u16_at(header_offset + 32)?;
+		let local_file_header_offset = u32_at(header_offset + 42)?;
+		let name = jar
+			.get(header_offset + 46..header_offset + 46 + name_length)
+			.and_then(|name| std::str::from_utf8(name).ok())
+			.ok_or_else(|| invalid_jar_error("The vanilla client JAR has an invalid file name"))?;
+
+		central_directory_header_offset =
+			header_offset + 46 + name_length + extra_field_length + comment_length;
+
+		if jar.get(local_file_header_offset..local_file_header_offset + 4)
+			!= Some(&LOCAL_FILE_HEADER_SIGNATURE[..])
+		{
+			return Err(invalid_jar_error(
+				"The vanilla client JAR has an invalid local file header"
+			));
+		}
+
+		// The local file header extra field may be different from the central directory one
+		let data_offset = local_file_header_offset
+			+ 30 + u16_at(local_file_header_offset + 26)?
+			+ u16_at(local_file_header_offset + 28)?;
+		let compressed_data = jar
+			.get(data_offset..data_offset + compressed_size)
+			.ok_or_else(|| invalid_jar_error("The vanilla client JAR is truncated"))?;
+
+		Ok(JarEntry {
+			name,
+			compression_method,
+			compressed_data,
+			uncompressed_size
+		})
+	}))
+}
+
+/// Creates an I/O error that signals that the vanilla client JAR is invalid.
+fn invalid_jar_error(message: &'static str) -> io::Error {
+	io::Error::new(ErrorKind::InvalidData, message)
+}
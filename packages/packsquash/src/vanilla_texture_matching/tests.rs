@@ -0,0 +1,140 @@
+use std::fs;
+
+use oxipng::{BitDepth, ColorType, Options, RawImage};
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use crate::vfs::os_fs::OsFilesystem;
+
+use super::*;
+
+/// Encodes a single row of pixels with the specified RGBA colors as a PNG.
+fn pixel_row(colors: &[[u8; 4]]) -> Vec<u8> {
+	RawImage::new(
+		colors.len() as u32,
+		1,
+		ColorType::RGBA,
+		BitDepth::Eight,
+		colors.concat()
+	)
+	.expect("The test image is assumed to be valid")
+	.create_optimized_png(&Options::from_preset(0))
+	.expect("Encoding the test image is assumed not to fail")
+}
+
+/// Creates a JAR file that stores the specified files without compression.
+fn stored_jar(files: &[(&str, &[u8])]) -> Vec<u8> {
+	let mut jar = Vec::new();
+	let mut central_directory = Vec::new();
+
+	for (name, data) in files {
+		let local_file_header_offset = jar.len() as u32;
+		let crc = crc32fast::hash(data);
+
+		jar.extend_from_slice(b"PK\x03\x04");
+		jar.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+		jar.extend_from_slice(&crc.to_le_bytes());
+		jar.extend_from_slice(&(data.len() as u32).to_le_bytes());
+		jar.extend_from_slice(&(data.len() as u32).to_le_bytes());
+		jar.extend_from_slice(&(name.len() as u16).to_le_bytes());
+		jar.extend_from_slice(&[0, 0]);
+		jar.extend_from_slice(name.as_bytes());
+		jar.extend_from_slice(data);
+
+		central_directory.extend_from_slice(b"PK\x01\x02");
+		central_directory.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+		central_directory.extend_from_slice(&crc.to_le_bytes());
+		central_directory.extend_from_slice(&(data.len() as u32).to_le_bytes());
+		central_directory.extend_from_slice(&(data.len() as u32).to_le_bytes());
+		central_directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+		central_directory.extend_from_slice(&[0; 12]);
+		central_directory.extend_from_slice(&local_file_header_offset.to_le_bytes());
+		central_directory.extend_from_slice(name.as_bytes());
+	}
+
+	let central_directory_offset = jar.len() as u32;
+	jar.extend_from_slice(&central_directory);
+
+	jar.extend_from_slice(b"PK\x05\x06");
+	jar.extend_from_slice(&[0, 0, 0, 0]);
+	jar.extend_from_slice(&(files.len() as u16).to_le_bytes());
+	jar.extend_from_slice(&(files.len() as u16).to_le_bytes());
+	jar.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+	jar.extend_from_slice(&central_directory_offset.to_le_bytes());
+	jar.extend_from_slice(&[0, 0]);
+
+	jar
+}
+
+#[test]
+fn vanilla_identical_textures_are_found() {
+	let vanilla_texture = pixel_row(&[[255, 0, 0, 255], [0, 0, 0, 0]]);
+	let vanilla_jar = stored_jar(&[
+		("assets/minecraft/textures/block/same.png", &vanilla_texture),
+		(
+			"assets/minecraft/textures/block/recolored.png",
+			&vanilla_texture
+		),
+		(
+			"assets/minecraft/textures/block/different.png",
+			&vanilla_texture
+		),
+		("assets/minecraft/lang/en_us.json", b"{}")
+	]);
+
+	let root_dir = Builder::new()
+		.prefix("ps-vanilla-texture-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	let jar_path = root_dir.path().join("client.jar");
+	let pack_dir = root_dir.path().join("pack");
+	let textures_dir = pack_dir.join("assets/minecraft/textures/block");
+	fs::create_dir_all(&textures_dir).expect("I/O operations are assumed not to fail during tests");
+	fs::write(&jar_path, vanilla_jar).expect("I/O operations are assumed not to fail during tests");
+	fs::write(textures_dir.join("same.png"), &vanilla_texture)
+		.expect("I/O operations are assumed not to fail during tests");
+	// Only the color of a fully transparent pixel differs, which is invisible
+	fs::write(
+		textures_dir.join("recolored.png"),
+		pixel_row(&[[255, 0, 0, 255], [0, 255, 0, 0]])
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+	fs::write(
+		textures_dir.join("different.png"),
+		pixel_row(&[[255, 0, 0, 255], [0, 255, 0, 255]])
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+	fs::write(textures_dir.join("new.png"), &vanilla_texture)
+		.expect("I/O operations are assumed not to fail during tests");
+
+	let mut vanilla_identical_textures = tokio_test::block_on(find_vanilla_identical_textures(
+		&OsFilesystem,
+		&pack_dir,
+		IteratorTraversalOptions::default(),
+		&jar_path
+	))
+	.expect("The vanilla client JAR should be valid")
+	.into_iter()
+	.map(|(relative_path, texture_match)| (relative_path.as_str().to_string(), texture_match))
+	.collect::<Vec<_>>();
+	vanilla_identical_textures.sort_unstable_by(|(path, _), (other_path, _)| path.cmp(other_path));
+
+	assert_eq!(
+		vanilla_identical_textures,
+		[
+			(
+				"assets/minecraft/textures/block/recolored.png".to_string(),
+				VanillaTextureMatch::PixelIdentical
+			),
+			(
+				"assets/minecraft/textures/block/same.png".to_string(),
+				VanillaTextureMatch::ByteIdentical
+			)
+		]
+	);
+}
+
+#[test]
+fn invalid_jar_is_rejected() {
+	assert!(jar_entries(b"not a JAR file").is_err());
+}
@@ -1,11 +1,14 @@
 //! Contains virtual file systems implementations to use with `PackSquasher`.
 
+use std::io::Cursor;
 use std::path::PathBuf;
 use std::time::SystemTime;
 use std::{fs::FileType, io, path::Path};
 
 use crate::RelativePath;
-use tokio::io::AsyncRead;
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::either::Either;
 
 pub mod os_fs;
 
@@ -45,7 +48,7 @@ pub trait VirtualFileSystem: Send + Sync {
 /// Contains options that tweak the operation of the [`VirtualFileSystem::file_iterator`]
 /// method.
 #[non_exhaustive]
-#[derive(Default)]
+#[derive(Default, Clone, Copy)]
 pub struct IteratorTraversalOptions {
 	/// Whether system (i.e. clearly not part of a pack file) and hidden files
 	/// (usually, those whose name begins with a dot) are yielded or not.
@@ -80,3 +83,53 @@ pub struct VfsPackFileMetadata {
 	/// time to the creation time.
 	pub modification_time: Option<SystemTime>
 }
+
+/// Reads the whole file at the specified path of a virtual file system, allocating a buffer
+/// as big as its size hint upfront. Its modification time is returned along with its data.
+pub(crate) async fn read_file<V: VirtualFileSystem>(
+	vfs: &V,
+	path: impl AsRef<Path>
+) -> Result<(Vec<u8>, Option<SystemTime>), io::Error> {
+	let mut file = vfs.open(path)?;
+	let mut data = Vec::with_capacity(file.file_size_hint.try_into().unwrap_or(0));
+	file.file_read.read_to_end(&mut data).await?;
+
+	Ok((data, file.metadata.modification_time))
+}
+
+/// Reads the whole file at the specified path of a virtual file system as UTF-8 text,
+/// allocating a buffer as big as its size hint upfront.
+pub(crate) async fn read_file_to_string<V: VirtualFileSystem>(
+	vfs: &V,
+	path: impl AsRef<Path>
+) -> Result<String, io::Error> {
+	let mut file = vfs.open(path)?;
+	let mut text = String::with_capacity(file.file_size_hint.try_into().unwrap_or(0));
+	file.file_read.read_to_string(&mut text).await?;
+
+	Ok(text)
+}
+
+/// Opens a file for a virtual file system adapter that generates or replaces some files of the
+/// virtual file system it wraps, keeping their data in memory. If in-memory data and its
+/// modification time are specified, the file is read from them. Otherwise, the file at the
+/// specified path of the wrapped virtual file system is opened.
+pub(crate) fn open_in_memory_or_inner<V: VirtualFileSystem>(
+	inner: &V,
+	path: impl AsRef<Path>,
+	in_memory_file: Option<(&Bytes, Option<SystemTime>)>
+) -> Result<VfsFile<Either<V::FileRead, Cursor<Bytes>>>, io::Error> {
+	if let Some((data, modification_time)) = in_memory_file {
+		return Ok(VfsFile {
+			file_read: Either::Right(Cursor::new(data.clone())),
+			file_size_hint: data.len() as u64,
+			metadata: VfsPackFileMetadata { modification_time }
+		});
+	}
+
+	inner.open(path).map(|file| VfsFile {
+		file_read: Either::Left(file.file_read),
+		file_size_hint: file.file_size_hint,
+		metadata: file.metadata
+	})
+}
@@ -0,0 +1,31 @@
+//! Contains routines to relate the files of a Minecraft world folder, as packaged by the world
+//! template packaging mode, to the pack files they correspond to.
+
+use std::borrow::Cow;
+
+use crate::RelativePath;
+
+#[cfg(test)]
+mod tests;
+
+/// The folder of a world that contains its data packs, one per subfolder or ZIP file.
+const DATA_PACKS_FOLDER: &str = "datapacks/";
+
+/// Returns the path that the specified file of a world folder has within the pack it belongs
+/// to, so that the asset types of the files of uncompressed data packs in the `datapacks`
+/// folder can be identified. The files of that folder that belong to no data pack, and the
+/// rest of the world files, keep their path.
+pub fn pack_relative_path<'path>(
+	world_relative_path: &'path RelativePath<'_>
+) -> RelativePath<'path> {
+	let world_relative_path = world_relative_path.as_str();
+
+	RelativePath::from_inner(Cow::Borrowed(
+		world_relative_path
+			.strip_prefix(DATA_PACKS_FOLDER)
+			.and_then(|data_pack_relative_path| data_pack_relative_path.split_once('/'))
+			.map_or(world_relative_path, |(_, pack_relative_path)| {
+				pack_relative_path
+			})
+	))
+}
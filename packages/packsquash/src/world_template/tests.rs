@@ -0,0 +1,22 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+#[test]
+fn pack_relative_paths_are_computed() {
+	for (world_relative_path, expected_pack_relative_path) in [
+		(
+			"datapacks/arena/data/arena/function/start.mcfunction",
+			"data/arena/function/start.mcfunction"
+		),
+		("datapacks/arena/pack.mcmeta", "pack.mcmeta"),
+		("datapacks/arena.zip", "datapacks/arena.zip"),
+		("region/r.0.0.mca", "region/r.0.0.mca"),
+		("level.dat", "level.dat")
+	] {
+		assert_eq!(
+			pack_relative_path(&RelativePath::from_inner(world_relative_path)).as_str(),
+			expected_pack_relative_path
+		);
+	}
+}
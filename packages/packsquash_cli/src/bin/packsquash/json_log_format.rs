@@ -0,0 +1,84 @@
+//! Contains routines to format log records as JSON objects, one per line, so that they can be
+//! parsed by other programs, such as CI systems and dashboards.
+
+use std::io::{self, Write};
+
+use log::{
+	Record,
+	kv::{self, Key, Value, VisitSource, VisitValue}
+};
+use serde_json::{Map, Number};
+
+/// Writes the specified log record to the specified writer as a single line JSON object. The
+/// object has `level` and `message` fields, and a field for each key-value pair of the record,
+/// such as the path and processing time of pack files.
+pub fn write_json_log_record(writer: &mut impl Write, record: &Record<'_>) -> io::Result<()> {
+	let mut fields = Map::new();
+	fields.insert("level".into(), record.level().as_str().into());
+	fields.insert("message".into(), record.args().to_string().into());
+
+	// The key-value pairs of our records always can be visited, so errors are not possible
+	record
+		.key_values()
+		.visit(&mut JsonFieldCollector(&mut fields))
+		.ok();
+
+	serde_json::to_writer(&mut *writer, &fields)?;
+	writeln!(writer)
+}
+
+/// Adds the key-value pairs of a log record to a JSON object.
+struct JsonFieldCollector<'fields>(&'fields mut Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFieldCollector<'_> {
+	fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+		let mut json_value = serde_json::Value::Null;
+		value.visit(JsonValueConverter(&mut json_value))?;
+
+		self.0.insert(key.to_string(), json_value);
+
+		Ok(())
+	}
+}
+
+/// Converts a value of a key-value pair of a log record to a JSON value, keeping numbers,
+/// booleans and missing values as such, and formatting any other value as a string.
+struct JsonValueConverter<'value>(&'value mut serde_json::Value);
+
+impl<'v> VisitValue<'v> for JsonValueConverter<'_> {
+	fn visit_any(&mut self, value: Value<'_>) -> Result<(), kv::Error> {
+		*self.0 = value.to_string().into();
+		Ok(())
+	}
+
+	fn visit_null(&mut self) -> Result<(), kv::Error> {
+		*self.0 = serde_json::Value::Null;
+		Ok(())
+	}
+
+	fn visit_u64(&mut self, value: u64) -> Result<(), kv::Error> {
+		*self.0 = value.into();
+		Ok(())
+	}
+
+	fn visit_i64(&mut self, value: i64) -> Result<(), kv::Error> {
+		*self.0 = value.into();
+		Ok(())
+	}
+
+	fn visit_f64(&mut self, value: f64) -> Result<(), kv::Error> {
+		// JSON has no representation for infinite and NaN numbers
+		*self.0 = Number::from_f64(value).map_or(serde_json::Value::Null, Into::into);
+		Ok(())
+	}
+
+	fn visit_bool(&mut self, value: bool) -> Result<(), kv::Error> {
+		*self.0 = value.into();
+		Ok(())
+	}
+
+	fn visit_str(&mut self, value: &str) -> Result<(), kv::Error> {
+		*self.0 = value.into();
+		Ok(())
+	}
+}
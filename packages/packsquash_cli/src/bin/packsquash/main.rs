@@ -5,22 +5,42 @@ use std::{
 	borrow::Cow,
 	env, fs,
 	io::{self, IsTerminal, Read, Stderr},
+	iter,
+	path::{Path, PathBuf},
 	process,
-	time::{Duration, Instant}
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH}
 };
 
 use env_logger::{Builder, Target, WriteStyle};
 use getopts::{Options, ParsingStyle};
-use log::{Level, LevelFilter, debug, error, info, trace, warn};
+use json_log_format::write_json_log_record;
+use log::{Level, LevelFilter, debug, error, info, log, warn};
+use options_file::{
+	environment_option_overrides, merged_pack_directories_override, parse_option_override,
+	parse_options_file, rewrite_migrated_options
+};
+use pack_watcher::PackWatcher;
 use packsquash::{
-	PackSquasher, PackSquasherError, PackSquasherStatus, PackSquasherWarning, config::SquashOptions,
+	PackDiffError, PackFileChange, PackSquasher, PackSquasherError, PackSquasherStatus,
+	PackSquasherWarning,
+	config::{SquashOptions, ZipSpecConformanceLevel, lint_options, options_json_schema},
+	diff_packs, expand_output_file_path, output_file_path_variables,
 	vfs::os_fs::OsFilesystem
 };
+use progress_display::ProgressDisplay;
+use remote_options_file::{download_remote_options_file, is_remote_options_file};
 use terminal_style::{environment_allows_color, environment_allows_emoji};
 use terminal_title_controller::TerminalTitleController;
 use tokio::{runtime, select, sync::mpsc::channel, time::sleep};
+use toml::Table;
 use tz::UtcDateTime;
 
+mod json_log_format;
+mod options_file;
+mod options_file_wizard;
+mod pack_watcher;
+mod progress_display;
+mod remote_options_file;
 mod terminal_style;
 mod terminal_title_controller;
 mod terminal_title_setter;
@@ -30,6 +50,15 @@ const LOG_TARGET: Target = Target::Stderr;
 /// A producer of the [`IsTerminal`] implementation that matches the [`LOG_TARGET`] constant.
 const LOG_TARGET_STREAM: fn() -> Stderr = io::stderr;
 
+/// The format of the status messages shown to the user.
+#[derive(Clone, Copy)]
+enum LogFormat {
+	/// Messages meant to be read by humans, with optional emojis and colors.
+	Human,
+	/// Messages formatted as JSON objects, one per line, meant to be parsed by other programs.
+	Json
+}
+
 /// Zopfli compression is pretty intensive on the memory allocator, and musl
 /// is known to use a simple but prone to extreme thread contention allocator.
 /// Replace its allocator with one whose performance is close to glibc so that
@@ -83,6 +112,104 @@ fn run(title_controller: Option<TerminalTitleController>) -> i32 {
 			"Always disable color in messages. \
 			This is equivalent to defining the NO_COLOR environment variable, or setting PACKSQUASH_COLOR or COLOR to something else than \"show\""
 		)
+		.optmulti(
+			"D",
+			"define",
+			"Defines a variable that replaces ${NAME} placeholders in JSON, language and text pack files. \
+			This takes precedence over PACKSQUASH_VAR_NAME environment variables, which take precedence over the template_variables option",
+			"NAME=VALUE"
+		)
+		.optflag(
+			"",
+			"check",
+			"Reads the options file and checks that it is valid, reporting any unknown options, and exits without processing the pack"
+		)
+		.optflag(
+			"",
+			"dry-run",
+			"Reads the options file and prints which asset type, file options table and output path each pack file would get, and which pack files would be skipped, without processing the pack"
+		)
+		.optflag(
+			"",
+			"watch",
+			"Keeps running after processing the pack, watching the pack directory for changes and processing the pack again when its files change, until interrupted. \
+			Unless the ZIP specification conformance level is pedantic, only the changed files are processed again"
+		)
+		.optflag(
+			"",
+			"progress",
+			"Shows an interactive display with the progress of the pack processing, the estimated time remaining and the pack files that are taking the longest to process, \
+			instead of a message for each processed pack file. This has no effect if status messages are not shown in a terminal or the json log format is used"
+		)
+		.optflag(
+			"",
+			"migrate",
+			"Rewrites the options file in place to replace the deprecated options it contains with their current equivalents, preserving its formatting and comments"
+		)
+		.optflag(
+			"",
+			"init",
+			"Inspects the pack in the directory at the specified path, or the working directory if no path is specified, asks some questions about it, and prints an options file tuned for it, then exits"
+		)
+		.optflag(
+			"",
+			"diff",
+			"Compares the two pack builds at the specified paths, which may be ZIP files generated by PackSquash or pack directories, \
+			prints the files that were added, removed or changed from the first to the second, with their size differences and SHA-256 hashes, and exits"
+		)
+		.optflag(
+			"",
+			"options-schema",
+			"Prints a JSON Schema that describes the options accepted in options files, which editors can use to complete and validate them, and exits"
+		)
+		.optmulti(
+			"s",
+			"set",
+			"Overrides an option of the options file, after applying the selected profile. KEY is a TOML key, which may be dotted to set options of tables, and VALUE a TOML value, or else a string. \
+			This takes precedence over PACKSQUASH_OPT__KEY environment variables, whose KEY separates keys with double underscores",
+			"KEY=VALUE"
+		)
+		.optmulti(
+			"",
+			"merge",
+			"Merges the files of the pack in the specified directory with the files of the pack to process, as if they were a single pack. \
+			This option may be repeated to merge several packs, and overrides the merged_pack_directories option",
+			"DIRECTORY"
+		)
+		.optopt(
+			"",
+			"options",
+			"Reads the options file from the specified path or HTTPS URL, instead of the first free argument. \
+			HTTPS URLs must pin the SHA-256 hash of the options file with a #sha256=HASH fragment, and the options file is rejected if its hash differs",
+			"LOCATION"
+		)
+		.optopt(
+			"",
+			"log-format",
+			"Sets the format of the status messages. The human format is meant to be read by people, while the json format prints a JSON object per line, \
+			with fields such as the path, processing time and sizes of each pack file, meant to be parsed by CI systems and other programs. The default format is human",
+			"FORMAT"
+		)
+		.optopt(
+			"",
+			"log-level",
+			"Only shows status messages of the specified level or more severe ones. The available levels are off, error, warn, info, debug and trace, the default one. \
+			This takes precedence over the PACKSQUASH_LOG and RUST_LOG environment variables",
+			"LEVEL"
+		)
+		.optopt(
+			"",
+			"preset",
+			"Uses the options of the specified built-in preset as the base options, which the options file overrides. \
+			The available presets are maximum, balanced, fast and lossless",
+			"NAME"
+		)
+		.optopt(
+			"p",
+			"profile",
+			"Applies the options of the specified profile, defined in a [profile.NAME] table of the options file or any options file it extends, over the rest of the options",
+			"NAME"
+		)
 		.parsing_style(ParsingStyle::StopAtFirstFree);
 
 	match options.parse(env::args().skip(1)) {
@@ -101,6 +228,60 @@ fn run(title_controller: Option<TerminalTitleController>) -> i32 {
 			} else if option_matches.opt_present("v") {
 				print_version_information(true);
 
+				0
+			} else if option_matches.opt_present("init") {
+				let pack_directory = option_matches.free.first().map_or(".", |path| path);
+
+				match options_file_wizard::generate_options_file(pack_directory) {
+					Ok(()) => 0,
+					Err(err) => {
+						init_logger(
+							enable_emoji_default,
+							enable_color_default,
+							LogFormat::Human,
+							None
+						);
+
+						error!("Couldn't generate an options file for {pack_directory}: {err}");
+
+						2
+					}
+				}
+			} else if option_matches.opt_present("diff") {
+				let [old_pack_path, new_pack_path] = option_matches.free.as_slice() else {
+					init_logger(
+						enable_emoji_default,
+						enable_color_default,
+						LogFormat::Human,
+						None
+					);
+
+					error!(
+						"The paths of the two pack builds to compare must be specified\nRun {} -h to see command line argument help",
+						env!("CARGO_BIN_NAME")
+					);
+
+					return 1;
+				};
+
+				match print_pack_diff(Path::new(old_pack_path), Path::new(new_pack_path)) {
+					Ok(()) => 0,
+					Err(err) => {
+						init_logger(
+							enable_emoji_default,
+							enable_color_default,
+							LogFormat::Human,
+							None
+						);
+
+						error!("Couldn't compare the pack builds: {err}");
+
+						2
+					}
+				}
+			} else if option_matches.opt_present("options-schema") {
+				println!("{:#}", options_json_schema());
+
 				0
 			} else {
 				let enable_emoji = if enable_emoji_default {
@@ -115,21 +296,119 @@ fn run(title_controller: Option<TerminalTitleController>) -> i32 {
 					option_matches.opt_present("color")
 				};
 
-				init_logger(enable_emoji, enable_color);
+				let log_format = match option_matches.opt_str("log-format").as_deref() {
+					None | Some("human") => Ok(LogFormat::Human),
+					Some("json") => Ok(LogFormat::Json),
+					Some(log_format) => Err(format!("Unknown log format: {log_format}"))
+				};
+
+				let log_level = option_matches
+					.opt_str("log-level")
+					.map(|log_level| {
+						log_level
+							.parse::<LevelFilter>()
+							.map_err(|_| format!("Unknown log level: {log_level}"))
+					})
+					.transpose();
+
+				let (log_format, log_level) = match (log_format, log_level) {
+					(Ok(log_format), Ok(log_level)) => (log_format, log_level),
+					(Err(err), _) | (_, Err(err)) => {
+						init_logger(
+							enable_emoji_default,
+							enable_color_default,
+							LogFormat::Human,
+							None
+						);
+
+						error!(
+							"{err}\nRun {} -h to see command line argument help",
+							env!("CARGO_BIN_NAME")
+						);
+
+						return 1;
+					}
+				};
+
+				init_logger(enable_emoji, enable_color, log_format, log_level);
+
+				let show_progress = option_matches.opt_present("progress")
+					&& log_target_is_tty
+					&& matches!(log_format, LogFormat::Human);
+
+				let Some(command_line_variables) = option_matches
+					.opt_strs("D")
+					.into_iter()
+					.map(|definition| {
+						definition
+							.split_once('=')
+							.map(|(name, value)| (name.to_string(), value.to_string()))
+					})
+					.collect::<Option<Vec<_>>>()
+				else {
+					error!(
+						"Variable definitions must have the NAME=VALUE syntax\nRun {} -h to see command line argument help",
+						env!("CARGO_BIN_NAME")
+					);
+
+					return 1;
+				};
+
+				// Options overridden in the command line take precedence over the ones
+				// overridden in the environment
+				let Some(option_overrides) = option_matches
+					.opt_strs("s")
+					.into_iter()
+					.map(|definition| parse_option_override(&definition))
+					.collect::<Option<Vec<_>>>()
+					.map(|command_line_overrides| {
+						let mut option_overrides = environment_option_overrides();
+						option_overrides.extend(command_line_overrides);
+						option_overrides.extend(merged_pack_directories_override(
+							option_matches.opt_strs("merge")
+						));
+						option_overrides
+					})
+				else {
+					error!(
+						"Option overrides must have the KEY=VALUE syntax, where KEY is a valid TOML key\nRun {} -h to see command line argument help",
+						env!("CARGO_BIN_NAME")
+					);
+
+					return 1;
+				};
 
 				print_version_information(false);
 				println!();
 				read_options_file_and_squash(
-					option_matches.free.first().filter(|path| {
-						// Let "-" behave as if no path was provided
-						path != &"-"
-					}),
+					option_matches
+						.opt_str("options")
+						.or_else(|| option_matches.free.first().cloned())
+						.filter(|path| {
+							// Let "-" behave as if no path was provided
+							path != "-"
+						})
+						.as_ref(),
+					option_matches.opt_str("preset").as_deref(),
+					option_matches.opt_str("p").as_deref(),
+					option_matches.opt_present("check"),
+					option_matches.opt_present("dry-run"),
+					option_matches.opt_present("watch"),
+					show_progress,
+					option_matches.opt_present("migrate"),
+					option_overrides,
+					command_line_variables,
 					title_controller
 				)
 			}
 		}
 		Err(parse_err) => {
-			init_logger(enable_emoji_default, enable_color_default);
+			init_logger(
+				enable_emoji_default,
+				enable_color_default,
+				LogFormat::Human,
+				None
+			);
 
 			error!(
 				"{}\nRun {} -h to see command line argument help",
@@ -143,9 +422,25 @@ fn run(title_controller: Option<TerminalTitleController>) -> i32 {
 }
 
 /// Reads an options file and launches a squash operation to optimize it with the
-/// read options.
+/// read options, resolving the options files it extends and applying the specified
+/// preset, profile and option overrides, and adding the template variables defined in the
+/// command line and the environment to them. Deprecated options are rewritten in the
+/// options file if requested. If only checking the options file, no squash operation
+/// is launched, and if doing a dry run, the squash operation is only planned. If watching,
+/// the pack is squashed again whenever its files change. The progress of squash operations
+/// is shown in an interactive display if requested.
+#[allow(clippy::too_many_arguments)] // Alternatives are not really more readable
 fn read_options_file_and_squash(
 	options_file_path: Option<&String>,
+	preset: Option<&str>,
+	profile: Option<&str>,
+	only_check: bool,
+	dry_run: bool,
+	watch: bool,
+	show_progress: bool,
+	rewrite_deprecated_options: bool,
+	option_overrides: Vec<Table>,
+	command_line_variables: Vec<(String, String)>,
 	title_controller: Option<TerminalTitleController>
 ) -> i32 {
 	let user_friendly_options_path =
@@ -166,13 +461,17 @@ fn read_options_file_and_squash(
 	);
 
 	// Read the TOML configuration data from the specified source
-	let options_string = match if let Some(path) = options_file_path {
-		fs::read_to_string(path)
-	} else {
-		let mut buf = String::new();
-		match io::stdin().read_to_string(&mut buf) {
-			Ok(_) => Ok(buf),
-			Err(err) => Err(err)
+	let options_string = match match options_file_path {
+		Some(path) if is_remote_options_file(path) => {
+			download_remote_options_file(path).map_err(|err| err.to_string())
+		}
+		Some(path) => fs::read_to_string(path).map_err(|err| err.to_string()),
+		None => {
+			let mut buf = String::new();
+			match io::stdin().read_to_string(&mut buf) {
+				Ok(_) => Ok(buf),
+				Err(err) => Err(err.to_string())
+			}
 		}
 	} {
 		Ok(options_string) => options_string,
@@ -183,14 +482,133 @@ fn read_options_file_and_squash(
 		}
 	};
 
-	// Deserialize the options struct contained in the string
-	let squash_options = match toml::from_str::<SquashOptions>(&options_string) {
-		Ok(squash_options) => squash_options,
+	// Deserialize the options struct contained in the string. Extended options files are
+	// relative to the directory of the options file, or the working directory for the
+	// standard input and remote options files
+	let (mut squash_options, option_migrations, options_file_warnings) = match parse_options_file(
+		&options_string,
+		options_file_path
+			.filter(|path| !is_remote_options_file(path))
+			.and_then(|path| Path::new(path).parent())
+			.unwrap_or(Path::new("")),
+		preset,
+		profile,
+		option_overrides
+	) {
+		Ok(parsed_options) => parsed_options,
 		Err(deserialize_error) => {
 			error!(
 				"An error occurred while parsing the options file from {user_friendly_options_path}: {deserialize_error}"
 			);
 
+			return if deserialize_error.is_io_error() {
+				2
+			} else {
+				3
+			};
+		}
+	};
+
+	for option_migration in &option_migrations {
+		warn!("{option_migration}");
+	}
+
+	for options_file_warning in &options_file_warnings {
+		warn!("{options_file_warning}");
+	}
+
+	if rewrite_deprecated_options {
+		if let Some(path) = options_file_path
+			&& !is_remote_options_file(path)
+		{
+			match rewrite_migrated_options(Path::new(path), &option_migrations) {
+				Ok(migrated_option_count) => {
+					info!("Rewrote {migrated_option_count} deprecated options in {path}");
+				}
+				Err(err) => {
+					error!("Couldn't rewrite the deprecated options in {path}: {err}");
+
+					return 2;
+				}
+			}
+		} else {
+			warn!("Options read from the standard input or a URL can't be rewritten");
+		}
+	}
+
+	// Valid options may still not do what the user meant, so point out those that are
+	// contradictory or have no effect before doing any work
+	for options_lint in lint_options(&squash_options) {
+		warn!("{options_lint}");
+	}
+
+	if only_check {
+		info!("The options file is valid");
+
+		return 0;
+	}
+
+	if dry_run {
+		return print_pack_file_plan(squash_options);
+	}
+
+	// Variables defined in the command line take precedence over environment variables, which
+	// take precedence over the options file. The build date is available when any variable is
+	// defined, so that pack files are not scanned for placeholders when none will be expanded
+	let template_variables = &mut squash_options.global_options.template_variables;
+	template_variables.extend(
+		env::vars_os()
+			.filter_map(|(name, value)| {
+				Some((
+					name.to_str()?.strip_prefix("PACKSQUASH_VAR_")?.to_string(),
+					value.into_string().ok()?
+				))
+			})
+			.chain(command_line_variables)
+	);
+	let build_date = template_variables.get("build_date").cloned().or_else(|| {
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+		let now = UtcDateTime::from_timespec(now.as_secs().try_into().unwrap_or(i64::MAX), 0).ok()?;
+
+		Some(format!(
+			"{:04}-{:02}-{:02}",
+			now.year(),
+			now.month(),
+			now.month_day()
+		))
+	});
+	if !template_variables.is_empty()
+		&& !template_variables.contains_key("build_date")
+		&& let Some(build_date) = &build_date
+	{
+		template_variables.insert("build_date".into(), build_date.clone());
+	}
+
+	// Output file paths may be templates of build metadata and the variables defined above,
+	// which take precedence. The build date is always available as a shorter date variable
+	let mut path_template_variables = output_file_path_variables(
+		&squash_options.pack_directory,
+		&squash_options.global_options.output_file_path
+	);
+	path_template_variables.extend(
+		template_variables
+			.iter()
+			.map(|(name, value)| (name.clone(), value.clone()))
+	);
+	if !path_template_variables.contains_key("date")
+		&& let Some(build_date) = build_date
+	{
+		path_template_variables.insert("date".into(), build_date);
+	}
+
+	squash_options.global_options.output_file_path = match expand_output_file_path(
+		&squash_options.global_options.output_file_path,
+		&path_template_variables
+	) {
+		Ok(output_file_path) => output_file_path,
+		Err(err) => {
+			error!("Invalid output file path template: {err}");
+
 			return 3;
 		}
 	};
@@ -200,7 +618,116 @@ fn read_options_file_and_squash(
 	let output_file_path = squash_options.global_options.output_file_path.clone();
 	let start_instant = Instant::now();
 
-	squash(squash_options, title_controller).map_or_else(
+	if !watch {
+		return report_squash_result(
+			squash(squash_options, show_progress, title_controller),
+			&output_file_path,
+			start_instant
+		);
+	}
+
+	report_squash_result(
+		squash(squash_options.clone(), show_progress, title_controller),
+		&output_file_path,
+		start_instant
+	);
+
+	watch_and_squash(squash_options, show_progress)
+}
+
+/// Watches the directories of the pack to process and the packs merged with it for changes,
+/// squashing the pack again after each batch of changes until the process is terminated.
+/// Unless the ZIP specification conformance level is pedantic, the previously generated ZIP
+/// file is reused, so only the changed pack files are processed again.
+fn watch_and_squash(squash_options: SquashOptions, show_progress: bool) -> i32 {
+	let global_options = &squash_options.global_options;
+
+	// Generated files may be within the watched directories, and changes to them should not
+	// trigger another squash operation
+	let pack_watcher = match PackWatcher::new(
+		iter::once(squash_options.pack_directory.as_path()).chain(
+			global_options
+				.merged_pack_directories
+				.iter()
+				.map(PathBuf::as_path)
+		),
+		[
+			Some(&global_options.output_file_path),
+			global_options.zip_statistics_file_path.as_ref(),
+			global_options
+				.texture_optimization_report_file_path
+				.as_ref(),
+			global_options.audio_preview_directory.as_ref(),
+			global_options.audio_diagnostics_report_file_path.as_ref(),
+			global_options.run_manifest_file_path.as_ref()
+		]
+		.into_iter()
+		.flatten()
+		.chain(global_options.split_output_groups.keys())
+		.map(PathBuf::as_path)
+	) {
+		Ok(pack_watcher) => pack_watcher,
+		Err(err) => {
+			error!("Couldn't watch the pack for changes: {err}");
+
+			return 2;
+		}
+	};
+
+	if matches!(
+		global_options.zip_spec_conformance_level,
+		ZipSpecConformanceLevel::Pedantic
+	) {
+		warn!(
+			"The pedantic ZIP specification conformance level does not allow reusing the \
+			generated ZIP file, so every pack file will be processed again after each change. \
+			Use another zip_spec_conformance_level to only process the changed pack files"
+		);
+	}
+
+	loop {
+		info!("Watching the pack for changes. Press Ctrl+C to stop");
+
+		match pack_watcher.wait_for_changes() {
+			Ok(changed_paths) => {
+				for changed_path in &changed_paths {
+					debug!("{} changed", changed_path.display());
+				}
+
+				info!(
+					"{} changed files detected. Processing pack...",
+					changed_paths.len()
+				);
+			}
+			Err(err) => {
+				error!("Couldn't watch the pack for changes: {err}");
+
+				return 2;
+			}
+		}
+
+		let start_instant = Instant::now();
+
+		report_squash_result(
+			squash(
+				squash_options.clone(),
+				show_progress,
+				TerminalTitleController::new()
+			),
+			&squash_options.global_options.output_file_path,
+			start_instant
+		);
+	}
+}
+
+/// Reports the result of a squash operation started at the specified instant, which generated
+/// the specified output file, to the user, returning the exit code that corresponds to it.
+fn report_squash_result(
+	squash_result: Result<Option<(u64, u64)>, PackSquasherError>,
+	output_file_path: &Path,
+	start_instant: Instant
+) -> i32 {
+	squash_result.map_or_else(
 		|err| {
 			error!(
 				"Pack processing error: {}{}\n\
@@ -222,15 +749,26 @@ fn read_options_file_and_squash(
 		},
 		|file_counts| {
 			let process_time = start_instant.elapsed();
+			let output_file_size = output_file_path
+				.metadata()
+				.ok()
+				.map(|metadata| metadata.len());
 
 			debug!(
+				event = "pack_processed",
+				output_file_path:% = output_file_path.display(),
+				output_file_size = output_file_size,
+				file_count = file_counts.map(|(total_file_count, _)| total_file_count),
+				stored_file_count =
+					file_counts.map(|(_, processed_file_count)| processed_file_count),
+				processing_time = process_time.as_secs_f64();
 				"{} ({} pack files, {} pack files stored, {}.{:03} s)",
-				output_file_path.metadata().ok().map_or_else(
+				output_file_size.map_or_else(
 					|| Cow::Borrowed("Pack processed"),
-					|metadata| Cow::Owned(format!(
+					|output_file_size| Cow::Owned(format!(
 						"{} generated, {:.3} MiB",
 						output_file_path.as_os_str().to_string_lossy(),
-						metadata.len() as f64 / (1024.0 * 1024.0)
+						output_file_size as f64 / (1024.0 * 1024.0)
 					))
 				),
 				file_counts.map_or_else(
@@ -250,8 +788,115 @@ fn read_options_file_and_squash(
 	)
 }
 
+/// Prints what a squash operation with the specified options would do with each pack file,
+/// without processing any of them.
+fn print_pack_file_plan(squash_options: SquashOptions) -> i32 {
+	info!("Options read. Planning pack processing...");
+
+	match PackSquasher::new().plan(OsFilesystem, squash_options) {
+		Ok(planned_pack_files) => {
+			let mut skipped_file_count = 0;
+
+			for planned_pack_file in &planned_pack_files {
+				let path = planned_pack_file.path().as_str();
+
+				if let Some(skip_reason) = planned_pack_file.skip_reason() {
+					skipped_file_count += 1;
+
+					warn!("{path}: Skipped: {skip_reason}");
+				} else if let (Some(asset_type), Some(output_path)) = (
+					planned_pack_file.asset_type(),
+					planned_pack_file.output_path()
+				) {
+					info!(
+						"{path}: {asset_type}, with {}, stored as {}",
+						planned_pack_file.file_options_pattern().map_or_else(
+							|| Cow::Borrowed("the default options"),
+							|pattern| Cow::Owned(format!("the options of the \"{pattern}\" table"))
+						),
+						output_path.as_str()
+					);
+				}
+			}
+
+			info!(
+				"Dry run finished: {} pack files, {} of them skipped. Some pack files may also be skipped \
+				after analyzing the pack contents, such as those that are identical to vanilla textures",
+				planned_pack_files.len(),
+				skipped_file_count
+			);
+
+			0
+		}
+		Err(err) => {
+			error!("Pack processing planning error: {err}");
+
+			128
+		}
+	}
+}
+
+/// Compares the pack builds at the specified paths, printing the files that differ between
+/// them and a summary of the differences to the standard output stream.
+fn print_pack_diff(old_pack_path: &Path, new_pack_path: &Path) -> Result<(), PackDiffError> {
+	let pack_diff = diff_packs(old_pack_path, new_pack_path)?;
+
+	for file_diff in pack_diff.file_diffs() {
+		let (old_size, new_size) = (
+			file_diff.old_size().unwrap_or(0),
+			file_diff.new_size().unwrap_or(0)
+		);
+
+		match file_diff.change() {
+			PackFileChange::Added => println!("+ {}: {new_size} bytes", file_diff.path()),
+			PackFileChange::Removed => println!("- {}: {old_size} bytes", file_diff.path()),
+			PackFileChange::Changed => println!(
+				"~ {}: {old_size} -> {new_size} bytes ({}), SHA-256 {} -> {}",
+				file_diff.path(),
+				byte_count_delta(old_size, new_size),
+				// Abbreviate the hashes, which are long and only meant to be compared
+				file_diff.old_sha256().map_or("", |sha256| &sha256[..12]),
+				file_diff.new_sha256().map_or("", |sha256| &sha256[..12])
+			)
+		}
+	}
+
+	let change_count = |change| {
+		pack_diff
+			.file_diffs()
+			.iter()
+			.filter(|file_diff| file_diff.change() == change)
+			.count()
+	};
+
+	if !pack_diff.file_diffs().is_empty() {
+		println!();
+	}
+	println!(
+		"{} added, {} removed, {} changed and {} unchanged files. Total size: {} -> {} bytes ({})",
+		change_count(PackFileChange::Added),
+		change_count(PackFileChange::Removed),
+		change_count(PackFileChange::Changed),
+		pack_diff.unchanged_file_count(),
+		pack_diff.old_total_size(),
+		pack_diff.new_total_size(),
+		byte_count_delta(pack_diff.old_total_size(), pack_diff.new_total_size())
+	);
+
+	Ok(())
+}
+
+/// Returns the signed difference between the specified old and new byte counts.
+fn byte_count_delta(old_byte_count: u64, new_byte_count: u64) -> String {
+	format!(
+		"{:+}",
+		i128::from(new_byte_count) - i128::from(old_byte_count)
+	)
+}
+
 fn squash(
 	squash_options: SquashOptions,
+	show_progress: bool,
 	mut title_controller: Option<TerminalTitleController>
 ) -> Result<Option<(u64, u64)>, PackSquasherError> {
 	let (sender, mut receiver) = channel(64);
@@ -275,6 +920,7 @@ fn squash(
 
 		let mut total_file_count = 0;
 		let mut processed_file_count = 0;
+		let mut progress_display = show_progress.then(ProgressDisplay::new);
 		let progress_tick_timer = sleep(PROGRESS_TICK_INTERVAL);
 
 		tokio::pin!(progress_tick_timer);
@@ -286,32 +932,59 @@ fn squash(
 
 				status_update_message = receiver.recv() => match status_update_message {
 					Some(status_update) => match status_update {
+						PackSquasherStatus::PackFilesFound(pack_file_count) => {
+							if let Some(progress_display) = &mut progress_display {
+								progress_display.pack_files_found(pack_file_count);
+							}
+						}
+						PackSquasherStatus::PackFileProcessingStarted(path) => {
+							if let Some(progress_display) = &mut progress_display {
+								progress_display.pack_file_processing_started(path.as_str());
+							}
+						}
 						PackSquasherStatus::PackFileProcessed(pack_file_status) => {
 							total_file_count += 1;
 							processed_file_count += 1 - u64::from(pack_file_status.skipped());
 
-							match pack_file_status.optimization_error() {
-								Some(error_description) => error!(
+							let optimization_error = pack_file_status.optimization_error();
+							let successfully_processed =
+								optimization_error.is_none() && !pack_file_status.skipped();
+
+							// The progress display replaces the messages about successfully
+							// processed pack files, but other messages are still shown
+							if !successfully_processed || progress_display.is_none() {
+								if let Some(progress_display) = &mut progress_display {
+									progress_display.clear();
+								}
+
+								log!(
+									if optimization_error.is_some() {
+										Level::Error
+									} else if pack_file_status.skipped() {
+										Level::Warn
+									} else {
+										Level::Trace
+									},
+									event = "pack_file_processed",
+									path = pack_file_status.path().as_str(),
+									optimization_strategy = pack_file_status.optimization_strategy(),
+									optimization_error = optimization_error,
+									skipped = pack_file_status.skipped(),
+									processing_time = pack_file_status
+										.processing_time()
+										.map(|processing_time| processing_time.as_secs_f64()),
+									input_size = pack_file_status.input_size(),
+									output_size = pack_file_status.output_size();
 									"{}: {}",
 									pack_file_status.path().as_str(),
-									error_description
-								),
-								None => {
-									if pack_file_status.skipped() {
-										warn!(
-											"{}: {}",
-											pack_file_status.path().as_str(),
-											pack_file_status.optimization_strategy()
-										);
-									} else {
-										trace!(
-											"{}: {}",
-											pack_file_status.path().as_str(),
-											pack_file_status.optimization_strategy()
-										);
-									};
-								}
-							};
+									optimization_error
+										.unwrap_or(pack_file_status.optimization_strategy())
+								);
+							}
+
+							if let Some(progress_display) = &mut progress_display {
+								progress_display.pack_file_processed(&pack_file_status);
+							}
 
 							if let Some(title_controller) = &mut title_controller {
 								title_controller.advance_and_show();
@@ -323,6 +996,11 @@ fn squash(
 							}
 						}
 						PackSquasherStatus::ZipFinish => {
+							if let Some(progress_display) = &mut progress_display {
+								progress_display.zip_finish();
+								progress_display.clear();
+							}
+
 							info!("Finishing up ZIP file...");
 
 							// Move on to the "finishing" title phase
@@ -331,29 +1009,97 @@ fn squash(
 								title_controller.show();
 							}
 						}
-						PackSquasherStatus::Notice(notice) => info!("{notice}"),
-						PackSquasherStatus::Warning(warning) => match warning {
-							PackSquasherWarning::UnusablePreviousZip(err) => warn!(
-								"The previous ZIP file could not be read. It will not be used to speed up processing. \
-									Was the file last modified by PackSquash? Cause: {err}"
-							),
-							PackSquasherWarning::PredictableSystemTimeSanitizationKey => warn!(
-								"Used predictable system IDs to build encryption keys. The dates embedded in the result ZIP file, \
-									which reveal when it was generated, may be easier to decrypt. For more information \
-									about the topic, check out <https://packsquash.aylas.org/links/Low-entropy-system-ID-help>"
-							),
-							PackSquasherWarning::VolatileSystemTimeSanitizationKey => warn!(
-								"Used a volatile system IDs to build encryption keys. You maybe should not reuse the result ZIP file, \
-									as unexpected results can occur after you use your device as usual. For more information \
-									about the topic, check out <https://packsquash.aylas.org/links/Volatile-system-ID-help>"
-							),
-							#[cfg(unix)]
-							PackSquasherWarning::ConcurrencyLimitedDueToOpenFdLimits => warn!(
-								"The number of pack files that will be processed in parallel was reduced to avoid \
-								exceeding open file descriptor limits. Please increase the open file descriptor \
-								limit for optimum performance, or decrease the number of threads"),
-							_ => unimplemented!()
-						},
+						PackSquasherStatus::Notice(notice) => {
+							if let Some(progress_display) = &mut progress_display {
+								progress_display.clear();
+							}
+
+							info!("{notice}");
+						}
+						PackSquasherStatus::Warning(warning) => {
+							if let Some(progress_display) = &mut progress_display {
+								progress_display.clear();
+							}
+
+							match warning {
+								PackSquasherWarning::UnusablePreviousZip(err) => warn!(
+									"The previous ZIP file could not be read. It will not be used to speed up processing. \
+										Was the file last modified by PackSquash? Cause: {err}"
+								),
+								PackSquasherWarning::PredictableSystemTimeSanitizationKey => warn!(
+									"Used predictable system IDs to build encryption keys. The dates embedded in the result ZIP file, \
+										which reveal when it was generated, may be easier to decrypt. For more information \
+										about the topic, check out <https://packsquash.aylas.org/links/Low-entropy-system-ID-help>"
+								),
+								PackSquasherWarning::VolatileSystemTimeSanitizationKey => warn!(
+									"Used a volatile system IDs to build encryption keys. You maybe should not reuse the result ZIP file, \
+										as unexpected results can occur after you use your device as usual. For more information \
+										about the topic, check out <https://packsquash.aylas.org/links/Volatile-system-ID-help>"
+								),
+								#[cfg(unix)]
+								PackSquasherWarning::ConcurrencyLimitedDueToOpenFdLimits => warn!(
+									"The number of pack files that will be processed in parallel was reduced to avoid \
+									exceeding open file descriptor limits. Please increase the open file descriptor \
+									limit for optimum performance, or decrease the number of threads"),
+								PackSquasherWarning::VanillaIdenticalTexture(path) => warn!(
+									"{path} is identical to the vanilla texture at the same path. Consider removing it \
+									from the pack, or enabling the exclude_vanilla_identical_textures option"),
+								PackSquasherWarning::MissingConnectedTextureTiles(path, tiles) => warn!(
+									"{path} references connected texture tiles that do not exist: {}",
+									tiles.join(", ")),
+								PackSquasherWarning::CustomEntityModelMismatch(path, mismatch) => warn!(
+									"{path} is a custom entity model that OptiFine may render with missing assets: \
+									{mismatch}"),
+								PackSquasherWarning::EmissiveTextureMismatch(path, mismatch) => warn!(
+									"{path} is an emissive texture that may be rendered misaligned: {mismatch}"),
+								PackSquasherWarning::DuplicateSounds(paths) => warn!(
+									"These sounds have the same audio data, so all but one of them could be removed \
+									from the pack: {}",
+									paths.iter().map(|path| path.as_str()).collect::<Vec<_>>().join(", ")),
+								PackSquasherWarning::MissingSound(path, sound_name) => warn!(
+									"{path} references the sound {sound_name}, which is not in the pack"),
+								PackSquasherWarning::UnreferencedSound(path) => warn!(
+									"{path} is not referenced by any sounds.json file. Consider removing it \
+									from the pack, or enabling the exclude_unreferenced_sounds option"),
+								PackSquasherWarning::AudioPreviewNotGenerated(path) => warn!(
+									"A preview of {path} could not be generated"),
+								PackSquasherWarning::StereoPositionalSound(path) => warn!(
+									"{path} is stereo, so Minecraft will play it at the same volume no matter how far \
+									away it is. Consider downmixing it to mono, or setting the \
+									stereo_positional_sound_handling option to downmix"),
+								PackSquasherWarning::PackMergeConflict(path, kept_file_path, discarded_file_paths) => warn!(
+									"{path} is in several merged packs. Keeping {} and discarding {}",
+									kept_file_path.display(),
+									discarded_file_paths
+										.iter()
+										.map(|file_path| file_path.display().to_string())
+										.collect::<Vec<_>>()
+										.join(", ")
+								),
+								PackSquasherWarning::JsonSchemaViolation(path, violation) => warn!(
+									"{path} does not match the schema Minecraft expects for it: {violation}"),
+								PackSquasherWarning::UnreferencedAsset(path) => warn!(
+									"{path} is not referenced by any other pack file. Consider removing it from the pack, \
+									or setting the unreferenced_asset_handling option to exclude"),
+								PackSquasherWarning::DuplicateTranslations(path, duplicate_translation_count) => warn!(
+									"{path} has {duplicate_translation_count} translations that are the same as the \
+									en_us translations Minecraft falls back to. Consider removing them from the file"),
+								PackSquasherWarning::ShaderProgramMismatch(path, mismatch) => warn!(
+									"{path} does not match the shaders it references: {mismatch}"),
+								PackSquasherWarning::PostEffectMismatch(path, mismatch) => warn!(
+									"{path} is a post-processing effect that is not wired correctly: {mismatch}"),
+								PackSquasherWarning::FontProviderMismatch(path, mismatch) => warn!(
+									"{path} has a font provider that Minecraft may skip: {mismatch}"),
+								PackSquasherWarning::ItemModelDefinitionMismatch(path, mismatch) => warn!(
+									"{path} is an item model definition that Minecraft may render with \
+									missing assets: {mismatch}"),
+								PackSquasherWarning::AtlasSourceMismatch(path, mismatch) => warn!(
+									"{path} has an atlas source that may leave textures missing: {mismatch}"),
+								PackSquasherWarning::DataPackReferenceMismatch(path, mismatch) => warn!(
+									"{path} is a tag that Minecraft may fail to load: {mismatch}"),
+								_ => unimplemented!()
+							}
+						}
 						_ => unimplemented!()
 					}
 					None => {
@@ -364,16 +1110,26 @@ fn squash(
 				() = &mut progress_tick_timer => {
 					// We have not yet received any message from the PackSquasher. Change the title
 					// so that we give the user the illusion of some progress, and then schedule
-					// another progress tick
+					// another progress tick. The progress display is redrawn below
 					if let Some(title_controller) = &mut title_controller {
 						title_controller.advance_and_show();
-
-						progress_tick_timer
-							.as_mut()
-							.reset(tokio::time::Instant::now() + PROGRESS_TICK_INTERVAL);
 					}
+
+					progress_tick_timer
+						.as_mut()
+						.reset(tokio::time::Instant::now() + PROGRESS_TICK_INTERVAL);
 				}
 			}
+
+			if let Some(progress_display) = &mut progress_display {
+				progress_display.draw();
+			}
+		}
+
+		if let Some(mut progress_display) = progress_display {
+			progress_display.clear();
+
+			debug!("Stage times: {}", progress_display.stage_breakdown());
 		}
 
 		(total_file_count, processed_file_count)
@@ -388,7 +1144,13 @@ fn squash(
 		// and we should propagate it
 		match packsquasher.await.unwrap() {
 			Ok(()) => Ok(cli_update_task.await.ok()),
-			Err(err) => Err(err)
+			Err(err) => {
+				// Let the status messages sent before the error be shown, and the progress
+				// display be cleared
+				cli_update_task.await.ok();
+
+				Err(err)
+			}
 		}
 	})
 }
@@ -437,20 +1199,28 @@ fn print_version_information(verbose: bool) {
 }
 
 /// Initializes the logging of the application, responsible for showing to the user relevant
-/// application operation information.
-fn init_logger(enable_emoji: bool, enable_colors: bool) {
+/// application operation information in the specified format. If a log level is specified,
+/// less severe messages are hidden, no matter what the log filtering environment variables say.
+fn init_logger(
+	enable_emoji: bool,
+	enable_colors: bool,
+	log_format: LogFormat,
+	log_level: Option<LevelFilter>
+) {
 	let mut logger_builder = Builder::new();
 
 	logger_builder
 		.target(LOG_TARGET)
-		.write_style(if enable_colors {
+		.write_style(if enable_colors && matches!(log_format, LogFormat::Human) {
 			WriteStyle::Always
 		} else {
 			WriteStyle::Never
 		})
 		// Hide log messages from libraries by default
-		.filter(Some("packsquash"), LevelFilter::max())
-		.format(move |f, record| {
+		.filter(Some("packsquash"), LevelFilter::max());
+
+	match log_format {
+		LogFormat::Human => logger_builder.format(move |f, record| {
 			use std::io::Write;
 
 			let (level_color, level_icon, bold_effect) = match record.level() {
@@ -496,11 +1266,17 @@ fn init_logger(enable_emoji: bool, enable_colors: bool) {
 					// than other characters. Output an extra space as a hack to look pretty
 					.replace('\n', if enable_emoji { "\n   " } else { "\n  " })
 			)
-		});
+		}),
+		LogFormat::Json => logger_builder.format(write_json_log_record)
+	};
 
 	if let Ok(log_filters) = env::var("PACKSQUASH_LOG").or_else(|_| env::var("RUST_LOG")) {
 		logger_builder.parse_filters(&log_filters);
 	}
 
+	if let Some(log_level) = log_level {
+		logger_builder.filter(Some("packsquash"), log_level);
+	}
+
 	logger_builder.init();
 }
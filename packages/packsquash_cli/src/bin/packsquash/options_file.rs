@@ -0,0 +1,637 @@
+//! Contains routines to read options files, resolving the options files they extend and
+//! applying the preset and profile selected in the command line, and substituting the secrets
+//! and environment variables the options refer to.
+
+use std::{
+	env,
+	fmt::{self, Display, Formatter},
+	fs, io,
+	path::{Path, PathBuf}
+};
+
+use packsquash::config::{
+	MinecraftVersionCondition, MinecraftVersionRange, OptionMigration, SquashOptions, UnknownOption,
+	find_unknown_options, migrate_options
+};
+use toml::{Table, Value};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+use crate::remote_options_file::{
+	RemoteOptionsFileError, download_remote_options_file, is_remote_options_file
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The key of an options file that holds the path of the options files it extends.
+const EXTENDS_KEY: &str = "extends";
+/// The key of an options file that holds the table of its named profiles.
+const PROFILE_KEY: &str = "profile";
+/// The key of an options file that holds the tables of options that only apply when the
+/// targeted Minecraft versions satisfy some condition.
+const CONDITIONAL_OPTIONS_KEY: &str = "if";
+/// The key of an options file that holds the path of the secrets file its options may refer to.
+const SECRETS_FILE_KEY: &str = "secrets_file";
+/// The key of the inline tables that stand for the value of a secret defined in the secrets file.
+const SECRET_REFERENCE_KEY: &str = "from_secret";
+/// The key of the inline tables that stand for the value of an environment variable.
+const ENVIRONMENT_VARIABLE_REFERENCE_KEY: &str = "from_environment_variable";
+/// The key of the option that holds the range of Minecraft versions targeted by a pack.
+const TARGET_MINECRAFT_VERSION_RANGE_KEY: &str = "target_minecraft_version_range";
+/// The key of the option that holds the directories of the packs merged with the processed pack.
+const MERGED_PACK_DIRECTORIES_KEY: &str = "merged_pack_directories";
+/// The names and options tables of the built-in option presets, which configure every file
+/// type coherently for a goal, so that options files only need to override what differs.
+const PRESETS: [(&str, &str); 4] = [
+	("maximum", include_str!("presets/maximum.toml")),
+	("balanced", include_str!("presets/balanced.toml")),
+	("fast", include_str!("presets/fast.toml")),
+	("lossless", include_str!("presets/lossless.toml"))
+];
+/// The prefix of the names of the environment variables that override options. The rest of
+/// the name is the path of keys to the overridden option, separated by
+/// [`OPTION_OVERRIDE_ENVIRONMENT_VARIABLE_KEY_SEPARATOR`].
+const OPTION_OVERRIDE_ENVIRONMENT_VARIABLE_PREFIX: &str = "PACKSQUASH_OPT__";
+/// The separator of the keys in the names of the environment variables that override options.
+const OPTION_OVERRIDE_ENVIRONMENT_VARIABLE_KEY_SEPARATOR: &str = "__";
+
+/// Represents an error that may happen while reading an options file.
+pub enum OptionsFileError {
+	/// An extended options file could not be read.
+	Io(PathBuf, io::Error),
+	/// An extended remote options file could not be downloaded.
+	Remote(String, RemoteOptionsFileError),
+	/// An options file is not a valid TOML document, or the resulting options are not
+	/// valid options.
+	Toml(toml::de::Error),
+	/// The `extends` key of an options file is not a path or a list of paths.
+	InvalidExtends,
+	/// An options file extends itself, directly or through other options files.
+	CyclicExtends(PathBuf),
+	/// The secrets file could not be read.
+	SecretsFileIo(PathBuf, io::Error),
+	/// The `secrets_file` key of an options file is not a path.
+	InvalidSecretsFileKey,
+	/// The secrets file is not a valid TOML document.
+	InvalidSecretsFile(PathBuf),
+	/// An option refers to a secret that is not defined in the secrets file.
+	UndefinedSecret(String),
+	/// An option refers to an environment variable that is not defined.
+	UndefinedEnvironmentVariable(String),
+	/// The selected preset is not a built-in preset.
+	UnknownPreset(String),
+	/// The selected profile is not defined in the options file or any file it extends.
+	UnknownProfile(String),
+	/// The `if` key of an options file is not a table of options tables.
+	InvalidConditionalOptions,
+	/// A condition of the `if` table is not a valid Minecraft version condition.
+	InvalidCondition(String, &'static str),
+	/// The options file contains options that PackSquash does not know about.
+	UnknownOptions(Vec<UnknownOption>)
+}
+
+impl OptionsFileError {
+	/// Returns whether this error happened while reading a file, rather than parsing it.
+	pub fn is_io_error(&self) -> bool {
+		matches!(
+			self,
+			Self::Io(..) | Self::Remote(..) | Self::SecretsFileIo(..)
+		)
+	}
+}
+
+impl Display for OptionsFileError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Io(path, err) => write!(
+				f,
+				"Couldn't read the extended options file {}: {err}",
+				path.display()
+			),
+			Self::Remote(url, err) => {
+				write!(
+					f,
+					"Couldn't download the extended options file {url}: {err}"
+				)
+			}
+			Self::Toml(err) => write!(f, "{err}"),
+			Self::InvalidExtends => write!(
+				f,
+				"The \"{EXTENDS_KEY}\" key must be an options file path or a list of them"
+			),
+			Self::CyclicExtends(path) => write!(
+				f,
+				"The options file {} extends itself through the \"{EXTENDS_KEY}\" key",
+				path.display()
+			),
+			Self::SecretsFileIo(path, err) => write!(
+				f,
+				"Couldn't read the secrets file {}: {err}",
+				path.display()
+			),
+			Self::InvalidSecretsFileKey => {
+				write!(
+					f,
+					"The \"{SECRETS_FILE_KEY}\" key must be a secrets file path"
+				)
+			}
+			// The parse error is not shown, as it may quote the values of secrets
+			Self::InvalidSecretsFile(path) => write!(
+				f,
+				"The secrets file {} is not a valid TOML document",
+				path.display()
+			),
+			Self::UndefinedSecret(secret) => {
+				write!(
+					f,
+					"The secret \"{secret}\" is not defined in the secrets file"
+				)
+			}
+			Self::UndefinedEnvironmentVariable(environment_variable) => write!(
+				f,
+				"The environment variable \"{environment_variable}\" is not defined"
+			),
+			Self::UnknownPreset(preset) => {
+				write!(
+					f,
+					"The preset \"{preset}\" does not exist. Available presets: "
+				)?;
+				for (i, (name, _)) in PRESETS.iter().enumerate() {
+					if i > 0 {
+						write!(f, ", ")?;
+					}
+					write!(f, "{name}")?;
+				}
+
+				Ok(())
+			}
+			Self::UnknownProfile(profile) => {
+				write!(f, "The profile \"{profile}\" is not defined")
+			}
+			Self::InvalidConditionalOptions => write!(
+				f,
+				"The \"{CONDITIONAL_OPTIONS_KEY}\" key must be a table of options tables"
+			),
+			Self::InvalidCondition(condition, err) => {
+				write!(
+					f,
+					"Invalid Minecraft version condition \"{condition}\": {err}"
+				)
+			}
+			Self::UnknownOptions(unknown_options) => {
+				for (i, unknown_option) in unknown_options.iter().enumerate() {
+					if i > 0 {
+						writeln!(f)?;
+					}
+					write!(f, "{unknown_option}")?;
+				}
+
+				Ok(())
+			}
+		}
+	}
+}
+
+/// Represents a problem found while reading an options file that does not prevent using it, but
+/// probably makes it not do what was meant.
+pub enum OptionsFileWarning {
+	/// The options file has conditional options tables, but the targeted Minecraft versions are
+	/// unknown, so none of them apply.
+	IgnoredConditionalOptions
+}
+
+impl Display for OptionsFileWarning {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::IgnoredConditionalOptions => write!(
+				f,
+				"The \"{CONDITIONAL_OPTIONS_KEY}\" options tables are ignored because the \
+				 \"{TARGET_MINECRAFT_VERSION_RANGE_KEY}\" option is not set, so the targeted \
+				 Minecraft versions are unknown"
+			)
+		}
+	}
+}
+
+/// Parses the specified options file contents to squash options. The options of the selected
+/// built-in preset, if any, are the base options, which the options files the options file
+/// extends override, in order, followed by the options file itself, merging tables key by
+/// key. The tables of the selected profile, if any, override the resulting options
+/// in the same way, followed by the conditional options tables whose Minecraft version
+/// condition the targeted Minecraft versions satisfy, and then the specified option
+/// overrides, in order. Then, inline tables that refer to a secret or an environment variable
+/// are replaced with its value, so that sensitive values can be kept out of options files.
+/// Extended options files may be remote options files, which are downloaded. Relative paths
+/// of extended options files and secrets files are resolved against the directory of the
+/// options file that contains them, which for the options file itself is the specified
+/// directory, and for remote options files the working directory. Deprecated options
+/// are migrated to the current options, returning the migrations that were done, and unknown
+/// options are reported with suggestions of the known options that were probably meant instead.
+/// Problems that do not prevent using the options are returned as warnings.
+pub fn parse_options_file(
+	options_string: &str,
+	directory: &Path,
+	preset: Option<&str>,
+	profile: Option<&str>,
+	option_overrides: impl IntoIterator<Item = Table>
+) -> Result<(SquashOptions, Vec<OptionMigration>, Vec<OptionsFileWarning>), OptionsFileError> {
+	let options_table = toml::from_str(options_string).map_err(OptionsFileError::Toml)?;
+	let mut options_table = resolve_extends(options_table, directory, &mut vec![])?;
+
+	if let Some(preset) = preset {
+		let Some((_, preset_options)) = PRESETS.iter().find(|(name, _)| *name == preset) else {
+			return Err(OptionsFileError::UnknownPreset(preset.to_string()));
+		};
+
+		let mut preset_table = toml::from_str(preset_options).map_err(OptionsFileError::Toml)?;
+		merge_tables(&mut preset_table, options_table);
+		options_table = preset_table;
+	}
+
+	let profiles = options_table.remove(PROFILE_KEY);
+	if let Some(profile) = profile {
+		let Some(Value::Table(profile_table)) = (match profiles {
+			Some(Value::Table(mut profiles)) => profiles.remove(profile),
+			_ => None
+		}) else {
+			return Err(OptionsFileError::UnknownProfile(profile.to_string()));
+		};
+
+		merge_tables(&mut options_table, profile_table);
+	}
+
+	// Conditional options depend on the targeted Minecraft versions, which may be overridden.
+	// They are not affected by conditional options themselves, as that could be circular
+	let option_overrides = option_overrides.into_iter().collect::<Vec<_>>();
+	let mut warnings = vec![];
+	if let Some(conditional_options) = options_table.remove(CONDITIONAL_OPTIONS_KEY) {
+		let Value::Table(conditional_options) = conditional_options else {
+			return Err(OptionsFileError::InvalidConditionalOptions);
+		};
+
+		let target_minecraft_version_range = option_overrides
+			.iter()
+			.rev()
+			.chain([&options_table])
+			.find_map(|options_table| options_table.get(TARGET_MINECRAFT_VERSION_RANGE_KEY))
+			.and_then(|version_range| {
+				version_range
+					.clone()
+					.try_into::<MinecraftVersionRange>()
+					.ok()
+			});
+		if target_minecraft_version_range.is_none() && !conditional_options.is_empty() {
+			warnings.push(OptionsFileWarning::IgnoredConditionalOptions);
+		}
+
+		for (condition, conditional_options_table) in conditional_options {
+			let version_condition = condition
+				.parse::<MinecraftVersionCondition>()
+				.map_err(|err| OptionsFileError::InvalidCondition(condition.clone(), err))?;
+			let Value::Table(conditional_options_table) = conditional_options_table else {
+				return Err(OptionsFileError::InvalidConditionalOptions);
+			};
+
+			if target_minecraft_version_range
+				.is_some_and(|version_range| version_condition.matches(&version_range))
+			{
+				merge_tables(&mut options_table, conditional_options_table);
+			}
+		}
+	}
+
+	for option_override in option_overrides {
+		merge_tables(&mut options_table, option_override);
+	}
+
+	let secrets = read_secrets_file(options_table.remove(SECRETS_FILE_KEY))?;
+	for (_, value) in options_table.iter_mut() {
+		substitute_references(value, &secrets)?;
+	}
+
+	let mut option_migrations = vec![];
+	if let Ok(serde_json::Value::Object(mut options)) = serde_json::to_value(&options_table) {
+		option_migrations = migrate_options(&mut options);
+		if !option_migrations.is_empty()
+			&& let Ok(migrated_options_table) =
+				serde_json::from_value(serde_json::Value::Object(options.clone()))
+		{
+			options_table = migrated_options_table;
+		}
+
+		let unknown_options = find_unknown_options(&options);
+		if !unknown_options.is_empty() {
+			return Err(OptionsFileError::UnknownOptions(unknown_options));
+		}
+	}
+
+	Ok((
+		options_table
+			.try_into::<SquashOptions>()
+			.map_err(OptionsFileError::Toml)?,
+		option_migrations,
+		warnings
+	))
+}
+
+/// Rewrites the options file at the specified path to apply the specified option migrations,
+/// preserving its formatting and comments, and returns how many options were migrated. Only
+/// the global options and per-file options tables of the options file itself are rewritten,
+/// so the deprecated options set in extended options files or in profiles are left as-is.
+pub fn rewrite_migrated_options(
+	options_file_path: &Path,
+	option_migrations: &[OptionMigration]
+) -> io::Result<usize> {
+	let mut options_document = fs::read_to_string(options_file_path)?
+		.parse::<DocumentMut>()
+		.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+	let mut migrated_option_count = 0;
+	for option_migration in option_migrations {
+		let options_table = match &option_migration.file_options_glob_pattern {
+			Some(glob_pattern) => options_document
+				.get_mut(glob_pattern)
+				.and_then(Item::as_table_like_mut),
+			None => Some(options_document.as_table_mut() as &mut dyn TableLike)
+		};
+
+		let Some(options_table) = options_table else {
+			continue;
+		};
+		if options_table
+			.remove(option_migration.deprecated_key)
+			.is_none()
+		{
+			continue;
+		}
+
+		if let Some((key, value)) = &option_migration.replacement
+			&& !options_table.contains_key(key)
+			&& let Some(value) = toml_edit_value(value)
+		{
+			options_table.insert(key, toml_edit::value(value));
+		}
+
+		migrated_option_count += 1;
+	}
+
+	if migrated_option_count > 0 {
+		fs::write(options_file_path, options_document.to_string())?;
+	}
+
+	Ok(migrated_option_count)
+}
+
+/// Converts the specified scalar JSON value, such as the value of a migrated option, to a TOML
+/// value. `None` is returned for other JSON values.
+fn toml_edit_value(value: &serde_json::Value) -> Option<toml_edit::Value> {
+	match value {
+		serde_json::Value::Bool(value) => Some((*value).into()),
+		serde_json::Value::Number(value) => value
+			.as_i64()
+			.map(Into::into)
+			.or_else(|| value.as_f64().map(Into::into)),
+		serde_json::Value::String(value) => Some(value.as_str().into()),
+		_ => None
+	}
+}
+
+/// Parses an option override definition with `KEY=VALUE` syntax, where `KEY` is a TOML key,
+/// which may be dotted to override options of tables, and `VALUE` is a TOML value. Values
+/// that are not valid TOML values are taken as strings, so that they do not need quoting.
+/// The returned table is meant to be merged over the options. `None` is returned if the
+/// definition syntax is invalid.
+pub fn parse_option_override(definition: &str) -> Option<Table> {
+	let (key, value) = definition.split_once('=')?;
+
+	// Let the TOML parser handle dotted and quoted keys, and then replace the placeholder
+	// value of the innermost key
+	let mut option_override = toml::from_str::<Table>(&format!("{key} = 0")).ok()?;
+	let mut table = &mut option_override;
+	loop {
+		match table.iter_mut().next()?.1 {
+			Value::Table(inner_table) => table = inner_table,
+			placeholder_value => {
+				*placeholder_value = parse_option_override_value(value);
+				break;
+			}
+		}
+	}
+
+	Some(option_override)
+}
+
+/// Returns an option override that sets the directories of the packs to merge with the
+/// processed pack to the specified ones, or `None` if no directories are specified.
+pub fn merged_pack_directories_override(merged_pack_directories: Vec<String>) -> Option<Table> {
+	(!merged_pack_directories.is_empty()).then(|| {
+		Table::from_iter([(
+			MERGED_PACK_DIRECTORIES_KEY.to_string(),
+			Value::Array(
+				merged_pack_directories
+					.into_iter()
+					.map(Value::String)
+					.collect()
+			)
+		)])
+	})
+}
+
+/// Returns the option overrides defined by environment variables whose name starts with
+/// `PACKSQUASH_OPT__`, followed by the path of keys to the overridden option separated by
+/// `__`. Their values are parsed like the values of [`parse_option_override`].
+pub fn environment_option_overrides() -> Vec<Table> {
+	let mut option_overrides = env::vars_os()
+		.filter_map(|(name, value)| {
+			Some((
+				name.to_str()?
+					.strip_prefix(OPTION_OVERRIDE_ENVIRONMENT_VARIABLE_PREFIX)?
+					.to_string(),
+				value.into_string().ok()?
+			))
+		})
+		.collect::<Vec<_>>();
+
+	// Sort the overrides by name, so that their precedence does not depend on the
+	// unspecified order of environment variables
+	option_overrides.sort_unstable();
+
+	option_overrides
+		.into_iter()
+		.filter_map(|(key_path, value)| {
+			match key_path
+				.rsplit(OPTION_OVERRIDE_ENVIRONMENT_VARIABLE_KEY_SEPARATOR)
+				.fold(parse_option_override_value(&value), |value, key| {
+					Value::Table(Table::from_iter([(key.to_string(), value)]))
+				}) {
+				Value::Table(option_override) => Some(option_override),
+				_ => None
+			}
+		})
+		.collect()
+}
+
+/// Parses the value of an option override as a TOML value, falling back to taking it as a
+/// string if it is not a valid TOML value.
+fn parse_option_override_value(value: &str) -> Value {
+	toml::from_str::<Table>(&format!("value = {value}"))
+		.ok()
+		.and_then(|mut table| table.remove("value"))
+		.unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+/// Resolves the options files that the specified options table extends, returning the
+/// result of merging the options table over them. The canonical paths of the options files
+/// that are being resolved are tracked to detect cycles.
+fn resolve_extends(
+	mut options_table: Table,
+	directory: &Path,
+	resolving_paths: &mut Vec<PathBuf>
+) -> Result<Table, OptionsFileError> {
+	// Secrets files are relative to the options file that contains them, but that directory
+	// is no longer known after merging the options files
+	if let Some(Value::String(secrets_file_path)) = options_table.get_mut(SECRETS_FILE_KEY)
+		&& let Some(resolved_secrets_file_path) = directory.join(&*secrets_file_path).to_str()
+	{
+		*secrets_file_path = resolved_secrets_file_path.to_string();
+	}
+
+	let extended_paths = match options_table.remove(EXTENDS_KEY) {
+		None => return Ok(options_table),
+		Some(Value::String(path)) => vec![path],
+		Some(Value::Array(paths)) => paths
+			.into_iter()
+			.map(|path| match path {
+				Value::String(path) => Ok(path),
+				_ => Err(OptionsFileError::InvalidExtends)
+			})
+			.collect::<Result<_, _>>()?,
+		Some(_) => return Err(OptionsFileError::InvalidExtends)
+	};
+
+	let mut extended_options_table = Table::new();
+	for extended_path in extended_paths {
+		// Remote options files have no directory, so the relative paths they contain are
+		// resolved against the working directory, like those of the standard input
+		let (options_string, canonical_path, extended_directory) =
+			if is_remote_options_file(&extended_path) {
+				let canonical_path = PathBuf::from(&extended_path);
+				if resolving_paths.contains(&canonical_path) {
+					return Err(OptionsFileError::CyclicExtends(canonical_path));
+				}
+
+				let options_string = download_remote_options_file(&extended_path)
+					.map_err(|err| OptionsFileError::Remote(extended_path, err))?;
+
+				(options_string, canonical_path, PathBuf::new())
+			} else {
+				let extended_path = directory.join(extended_path);
+				let canonical_path = fs::canonicalize(&extended_path)
+					.map_err(|err| OptionsFileError::Io(extended_path.clone(), err))?;
+				if resolving_paths.contains(&canonical_path) {
+					return Err(OptionsFileError::CyclicExtends(extended_path));
+				}
+
+				let options_string = fs::read_to_string(&canonical_path)
+					.map_err(|err| OptionsFileError::Io(extended_path, err))?;
+				let extended_directory = canonical_path.parent().unwrap_or(directory).to_path_buf();
+
+				(options_string, canonical_path, extended_directory)
+			};
+
+		resolving_paths.push(canonical_path);
+		let options_table = resolve_extends(
+			toml::from_str(&options_string).map_err(OptionsFileError::Toml)?,
+			&extended_directory,
+			resolving_paths
+		)?;
+		resolving_paths.pop();
+
+		merge_tables(&mut extended_options_table, options_table);
+	}
+
+	merge_tables(&mut extended_options_table, options_table);
+
+	Ok(extended_options_table)
+}
+
+/// Reads the secrets defined in the secrets file at the specified path, which is the value of
+/// the `secrets_file` key of the options. A secrets file is a TOML document whose keys are
+/// the names of the secrets. As secrets files are meant to be left out of version control,
+/// no secrets are defined if the secrets file or its path are missing.
+fn read_secrets_file(secrets_file_path: Option<Value>) -> Result<Table, OptionsFileError> {
+	let secrets_file_path = match secrets_file_path {
+		None => return Ok(Table::new()),
+		Some(Value::String(path)) => PathBuf::from(path),
+		Some(_) => return Err(OptionsFileError::InvalidSecretsFileKey)
+	};
+
+	let secrets_string = match fs::read_to_string(&secrets_file_path) {
+		Ok(secrets_string) => secrets_string,
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Table::new()),
+		Err(err) => return Err(OptionsFileError::SecretsFileIo(secrets_file_path, err))
+	};
+
+	toml::from_str(&secrets_string)
+		.map_err(|_| OptionsFileError::InvalidSecretsFile(secrets_file_path))
+}
+
+/// Replaces the inline tables of the specified option value that refer to a secret, such as
+/// `{ from_secret = "name" }`, or an environment variable, such as
+/// `{ from_environment_variable = "NAME" }`, with the value they refer to. The values of
+/// environment variables are strings, used verbatim.
+fn substitute_references(value: &mut Value, secrets: &Table) -> Result<(), OptionsFileError> {
+	match value {
+		Value::Table(table) => {
+			let referenced_value = match table.iter().next() {
+				Some((key, Value::String(name))) if table.len() == 1 => match key.as_str() {
+					SECRET_REFERENCE_KEY => Some(
+						secrets
+							.get(name)
+							.cloned()
+							.ok_or_else(|| OptionsFileError::UndefinedSecret(name.clone()))?
+					),
+					ENVIRONMENT_VARIABLE_REFERENCE_KEY => {
+						Some(env::var(name).map(Value::String).map_err(|_| {
+							OptionsFileError::UndefinedEnvironmentVariable(name.clone())
+						})?)
+					}
+					_ => None
+				},
+				_ => None
+			};
+
+			if let Some(referenced_value) = referenced_value {
+				*value = referenced_value;
+			} else {
+				for (_, value) in table.iter_mut() {
+					substitute_references(value, secrets)?;
+				}
+			}
+		}
+		Value::Array(values) => {
+			for value in values {
+				substitute_references(value, secrets)?;
+			}
+		}
+		_ => {}
+	}
+
+	Ok(())
+}
+
+/// Merges the specified overriding table into the base table. Tables present in both are
+/// merged recursively, while any other value of the overriding table replaces the value of
+/// the base table.
+fn merge_tables(base_table: &mut Table, overriding_table: Table) {
+	for (key, value) in overriding_table {
+		match (base_table.get_mut(&key), value) {
+			(Some(Value::Table(base_value)), Value::Table(overriding_value)) => {
+				merge_tables(base_value, overriding_value);
+			}
+			(_, value) => {
+				base_table.insert(key, value);
+			}
+		}
+	}
+}
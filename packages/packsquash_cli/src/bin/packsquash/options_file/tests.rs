@@ -0,0 +1,49 @@
+use pretty_assertions::assert_eq;
+use tempfile::Builder;
+
+use super::*;
+
+#[test]
+fn environment_variable_references_are_substituted_verbatim() {
+	let options_dir = Builder::new()
+		.prefix("ps-options-file-test")
+		.tempdir()
+		.expect("I/O operations are assumed not to fail during tests");
+	fs::write(
+		options_dir.path().join("secrets.toml"),
+		"analytics_token = 'c2VjcmV0'"
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+
+	// SAFETY: no other test reads or writes environment variables
+	unsafe { env::set_var("CI_BUILD_NUMBER", "42") };
+
+	// This is the secrets example of the options files documentation
+	let (squash_options, _, _) = parse_options_file(
+		r#"
+		pack_directory = '.'
+		secrets_file = 'secrets.toml'
+
+		[template_variables]
+		analytics_token = { from_secret = 'analytics_token' }
+		build_number = { from_environment_variable = 'CI_BUILD_NUMBER' }
+		"#,
+		options_dir.path(),
+		None,
+		None,
+		[]
+	)
+	.unwrap_or_else(|err| panic!("The options file should be valid: {err}"));
+
+	assert_eq!(
+		squash_options
+			.global_options
+			.template_variables
+			.into_iter()
+			.collect::<Vec<_>>(),
+		[
+			("analytics_token".to_string(), "c2VjcmV0".to_string()),
+			("build_number".to_string(), "42".to_string())
+		]
+	);
+}
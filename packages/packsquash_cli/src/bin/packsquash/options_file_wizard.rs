@@ -0,0 +1,293 @@
+//! Contains an interactive wizard that inspects a pack and generates an options file tuned
+//! for it, asking the user about the choices that can't be inferred from the pack contents.
+
+use std::{
+	fs,
+	io::{self, BufRead, IsTerminal, Write},
+	path::Path
+};
+
+use packsquash::config::{MinecraftVersion, MinecraftVersionRange};
+
+/// The extensions of the audio files PackSquash can optimize.
+const AUDIO_FILE_EXTENSIONS: &[&str] = &["ogg", "oga", "mp3", "m4a", "wav", "flac"];
+/// The extensions of shader source files.
+const SHADER_FILE_EXTENSIONS: &[&str] = &["fsh", "vsh", "glsl"];
+/// The extensions of files that are only read by OptiFine.
+const OPTIFINE_FILE_EXTENSIONS: &[&str] = &["properties", "jem", "jemc", "jpm", "jpmc"];
+/// The total size of the audio files of a pack above which transcoding them to a lower
+/// quality is suggested.
+const BIG_AUDIO_FILES_SIZE: u64 = 16 * 1024 * 1024;
+
+/// The facts about a pack that are relevant to choose its options.
+#[derive(Default)]
+struct PackInspection {
+	has_assets: bool,
+	has_data: bool,
+	shader_file_count: usize,
+	optifine_file_count: usize,
+	audio_file_count: usize,
+	audio_files_size: u64
+}
+
+/// Inspects the pack at the specified directory, asks the user about the options to use for
+/// it, and prints the generated options file to the standard output. Questions are asked
+/// through the standard error and answered through the standard input, and their default
+/// answers are used without asking if the standard input is not a terminal.
+pub fn generate_options_file(pack_directory: &str) -> io::Result<()> {
+	let pack_inspection = inspect_pack(Path::new(pack_directory))?;
+	let mut prompter = Prompter::new();
+
+	let mut options_file = format!(
+		"# Options file generated by PackSquash for the pack at {pack_directory}\n\
+		# Please check out <https://packsquash.aylas.org/links/Options-files> to learn\n\
+		# about every available option\n\
+		pack_directory = {}\n",
+		toml_string(pack_directory)
+	);
+
+	let output_file_path = prompter.ask(
+		"Where should the optimized pack ZIP file be written?",
+		"pack.zip"
+	)?;
+	options_file.push_str(&format!(
+		"output_file_path = {}\n",
+		toml_string(&output_file_path)
+	));
+
+	if let Some([minimum, maximum]) = prompter.ask_minecraft_version_range()? {
+		options_file.push_str(&format!(
+			"\n# Keep the pack format versions of pack.mcmeta in sync with the targeted versions\n\
+			target_minecraft_version_range = [{}, {}]\n",
+			toml_string(&minimum),
+			toml_string(&maximum)
+		));
+	}
+
+	if prompter.confirm(
+		"Will the pack only be used by Minecraft, so that compatibility with other ZIP programs \
+		can be traded for a smaller size?",
+		false
+	)? {
+		options_file.push_str(
+			"\n# Other ZIP programs may not be able to open the generated ZIP file\n\
+			zip_spec_conformance_level = 'balanced'\n"
+		);
+	}
+
+	if pack_inspection.optifine_file_count > 0
+		&& prompter.confirm(
+			&format!(
+				"{} OptiFine files were found. Should they be added to the generated ZIP file?",
+				pack_inspection.optifine_file_count
+			),
+			true
+		)? {
+		options_file.push_str(
+			"\n# Optimize and add the files read by OptiFine, such as .properties files\n\
+			allow_mods = ['OptiFine']\n"
+		);
+	}
+
+	if pack_inspection.has_data && !pack_inspection.has_assets {
+		options_file.push_str(
+			"\n# This pack looks like a data pack, whose command functions are minified\n\
+			# and validated by default\n"
+		);
+	}
+
+	// Per-file options tables must come after global options
+	if pack_inspection.shader_file_count > 0
+		&& !prompter.confirm(
+			&format!(
+				"{} shader files were found. Should they be minified?",
+				pack_inspection.shader_file_count
+			),
+			true
+		)? {
+		options_file.push_str(&format!(
+			"\n# Keep shaders as they are, instead of minifying them\n\
+			['**/*?.{{{}}}']\n\
+			shader_source_transformation_strategy = 'keep_as_is'\n",
+			SHADER_FILE_EXTENSIONS.join(",")
+		));
+	}
+
+	if pack_inspection.audio_files_size > BIG_AUDIO_FILES_SIZE
+		&& prompter.confirm(
+			&format!(
+				"{} audio files, {:.1} MiB in total, were found. Should they be transcoded to a \
+				lower quality to save space?",
+				pack_inspection.audio_file_count,
+				pack_inspection.audio_files_size as f64 / (1024.0 * 1024.0)
+			),
+			false
+		)? {
+		options_file.push_str(&format!(
+			"\n# Trade some audio quality for a smaller size\n\
+			['**/*?.{{{}}}']\n\
+			sampling_frequency = 32000\n\
+			target_bitrate_control_metric = -0.1\n",
+			AUDIO_FILE_EXTENSIONS.join(",")
+		));
+	}
+
+	print!("{options_file}");
+
+	Ok(())
+}
+
+/// Walks the files of the pack at the specified directory to gather the facts about it that
+/// are relevant to choose its options.
+fn inspect_pack(pack_directory: &Path) -> io::Result<PackInspection> {
+	let mut pack_inspection = PackInspection {
+		has_assets: pack_directory.join("assets").is_dir(),
+		has_data: pack_directory.join("data").is_dir(),
+		..Default::default()
+	};
+
+	let mut pending_directories = vec![pack_directory.to_path_buf()];
+	while let Some(directory) = pending_directories.pop() {
+		for entry in fs::read_dir(directory)? {
+			let entry = entry?;
+			let file_type = entry.file_type()?;
+			let path = entry.path();
+
+			if file_type.is_dir() {
+				pending_directories.push(path);
+				continue;
+			}
+
+			let extension = path
+				.extension()
+				.and_then(|extension| extension.to_str())
+				.unwrap_or_default()
+				.to_ascii_lowercase();
+			let is_in_optifine_directory = path
+				.strip_prefix(pack_directory)
+				.is_ok_and(|relative_path| relative_path.starts_with("assets/minecraft/optifine"));
+
+			if AUDIO_FILE_EXTENSIONS.contains(&&*extension) {
+				pack_inspection.audio_file_count += 1;
+				pack_inspection.audio_files_size += entry.metadata()?.len();
+			} else if SHADER_FILE_EXTENSIONS.contains(&&*extension) {
+				pack_inspection.shader_file_count += 1;
+			} else if is_in_optifine_directory || OPTIFINE_FILE_EXTENSIONS.contains(&&*extension) {
+				pack_inspection.optifine_file_count += 1;
+			}
+		}
+	}
+
+	Ok(pack_inspection)
+}
+
+/// Asks the user questions through the standard error, reading the answers from the standard
+/// input, or answers them with their defaults if the standard input is not a terminal.
+struct Prompter {
+	interactive: bool
+}
+
+impl Prompter {
+	fn new() -> Self {
+		Self {
+			interactive: io::stdin().is_terminal()
+		}
+	}
+
+	/// Asks a question, returning its trimmed answer, or `None` if it was not answered or
+	/// the user can't be asked.
+	fn read_answer(&mut self, question: &str, hint: &str) -> io::Result<Option<String>> {
+		if !self.interactive {
+			return Ok(None);
+		}
+
+		eprint!("{question} [{hint}] ");
+		io::stderr().flush()?;
+
+		let mut answer = String::new();
+		io::stdin().lock().read_line(&mut answer)?;
+
+		Ok(Some(answer.trim().to_string()).filter(|answer| !answer.is_empty()))
+	}
+
+	/// Asks a question, returning its answer, or the default answer if it was not answered.
+	fn ask(&mut self, question: &str, default_answer: &str) -> io::Result<String> {
+		Ok(self
+			.read_answer(question, default_answer)?
+			.unwrap_or_else(|| default_answer.to_string()))
+	}
+
+	/// Asks a yes or no question until it is answered, returning the default answer if it
+	/// was not answered.
+	fn confirm(&mut self, question: &str, default_answer: bool) -> io::Result<bool> {
+		loop {
+			let answer = self
+				.read_answer(question, if default_answer { "Y/n" } else { "y/N" })?
+				.map(|answer| answer.to_ascii_lowercase());
+
+			match answer.as_deref() {
+				None => return Ok(default_answer),
+				Some("y" | "yes") => return Ok(true),
+				Some("n" | "no") => return Ok(false),
+				Some(_) => eprintln!("Please answer yes or no")
+			}
+		}
+	}
+
+	/// Asks for the oldest and newest Minecraft versions the pack targets until they are
+	/// valid, or not answered.
+	fn ask_minecraft_version_range(&mut self) -> io::Result<Option<[String; 2]>> {
+		loop {
+			let Some(answer) = self.read_answer(
+				"Which Minecraft versions does the pack target? Enter the oldest and newest \
+				separated by a space, such as \"1.20 1.21.4\", or nothing to skip",
+				"skip"
+			)?
+			else {
+				return Ok(None);
+			};
+
+			let versions = answer.split_whitespace().collect::<Vec<_>>();
+			let version_range = match versions[..] {
+				[minimum, maximum] => MinecraftVersion::try_from(minimum.to_string())
+					.and_then(|minimum_version| {
+						Ok([
+							minimum_version,
+							MinecraftVersion::try_from(maximum.to_string())?
+						])
+					})
+					.and_then(MinecraftVersionRange::try_from)
+					.map(|_| [minimum.to_string(), maximum.to_string()]),
+				_ => Err("Please enter two versions separated by a space")
+			};
+
+			match version_range {
+				Ok(version_range) => return Ok(Some(version_range)),
+				Err(err) => eprintln!("{err}")
+			}
+		}
+	}
+}
+
+/// Formats the specified string as a TOML string, preferring literal strings for readability.
+fn toml_string(value: &str) -> String {
+	if value.contains(['\'', '\n', '\r']) {
+		let mut basic_string = String::with_capacity(value.len() + 2);
+		basic_string.push('"');
+		for c in value.chars() {
+			match c {
+				'"' => basic_string.push_str("\\\""),
+				'\\' => basic_string.push_str("\\\\"),
+				'\n' => basic_string.push_str("\\n"),
+				'\r' => basic_string.push_str("\\r"),
+				'\t' => basic_string.push_str("\\t"),
+				c => basic_string.push(c)
+			}
+		}
+		basic_string.push('"');
+
+		basic_string
+	} else {
+		format!("'{value}'")
+	}
+}
@@ -0,0 +1,117 @@
+//! Contains routines to watch pack directories for changes through filesystem notifications,
+//! so that packs can be squashed again as soon as their files change.
+
+use std::{
+	fs,
+	path::{self, Path, PathBuf},
+	sync::mpsc::{self, Receiver},
+	time::Duration
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// How long to wait for more changes after a change before considering that a batch of
+/// changes is complete. Editors and version control tools usually touch several files in
+/// quick succession when saving or checking out files, which should be processed at once.
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Watches some directories for changes to the files within them, ignoring the changes to
+/// some paths, such as the files generated by PackSquash in those directories.
+pub struct PackWatcher {
+	/// The watcher that sends filesystem notifications to the receiver. It stops watching
+	/// when dropped, so it is kept alive here.
+	_watcher: RecommendedWatcher,
+	receiver: Receiver<notify::Result<Event>>,
+	ignored_paths: Vec<PathBuf>
+}
+
+impl PackWatcher {
+	/// Starts watching the specified directories and their subdirectories for changes. Changes
+	/// to the specified ignored paths, or to files within them, are ignored.
+	pub fn new<'path>(
+		watched_directories: impl IntoIterator<Item = &'path Path>,
+		ignored_paths: impl IntoIterator<Item = &'path Path>
+	) -> notify::Result<Self> {
+		let (sender, receiver) = mpsc::channel();
+		let mut watcher = notify::recommended_watcher(sender)?;
+
+		for watched_directory in watched_directories {
+			watcher.watch(
+				&fs::canonicalize(watched_directory)?,
+				RecursiveMode::Recursive
+			)?;
+		}
+
+		Ok(Self {
+			_watcher: watcher,
+			receiver,
+			ignored_paths: ignored_paths.into_iter().map(canonical_path).collect()
+		})
+	}
+
+	/// Blocks until some file within the watched directories changes, and then until no more
+	/// changes happen for a short while, returning the paths of the changed files, sorted and
+	/// without duplicates.
+	pub fn wait_for_changes(&self) -> notify::Result<Vec<PathBuf>> {
+		let mut changed_paths = vec![];
+
+		while changed_paths.is_empty() {
+			// The watcher is never dropped while this struct is alive, so the channel can't
+			// be disconnected
+			let event = self
+				.receiver
+				.recv()
+				.map_err(|_| notify::Error::generic("The filesystem watcher stopped"))?;
+			self.collect_changed_paths(event, &mut changed_paths)?;
+
+			while let Ok(event) = self.receiver.recv_timeout(DEBOUNCE_INTERVAL) {
+				self.collect_changed_paths(event, &mut changed_paths)?;
+			}
+		}
+
+		changed_paths.sort_unstable();
+		changed_paths.dedup();
+
+		Ok(changed_paths)
+	}
+
+	/// Adds the paths changed by the specified filesystem notification that are not ignored
+	/// to the specified list of changed paths.
+	fn collect_changed_paths(
+		&self,
+		event: notify::Result<Event>,
+		changed_paths: &mut Vec<PathBuf>
+	) -> notify::Result<()> {
+		let event = event?;
+
+		// Reading files does not change them
+		if matches!(event.kind, EventKind::Access(_)) {
+			return Ok(());
+		}
+
+		changed_paths.extend(event.paths.into_iter().filter(|path| {
+			!self
+				.ignored_paths
+				.iter()
+				.any(|ignored_path| path.starts_with(ignored_path))
+		}));
+
+		Ok(())
+	}
+}
+
+/// Returns the canonical form of the specified path, which filesystem notifications use, even
+/// if the path does not exist yet, as long as its parent directory does.
+fn canonical_path(path: &Path) -> PathBuf {
+	fs::canonicalize(path)
+		.ok()
+		.or_else(|| {
+			Some(
+				fs::canonicalize(path.parent()?)
+					.ok()?
+					.join(path.file_name()?)
+			)
+		})
+		.or_else(|| path::absolute(path).ok())
+		.unwrap_or_else(|| path.to_path_buf())
+}
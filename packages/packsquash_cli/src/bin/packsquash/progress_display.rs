@@ -0,0 +1,267 @@
+//! Contains an interactive display of the progress of squash operations, meant to be shown in
+//! terminals instead of a status message for each processed pack file.
+
+use std::{
+	collections::HashMap,
+	io::{self, Write},
+	time::{Duration, Instant}
+};
+
+use packsquash::PackFileStatus;
+
+/// The width of the progress bar, in characters.
+const PROGRESS_BAR_WIDTH: usize = 30;
+/// How many of the pack files that are taking the longest to process are shown at most.
+const SHOWN_SLOWEST_PACK_FILE_COUNT: usize = 3;
+/// How long a pack file must have been processing for to be shown as one of the slowest.
+/// Most pack files are processed much faster, and showing them would be distracting.
+const SLOW_PACK_FILE_PROCESSING_TIME: Duration = Duration::from_secs(1);
+/// The minimum interval of time between two redraws of the progress display, to keep the
+/// terminal responsive when many pack files are processed quickly.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+/// The width assumed for terminals whose width can't be queried.
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+/// A stage of a squash operation.
+#[derive(Clone, Copy)]
+enum SquashStage {
+	/// Pack files are being found and analyzed before processing them.
+	Preparing,
+	/// Pack files are being processed.
+	ProcessingPackFiles,
+	/// The output ZIP file is being finished up.
+	FinishingZipFile
+}
+
+impl SquashStage {
+	/// Returns the user-friendly name of this stage.
+	const fn name(self) -> &'static str {
+		match self {
+			Self::Preparing => "Preparing",
+			Self::ProcessingPackFiles => "Processing pack files",
+			Self::FinishingZipFile => "Finishing ZIP file"
+		}
+	}
+}
+
+/// Shows the progress of a squash operation in the standard error stream, which must be a
+/// terminal, in a few lines that are redrawn as the operation progresses. Those lines show
+/// the processed pack files, the read and written bytes, the estimated time remaining, and
+/// the pack files that are taking the longest to process.
+///
+/// Other messages must not be written to the standard error stream while the progress display
+/// is drawn, so it should be cleared before writing them.
+pub struct ProgressDisplay {
+	stage_start_instants: Vec<(SquashStage, Instant)>,
+	pack_file_count: Option<u64>,
+	processed_pack_file_count: u64,
+	read_byte_count: u64,
+	written_byte_count: u64,
+	in_progress_pack_files: HashMap<String, Instant>,
+	drawn_line_count: usize,
+	last_draw_instant: Instant
+}
+
+impl ProgressDisplay {
+	/// Creates a new progress display for a squash operation that is starting. Nothing is
+	/// shown until [`ProgressDisplay::draw`] is called.
+	pub fn new() -> Self {
+		let now = Instant::now();
+
+		Self {
+			stage_start_instants: vec![(SquashStage::Preparing, now)],
+			pack_file_count: None,
+			processed_pack_file_count: 0,
+			read_byte_count: 0,
+			written_byte_count: 0,
+			in_progress_pack_files: HashMap::new(),
+			drawn_line_count: 0,
+			last_draw_instant: now
+		}
+	}
+
+	/// Records that the specified number of pack files will be processed, which starts the
+	/// pack file processing stage.
+	pub fn pack_files_found(&mut self, pack_file_count: u64) {
+		self.pack_file_count = Some(pack_file_count);
+		self.start_stage(SquashStage::ProcessingPackFiles);
+	}
+
+	/// Records that the pack file at the specified path started being processed.
+	pub fn pack_file_processing_started(&mut self, path: &str) {
+		self.in_progress_pack_files
+			.insert(path.to_string(), Instant::now());
+	}
+
+	/// Records that the pack file with the specified status was processed.
+	pub fn pack_file_processed(&mut self, pack_file_status: &PackFileStatus) {
+		self.processed_pack_file_count += 1;
+		self.read_byte_count += pack_file_status.input_size().unwrap_or(0);
+		self.written_byte_count += pack_file_status.output_size().unwrap_or(0);
+		self.in_progress_pack_files
+			.remove(pack_file_status.path().as_str());
+	}
+
+	/// Records that every pack file was processed, which starts the ZIP file finishing stage.
+	pub fn zip_finish(&mut self) {
+		self.in_progress_pack_files.clear();
+		self.start_stage(SquashStage::FinishingZipFile);
+	}
+
+	/// Returns a user-friendly description of how long each stage of the squash operation
+	/// took so far.
+	pub fn stage_breakdown(&self) -> String {
+		self.stage_start_instants
+			.iter()
+			.zip(
+				self.stage_start_instants
+					.iter()
+					.skip(1)
+					.map(|(_, start_instant)| *start_instant)
+					.chain([Instant::now()])
+			)
+			.map(|((stage, start_instant), end_instant)| {
+				let stage_time = end_instant - *start_instant;
+
+				format!(
+					"{}: {}.{:03} s",
+					stage.name(),
+					stage_time.as_secs(),
+					stage_time.subsec_millis()
+				)
+			})
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+
+	/// Draws the progress display, replacing the previously drawn one, unless it was drawn
+	/// very recently and has not been cleared since.
+	pub fn draw(&mut self) {
+		if self.drawn_line_count > 0 && self.last_draw_instant.elapsed() < REDRAW_INTERVAL {
+			return;
+		}
+
+		let terminal_width = terminal_size::terminal_size_of(io::stderr())
+			.map_or(DEFAULT_TERMINAL_WIDTH, |(terminal_width, _)| {
+				usize::from(terminal_width.0)
+			});
+
+		// Lines as long as the terminal may wrap, which would break clearing them
+		let lines = self
+			.lines()
+			.into_iter()
+			.map(|line| {
+				line.chars()
+					.take(terminal_width.saturating_sub(1))
+					.collect::<String>()
+			})
+			.collect::<Vec<_>>();
+
+		let mut stderr = io::stderr().lock();
+		self.clear_lines(&mut stderr);
+		for line in &lines {
+			writeln!(stderr, "{line}").ok();
+		}
+		stderr.flush().ok();
+
+		self.drawn_line_count = lines.len();
+		self.last_draw_instant = Instant::now();
+	}
+
+	/// Clears the drawn progress display, if any, so that other messages can be written.
+	pub fn clear(&mut self) {
+		let mut stderr = io::stderr().lock();
+		self.clear_lines(&mut stderr);
+		stderr.flush().ok();
+	}
+
+	/// Erases the drawn lines from the specified terminal stream, moving the cursor to the
+	/// start of the first of them.
+	fn clear_lines(&mut self, stream: &mut impl Write) {
+		if self.drawn_line_count > 0 {
+			// Move the cursor to the start of the first drawn line, and erase the display
+			// from there on
+			write!(stream, "\x1b[{}F\x1b[J", self.drawn_line_count).ok();
+			self.drawn_line_count = 0;
+		}
+	}
+
+	/// Returns the lines of text of the progress display.
+	fn lines(&self) -> Vec<String> {
+		let (stage, stage_start_instant) = *self.stage_start_instants.last().unwrap();
+		let stage_time = stage_start_instant.elapsed();
+
+		let mut lines = vec![match (stage, self.pack_file_count) {
+			(SquashStage::ProcessingPackFiles, Some(pack_file_count)) => {
+				let progress = if pack_file_count > 0 {
+					(self.processed_pack_file_count as f64 / pack_file_count as f64).min(1.0)
+				} else {
+					1.0
+				};
+				let filled_width = (progress * PROGRESS_BAR_WIDTH as f64) as usize;
+
+				format!(
+					"{} [{}{}] {:3.0}% {}/{} files",
+					stage.name(),
+					"#".repeat(filled_width),
+					".".repeat(PROGRESS_BAR_WIDTH - filled_width),
+					progress * 100.0,
+					self.processed_pack_file_count,
+					pack_file_count
+				)
+			}
+			_ => format!("{}... {} s", stage.name(), stage_time.as_secs())
+		}];
+
+		if let (SquashStage::ProcessingPackFiles, Some(pack_file_count)) =
+			(stage, self.pack_file_count)
+		{
+			// Estimate the remaining time assuming that the remaining pack files take as long
+			// to process as the processed ones did on average
+			let estimated_remaining_time = (self.processed_pack_file_count > 0).then(|| {
+				stage_time.mul_f64(
+					pack_file_count.saturating_sub(self.processed_pack_file_count) as f64
+						/ self.processed_pack_file_count as f64
+				)
+			});
+
+			lines.push(format!(
+				"{:.1} MiB read, {:.1} MiB written, ETA {}",
+				self.read_byte_count as f64 / (1024.0 * 1024.0),
+				self.written_byte_count as f64 / (1024.0 * 1024.0),
+				estimated_remaining_time.map_or_else(
+					|| "unknown".into(),
+					|remaining_time| format!(
+						"{}:{:02}",
+						remaining_time.as_secs() / 60,
+						remaining_time.as_secs() % 60
+					)
+				)
+			));
+
+			let mut slowest_pack_files = self
+				.in_progress_pack_files
+				.iter()
+				.map(|(path, start_instant)| (path, start_instant.elapsed()))
+				.filter(|(_, processing_time)| *processing_time >= SLOW_PACK_FILE_PROCESSING_TIME)
+				.collect::<Vec<_>>();
+			slowest_pack_files.sort_unstable_by(|(_, a), (_, b)| b.cmp(a));
+
+			lines.extend(
+				slowest_pack_files
+					.into_iter()
+					.take(SHOWN_SLOWEST_PACK_FILE_COUNT)
+					.map(|(path, processing_time)| {
+						format!("  {path}: {} s", processing_time.as_secs())
+					})
+			);
+		}
+
+		lines
+	}
+
+	/// Starts the specified stage of the squash operation, which ends the current one.
+	fn start_stage(&mut self, stage: SquashStage) {
+		self.stage_start_instants.push((stage, Instant::now()));
+	}
+}
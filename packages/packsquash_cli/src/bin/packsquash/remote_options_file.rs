@@ -0,0 +1,81 @@
+//! Contains routines to download options files served over HTTPS, checking that their
+//! contents match the SHA-256 hash pinned in their URL.
+
+use std::fmt::{self, Display, Formatter};
+
+use sha2::{Digest, Sha256};
+
+/// The scheme of the URLs of remote options files.
+const REMOTE_OPTIONS_FILE_URL_SCHEME: &str = "https://";
+/// The prefix of the URL fragment that pins the SHA-256 hash of a remote options file.
+const INTEGRITY_PIN_FRAGMENT_PREFIX: &str = "#sha256=";
+
+/// Represents an error that may happen while downloading a remote options file.
+pub enum RemoteOptionsFileError {
+	/// The URL does not pin the SHA-256 hash of the options file.
+	MissingIntegrityPin,
+	/// The SHA-256 hash pinned in the URL is not a hexadecimal SHA-256 hash.
+	InvalidIntegrityPin,
+	/// The options file could not be downloaded.
+	Download(Box<ureq::Error>),
+	/// The SHA-256 hash of the downloaded options file is not the pinned one.
+	IntegrityMismatch(String),
+	/// The downloaded options file is not valid UTF-8 text.
+	InvalidUtf8
+}
+
+impl Display for RemoteOptionsFileError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::MissingIntegrityPin => write!(
+				f,
+				"The URL must pin the SHA-256 hash of the options file with a \
+				{INTEGRITY_PIN_FRAGMENT_PREFIX}<hash> fragment"
+			),
+			Self::InvalidIntegrityPin => {
+				write!(f, "The pinned hash is not a hexadecimal SHA-256 hash")
+			}
+			Self::Download(err) => write!(f, "{err}"),
+			Self::IntegrityMismatch(actual_hash) => write!(
+				f,
+				"The SHA-256 hash of the downloaded options file, {actual_hash}, does not match \
+				the pinned hash. Its contents may have been tampered with"
+			),
+			Self::InvalidUtf8 => write!(f, "The downloaded options file is not UTF-8 text")
+		}
+	}
+}
+
+/// Returns whether the specified options file location is the URL of a remote options file,
+/// rather than a path.
+pub fn is_remote_options_file(location: &str) -> bool {
+	location.starts_with(REMOTE_OPTIONS_FILE_URL_SCHEME)
+}
+
+/// Downloads the remote options file at the specified HTTPS URL, which must end with a
+/// `#sha256=<hash>` fragment that pins the SHA-256 hash of its contents, and returns its
+/// contents. The options file is rejected if its hash is not the pinned one, so that it
+/// can't be silently tampered with.
+pub fn download_remote_options_file(url: &str) -> Result<String, RemoteOptionsFileError> {
+	let (url, pinned_hash) = url
+		.rsplit_once(INTEGRITY_PIN_FRAGMENT_PREFIX)
+		.ok_or(RemoteOptionsFileError::MissingIntegrityPin)?;
+	if pinned_hash.len() != 64 || !pinned_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+		return Err(RemoteOptionsFileError::InvalidIntegrityPin);
+	}
+
+	let options_file = ureq::get(url)
+		.call()
+		.and_then(|mut response| response.body_mut().read_to_vec())
+		.map_err(|err| RemoteOptionsFileError::Download(Box::new(err)))?;
+
+	let actual_hash = Sha256::digest(&options_file)
+		.iter()
+		.map(|byte| format!("{byte:02x}"))
+		.collect::<String>();
+	if !actual_hash.eq_ignore_ascii_case(pinned_hash) {
+		return Err(RemoteOptionsFileError::IntegrityMismatch(actual_hash));
+	}
+
+	String::from_utf8(options_file).map_err(|_| RemoteOptionsFileError::InvalidUtf8)
+}